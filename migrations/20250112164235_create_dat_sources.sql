@@ -0,0 +1,5 @@
+CREATE TABLE IF NOT EXISTS dat_sources (
+    id INTEGER NOT NULL PRIMARY KEY,
+    url VARCHAR NOT NULL UNIQUE,
+    header VARCHAR
+);
@@ -0,0 +1,7 @@
+CREATE TABLE IF NOT EXISTS romfile_moves (
+    id INTEGER NOT NULL PRIMARY KEY,
+    old_path VARCHAR NOT NULL,
+    new_path VARCHAR NOT NULL,
+    romfile_id INTEGER NOT NULL UNIQUE,
+    FOREIGN KEY (romfile_id) REFERENCES romfiles (id) ON DELETE CASCADE
+);
@@ -0,0 +1,7 @@
+CREATE TABLE IF NOT EXISTS cuesheets (
+    id INTEGER NOT NULL PRIMARY KEY,
+    name VARCHAR NOT NULL,
+    content VARCHAR NOT NULL,
+    game_id INTEGER NOT NULL UNIQUE,
+    FOREIGN KEY (game_id) REFERENCES games (id) ON DELETE CASCADE
+);
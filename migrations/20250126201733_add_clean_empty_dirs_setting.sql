@@ -0,0 +1,2 @@
+INSERT OR REPLACE INTO settings ("key", value)
+VALUES ('CLEAN_EMPTY_DIRS', 'false');
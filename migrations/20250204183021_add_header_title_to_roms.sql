@@ -0,0 +1,2 @@
+ALTER TABLE roms
+ADD COLUMN header_title VARCHAR;
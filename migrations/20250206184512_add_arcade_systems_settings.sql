@@ -0,0 +1,5 @@
+INSERT OR REPLACE INTO settings ("key", value)
+VALUES ('ARCADE_SYSTEMS', NULL);
+
+INSERT OR REPLACE INTO settings ("key", value)
+VALUES ('NON_ARCADE_SYSTEMS', NULL);
@@ -0,0 +1,2 @@
+INSERT OR REPLACE INTO settings ("key", value)
+VALUES ('CHECK_SECTOR_ALIGNMENT', 'true');
@@ -0,0 +1,2 @@
+INSERT OR REPLACE INTO settings ("key", value)
+VALUES ('EXPORT_UPPERCASE_EXTENSIONS', NULL);
@@ -0,0 +1,5 @@
+ALTER TABLE romfiles
+ADD COLUMN updated_at INTEGER NOT NULL DEFAULT 0;
+
+UPDATE romfiles
+SET updated_at = CAST(strftime('%s', 'now') AS INTEGER);
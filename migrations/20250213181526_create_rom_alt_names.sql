@@ -0,0 +1,12 @@
+CREATE TABLE IF NOT EXISTS rom_alt_names (
+    id INTEGER NOT NULL PRIMARY KEY,
+    name VARCHAR NOT NULL,
+    region VARCHAR,
+    rom_id INTEGER NOT NULL,
+    FOREIGN KEY (rom_id) REFERENCES roms (id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS rom_alt_names_rom_id ON rom_alt_names (rom_id);
+
+INSERT OR REPLACE INTO settings ("key", value)
+VALUES ('ALT_NAME_REGIONS', NULL);
@@ -0,0 +1,11 @@
+CREATE TABLE IF NOT EXISTS game_releases (
+    id INTEGER NOT NULL PRIMARY KEY,
+    name VARCHAR NOT NULL,
+    region VARCHAR NOT NULL,
+    language VARCHAR,
+    date VARCHAR,
+    game_id INTEGER NOT NULL,
+    FOREIGN KEY (game_id) REFERENCES games (id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS game_releases_game_id ON game_releases (game_id);
@@ -0,0 +1,5 @@
+CREATE TABLE IF NOT EXISTS unmatched (
+    id INTEGER NOT NULL PRIMARY KEY,
+    path VARCHAR NOT NULL UNIQUE,
+    size INTEGER NOT NULL
+);
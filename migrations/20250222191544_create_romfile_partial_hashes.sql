@@ -0,0 +1,7 @@
+CREATE TABLE IF NOT EXISTS romfile_partial_hashes (
+    romfile_id INTEGER NOT NULL PRIMARY KEY,
+    window_size INTEGER NOT NULL,
+    crc VARCHAR NOT NULL,
+    updated_at INTEGER NOT NULL,
+    FOREIGN KEY (romfile_id) REFERENCES romfiles (id) ON DELETE CASCADE
+);
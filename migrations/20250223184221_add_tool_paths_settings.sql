@@ -0,0 +1,5 @@
+INSERT OR REPLACE INTO settings ("key", value)
+VALUES ('CHDMAN_PATHS', NULL);
+
+INSERT OR REPLACE INTO settings ("key", value)
+VALUES ('MAXCSO_PATHS', NULL);
@@ -0,0 +1,5 @@
+INSERT OR REPLACE INTO settings ("key", value)
+VALUES ('TRASH_MAX_ITEMS', NULL);
+
+INSERT OR REPLACE INTO settings ("key", value)
+VALUES ('TRASH_RETENTION_DAYS', NULL);
@@ -0,0 +1,10 @@
+CREATE TABLE IF NOT EXISTS events (
+    id INTEGER NOT NULL PRIMARY KEY,
+    event_type INTEGER NOT NULL,
+    source VARCHAR,
+    destination VARCHAR NOT NULL,
+    created_at INTEGER NOT NULL,
+    game_id INTEGER NOT NULL,
+    FOREIGN KEY (game_id) REFERENCES games (id) ON DELETE CASCADE
+);
+CREATE INDEX IF NOT EXISTS events_game_id ON events (game_id);
@@ -0,0 +1,2 @@
+INSERT OR REPLACE INTO settings ("key", value)
+VALUES ('CUE_BIN_NAME_TEMPLATE', '{game}');
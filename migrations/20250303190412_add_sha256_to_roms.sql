@@ -0,0 +1,7 @@
+ALTER TABLE roms
+ADD COLUMN sha256 VARCHAR(64);
+
+CREATE INDEX IF NOT EXISTS roms_size_sha256 ON roms (size, sha256);
+
+INSERT OR REPLACE INTO settings ("key", value)
+VALUES ('HASH_ALGORITHMS', 'crc|md5|sha1|sha256');
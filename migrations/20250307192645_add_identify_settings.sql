@@ -0,0 +1,5 @@
+INSERT OR REPLACE INTO settings ("key", value)
+VALUES ('IDENTIFY_INTERVAL', '1000');
+
+INSERT OR REPLACE INTO settings ("key", value)
+VALUES ('IDENTIFY_URL', NULL);
@@ -0,0 +1,2 @@
+ALTER TABLE unmatched
+ADD COLUMN goodtools_status VARCHAR;
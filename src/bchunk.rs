@@ -8,6 +8,7 @@ use lazy_static::lazy_static;
 use regex::Regex;
 use std::path::Path;
 use std::time::Duration;
+use tokio::fs;
 use tokio::process::Command;
 
 const BCHUNK: &str = "bchunk";
@@ -25,6 +26,9 @@ impl ToIso for CueBinRomfile {
         if self.bin_romfiles.len() > 1 {
             bail!("Only single bins are supported");
         }
+        if !is_single_data_track(&self.cue_romfile.path).await? {
+            bail!("Only single data tracks are supported");
+        }
 
         progress_bar.set_message("Creating iso");
         progress_bar.set_style(get_none_progress_style());
@@ -40,7 +44,7 @@ impl ToIso for CueBinRomfile {
             .arg(&self.cue_romfile.path)
             .arg(BCHUNK)
             .current_dir(destination_directory.as_ref())
-            .output()
+            .output_with_retries()
             .await
             .expect("Failed to create iso");
 
@@ -65,9 +69,20 @@ impl ToIso for CueBinRomfile {
     }
 }
 
+pub async fn is_single_data_track<P: AsRef<Path>>(cue_path: &P) -> SimpleResult<bool> {
+    let cue = try_with!(fs::read(cue_path.as_ref()).await, "Failed to read cue");
+    // mixed-mode discs have audio tracks following the data track
+    let tracks: Vec<String> = String::from_utf8_lossy(&cue)
+        .lines()
+        .map(|line| line.trim().to_uppercase())
+        .filter(|line| line.starts_with("TRACK "))
+        .collect();
+    Ok(tracks.len() == 1 && !tracks[0].ends_with("AUDIO"))
+}
+
 pub async fn get_version() -> SimpleResult<String> {
     let output = try_with!(
-        Command::new(BCHUNK).output().await,
+        Command::new(BCHUNK).output_with_retries().await,
         "Failed to spawn bchunk"
     );
 
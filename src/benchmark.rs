@@ -1,28 +1,56 @@
 use super::common::*;
-use super::config::HashAlgorithm;
+use super::config::*;
 use super::database::*;
 use super::progress::*;
 use super::util::*;
 use super::SimpleResult;
-use clap::{Arg, ArgMatches, Command};
+use clap::{value_parser, Arg, ArgAction, ArgMatches, Command};
 use indicatif::ProgressBar;
 use sqlx::sqlite::SqliteConnection;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::time::Duration;
 use std::time::Instant;
+use strum::IntoEnumIterator;
 use tokio::fs;
 use tokio::io::{copy, AsyncReadExt, AsyncWriteExt, BufReader, BufWriter};
 
+const HASHING_MB_COUNT: usize = 256;
+
+pub struct HashingThroughput {
+    pub hash_algorithm: HashAlgorithm,
+    pub buffer_speed: f64,
+    pub file_speed: f64,
+}
+
 pub fn subcommand() -> Command {
-    Command::new("benchmark").about("Benchmark oxyromon").arg(
-        Arg::new("CHUNK_SIZE")
-            .short('c')
-            .long("chunk-size")
-            .help("Set the chunk size in KB for read and writes (Default: 256)")
-            .required(false)
-            .num_args(1)
-            .default_value("256"),
-    )
+    Command::new("benchmark")
+        .about("Benchmark oxyromon")
+        .arg(
+            Arg::new("CHUNK_SIZE")
+                .short('c')
+                .long("chunk-size")
+                .help("Set the chunk size in KB for read and writes (Default: 256)")
+                .required(false)
+                .num_args(1)
+                .default_value("256"),
+        )
+        .arg(
+            Arg::new("HASHING")
+                .long("hashing")
+                .help("Only measure hashing throughput")
+                .required(false)
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("FILE")
+                .short('f')
+                .long("file")
+                .help("Measure hashing throughput on this file instead of a generated one")
+                .required(false)
+                .num_args(1)
+                .requires("HASHING")
+                .value_parser(value_parser!(PathBuf)),
+        )
 }
 
 pub async fn main(
@@ -33,6 +61,17 @@ pub async fn main(
     progress_bar.set_style(get_none_progress_style());
     progress_bar.enable_steady_tick(Duration::from_millis(100));
 
+    if matches.get_flag("HASHING") {
+        benchmark_hashing(
+            connection,
+            progress_bar,
+            HASHING_MB_COUNT,
+            matches.get_one::<PathBuf>("FILE"),
+        )
+        .await?;
+        return Ok(());
+    }
+
     let rom_directory = find_setting_by_key(connection, "ROM_DIRECTORY")
         .await
         .unwrap()
@@ -187,3 +226,79 @@ pub async fn main(
 
     Ok(())
 }
+
+pub async fn benchmark_hashing(
+    connection: &mut SqliteConnection,
+    progress_bar: &ProgressBar,
+    mb_count: usize,
+    file_path: Option<&PathBuf>,
+) -> SimpleResult<Vec<HashingThroughput>> {
+    let tmp_directory = get_tmp_directory(connection).await;
+
+    // pseudo random data so that no algorithm benefits from trivial input
+    progress_bar.set_message("Generating buffer");
+    let mut buffer: Vec<u8> = Vec::with_capacity(mb_count * 1024 * 1024);
+    let mut state: u64 = 0x9e37_79b9_7f4a_7c15;
+    while buffer.len() < mb_count * 1024 * 1024 {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        buffer.extend_from_slice(&state.to_le_bytes());
+    }
+
+    // without a real file, hash the generated buffer once written to disk
+    let generated_file_path = tmp_directory.join(".oxyromon");
+    let (romfile, file_mb_count) = match file_path {
+        Some(file_path) => {
+            let romfile = CommonRomfile::from_path(file_path)?;
+            let size = romfile.get_size(connection, progress_bar).await?;
+            (romfile, size as f64 / 1024.0 / 1024.0)
+        }
+        None => {
+            try_with!(
+                fs::write(&generated_file_path, &buffer).await,
+                "Failed to write generated file"
+            );
+            (
+                CommonRomfile::from_path(&generated_file_path)?,
+                mb_count as f64,
+            )
+        }
+    };
+
+    let mut throughputs: Vec<HashingThroughput> = vec![];
+    for hash_algorithm in HashAlgorithm::iter() {
+        progress_bar.set_message(format!("Measuring {} buffer throughput", hash_algorithm));
+        let start = Instant::now();
+        compute_hash(&mut buffer.as_slice(), progress_bar, &hash_algorithm)?;
+        let buffer_speed = mb_count as f64 / start.elapsed().as_secs_f64();
+
+        let start = Instant::now();
+        romfile
+            .get_hash_and_size(connection, progress_bar, 1, 1, &hash_algorithm)
+            .await?;
+        let file_speed = file_mb_count / start.elapsed().as_secs_f64();
+
+        progress_bar.println(format!(
+            "{} Speed: {:.2}Mb/s (buffer), {:.2}Mb/s (file)",
+            hash_algorithm.to_string().to_uppercase(),
+            buffer_speed,
+            file_speed
+        ));
+
+        throughputs.push(HashingThroughput {
+            hash_algorithm,
+            buffer_speed,
+            file_speed,
+        });
+    }
+
+    if file_path.is_none() {
+        remove_file(progress_bar, &generated_file_path, true).await?;
+    }
+
+    Ok(throughputs)
+}
+
+#[cfg(test)]
+mod test_hashing;
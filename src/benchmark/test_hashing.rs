@@ -0,0 +1,32 @@
+use super::super::database::*;
+use super::*;
+use tempfile::{NamedTempFile, TempDir};
+
+#[tokio::test]
+async fn test() {
+    // given
+    let _guard = MUTEX.lock().await;
+
+    let test_directory = Path::new("tests");
+    let progress_bar = ProgressBar::hidden();
+
+    let db_file = NamedTempFile::new().unwrap();
+    let pool = establish_connection(db_file.path().to_str().unwrap()).await;
+    let mut connection = pool.acquire().await.unwrap();
+
+    let tmp_directory = TempDir::new_in(&test_directory).unwrap();
+    let tmp_directory = set_tmp_directory(PathBuf::from(tmp_directory.path()));
+
+    // when
+    let throughputs = benchmark_hashing(&mut connection, &progress_bar, 4, None)
+        .await
+        .unwrap();
+
+    // then
+    assert_eq!(throughputs.len(), HashAlgorithm::iter().count());
+    for throughput in throughputs {
+        assert!(throughput.buffer_speed > 0.0);
+        assert!(throughput.file_speed > 0.0);
+    }
+    assert!(!tmp_directory.join(".oxyromon").exists());
+}
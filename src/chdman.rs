@@ -8,9 +8,12 @@ use super::SimpleResult;
 use indicatif::ProgressBar;
 use regex::Regex;
 use sqlx::SqliteConnection;
+use std::io::SeekFrom;
 use std::path::{Path, PathBuf};
 use std::time::Duration;
 use strum::{Display, EnumString, VariantNames};
+use tokio::fs;
+use tokio::io::{AsyncReadExt, AsyncSeekExt};
 use tokio::process::Command;
 
 const CHDMAN: &str = "chdman";
@@ -242,6 +245,15 @@ impl Check for ChdRomfile {
         roms: &[&Rom],
     ) -> SimpleResult<()> {
         progress_bar.println(format!("Checking \"{}\"", self.romfile));
+        // MAME disks are identified by the CHD SHA1 rather than their decompressed content
+        if let [rom] = roms {
+            if rom.disk && rom.size == 0 {
+                if rom.sha1.as_deref() != Some(self.chd_sha1.as_str()) {
+                    bail!("SHA1 mismatch");
+                }
+                return verify_chd(progress_bar, &self.romfile, self.parent_romfile.as_ref()).await;
+            }
+        }
         let tmp_directory = create_tmp_directory(connection).await?;
         match self.chd_type {
             ChdType::Cd => {
@@ -256,6 +268,12 @@ impl Check for ChdRomfile {
             }
             ChdType::Dvd => {
                 let iso_romfile = self.to_iso(progress_bar, &tmp_directory.path()).await?;
+                if let [rom] = roms {
+                    if rom.size > 0 {
+                        trim_zero_padding(progress_bar, &iso_romfile.romfile.path, rom.size as u64)
+                            .await?;
+                    }
+                }
                 iso_romfile
                     .romfile
                     .check(connection, progress_bar, header, roms)
@@ -477,7 +495,13 @@ impl ToCueBin for ChdRomfile {
                     .await?;
                 cue_romfile.as_cue_bin(bin_romfiles)
             }
-            None => CommonRomfile::from_path(&cue_path.unwrap())?.as_cue_bin(bin_romfiles),
+            None => {
+                let cue_path = cue_path.unwrap();
+                if split {
+                    rewrite_cue_files(&cue_path, &bin_romfiles).await?;
+                }
+                CommonRomfile::from_path(&cue_path)?.as_cue_bin(bin_romfiles)
+            }
         }
     }
 }
@@ -550,11 +574,11 @@ pub trait AsChd {
 
 impl AsChd for CommonRomfile {
     async fn parse_chd(&self) -> SimpleResult<(ChdType, u64, String, String, usize)> {
-        let output = Command::new(CHDMAN)
+        let output = Command::new(get_tool_path(CHDMAN))
             .arg("info")
             .arg("-i")
             .arg(&self.path)
-            .output()
+            .output_with_retries()
             .await
             .expect("Failed to parse chd");
 
@@ -692,7 +716,7 @@ async fn create_chd<P: AsRef<Path>, Q: AsRef<Path>>(
         ));
     }
 
-    let mut command = Command::new(CHDMAN);
+    let mut command = Command::new(get_tool_path(CHDMAN));
     command
         .arg(match chd_type {
             ChdType::Cd => "createcd",
@@ -718,7 +742,10 @@ async fn create_chd<P: AsRef<Path>, Q: AsRef<Path>>(
 
     log::debug!("{:?}", command);
 
-    let output = command.output().await.expect("Failed to create chd");
+    let output = command
+        .output_with_retries()
+        .await
+        .expect("Failed to create chd");
 
     if !output.status.success() {
         bail!(String::from_utf8(output.stderr).unwrap().as_str())
@@ -764,7 +791,7 @@ async fn extract_chd<P: AsRef<Path>, Q: AsRef<Path>>(
         ));
     }
 
-    let mut command = Command::new(CHDMAN);
+    let mut command = Command::new(get_tool_path(CHDMAN));
     command
         .arg(match chd_type {
             ChdType::Cd => "extractcd",
@@ -805,7 +832,10 @@ async fn extract_chd<P: AsRef<Path>, Q: AsRef<Path>>(
 
     log::debug!("{:?}", command);
 
-    let output = command.output().await.expect("Failed to extract chd");
+    let output = command
+        .output_with_retries()
+        .await
+        .expect("Failed to extract chd");
 
     if !output.status.success() {
         bail!(String::from_utf8(output.stderr).unwrap().as_str());
@@ -817,9 +847,195 @@ async fn extract_chd<P: AsRef<Path>, Q: AsRef<Path>>(
     Ok((bin_path, cue_path))
 }
 
+// split bins may have been renamed, only FILE entries are rewritten to keep track modes and sector sizes intact
+async fn rewrite_cue_files<P: AsRef<Path>>(
+    cue_path: &P,
+    bin_romfiles: &[CommonRomfile],
+) -> SimpleResult<()> {
+    let cue = try_with!(
+        fs::read_to_string(cue_path.as_ref()).await,
+        "Failed to read cue"
+    );
+    let line_ending = if cue.contains("\r\n") { "\r\n" } else { "\n" };
+    let mut bin_names = bin_romfiles
+        .iter()
+        .map(|bin_romfile| bin_romfile.path.file_name().unwrap().to_str().unwrap());
+    let mut lines: Vec<String> = vec![];
+    for line in cue.lines() {
+        let trimmed_line = line.trim_start();
+        if trimmed_line.starts_with("FILE ") {
+            if let Some(bin_name) = bin_names.next() {
+                let indentation = &line[..line.len() - trimmed_line.len()];
+                let file_type = trimmed_line.rsplit_once(' ').unwrap().1;
+                lines.push(format!(
+                    "{}FILE \"{}\" {}",
+                    indentation, bin_name, file_type
+                ));
+                continue;
+            }
+        }
+        lines.push(line.to_owned());
+    }
+    try_with!(
+        fs::write(
+            cue_path.as_ref(),
+            format!("{}{}", lines.join(line_ending), line_ending)
+        )
+        .await,
+        "Failed to write cue"
+    );
+    Ok(())
+}
+
+// trailing zero padding doesn't always survive a round trip through chdman, so anything past the
+// expected size can be dropped as long as it only contains zeros
+async fn trim_zero_padding<P: AsRef<Path>>(
+    progress_bar: &ProgressBar,
+    path: &P,
+    size: u64,
+) -> SimpleResult<()> {
+    let mut file = try_with!(
+        fs::OpenOptions::new()
+            .read(true)
+            .write(true)
+            .open(path.as_ref())
+            .await,
+        "Failed to open ISO"
+    );
+    let file_size = try_with!(file.metadata().await, "Failed to get metadata").len();
+    if file_size <= size {
+        return Ok(());
+    }
+    try_with!(file.seek(SeekFrom::Start(size)).await, "Failed to seek ISO");
+    let mut buffer = vec![0u8; 65536];
+    loop {
+        let count = try_with!(file.read(&mut buffer).await, "Failed to read ISO");
+        if count == 0 {
+            break;
+        }
+        if buffer[..count].iter().any(|&byte| byte != 0) {
+            return Ok(());
+        }
+    }
+    progress_bar.println(format!(
+        "Ignoring {} bytes of trailing zero padding",
+        file_size - size
+    ));
+    try_with!(file.set_len(size).await, "Failed to truncate ISO");
+    Ok(())
+}
+
+pub fn get_cue_bin_name(template: &str, system: &System, game: &Game) -> String {
+    template
+        .replace("{system}", &system.name)
+        .replace("{game}", &game.name)
+}
+
+// names generated cues after the template, bins keep their DAT names when they have one
+pub async fn rename_cue_bin<P: AsRef<Path>>(
+    progress_bar: &ProgressBar,
+    cue_bin_romfile: CueBinRomfile,
+    destination_directory: &P,
+    bin_roms: &[&Rom],
+    name: &str,
+    quiet: bool,
+) -> SimpleResult<CueBinRomfile> {
+    let track_count = cue_bin_romfile.bin_romfiles.len();
+    let mut bin_romfiles: Vec<CommonRomfile> = vec![];
+    for (i, bin_romfile) in cue_bin_romfile.bin_romfiles.into_iter().enumerate() {
+        let bin_name = match bin_roms.get(i) {
+            Some(bin_rom) => bin_rom.name.to_owned(),
+            None if track_count > 1 => format!(
+                "{} (Track {:0width$}).{}",
+                name,
+                i + 1,
+                BIN_EXTENSION,
+                width = if track_count > 9 { 2 } else { 1 }
+            ),
+            None => format!("{}.{}", name, BIN_EXTENSION),
+        };
+        bin_romfiles.push(
+            bin_romfile
+                .rename(
+                    progress_bar,
+                    &destination_directory.as_ref().join(bin_name),
+                    quiet,
+                )
+                .await?,
+        );
+    }
+    rewrite_cue_files(&cue_bin_romfile.cue_romfile.path, &bin_romfiles).await?;
+    cue_bin_romfile
+        .cue_romfile
+        .rename(
+            progress_bar,
+            &destination_directory
+                .as_ref()
+                .join(format!("{}.{}", name, CUE_EXTENSION)),
+            quiet,
+        )
+        .await?
+        .as_cue_bin(bin_romfiles)
+}
+
+pub async fn get_parent_sha1(romfile: &CommonRomfile) -> SimpleResult<Option<String>> {
+    let output = Command::new(get_tool_path(CHDMAN))
+        .arg("info")
+        .arg("-i")
+        .arg(&romfile.path)
+        .output_with_retries()
+        .await
+        .expect("Failed to parse chd");
+
+    if !output.status.success() {
+        bail!(String::from_utf8(output.stderr).unwrap().as_str());
+    }
+
+    let stdout = String::from_utf8(output.stdout).unwrap();
+
+    Ok(stdout
+        .lines()
+        .find(|&line| line.starts_with("Parent SHA1:"))
+        .map(|line| line.split(":").last().unwrap().trim().to_string()))
+}
+
+pub async fn verify_chd(
+    progress_bar: &ProgressBar,
+    romfile: &CommonRomfile,
+    parent_romfile: Option<&CommonRomfile>,
+) -> SimpleResult<()> {
+    progress_bar.set_message("Verifying chd");
+    progress_bar.set_style(get_none_progress_style());
+    progress_bar.enable_steady_tick(Duration::from_millis(100));
+
+    let mut command = Command::new(get_tool_path(CHDMAN));
+    command.arg("verify").arg("-i").arg(&romfile.path);
+    if let Some(parent_romfile) = parent_romfile {
+        command.arg("-ip").arg(&parent_romfile.path);
+    }
+
+    log::debug!("{:?}", command);
+
+    let output = command
+        .output_with_retries()
+        .await
+        .expect("Failed to verify chd");
+
+    progress_bar.set_message("");
+    progress_bar.disable_steady_tick();
+
+    if !output.status.success() {
+        bail!(String::from_utf8(output.stderr).unwrap().as_str());
+    }
+
+    Ok(())
+}
+
 pub async fn get_version() -> SimpleResult<String> {
     let output = try_with!(
-        Command::new(CHDMAN).output().await,
+        Command::new(get_tool_path(CHDMAN))
+            .output_with_retries()
+            .await,
         "Failed to spawn chdman"
     );
 
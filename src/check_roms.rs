@@ -2,10 +2,11 @@ use super::chdman;
 use super::chdman::AsChd;
 use super::common::*;
 use super::config::*;
+use super::convert_roms::ALL_FORMATS;
 use super::database::*;
 use super::dolphin;
 use super::dolphin::AsRvz;
-use super::maxcso;
+use super::import_dats::parse_dat;
 use super::maxcso::AsXso;
 use super::mimetype::*;
 use super::model::*;
@@ -15,11 +16,12 @@ use super::prompt::*;
 use super::sevenzip;
 use super::sevenzip::AsArchive;
 use super::util::*;
-use clap::{Arg, ArgAction, ArgMatches, Command};
+use clap::{value_parser, Arg, ArgAction, ArgMatches, Command};
 use indicatif::ProgressBar;
 use simple_error::SimpleResult;
 use sqlx::sqlite::SqliteConnection;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
 
 pub fn subcommand() -> Command {
     Command::new("check-roms")
@@ -48,6 +50,65 @@ pub fn subcommand() -> Command {
                 .required(false)
                 .action(ArgAction::SetTrue),
         )
+        .arg(
+            Arg::new("PARTIAL")
+                .long("partial")
+                .help("Also check partially complete games")
+                .required(false)
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("INDEX")
+                .short('i')
+                .long("index")
+                .help("Match original files against an in-memory index of the expected hashes")
+                .required(false)
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("HASH_WINDOW")
+                .long("hash-window")
+                .help("Only hash the first and last N MiB of files already fully checked")
+                .required(false)
+                .num_args(1)
+                .value_parser(value_parser!(u64).range(1..)),
+        )
+        .arg(
+            Arg::new("TEST_ARCHIVES")
+                .long("test-archives")
+                .help("Test archive containers before hashing their contents")
+                .required(false)
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("CHD_PARENTS")
+                .long("chd-parents")
+                .help("Only check CHD parent chains")
+                .required(false)
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("FIX")
+                .long("fix")
+                .help("Relink CHDs to unlinked parents, trash other broken chains")
+                .required(false)
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("DAT")
+                .long("dat")
+                .help("Only report completeness against additional DATs")
+                .required(false)
+                .action(ArgAction::Append)
+                .value_parser(value_parser!(PathBuf)),
+        )
+        .arg(
+            Arg::new("ENFORCE_FORMAT")
+                .long("enforce-format")
+                .help("Only flag ROM files not in their system's configured format")
+                .required(false)
+                .action(ArgAction::SetTrue),
+        )
 }
 
 pub async fn main(
@@ -55,52 +116,119 @@ pub async fn main(
     matches: &ArgMatches,
     progress_bar: &ProgressBar,
 ) -> SimpleResult<()> {
-    let systems = prompt_for_systems(connection, None, false, matches.get_flag("ALL")).await?;
+    let mut systems = prompt_for_systems(connection, None, false, matches.get_flag("ALL")).await?;
+    for system in &mut systems {
+        system.arcade = is_arcade(connection, system).await;
+    }
+    if matches.get_flag("CHD_PARENTS") {
+        if chdman::get_version().await.is_err() {
+            print_missing_tool(progress_bar, "chdman");
+            return Ok(());
+        }
+        for system in systems {
+            progress_bar.println(format!("Processing \"{}\"", system.name));
+            check_chd_parents(connection, progress_bar, &system, matches.get_flag("FIX")).await?;
+            progress_bar.println("");
+        }
+        return Ok(());
+    }
+    if let Some(dat_paths) = matches.get_many::<PathBuf>("DAT") {
+        let dat_paths: Vec<&PathBuf> = dat_paths.collect();
+        for system in systems {
+            progress_bar.println(format!("Processing \"{}\"", system.name));
+            check_dats(connection, progress_bar, &system, &dat_paths).await?;
+            progress_bar.println("");
+        }
+        return Ok(());
+    }
+    if matches.get_flag("ENFORCE_FORMAT") {
+        for system in systems {
+            progress_bar.println(format!("Processing \"{}\"", system.name));
+            check_format(connection, progress_bar, &system).await?;
+            progress_bar.println("");
+        }
+        return Ok(());
+    }
+    let mut found = false;
     for system in systems {
         progress_bar.println(format!("Processing \"{}\"", system.name));
-        let games = match matches.get_many::<String>("GAME") {
-            Some(game_names) => {
-                let mut games: Vec<Game> = vec![];
-                for game_name in game_names {
-                    games.append(
-                        &mut find_complete_games_by_name_and_system_id(
-                            connection, game_name, system.id,
-                        )
-                        .await,
-                    );
-                }
-                games.dedup_by_key(|game| game.id);
-                prompt_for_games(games, cfg!(test))?
-            }
-            None => find_complete_games_by_system_id(connection, system.id).await,
-        };
-
+        let games = find_games(connection, matches, &system).await?;
         if games.is_empty() {
             if matches.index_of("GAME").is_some() {
                 progress_bar.println("No matching game");
             }
             continue;
         }
-        check_system(
-            connection,
-            progress_bar,
-            &system,
-            games,
-            matches.get_flag("SIZE"),
+        found = true;
+        let tool_paths = get_system_tool_paths(connection, &system.name).await;
+        let (_, report) = with_tool_paths(
+            tool_paths,
+            check_system(
+                connection,
+                progress_bar,
+                &system,
+                games,
+                matches.get_flag("SIZE"),
+                matches.get_flag("INDEX"),
+                matches.get_one::<u64>("HASH_WINDOW").copied(),
+                matches.get_flag("TEST_ARCHIVES"),
+            ),
         )
         .await?;
+        for line in report {
+            progress_bar.println(line);
+        }
         progress_bar.println("");
     }
+    if !found && matches.index_of("GAME").is_some() {
+        set_outcome(Outcome::NothingMatched);
+    }
     Ok(())
 }
 
+async fn find_games(
+    connection: &mut SqliteConnection,
+    matches: &ArgMatches,
+    system: &System,
+) -> SimpleResult<Vec<Game>> {
+    let games = match matches.get_many::<String>("GAME") {
+        Some(game_names) => {
+            let mut games: Vec<Game> = vec![];
+            for game_name in game_names {
+                games.append(&mut if matches.get_flag("PARTIAL") {
+                    find_partial_or_complete_games_by_name_and_system_id(
+                        connection, game_name, system.id,
+                    )
+                    .await
+                } else {
+                    find_complete_games_by_name_and_system_id(connection, game_name, system.id)
+                        .await
+                });
+            }
+            games.dedup_by_key(|game| game.id);
+            prompt_for_games(games, cfg!(test))?
+        }
+        None => {
+            if matches.get_flag("PARTIAL") {
+                find_partial_or_complete_games_by_system_id(connection, system.id).await
+            } else {
+                find_complete_games_by_system_id(connection, system.id).await
+            }
+        }
+    };
+    Ok(games)
+}
+
 async fn check_system(
     connection: &mut SqliteConnection,
     progress_bar: &ProgressBar,
     system: &System,
     games: Vec<Game>,
     size: bool,
-) -> SimpleResult<()> {
+    index: bool,
+    hash_window: Option<u64>,
+    test_archives: bool,
+) -> SimpleResult<(Vec<i64>, Vec<String>)> {
     let roms = find_roms_with_romfile_by_game_ids(
         connection,
         &games.iter().map(|game| game.id).collect::<Vec<i64>>(),
@@ -122,10 +250,20 @@ async fn check_system(
         group.push(rom);
     });
     let header = find_header_by_system_id(connection, system.id).await;
+    let check_sector_alignment = get_bool(connection, "CHECK_SECTOR_ALIGNMENT").await;
+    let mut roms_by_size_and_crc: HashMap<(i64, &str), Vec<&Rom>> = HashMap::new();
+    if index {
+        roms.iter().filter(|rom| rom.crc.is_some()).for_each(|rom| {
+            let group = roms_by_size_and_crc
+                .entry((rom.size, rom.crc.as_deref().unwrap()))
+                .or_default();
+            group.push(rom);
+        });
+    }
 
     let mut transaction = begin_transaction(connection).await;
 
-    let mut errors = 0;
+    let mut bad_romfile_ids: Vec<i64> = vec![];
 
     for romfile in &romfiles {
         let romfile_path = romfile.as_common(&mut transaction).await?.path;
@@ -137,103 +275,110 @@ async fn check_system(
             romfile_path.file_name().unwrap().to_str().unwrap()
         ));
 
-        let result;
-        if ARCHIVE_EXTENSIONS.contains(&romfile_extension) {
-            if sevenzip::get_version().await.is_err() {
-                progress_bar.println("Please install sevenzip");
-                break;
+        if check_sector_alignment {
+            if let Some(warning) = get_sector_alignment_warning(&romfile_path) {
+                progress_bar.println(warning);
             }
-            result = check_archive(
+        }
+
+        if ARCHIVE_EXTENSIONS.contains(&romfile_extension) && sevenzip::get_version().await.is_err()
+        {
+            print_missing_tool(progress_bar, "sevenzip");
+            break;
+        }
+        if CHD_EXTENSION == romfile_extension && chdman::get_version().await.is_err() {
+            print_missing_tool(progress_bar, "chdman");
+            break;
+        }
+        if NSZ_EXTENSION == romfile_extension && nsz::get_version().await.is_err() {
+            print_missing_tool(progress_bar, "nsz");
+            break;
+        }
+        if RVZ_EXTENSION == romfile_extension && dolphin::get_version().await.is_err() {
+            print_missing_tool(progress_bar, "dolphin-tool");
+            break;
+        }
+
+        // a partial hash can only vouch for files fully checked since their last change
+        let partial_hash = match hash_window {
+            Some(hash_window) => {
+                find_romfile_partial_hash_by_romfile_id(&mut transaction, romfile.id)
+                    .await
+                    .filter(|partial_hash| {
+                        partial_hash.window_size == hash_window as i64
+                            && partial_hash.updated_at == romfile.updated_at
+                    })
+            }
+            None => None,
+        };
+
+        // a damaged container can still yield members that hash fine
+        let container_result = if test_archives && ARCHIVE_EXTENSIONS.contains(&romfile_extension) {
+            sevenzip::test_archive(progress_bar, &romfile_path).await
+        } else {
+            Ok(())
+        };
+
+        let result = if container_result.is_err() {
+            container_result
+        } else if let Some(partial_hash) = &partial_hash {
+            check_with_partial_hash(progress_bar, &romfile_path, romfile, partial_hash)
+        } else if index
+            && header.is_none()
+            && !ARCHIVE_EXTENSIONS.contains(&romfile_extension)
+            && ![
+                CHD_EXTENSION,
+                CSO_EXTENSION,
+                NSZ_EXTENSION,
+                RVZ_EXTENSION,
+                ZSO_EXTENSION,
+            ]
+            .contains(&romfile_extension)
+            && romfile_roms
+                .iter()
+                .all(|rom| rom.crc.is_some() && rom.size > 0)
+        {
+            check_with_index(
+                &mut transaction,
+                progress_bar,
+                &CommonRomfile::from_path(&romfile_path)?,
+                romfile,
+                &roms_by_size_and_crc,
+            )
+            .await
+        } else {
+            check_romfile(
                 &mut transaction,
                 progress_bar,
                 &header,
                 romfile,
                 romfile_roms,
             )
-            .await;
-        } else if CHD_EXTENSION == romfile_extension {
-            if chdman::get_version().await.is_err() {
-                progress_bar.println("Please install chdman");
-                break;
-            }
-            let chd_romfile = match romfile.parent_id {
-                Some(parent_id) => {
-                    let parent_chd_romfile = find_romfile_by_id(&mut transaction, parent_id).await;
-                    romfile
-                        .as_common(&mut transaction)
-                        .await?
-                        .as_chd_with_parent(
-                            parent_chd_romfile
-                                .as_common(&mut transaction)
-                                .await?
-                                .as_chd()
-                                .await?,
-                        )
-                        .await?
-                }
-                None => romfile.as_common(&mut transaction).await?.as_chd().await?,
-            };
-            result = chd_romfile
-                .check(&mut transaction, progress_bar, &header, &romfile_roms)
-                .await;
-        } else if CSO_EXTENSION == romfile_extension {
-            if maxcso::get_version().await.is_err() {
-                progress_bar.println("Please install maxcso");
-                break;
-            }
-            result = romfile
-                .as_common(&mut transaction)
-                .await?
-                .as_xso()
-                .await?
-                .check(&mut transaction, progress_bar, &header, &romfile_roms)
-                .await;
-        } else if NSZ_EXTENSION == romfile_extension {
-            if nsz::get_version().await.is_err() {
-                progress_bar.println("Please install nsz");
-                break;
-            }
-            result = romfile
-                .as_common(&mut transaction)
-                .await?
-                .as_nsz()?
-                .check(&mut transaction, progress_bar, &header, &romfile_roms)
-                .await;
-        } else if RVZ_EXTENSION == romfile_extension {
-            if dolphin::get_version().await.is_err() {
-                progress_bar.println("Please install dolphin-tool");
-                break;
-            }
-            result = romfile
-                .as_common(&mut transaction)
-                .await?
-                .as_rvz()?
-                .check(&mut transaction, progress_bar, &header, &romfile_roms)
-                .await;
-        } else if ZSO_EXTENSION == romfile_extension {
-            if maxcso::get_version().await.is_err() {
-                progress_bar.println("Please install maxcso");
-                break;
-            }
-            result = romfile
-                .as_common(&mut transaction)
-                .await?
-                .as_xso()
-                .await?
-                .check(&mut transaction, progress_bar, &header, &romfile_roms)
-                .await;
-        } else {
-            result = romfile
-                .as_common(&mut transaction)
-                .await?
-                .check(&mut transaction, progress_bar, &header, &romfile_roms)
-                .await;
-        }
+            .await
+        };
 
         if result.is_err() {
-            errors += 1;
+            bad_romfile_ids.push(romfile.id);
             move_to_trash(&mut transaction, progress_bar, system, romfile).await?;
-        } else if size {
+            continue;
+        }
+        if let Some(hash_window) = hash_window {
+            // sizes recorded by older versions can't back a partial check
+            if partial_hash.is_none()
+                && romfile_path.metadata().map_or(0, |metadata| metadata.len()) as i64
+                    == romfile.size
+            {
+                create_or_update_romfile_partial_hash(
+                    &mut transaction,
+                    romfile.id,
+                    hash_window as i64,
+                    &compute_partial_hash(&romfile_path, hash_window * 1024 * 1024)?,
+                    romfile.updated_at,
+                )
+                .await;
+            }
+        }
+        if size {
             romfile
                 .as_common(&mut transaction)
                 .await?
@@ -242,16 +387,169 @@ async fn check_system(
         }
     }
 
+    let roms_without_romfile = find_roms_without_romfile_by_game_ids(
+        &mut transaction,
+        &games.iter().map(|game| game.id).collect::<Vec<i64>>(),
+    )
+    .await;
+    let mut report: Vec<String> = vec![];
+    for game in &games {
+        let (present_roms, missing_roms, bad_roms) =
+            partition_roms_by_status(game.id, &roms, &roms_without_romfile, &bad_romfile_ids);
+        if missing_roms.is_empty() && bad_roms.is_empty() {
+            continue;
+        }
+        report.push(format!(
+            "\"{}\": {}/{} ROM(s) present",
+            game.name,
+            present_roms.len(),
+            present_roms.len() + missing_roms.len() + bad_roms.len()
+        ));
+        for rom in missing_roms {
+            report.push(format!("Missing \"{}\"", rom.name));
+        }
+        for rom in bad_roms {
+            report.push(format!("Bad \"{}\"", rom.name));
+        }
+    }
+
     // update games and systems completion
-    if errors > 0 {
+    if !bad_romfile_ids.is_empty() {
         compute_system_completion(&mut transaction, progress_bar, system).await;
+        set_outcome(Outcome::VerificationFailures);
     }
 
     commit_transaction(transaction).await;
 
+    Ok((bad_romfile_ids, report))
+}
+
+pub async fn check_romfile(
+    connection: &mut SqliteConnection,
+    progress_bar: &ProgressBar,
+    header: &Option<Header>,
+    romfile: &Romfile,
+    roms: Vec<&Rom>,
+) -> SimpleResult<()> {
+    let romfile_path = romfile.as_common(connection).await?.path;
+    let romfile_extension = romfile_path.extension().unwrap().to_str().unwrap();
+    if ARCHIVE_EXTENSIONS.contains(&romfile_extension) {
+        check_archive(connection, progress_bar, header, romfile, roms).await
+    } else if CHD_EXTENSION == romfile_extension {
+        let chd_romfile = match romfile.parent_id {
+            Some(parent_id) => {
+                let parent_chd_romfile = find_romfile_by_id(connection, parent_id).await;
+                romfile
+                    .as_common(connection)
+                    .await?
+                    .as_chd_with_parent(
+                        parent_chd_romfile
+                            .as_common(connection)
+                            .await?
+                            .as_chd()
+                            .await?,
+                    )
+                    .await?
+            }
+            None => romfile.as_common(connection).await?.as_chd().await?,
+        };
+        chd_romfile
+            .check(connection, progress_bar, header, &roms)
+            .await
+    } else if CSO_EXTENSION == romfile_extension || ZSO_EXTENSION == romfile_extension {
+        romfile
+            .as_common(connection)
+            .await?
+            .as_xso()
+            .await?
+            .check(connection, progress_bar, header, &roms)
+            .await
+    } else if NSZ_EXTENSION == romfile_extension {
+        romfile
+            .as_common(connection)
+            .await?
+            .as_nsz()?
+            .check(connection, progress_bar, header, &roms)
+            .await
+    } else if RVZ_EXTENSION == romfile_extension {
+        romfile
+            .as_common(connection)
+            .await?
+            .as_rvz()?
+            .check(connection, progress_bar, header, &roms)
+            .await
+    } else {
+        romfile
+            .as_common(connection)
+            .await?
+            .check(connection, progress_bar, header, &roms)
+            .await
+    }
+}
+
+fn partition_roms_by_status<'a>(
+    game_id: i64,
+    roms: &'a [Rom],
+    missing_roms: &'a [Rom],
+    bad_romfile_ids: &[i64],
+) -> (Vec<&'a Rom>, Vec<&'a Rom>, Vec<&'a Rom>) {
+    let (bad_roms, present_roms): (Vec<&Rom>, Vec<&Rom>) = roms
+        .iter()
+        .filter(|rom| rom.game_id == game_id)
+        .partition(|rom| bad_romfile_ids.contains(&rom.romfile_id.unwrap()));
+    let missing_roms: Vec<&Rom> = missing_roms
+        .iter()
+        .filter(|rom| rom.game_id == game_id)
+        .collect();
+    (present_roms, missing_roms, bad_roms)
+}
+
+fn check_with_partial_hash(
+    progress_bar: &ProgressBar,
+    romfile_path: &PathBuf,
+    romfile: &Romfile,
+    partial_hash: &RomfilePartialHash,
+) -> SimpleResult<()> {
+    progress_bar.println(format!(
+        "Checking the first and last {} MiB of \"{}\"",
+        partial_hash.window_size,
+        romfile_path.file_name().unwrap().to_str().unwrap()
+    ));
+    let size = try_with!(
+        romfile_path.metadata(),
+        "Failed to read \"{}\" metadata",
+        romfile_path.as_os_str().to_str().unwrap()
+    )
+    .len();
+    if size as i64 != romfile.size {
+        bail!("Size mismatch");
+    }
+    // changes between both windows go unnoticed
+    if compute_partial_hash(romfile_path, partial_hash.window_size as u64 * 1024 * 1024)?
+        != partial_hash.crc
+    {
+        bail!("Checksum mismatch");
+    }
     Ok(())
 }
 
+async fn check_with_index(
+    connection: &mut SqliteConnection,
+    progress_bar: &ProgressBar,
+    common_romfile: &CommonRomfile,
+    romfile: &Romfile,
+    roms_by_size_and_crc: &HashMap<(i64, &str), Vec<&Rom>>,
+) -> SimpleResult<()> {
+    progress_bar.println(format!("Checking \"{}\"", common_romfile));
+    let (crc, size) = common_romfile
+        .get_hash_and_size(connection, progress_bar, 1, 1, &HashAlgorithm::Crc)
+        .await?;
+    match roms_by_size_and_crc.get(&(size as i64, crc.as_str())) {
+        Some(roms) if roms.iter().any(|rom| rom.romfile_id == Some(romfile.id)) => Ok(()),
+        _ => bail!("Checksum mismatch"),
+    }
+}
+
 async fn check_archive(
     connection: &mut SqliteConnection,
     progress_bar: &ProgressBar,
@@ -279,6 +577,260 @@ async fn check_archive(
     Ok(())
 }
 
+async fn check_chd_parents(
+    connection: &mut SqliteConnection,
+    progress_bar: &ProgressBar,
+    system: &System,
+    fix: bool,
+) -> SimpleResult<Vec<i64>> {
+    let romfiles = find_romfiles_by_system_id(connection, system.id).await;
+    let mut broken_romfile_ids: Vec<i64> = vec![];
+
+    for romfile in romfiles
+        .iter()
+        .filter(|romfile| romfile.path.ends_with(CHD_EXTENSION))
+    {
+        if let Err(error) =
+            check_chd_parent(connection, progress_bar, &romfiles, romfile, fix).await
+        {
+            progress_bar.println(format!(
+                "\"{}\" has a broken parent chain: {}",
+                romfile.path,
+                error.as_str().trim()
+            ));
+            broken_romfile_ids.push(romfile.id);
+            if fix {
+                move_to_trash(connection, progress_bar, system, romfile).await?;
+            }
+        }
+    }
+
+    if broken_romfile_ids.is_empty() {
+        progress_bar.println("No broken parent chain");
+    } else if fix {
+        compute_system_completion(connection, progress_bar, system).await;
+    }
+
+    Ok(broken_romfile_ids)
+}
+
+async fn check_dats(
+    connection: &mut SqliteConnection,
+    progress_bar: &ProgressBar,
+    system: &System,
+    dat_paths: &[&PathBuf],
+) -> SimpleResult<Vec<(String, usize, usize)>> {
+    let roms = find_roms_with_romfile_by_system_id(connection, system.id).await;
+    let sha1s: HashSet<(i64, String)> = roms
+        .iter()
+        .filter_map(|rom| {
+            rom.sha1
+                .as_ref()
+                .map(|sha1| (rom.size, sha1.to_lowercase()))
+        })
+        .collect();
+    let sha256s: HashSet<(i64, String)> = roms
+        .iter()
+        .filter_map(|rom| {
+            rom.sha256
+                .as_ref()
+                .map(|sha256| (rom.size, sha256.to_lowercase()))
+        })
+        .collect();
+    let crcs: HashSet<(i64, String)> = roms
+        .iter()
+        .filter_map(|rom| rom.crc.as_ref().map(|crc| (rom.size, crc.to_lowercase())))
+        .collect();
+
+    let mut completeness: Vec<(String, usize, usize)> = vec![];
+
+    for dat_path in dat_paths {
+        let (datfile_xml, _) = parse_dat(progress_bar, dat_path, true).await?;
+        let game_xmls = if datfile_xml.machines.is_empty() {
+            &datfile_xml.games
+        } else {
+            &datfile_xml.machines
+        };
+
+        let mut complete_count = 0;
+        let mut nodump_count = 0;
+        for game_xml in game_xmls {
+            let rom_xmls: Vec<&RomXml> = game_xml
+                .roms
+                .iter()
+                .filter(|rom_xml| rom_xml.status.as_deref() != Some("nodump"))
+                .collect();
+            // games without any dumped ROM can't be complete nor incomplete
+            if rom_xmls.is_empty() {
+                nodump_count += 1;
+                continue;
+            }
+            // prefer SHA1 when the DAT has it, then SHA256, otherwise fall back to CRC
+            let present_count = rom_xmls
+                .iter()
+                .filter(
+                    |rom_xml| match (&rom_xml.sha1, &rom_xml.sha256, &rom_xml.crc) {
+                        (Some(sha1), _, _) => sha1s.contains(&(rom_xml.size, sha1.to_lowercase())),
+                        (None, Some(sha256), _) => {
+                            sha256s.contains(&(rom_xml.size, sha256.to_lowercase()))
+                        }
+                        (None, None, Some(crc)) => {
+                            crcs.contains(&(rom_xml.size, crc.to_lowercase()))
+                        }
+                        (None, None, None) => false,
+                    },
+                )
+                .count();
+            if present_count == rom_xmls.len() {
+                complete_count += 1;
+            } else if present_count > 0 {
+                progress_bar.println(format!(
+                    "\"{}\": {}/{} ROM(s) present",
+                    game_xml.name,
+                    present_count,
+                    rom_xmls.len()
+                ));
+            }
+        }
+
+        let dat_name = format!(
+            "{} ({})",
+            datfile_xml.system.name, datfile_xml.system.version
+        );
+        let total_count = game_xmls.len() - nodump_count;
+        progress_bar.println(format!(
+            "\"{}\": {}/{} game(s) complete",
+            dat_name, complete_count, total_count
+        ));
+        if nodump_count > 0 {
+            progress_bar.println(format!(
+                "\"{}\": {} game(s) without dumps skipped",
+                dat_name, nodump_count
+            ));
+        }
+        completeness.push((dat_name, complete_count, total_count));
+    }
+
+    Ok(completeness)
+}
+
+async fn check_format(
+    connection: &mut SqliteConnection,
+    progress_bar: &ProgressBar,
+    system: &System,
+) -> SimpleResult<Vec<i64>> {
+    let format = match get_system_value(connection, "SYSTEM_FORMATS", &system.name).await {
+        Some(format) => format.to_uppercase(),
+        None => {
+            progress_bar.println("No format configured");
+            return Ok(vec![]);
+        }
+    };
+    if !ALL_FORMATS.contains(&format.as_str()) {
+        bail!("Unsupported format \"{}\"", format);
+    }
+
+    let romfiles = find_romfiles_by_system_id(connection, system.id).await;
+    let mut stray_romfile_ids: Vec<i64> = vec![];
+
+    for romfile in &romfiles {
+        let romfile_format = get_romfile_format(&romfile.path);
+        if romfile_format != format {
+            progress_bar.println(format!(
+                "\"{}\" is in {} instead of {}",
+                romfile.path, romfile_format, format
+            ));
+            stray_romfile_ids.push(romfile.id);
+        }
+    }
+
+    if stray_romfile_ids.is_empty() {
+        progress_bar.println(format!("All ROM files are in {}", format));
+    }
+
+    Ok(stray_romfile_ids)
+}
+
+async fn check_chd_parent(
+    connection: &mut SqliteConnection,
+    progress_bar: &ProgressBar,
+    romfiles: &[Romfile],
+    romfile: &Romfile,
+    fix: bool,
+) -> SimpleResult<()> {
+    let common_romfile = romfile.as_common(connection).await?;
+    // missing files are handled by purge-roms
+    if !common_romfile.path.is_file() {
+        return Ok(());
+    }
+    let parent_sha1 = chdman::get_parent_sha1(&common_romfile).await?;
+    let (parent_id, parent_sha1) = match (romfile.parent_id, parent_sha1) {
+        (Some(parent_id), Some(parent_sha1)) => (parent_id, parent_sha1),
+        (None, Some(parent_sha1)) => {
+            let parent_romfile =
+                match find_chd_by_sha1(connection, romfiles, romfile, &parent_sha1).await? {
+                    Some(parent_romfile) => parent_romfile,
+                    None => bail!("parent is unknown"),
+                };
+            if !fix {
+                bail!("parent \"{}\" isn't linked", parent_romfile.path);
+            }
+            progress_bar.println(format!(
+                "Relinking \"{}\" to \"{}\"",
+                romfile.path, parent_romfile.path
+            ));
+            update_romfile_parent(connection, romfile.id, Some(parent_romfile.id)).await;
+            (parent_romfile.id, parent_sha1)
+        }
+        // standalone CHD
+        (_, None) => return Ok(()),
+    };
+    let parent_romfile = match find_romfiles_by_ids(connection, &[parent_id]).await.pop() {
+        Some(parent_romfile) => parent_romfile,
+        None => bail!("parent is missing from the database"),
+    };
+    let parent_common_romfile = parent_romfile.as_common(connection).await?;
+    if !parent_common_romfile.path.is_file() {
+        bail!("parent \"{}\" is missing", parent_romfile.path);
+    }
+    let parent_chd_romfile = parent_common_romfile.as_chd().await?;
+    if parent_chd_romfile.chd_sha1 != parent_sha1 {
+        bail!("parent \"{}\" doesn't match", parent_romfile.path);
+    }
+    chdman::verify_chd(
+        progress_bar,
+        &common_romfile,
+        Some(&parent_chd_romfile.romfile),
+    )
+    .await
+}
+
+async fn find_chd_by_sha1<'a>(
+    connection: &mut SqliteConnection,
+    romfiles: &'a [Romfile],
+    romfile: &Romfile,
+    chd_sha1: &str,
+) -> SimpleResult<Option<&'a Romfile>> {
+    for other_romfile in romfiles
+        .iter()
+        .filter(|other_romfile| other_romfile.id != romfile.id)
+        .filter(|other_romfile| other_romfile.path.ends_with(CHD_EXTENSION))
+    {
+        let other_common_romfile = other_romfile.as_common(connection).await?;
+        if !other_common_romfile.path.is_file() {
+            continue;
+        }
+        if other_common_romfile
+            .as_chd()
+            .await
+            .is_ok_and(|chd_romfile| chd_romfile.chd_sha1 == chd_sha1)
+        {
+            return Ok(Some(other_romfile));
+        }
+    }
+    Ok(None)
+}
+
 async fn move_to_trash(
     connection: &mut SqliteConnection,
     progress_bar: &ProgressBar,
@@ -303,17 +855,43 @@ async fn move_to_trash(
     Ok(())
 }
 
+#[cfg(test)]
+mod test_chd_parents_missing_parent;
+#[cfg(test)]
+mod test_chd_parents_unlinked_parent;
 #[cfg(test)]
 mod test_cso;
 #[cfg(test)]
+mod test_dats;
+#[cfg(test)]
+mod test_enforce_format;
+#[cfg(test)]
+mod test_hash_window_mid_file_change;
+#[cfg(test)]
+mod test_hash_window_truncated;
+#[cfg(test)]
 mod test_iso_chd;
 #[cfg(test)]
+mod test_iso_chd_zero_padding;
+#[cfg(test)]
 mod test_multiple_tracks_chd;
 #[cfg(test)]
+mod test_multiple_tracks_partial;
+#[cfg(test)]
 mod test_original;
 #[cfg(test)]
 mod test_original_crc_mismatch;
 #[cfg(test)]
+mod test_original_crc_mismatch_outcome;
+#[cfg(test)]
+mod test_original_empty_rom;
+#[cfg(test)]
+mod test_original_headered_full;
+#[cfg(test)]
+mod test_original_index;
+#[cfg(test)]
+mod test_original_sha256_mismatch;
+#[cfg(test)]
 mod test_original_size_mismatch;
 #[cfg(test)]
 mod test_original_with_header;
@@ -324,6 +902,10 @@ mod test_sevenzip;
 #[cfg(test)]
 mod test_sevenzip_with_header;
 #[cfg(test)]
+mod test_split_clone;
+#[cfg(test)]
 mod test_zip;
 #[cfg(test)]
+mod test_zip_corrupt_container;
+#[cfg(test)]
 mod test_zso;
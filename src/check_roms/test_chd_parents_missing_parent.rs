@@ -0,0 +1,84 @@
+use super::super::convert_roms;
+use super::super::database::*;
+use super::super::generate_playlists;
+use super::super::import_dats;
+use super::super::import_roms;
+use super::*;
+use std::path::{Path, PathBuf};
+use tempfile::{NamedTempFile, TempDir};
+use tokio::fs;
+
+#[tokio::test]
+async fn test() {
+    // given
+    let _guard = MUTEX.lock().await;
+
+    let test_directory = Path::new("tests");
+    let progress_bar = ProgressBar::hidden();
+
+    let db_file = NamedTempFile::new().unwrap();
+    let pool = establish_connection(db_file.path().to_str().unwrap()).await;
+    let mut connection = pool.acquire().await.unwrap();
+
+    let rom_directory = TempDir::new_in(&test_directory).unwrap();
+    set_rom_directory(PathBuf::from(rom_directory.path()));
+    let tmp_directory = TempDir::new_in(&test_directory).unwrap();
+    let tmp_directory = set_tmp_directory(PathBuf::from(tmp_directory.path()));
+
+    let matches = import_dats::subcommand().get_matches_from(&[
+        "import-dats",
+        "tests/Test System (20230105) (Multiple Discs).dat",
+    ]);
+    import_dats::main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    let system = find_systems(&mut connection).await.remove(0);
+
+    for i in 1..=2 {
+        let romfile_name = format!("Test Game (USA, Europe) (Disc {}).iso", i);
+        let romfile_path = tmp_directory.join(&romfile_name);
+        fs::copy(test_directory.join(&romfile_name), &romfile_path)
+            .await
+            .unwrap();
+        let matches = import_roms::subcommand()
+            .get_matches_from(&["import-roms", romfile_path.as_os_str().to_str().unwrap()]);
+        import_roms::main(&mut connection, &matches, &progress_bar)
+            .await
+            .unwrap();
+    }
+
+    let matches = generate_playlists::subcommand().get_matches_from(&["generate-playlists", "-a"]);
+    generate_playlists::main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    set_bool(&mut connection, "CHD_PARENTS", true).await;
+    let matches = convert_roms::subcommand().get_matches_from(&["convert-roms", "-f", "CHD", "-a"]);
+    convert_roms::main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    let romfiles = find_romfiles_by_system_id(&mut connection, system.id).await;
+    let parent_romfile = romfiles
+        .iter()
+        .find(|romfile| romfile.parent_id.is_none())
+        .unwrap();
+    let child_romfile = romfiles
+        .iter()
+        .find(|romfile| romfile.parent_id == Some(parent_romfile.id))
+        .unwrap();
+    fs::remove_file(rom_directory.path().join(&parent_romfile.path))
+        .await
+        .unwrap();
+
+    // when
+    let broken_romfile_ids = check_chd_parents(&mut connection, &progress_bar, &system, false)
+        .await
+        .unwrap();
+
+    // then
+    assert_eq!(broken_romfile_ids, vec![child_romfile.id]);
+    let romfile = find_romfile_by_id(&mut connection, child_romfile.id).await;
+    assert!(!romfile.path.contains("/Trash/"));
+}
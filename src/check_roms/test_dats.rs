@@ -0,0 +1,74 @@
+use super::super::database::*;
+use super::super::import_dats;
+use super::super::import_roms;
+use super::*;
+use std::path::{Path, PathBuf};
+use tempfile::{NamedTempFile, TempDir};
+use tokio::fs;
+
+#[tokio::test]
+async fn test() {
+    // given
+    let _guard = MUTEX.lock().await;
+
+    let test_directory = Path::new("tests");
+    let progress_bar = ProgressBar::hidden();
+
+    let db_file = NamedTempFile::new().unwrap();
+    let pool = establish_connection(db_file.path().to_str().unwrap()).await;
+    let mut connection = pool.acquire().await.unwrap();
+
+    let rom_directory = TempDir::new_in(&test_directory).unwrap();
+    set_rom_directory(PathBuf::from(rom_directory.path()));
+    let tmp_directory = TempDir::new_in(&test_directory).unwrap();
+    let tmp_directory = set_tmp_directory(PathBuf::from(tmp_directory.path()));
+
+    let matches = import_dats::subcommand()
+        .get_matches_from(&["import-dats", "tests/Test System (20200721).dat"]);
+    import_dats::main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    let romfile_path = tmp_directory.join("Test Game (USA, Europe).rom");
+    fs::copy(
+        test_directory.join("Test Game (USA, Europe).rom"),
+        &romfile_path,
+    )
+    .await
+    .unwrap();
+
+    let system = find_systems(&mut connection).await.remove(0);
+
+    let matches = import_roms::subcommand()
+        .get_matches_from(&["import-roms", romfile_path.as_os_str().to_str().unwrap()]);
+    import_roms::main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    let no_intro_dat_path = test_directory.join("Test System (20200721).dat");
+    let redump_dat_path = test_directory.join("Test System (20250217) (Redump).dat");
+
+    // when
+    let completeness = check_dats(
+        &mut connection,
+        &progress_bar,
+        &system,
+        &[&no_intro_dat_path, &redump_dat_path],
+    )
+    .await
+    .unwrap();
+
+    // then
+    assert_eq!(completeness.len(), 2);
+
+    let (dat_name, complete_count, total_count) = completeness.first().unwrap();
+    assert_eq!(dat_name, "Test System (20200721)");
+    assert_eq!(*complete_count, 1);
+    assert_eq!(*total_count, 6);
+
+    // the game without any dump is left out of the total
+    let (dat_name, complete_count, total_count) = completeness.get(1).unwrap();
+    assert_eq!(dat_name, "Test System (20250217)");
+    assert_eq!(*complete_count, 0);
+    assert_eq!(*total_count, 1);
+}
@@ -0,0 +1,78 @@
+use super::super::database::*;
+use super::super::import_dats;
+use super::super::import_roms;
+use super::*;
+use std::path::{Path, PathBuf};
+use tempfile::{NamedTempFile, TempDir};
+use tokio::fs;
+
+#[tokio::test]
+async fn test() {
+    // given
+    let _guard = MUTEX.lock().await;
+
+    let test_directory = Path::new("tests");
+    let progress_bar = ProgressBar::hidden();
+
+    let db_file = NamedTempFile::new().unwrap();
+    let pool = establish_connection(db_file.path().to_str().unwrap()).await;
+    let mut connection = pool.acquire().await.unwrap();
+
+    let rom_directory = TempDir::new_in(&test_directory).unwrap();
+    set_rom_directory(PathBuf::from(rom_directory.path()));
+    let tmp_directory = TempDir::new_in(&test_directory).unwrap();
+    let tmp_directory = set_tmp_directory(PathBuf::from(tmp_directory.path()));
+
+    let matches = import_dats::subcommand()
+        .get_matches_from(&["import-dats", "tests/Test System (20200721).dat"]);
+    import_dats::main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    let zip_path = tmp_directory.join("Test Game (USA, Europe).rom.zip");
+    fs::copy(
+        test_directory.join("Test Game (USA, Europe).rom.zip"),
+        &zip_path,
+    )
+    .await
+    .unwrap();
+    let original_path = tmp_directory.join("Test Game (Japan).rom");
+    fs::copy(test_directory.join("Test Game (Japan).rom"), &original_path)
+        .await
+        .unwrap();
+
+    let system = find_systems(&mut connection).await.remove(0);
+
+    let matches = import_roms::subcommand().get_matches_from(&[
+        "import-roms",
+        zip_path.as_os_str().to_str().unwrap(),
+        original_path.as_os_str().to_str().unwrap(),
+    ]);
+    import_roms::main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    add_to_list(
+        &mut connection,
+        "SYSTEM_FORMATS",
+        &format!("{}=ZIP", system.name),
+    )
+    .await;
+
+    // when
+    let stray_romfile_ids = check_format(&mut connection, &progress_bar, &system)
+        .await
+        .unwrap();
+
+    // then
+    assert_eq!(stray_romfile_ids.len(), 1);
+
+    let romfile = find_romfile_by_id(&mut connection, stray_romfile_ids[0]).await;
+    assert!(romfile.path.ends_with("Test Game (Japan).rom"));
+
+    let romfiles = find_romfiles(&mut connection).await;
+    assert_eq!(romfiles.len(), 2);
+    assert!(romfiles
+        .iter()
+        .all(|romfile| rom_directory.path().join(&romfile.path).is_file()));
+}
@@ -0,0 +1,114 @@
+use super::super::database::*;
+use super::super::import_dats;
+use super::super::import_roms;
+use super::*;
+use std::io::SeekFrom;
+use std::path::{Path, PathBuf};
+use tempfile::{NamedTempFile, TempDir};
+use tokio::fs;
+use tokio::io::{AsyncSeekExt, AsyncWriteExt};
+
+#[tokio::test]
+async fn test() {
+    // given
+    let _guard = MUTEX.lock().await;
+
+    let test_directory = Path::new("tests");
+    let progress_bar = ProgressBar::hidden();
+
+    let db_file = NamedTempFile::new().unwrap();
+    let pool = establish_connection(db_file.path().to_str().unwrap()).await;
+    let mut connection = pool.acquire().await.unwrap();
+
+    let rom_directory = TempDir::new_in(&test_directory).unwrap();
+    set_rom_directory(PathBuf::from(rom_directory.path()));
+    let tmp_directory = TempDir::new_in(&test_directory).unwrap();
+    let tmp_directory = set_tmp_directory(PathBuf::from(tmp_directory.path()));
+
+    let matches = import_dats::subcommand()
+        .get_matches_from(&["import-dats", "tests/Test System (20200721).dat"]);
+    import_dats::main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    let romfile_path = tmp_directory.join("Test Game (USA, Europe) (CUE BIN) (Track 01).bin");
+    fs::copy(
+        test_directory.join("Test Game (USA, Europe) (CUE BIN) (Track 01).bin"),
+        &romfile_path,
+    )
+    .await
+    .unwrap();
+
+    let matches = import_roms::subcommand()
+        .get_matches_from(&["import-roms", romfile_path.as_os_str().to_str().unwrap()]);
+    import_roms::main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    let system = find_systems(&mut connection).await.remove(0);
+    let romfile = find_romfiles(&mut connection).await.remove(0);
+
+    // the first pass is a full check storing the partial hash
+    let games = find_partial_or_complete_games_by_system_id(&mut connection, system.id).await;
+    check_system(
+        &mut connection,
+        &progress_bar,
+        &system,
+        games,
+        false,
+        false,
+        Some(1),
+        false,
+    )
+    .await
+    .unwrap();
+
+    // corrupt the middle of the file, outside of both 1 MiB windows
+    let mut file = fs::OpenOptions::new()
+        .write(true)
+        .open(rom_directory.path().join(&romfile.path))
+        .await
+        .unwrap();
+    file.seek(SeekFrom::Start(romfile.size as u64 / 2))
+        .await
+        .unwrap();
+    file.write_all(b"00000000").await.unwrap();
+    file.sync_all().await.unwrap();
+
+    let games = find_partial_or_complete_games_by_system_id(&mut connection, system.id).await;
+
+    // when
+    let (bad_romfile_ids, _) = check_system(
+        &mut connection,
+        &progress_bar,
+        &system,
+        games,
+        false,
+        false,
+        Some(1),
+        false,
+    )
+    .await
+    .unwrap();
+
+    // then the partial check misses it, as documented
+    assert!(bad_romfile_ids.is_empty());
+
+    // when
+    let games = find_partial_or_complete_games_by_system_id(&mut connection, system.id).await;
+    let (bad_romfile_ids, _) = check_system(
+        &mut connection,
+        &progress_bar,
+        &system,
+        games,
+        false,
+        false,
+        None,
+        false,
+    )
+    .await
+    .unwrap();
+
+    // then a full check catches it
+    assert_eq!(bad_romfile_ids, vec![romfile.id]);
+}
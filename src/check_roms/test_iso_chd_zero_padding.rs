@@ -0,0 +1,104 @@
+use super::super::chdman::{ToChd, ToIso};
+use super::super::database::*;
+use super::super::import_dats;
+use super::super::import_roms;
+use super::*;
+use std::path::{Path, PathBuf};
+use tempfile::{NamedTempFile, TempDir};
+use tokio::fs;
+use tokio::io::AsyncWriteExt;
+
+#[tokio::test]
+async fn test() {
+    // given
+    let _guard = MUTEX.lock().await;
+
+    let test_directory = Path::new("tests");
+    let progress_bar = ProgressBar::hidden();
+
+    let db_file = NamedTempFile::new().unwrap();
+    let pool = establish_connection(db_file.path().to_str().unwrap()).await;
+    let mut connection = pool.acquire().await.unwrap();
+
+    let rom_directory = TempDir::new_in(&test_directory).unwrap();
+    set_rom_directory(PathBuf::from(rom_directory.path()));
+    let tmp_directory = TempDir::new_in(&test_directory).unwrap();
+    let tmp_directory = set_tmp_directory(PathBuf::from(tmp_directory.path()));
+
+    let matches = import_dats::subcommand()
+        .get_matches_from(&["import-dats", "tests/Test System (20200721).dat"]);
+    import_dats::main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    let romfile_path = tmp_directory.join("Test Game (USA, Europe) (ISO).chd");
+    fs::copy(
+        test_directory.join("Test Game (USA, Europe) (ISO).chd"),
+        &romfile_path,
+    )
+    .await
+    .unwrap();
+
+    let system = find_systems(&mut connection).await.remove(0);
+
+    let matches = import_roms::subcommand()
+        .get_matches_from(&["import-roms", romfile_path.as_os_str().to_str().unwrap()]);
+    import_roms::main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    // replace the imported CHD with one made from a zero-padded copy of the same ISO
+    let romfile = find_romfiles(&mut connection).await.remove(0);
+    let iso_romfile = romfile
+        .as_common(&mut connection)
+        .await
+        .unwrap()
+        .as_chd()
+        .await
+        .unwrap()
+        .to_iso(&progress_bar, &tmp_directory)
+        .await
+        .unwrap();
+    let mut iso_file = fs::OpenOptions::new()
+        .append(true)
+        .open(&iso_romfile.romfile.path)
+        .await
+        .unwrap();
+    iso_file.write_all(&[0u8; 8192]).await.unwrap();
+    iso_file.flush().await.unwrap();
+    let padded_directory = TempDir::new_in(&tmp_directory).unwrap();
+    let padded_chd_romfile = iso_romfile
+        .to_chd(&progress_bar, &padded_directory.path(), &[], &None, None)
+        .await
+        .unwrap();
+    fs::copy(
+        &padded_chd_romfile.romfile.path,
+        rom_directory.path().join(&romfile.path),
+    )
+    .await
+    .unwrap();
+
+    let games = find_complete_games_by_system_id(&mut connection, system.id).await;
+
+    // when
+    check_system(
+        &mut connection,
+        &progress_bar,
+        &system,
+        games,
+        false,
+        false,
+        None,
+        false,
+    )
+    .await
+    .unwrap();
+
+    // then
+    let mut romfiles = find_romfiles(&mut connection).await;
+    assert_eq!(romfiles.len(), 1);
+
+    let romfile = romfiles.remove(0);
+    assert!(!romfile.path.contains("/Trash/"));
+    assert!(&rom_directory.path().join(&romfile.path).is_file());
+}
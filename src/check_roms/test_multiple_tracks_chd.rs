@@ -55,9 +55,18 @@ async fn test() {
     let games = find_complete_games_by_system_id(&mut connection, system.id).await;
 
     // when
-    check_system(&mut connection, &progress_bar, &system, games, false)
-        .await
-        .unwrap();
+    check_system(
+        &mut connection,
+        &progress_bar,
+        &system,
+        games,
+        false,
+        false,
+        None,
+        false,
+    )
+    .await
+    .unwrap();
 
     // then
     let romfiles = find_romfiles(&mut connection).await;
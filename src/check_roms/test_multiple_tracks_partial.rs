@@ -0,0 +1,105 @@
+use super::super::database::*;
+use super::super::import_dats;
+use super::super::import_roms;
+use super::*;
+use std::path::{Path, PathBuf};
+use tempfile::{NamedTempFile, TempDir};
+use tokio::fs;
+use tokio::io::AsyncWriteExt;
+
+#[tokio::test]
+async fn test() {
+    // given
+    let _guard = MUTEX.lock().await;
+
+    let test_directory = Path::new("tests");
+    let progress_bar = ProgressBar::hidden();
+
+    let db_file = NamedTempFile::new().unwrap();
+    let pool = establish_connection(db_file.path().to_str().unwrap()).await;
+    let mut connection = pool.acquire().await.unwrap();
+
+    let rom_directory = TempDir::new_in(&test_directory).unwrap();
+    set_rom_directory(PathBuf::from(rom_directory.path()));
+    let tmp_directory = TempDir::new_in(&test_directory).unwrap();
+    let tmp_directory = set_tmp_directory(PathBuf::from(tmp_directory.path()));
+
+    let matches = import_dats::subcommand()
+        .get_matches_from(&["import-dats", "tests/Test System (20200721).dat"]);
+    import_dats::main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    for romfile_name in [
+        "Test Game (USA, Europe) (Multiple Tracks).cue",
+        "Test Game (USA, Europe) (CUE BIN) (Track 02).bin",
+    ] {
+        let romfile_path = tmp_directory.join(romfile_name);
+        fs::copy(test_directory.join(romfile_name), &romfile_path)
+            .await
+            .unwrap();
+        let matches = import_roms::subcommand()
+            .get_matches_from(&["import-roms", romfile_path.as_os_str().to_str().unwrap()]);
+        import_roms::main(&mut connection, &matches, &progress_bar)
+            .await
+            .unwrap();
+    }
+
+    let system = find_systems(&mut connection).await.remove(0);
+
+    let romfile = find_romfiles(&mut connection)
+        .await
+        .into_iter()
+        .find(|romfile| romfile.path.ends_with("(Track 02).bin"))
+        .unwrap();
+    let mut file = fs::OpenOptions::new()
+        .write(true)
+        .open(rom_directory.path().join(&romfile.path))
+        .await
+        .unwrap();
+    file.write_all(b"00000000").await.unwrap();
+    file.sync_all().await.unwrap();
+
+    let games = find_partial_or_complete_games_by_system_id(&mut connection, system.id).await;
+    assert_eq!(games.len(), 1);
+    let game_id = games.first().unwrap().id;
+
+    // when
+    let (bad_romfile_ids, _) = check_system(
+        &mut connection,
+        &progress_bar,
+        &system,
+        games,
+        false,
+        false,
+        None,
+        false,
+    )
+    .await
+    .unwrap();
+
+    // then
+    assert_eq!(bad_romfile_ids, vec![romfile.id]);
+
+    let roms = find_roms_with_romfile_by_game_ids(&mut connection, &[game_id]).await;
+    let roms_without_romfile =
+        find_roms_without_romfile_by_game_ids(&mut connection, &[game_id]).await;
+    let (present_roms, missing_roms, bad_roms) =
+        partition_roms_by_status(game_id, &roms, &roms_without_romfile, &bad_romfile_ids);
+
+    assert_eq!(present_roms.len(), 1);
+    assert_eq!(
+        present_roms[0].name,
+        "Test Game (USA, Europe) (CUE BIN).cue"
+    );
+    assert_eq!(missing_roms.len(), 1);
+    assert_eq!(
+        missing_roms[0].name,
+        "Test Game (USA, Europe) (CUE BIN) (Track 01).bin"
+    );
+    assert_eq!(bad_roms.len(), 1);
+    assert_eq!(
+        bad_roms[0].name,
+        "Test Game (USA, Europe) (CUE BIN) (Track 02).bin"
+    );
+}
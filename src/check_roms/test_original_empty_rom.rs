@@ -0,0 +1,96 @@
+use super::super::database::*;
+use super::super::import_dats;
+use super::super::import_roms;
+use super::*;
+use std::path::{Path, PathBuf};
+use tempfile::{NamedTempFile, TempDir};
+use tokio::fs;
+
+#[tokio::test]
+async fn test() {
+    // given
+    let _guard = MUTEX.lock().await;
+
+    let test_directory = Path::new("tests");
+    let progress_bar = ProgressBar::hidden();
+
+    let db_file = NamedTempFile::new().unwrap();
+    let pool = establish_connection(db_file.path().to_str().unwrap()).await;
+    let mut connection = pool.acquire().await.unwrap();
+
+    let rom_directory = TempDir::new_in(&test_directory).unwrap();
+    set_rom_directory(PathBuf::from(rom_directory.path()));
+    let tmp_directory = TempDir::new_in(&test_directory).unwrap();
+    let tmp_directory = set_tmp_directory(PathBuf::from(tmp_directory.path()));
+
+    let matches = import_dats::subcommand().get_matches_from(&[
+        "import-dats",
+        "tests/Test System (20250227) (Empty Roms).dat",
+    ]);
+    import_dats::main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    let game_directory = tmp_directory.join("Test Game (USA, Europe)");
+    fs::create_dir(&game_directory).await.unwrap();
+    fs::copy(
+        test_directory.join("Test Game (USA, Europe).rom"),
+        game_directory.join("Test Game (USA, Europe).rom"),
+    )
+    .await
+    .unwrap();
+    fs::write(
+        game_directory.join("Test Game (USA, Europe) (Side B).rom"),
+        b"",
+    )
+    .await
+    .unwrap();
+
+    let system = find_systems(&mut connection).await.remove(0);
+
+    let matches = import_roms::subcommand().get_matches_from(&[
+        "import-roms",
+        "-u",
+        game_directory.as_os_str().to_str().unwrap(),
+    ]);
+    import_roms::main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    let games = find_complete_games_by_system_id(&mut connection, system.id).await;
+    assert_eq!(games.len(), 1);
+    assert_eq!(games[0].name, "Test Game (USA, Europe)");
+
+    // when
+    check_system(
+        &mut connection,
+        &progress_bar,
+        &system,
+        games,
+        true,
+        false,
+        None,
+        false,
+    )
+    .await
+    .unwrap();
+
+    // then
+    let romfiles = find_romfiles(&mut connection).await;
+    assert_eq!(romfiles.len(), 2);
+    for romfile in &romfiles {
+        assert!(!romfile.path.contains("/Trash/"));
+        assert!(rom_directory.path().join(&romfile.path).is_file());
+    }
+    let romfile = romfiles
+        .iter()
+        .find(|romfile| romfile.path.ends_with("(Side B).rom"))
+        .unwrap();
+    assert_eq!(romfile.size, 0);
+
+    let game = find_complete_games_by_system_id(&mut connection, system.id)
+        .await
+        .remove(0);
+    assert_eq!(game.name, "Test Game (USA, Europe)");
+    assert_eq!(game.completion, Completion::Full as i64);
+}
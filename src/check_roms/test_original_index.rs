@@ -0,0 +1,107 @@
+use super::super::database::*;
+use super::super::import_dats;
+use super::super::import_roms;
+use super::*;
+use std::path::{Path, PathBuf};
+use tempfile::{NamedTempFile, TempDir};
+use tokio::fs;
+use tokio::io::AsyncWriteExt;
+
+#[tokio::test]
+async fn test() {
+    // given
+    let _guard = MUTEX.lock().await;
+
+    let test_directory = Path::new("tests");
+    let progress_bar = ProgressBar::hidden();
+
+    let mut results: Vec<Vec<(String, bool)>> = vec![];
+
+    for index in [false, true] {
+        let db_file = NamedTempFile::new().unwrap();
+        let pool = establish_connection(db_file.path().to_str().unwrap()).await;
+        let mut connection = pool.acquire().await.unwrap();
+
+        let rom_directory = TempDir::new_in(&test_directory).unwrap();
+        set_rom_directory(PathBuf::from(rom_directory.path()));
+        let tmp_directory = TempDir::new_in(&test_directory).unwrap();
+        let tmp_directory = set_tmp_directory(PathBuf::from(tmp_directory.path()));
+
+        let matches = import_dats::subcommand()
+            .get_matches_from(&["import-dats", "tests/Test System (20200721).dat"]);
+        import_dats::main(&mut connection, &matches, &progress_bar)
+            .await
+            .unwrap();
+
+        for romfile_name in ["Test Game (Japan).rom", "Test Game (USA, Europe).rom"] {
+            let romfile_path = tmp_directory.join(romfile_name);
+            fs::copy(test_directory.join(romfile_name), &romfile_path)
+                .await
+                .unwrap();
+            let matches = import_roms::subcommand()
+                .get_matches_from(&["import-roms", romfile_path.as_os_str().to_str().unwrap()]);
+            import_roms::main(&mut connection, &matches, &progress_bar)
+                .await
+                .unwrap();
+        }
+
+        let system = find_systems(&mut connection).await.remove(0);
+
+        let romfile = find_romfiles(&mut connection)
+            .await
+            .into_iter()
+            .find(|romfile| romfile.path.contains("Japan"))
+            .unwrap();
+        let mut file = fs::OpenOptions::new()
+            .write(true)
+            .open(rom_directory.path().join(&romfile.path))
+            .await
+            .unwrap();
+        file.write_all(b"00000000").await.unwrap();
+        file.sync_all().await.unwrap();
+
+        let games = find_complete_games_by_system_id(&mut connection, system.id).await;
+
+        // when
+        check_system(
+            &mut connection,
+            &progress_bar,
+            &system,
+            games,
+            false,
+            index,
+            None,
+            false,
+        )
+        .await
+        .unwrap();
+
+        let mut romfiles: Vec<(String, bool)> = find_romfiles(&mut connection)
+            .await
+            .into_iter()
+            .map(|romfile| {
+                (
+                    Path::new(&romfile.path)
+                        .file_name()
+                        .unwrap()
+                        .to_str()
+                        .unwrap()
+                        .to_owned(),
+                    romfile.path.contains("/Trash/"),
+                )
+            })
+            .collect();
+        romfiles.sort();
+        results.push(romfiles);
+    }
+
+    // then
+    assert_eq!(results[0], results[1]);
+    assert_eq!(
+        results[1],
+        vec![
+            (String::from("Test Game (Japan).rom"), true),
+            (String::from("Test Game (USA, Europe).rom"), false),
+        ]
+    );
+}
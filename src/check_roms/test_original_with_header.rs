@@ -49,7 +49,9 @@ async fn test() {
         true,
         false,
         false,
+        import_roms::ImportMode::Move,
         &None,
+        &HashMap::new(),
     )
     .await
     .unwrap();
@@ -57,9 +59,18 @@ async fn test() {
     let games = find_complete_games_by_system_id(&mut connection, system.id).await;
 
     // when
-    check_system(&mut connection, &progress_bar, &system, games, false)
-        .await
-        .unwrap();
+    check_system(
+        &mut connection,
+        &progress_bar,
+        &system,
+        games,
+        false,
+        false,
+        None,
+        false,
+    )
+    .await
+    .unwrap();
 
     // then
     let mut romfiles = find_romfiles(&mut connection).await;
@@ -0,0 +1,82 @@
+use super::super::database::*;
+use super::super::import_dats;
+use super::super::import_roms;
+use super::*;
+use std::path::{Path, PathBuf};
+use tempfile::{NamedTempFile, TempDir};
+use tokio::fs;
+
+#[tokio::test]
+async fn test() {
+    // given
+    let _guard = MUTEX.lock().await;
+
+    let test_directory = Path::new("tests");
+    let progress_bar = ProgressBar::hidden();
+
+    let db_file = NamedTempFile::new().unwrap();
+    let pool = establish_connection(db_file.path().to_str().unwrap()).await;
+    let mut connection = pool.acquire().await.unwrap();
+
+    let rom_directory = TempDir::new_in(&test_directory).unwrap();
+    set_rom_directory(PathBuf::from(rom_directory.path()));
+    let tmp_directory = TempDir::new_in(&test_directory).unwrap();
+    let tmp_directory = set_tmp_directory(PathBuf::from(tmp_directory.path()));
+
+    let matches = import_dats::subcommand().get_matches_from(&[
+        "import-dats",
+        "tests/Test System (20250225) (MAME Split).dat",
+    ]);
+    import_dats::main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    let romfile_path = tmp_directory.join("Test Game (Japan).rom");
+    fs::copy(test_directory.join("Test Game (Japan).rom"), &romfile_path)
+        .await
+        .unwrap();
+
+    let system = find_systems(&mut connection).await.remove(0);
+
+    let matches = import_roms::subcommand()
+        .get_matches_from(&["import-roms", romfile_path.as_os_str().to_str().unwrap()]);
+    import_roms::main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    let games = find_complete_games_by_system_id(&mut connection, system.id).await;
+
+    // when
+    check_system(
+        &mut connection,
+        &progress_bar,
+        &system,
+        games,
+        true,
+        false,
+        None,
+        false,
+    )
+    .await
+    .unwrap();
+
+    // then
+    let mut games = find_complete_games_by_system_id(&mut connection, system.id).await;
+    assert_eq!(games.len(), 1);
+
+    let game = games.remove(0);
+    assert_eq!(game.name, "Test Game (Japan)");
+    assert_eq!(game.completion, Completion::Full as i64);
+
+    let roms = find_roms_by_game_id_parents_only(&mut connection, game.id).await;
+    assert_eq!(roms.len(), 1);
+    assert_eq!(roms[0].name, "Test Game (USA, Europe).rom");
+    assert!(roms[0].romfile_id.is_none());
+
+    let mut romfiles = find_romfiles(&mut connection).await;
+    assert_eq!(romfiles.len(), 1);
+
+    let romfile = romfiles.remove(0);
+    assert!(!romfile.path.contains("/Trash/"));
+    assert!(&rom_directory.path().join(&romfile.path).is_file());
+}
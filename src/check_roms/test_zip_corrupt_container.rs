@@ -0,0 +1,91 @@
+use super::super::database::*;
+use super::super::import_dats;
+use super::super::import_roms;
+use super::*;
+use std::io::SeekFrom;
+use std::path::{Path, PathBuf};
+use tempfile::{NamedTempFile, TempDir};
+use tokio::fs;
+use tokio::io::{AsyncSeekExt, AsyncWriteExt};
+
+#[tokio::test]
+async fn test() {
+    // given
+    let _guard = MUTEX.lock().await;
+
+    let test_directory = Path::new("tests");
+    let progress_bar = ProgressBar::hidden();
+
+    let db_file = NamedTempFile::new().unwrap();
+    let pool = establish_connection(db_file.path().to_str().unwrap()).await;
+    let mut connection = pool.acquire().await.unwrap();
+
+    let rom_directory = TempDir::new_in(&test_directory).unwrap();
+    set_rom_directory(PathBuf::from(rom_directory.path()));
+    let tmp_directory = TempDir::new_in(&test_directory).unwrap();
+    let tmp_directory = set_tmp_directory(PathBuf::from(tmp_directory.path()));
+
+    let matches = import_dats::subcommand()
+        .get_matches_from(&["import-dats", "tests/Test System (20200721).dat"]);
+    import_dats::main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    let romfile_path = tmp_directory.join("Test Game (USA, Europe).rom.zip");
+    fs::copy(
+        test_directory.join("Test Game (USA, Europe).rom.zip"),
+        &romfile_path,
+    )
+    .await
+    .unwrap();
+
+    let system = find_systems(&mut connection).await.remove(0);
+
+    let matches = import_roms::subcommand()
+        .get_matches_from(&["import-roms", romfile_path.as_os_str().to_str().unwrap()]);
+    import_roms::main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    // overwrite some of the stored member data, its CRC in the container no longer matches
+    let romfile = find_romfiles(&mut connection).await.remove(0);
+    let archive_path = rom_directory.path().join(&romfile.path);
+    let mut file = fs::OpenOptions::new()
+        .write(true)
+        .open(&archive_path)
+        .await
+        .unwrap();
+    file.seek(SeekFrom::Start(64)).await.unwrap();
+    file.write_all(b"00000000").await.unwrap();
+    file.sync_all().await.unwrap();
+
+    assert!(sevenzip::test_archive(&progress_bar, &archive_path)
+        .await
+        .is_err());
+
+    let games = find_complete_games_by_system_id(&mut connection, system.id).await;
+
+    // when
+    let (bad_romfile_ids, _) = check_system(
+        &mut connection,
+        &progress_bar,
+        &system,
+        games,
+        false,
+        false,
+        None,
+        true,
+    )
+    .await
+    .unwrap();
+
+    // then
+    assert_eq!(bad_romfile_ids, vec![romfile.id]);
+
+    let mut romfiles = find_romfiles(&mut connection).await;
+    assert_eq!(romfiles.len(), 1);
+
+    let romfile = romfiles.remove(0);
+    assert!(romfile.path.contains("/Trash/"));
+    assert!(&rom_directory.path().join(&romfile.path).is_file());
+}
@@ -0,0 +1,79 @@
+use super::config::*;
+use super::prompt::*;
+use super::util::*;
+use super::SimpleResult;
+use clap::{Arg, ArgAction, ArgMatches, Command};
+use indicatif::ProgressBar;
+use sqlx::sqlite::SqliteConnection;
+use walkdir::WalkDir;
+
+pub fn subcommand() -> Command {
+    Command::new("commit")
+        .about("Delete the files staged by previous commands run with --safe")
+        .arg(
+            Arg::new("LIST")
+                .short('l')
+                .long("list")
+                .help("List staged files and exit")
+                .required(false)
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("YES")
+                .short('y')
+                .long("yes")
+                .help("Automatically say yes to prompts")
+                .required(false)
+                .action(ArgAction::SetTrue),
+        )
+}
+
+pub async fn main(
+    connection: &mut SqliteConnection,
+    matches: &ArgMatches,
+    progress_bar: &ProgressBar,
+) -> SimpleResult<()> {
+    let staging_directory = get_rom_directory(connection)
+        .await
+        .join(STAGING_DIRECTORY_NAME);
+    let paths: Vec<String> = WalkDir::new(&staging_directory)
+        .sort_by_file_name()
+        .into_iter()
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_type().is_file())
+        .map(|entry| {
+            entry
+                .path()
+                .strip_prefix(&staging_directory)
+                .unwrap()
+                .as_os_str()
+                .to_str()
+                .unwrap()
+                .to_owned()
+        })
+        .collect();
+
+    if paths.is_empty() {
+        progress_bar.println("Nothing to commit");
+        return Ok(());
+    }
+
+    progress_bar.println("Summary:");
+    for path in &paths {
+        progress_bar.println(path);
+    }
+
+    if matches.get_flag("LIST") {
+        return Ok(());
+    }
+
+    if matches.get_flag("YES") || confirm(true)? {
+        remove_directory(progress_bar, &staging_directory, true).await?;
+        progress_bar.println(format!("Deleted {} staged file(s)", paths.len()));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test_convert;
@@ -0,0 +1,77 @@
+use super::super::convert_roms;
+use super::super::database::*;
+use super::super::import_dats;
+use super::super::import_roms;
+use super::*;
+use std::path::{Path, PathBuf};
+use tempfile::{NamedTempFile, TempDir};
+use tokio::fs;
+
+#[tokio::test]
+async fn test() {
+    // given
+    let _guard = MUTEX.lock().await;
+
+    let test_directory = Path::new("tests");
+    let progress_bar = ProgressBar::hidden();
+
+    let db_file = NamedTempFile::new().unwrap();
+    let pool = establish_connection(db_file.path().to_str().unwrap()).await;
+    let mut connection = pool.acquire().await.unwrap();
+
+    let rom_directory = TempDir::new_in(&test_directory).unwrap();
+    let rom_directory = set_rom_directory(PathBuf::from(rom_directory.path()));
+    let tmp_directory = TempDir::new_in(&test_directory).unwrap();
+    let tmp_directory = set_tmp_directory(PathBuf::from(tmp_directory.path()));
+
+    let matches = import_dats::subcommand()
+        .get_matches_from(&["import-dats", "tests/Test System (20200721).dat"]);
+    import_dats::main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    let romfile_path = tmp_directory.join("Test Game (USA, Europe).rom");
+    fs::copy(
+        test_directory.join("Test Game (USA, Europe).rom"),
+        &romfile_path,
+    )
+    .await
+    .unwrap();
+
+    let matches = import_roms::subcommand()
+        .get_matches_from(&["import-roms", romfile_path.as_os_str().to_str().unwrap()]);
+    import_roms::main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    let original_path = rom_directory.join(&find_romfiles(&mut connection).await[0].path);
+    let staged_path = rom_directory
+        .join(STAGING_DIRECTORY_NAME)
+        .join(original_path.strip_prefix(rom_directory).unwrap());
+
+    // when
+    set_safe_mode(Some((rom_directory.clone(), tmp_directory.clone())));
+    let matches = convert_roms::subcommand().get_matches_from(&["convert-roms", "-f", "ZIP", "-a"]);
+    let result = convert_roms::main(&mut connection, &matches, &progress_bar).await;
+    set_safe_mode(None);
+    result.unwrap();
+
+    // then
+    let romfiles = find_romfiles(&mut connection).await;
+    assert_eq!(romfiles.len(), 1);
+    assert!(romfiles[0].path.ends_with(".zip"));
+    assert!(rom_directory.join(&romfiles[0].path).is_file());
+    assert!(!original_path.exists());
+    assert!(staged_path.is_file());
+
+    // when
+    let matches = subcommand().get_matches_from(&["commit", "-y"]);
+    main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    // then
+    assert!(!staged_path.exists());
+    assert!(!rom_directory.join(STAGING_DIRECTORY_NAME).exists());
+    assert!(rom_directory.join(&romfiles[0].path).is_file());
+}
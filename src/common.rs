@@ -10,13 +10,16 @@ use core::fmt;
 use digest::Digest;
 use indicatif::ProgressBar;
 use md5::Md5;
+use rayon::prelude::*;
 use sha1::Sha1;
+use sha2::Sha256;
 use simple_error::SimpleResult;
 use sqlx::SqliteConnection;
 use std::fs::File;
 use std::io;
 use std::io::prelude::*;
 use std::path::{Path, PathBuf};
+use strum::IntoEnumIterator;
 
 #[derive(Clone)]
 pub struct CommonRomfile {
@@ -126,6 +129,164 @@ impl Size for CommonRomfile {
     }
 }
 
+pub fn compute_hash<R: Read>(
+    reader: &mut R,
+    progress_bar: &ProgressBar,
+    hash_algorithm: &HashAlgorithm,
+) -> SimpleResult<String> {
+    let hash = match hash_algorithm {
+        HashAlgorithm::Crc => {
+            let mut digest = Crc32::new();
+            try_with!(
+                io::copy(reader, &mut progress_bar.wrap_write(&mut digest)),
+                "Failed to copy data"
+            );
+            format!("{:08x}", digest.finalize()).to_lowercase()
+        }
+        HashAlgorithm::Md5 => {
+            let mut digest = Md5::new();
+            try_with!(
+                io::copy(reader, &mut progress_bar.wrap_write(&mut digest)),
+                "Failed to copy data"
+            );
+            format!("{:032x}", digest.finalize()).to_lowercase()
+        }
+        HashAlgorithm::Sha1 => {
+            let mut digest = Sha1::new();
+            try_with!(
+                io::copy(reader, &mut progress_bar.wrap_write(&mut digest)),
+                "Failed to copy data"
+            );
+            format!("{:040x}", digest.finalize()).to_lowercase()
+        }
+        HashAlgorithm::Sha256 => {
+            let mut digest = Sha256::new();
+            try_with!(
+                io::copy(reader, &mut progress_bar.wrap_write(&mut digest)),
+                "Failed to copy data"
+            );
+            format!("{:064x}", digest.finalize()).to_lowercase()
+        }
+    };
+    Ok(hash)
+}
+
+// single pass for callers needing every DAT hash of the same file
+pub fn compute_crc_md5_sha1<R: Read>(
+    reader: &mut R,
+    progress_bar: &ProgressBar,
+) -> SimpleResult<(String, String, String)> {
+    let mut crc_digest = Crc32::new();
+    let mut md5_digest = Md5::new();
+    let mut sha1_digest = Sha1::new();
+    let mut buffer = vec![0u8; 1024 * 1024];
+    loop {
+        let length = try_with!(reader.read(&mut buffer), "Failed to read data");
+        if length == 0 {
+            break;
+        }
+        try_with!(
+            crc_digest.write_all(&buffer[..length]),
+            "Failed to copy data"
+        );
+        try_with!(
+            md5_digest.write_all(&buffer[..length]),
+            "Failed to copy data"
+        );
+        try_with!(
+            sha1_digest.write_all(&buffer[..length]),
+            "Failed to copy data"
+        );
+        progress_bar.inc(length as u64);
+    }
+    Ok((
+        format!("{:08x}", crc_digest.finalize()).to_lowercase(),
+        format!("{:032x}", md5_digest.finalize()).to_lowercase(),
+        format!("{:040x}", sha1_digest.finalize()).to_lowercase(),
+    ))
+}
+
+// files are hashed concurrently, results keep the order of the given romfiles
+pub fn compute_hashes_and_sizes(
+    progress_bar: &ProgressBar,
+    romfiles: &[CommonRomfile],
+    hash_algorithm: &HashAlgorithm,
+) -> SimpleResult<Vec<(String, u64)>> {
+    let mut sizes: Vec<u64> = vec![];
+    for romfile in romfiles {
+        sizes.push(
+            try_with!(
+                romfile.path.metadata(),
+                "Failed to read \"{}\" metadata",
+                romfile.path.as_os_str().to_str().unwrap()
+            )
+            .len(),
+        );
+    }
+
+    progress_bar.reset();
+    progress_bar.set_message(format!(
+        "Computing {} (1-{}/{})",
+        hash_algorithm,
+        romfiles.len(),
+        romfiles.len()
+    ));
+    progress_bar.set_style(get_bytes_progress_style());
+    progress_bar.set_length(sizes.iter().sum());
+
+    // the global pool is sized by PARALLELISM and niced on startup
+    let hashes_and_sizes = romfiles
+        .par_iter()
+        .zip(sizes)
+        .map(|(romfile, size)| {
+            let mut file = open_file_sync(&romfile.path)?;
+            let hash = compute_hash(&mut file, progress_bar, hash_algorithm)?;
+            Ok((hash, size))
+        })
+        .collect::<SimpleResult<Vec<(String, u64)>>>();
+
+    progress_bar.set_message("");
+    progress_bar.set_style(get_none_progress_style());
+
+    hashes_and_sizes
+}
+
+pub fn get_rom_hash<'a>(rom: &'a Rom, hash_algorithm: &HashAlgorithm) -> Option<&'a String> {
+    match hash_algorithm {
+        HashAlgorithm::Crc => rom.crc.as_ref(),
+        HashAlgorithm::Md5 => rom.md5.as_ref(),
+        HashAlgorithm::Sha1 => rom.sha1.as_ref(),
+        HashAlgorithm::Sha256 => rom.sha256.as_ref(),
+    }
+}
+
+pub fn compute_partial_hash<P: AsRef<Path>>(path: &P, window_size: u64) -> SimpleResult<String> {
+    let mut file = open_file_sync(path)?;
+    let size = try_with!(
+        file.metadata(),
+        "Failed to read \"{}\" metadata",
+        path.as_ref().as_os_str().to_str().unwrap()
+    )
+    .len();
+    let mut digest = Crc32::new();
+    // files that fit in both windows are hashed whole
+    if size <= window_size * 2 {
+        try_with!(io::copy(&mut file, &mut digest), "Failed to copy data");
+    } else {
+        try_with!(
+            io::copy(&mut (&mut file).take(window_size), &mut digest),
+            "Failed to copy data"
+        );
+        try_with!(
+            file.seek(io::SeekFrom::End(-(window_size as i64))),
+            "Failed to seek \"{}\"",
+            path.as_ref().as_os_str().to_str().unwrap()
+        );
+        try_with!(io::copy(&mut file, &mut digest), "Failed to copy data");
+    }
+    Ok(format!("{:08x}", digest.finalize()).to_lowercase())
+}
+
 pub trait HashAndSize {
     async fn get_hash_and_size(
         &self,
@@ -153,32 +314,7 @@ impl HashAndSize for CommonRomfile {
         ));
 
         let mut file = open_file_sync(&self.path)?;
-        let hash = match hash_algorithm {
-            HashAlgorithm::Crc => {
-                let mut digest = Crc32::new();
-                try_with!(
-                    io::copy(&mut file, &mut progress_bar.wrap_write(&mut digest)),
-                    "Failed to copy data"
-                );
-                format!("{:08x}", digest.finalize()).to_lowercase()
-            }
-            HashAlgorithm::Md5 => {
-                let mut digest = Md5::new();
-                try_with!(
-                    io::copy(&mut file, &mut progress_bar.wrap_write(&mut digest)),
-                    "Failed to copy data"
-                );
-                format!("{:032x}", digest.finalize()).to_lowercase()
-            }
-            HashAlgorithm::Sha1 => {
-                let mut digest = Sha1::new();
-                try_with!(
-                    io::copy(&mut file, &mut progress_bar.wrap_write(&mut digest)),
-                    "Failed to copy data"
-                );
-                format!("{:040x}", digest.finalize()).to_lowercase()
-            }
-        };
+        let hash = compute_hash(&mut file, progress_bar, hash_algorithm)?;
         let size = self.get_size(connection, progress_bar).await?;
 
         progress_bar.set_message("");
@@ -286,32 +422,7 @@ impl HeaderedHashAndSize for CommonRomfile {
             .get_file_and_header_size(connection, progress_bar, header)
             .await?
             .0;
-        let hash = match hash_algorithm {
-            HashAlgorithm::Crc => {
-                let mut digest = Crc32::new();
-                try_with!(
-                    io::copy(&mut file, &mut progress_bar.wrap_write(&mut digest)),
-                    "Failed to copy data"
-                );
-                format!("{:08x}", digest.finalize()).to_lowercase()
-            }
-            HashAlgorithm::Md5 => {
-                let mut digest = Md5::new();
-                try_with!(
-                    io::copy(&mut file, &mut progress_bar.wrap_write(&mut digest)),
-                    "Failed to copy data"
-                );
-                format!("{:032x}", digest.finalize()).to_lowercase()
-            }
-            HashAlgorithm::Sha1 => {
-                let mut digest = Sha1::new();
-                try_with!(
-                    io::copy(&mut file, &mut progress_bar.wrap_write(&mut digest)),
-                    "Failed to copy data"
-                );
-                format!("{:040x}", digest.finalize()).to_lowercase()
-            }
-        };
+        let hash = compute_hash(&mut file, progress_bar, hash_algorithm)?;
 
         progress_bar.set_message("");
         progress_bar.set_style(get_none_progress_style());
@@ -340,16 +451,16 @@ impl Check for CommonRomfile {
     ) -> SimpleResult<()> {
         progress_bar.println(format!("Checking \"{}\"", self));
         let rom = roms[0];
-        let hash_algorithm: HashAlgorithm;
-        if rom.crc.is_some() {
-            hash_algorithm = HashAlgorithm::Crc;
-        } else if rom.md5.is_some() {
-            hash_algorithm = HashAlgorithm::Md5;
-        } else if rom.sha1.is_some() {
-            hash_algorithm = HashAlgorithm::Sha1;
-        } else {
-            bail!("Not possible")
-        }
+        // use the first configured hash the DAT provides, or any it provides
+        let hash_algorithm = match get_hash_algorithms(connection)
+            .await
+            .into_iter()
+            .chain(HashAlgorithm::iter())
+            .find(|hash_algorithm| get_rom_hash(rom, hash_algorithm).is_some())
+        {
+            Some(hash_algorithm) => hash_algorithm,
+            None => bail!("Not possible"),
+        };
         let (hash, size) = match header {
             Some(header) => {
                 self.get_headered_hash_and_size(
@@ -369,28 +480,41 @@ impl Check for CommonRomfile {
                 (hash, size)
             }
         };
-        if rom.size > 0 && size != rom.size as u64 {
-            bail!("Size mismatch");
-        };
-        match hash_algorithm {
-            HashAlgorithm::Crc => {
-                if &hash != rom.crc.as_ref().unwrap() {
-                    bail!("Checksum mismatch");
-                }
-            }
-            HashAlgorithm::Md5 => {
-                if &hash != rom.md5.as_ref().unwrap() {
-                    bail!("Checksum mismatch");
-                }
-            }
-            HashAlgorithm::Sha1 => {
-                if &hash != rom.sha1.as_ref().unwrap() {
-                    bail!("Checksum mismatch");
+        let result = check_hash_and_size(rom, &hash_algorithm, &hash, size);
+        // headered dumps may have been matched on import by their whole file hash
+        if result.is_err() && header.is_some() {
+            let file_size = try_with!(
+                self.path.metadata(),
+                "Failed to read \"{}\" metadata",
+                self.path.as_os_str().to_str().unwrap()
+            )
+            .len();
+            if file_size != size {
+                let (hash, size) = self
+                    .get_hash_and_size(connection, progress_bar, 1, 1, &hash_algorithm)
+                    .await?;
+                if check_hash_and_size(rom, &hash_algorithm, &hash, size).is_ok() {
+                    return Ok(());
                 }
             }
         }
-        Ok(())
+        result
+    }
+}
+
+fn check_hash_and_size(
+    rom: &Rom,
+    hash_algorithm: &HashAlgorithm,
+    hash: &str,
+    size: u64,
+) -> SimpleResult<()> {
+    if rom.size > 0 && size != rom.size as u64 {
+        bail!("Size mismatch");
+    };
+    if get_rom_hash(rom, hash_algorithm).map(|rom_hash| rom_hash.as_str()) != Some(hash) {
+        bail!("Checksum mismatch");
     }
+    Ok(())
 }
 
 pub struct IsoRomfile {
@@ -4,6 +4,8 @@ use super::chdman::{
 };
 use super::database::*;
 use super::dolphin::{RvzCompressionAlgorithm, RVZ_BLOCK_SIZE_RANGE, RVZ_COMPRESSION_LEVEL_RANGE};
+use super::identify::{get_identify_placeholder, IDENTIFY_INTERVAL_RANGE};
+use super::purge_roms::{TRASH_MAX_ITEMS_RANGE, TRASH_RETENTION_DAYS_RANGE};
 use super::sevenzip::{SEVENZIP_COMPRESSION_LEVEL_RANGE, ZIP_COMPRESSION_LEVEL_RANGE};
 use super::util::*;
 use super::SimpleResult;
@@ -14,12 +16,13 @@ use phf::phf_map;
 use sqlx::sqlite::SqliteConnection;
 use std::path::{Path, PathBuf};
 use std::str::FromStr;
-use strum::{Display, EnumIter, EnumString, VariantNames};
+use strum::{Display, EnumIter, EnumString, IntoEnumIterator, VariantNames};
 
 cfg_if! {
     if #[cfg(test)] {
         use tokio::sync::Mutex;
 
+        static mut DAT_DIRECTORY: Option<PathBuf> = None;
         static mut ROM_DIRECTORY: Option<PathBuf> = None;
         static mut TMP_DIRECTORY: Option<PathBuf> = None;
 
@@ -30,6 +33,7 @@ cfg_if! {
         use async_once_cell::OnceCell;
         use std::env;
 
+        static DAT_DIRECTORY: OnceCell<PathBuf> = OnceCell::new();
         static ROM_DIRECTORY: OnceCell<PathBuf> = OnceCell::new();
         static TMP_DIRECTORY: OnceCell<PathBuf> = OnceCell::new();
     }
@@ -41,6 +45,7 @@ pub enum HashAlgorithm {
     Crc,
     Md5,
     Sha1,
+    Sha256,
 }
 
 #[derive(PartialEq, EnumString, VariantNames)]
@@ -66,68 +71,133 @@ pub enum PreferredRegion {
     Narrow,
 }
 
+#[derive(PartialEq, EnumString, VariantNames)]
+#[strum(serialize_all = "lowercase")]
+pub enum SystemMatch {
+    Exact,
+    Prefix,
+    Like,
+}
+
+#[derive(PartialEq, EnumString, VariantNames)]
+#[strum(serialize_all = "kebab-case")]
+pub enum StorageMode {
+    Named,
+    ContentAddressed,
+}
+
+#[derive(PartialEq, EnumString, VariantNames)]
+#[strum(serialize_all = "kebab-case")]
+pub enum MultidiscArchive {
+    PerDisc,
+    PerGame,
+}
+
 const BOOLEANS: &[&str] = &[
     "CHD_PARENTS",
+    "CHECK_ENTROPY",
+    "CHECK_SECTOR_ALIGNMENT",
+    "CHECK_WII_PARTITIONS",
+    "CLEAN_EMPTY_DIRS",
+    "CONVERT_WRITE_MANIFEST",
+    "DAT_STORE_COMPRESSED",
+    "GOODTOOLS_FLAGS",
     "GROUP_SUBSYSTEMS",
     "PREFER_PARENTS",
     "REGIONS_ONE_STRICT",
     "RVZ_SCRUB",
     "SEVENZIP_SOLID_COMPRESSION",
+    "TRACK_UNMATCHED",
+    "UPDATE_PLAYLISTS",
 ];
 const CHOICES: phf::Map<&str, &[&str]> = phf_map! {
+    "MULTIDISC_ARCHIVE" => MultidiscArchive::VARIANTS,
     "PREFER_REGIONS" => PreferredRegion::VARIANTS,
     "PREFER_VERSIONS" => PreferredVersion::VARIANTS,
     "REGIONS_ALL_SUBFOLDERS" => SubfolderScheme::VARIANTS,
     "REGIONS_ONE_SUBFOLDERS" => SubfolderScheme::VARIANTS,
     "RVZ_COMPRESSION_ALGORITHM" => RvzCompressionAlgorithm::VARIANTS,
+    "STORAGE_MODE" => StorageMode::VARIANTS,
+    "SYSTEM_MATCH" => SystemMatch::VARIANTS,
 };
 const CHOICE_LISTS: phf::Map<&str, &[&str]> = phf_map! {
     "CHD_CD_COMPRESSION_ALGORITHMS" => ChdCdCompressionAlgorithm::VARIANTS,
     "CHD_DVD_COMPRESSION_ALGORITHMS" => ChdDvdCompressionAlgorithm::VARIANTS,
     "CHD_HD_COMPRESSION_ALGORITHMS" => ChdHdCompressionAlgorithm::VARIANTS,
     "CHD_LD_COMPRESSION_ALGORITHMS" => ChdLdCompressionAlgorithm::VARIANTS,
+    "HASH_ALGORITHMS" => HashAlgorithm::VARIANTS,
 };
 const INTEGERS: phf::Map<&str, &[usize; 2]> = phf_map! {
     "CHD_CD_HUNK_SIZE" => &CHD_HUNK_SIZE_RANGE,
     "CHD_DVD_HUNK_SIZE" => &CHD_HUNK_SIZE_RANGE,
     "CHD_HD_HUNK_SIZE" => &CHD_HUNK_SIZE_RANGE,
     "CHD_LD_HUNK_SIZE" => &CHD_HUNK_SIZE_RANGE,
+    "IDENTIFY_INTERVAL" => &IDENTIFY_INTERVAL_RANGE,
+    "MAX_TOOL_PROCESSES" => &MAX_TOOL_PROCESSES_RANGE,
+    "PARALLELISM" => &PARALLELISM_RANGE,
     "RVZ_BLOCK_SIZE" => &RVZ_BLOCK_SIZE_RANGE,
     "RVZ_COMPRESSION_LEVEL" => &RVZ_COMPRESSION_LEVEL_RANGE,
     "SEVENZIP_COMPRESSION_LEVEL" => &SEVENZIP_COMPRESSION_LEVEL_RANGE,
+    "TOOL_NICENESS" => &TOOL_NICENESS_RANGE,
+    "TOOL_RETRIES" => &TOOL_RETRIES_RANGE,
+    "TRASH_MAX_ITEMS" => &TRASH_MAX_ITEMS_RANGE,
+    "TRASH_RETENTION_DAYS" => &TRASH_RETENTION_DAYS_RANGE,
     "ZIP_COMPRESSION_LEVEL" => &ZIP_COMPRESSION_LEVEL_RANGE,
 };
 const LISTS: &[&str] = &[
+    "ALT_NAME_REGIONS",
+    "ARCADE_SYSTEMS",
+    "CHDMAN_PATHS",
     "DISCARD_FLAGS",
     "DISCARD_RELEASES",
+    "EXPORT_UPPERCASE_EXTENSIONS",
     "LANGUAGES",
+    "MAXCSO_PATHS",
+    "NON_ARCADE_SYSTEMS",
     "PREFER_FLAGS",
     "REGIONS_ALL",
     "REGIONS_ONE",
+    "SYSTEM_FORMATS",
 ];
-const PATHS: &[&str] = &["ROM_DIRECTORY", "TMP_DIRECTORY"];
+const PATHS: &[&str] = &["EXPORT_DIRECTORY", "ROM_DIRECTORY", "TMP_DIRECTORY"];
+const TEMPLATES: &[&str] = &["CUE_BIN_NAME_TEMPLATE"];
+const URLS: &[&str] = &["IDENTIFY_URL"];
 
 const NULLABLES: &[&str] = &[
+    "ALT_NAME_REGIONS",
+    "ARCADE_SYSTEMS",
     "CHD_CD_HUNK_SIZE",
     "CHD_CD_COMPRESSION_ALGORITHMS",
     "CHD_DVD_HUNK_SIZE",
     "CHD_DVD_COMPRESSION_ALGORITHMS",
+    "CHDMAN_PATHS",
     "DISCARD_FLAGS",
     "DISCARD_RELEASES",
+    "EXPORT_DIRECTORY",
+    "EXPORT_UPPERCASE_EXTENSIONS",
+    "HASH_ALGORITHMS",
+    "IDENTIFY_URL",
     "LANGUAGES",
+    "MAXCSO_PATHS",
+    "NON_ARCADE_SYSTEMS",
     "PREFER_FLAGS",
     "REGIONS_ALL",
     "REGIONS_ONE",
     "SEVENZIP_COMPRESSION_LEVEL",
+    "SYSTEM_FORMATS",
+    "TRASH_MAX_ITEMS",
+    "TRASH_RETENTION_DAYS",
     "ZIP_COMPRESSION_LEVEL",
 ];
 
 const SORTED_LISTS: &[&str] = &[
     "CHD_CD_COMPRESSION_ALGORITHMS",
     "CHD_DVD_COMPRESSION_ALGORITHMS",
+    "HASH_ALGORITHMS",
     "REGIONS_ONE",
 ];
 const LIST_SEPARATOR: &str = "|";
+const SYSTEM_VALUE_SEPARATOR: char = '=';
 
 pub static BIN_EXTENSION: &str = "bin";
 pub static CIA_EXTENSION: &str = "cia";
@@ -141,7 +211,9 @@ pub static PUP_EXTENSION: &str = "pup";
 pub static RAP_EXTENSION: &str = "rap";
 pub static SEVENZIP_EXTENSION: &str = "7z";
 pub static WBFS_EXTENSION: &str = "wbfs";
+pub static Z64_EXTENSION: &str = "z64";
 pub static ZIP_EXTENSION: &str = "zip";
+pub static ZST_EXTENSION: &str = "zst";
 
 pub static ARCHIVE_EXTENSIONS: [&str; 2] = [SEVENZIP_EXTENSION, ZIP_EXTENSION];
 pub static PS3_EXTENSIONS: [&str; 3] = [PKG_EXTENSION, PUP_EXTENSION, RAP_EXTENSION];
@@ -292,6 +364,28 @@ async fn set_setting(
         } else {
             println!("Valid range: {:?}", INTEGERS.get(key).unwrap());
         }
+    } else if TEMPLATES.contains(&key) {
+        if value.contains("{game}") {
+            set_string(connection, key, value).await;
+        } else {
+            println!("Templates must contain {{game}}");
+        }
+    } else if URLS.contains(&key) {
+        let placeholders: Vec<String> = HashAlgorithm::iter()
+            .map(|hash_algorithm| get_identify_placeholder(&hash_algorithm))
+            .collect();
+        if (value.starts_with("http://") || value.starts_with("https://"))
+            && placeholders
+                .iter()
+                .any(|placeholder| value.contains(placeholder))
+        {
+            set_string(connection, key, value).await;
+        } else {
+            println!(
+                "URLs must start with http:// or https:// and contain one of {:?}",
+                placeholders
+            );
+        }
     } else if LISTS.contains(&key) {
         println!("Lists can't be set directly, please use ADD or REMOVE instead");
     } else {
@@ -357,6 +451,33 @@ pub async fn get_list(connection: &mut SqliteConnection, key: &str) -> Vec<Strin
     }
 }
 
+pub async fn get_hash_algorithms(connection: &mut SqliteConnection) -> Vec<HashAlgorithm> {
+    let hash_algorithms: Vec<HashAlgorithm> = get_list(connection, "HASH_ALGORITHMS")
+        .await
+        .iter()
+        .filter_map(|hash_algorithm| HashAlgorithm::from_str(hash_algorithm).ok())
+        .collect();
+    if hash_algorithms.is_empty() {
+        HashAlgorithm::iter().collect()
+    } else {
+        hash_algorithms
+    }
+}
+
+pub async fn get_system_value(
+    connection: &mut SqliteConnection,
+    key: &str,
+    system_name: &str,
+) -> Option<String> {
+    get_list(connection, key)
+        .await
+        .into_iter()
+        .find_map(|entry| match entry.split_once(SYSTEM_VALUE_SEPARATOR) {
+            Some((name, value)) if name == system_name => Some(value.to_owned()),
+            _ => None,
+        })
+}
+
 pub async fn add_to_list(connection: &mut SqliteConnection, key: &str, value: &str) {
     if LISTS.contains(&key) {
         let mut list = get_list(connection, key).await;
@@ -417,8 +538,11 @@ async fn set_list(connection: &mut SqliteConnection, key: &str, value: &[String]
 }
 
 pub async fn get_directory(connection: &mut SqliteConnection, key: &str) -> Option<PathBuf> {
-    match find_setting_by_key(connection, key).await {
-        Some(p) => match get_canonicalized_path(&p.value.unwrap()).await {
+    match find_setting_by_key(connection, key)
+        .await
+        .and_then(|setting| setting.value)
+    {
+        Some(p) => match get_canonicalized_path(&p).await {
             Ok(path) => Some(path),
             Err(_) => None,
         },
@@ -478,6 +602,19 @@ cfg_if::cfg_if! {
                 TMP_DIRECTORY.as_ref().unwrap()
             }
         }
+
+        pub async fn get_dat_directory(_: &mut SqliteConnection) -> &'static PathBuf {
+            unsafe {
+                DAT_DIRECTORY.as_ref().unwrap()
+            }
+        }
+
+        pub fn set_dat_directory(dat_directory: PathBuf) -> &'static PathBuf {
+            unsafe {
+                DAT_DIRECTORY.replace(dat_directory);
+                DAT_DIRECTORY.as_ref().unwrap()
+            }
+        }
     } else {
         async fn init_rom_directory(connection: &mut SqliteConnection) -> PathBuf {
             match get_directory(connection, "ROM_DIRECTORY").await {
@@ -514,6 +651,18 @@ cfg_if::cfg_if! {
         pub async fn get_tmp_directory(connection: &mut SqliteConnection) -> &'static PathBuf {
             TMP_DIRECTORY.get_or_init(init_tmp_directory(connection)).await
         }
+
+        async fn init_dat_directory() -> PathBuf {
+            let data_directory = match env::var("OXYROMON_DATA_DIRECTORY") {
+                Ok(data_directory) => PathBuf::from(data_directory),
+                Err(_) => dirs::data_dir().map(PathBuf::from).unwrap().join("oxyromon")
+            };
+            data_directory.join("dats")
+        }
+
+        pub async fn get_dat_directory(_: &mut SqliteConnection) -> &'static PathBuf {
+            DAT_DIRECTORY.get_or_init(init_dat_directory()).await
+        }
     }
 }
 
@@ -1,34 +1,44 @@
 use super::chdman;
 use super::chdman::{AsChd, AsRdsk, AsRiff, ChdType, ToChd, ToRdsk, ToRiff};
+use super::check_roms::check_romfile;
 use super::common::*;
 use super::config::*;
 use super::database::*;
 use super::dolphin;
 use super::dolphin::{AsRvz, RvzCompressionAlgorithm, ToRvz};
+use super::export_roms;
+use super::generate_playlists::{add_sibling_discs, get_disc_archive_name, update_playlists};
+use super::import_cuesheets::write_cuesheet;
 use super::maxcso;
 use super::maxcso::{AsXso, ToXso, XsoType};
 use super::mimetype::*;
 use super::model::*;
 use super::nsz;
 use super::nsz::{AsNsp, AsNsz, ToNsp, ToNsz};
+use super::progress::*;
 use super::prompt::*;
 use super::sevenzip;
 use super::sevenzip::{ArchiveFile, ArchiveRomfile, AsArchive, ToArchive};
 use super::util::*;
 use super::SimpleResult;
 use clap::builder::PossibleValuesParser;
-use clap::{Arg, ArgAction, ArgMatches, Command};
+use clap::{value_parser, Arg, ArgAction, ArgMatches, Command};
 use indexmap::map::IndexMap;
 use indicatif::{HumanBytes, ProgressBar};
+use itertools::Itertools;
 use rayon::prelude::*;
 use sqlx::sqlite::SqliteConnection;
 use std::cmp::Ordering;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::mem::drop;
 use std::str::FromStr;
+use walkdir::WalkDir;
 
-const ALL_FORMATS: &[&str] = &["ORIGINAL", "7Z", "CHD", "CSO", "NSZ", "RVZ", "ZIP", "ZSO"];
+pub const ALL_FORMATS: &[&str] = &["ORIGINAL", "7Z", "CHD", "CSO", "NSZ", "RVZ", "ZIP", "ZSO"];
 const ARCADE_FORMATS: &[&str] = &["ORIGINAL", "ZIP"];
+const ESTIMATE_SAMPLE_SIZE: usize = 5;
+
+type Partition = IndexMap<i64, Vec<Rom>>;
 
 pub fn subcommand() -> Command {
     Command::new("convert-roms")
@@ -50,6 +60,13 @@ pub fn subcommand() -> Command {
                 .required(false)
                 .action(ArgAction::Append),
         )
+        .arg(
+            Arg::new("ROM")
+                .long("rom")
+                .help("Select ROMs by name within the selected games")
+                .required(false)
+                .action(ArgAction::Append),
+        )
         .arg(
             Arg::new("SYSTEM")
                 .short('s')
@@ -58,6 +75,13 @@ pub fn subcommand() -> Command {
                 .required(false)
                 .action(ArgAction::Append),
         )
+        .arg(
+            Arg::new("EXACT_SYSTEM")
+                .long("exact-system")
+                .help("Match system names exactly")
+                .required(false)
+                .action(ArgAction::SetTrue),
+        )
         .arg(
             Arg::new("ALL")
                 .short('a')
@@ -74,6 +98,14 @@ pub fn subcommand() -> Command {
                 .required(false)
                 .action(ArgAction::SetTrue),
         )
+        .arg(
+            Arg::new("PRESERVE_METHOD")
+                .long("preserve-method")
+                .help("Keep the source archive's compression method when recompressing archives")
+                .required(false)
+                .action(ArgAction::SetTrue)
+                .requires("RECOMPRESS"),
+        )
         .arg(
             Arg::new("DIFF")
                 .short('d')
@@ -90,6 +122,13 @@ pub fn subcommand() -> Command {
                 .required(false)
                 .action(ArgAction::SetTrue),
         )
+        .arg(
+            Arg::new("STRICT")
+                .long("strict")
+                .help("Abort on games whose ROM files span multiple archives")
+                .required(false)
+                .action(ArgAction::SetTrue),
+        )
         .arg(
             Arg::new("PARENTS")
                 .short('p')
@@ -98,18 +137,88 @@ pub fn subcommand() -> Command {
                 .required(false)
                 .action(ArgAction::SetTrue),
         )
+        .arg(
+            Arg::new("ONLY_MISSING")
+                .short('m')
+                .long("only-missing")
+                .help("Only convert games not already in the selected format")
+                .required(false)
+                .action(ArgAction::SetTrue)
+                .conflicts_with("RECOMPRESS"),
+        )
+        .arg(
+            Arg::new("ESTIMATE")
+                .short('e')
+                .long("estimate")
+                .help("Estimate the converted size using a sample of games")
+                .required(false)
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("CLEAN_EMPTY_DIRS")
+                .long("clean-empty-dirs")
+                .help("Remove empty directories afterwards")
+                .required(false)
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("UPDATE_PLAYLISTS")
+                .long("update-playlists")
+                .help("Regenerate affected playlists afterwards")
+                .required(false)
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("MAX_SIZE")
+                .long("max-size")
+                .help("Skip games larger than the given size in bytes")
+                .required(false)
+                .num_args(1)
+                .value_parser(value_parser!(u64)),
+        )
+        .arg(
+            Arg::new("LIST")
+                .short('l')
+                .long("list")
+                .help("Only list the games that would be converted")
+                .required(false)
+                .action(ArgAction::SetTrue)
+                .conflicts_with("ESTIMATE"),
+        )
+        .arg(
+            Arg::new("DRY_RUN")
+                .long("dry-run")
+                .help("Print the planned conversions without converting anything")
+                .required(false)
+                .action(ArgAction::SetTrue)
+                .conflicts_with_all(["ESTIMATE", "LIST"]),
+        )
+        .arg(
+            Arg::new("VERIFY_SOURCE")
+                .long("verify-source")
+                .help("Check ROM files before conversion and skip corrupt games")
+                .required(false)
+                .action(ArgAction::SetTrue),
+        )
 }
 
 pub async fn main(
     connection: &mut SqliteConnection,
     matches: &ArgMatches,
     progress_bar: &ProgressBar,
-) -> SimpleResult<()> {
+) -> SimpleResult<Summary> {
     let systems = match matches.get_many::<String>("SYSTEM") {
         Some(system_names) => {
             let mut systems: Vec<System> = vec![];
             for system_name in system_names {
-                systems.append(&mut find_systems_by_name_like(connection, system_name).await);
+                systems.append(
+                    &mut find_systems_by_name_match(
+                        connection,
+                        system_name,
+                        matches.get_flag("EXACT_SYSTEM"),
+                    )
+                    .await,
+                );
             }
             systems.dedup_by_key(|system| system.id);
             systems
@@ -123,297 +232,959 @@ pub async fn main(
             .map(|&s| s.to_owned())
             .unwrap(),
     };
-    let recompress = matches.get_flag("RECOMPRESS");
-    let diff = matches.get_flag("DIFF");
-    let check = matches.get_flag("CHECK");
+    let mut summary = Summary::new();
+
+    if format == "CHD" && matches.contains_id("ROM") {
+        bail!("CHD conversion requires whole games, selecting ROMs is not supported");
+    }
 
-    match format.as_str() {
+    let regenerate_playlists = !matches.get_flag("ESTIMATE")
+        && !matches.get_flag("LIST")
+        && !matches.get_flag("DRY_RUN")
+        && (matches.get_flag("UPDATE_PLAYLISTS") || get_bool(connection, "UPDATE_PLAYLISTS").await);
+
+    for mut system in systems {
+        progress_bar.println(format!("Processing \"{}\"", system.name));
+        system.arcade = is_arcade(connection, &system).await;
+
+        // tools are checked with the binaries overridden for this system
+        let tool_paths = get_system_tool_paths(connection, &system.name).await;
+        if !with_tool_paths(
+            tool_paths.clone(),
+            check_format(progress_bar, &system, &format),
+        )
+        .await?
+        {
+            continue;
+        }
+
+        let games = match matches.get_many::<String>("GAME") {
+            Some(game_names) => {
+                let mut games: Vec<Game> = vec![];
+                for game_name in game_names {
+                    games.append(
+                        &mut find_complete_games_by_name_and_system_id(
+                            connection, game_name, system.id,
+                        )
+                        .await,
+                    );
+                }
+                games.dedup_by_key(|game| game.id);
+                let games = prompt_for_games(games, cfg!(test))?;
+                add_sibling_discs(connection, games, system.id).await
+            }
+            None => find_complete_games_by_system_id(connection, system.id).await,
+        };
+
+        if games.is_empty() {
+            if matches.index_of("GAME").is_some() {
+                progress_bar.println("No matching game");
+            }
+            continue;
+        }
+
+        let game_ids: Vec<i64> = games.iter().map(|game| game.id).collect();
+        with_tool_paths(
+            tool_paths,
+            convert_games(
+                connection,
+                matches,
+                progress_bar,
+                &system,
+                games,
+                &format,
+                &mut summary,
+            ),
+        )
+        .await?;
+
+        if regenerate_playlists {
+            update_playlists(connection, progress_bar, &system, &game_ids).await?;
+        }
+
+        progress_bar.println("");
+    }
+
+    if !matches.get_flag("ESTIMATE") && !matches.get_flag("LIST") && !matches.get_flag("DRY_RUN") {
+        if get_bool(connection, "CONVERT_WRITE_MANIFEST").await {
+            write_checksum_manifests(connection, progress_bar).await?;
+        }
+        if matches.get_flag("CLEAN_EMPTY_DIRS") || get_bool(connection, "CLEAN_EMPTY_DIRS").await {
+            remove_empty_directories(connection, progress_bar).await?;
+        }
+        summary.print(progress_bar);
+        print_tally(
+            progress_bar,
+            &format,
+            summary.converted,
+            summary.compliant,
+            summary.skipped,
+        );
+    }
+
+    Ok(summary)
+}
+
+pub async fn check_format(
+    progress_bar: &ProgressBar,
+    system: &System,
+    format: &str,
+) -> SimpleResult<bool> {
+    Ok(check_format_tool(progress_bar, format).await?
+        && check_system_format(progress_bar, system, format).await?)
+}
+
+async fn check_format_tool(progress_bar: &ProgressBar, format: &str) -> SimpleResult<bool> {
+    match format {
         "7Z" | "ZIP" => {
             if sevenzip::get_version().await.is_err() {
-                progress_bar.println("Please install sevenzip");
-                return Ok(());
+                print_missing_tool(progress_bar, "sevenzip");
+                return Ok(false);
             }
         }
         "CHD" => {
             if chdman::get_version().await.is_err() {
-                progress_bar.println("Please install chdman");
-                return Ok(());
+                print_missing_tool(progress_bar, "chdman");
+                return Ok(false);
             }
         }
         "CSO" => {
             if maxcso::get_version().await.is_err() {
-                progress_bar.println("Please install maxcso");
-                return Ok(());
+                print_missing_tool(progress_bar, "maxcso");
+                return Ok(false);
             }
         }
         "NSZ" => {
             if nsz::get_version().await.is_err() {
-                progress_bar.println("Please install nsz");
-                return Ok(());
+                print_missing_tool(progress_bar, "nsz");
+                return Ok(false);
             }
         }
         "RVZ" => {
             if dolphin::get_version().await.is_err() {
-                progress_bar.println("Please install dolphin-tool");
-                return Ok(());
+                print_missing_tool(progress_bar, "dolphin-tool");
+                return Ok(false);
             }
         }
         "ZSO" => {
             if maxcso::get_version().await.is_err() {
-                progress_bar.println("Please install maxcso");
-                return Ok(());
+                print_missing_tool(progress_bar, "maxcso");
+                return Ok(false);
             }
         }
         "ORIGINAL" => {}
         _ => bail!("Not supported"),
     }
+    Ok(true)
+}
 
-    for system in systems {
-        progress_bar.println(format!("Processing \"{}\"", system.name));
+async fn check_system_format(
+    progress_bar: &ProgressBar,
+    system: &System,
+    format: &str,
+) -> SimpleResult<bool> {
+    if format == "CHD"
+        && system.name.contains("Dreamcast")
+        && chdman::get_version()
+            .await?
+            .as_str()
+            .cmp(chdman::MIN_DREAMCAST_VERSION)
+            == Ordering::Less
+    {
+        progress_bar.println(format!(
+            "Older chdman versions have issues with Dreamcast games, please update to {} or newer",
+            chdman::MIN_DREAMCAST_VERSION
+        ));
+        return Ok(false);
+    }
 
-        if format == "CHD"
-            && system.name.contains("Dreamcast")
-            && chdman::get_version()
-                .await?
-                .as_str()
-                .cmp(chdman::MIN_DREAMCAST_VERSION)
-                == Ordering::Less
-        {
-            progress_bar.println(format!("Older chdman versions have issues with Dreamcast games, please update to {} or newer", chdman::MIN_DREAMCAST_VERSION));
-            continue;
-        }
+    if system.arcade && !ARCADE_FORMATS.contains(&format) {
+        progress_bar.println(format!(
+            "Only {:?} are supported for arcade systems",
+            ARCADE_FORMATS
+        ));
+        return Ok(false);
+    }
 
-        if system.arcade && !ARCADE_FORMATS.contains(&format.as_str()) {
-            progress_bar.println(format!(
-                "Only {:?} are supported for arcade systems",
-                ARCADE_FORMATS
-            ));
-            continue;
-        }
+    Ok(true)
+}
 
-        let games = match matches.get_many::<String>("GAME") {
-            Some(game_names) => {
-                let mut games: Vec<Game> = vec![];
-                for game_name in game_names {
-                    games.append(
-                        &mut find_complete_games_by_name_and_system_id(
-                            connection, game_name, system.id,
-                        )
-                        .await,
-                    );
-                }
-                games.dedup_by_key(|game| game.id);
-                prompt_for_games(games, cfg!(test))?
+pub async fn convert_games(
+    connection: &mut SqliteConnection,
+    matches: &ArgMatches,
+    progress_bar: &ProgressBar,
+    system: &System,
+    games: Vec<Game>,
+    format: &str,
+    summary: &mut Summary,
+) -> SimpleResult<()> {
+    let recompress = matches.get_flag("RECOMPRESS");
+    let preserve_method = matches.get_flag("PRESERVE_METHOD");
+    let diff = matches.get_flag("DIFF");
+    let check = matches.get_flag("CHECK");
+    let strict = matches.get_flag("STRICT");
+
+    let roms = find_roms_with_romfile_by_game_ids(
+        connection,
+        &games.par_iter().map(|game| game.id).collect::<Vec<i64>>(),
+    )
+    .await;
+    let romfiles = find_romfiles_by_ids(
+        connection,
+        roms.par_iter()
+            .map(|rom| rom.romfile_id.unwrap())
+            .collect::<Vec<i64>>()
+            .as_slice(),
+    )
+    .await;
+
+    let mut roms_by_game_id: IndexMap<i64, Vec<Rom>> = IndexMap::new();
+    roms.into_iter().for_each(|rom| {
+        let group = roms_by_game_id.entry(rom.game_id).or_default();
+        group.push(rom);
+    });
+    let games_by_id: HashMap<i64, Game> =
+        games.into_par_iter().map(|game| (game.id, game)).collect();
+    let mut romfiles_by_id: HashMap<i64, Romfile> = romfiles
+        .into_par_iter()
+        .map(|romfile| (romfile.id, romfile))
+        .collect();
+
+    if let Some(rom_names) = matches.get_many::<String>("ROM") {
+        let rom_names: Vec<&String> = rom_names.collect();
+        roms_by_game_id.retain(|game_id, roms| {
+            let roms_count = roms.len();
+            roms.retain(|rom| {
+                rom_names
+                    .iter()
+                    .any(|rom_name| rom.name.contains(rom_name.as_str()))
+            });
+            if roms.len() != roms_count
+                && roms.iter().any(|rom| {
+                    romfiles_by_id
+                        .get(&rom.romfile_id.unwrap())
+                        .unwrap()
+                        .path
+                        .ends_with(CHD_EXTENSION)
+                })
+            {
+                progress_bar.println(format!(
+                    "CHDs require whole games, skipping \"{}\"",
+                    games_by_id.get(game_id).unwrap().name
+                ));
+                return false;
             }
-            None => find_complete_games_by_system_id(connection, system.id).await,
-        };
+            !roms.is_empty()
+        });
+    }
 
-        if games.is_empty() {
-            if matches.index_of("GAME").is_some() {
-                progress_bar.println("No matching game");
+    if let Some(max_size) = matches.get_one::<u64>("MAX_SIZE") {
+        roms_by_game_id.retain(|game_id, roms| {
+            let size = roms.iter().map(|rom| rom.size as u64).sum::<u64>();
+            if size > *max_size {
+                progress_bar.println(format!(
+                    "Skipping \"{}\" ({} > {})",
+                    games_by_id.get(game_id).unwrap().name,
+                    HumanBytes(size),
+                    HumanBytes(*max_size)
+                ));
+                return false;
             }
-            continue;
-        }
+            true
+        });
+    }
 
-        let roms = find_roms_with_romfile_by_game_ids(
+    let mut trimmed_count = 0;
+    if matches.get_flag("ONLY_MISSING") {
+        let compliant_count = trim_compliant_games(format, &mut roms_by_game_id, &romfiles_by_id);
+        progress_bar.println(format!(
+            "Already in {}: {}, converting: {}",
+            format,
+            compliant_count,
+            roms_by_game_id.len()
+        ));
+        trimmed_count = compliant_count;
+        let romfile_ids: HashSet<i64> = roms_by_game_id
+            .values()
+            .flatten()
+            .map(|rom| rom.romfile_id.unwrap())
+            .collect();
+        romfiles_by_id.retain(|romfile_id, _| romfile_ids.contains(romfile_id));
+    }
+
+    // nothing below the plan is run, files and database are left untouched
+    if matches.get_flag("DRY_RUN") {
+        let roms_by_game_id = list_conversions(
+            progress_bar,
+            format,
+            recompress,
+            &games_by_id,
+            roms_by_game_id,
+            &romfiles_by_id,
+            true,
+        );
+        let romfile_ids: HashSet<i64> = roms_by_game_id
+            .values()
+            .flatten()
+            .map(|rom| rom.romfile_id.unwrap())
+            .collect();
+        summary.add(
+            romfile_ids.len(),
+            romfile_ids
+                .iter()
+                .map(|romfile_id| romfiles_by_id.get(romfile_id).unwrap().size as u64)
+                .sum(),
+            0,
+        );
+        return Ok(());
+    }
+
+    if matches.get_flag("LIST") {
+        list_conversions(
+            progress_bar,
+            format,
+            recompress,
+            &games_by_id,
+            roms_by_game_id,
+            &romfiles_by_id,
+            false,
+        );
+        return Ok(());
+    }
+
+    if matches.get_flag("ESTIMATE") {
+        estimate(
             connection,
-            &games.par_iter().map(|game| game.id).collect::<Vec<i64>>(),
+            progress_bar,
+            system,
+            format,
+            games_by_id,
+            roms_by_game_id,
+            romfiles_by_id,
         )
-        .await;
-        let romfiles = find_romfiles_by_ids(
+        .await?;
+        return Ok(());
+    }
+
+    let mut corrupt_count = 0;
+    if matches.get_flag("VERIFY_SOURCE") {
+        corrupt_count = trim_corrupt_games(
             connection,
-            roms.par_iter()
-                .map(|rom| rom.romfile_id.unwrap())
-                .collect::<Vec<i64>>()
-                .as_slice(),
+            progress_bar,
+            system,
+            &games_by_id,
+            &mut roms_by_game_id,
+            &romfiles_by_id,
         )
-        .await;
-
-        let mut roms_by_game_id: IndexMap<i64, Vec<Rom>> = IndexMap::new();
-        roms.into_iter().for_each(|rom| {
-            let group = roms_by_game_id.entry(rom.game_id).or_default();
-            group.push(rom);
-        });
-        let games_by_id: HashMap<i64, Game> =
-            games.into_par_iter().map(|game| (game.id, game)).collect();
-        let romfiles_by_id: HashMap<i64, Romfile> = romfiles
-            .into_par_iter()
-            .map(|romfile| (romfile.id, romfile))
+        .await?;
+        let romfile_ids: HashSet<i64> = roms_by_game_id
+            .values()
+            .flatten()
+            .map(|rom| rom.romfile_id.unwrap())
             .collect();
+        romfiles_by_id.retain(|romfile_id, _| romfile_ids.contains(romfile_id));
+    }
 
-        match format.as_str() {
-            "ORIGINAL" => {
-                to_original(
-                    connection,
-                    progress_bar,
-                    &system,
-                    roms_by_game_id,
-                    romfiles_by_id,
-                    check,
-                )
-                .await?
-            }
-            "7Z" => {
-                let compression_level = get_integer(connection, "SEVENZIP_COMPRESSION_LEVEL").await;
-                let solid = get_bool(connection, "SEVENZIP_SOLID_COMPRESSION").await;
-                to_archive(
-                    connection,
-                    progress_bar,
-                    &system,
-                    games_by_id,
-                    roms_by_game_id,
-                    romfiles_by_id,
-                    sevenzip::ArchiveType::Sevenzip,
-                    recompress,
-                    diff,
-                    check,
-                    &compression_level,
-                    solid,
-                )
-                .await?
-            }
-            "ZIP" => {
-                let compression_level = get_integer(connection, "ZIP_COMPRESSION_LEVEL").await;
-                to_archive(
-                    connection,
-                    progress_bar,
-                    &system,
-                    games_by_id,
-                    roms_by_game_id,
-                    romfiles_by_id,
-                    sevenzip::ArchiveType::Zip,
-                    recompress,
-                    diff,
-                    check,
-                    &compression_level,
-                    false,
-                )
-                .await?
-            }
-            "CHD" => {
-                let cd_compression_algorithms =
-                    get_list(connection, "CHD_CD_COMPRESSION_ALGORITHMS").await;
-                let cd_hunk_size = get_integer(connection, "CHD_CD_HUNK_SIZE").await;
-                let dvd_compression_algorithms =
-                    get_list(connection, "CHD_DVD_COMPRESSION_ALGORITHMS").await;
-                let dvd_hunk_size = get_integer(connection, "CHD_DVD_HUNK_SIZE").await;
-                let hd_compression_algorithms =
-                    get_list(connection, "CHD_HD_COMPRESSION_ALGORITHMS").await;
-                let hd_hunk_size = get_integer(connection, "CHD_HD_HUNK_SIZE").await;
-                let ld_compression_algorithms =
-                    get_list(connection, "CHD_LD_COMPRESSION_ALGORITHMS").await;
-                let ld_hunk_size = get_integer(connection, "CHD_LD_HUNK_SIZE").await;
-                let parents = get_bool(connection, "CHD_PARENTS").await;
-                let prompt_for_parents = matches.get_flag("PARENTS");
-                to_chd(
-                    connection,
-                    progress_bar,
-                    games_by_id,
-                    roms_by_game_id,
-                    romfiles_by_id,
-                    recompress,
-                    diff,
-                    check,
-                    &cd_compression_algorithms,
-                    &cd_hunk_size,
-                    &dvd_compression_algorithms,
-                    &dvd_hunk_size,
-                    &hd_compression_algorithms,
-                    &hd_hunk_size,
-                    &ld_compression_algorithms,
-                    &ld_hunk_size,
-                    parents,
-                    prompt_for_parents,
-                )
-                .await?
-            }
-            "CSO" => {
-                to_cso(
-                    connection,
-                    progress_bar,
-                    roms_by_game_id,
-                    romfiles_by_id,
-                    recompress,
-                    diff,
-                    check,
-                )
-                .await?
-            }
-            "NSZ" => {
-                to_nsz(
-                    connection,
-                    progress_bar,
-                    roms_by_game_id,
-                    romfiles_by_id,
-                    recompress,
-                    diff,
-                    check,
-                )
-                .await?
-            }
-            "RVZ" => {
-                let compression_algorithm = RvzCompressionAlgorithm::from_str(
-                    &get_string(connection, "RVZ_COMPRESSION_ALGORITHM")
-                        .await
-                        .unwrap(),
-                )
-                .unwrap();
-                let compression_level = get_integer(connection, "RVZ_COMPRESSION_LEVEL")
+    let game_ids: Vec<i64> = roms_by_game_id.keys().copied().collect();
+    // snapshot romfiles to record where converted games came from
+    let romfile_states_by_game_id: HashMap<i64, (bool, Vec<(i64, String, i64)>)> = roms_by_game_id
+        .iter()
+        .map(|(game_id, roms)| {
+            (
+                *game_id,
+                (
+                    is_compliant(format, roms, &romfiles_by_id),
+                    get_romfile_states(roms, &romfiles_by_id),
+                ),
+            )
+        })
+        .collect();
+    let romfiles_count = romfiles_by_id.len();
+    let romfiles_size: u64 = romfiles_by_id
+        .values()
+        .map(|romfile| romfile.size as u64)
+        .sum();
+
+    let converted_game_ids = match format {
+        "ORIGINAL" => {
+            to_original(
+                connection,
+                progress_bar,
+                system,
+                roms_by_game_id,
+                romfiles_by_id,
+                check,
+                strict,
+            )
+            .await?
+        }
+        "7Z" => {
+            let compression_level = get_integer(connection, "SEVENZIP_COMPRESSION_LEVEL").await;
+            let solid = get_bool(connection, "SEVENZIP_SOLID_COMPRESSION").await;
+            to_archive(
+                connection,
+                progress_bar,
+                system,
+                games_by_id,
+                roms_by_game_id,
+                romfiles_by_id,
+                sevenzip::ArchiveType::Sevenzip,
+                recompress,
+                diff,
+                check,
+                &compression_level,
+                solid,
+                preserve_method,
+            )
+            .await?
+        }
+        "ZIP" => {
+            let compression_level = get_integer(connection, "ZIP_COMPRESSION_LEVEL").await;
+            to_archive(
+                connection,
+                progress_bar,
+                system,
+                games_by_id,
+                roms_by_game_id,
+                romfiles_by_id,
+                sevenzip::ArchiveType::Zip,
+                recompress,
+                diff,
+                check,
+                &compression_level,
+                false,
+                preserve_method,
+            )
+            .await?
+        }
+        "CHD" => {
+            let cd_compression_algorithms =
+                get_list(connection, "CHD_CD_COMPRESSION_ALGORITHMS").await;
+            let cd_hunk_size = get_integer(connection, "CHD_CD_HUNK_SIZE").await;
+            let dvd_compression_algorithms =
+                get_list(connection, "CHD_DVD_COMPRESSION_ALGORITHMS").await;
+            let dvd_hunk_size = get_integer(connection, "CHD_DVD_HUNK_SIZE").await;
+            let hd_compression_algorithms =
+                get_list(connection, "CHD_HD_COMPRESSION_ALGORITHMS").await;
+            let hd_hunk_size = get_integer(connection, "CHD_HD_HUNK_SIZE").await;
+            let ld_compression_algorithms =
+                get_list(connection, "CHD_LD_COMPRESSION_ALGORITHMS").await;
+            let ld_hunk_size = get_integer(connection, "CHD_LD_HUNK_SIZE").await;
+            let parents = get_bool(connection, "CHD_PARENTS").await;
+            let prompt_for_parents = matches.get_flag("PARENTS");
+            to_chd(
+                connection,
+                progress_bar,
+                games_by_id,
+                roms_by_game_id,
+                romfiles_by_id,
+                recompress,
+                diff,
+                check,
+                &cd_compression_algorithms,
+                &cd_hunk_size,
+                &dvd_compression_algorithms,
+                &dvd_hunk_size,
+                &hd_compression_algorithms,
+                &hd_hunk_size,
+                &ld_compression_algorithms,
+                &ld_hunk_size,
+                parents,
+                prompt_for_parents,
+                strict,
+            )
+            .await?
+        }
+        "CSO" => {
+            to_cso(
+                connection,
+                progress_bar,
+                roms_by_game_id,
+                romfiles_by_id,
+                recompress,
+                diff,
+                check,
+            )
+            .await?
+        }
+        "NSZ" => {
+            to_nsz(
+                connection,
+                progress_bar,
+                roms_by_game_id,
+                romfiles_by_id,
+                recompress,
+                diff,
+                check,
+            )
+            .await?
+        }
+        "RVZ" => {
+            let compression_algorithm = RvzCompressionAlgorithm::from_str(
+                &get_string(connection, "RVZ_COMPRESSION_ALGORITHM")
                     .await
-                    .unwrap();
-                let block_size = get_integer(connection, "RVZ_BLOCK_SIZE").await.unwrap();
-                to_rvz(
-                    connection,
-                    progress_bar,
-                    roms_by_game_id,
-                    romfiles_by_id,
-                    recompress,
-                    diff,
-                    check,
-                    &compression_algorithm,
-                    compression_level,
-                    block_size,
-                )
-                .await?
+                    .unwrap(),
+            )
+            .unwrap();
+            let compression_level = get_integer(connection, "RVZ_COMPRESSION_LEVEL")
+                .await
+                .unwrap();
+            let block_size = get_integer(connection, "RVZ_BLOCK_SIZE").await.unwrap();
+            to_rvz(
+                connection,
+                progress_bar,
+                roms_by_game_id,
+                romfiles_by_id,
+                recompress,
+                diff,
+                check,
+                &compression_algorithm,
+                compression_level,
+                block_size,
+            )
+            .await?
+        }
+        "ZSO" => {
+            to_zso(
+                connection,
+                progress_bar,
+                roms_by_game_id,
+                romfiles_by_id,
+                recompress,
+                diff,
+                check,
+            )
+            .await?
+        }
+        _ => bail!("Not supported"),
+    };
+
+    // conversions relink roms to their new romfiles
+    compute_system_completion(connection, progress_bar, system).await;
+
+    let roms = find_roms_with_romfile_by_game_ids(connection, &game_ids).await;
+    let new_romfiles_by_id: HashMap<i64, Romfile> = find_romfiles_by_ids(
+        connection,
+        roms.par_iter()
+            .map(|rom| rom.romfile_id.unwrap())
+            .collect::<Vec<i64>>()
+            .as_slice(),
+    )
+    .await
+    .into_par_iter()
+    .map(|romfile| (romfile.id, romfile))
+    .collect();
+    let new_romfiles_size: u64 = new_romfiles_by_id
+        .values()
+        .map(|romfile| romfile.size as u64)
+        .sum();
+
+    let mut new_roms_by_game_id: HashMap<i64, Vec<Rom>> = HashMap::new();
+    roms.into_iter().for_each(|rom| {
+        let group = new_roms_by_game_id.entry(rom.game_id).or_default();
+        group.push(rom);
+    });
+    let mut converted_count = 0;
+    let mut compliant_count = trimmed_count;
+    let mut skipped_count = trimmed_count + corrupt_count;
+    for (game_id, (compliant, romfile_states)) in &romfile_states_by_game_id {
+        let new_romfile_states = new_roms_by_game_id
+            .get(game_id)
+            .map(|roms| get_romfile_states(roms, &new_romfiles_by_id))
+            .unwrap_or_default();
+        if converted_game_ids.contains(game_id) {
+            converted_count += 1;
+            create_event(
+                connection,
+                *game_id,
+                EventType::Convert,
+                Some(&join_romfile_paths(romfile_states)),
+                &join_romfile_paths(&new_romfile_states),
+            )
+            .await;
+        } else {
+            skipped_count += 1;
+            if *compliant {
+                compliant_count += 1;
             }
-            "ZSO" => {
-                to_zso(
-                    connection,
-                    progress_bar,
-                    roms_by_game_id,
-                    romfiles_by_id,
-                    recompress,
-                    diff,
-                    check,
-                )
-                .await?
+        }
+    }
+    print_tally(
+        progress_bar,
+        format,
+        converted_count,
+        compliant_count,
+        skipped_count,
+    );
+    summary.add_games(converted_count, compliant_count, skipped_count);
+    summary.add(romfiles_count, romfiles_size, new_romfiles_size);
+
+    Ok(())
+}
+
+fn sort_chds_by_parent_depth(
+    chds: &mut IndexMap<i64, Vec<Rom>>,
+    romfiles_by_id: &HashMap<i64, Romfile>,
+) {
+    // deeper CHDs come first so that whole chains are converted from the leaves up
+    let get_depth = |roms: &Vec<Rom>| {
+        roms.iter()
+            .map(|rom| get_parent_depth(rom.romfile_id.unwrap(), romfiles_by_id))
+            .max()
+            .unwrap_or(0)
+    };
+    chds.par_sort_by(|_, a, _, b| get_depth(b).cmp(&get_depth(a)));
+}
+
+fn get_parent_depth(romfile_id: i64, romfiles_by_id: &HashMap<i64, Romfile>) -> usize {
+    let mut depth = 0;
+    let mut parent_id = romfiles_by_id
+        .get(&romfile_id)
+        .and_then(|romfile| romfile.parent_id);
+    // bail out of malformed cyclic chains
+    while let Some(romfile_id) = parent_id {
+        depth += 1;
+        if depth > romfiles_by_id.len() {
+            break;
+        }
+        parent_id = romfiles_by_id
+            .get(&romfile_id)
+            .and_then(|romfile| romfile.parent_id);
+    }
+    depth
+}
+
+fn trim_compliant_games(
+    format: &str,
+    roms_by_game_id: &mut IndexMap<i64, Vec<Rom>>,
+    romfiles_by_id: &HashMap<i64, Romfile>,
+) -> usize {
+    let games_count = roms_by_game_id.len();
+    roms_by_game_id.retain(|_, roms| !is_compliant(format, roms, romfiles_by_id));
+    games_count - roms_by_game_id.len()
+}
+
+async fn trim_corrupt_games(
+    connection: &mut SqliteConnection,
+    progress_bar: &ProgressBar,
+    system: &System,
+    games_by_id: &HashMap<i64, Game>,
+    roms_by_game_id: &mut IndexMap<i64, Vec<Rom>>,
+    romfiles_by_id: &HashMap<i64, Romfile>,
+) -> SimpleResult<usize> {
+    let header = find_header_by_system_id(connection, system.id).await;
+    // archives are checked as a whole, so use all their ROMs and not only the selected ones
+    let roms = find_roms_with_romfile_by_game_ids(
+        connection,
+        &roms_by_game_id.keys().copied().collect::<Vec<i64>>(),
+    )
+    .await;
+    let mut corrupt_game_ids: HashSet<i64> = HashSet::new();
+    for (game_id, game_roms) in roms_by_game_id.iter() {
+        let romfile_ids: HashSet<i64> = game_roms
+            .iter()
+            .map(|rom| rom.romfile_id.unwrap())
+            .collect();
+        for romfile_id in romfile_ids {
+            let romfile = romfiles_by_id.get(&romfile_id).unwrap();
+            let romfile_roms: Vec<&Rom> = roms
+                .iter()
+                .filter(|rom| rom.romfile_id == Some(romfile_id))
+                .collect();
+            if check_romfile(connection, progress_bar, &header, romfile, romfile_roms)
+                .await
+                .is_err()
+            {
+                progress_bar.println(format!(
+                    "Source of \"{}\" is corrupt, skipping",
+                    games_by_id.get(game_id).unwrap().name
+                ));
+                corrupt_game_ids.insert(*game_id);
+                break;
             }
-            _ => bail!("Not supported"),
         }
+    }
+    roms_by_game_id.retain(|game_id, _| !corrupt_game_ids.contains(game_id));
+    Ok(corrupt_game_ids.len())
+}
 
-        progress_bar.println("");
+// games are partitioned like the conversion itself would, so the plan can't drift from it
+fn partition_games(
+    format: &str,
+    roms_by_game_id: Partition,
+    romfiles_by_id: &HashMap<i64, Romfile>,
+) -> Partition {
+    let partitions: Vec<Partition> = match format {
+        "ORIGINAL" => {
+            let (archives, chds, csos, nszs, rvzs, zsos) =
+                partition_for_original(roms_by_game_id, romfiles_by_id);
+            vec![archives, chds, csos, nszs, rvzs, zsos]
+        }
+        "7Z" | "ZIP" => {
+            let (chds, csos, nszs, rvzs, zsos, archives, originals) =
+                partition_for_archive(roms_by_game_id, romfiles_by_id);
+            vec![chds, csos, nszs, rvzs, zsos, archives, originals]
+        }
+        "CHD" => {
+            let (archives, cue_bins, isos, csos, zsos, chds, others) =
+                partition_for_chd(roms_by_game_id, romfiles_by_id);
+            vec![archives, cue_bins, isos, csos, zsos, chds, others]
+        }
+        "CSO" => {
+            let (archives, isos, chds, csos, zsos) =
+                partition_for_cso(roms_by_game_id, romfiles_by_id);
+            vec![archives, isos, chds, csos, zsos]
+        }
+        "NSZ" => {
+            let (archives, nsps, nszs) = partition_for_nsz(roms_by_game_id, romfiles_by_id);
+            vec![archives, nsps, nszs]
+        }
+        "RVZ" => {
+            let (archives, isos, chds, rvzs) = partition_for_rvz(roms_by_game_id, romfiles_by_id);
+            vec![archives, isos, chds, rvzs]
+        }
+        "ZSO" => {
+            let (archives, isos, chds, csos, zsos) =
+                partition_for_zso(roms_by_game_id, romfiles_by_id);
+            vec![archives, isos, chds, csos, zsos]
+        }
+        _ => vec![],
+    };
+    partitions.into_iter().flatten().collect()
+}
+
+fn list_conversions(
+    progress_bar: &ProgressBar,
+    format: &str,
+    recompress: bool,
+    games_by_id: &HashMap<i64, Game>,
+    roms_by_game_id: Partition,
+    romfiles_by_id: &HashMap<i64, Romfile>,
+    sizes: bool,
+) -> Partition {
+    let mut listed_roms_by_game_id: Partition = IndexMap::new();
+    let mut current_size: u64 = 0;
+    let mut uncompressed_size: u64 = 0;
+    for (game_id, roms) in partition_games(format, roms_by_game_id, romfiles_by_id) {
+        if !recompress && is_compliant(format, &roms, romfiles_by_id) {
+            continue;
+        }
+        let game_name = &games_by_id.get(&game_id).unwrap().name;
+        let source_formats = get_source_formats(&roms, romfiles_by_id).join(", ");
+        if sizes {
+            // archives can hold several ROMs, count them once
+            let romfile_ids: HashSet<i64> =
+                roms.iter().map(|rom| rom.romfile_id.unwrap()).collect();
+            let game_current_size: u64 = romfile_ids
+                .iter()
+                .map(|romfile_id| romfiles_by_id.get(romfile_id).unwrap().size as u64)
+                .sum();
+            let game_uncompressed_size: u64 = roms.iter().map(|rom| rom.size as u64).sum();
+            progress_bar.println(format!(
+                "\"{}\": {} -> {}, current: {}, uncompressed: {}",
+                game_name,
+                source_formats,
+                format,
+                HumanBytes(game_current_size),
+                HumanBytes(game_uncompressed_size)
+            ));
+            current_size += game_current_size;
+            uncompressed_size += game_uncompressed_size;
+        } else {
+            progress_bar.println(format!(
+                "\"{}\": {} -> {}",
+                game_name, source_formats, format
+            ));
+        }
+        listed_roms_by_game_id.insert(game_id, roms);
+    }
+    if sizes {
+        progress_bar.println(format!(
+            "Games to convert: {}; Current: {}; Uncompressed: {}",
+            listed_roms_by_game_id.len(),
+            HumanBytes(current_size),
+            HumanBytes(uncompressed_size)
+        ));
+    } else {
+        progress_bar.println(format!(
+            "Games to convert: {}",
+            listed_roms_by_game_id.len()
+        ));
     }
+    listed_roms_by_game_id
+}
 
-    Ok(())
+fn get_source_formats<'a>(roms: &[Rom], romfiles_by_id: &'a HashMap<i64, Romfile>) -> Vec<&'a str> {
+    let mut source_formats: Vec<&str> = roms
+        .iter()
+        .map(|rom| get_romfile_format(&romfiles_by_id.get(&rom.romfile_id.unwrap()).unwrap().path))
+        .collect();
+    source_formats.sort_unstable();
+    source_formats.dedup();
+    source_formats
 }
 
-#[allow(clippy::too_many_arguments)]
-async fn to_archive(
+fn is_compliant(format: &str, roms: &[Rom], romfiles_by_id: &HashMap<i64, Romfile>) -> bool {
+    roms.iter().all(|rom| {
+        let path = &romfiles_by_id.get(&rom.romfile_id.unwrap()).unwrap().path;
+        match format {
+            "ORIGINAL" => ![
+                SEVENZIP_EXTENSION,
+                ZIP_EXTENSION,
+                CHD_EXTENSION,
+                CSO_EXTENSION,
+                NSZ_EXTENSION,
+                RVZ_EXTENSION,
+                ZSO_EXTENSION,
+            ]
+            .iter()
+            .any(|extension| path.ends_with(extension)),
+            "7Z" => path.ends_with(SEVENZIP_EXTENSION),
+            "CHD" => path.ends_with(CHD_EXTENSION),
+            "CSO" => path.ends_with(CSO_EXTENSION),
+            "NSZ" => path.ends_with(NSZ_EXTENSION),
+            "RVZ" => path.ends_with(RVZ_EXTENSION),
+            "ZIP" => path.ends_with(ZIP_EXTENSION),
+            "ZSO" => path.ends_with(ZSO_EXTENSION),
+            _ => false,
+        }
+    })
+}
+
+fn get_romfile_states(
+    roms: &[Rom],
+    romfiles_by_id: &HashMap<i64, Romfile>,
+) -> Vec<(i64, String, i64)> {
+    let mut romfile_states: Vec<(i64, String, i64)> = roms
+        .iter()
+        .map(|rom| {
+            let romfile = romfiles_by_id.get(&rom.romfile_id.unwrap()).unwrap();
+            (romfile.id, romfile.path.clone(), romfile.size)
+        })
+        .collect();
+    romfile_states.sort();
+    romfile_states
+}
+
+fn join_romfile_paths(romfile_states: &[(i64, String, i64)]) -> String {
+    romfile_states
+        .iter()
+        .map(|(_, path, _)| path.as_str())
+        .sorted()
+        .dedup()
+        .join(", ")
+}
+
+fn print_tally(
+    progress_bar: &ProgressBar,
+    format: &str,
+    converted: usize,
+    compliant: usize,
+    skipped: usize,
+) {
+    progress_bar.println(format!(
+        "Skipped: {} (already {}: {}), converted: {}",
+        skipped, format, compliant, converted
+    ));
+}
+
+async fn estimate(
     connection: &mut SqliteConnection,
     progress_bar: &ProgressBar,
     system: &System,
+    format: &str,
     games_by_id: HashMap<i64, Game>,
     roms_by_game_id: IndexMap<i64, Vec<Rom>>,
     romfiles_by_id: HashMap<i64, Romfile>,
-    archive_type: sevenzip::ArchiveType,
-    recompress: bool,
-    diff: bool,
-    check: bool,
-    compression_level: &Option<usize>,
-    solid: bool,
-) -> SimpleResult<()> {
+) -> SimpleResult<Option<u64>> {
+    let games_count = roms_by_game_id.len();
+    let size: u64 = romfiles_by_id
+        .values()
+        .map(|romfile| romfile.size as u64)
+        .sum();
+    if games_count == 0 || size == 0 {
+        return Ok(None);
+    }
+
+    // spread the sample evenly across the selected games
+    let sample_roms_by_game_id: IndexMap<i64, Vec<Rom>> = roms_by_game_id
+        .into_iter()
+        .step_by((games_count / ESTIMATE_SAMPLE_SIZE).max(1))
+        .take(ESTIMATE_SAMPLE_SIZE)
+        .collect();
+    let sample_romfile_ids: HashSet<i64> = sample_roms_by_game_id
+        .values()
+        .flatten()
+        .map(|rom| rom.romfile_id.unwrap())
+        .collect();
+    let sample_romfiles_by_id: HashMap<i64, Romfile> = romfiles_by_id
+        .into_iter()
+        .filter(|(romfile_id, _)| sample_romfile_ids.contains(romfile_id))
+        .collect();
+    let sample_count = sample_roms_by_game_id.len();
+    let sample_size: u64 = sample_romfiles_by_id
+        .values()
+        .map(|romfile| romfile.size as u64)
+        .sum();
+
+    // reuse the export code so that original files are left untouched
+    let tmp_directory = create_tmp_directory(connection).await?;
+    let destination_directory = tmp_directory.path().to_path_buf();
+    export_roms::export_system(
+        connection,
+        progress_bar,
+        &destination_directory,
+        system,
+        format,
+        games_by_id,
+        sample_roms_by_game_id,
+        sample_romfiles_by_id,
+        false,
+        None,
+        &None,
+        export_roms::ArchiveGrouping::Game,
+    )
+    .await?;
+    let new_sample_size: u64 = WalkDir::new(&destination_directory)
+        .into_iter()
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.path().is_file())
+        .map(|entry| entry.metadata().map_or(0, |metadata| metadata.len()))
+        .sum();
+
+    if sample_size == 0 {
+        return Ok(None);
+    }
+    let ratio = new_sample_size as f64 / sample_size as f64;
+    let new_size = (size as f64 * ratio) as u64;
+    progress_bar.println(format!(
+        "Current: {}; Estimated: {} ({:.1}%)",
+        HumanBytes(size),
+        HumanBytes(new_size),
+        ratio * 100f64
+    ));
+    progress_bar.println(format!(
+        "Based on a sample of {} out of {} game(s), actual results may vary",
+        sample_count, games_count
+    ));
+
+    Ok(Some(new_size))
+}
+
+fn partition_for_archive(
+    roms_by_game_id: Partition,
+    romfiles_by_id: &HashMap<i64, Romfile>,
+) -> (
+    Partition,
+    Partition,
+    Partition,
+    Partition,
+    Partition,
+    Partition,
+    Partition,
+) {
     // partition CHDs
-    let (mut chds, roms_by_game_id): (IndexMap<i64, Vec<Rom>>, IndexMap<i64, Vec<Rom>>) =
+    let (mut chds, roms_by_game_id): (Partition, Partition) =
         roms_by_game_id.into_iter().partition(|(_, roms)| {
             roms.par_iter().any(|rom| {
                 romfiles_by_id
@@ -424,19 +1195,10 @@ async fn to_archive(
             })
         });
     // make sure children are converted before parents
-    chds.par_sort_by(|_, a, _, b| {
-        b.par_iter()
-            .map(|rom| romfiles_by_id.get(&rom.romfile_id.unwrap()).unwrap())
-            .any(|romfile| romfile.parent_id.is_some())
-            .cmp(
-                &a.par_iter()
-                    .map(|rom| romfiles_by_id.get(&rom.romfile_id.unwrap()).unwrap())
-                    .any(|romfile| romfile.parent_id.is_some()),
-            )
-    });
+    sort_chds_by_parent_depth(&mut chds, &romfiles_by_id);
 
     // partition CSOs
-    let (csos, roms_by_game_id): (IndexMap<i64, Vec<Rom>>, IndexMap<i64, Vec<Rom>>) =
+    let (csos, roms_by_game_id): (Partition, Partition) =
         roms_by_game_id.into_iter().partition(|(_, roms)| {
             roms.par_iter().any(|rom| {
                 romfiles_by_id
@@ -448,7 +1210,7 @@ async fn to_archive(
         });
 
     // partition NSZs
-    let (nszs, roms_by_game_id): (IndexMap<i64, Vec<Rom>>, IndexMap<i64, Vec<Rom>>) =
+    let (nszs, roms_by_game_id): (Partition, Partition) =
         roms_by_game_id.into_iter().partition(|(_, roms)| {
             roms.par_iter().any(|rom| {
                 romfiles_by_id
@@ -460,7 +1222,7 @@ async fn to_archive(
         });
 
     // partition RVZs
-    let (rvzs, roms_by_game_id): (IndexMap<i64, Vec<Rom>>, IndexMap<i64, Vec<Rom>>) =
+    let (rvzs, roms_by_game_id): (Partition, Partition) =
         roms_by_game_id.into_iter().partition(|(_, roms)| {
             roms.par_iter().any(|rom| {
                 romfiles_by_id
@@ -472,7 +1234,7 @@ async fn to_archive(
         });
 
     // partition ZSOs
-    let (zsos, roms_by_game_id): (IndexMap<i64, Vec<Rom>>, IndexMap<i64, Vec<Rom>>) =
+    let (zsos, roms_by_game_id): (Partition, Partition) =
         roms_by_game_id.into_iter().partition(|(_, roms)| {
             roms.par_iter().any(|rom| {
                 romfiles_by_id
@@ -484,7 +1246,7 @@ async fn to_archive(
         });
 
     // partition archives
-    let (archives, roms_by_game_id): (IndexMap<i64, Vec<Rom>>, IndexMap<i64, Vec<Rom>>) =
+    let (archives, roms_by_game_id): (Partition, Partition) =
         roms_by_game_id.into_iter().partition(|(_, roms)| {
             roms.par_iter().any(|rom| {
                 let path = &romfiles_by_id.get(&rom.romfile_id.unwrap()).unwrap().path;
@@ -492,6 +1254,29 @@ async fn to_archive(
             })
         });
 
+    (chds, csos, nszs, rvzs, zsos, archives, roms_by_game_id)
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn to_archive(
+    connection: &mut SqliteConnection,
+    progress_bar: &ProgressBar,
+    system: &System,
+    games_by_id: HashMap<i64, Game>,
+    roms_by_game_id: IndexMap<i64, Vec<Rom>>,
+    romfiles_by_id: HashMap<i64, Romfile>,
+    archive_type: sevenzip::ArchiveType,
+    recompress: bool,
+    diff: bool,
+    check: bool,
+    compression_level: &Option<usize>,
+    solid: bool,
+    preserve_method: bool,
+) -> SimpleResult<HashSet<i64>> {
+    let mut converted_game_ids: HashSet<i64> = HashSet::new();
+    let (chds, csos, nszs, rvzs, zsos, archives, roms_by_game_id) =
+        partition_for_archive(roms_by_game_id, &romfiles_by_id);
+
     // convert CHDs
     for roms in chds.values() {
         // leave arcade CHDs untouched
@@ -571,6 +1356,7 @@ async fn to_archive(
                         &game.name,
                         &archive_type,
                         compression_level,
+                        &None,
                         solid,
                     )
                     .await?;
@@ -585,6 +1371,7 @@ async fn to_archive(
                             &game.name,
                             &archive_type,
                             compression_level,
+                            &None,
                             solid,
                         )
                         .await?;
@@ -655,6 +1442,7 @@ async fn to_archive(
                         &game.name,
                         &archive_type,
                         compression_level,
+                        &None,
                         solid,
                     )
                     .await?;
@@ -706,6 +1494,7 @@ async fn to_archive(
                         &game.name,
                         &archive_type,
                         compression_level,
+                        &None,
                         solid,
                     )
                     .await?;
@@ -757,6 +1546,7 @@ async fn to_archive(
                         &game.name,
                         &archive_type,
                         compression_level,
+                        &None,
                         solid,
                     )
                     .await?;
@@ -798,6 +1588,7 @@ async fn to_archive(
             }
         }
 
+        converted_game_ids.insert(roms.first().unwrap().game_id);
         commit_transaction(transaction).await;
     }
 
@@ -820,6 +1611,7 @@ async fn to_archive(
                 &game.name,
                 &archive_type,
                 compression_level,
+                &None,
                 solid,
             )
             .await?;
@@ -853,6 +1645,7 @@ async fn to_archive(
 
         cso_romfile.romfile.delete(progress_bar, false).await?;
 
+        converted_game_ids.insert(roms.first().unwrap().game_id);
         commit_transaction(transaction).await;
     }
 
@@ -875,6 +1668,7 @@ async fn to_archive(
                 &game.name,
                 &archive_type,
                 compression_level,
+                &None,
                 solid,
             )
             .await?;
@@ -908,6 +1702,7 @@ async fn to_archive(
 
         nsz_romfile.romfile.delete(progress_bar, false).await?;
 
+        converted_game_ids.insert(roms.first().unwrap().game_id);
         commit_transaction(transaction).await;
     }
 
@@ -930,6 +1725,7 @@ async fn to_archive(
                 &game.name,
                 &archive_type,
                 compression_level,
+                &None,
                 solid,
             )
             .await?;
@@ -963,6 +1759,7 @@ async fn to_archive(
 
         rvz_romfile.romfile.delete(progress_bar, false).await?;
 
+        converted_game_ids.insert(roms.first().unwrap().game_id);
         commit_transaction(transaction).await;
     }
 
@@ -985,6 +1782,7 @@ async fn to_archive(
                 &game.name,
                 &archive_type,
                 compression_level,
+                &None,
                 solid,
             )
             .await?;
@@ -1018,6 +1816,7 @@ async fn to_archive(
 
         zso_romfile.romfile.delete(progress_bar, false).await?;
 
+        converted_game_ids.insert(roms.first().unwrap().game_id);
         commit_transaction(transaction).await;
     }
 
@@ -1035,6 +1834,16 @@ async fn to_archive(
             .as_archive(progress_bar, None)
             .await?;
         let source_archive_type = archive_romfiles.first().unwrap().archive_type;
+        let compression_method =
+            if recompress && preserve_method && source_archive_type == archive_type {
+                sevenzip::get_compression_method(
+                    progress_bar,
+                    &romfile.as_common(&mut transaction).await?,
+                )
+                .await?
+            } else {
+                None
+            };
         let mut archive_romfiles_roms: Vec<(ArchiveRomfile, &Rom)> = vec![];
         for rom in roms {
             let archive_romfile = archive_romfiles
@@ -1051,6 +1860,7 @@ async fn to_archive(
                             &game.name,
                             &archive_type,
                             compression_level,
+                            &None,
                             solid,
                         )
                         .await?,
@@ -1066,6 +1876,7 @@ async fn to_archive(
                             &game.name,
                             &archive_type,
                             compression_level,
+                            &compression_method,
                             solid,
                         )
                         .await?,
@@ -1131,13 +1942,33 @@ async fn to_archive(
             }
         };
 
+        converted_game_ids.insert(roms.first().unwrap().game_id);
         commit_transaction(transaction).await;
     }
 
+    // group discs sharing an archive
+    let per_game =
+        MultidiscArchive::from_str(&get_string(connection, "MULTIDISC_ARCHIVE").await.unwrap())
+            .unwrap()
+            == MultidiscArchive::PerGame;
+    let mut roms_by_archive_name: IndexMap<String, (i64, Vec<Rom>)> = IndexMap::new();
+    for (game_id, roms) in roms_by_game_id {
+        let game = games_by_id.get(&game_id).unwrap();
+        let group = roms_by_archive_name
+            .entry(get_disc_archive_name(&game.name, per_game))
+            .or_insert((game_id, vec![]));
+        group.1.extend(roms);
+    }
+
     // convert others
-    for (game_id, mut roms) in roms_by_game_id {
+    for (archive_name, (game_id, mut roms)) in roms_by_archive_name {
         let mut transaction = begin_transaction(connection).await;
-        if roms.len() == 1 && !system.arcade {
+        // discs of a multidisc game share a single archive
+        let game_ids: Vec<i64> = roms.iter().map(|rom| rom.game_id).unique().collect();
+        if roms.len() == 1
+            && !system.arcade
+            && archive_name == games_by_id.get(&game_id).unwrap().name
+        {
             let rom = roms.first().unwrap();
             let game = games_by_id.get(&rom.game_id).unwrap();
             let romfile = romfiles_by_id.get(&rom.romfile_id.unwrap()).unwrap();
@@ -1150,6 +1981,7 @@ async fn to_archive(
                     &game.name,
                     &archive_type,
                     compression_level,
+                    &None,
                     solid,
                 )
                 .await?;
@@ -1183,7 +2015,6 @@ async fn to_archive(
 
             common_romfile.delete(progress_bar, false).await?;
         } else {
-            let game = games_by_id.get(&game_id).unwrap();
             roms.retain(|rom| {
                 let romfile = romfiles_by_id.get(&rom.romfile_id.unwrap()).unwrap();
                 !(romfile.path.ends_with(match archive_type {
@@ -1216,9 +2047,10 @@ async fn to_archive(
                             true => directory.parent().unwrap(),
                             false => directory.as_path(),
                         },
-                        &game.name,
+                        &archive_name,
                         &archive_type,
                         compression_level,
+                        &None,
                         solid,
                     )
                     .await?;
@@ -1244,11 +2076,28 @@ async fn to_archive(
 
             let archive_romfile_id = match find_romfile_by_path(
                 &mut transaction,
-                &archive_romfiles.first().unwrap().romfile.to_string(),
+                archive_romfiles
+                    .first()
+                    .unwrap()
+                    .romfile
+                    .get_relative_path(&mut transaction)
+                    .await?
+                    .as_os_str()
+                    .to_str()
+                    .unwrap(),
             )
             .await
             {
-                Some(romfile) => romfile.id,
+                Some(romfile) => {
+                    // the archive grew, e.g. when adding a disc to a per-game archive
+                    archive_romfiles
+                        .first()
+                        .unwrap()
+                        .romfile
+                        .update(&mut transaction, progress_bar, romfile.id)
+                        .await?;
+                    romfile.id
+                }
                 None => {
                     archive_romfiles
                         .first()
@@ -1293,10 +2142,98 @@ async fn to_archive(
             }
         }
 
+        converted_game_ids.extend(game_ids);
         commit_transaction(transaction).await;
     }
 
-    Ok(())
+    Ok(converted_game_ids)
+}
+
+fn partition_for_chd(
+    roms_by_game_id: Partition,
+    romfiles_by_id: &HashMap<i64, Romfile>,
+) -> (
+    Partition,
+    Partition,
+    Partition,
+    Partition,
+    Partition,
+    Partition,
+    Partition,
+) {
+    // partition archives
+    let (archives, others): (Partition, Partition) =
+        roms_by_game_id.into_iter().partition(|(_, roms)| {
+            roms.par_iter().any(|rom| {
+                let romfile = romfiles_by_id.get(&rom.romfile_id.unwrap()).unwrap();
+                romfile.path.ends_with(ZIP_EXTENSION) || romfile.path.ends_with(SEVENZIP_EXTENSION)
+            })
+        });
+
+    // partition CUE/BINs
+    let (cue_bins, others): (Partition, Partition) = others.into_iter().partition(|(_, roms)| {
+        roms.par_iter().any(|rom| {
+            romfiles_by_id
+                .get(&rom.romfile_id.unwrap())
+                .unwrap()
+                .path
+                .ends_with(CUE_EXTENSION)
+        }) && roms.par_iter().any(|rom| {
+            romfiles_by_id
+                .get(&rom.romfile_id.unwrap())
+                .unwrap()
+                .path
+                .ends_with(BIN_EXTENSION)
+        })
+    });
+
+    // partition ISOs
+    let (isos, others): (Partition, Partition) = others.into_iter().partition(|(_, roms)| {
+        roms.par_iter().any(|rom| {
+            romfiles_by_id
+                .get(&rom.romfile_id.unwrap())
+                .unwrap()
+                .path
+                .ends_with(ISO_EXTENSION)
+        })
+    });
+
+    // partition CSOs
+    let (csos, others): (Partition, Partition) = others.into_iter().partition(|(_, roms)| {
+        roms.par_iter().any(|rom| {
+            romfiles_by_id
+                .get(&rom.romfile_id.unwrap())
+                .unwrap()
+                .path
+                .ends_with(CSO_EXTENSION)
+        })
+    });
+
+    // partition ZSOs
+    let (zsos, others): (Partition, Partition) = others.into_iter().partition(|(_, roms)| {
+        roms.par_iter().any(|rom| {
+            romfiles_by_id
+                .get(&rom.romfile_id.unwrap())
+                .unwrap()
+                .path
+                .ends_with(ZSO_EXTENSION)
+        })
+    });
+
+    // partition CHDs
+    let (mut chds, others): (Partition, Partition) = others.into_iter().partition(|(_, roms)| {
+        roms.par_iter().any(|rom| {
+            romfiles_by_id
+                .get(&rom.romfile_id.unwrap())
+                .unwrap()
+                .path
+                .ends_with(CHD_EXTENSION)
+        })
+    });
+    // make sure children are converted before parents
+    sort_chds_by_parent_depth(&mut chds, &romfiles_by_id);
+
+    (archives, cue_bins, isos, csos, zsos, chds, others)
 }
 
 #[allow(clippy::too_many_arguments)]
@@ -1319,92 +2256,11 @@ async fn to_chd(
     ld_hunk_size: &Option<usize>,
     parents: bool,
     prompt_for_parents: bool,
-) -> SimpleResult<()> {
-    // partition archives
-    let (archives, others): (IndexMap<i64, Vec<Rom>>, IndexMap<i64, Vec<Rom>>) =
-        roms_by_game_id.into_iter().partition(|(_, roms)| {
-            roms.par_iter().any(|rom| {
-                let romfile = romfiles_by_id.get(&rom.romfile_id.unwrap()).unwrap();
-                romfile.path.ends_with(ZIP_EXTENSION) || romfile.path.ends_with(SEVENZIP_EXTENSION)
-            })
-        });
-
-    // partition CUE/BINs
-    let (cue_bins, others): (IndexMap<i64, Vec<Rom>>, IndexMap<i64, Vec<Rom>>) =
-        others.into_iter().partition(|(_, roms)| {
-            roms.par_iter().any(|rom| {
-                romfiles_by_id
-                    .get(&rom.romfile_id.unwrap())
-                    .unwrap()
-                    .path
-                    .ends_with(CUE_EXTENSION)
-            }) && roms.par_iter().any(|rom| {
-                romfiles_by_id
-                    .get(&rom.romfile_id.unwrap())
-                    .unwrap()
-                    .path
-                    .ends_with(BIN_EXTENSION)
-            })
-        });
-
-    // partition ISOs
-    let (isos, others): (IndexMap<i64, Vec<Rom>>, IndexMap<i64, Vec<Rom>>) =
-        others.into_iter().partition(|(_, roms)| {
-            roms.par_iter().any(|rom| {
-                romfiles_by_id
-                    .get(&rom.romfile_id.unwrap())
-                    .unwrap()
-                    .path
-                    .ends_with(ISO_EXTENSION)
-            })
-        });
-
-    // partition CSOs
-    let (csos, others): (IndexMap<i64, Vec<Rom>>, IndexMap<i64, Vec<Rom>>) =
-        others.into_iter().partition(|(_, roms)| {
-            roms.par_iter().any(|rom| {
-                romfiles_by_id
-                    .get(&rom.romfile_id.unwrap())
-                    .unwrap()
-                    .path
-                    .ends_with(CSO_EXTENSION)
-            })
-        });
-
-    // partition ZSOs
-    let (zsos, others): (IndexMap<i64, Vec<Rom>>, IndexMap<i64, Vec<Rom>>) =
-        others.into_iter().partition(|(_, roms)| {
-            roms.par_iter().any(|rom| {
-                romfiles_by_id
-                    .get(&rom.romfile_id.unwrap())
-                    .unwrap()
-                    .path
-                    .ends_with(ZSO_EXTENSION)
-            })
-        });
-
-    // partition CHDs
-    let (mut chds, others): (IndexMap<i64, Vec<Rom>>, IndexMap<i64, Vec<Rom>>) =
-        others.into_iter().partition(|(_, roms)| {
-            roms.par_iter().any(|rom| {
-                romfiles_by_id
-                    .get(&rom.romfile_id.unwrap())
-                    .unwrap()
-                    .path
-                    .ends_with(CHD_EXTENSION)
-            })
-        });
-    // make sure children are converted before parents
-    chds.par_sort_by(|_, a, _, b| {
-        b.par_iter()
-            .map(|rom| romfiles_by_id.get(&rom.romfile_id.unwrap()).unwrap())
-            .any(|romfile| romfile.parent_id.is_some())
-            .cmp(
-                &a.par_iter()
-                    .map(|rom| romfiles_by_id.get(&rom.romfile_id.unwrap()).unwrap())
-                    .any(|romfile| romfile.parent_id.is_some()),
-            )
-    });
+    strict: bool,
+) -> SimpleResult<HashSet<i64>> {
+    let mut converted_game_ids: HashSet<i64> = HashSet::new();
+    let (archives, cue_bins, isos, csos, zsos, chds, others) =
+        partition_for_chd(roms_by_game_id, &romfiles_by_id);
 
     // convert archives
     for roms in archives.values() {
@@ -1416,12 +2272,19 @@ async fn to_chd(
             .collect();
         romfiles.dedup();
 
+        let game = games_by_id.get(&roms.first().unwrap().game_id).unwrap();
         if romfiles.len() > 1 {
-            bail!("Multiple archives found");
+            if strict {
+                bail!("Multiple archives found");
+            }
+            progress_bar.println(format!(
+                "Skipping \"{}\": ROM files span multiple archives",
+                game.name
+            ));
+            continue;
         }
 
         let romfile = romfiles.first().unwrap();
-        let game = games_by_id.get(&roms.first().unwrap().game_id).unwrap();
         let parent_chd_romfile = if prompt_for_parents {
             prompt_for_parent_romfile(&mut transaction, game, CHD_EXTENSION).await?
         } else if parents {
@@ -1560,29 +2423,29 @@ async fn to_chd(
             }
 
             let cue_rom = cue_roms.pop().unwrap();
-            let cue_romfile = romfile
+            // extract the CUE and all its BINs in a single 7z invocation
+            let archive_romfiles = romfile
                 .as_common(&mut transaction)
                 .await?
-                .as_archive(progress_bar, Some(cue_rom))
-                .await?
-                .pop()
-                .unwrap()
-                .to_common(progress_bar, &tmp_directory.path())
+                .as_archive(progress_bar, None)
                 .await?;
-            let mut bin_romfiles: Vec<CommonRomfile> = vec![];
-            for rom in &bin_roms {
-                bin_romfiles.push(
-                    romfile
-                        .as_common(&mut transaction)
-                        .await?
-                        .as_archive(progress_bar, Some(rom))
-                        .await?
-                        .pop()
-                        .unwrap()
-                        .to_common(progress_bar, &tmp_directory.path())
-                        .await?,
-                );
+            let mut wanted_archive_romfiles: Vec<&ArchiveRomfile> = vec![];
+            for rom in [cue_rom].iter().chain(bin_roms.iter()) {
+                match archive_romfiles
+                    .iter()
+                    .find(|archive_romfile| archive_romfile.path == rom.name)
+                {
+                    Some(archive_romfile) => wanted_archive_romfiles.push(archive_romfile),
+                    None => bail!("\"{}\" is missing from the archive", rom.name),
+                }
             }
+            let mut bin_romfiles = sevenzip::extract_files(
+                progress_bar,
+                &wanted_archive_romfiles,
+                &tmp_directory.path(),
+            )
+            .await?;
+            let cue_romfile = bin_romfiles.remove(0);
             let cue_bin_romfile = cue_romfile.as_cue_bin(bin_romfiles)?;
             let chd_romfile = cue_bin_romfile
                 .to_chd(
@@ -1656,6 +2519,7 @@ async fn to_chd(
                 .await?;
         }
 
+        converted_game_ids.insert(roms.first().unwrap().game_id);
         commit_transaction(transaction).await;
     }
 
@@ -1751,6 +2615,7 @@ async fn to_chd(
                 .await?;
         }
 
+        converted_game_ids.insert(roms.first().unwrap().game_id);
         commit_transaction(transaction).await;
     }
 
@@ -1812,6 +2677,7 @@ async fn to_chd(
         .await;
         iso_romfile.romfile.delete(progress_bar, false).await?;
 
+        converted_game_ids.insert(roms.first().unwrap().game_id);
         commit_transaction(transaction).await;
     }
 
@@ -1876,6 +2742,7 @@ async fn to_chd(
         .await;
         cso_romfile.romfile.delete(progress_bar, false).await?;
 
+        converted_game_ids.insert(roms.first().unwrap().game_id);
         commit_transaction(transaction).await;
     }
 
@@ -1940,6 +2807,7 @@ async fn to_chd(
         .await;
         zso_romfile.romfile.delete(progress_bar, false).await?;
 
+        converted_game_ids.insert(roms.first().unwrap().game_id);
         commit_transaction(transaction).await;
     }
 
@@ -2036,6 +2904,7 @@ async fn to_chd(
                 .await?;
         }
 
+        converted_game_ids.insert(roms.first().unwrap().game_id);
         commit_transaction(transaction).await;
     }
     // convert CHDs
@@ -2219,25 +3088,20 @@ async fn to_chd(
                 .await;
             };
 
+            converted_game_ids.insert(roms.first().unwrap().game_id);
             commit_transaction(transaction).await;
         }
     }
 
-    Ok(())
+    Ok(converted_game_ids)
 }
 
-#[allow(clippy::too_many_arguments)]
-async fn to_cso(
-    connection: &mut SqliteConnection,
-    progress_bar: &ProgressBar,
-    roms_by_game_id: IndexMap<i64, Vec<Rom>>,
-    romfiles_by_id: HashMap<i64, Romfile>,
-    recompress: bool,
-    diff: bool,
-    check: bool,
-) -> SimpleResult<()> {
+fn partition_for_cso(
+    roms_by_game_id: Partition,
+    romfiles_by_id: &HashMap<i64, Romfile>,
+) -> (Partition, Partition, Partition, Partition, Partition) {
     // partition archives
-    let (archives, others): (IndexMap<i64, Vec<Rom>>, IndexMap<i64, Vec<Rom>>) =
+    let (archives, others): (Partition, Partition) =
         roms_by_game_id.into_iter().partition(|(_, roms)| {
             roms.par_iter().any(|rom| {
                 let romfile = romfiles_by_id.get(&rom.romfile_id.unwrap()).unwrap();
@@ -2246,67 +3110,70 @@ async fn to_cso(
         });
 
     // partition ISOs
-    let (isos, others): (IndexMap<i64, Vec<Rom>>, IndexMap<i64, Vec<Rom>>) =
-        others.into_iter().partition(|(_, roms)| {
-            roms.par_iter().any(|rom| {
-                romfiles_by_id
-                    .get(&rom.romfile_id.unwrap())
-                    .unwrap()
-                    .path
-                    .ends_with(ISO_EXTENSION)
-            })
-        });
+    let (isos, others): (Partition, Partition) = others.into_iter().partition(|(_, roms)| {
+        roms.par_iter().any(|rom| {
+            romfiles_by_id
+                .get(&rom.romfile_id.unwrap())
+                .unwrap()
+                .path
+                .ends_with(ISO_EXTENSION)
+        })
+    });
 
     // partition CHDs
-    let (mut chds, others): (IndexMap<i64, Vec<Rom>>, IndexMap<i64, Vec<Rom>>) =
-        others.into_iter().partition(|(_, roms)| {
-            roms.par_iter().any(|rom| {
-                romfiles_by_id
-                    .get(&rom.romfile_id.unwrap())
-                    .unwrap()
-                    .path
-                    .ends_with(CHD_EXTENSION)
-            })
-        });
-    // make sure children are converted before parents
-    chds.par_sort_by(|_, a, _, b| {
-        b.par_iter()
-            .map(|rom| romfiles_by_id.get(&rom.romfile_id.unwrap()).unwrap())
-            .any(|romfile| romfile.parent_id.is_some())
-            .cmp(
-                &a.par_iter()
-                    .map(|rom| romfiles_by_id.get(&rom.romfile_id.unwrap()).unwrap())
-                    .any(|romfile| romfile.parent_id.is_some()),
-            )
+    let (mut chds, others): (Partition, Partition) = others.into_iter().partition(|(_, roms)| {
+        roms.par_iter().any(|rom| {
+            romfiles_by_id
+                .get(&rom.romfile_id.unwrap())
+                .unwrap()
+                .path
+                .ends_with(CHD_EXTENSION)
+        })
     });
+    // make sure children are converted before parents
+    sort_chds_by_parent_depth(&mut chds, &romfiles_by_id);
 
     // partition CSOs
-    let (csos, others): (IndexMap<i64, Vec<Rom>>, IndexMap<i64, Vec<Rom>>) =
-        others.into_iter().partition(|(_, roms)| {
-            roms.par_iter().any(|rom| {
-                romfiles_by_id
-                    .get(&rom.romfile_id.unwrap())
-                    .unwrap()
-                    .path
-                    .ends_with(CSO_EXTENSION)
-            })
-        });
+    let (csos, others): (Partition, Partition) = others.into_iter().partition(|(_, roms)| {
+        roms.par_iter().any(|rom| {
+            romfiles_by_id
+                .get(&rom.romfile_id.unwrap())
+                .unwrap()
+                .path
+                .ends_with(CSO_EXTENSION)
+        })
+    });
 
     // partition ZSOs
-    let (zsos, others): (IndexMap<i64, Vec<Rom>>, IndexMap<i64, Vec<Rom>>) =
-        others.into_iter().partition(|(_, roms)| {
-            roms.par_iter().any(|rom| {
-                romfiles_by_id
-                    .get(&rom.romfile_id.unwrap())
-                    .unwrap()
-                    .path
-                    .ends_with(ZSO_EXTENSION)
-            })
-        });
+    let (zsos, others): (Partition, Partition) = others.into_iter().partition(|(_, roms)| {
+        roms.par_iter().any(|rom| {
+            romfiles_by_id
+                .get(&rom.romfile_id.unwrap())
+                .unwrap()
+                .path
+                .ends_with(ZSO_EXTENSION)
+        })
+    });
 
     // drop others
     drop(others);
 
+    (archives, isos, chds, csos, zsos)
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn to_cso(
+    connection: &mut SqliteConnection,
+    progress_bar: &ProgressBar,
+    roms_by_game_id: IndexMap<i64, Vec<Rom>>,
+    romfiles_by_id: HashMap<i64, Romfile>,
+    recompress: bool,
+    diff: bool,
+    check: bool,
+) -> SimpleResult<HashSet<i64>> {
+    let mut converted_game_ids: HashSet<i64> = HashSet::new();
+    let (archives, isos, chds, csos, zsos) = partition_for_cso(roms_by_game_id, &romfiles_by_id);
+
     // convert archives
     for roms in archives.values() {
         if roms.len() > 1 || !roms.first().unwrap().name.ends_with(ISO_EXTENSION) {
@@ -2362,6 +3229,7 @@ async fn to_cso(
             .await?;
         archive_romfile.romfile.delete(progress_bar, false).await?;
 
+        converted_game_ids.insert(roms.first().unwrap().game_id);
         commit_transaction(transaction).await;
     }
 
@@ -2406,6 +3274,7 @@ async fn to_cso(
             .await?;
         iso_romfile.romfile.delete(progress_bar, false).await?;
 
+        converted_game_ids.insert(roms.first().unwrap().game_id);
         commit_transaction(transaction).await;
     }
 
@@ -2483,6 +3352,7 @@ async fn to_cso(
         }
         chd_romfile.romfile.delete(progress_bar, false).await?;
 
+        converted_game_ids.insert(roms.first().unwrap().game_id);
         commit_transaction(transaction).await;
     }
 
@@ -2529,6 +3399,7 @@ async fn to_cso(
             .await?;
         zso_romfile.romfile.delete(progress_bar, false).await?;
 
+        converted_game_ids.insert(roms.first().unwrap().game_id);
         commit_transaction(transaction).await;
     }
 
@@ -2575,25 +3446,20 @@ async fn to_cso(
                     .await?;
             };
 
+            converted_game_ids.insert(roms.first().unwrap().game_id);
             commit_transaction(transaction).await;
         }
     }
 
-    Ok(())
+    Ok(converted_game_ids)
 }
 
-#[allow(clippy::too_many_arguments)]
-async fn to_nsz(
-    connection: &mut SqliteConnection,
-    progress_bar: &ProgressBar,
-    roms_by_game_id: IndexMap<i64, Vec<Rom>>,
-    romfiles_by_id: HashMap<i64, Romfile>,
-    recompress: bool,
-    diff: bool,
-    check: bool,
-) -> SimpleResult<()> {
+fn partition_for_nsz(
+    roms_by_game_id: Partition,
+    romfiles_by_id: &HashMap<i64, Romfile>,
+) -> (Partition, Partition, Partition) {
     // partition archives
-    let (archives, others): (IndexMap<i64, Vec<Rom>>, IndexMap<i64, Vec<Rom>>) =
+    let (archives, others): (Partition, Partition) =
         roms_by_game_id.into_iter().partition(|(_, roms)| {
             roms.par_iter().any(|rom| {
                 let romfile = romfiles_by_id.get(&rom.romfile_id.unwrap()).unwrap();
@@ -2602,32 +3468,46 @@ async fn to_nsz(
         });
 
     // partition NSPs
-    let (nsps, others): (IndexMap<i64, Vec<Rom>>, IndexMap<i64, Vec<Rom>>) =
-        others.into_iter().partition(|(_, roms)| {
-            roms.par_iter().any(|rom| {
-                romfiles_by_id
-                    .get(&rom.romfile_id.unwrap())
-                    .unwrap()
-                    .path
-                    .ends_with(NSP_EXTENSION)
-            })
-        });
+    let (nsps, others): (Partition, Partition) = others.into_iter().partition(|(_, roms)| {
+        roms.par_iter().any(|rom| {
+            romfiles_by_id
+                .get(&rom.romfile_id.unwrap())
+                .unwrap()
+                .path
+                .ends_with(NSP_EXTENSION)
+        })
+    });
 
     // partition NSZs
-    let (nszs, others): (IndexMap<i64, Vec<Rom>>, IndexMap<i64, Vec<Rom>>) =
-        others.into_iter().partition(|(_, roms)| {
-            roms.par_iter().any(|rom| {
-                romfiles_by_id
-                    .get(&rom.romfile_id.unwrap())
-                    .unwrap()
-                    .path
-                    .ends_with(NSZ_EXTENSION)
-            })
-        });
+    let (nszs, others): (Partition, Partition) = others.into_iter().partition(|(_, roms)| {
+        roms.par_iter().any(|rom| {
+            romfiles_by_id
+                .get(&rom.romfile_id.unwrap())
+                .unwrap()
+                .path
+                .ends_with(NSZ_EXTENSION)
+        })
+    });
 
     // drop others
     drop(others);
 
+    (archives, nsps, nszs)
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn to_nsz(
+    connection: &mut SqliteConnection,
+    progress_bar: &ProgressBar,
+    roms_by_game_id: IndexMap<i64, Vec<Rom>>,
+    romfiles_by_id: HashMap<i64, Romfile>,
+    recompress: bool,
+    diff: bool,
+    check: bool,
+) -> SimpleResult<HashSet<i64>> {
+    let mut converted_game_ids: HashSet<i64> = HashSet::new();
+    let (archives, nsps, nszs) = partition_for_nsz(roms_by_game_id, &romfiles_by_id);
+
     // convert archives
     for roms in archives.values() {
         if roms.len() > 1 || !roms.first().unwrap().name.ends_with(NSP_EXTENSION) {
@@ -2682,6 +3562,7 @@ async fn to_nsz(
             .await?;
         archive_romfile.romfile.delete(progress_bar, false).await?;
 
+        converted_game_ids.insert(roms.first().unwrap().game_id);
         commit_transaction(transaction).await;
     }
 
@@ -2720,6 +3601,7 @@ async fn to_nsz(
             .await?;
         nsp_romfile.romfile.delete(progress_bar, false).await?;
 
+        converted_game_ids.insert(roms.first().unwrap().game_id);
         commit_transaction(transaction).await;
     }
 
@@ -2766,28 +3648,20 @@ async fn to_nsz(
                     .await?;
             };
 
+            converted_game_ids.insert(roms.first().unwrap().game_id);
             commit_transaction(transaction).await;
         }
     }
 
-    Ok(())
+    Ok(converted_game_ids)
 }
 
-#[allow(clippy::too_many_arguments)]
-async fn to_rvz(
-    connection: &mut SqliteConnection,
-    progress_bar: &ProgressBar,
-    roms_by_game_id: IndexMap<i64, Vec<Rom>>,
-    romfiles_by_id: HashMap<i64, Romfile>,
-    recompress: bool,
-    diff: bool,
-    check: bool,
-    compression_algorithm: &RvzCompressionAlgorithm,
-    compression_level: usize,
-    block_size: usize,
-) -> SimpleResult<()> {
+fn partition_for_rvz(
+    roms_by_game_id: Partition,
+    romfiles_by_id: &HashMap<i64, Romfile>,
+) -> (Partition, Partition, Partition, Partition) {
     // partition archives
-    let (archives, others): (IndexMap<i64, Vec<Rom>>, IndexMap<i64, Vec<Rom>>) =
+    let (archives, others): (Partition, Partition) =
         roms_by_game_id.into_iter().partition(|(_, roms)| {
             roms.par_iter().any(|rom| {
                 let romfile = romfiles_by_id.get(&rom.romfile_id.unwrap()).unwrap();
@@ -2796,32 +3670,60 @@ async fn to_rvz(
         });
 
     // partition ISOs
-    let (isos, others): (IndexMap<i64, Vec<Rom>>, IndexMap<i64, Vec<Rom>>) =
-        others.into_iter().partition(|(_, roms)| {
-            roms.par_iter().any(|rom| {
-                romfiles_by_id
-                    .get(&rom.romfile_id.unwrap())
-                    .unwrap()
-                    .path
-                    .ends_with(ISO_EXTENSION)
-            })
-        });
+    let (isos, others): (Partition, Partition) = others.into_iter().partition(|(_, roms)| {
+        roms.par_iter().any(|rom| {
+            romfiles_by_id
+                .get(&rom.romfile_id.unwrap())
+                .unwrap()
+                .path
+                .ends_with(ISO_EXTENSION)
+        })
+    });
+
+    // partition CHDs
+    let (chds, others): (Partition, Partition) = others.into_iter().partition(|(_, roms)| {
+        roms.par_iter().any(|rom| {
+            romfiles_by_id
+                .get(&rom.romfile_id.unwrap())
+                .unwrap()
+                .path
+                .ends_with(CHD_EXTENSION)
+        })
+    });
 
     // partition RVZs
-    let (rvzs, others): (IndexMap<i64, Vec<Rom>>, IndexMap<i64, Vec<Rom>>) =
-        others.into_iter().partition(|(_, roms)| {
-            roms.par_iter().any(|rom| {
-                romfiles_by_id
-                    .get(&rom.romfile_id.unwrap())
-                    .unwrap()
-                    .path
-                    .ends_with(RVZ_EXTENSION)
-            })
-        });
+    let (rvzs, others): (Partition, Partition) = others.into_iter().partition(|(_, roms)| {
+        roms.par_iter().any(|rom| {
+            romfiles_by_id
+                .get(&rom.romfile_id.unwrap())
+                .unwrap()
+                .path
+                .ends_with(RVZ_EXTENSION)
+        })
+    });
 
     // drop others
     drop(others);
 
+    (archives, isos, chds, rvzs)
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn to_rvz(
+    connection: &mut SqliteConnection,
+    progress_bar: &ProgressBar,
+    roms_by_game_id: IndexMap<i64, Vec<Rom>>,
+    romfiles_by_id: HashMap<i64, Romfile>,
+    recompress: bool,
+    diff: bool,
+    check: bool,
+    compression_algorithm: &RvzCompressionAlgorithm,
+    compression_level: usize,
+    block_size: usize,
+) -> SimpleResult<HashSet<i64>> {
+    let mut converted_game_ids: HashSet<i64> = HashSet::new();
+    let (archives, isos, chds, rvzs) = partition_for_rvz(roms_by_game_id, &romfiles_by_id);
+
     // convert archives
     for roms in archives.values() {
         if roms.len() > 1 || !roms.first().unwrap().name.ends_with(ISO_EXTENSION) {
@@ -2873,26 +3775,105 @@ async fn to_rvz(
             )
             .await?;
         }
-
-        rvz_romfile
-            .romfile
-            .update(&mut transaction, progress_bar, romfile.id)
-            .await?;
-        archive_romfile.romfile.delete(progress_bar, false).await?;
-
-        commit_transaction(transaction).await;
-    }
-
-    // convert ISOs
-    for roms in isos.values() {
-        let mut transaction = begin_transaction(connection).await;
-        let rom = roms.first().unwrap();
-        let romfile = romfiles_by_id.get(&rom.romfile_id.unwrap()).unwrap();
-        let iso_romfile = romfile.as_common(&mut transaction).await?.as_iso()?;
-        let rvz_romfile = iso_romfile
+
+        rvz_romfile
+            .romfile
+            .update(&mut transaction, progress_bar, romfile.id)
+            .await?;
+        archive_romfile.romfile.delete(progress_bar, false).await?;
+
+        converted_game_ids.insert(roms.first().unwrap().game_id);
+        commit_transaction(transaction).await;
+    }
+
+    // convert ISOs
+    for roms in isos.values() {
+        let mut transaction = begin_transaction(connection).await;
+        let rom = roms.first().unwrap();
+        let romfile = romfiles_by_id.get(&rom.romfile_id.unwrap()).unwrap();
+        let iso_romfile = romfile.as_common(&mut transaction).await?.as_iso()?;
+        let rvz_romfile = iso_romfile
+            .to_rvz(
+                progress_bar,
+                &iso_romfile.romfile.path.parent().unwrap(),
+                compression_algorithm,
+                compression_level,
+                block_size,
+                false,
+            )
+            .await?;
+        if check
+            && rvz_romfile
+                .check(&mut transaction, progress_bar, &None, &[rom])
+                .await
+                .is_err()
+        {
+            progress_bar.println("Converted file doesn't match the original");
+            rvz_romfile.romfile.delete(progress_bar, false).await?;
+            continue;
+        };
+        if diff {
+            print_diff(
+                &mut transaction,
+                progress_bar,
+                &[rom],
+                &[&iso_romfile.romfile],
+                &[&rvz_romfile.romfile],
+            )
+            .await?;
+        }
+        rvz_romfile
+            .romfile
+            .update(&mut transaction, progress_bar, romfile.id)
+            .await?;
+        iso_romfile.romfile.delete(progress_bar, false).await?;
+
+        converted_game_ids.insert(roms.first().unwrap().game_id);
+        commit_transaction(transaction).await;
+    }
+
+    // convert CHDs
+    for roms in chds.values() {
+        if roms.len() > 1 || !roms.first().unwrap().name.ends_with(ISO_EXTENSION) {
+            continue;
+        }
+        let tmp_directory = create_tmp_directory(connection).await?;
+        let mut transaction = begin_transaction(connection).await;
+        let rom = roms.first().unwrap();
+        let romfile = romfiles_by_id.get(&rom.romfile_id.unwrap()).unwrap();
+        if !find_romfiles_by_parent_id(&mut transaction, romfile.id)
+            .await
+            .is_empty()
+        {
+            progress_bar.println("CHD has children, skipping");
+            continue;
+        }
+        let chd_romfile = match romfile.parent_id {
+            Some(parent_id) => {
+                let parent_chd_romfile = find_romfile_by_id(&mut transaction, parent_id)
+                    .await
+                    .as_common(&mut transaction)
+                    .await?
+                    .as_chd()
+                    .await?;
+                romfile
+                    .as_common(&mut transaction)
+                    .await?
+                    .as_chd_with_parent(parent_chd_romfile)
+                    .await?
+            }
+            None => romfile.as_common(&mut transaction).await?.as_chd().await?,
+        };
+        if chd_romfile.chd_type != ChdType::Dvd {
+            continue;
+        }
+        // dolphin-tool seeks through its input, chdman output can't be piped to it
+        let rvz_romfile = chd_romfile
+            .to_iso(progress_bar, &tmp_directory.path())
+            .await?
             .to_rvz(
                 progress_bar,
-                &iso_romfile.romfile.path.parent().unwrap(),
+                &chd_romfile.romfile.path.parent().unwrap(),
                 compression_algorithm,
                 compression_level,
                 block_size,
@@ -2914,17 +3895,22 @@ async fn to_rvz(
                 &mut transaction,
                 progress_bar,
                 &[rom],
-                &[&iso_romfile.romfile],
+                &[&chd_romfile.romfile],
                 &[&rvz_romfile.romfile],
             )
             .await?;
         }
+
         rvz_romfile
             .romfile
             .update(&mut transaction, progress_bar, romfile.id)
             .await?;
-        iso_romfile.romfile.delete(progress_bar, false).await?;
+        if romfile.parent_id.is_some() {
+            update_romfile_parent(&mut transaction, romfile.id, None).await;
+        }
+        chd_romfile.romfile.delete(progress_bar, false).await?;
 
+        converted_game_ids.insert(roms.first().unwrap().game_id);
         commit_transaction(transaction).await;
     }
 
@@ -2980,25 +3966,20 @@ async fn to_rvz(
                 };
             }
 
+            converted_game_ids.insert(roms.first().unwrap().game_id);
             commit_transaction(transaction).await;
         }
     }
 
-    Ok(())
+    Ok(converted_game_ids)
 }
 
-#[allow(clippy::too_many_arguments)]
-async fn to_zso(
-    connection: &mut SqliteConnection,
-    progress_bar: &ProgressBar,
-    roms_by_game_id: IndexMap<i64, Vec<Rom>>,
-    romfiles_by_id: HashMap<i64, Romfile>,
-    recompress: bool,
-    diff: bool,
-    check: bool,
-) -> SimpleResult<()> {
+fn partition_for_zso(
+    roms_by_game_id: Partition,
+    romfiles_by_id: &HashMap<i64, Romfile>,
+) -> (Partition, Partition, Partition, Partition, Partition) {
     // partition archives
-    let (archives, others): (IndexMap<i64, Vec<Rom>>, IndexMap<i64, Vec<Rom>>) =
+    let (archives, others): (Partition, Partition) =
         roms_by_game_id.into_iter().partition(|(_, roms)| {
             roms.par_iter().any(|rom| {
                 let romfile = romfiles_by_id.get(&rom.romfile_id.unwrap()).unwrap();
@@ -3007,67 +3988,70 @@ async fn to_zso(
         });
 
     // partition ISOs
-    let (isos, others): (IndexMap<i64, Vec<Rom>>, IndexMap<i64, Vec<Rom>>) =
-        others.into_iter().partition(|(_, roms)| {
-            roms.par_iter().any(|rom| {
-                romfiles_by_id
-                    .get(&rom.romfile_id.unwrap())
-                    .unwrap()
-                    .path
-                    .ends_with(ISO_EXTENSION)
-            })
-        });
+    let (isos, others): (Partition, Partition) = others.into_iter().partition(|(_, roms)| {
+        roms.par_iter().any(|rom| {
+            romfiles_by_id
+                .get(&rom.romfile_id.unwrap())
+                .unwrap()
+                .path
+                .ends_with(ISO_EXTENSION)
+        })
+    });
 
     // partition CHDs
-    let (mut chds, others): (IndexMap<i64, Vec<Rom>>, IndexMap<i64, Vec<Rom>>) =
-        others.into_iter().partition(|(_, roms)| {
-            roms.par_iter().any(|rom| {
-                romfiles_by_id
-                    .get(&rom.romfile_id.unwrap())
-                    .unwrap()
-                    .path
-                    .ends_with(CHD_EXTENSION)
-            })
-        });
-    // make sure children are converted before parents
-    chds.par_sort_by(|_, a, _, b| {
-        b.par_iter()
-            .map(|rom| romfiles_by_id.get(&rom.romfile_id.unwrap()).unwrap())
-            .any(|romfile| romfile.parent_id.is_some())
-            .cmp(
-                &a.par_iter()
-                    .map(|rom| romfiles_by_id.get(&rom.romfile_id.unwrap()).unwrap())
-                    .any(|romfile| romfile.parent_id.is_some()),
-            )
+    let (mut chds, others): (Partition, Partition) = others.into_iter().partition(|(_, roms)| {
+        roms.par_iter().any(|rom| {
+            romfiles_by_id
+                .get(&rom.romfile_id.unwrap())
+                .unwrap()
+                .path
+                .ends_with(CHD_EXTENSION)
+        })
     });
+    // make sure children are converted before parents
+    sort_chds_by_parent_depth(&mut chds, &romfiles_by_id);
 
     // partition CSOs
-    let (csos, others): (IndexMap<i64, Vec<Rom>>, IndexMap<i64, Vec<Rom>>) =
-        others.into_iter().partition(|(_, roms)| {
-            roms.par_iter().any(|rom| {
-                romfiles_by_id
-                    .get(&rom.romfile_id.unwrap())
-                    .unwrap()
-                    .path
-                    .ends_with(CSO_EXTENSION)
-            })
-        });
+    let (csos, others): (Partition, Partition) = others.into_iter().partition(|(_, roms)| {
+        roms.par_iter().any(|rom| {
+            romfiles_by_id
+                .get(&rom.romfile_id.unwrap())
+                .unwrap()
+                .path
+                .ends_with(CSO_EXTENSION)
+        })
+    });
 
     // partition ZSOs
-    let (zsos, others): (IndexMap<i64, Vec<Rom>>, IndexMap<i64, Vec<Rom>>) =
-        others.into_iter().partition(|(_, roms)| {
-            roms.par_iter().any(|rom| {
-                romfiles_by_id
-                    .get(&rom.romfile_id.unwrap())
-                    .unwrap()
-                    .path
-                    .ends_with(ZSO_EXTENSION)
-            })
-        });
+    let (zsos, others): (Partition, Partition) = others.into_iter().partition(|(_, roms)| {
+        roms.par_iter().any(|rom| {
+            romfiles_by_id
+                .get(&rom.romfile_id.unwrap())
+                .unwrap()
+                .path
+                .ends_with(ZSO_EXTENSION)
+        })
+    });
 
     // drop others
     drop(others);
 
+    (archives, isos, chds, csos, zsos)
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn to_zso(
+    connection: &mut SqliteConnection,
+    progress_bar: &ProgressBar,
+    roms_by_game_id: IndexMap<i64, Vec<Rom>>,
+    romfiles_by_id: HashMap<i64, Romfile>,
+    recompress: bool,
+    diff: bool,
+    check: bool,
+) -> SimpleResult<HashSet<i64>> {
+    let mut converted_game_ids: HashSet<i64> = HashSet::new();
+    let (archives, isos, chds, csos, zsos) = partition_for_zso(roms_by_game_id, &romfiles_by_id);
+
     // convert archives
     for roms in archives.values() {
         if roms.len() > 1 || !roms.first().unwrap().name.ends_with(ISO_EXTENSION) {
@@ -3123,6 +4107,7 @@ async fn to_zso(
             .await?;
         archive_romfile.romfile.delete(progress_bar, false).await?;
 
+        converted_game_ids.insert(roms.first().unwrap().game_id);
         commit_transaction(transaction).await;
     }
 
@@ -3165,6 +4150,7 @@ async fn to_zso(
             .await?;
         iso_romfile.romfile.delete(progress_bar, false).await?;
 
+        converted_game_ids.insert(roms.first().unwrap().game_id);
         commit_transaction(transaction).await;
     }
 
@@ -3241,6 +4227,7 @@ async fn to_zso(
         }
         chd_romfile.romfile.delete(progress_bar, false).await?;
 
+        converted_game_ids.insert(roms.first().unwrap().game_id);
         commit_transaction(transaction).await;
     }
 
@@ -3286,6 +4273,7 @@ async fn to_zso(
             .await?;
         cso_romfile.romfile.delete(progress_bar, false).await?;
 
+        converted_game_ids.insert(roms.first().unwrap().game_id);
         commit_transaction(transaction).await;
     }
 
@@ -3332,22 +4320,26 @@ async fn to_zso(
                     .await?;
             };
 
+            converted_game_ids.insert(roms.first().unwrap().game_id);
             commit_transaction(transaction).await;
         }
     }
-    Ok(())
+    Ok(converted_game_ids)
 }
 
-async fn to_original(
-    connection: &mut SqliteConnection,
-    progress_bar: &ProgressBar,
-    system: &System,
-    roms_by_game_id: IndexMap<i64, Vec<Rom>>,
-    romfiles_by_id: HashMap<i64, Romfile>,
-    check: bool,
-) -> SimpleResult<()> {
+fn partition_for_original(
+    roms_by_game_id: Partition,
+    romfiles_by_id: &HashMap<i64, Romfile>,
+) -> (
+    Partition,
+    Partition,
+    Partition,
+    Partition,
+    Partition,
+    Partition,
+) {
     // partition archives
-    let (archives, others): (IndexMap<i64, Vec<Rom>>, IndexMap<i64, Vec<Rom>>) =
+    let (archives, others): (Partition, Partition) =
         roms_by_game_id.into_iter().partition(|(_, roms)| {
             roms.par_iter().any(|rom| {
                 let romfile = romfiles_by_id.get(&rom.romfile_id.unwrap()).unwrap();
@@ -3356,83 +4348,85 @@ async fn to_original(
         });
 
     // partition CHDs
-    let (mut chds, others): (IndexMap<i64, Vec<Rom>>, IndexMap<i64, Vec<Rom>>) =
-        others.into_iter().partition(|(_, roms)| {
-            roms.par_iter().any(|rom| {
-                romfiles_by_id
-                    .get(&rom.romfile_id.unwrap())
-                    .unwrap()
-                    .path
-                    .ends_with(CHD_EXTENSION)
-            })
-        });
-    // make sure children are converted before parents
-    chds.par_sort_by(|_, a, _, b| {
-        b.par_iter()
-            .map(|rom| romfiles_by_id.get(&rom.romfile_id.unwrap()).unwrap())
-            .any(|romfile| romfile.parent_id.is_some())
-            .cmp(
-                &a.par_iter()
-                    .map(|rom| romfiles_by_id.get(&rom.romfile_id.unwrap()).unwrap())
-                    .any(|romfile| romfile.parent_id.is_some()),
-            )
+    let (mut chds, others): (Partition, Partition) = others.into_iter().partition(|(_, roms)| {
+        roms.par_iter().any(|rom| {
+            romfiles_by_id
+                .get(&rom.romfile_id.unwrap())
+                .unwrap()
+                .path
+                .ends_with(CHD_EXTENSION)
+        })
     });
+    // make sure children are converted before parents
+    sort_chds_by_parent_depth(&mut chds, &romfiles_by_id);
 
     // partition CSOs
-    let (csos, others): (IndexMap<i64, Vec<Rom>>, IndexMap<i64, Vec<Rom>>) =
-        others.into_iter().partition(|(_, roms)| {
-            roms.par_iter().any(|rom| {
-                romfiles_by_id
-                    .get(&rom.romfile_id.unwrap())
-                    .unwrap()
-                    .path
-                    .ends_with(CSO_EXTENSION)
-            })
-        });
+    let (csos, others): (Partition, Partition) = others.into_iter().partition(|(_, roms)| {
+        roms.par_iter().any(|rom| {
+            romfiles_by_id
+                .get(&rom.romfile_id.unwrap())
+                .unwrap()
+                .path
+                .ends_with(CSO_EXTENSION)
+        })
+    });
 
     // partition NSZs
-    let (nszs, others): (IndexMap<i64, Vec<Rom>>, IndexMap<i64, Vec<Rom>>) =
-        others.into_iter().partition(|(_, roms)| {
-            roms.par_iter().any(|rom| {
-                romfiles_by_id
-                    .get(&rom.romfile_id.unwrap())
-                    .unwrap()
-                    .path
-                    .ends_with(NSP_EXTENSION)
-            })
-        });
+    let (nszs, others): (Partition, Partition) = others.into_iter().partition(|(_, roms)| {
+        roms.par_iter().any(|rom| {
+            romfiles_by_id
+                .get(&rom.romfile_id.unwrap())
+                .unwrap()
+                .path
+                .ends_with(NSP_EXTENSION)
+        })
+    });
 
     // partition RVZs
-    let (rvzs, others): (IndexMap<i64, Vec<Rom>>, IndexMap<i64, Vec<Rom>>) =
-        others.into_iter().partition(|(_, roms)| {
-            roms.par_iter().any(|rom| {
-                romfiles_by_id
-                    .get(&rom.romfile_id.unwrap())
-                    .unwrap()
-                    .path
-                    .ends_with(RVZ_EXTENSION)
-            })
-        });
+    let (rvzs, others): (Partition, Partition) = others.into_iter().partition(|(_, roms)| {
+        roms.par_iter().any(|rom| {
+            romfiles_by_id
+                .get(&rom.romfile_id.unwrap())
+                .unwrap()
+                .path
+                .ends_with(RVZ_EXTENSION)
+        })
+    });
 
     // partition ZSOs
-    let (zsos, others): (IndexMap<i64, Vec<Rom>>, IndexMap<i64, Vec<Rom>>) =
-        others.into_iter().partition(|(_, roms)| {
-            roms.par_iter().any(|rom| {
-                romfiles_by_id
-                    .get(&rom.romfile_id.unwrap())
-                    .unwrap()
-                    .path
-                    .ends_with(ZSO_EXTENSION)
-            })
-        });
+    let (zsos, others): (Partition, Partition) = others.into_iter().partition(|(_, roms)| {
+        roms.par_iter().any(|rom| {
+            romfiles_by_id
+                .get(&rom.romfile_id.unwrap())
+                .unwrap()
+                .path
+                .ends_with(ZSO_EXTENSION)
+        })
+    });
 
     // drop originals
     drop(others);
 
+    (archives, chds, csos, nszs, rvzs, zsos)
+}
+
+async fn to_original(
+    connection: &mut SqliteConnection,
+    progress_bar: &ProgressBar,
+    system: &System,
+    roms_by_game_id: IndexMap<i64, Vec<Rom>>,
+    romfiles_by_id: HashMap<i64, Romfile>,
+    check: bool,
+    strict: bool,
+) -> SimpleResult<HashSet<i64>> {
+    let mut converted_game_ids: HashSet<i64> = HashSet::new();
+    let (archives, chds, csos, nszs, rvzs, zsos) =
+        partition_for_original(roms_by_game_id, &romfiles_by_id);
+
     // convert archives
     for roms in archives.values() {
         if sevenzip::get_version().await.is_err() {
-            progress_bar.println("Please install sevenzip");
+            print_missing_tool(progress_bar, "sevenzip");
             break;
         }
         let mut transaction = begin_transaction(connection).await;
@@ -3445,7 +4439,15 @@ async fn to_original(
             .collect();
         romfiles.dedup();
         if romfiles.len() > 1 {
-            bail!("Multiple archives found");
+            if strict {
+                bail!("Multiple archives found");
+            }
+            let game = find_game_by_id(&mut transaction, roms.first().unwrap().game_id).await;
+            progress_bar.println(format!(
+                "Skipping \"{}\": ROM files span multiple archives",
+                game.name
+            ));
+            continue;
         }
         let romfile = romfiles.first().unwrap();
         let archive_romfiles = romfile
@@ -3534,6 +4536,7 @@ async fn to_original(
             .delete(progress_bar, false)
             .await?;
 
+        converted_game_ids.insert(roms.first().unwrap().game_id);
         commit_transaction(transaction).await;
     }
 
@@ -3544,7 +4547,7 @@ async fn to_original(
             continue;
         }
         if chdman::get_version().await.is_err() {
-            progress_bar.println("Please install chdman");
+            print_missing_tool(progress_bar, "chdman");
             break;
         }
         let mut transaction = begin_transaction(connection).await;
@@ -3598,23 +4601,48 @@ async fn to_original(
                     ));
                     continue;
                 }
-                let cue_romfile = romfiles_by_id
-                    .get(&cue_roms.first().unwrap().romfile_id.unwrap())
-                    .unwrap()
-                    .as_common(&mut transaction)
-                    .await?;
-                let cue_bin_romfile = chd_romfile
+                // prefer canonical cuesheets over the stored CUE files
+                let cuesheet_directory = create_tmp_directory(&mut transaction).await?;
+                let cuesheet_romfile = write_cuesheet(
+                    &mut transaction,
+                    progress_bar,
+                    bin_roms.first().unwrap().game_id,
+                    &cuesheet_directory.path(),
+                )
+                .await?;
+                let canonical = cuesheet_romfile.is_some();
+                let cue_romfile = match cuesheet_romfile {
+                    Some(cuesheet_romfile) => Some(cuesheet_romfile),
+                    None => match cue_roms.first() {
+                        Some(cue_rom) => Some(
+                            romfiles_by_id
+                                .get(&cue_rom.romfile_id.unwrap())
+                                .unwrap()
+                                .as_common(&mut transaction)
+                                .await?,
+                        ),
+                        None => None,
+                    },
+                };
+                let generated = cue_romfile.is_none();
+                let mut cue_bin_romfile = chd_romfile
                     .to_cue_bin(
                         progress_bar,
                         &chd_romfile.romfile.path.parent().unwrap(),
-                        Some(cue_romfile),
+                        cue_romfile,
                         &bin_roms,
                         false,
                     )
                     .await?;
 
                 if check {
-                    let mut error = false;
+                    let mut error = canonical
+                        && !cue_roms.is_empty()
+                        && cue_bin_romfile
+                            .cue_romfile
+                            .check(&mut transaction, progress_bar, &None, &cue_roms)
+                            .await
+                            .is_err();
                     for (bin_romfile, bin_rom) in cue_bin_romfile
                         .bin_romfiles
                         .iter()
@@ -3645,11 +4673,81 @@ async fn to_original(
                     }
                 }
 
-                if cue_roms.is_empty() {
-                    cue_bin_romfile
-                        .cue_romfile
-                        .delete(progress_bar, false)
-                        .await?;
+                // cue-less games keep the generated cue, named after the template and recorded as a cuesheet
+                if generated {
+                    let game =
+                        find_game_by_id(&mut transaction, bin_roms.first().unwrap().game_id).await;
+                    let name = chdman::get_cue_bin_name(
+                        &get_string(&mut transaction, "CUE_BIN_NAME_TEMPLATE")
+                            .await
+                            .unwrap(),
+                        system,
+                        &game,
+                    );
+                    cue_bin_romfile = chdman::rename_cue_bin(
+                        progress_bar,
+                        cue_bin_romfile,
+                        &chd_romfile.romfile.path.parent().unwrap(),
+                        &bin_roms,
+                        &name,
+                        false,
+                    )
+                    .await?;
+                }
+
+                match cue_roms.first() {
+                    Some(cue_rom) => {
+                        if canonical {
+                            let cue_romfile =
+                                romfiles_by_id.get(&cue_rom.romfile_id.unwrap()).unwrap();
+                            cue_bin_romfile
+                                .cue_romfile
+                                .rename(
+                                    progress_bar,
+                                    &cue_romfile.as_common(&mut transaction).await?.path,
+                                    false,
+                                )
+                                .await?
+                                .update(&mut transaction, progress_bar, cue_romfile.id)
+                                .await?;
+                        }
+                    }
+                    None => {
+                        if generated {
+                            let path = cue_bin_romfile
+                                .cue_romfile
+                                .get_relative_path(&mut transaction)
+                                .await?;
+                            match find_romfile_by_path(
+                                &mut transaction,
+                                path.as_os_str().to_str().unwrap(),
+                            )
+                            .await
+                            {
+                                Some(cuesheet_romfile) => {
+                                    cue_bin_romfile
+                                        .cue_romfile
+                                        .update(&mut transaction, progress_bar, cuesheet_romfile.id)
+                                        .await?;
+                                }
+                                None => {
+                                    cue_bin_romfile
+                                        .cue_romfile
+                                        .create(
+                                            &mut transaction,
+                                            progress_bar,
+                                            RomfileType::Cuesheet,
+                                        )
+                                        .await?;
+                                }
+                            }
+                        } else {
+                            cue_bin_romfile
+                                .cue_romfile
+                                .delete(progress_bar, false)
+                                .await?;
+                        }
+                    }
                 }
                 for (bin_romfile, bin_rom) in cue_bin_romfile
                     .bin_romfiles
@@ -3760,15 +4858,12 @@ async fn to_original(
             }
         }
 
+        converted_game_ids.insert(roms.first().unwrap().game_id);
         commit_transaction(transaction).await;
     }
 
     // convert CSOs
     for roms in csos.values() {
-        if maxcso::get_version().await.is_err() {
-            progress_bar.println("Please install maxcso");
-            break;
-        }
         let mut transaction = begin_transaction(connection).await;
         let rom = roms.first().unwrap();
         let romfile = romfiles_by_id.get(&rom.romfile_id.unwrap()).unwrap();
@@ -3795,13 +4890,14 @@ async fn to_original(
             .await?;
         cso_romfile.romfile.delete(progress_bar, false).await?;
 
+        converted_game_ids.insert(roms.first().unwrap().game_id);
         commit_transaction(transaction).await;
     }
 
     // convert NSZs
     for roms in nszs.values() {
         if nsz::get_version().await.is_err() {
-            progress_bar.println("Please install nsz");
+            print_missing_tool(progress_bar, "nsz");
             break;
         }
         let mut transaction = begin_transaction(connection).await;
@@ -3830,13 +4926,14 @@ async fn to_original(
             .await?;
         nsz_romfile.romfile.delete(progress_bar, false).await?;
 
+        converted_game_ids.insert(roms.first().unwrap().game_id);
         commit_transaction(transaction).await;
     }
 
     // convert RVZs
     for roms in rvzs.values() {
         if dolphin::get_version().await.is_err() {
-            progress_bar.println("Please install dolphin-tool");
+            print_missing_tool(progress_bar, "dolphin-tool");
             break;
         }
         let mut transaction = begin_transaction(connection).await;
@@ -3865,15 +4962,12 @@ async fn to_original(
             .await?;
         rvz_romfile.romfile.delete(progress_bar, false).await?;
 
+        converted_game_ids.insert(roms.first().unwrap().game_id);
         commit_transaction(transaction).await;
     }
 
     // convert ZSOs
     for roms in zsos.values() {
-        if maxcso::get_version().await.is_err() {
-            progress_bar.println("Please install maxcso");
-            break;
-        }
         let mut transaction = begin_transaction(connection).await;
         let rom = roms.first().unwrap();
         let romfile = romfiles_by_id.get(&rom.romfile_id.unwrap()).unwrap();
@@ -3900,10 +4994,11 @@ async fn to_original(
             .await?;
         zso_romfile.romfile.delete(progress_bar, false).await?;
 
+        converted_game_ids.insert(roms.first().unwrap().game_id);
         commit_transaction(transaction).await;
     }
 
-    Ok(())
+    Ok(converted_game_ids)
 }
 
 async fn print_diff(
@@ -3933,6 +5028,8 @@ async fn print_diff(
     Ok(())
 }
 
+#[cfg(test)]
+mod test_chd_parents_sort_by_parent_depth;
 #[cfg(test)]
 mod test_chd_parents_to_chd_should_not_touch_parent;
 #[cfg(test)]
@@ -3954,55 +5051,97 @@ mod test_iso_chd_to_cso;
 #[cfg(test)]
 mod test_iso_chd_to_iso;
 #[cfg(test)]
+mod test_iso_chd_to_rvz;
+#[cfg(test)]
 mod test_iso_chd_to_sevenzip_iso;
 #[cfg(test)]
 mod test_iso_chd_to_zso;
 #[cfg(test)]
+mod test_iso_estimate_zip;
+#[cfg(test)]
 mod test_iso_to_chd;
 #[cfg(test)]
 mod test_iso_to_chd_parents;
 #[cfg(test)]
 mod test_iso_to_cso;
+#[cfg(all(test, unix))]
+mod test_iso_to_cso_system_tool_path;
 #[cfg(test)]
 mod test_iso_to_rvz;
 #[cfg(test)]
 mod test_iso_to_zso;
 #[cfg(test)]
+mod test_multiple_discs_to_zip_per_disc;
+#[cfg(test)]
+mod test_multiple_discs_to_zip_per_game;
+#[cfg(test)]
+mod test_multiple_discs_to_zip_update_playlists;
+#[cfg(test)]
 mod test_multiple_tracks_chd_to_chd;
 #[cfg(test)]
 mod test_multiple_tracks_chd_to_cso_should_do_nothing;
 #[cfg(test)]
 mod test_multiple_tracks_chd_to_cue_bin;
 #[cfg(test)]
+mod test_multiple_tracks_chd_to_cue_bin_with_cuesheet;
+#[cfg(test)]
 mod test_multiple_tracks_chd_to_sevenzip_cue_bin;
 #[cfg(test)]
 mod test_multiple_tracks_chd_to_zso_should_do_nothing;
 #[cfg(test)]
 mod test_multiple_tracks_cue_bin_to_chd;
 #[cfg(test)]
+mod test_original_and_zip_to_zip_only_missing;
+#[cfg(test)]
+mod test_original_and_zip_to_zip_tally;
+#[cfg(all(test, unix))]
+mod test_original_to_chd_dreamcast_system_tool_path;
+#[cfg(test)]
+mod test_original_to_chd_tool_missing;
+#[cfg(test)]
 mod test_original_to_sevenzip;
 #[cfg(test)]
 mod test_original_to_zip;
 #[cfg(test)]
+mod test_original_to_zip_arcade_safe;
+#[cfg(test)]
+mod test_original_to_zip_dry_run;
+#[cfg(test)]
+mod test_original_to_zip_list;
+#[cfg(test)]
 mod test_original_to_zip_multiple_roms;
 #[cfg(test)]
+mod test_original_to_zip_single_rom;
+#[cfg(test)]
+mod test_original_to_zip_verify_source;
+#[cfg(test)]
 mod test_original_to_zip_with_correct_name;
 #[cfg(test)]
 mod test_original_to_zip_with_incorrect_name;
 #[cfg(test)]
+mod test_original_to_zip_write_manifest;
+#[cfg(test)]
 mod test_rvz_to_iso;
 #[cfg(test)]
 mod test_rvz_to_rvz;
 #[cfg(test)]
 mod test_rvz_to_sevenzip_iso;
 #[cfg(test)]
+mod test_saturn_multiple_tracks_cue_bin_to_chd_and_back;
+#[cfg(test)]
 mod test_sevenzip_iso_to_chd;
 #[cfg(test)]
 mod test_sevenzip_iso_to_cso;
 #[cfg(test)]
 mod test_sevenzip_iso_to_zso;
 #[cfg(test)]
+mod test_sevenzip_multiple_archives_to_chd;
+#[cfg(test)]
+mod test_sevenzip_multiple_archives_to_original;
+#[cfg(test)]
 mod test_sevenzip_multiple_tracks_cue_bin_to_chd;
+#[cfg(all(test, unix))]
+mod test_sevenzip_multiple_tracks_cue_bin_to_chd_single_extraction;
 #[cfg(test)]
 mod test_sevenzip_to_original;
 #[cfg(test)]
@@ -4012,12 +5151,20 @@ mod test_sevenzip_to_zip;
 #[cfg(test)]
 mod test_sevenzip_to_zip_multiple_files;
 #[cfg(test)]
+mod test_single_track_chd_to_cue_bin_name_template;
+#[cfg(test)]
 mod test_zip_to_original;
 #[cfg(test)]
+mod test_zip_to_original_non_arcade_override;
+#[cfg(test)]
 mod test_zip_to_sevenzip;
 #[cfg(test)]
 mod test_zip_to_zip;
 #[cfg(test)]
+mod test_zip_to_zip_preserve_method;
+#[cfg(test)]
+mod test_zip_to_zip_recompress_tally;
+#[cfg(test)]
 mod test_zip_to_zip_should_do_nothing;
 #[cfg(test)]
 mod test_zso_to_chd;
@@ -0,0 +1,46 @@
+use super::*;
+
+#[test]
+fn test() {
+    // given
+    let mut romfiles_by_id: HashMap<i64, Romfile> = HashMap::new();
+    let mut chds: IndexMap<i64, Vec<Rom>> = IndexMap::new();
+    // grandparent -> parent -> child, inserted in the worst order
+    for (id, parent_id) in [(1, None), (2, Some(1)), (3, Some(2))] {
+        romfiles_by_id.insert(
+            id,
+            Romfile {
+                id,
+                path: format!("Test Game (Disc {}).chd", id),
+                size: 0,
+                parent_id,
+                romfile_type: RomfileType::Romfile as i64,
+            },
+        );
+        chds.insert(
+            id,
+            vec![Rom {
+                id,
+                name: format!("Test Game (Disc {}).iso", id),
+                bios: false,
+                disk: false,
+                size: 0,
+                crc: None,
+                md5: None,
+                sha1: None,
+                sha256: None,
+                rom_status: None,
+                header_title: None,
+                game_id: id,
+                romfile_id: Some(id),
+                parent_id: None,
+            }],
+        );
+    }
+
+    // when
+    sort_chds_by_parent_depth(&mut chds, &romfiles_by_id);
+
+    // then
+    assert_eq!(chds.keys().copied().collect::<Vec<i64>>(), vec![3, 2, 1]);
+}
@@ -96,6 +96,7 @@ async fn test() {
         &None,
         true,
         false,
+        false,
     )
     .await
     .unwrap();
@@ -145,6 +146,7 @@ async fn test() {
         &None,
         true,
         false,
+        false,
     )
     .await
     .unwrap();
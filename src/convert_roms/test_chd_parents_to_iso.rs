@@ -96,6 +96,7 @@ async fn test() {
         &None,
         true,
         false,
+        false,
     )
     .await
     .unwrap();
@@ -120,6 +121,7 @@ async fn test() {
         roms_by_game_id,
         romfiles_by_id,
         false,
+        false,
     )
     .await
     .unwrap();
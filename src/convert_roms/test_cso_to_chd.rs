@@ -86,6 +86,7 @@ async fn test() {
         &None,
         false,
         false,
+        false,
     )
     .await
     .unwrap();
@@ -90,6 +90,7 @@ async fn test() {
         &None,
         false,
         false,
+        false,
     )
     .await
     .unwrap();
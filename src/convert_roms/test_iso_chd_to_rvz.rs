@@ -0,0 +1,131 @@
+use super::super::database::*;
+use super::super::import_dats;
+use super::super::import_roms;
+use super::*;
+use std::env;
+use std::path::{Path, PathBuf};
+use tempfile::{NamedTempFile, TempDir};
+use walkdir::WalkDir;
+
+#[tokio::test]
+async fn test() {
+    if chdman::get_version().await.is_err() || dolphin::get_version().await.is_err() {
+        return;
+    }
+
+    // given
+    let _guard = MUTEX.lock().await;
+
+    let test_directory = Path::new("tests");
+    env::set_var(
+        "PATH",
+        format!(
+            "{}:{}",
+            test_directory.as_os_str().to_str().unwrap(),
+            env::var("PATH").unwrap()
+        ),
+    );
+    let progress_bar = ProgressBar::hidden();
+
+    let db_file = NamedTempFile::new().unwrap();
+    let pool = establish_connection(db_file.path().to_str().unwrap()).await;
+    let mut connection = pool.acquire().await.unwrap();
+
+    let rom_directory = TempDir::new_in(&test_directory).unwrap();
+    set_rom_directory(PathBuf::from(rom_directory.path()));
+    let tmp_directory = TempDir::new_in(&test_directory).unwrap();
+    let tmp_directory = set_tmp_directory(PathBuf::from(tmp_directory.path()));
+
+    let matches = import_dats::subcommand()
+        .get_matches_from(&["import-dats", "tests/Test System (20230618) (RVZ).dat"]);
+    import_dats::main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    let chd_romfile = CommonRomfile::from_path(&test_directory.join("Test Game (USA).iso"))
+        .unwrap()
+        .as_iso()
+        .unwrap()
+        .to_chd(&progress_bar, &tmp_directory, &[], &None, None)
+        .await
+        .unwrap();
+
+    let system = find_systems(&mut connection).await.remove(0);
+    let system_directory = get_system_directory(&mut connection, &system)
+        .await
+        .unwrap();
+
+    let matches = import_roms::subcommand().get_matches_from(&[
+        "import-roms",
+        chd_romfile.romfile.path.as_os_str().to_str().unwrap(),
+    ]);
+    import_roms::main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    let roms = find_roms_with_romfile_by_system_id(&mut connection, system.id).await;
+    let romfile = find_romfile_by_id(&mut connection, roms[0].romfile_id.unwrap()).await;
+    let mut roms_by_game_id: IndexMap<i64, Vec<Rom>> = IndexMap::new();
+    roms_by_game_id.insert(roms[0].game_id, roms);
+    let mut romfiles_by_id: HashMap<i64, Romfile> = HashMap::new();
+    romfiles_by_id.insert(romfile.id, romfile);
+
+    // when
+    to_rvz(
+        &mut connection,
+        &progress_bar,
+        roms_by_game_id,
+        romfiles_by_id,
+        false,
+        true,
+        true,
+        &RvzCompressionAlgorithm::Zstd,
+        5,
+        128,
+    )
+    .await
+    .unwrap();
+
+    // then
+    let roms = find_roms_with_romfile_by_system_id(&mut connection, system.id).await;
+    assert_eq!(roms.len(), 1);
+    let romfiles = find_romfiles(&mut connection).await;
+    assert_eq!(romfiles.len(), 1);
+
+    let rom = roms.first().unwrap();
+    assert_eq!(rom.name, "Test Game (USA).iso");
+
+    let romfile = romfiles.first().unwrap();
+    assert_eq!(
+        romfile.path,
+        system_directory
+            .join("Test Game (USA).rvz")
+            .strip_prefix(&rom_directory)
+            .unwrap()
+            .as_os_str()
+            .to_str()
+            .unwrap(),
+    );
+    assert!(rom_directory.path().join(&romfile.path).is_file());
+    assert_eq!(rom.romfile_id, Some(romfile.id));
+
+    // the RVZ decompresses to the original ISO
+    romfile
+        .as_common(&mut connection)
+        .await
+        .unwrap()
+        .as_rvz()
+        .unwrap()
+        .check(&mut connection, &progress_bar, &None, &[rom])
+        .await
+        .unwrap();
+
+    // no intermediate ISO is left behind
+    for directory in [rom_directory.path(), tmp_directory.as_path()] {
+        assert!(!WalkDir::new(directory).into_iter().any(|entry| entry
+            .unwrap()
+            .path()
+            .extension()
+            .is_some_and(|extension| extension == ISO_EXTENSION)));
+    }
+}
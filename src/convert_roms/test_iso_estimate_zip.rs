@@ -0,0 +1,83 @@
+use super::super::database::*;
+use super::super::import_dats;
+use super::super::import_roms;
+use super::*;
+use std::path::{Path, PathBuf};
+use tempfile::{NamedTempFile, TempDir};
+use tokio::fs;
+
+#[tokio::test]
+async fn test() {
+    // given
+    let _guard = MUTEX.lock().await;
+
+    let test_directory = Path::new("tests");
+    let progress_bar = ProgressBar::hidden();
+
+    let db_file = NamedTempFile::new().unwrap();
+    let pool = establish_connection(db_file.path().to_str().unwrap()).await;
+    let mut connection = pool.acquire().await.unwrap();
+
+    let rom_directory = TempDir::new_in(&test_directory).unwrap();
+    set_rom_directory(PathBuf::from(rom_directory.path()));
+    let tmp_directory = TempDir::new_in(&test_directory).unwrap();
+    let tmp_directory = set_tmp_directory(PathBuf::from(tmp_directory.path()));
+
+    let matches = import_dats::subcommand()
+        .get_matches_from(&["import-dats", "tests/Test System (20200721).dat"]);
+    import_dats::main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    let romfile_path = tmp_directory.join("Test Game (USA, Europe).iso");
+    fs::copy(
+        test_directory.join("Test Game (USA, Europe).iso"),
+        &romfile_path,
+    )
+    .await
+    .unwrap();
+
+    let system = find_systems(&mut connection).await.remove(0);
+
+    let matches = import_roms::subcommand()
+        .get_matches_from(&["import-roms", romfile_path.as_os_str().to_str().unwrap()]);
+    import_roms::main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    let games = find_complete_games_by_system_id(&mut connection, system.id).await;
+    let roms =
+        find_roms_with_romfile_by_game_ids(&mut connection, &[games.first().unwrap().id]).await;
+    let romfile = find_romfile_by_id(&mut connection, roms[0].romfile_id.unwrap()).await;
+    let original_path = rom_directory.path().join(&romfile.path);
+    let original_size = romfile.size as u64;
+    let mut roms_by_game_id: IndexMap<i64, Vec<Rom>> = IndexMap::new();
+    roms_by_game_id.insert(roms[0].game_id, roms);
+    let games_by_id: HashMap<i64, Game> = games.into_iter().map(|game| (game.id, game)).collect();
+    let mut romfiles_by_id: HashMap<i64, Romfile> = HashMap::new();
+    romfiles_by_id.insert(romfile.id, romfile);
+
+    // when
+    let estimated_size = estimate(
+        &mut connection,
+        &progress_bar,
+        &system,
+        "ZIP",
+        games_by_id,
+        roms_by_game_id,
+        romfiles_by_id,
+    )
+    .await
+    .unwrap()
+    .unwrap();
+
+    // then
+    assert!(estimated_size > 0);
+    assert!(estimated_size < original_size);
+
+    let romfiles = find_romfiles(&mut connection).await;
+    assert_eq!(romfiles.len(), 1);
+    let romfile = romfiles.first().unwrap();
+    assert!(romfile.path.ends_with(".iso"));
+    assert!(original_path.is_file());
+}
@@ -97,6 +97,7 @@ async fn test() {
         &None,
         true,
         false,
+        false,
     )
     .await
     .unwrap();
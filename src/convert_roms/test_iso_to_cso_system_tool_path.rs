@@ -0,0 +1,114 @@
+use super::super::database::*;
+use super::super::import_dats;
+use super::super::import_roms;
+use super::*;
+use std::env;
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+use tempfile::{NamedTempFile, TempDir};
+use tokio::fs;
+
+// restores PATH even when an assertion fails
+struct PathGuard(String);
+
+impl Drop for PathGuard {
+    fn drop(&mut self) {
+        env::set_var("PATH", &self.0);
+    }
+}
+
+#[tokio::test]
+async fn test() {
+    // given
+    let _guard = MUTEX.lock().await;
+
+    let test_directory = Path::new("tests");
+    let path_guard = PathGuard(env::var("PATH").unwrap());
+    env::set_var(
+        "PATH",
+        format!(
+            "{}:{}",
+            test_directory.as_os_str().to_str().unwrap(),
+            path_guard.0
+        ),
+    );
+    let progress_bar = ProgressBar::hidden();
+
+    let db_file = NamedTempFile::new().unwrap();
+    let pool = establish_connection(db_file.path().to_str().unwrap()).await;
+    let mut connection = pool.acquire().await.unwrap();
+
+    let rom_directory = TempDir::new_in(&test_directory).unwrap();
+    set_rom_directory(PathBuf::from(rom_directory.path()));
+    let tmp_directory = TempDir::new_in(&test_directory).unwrap();
+    let tmp_directory = set_tmp_directory(PathBuf::from(tmp_directory.path()));
+
+    let matches = import_dats::subcommand()
+        .get_matches_from(&["import-dats", "tests/Test System (20200721).dat"]);
+    import_dats::main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    let romfile_path = tmp_directory.join("Test Game (USA, Europe).iso");
+    fs::copy(
+        test_directory.join("Test Game (USA, Europe).iso"),
+        &romfile_path,
+    )
+    .await
+    .unwrap();
+
+    let matches = import_roms::subcommand()
+        .get_matches_from(&["import-roms", romfile_path.as_os_str().to_str().unwrap()]);
+    import_roms::main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    // a wrapper leaving a trace before handing over to the maxcso in PATH
+    let tool_directory = tmp_directory.canonicalize().unwrap();
+    let marker_path = tool_directory.join("maxcso.called");
+    let tool_path = tool_directory.join("maxcso-custom");
+    fs::write(
+        &tool_path,
+        format!(
+            "#!/bin/sh\ntouch \"{}\"\nexec maxcso \"$@\"\n",
+            marker_path.as_os_str().to_str().unwrap()
+        ),
+    )
+    .await
+    .unwrap();
+    fs::set_permissions(&tool_path, std::fs::Permissions::from_mode(0o755))
+        .await
+        .unwrap();
+
+    let system = find_systems(&mut connection).await.remove(0);
+    add_to_list(
+        &mut connection,
+        "MAXCSO_PATHS",
+        &format!(
+            "{}={}",
+            system.name,
+            tool_path.as_os_str().to_str().unwrap()
+        ),
+    )
+    .await;
+
+    // when
+    let matches =
+        subcommand().get_matches_from(&["convert-roms", "-f", "CSO", "-s", "Test System"]);
+    let summary = main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    // then
+    assert_eq!(summary.converted, 1);
+    assert!(marker_path.is_file());
+
+    let romfiles = find_romfiles(&mut connection).await;
+    assert_eq!(romfiles.len(), 1);
+    let romfile = romfiles.first().unwrap();
+    assert!(romfile.path.ends_with("Test Game (USA, Europe) (ISO).cso"));
+    assert!(rom_directory.path().join(&romfile.path).is_file());
+
+    // other systems and contexts keep using the binary in PATH
+    assert_eq!(get_tool_path("maxcso"), PathBuf::from("maxcso"));
+}
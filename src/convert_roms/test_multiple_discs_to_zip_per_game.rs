@@ -0,0 +1,153 @@
+use super::super::database::*;
+use super::super::generate_playlists;
+use super::super::import_dats;
+use super::super::import_roms;
+use super::*;
+use std::path::{Path, PathBuf};
+use tempfile::{NamedTempFile, TempDir};
+use tokio::fs;
+
+#[tokio::test]
+async fn test() {
+    // given
+    let _guard = MUTEX.lock().await;
+
+    let test_directory = Path::new("tests");
+    let progress_bar = ProgressBar::hidden();
+
+    let db_file = NamedTempFile::new().unwrap();
+    let pool = establish_connection(db_file.path().to_str().unwrap()).await;
+    let mut connection = pool.acquire().await.unwrap();
+
+    let rom_directory = TempDir::new_in(&test_directory).unwrap();
+    set_rom_directory(PathBuf::from(rom_directory.path()));
+    let tmp_directory = TempDir::new_in(&test_directory).unwrap();
+    let tmp_directory = set_tmp_directory(PathBuf::from(tmp_directory.path()));
+
+    set_string(&mut connection, "MULTIDISC_ARCHIVE", "per-game").await;
+
+    let matches = import_dats::subcommand().get_matches_from(&[
+        "import-dats",
+        "tests/Test System (20230105) (Multiple Discs).dat",
+    ]);
+    import_dats::main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    let system = find_systems(&mut connection).await.remove(0);
+    let system_directory = get_system_directory(&mut connection, &system)
+        .await
+        .unwrap();
+
+    for i in 1..=2 {
+        let romfile_name = format!("Test Game (USA, Europe) (Disc {}).iso", i);
+        let romfile_path = tmp_directory.join(&romfile_name);
+        fs::copy(test_directory.join(&romfile_name), &romfile_path)
+            .await
+            .unwrap();
+        let matches = import_roms::subcommand()
+            .get_matches_from(&["import-roms", romfile_path.as_os_str().to_str().unwrap()]);
+        import_roms::main(&mut connection, &matches, &progress_bar)
+            .await
+            .unwrap();
+    }
+
+    let games = find_complete_games_by_system_id(&mut connection, system.id).await;
+    let roms = find_roms_with_romfile_by_game_ids(
+        &mut connection,
+        &games.iter().map(|game| game.id).collect::<Vec<i64>>(),
+    )
+    .await;
+    let romfiles = find_romfiles(&mut connection).await;
+    let mut roms_by_game_id: IndexMap<i64, Vec<Rom>> = IndexMap::new();
+    roms.into_iter().for_each(|rom| {
+        let group = roms_by_game_id.entry(rom.game_id).or_default();
+        group.push(rom);
+    });
+    let games_by_id: HashMap<i64, Game> = games.into_iter().map(|game| (game.id, game)).collect();
+    let romfiles_by_id: HashMap<i64, Romfile> = romfiles
+        .into_iter()
+        .map(|romfile| (romfile.id, romfile))
+        .collect();
+
+    // when
+    to_archive(
+        &mut connection,
+        &progress_bar,
+        &system,
+        games_by_id,
+        roms_by_game_id,
+        romfiles_by_id,
+        sevenzip::ArchiveType::Zip,
+        false,
+        false,
+        true,
+        &None,
+        false,
+        false,
+    )
+    .await
+    .unwrap();
+
+    let matches = generate_playlists::subcommand().get_matches_from(&["generate-playlists", "-a"]);
+    generate_playlists::main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    // then
+    let roms = find_roms_with_romfile_by_system_id(&mut connection, system.id).await;
+    assert_eq!(roms.len(), 2);
+    let romfiles = find_romfiles_by_ids(
+        &mut connection,
+        &roms
+            .iter()
+            .map(|rom| rom.romfile_id.unwrap())
+            .collect::<Vec<i64>>(),
+    )
+    .await;
+    assert_eq!(romfiles.len(), 1);
+
+    let romfile = romfiles.first().unwrap();
+    assert_eq!(
+        romfile.path,
+        system_directory
+            .join("Test Game (USA, Europe).zip")
+            .strip_prefix(&rom_directory)
+            .unwrap()
+            .as_os_str()
+            .to_str()
+            .unwrap(),
+    );
+    assert!(rom_directory.path().join(&romfile.path).is_file());
+    for rom in &roms {
+        assert_eq!(rom.romfile_id, Some(romfile.id));
+    }
+
+    let archive_romfiles = romfile
+        .as_common(&mut connection)
+        .await
+        .unwrap()
+        .as_archive(&progress_bar, None)
+        .await
+        .unwrap();
+    assert_eq!(archive_romfiles.len(), 2);
+
+    let playlist_path = system_directory.join("Test Game (USA, Europe).m3u");
+    assert!(playlist_path.is_file());
+    let lines = fs::read_to_string(playlist_path)
+        .await
+        .unwrap()
+        .split("\n")
+        .map(|s| s.to_owned())
+        .collect::<Vec<String>>();
+    assert_eq!(lines.len(), 3);
+    assert_eq!(
+        lines.first().unwrap(),
+        "Test Game (USA, Europe).zip#Test Game (USA, Europe) (Disc 1).iso"
+    );
+    assert_eq!(
+        lines.get(1).unwrap(),
+        "Test Game (USA, Europe).zip#Test Game (USA, Europe) (Disc 2).iso"
+    );
+    assert_eq!(lines.get(2).unwrap(), "");
+}
@@ -0,0 +1,99 @@
+use super::super::database::*;
+use super::super::generate_playlists;
+use super::super::import_dats;
+use super::super::import_roms;
+use super::*;
+use std::path::{Path, PathBuf};
+use tempfile::{NamedTempFile, TempDir};
+use tokio::fs;
+
+#[tokio::test]
+async fn test() {
+    // given
+    let _guard = MUTEX.lock().await;
+
+    let test_directory = Path::new("tests");
+    let progress_bar = ProgressBar::hidden();
+
+    let db_file = NamedTempFile::new().unwrap();
+    let pool = establish_connection(db_file.path().to_str().unwrap()).await;
+    let mut connection = pool.acquire().await.unwrap();
+
+    let rom_directory = TempDir::new_in(&test_directory).unwrap();
+    set_rom_directory(PathBuf::from(rom_directory.path()));
+    let tmp_directory = TempDir::new_in(&test_directory).unwrap();
+    let tmp_directory = set_tmp_directory(PathBuf::from(tmp_directory.path()));
+
+    let matches = import_dats::subcommand().get_matches_from(&[
+        "import-dats",
+        "tests/Test System (20230105) (Multiple Discs).dat",
+    ]);
+    import_dats::main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    let system = find_systems(&mut connection).await.remove(0);
+    let system_directory = get_system_directory(&mut connection, &system)
+        .await
+        .unwrap();
+
+    for i in 1..=2 {
+        let romfile_name = format!("Test Game (USA, Europe) (Disc {}).iso", i);
+        let romfile_path = tmp_directory.join(&romfile_name);
+        fs::copy(test_directory.join(&romfile_name), &romfile_path)
+            .await
+            .unwrap();
+        let matches = import_roms::subcommand()
+            .get_matches_from(&["import-roms", romfile_path.as_os_str().to_str().unwrap()]);
+        import_roms::main(&mut connection, &matches, &progress_bar)
+            .await
+            .unwrap();
+    }
+
+    let matches = generate_playlists::subcommand().get_matches_from(&["generate-playlists", "-a"]);
+    generate_playlists::main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    // when
+    let matches =
+        subcommand().get_matches_from(&["convert-roms", "-a", "-f", "ZIP", "--update-playlists"]);
+    main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    // then
+    let games = find_complete_games_by_system_id(&mut connection, system.id).await;
+    assert_eq!(games.len(), 2);
+    let playlist_id = games.first().unwrap().playlist_id;
+    assert!(playlist_id.is_some());
+    assert!(games.iter().all(|game| game.playlist_id == playlist_id));
+
+    let playlist = find_romfile_by_id(&mut connection, playlist_id.unwrap()).await;
+    let playlist_path = system_directory.join("Test Game (USA, Europe).m3u");
+    assert_eq!(
+        playlist.path,
+        playlist_path
+            .strip_prefix(&rom_directory)
+            .unwrap()
+            .as_os_str()
+            .to_str()
+            .unwrap()
+    );
+    let lines = fs::read_to_string(playlist_path)
+        .await
+        .unwrap()
+        .split("\n")
+        .map(|s| s.to_owned())
+        .collect::<Vec<String>>();
+    assert_eq!(lines.len(), 3);
+    assert_eq!(
+        lines.first().unwrap(),
+        "Test Game (USA, Europe) (Disc 1).zip"
+    );
+    assert_eq!(
+        lines.get(1).unwrap(),
+        "Test Game (USA, Europe) (Disc 2).zip"
+    );
+    assert_eq!(lines.get(2).unwrap(), "");
+}
@@ -117,6 +117,7 @@ async fn test() {
         &None,
         false,
         false,
+        false,
     )
     .await
     .unwrap();
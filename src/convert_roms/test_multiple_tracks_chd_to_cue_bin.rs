@@ -81,6 +81,7 @@ async fn test() {
         roms_by_game_id,
         romfiles_by_id,
         true,
+        false,
     )
     .await
     .unwrap();
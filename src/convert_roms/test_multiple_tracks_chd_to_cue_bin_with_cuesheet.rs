@@ -0,0 +1,139 @@
+use super::super::database::*;
+use super::super::import_cuesheets;
+use super::super::import_dats;
+use super::super::import_roms;
+use super::*;
+use std::path::{Path, PathBuf};
+use tempfile::{NamedTempFile, TempDir};
+use tokio::fs;
+
+#[tokio::test]
+async fn test() {
+    if let Ok(version) = chdman::get_version().await {
+        if version.as_str().cmp(chdman::MIN_SPLITBIN_VERSION) == Ordering::Less {
+            return;
+        }
+    }
+
+    // given
+    let _guard = MUTEX.lock().await;
+
+    let test_directory = Path::new("tests");
+    let progress_bar = ProgressBar::hidden();
+
+    let db_file = NamedTempFile::new().unwrap();
+    let pool = establish_connection(db_file.path().to_str().unwrap()).await;
+    let mut connection = pool.acquire().await.unwrap();
+
+    let rom_directory = TempDir::new_in(&test_directory).unwrap();
+    set_rom_directory(PathBuf::from(rom_directory.path()));
+    let tmp_directory = TempDir::new_in(&test_directory).unwrap();
+    let tmp_directory = set_tmp_directory(PathBuf::from(tmp_directory.path()));
+
+    let matches = import_dats::subcommand()
+        .get_matches_from(&["import-dats", "tests/Test System (20200721).dat"]);
+    import_dats::main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    let cue_romfile_path = tmp_directory.join("Test Game (USA, Europe) (Multiple Tracks).cue");
+    fs::copy(
+        test_directory.join("Test Game (USA, Europe) (Multiple Tracks).cue"),
+        &cue_romfile_path,
+    )
+    .await
+    .unwrap();
+    let chd_romfile_path = tmp_directory.join("Test Game (USA, Europe) (Multiple Tracks).chd");
+    fs::copy(
+        test_directory.join("Test Game (USA, Europe) (Multiple Tracks).chd"),
+        &chd_romfile_path,
+    )
+    .await
+    .unwrap();
+
+    let system = find_systems(&mut connection).await.remove(0);
+    let system_directory = get_system_directory(&mut connection, &system)
+        .await
+        .unwrap();
+
+    let matches = import_roms::subcommand().get_matches_from(&[
+        "import-roms",
+        chd_romfile_path.as_os_str().to_str().unwrap(),
+        cue_romfile_path.as_os_str().to_str().unwrap(),
+    ]);
+    import_roms::main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    // canonical cuesheets only differ from the stored CUE files by their line endings
+    let cuesheet_content =
+        fs::read_to_string(test_directory.join("Test Game (USA, Europe) (Multiple Tracks).cue"))
+            .await
+            .unwrap()
+            .replace("\r\n", "\n")
+            .replace('\n', "\r\n");
+    let cuesheet_path = tmp_directory.join("Test Game (USA, Europe) (CUE BIN).cue");
+    fs::write(&cuesheet_path, &cuesheet_content).await.unwrap();
+
+    let matches = import_cuesheets::subcommand().get_matches_from(&[
+        "import-cuesheets",
+        "-s",
+        "Test System",
+        cuesheet_path.as_os_str().to_str().unwrap(),
+    ]);
+    import_cuesheets::main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+    fs::remove_file(&cuesheet_path).await.unwrap();
+
+    let roms = find_roms_with_romfile_by_system_id(&mut connection, system.id).await;
+    let mut romfiles_by_id: HashMap<i64, Romfile> = HashMap::new();
+    for rom in &roms {
+        let romfile = find_romfile_by_id(&mut connection, rom.romfile_id.unwrap()).await;
+        romfiles_by_id.insert(romfile.id, romfile);
+    }
+    let mut roms_by_game_id: IndexMap<i64, Vec<Rom>> = IndexMap::new();
+    roms_by_game_id.insert(roms[0].game_id, roms);
+
+    // when
+    to_original(
+        &mut connection,
+        &progress_bar,
+        &system,
+        roms_by_game_id,
+        romfiles_by_id,
+        false,
+        false,
+    )
+    .await
+    .unwrap();
+
+    // then
+    let roms = find_roms_with_romfile_by_system_id(&mut connection, system.id).await;
+    assert_eq!(roms.len(), 3);
+    let romfiles = find_romfiles(&mut connection).await;
+    assert_eq!(romfiles.len(), 3);
+
+    let rom = roms.get(2).unwrap();
+    assert_eq!(rom.name, "Test Game (USA, Europe) (CUE BIN).cue");
+
+    let romfile = romfiles.get(2).unwrap();
+    assert_eq!(
+        romfile.path,
+        system_directory
+            .join("Test Game (USA, Europe) (CUE BIN).cue")
+            .strip_prefix(&rom_directory)
+            .unwrap()
+            .as_os_str()
+            .to_str()
+            .unwrap(),
+    );
+    assert_eq!(romfile.size, cuesheet_content.len() as i64);
+    assert_eq!(rom.romfile_id, Some(romfile.id));
+    assert_eq!(
+        fs::read_to_string(rom_directory.path().join(&romfile.path))
+            .await
+            .unwrap(),
+        cuesheet_content
+    );
+}
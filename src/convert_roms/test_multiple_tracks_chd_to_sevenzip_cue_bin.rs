@@ -89,6 +89,7 @@ async fn test() {
         true,
         &None,
         false,
+        false,
     )
     .await
     .unwrap();
@@ -105,6 +105,7 @@ async fn test() {
         &None,
         false,
         false,
+        false,
     )
     .await
     .unwrap();
@@ -0,0 +1,121 @@
+use super::super::database::*;
+use super::super::import_dats;
+use super::super::import_roms;
+use super::*;
+use std::path::{Path, PathBuf};
+use tempfile::{NamedTempFile, TempDir};
+use tokio::fs;
+
+#[tokio::test]
+async fn test() {
+    // given
+    let _guard = MUTEX.lock().await;
+
+    let test_directory = Path::new("tests");
+    let progress_bar = ProgressBar::hidden();
+
+    let db_file = NamedTempFile::new().unwrap();
+    let pool = establish_connection(db_file.path().to_str().unwrap()).await;
+    let mut connection = pool.acquire().await.unwrap();
+
+    let rom_directory = TempDir::new_in(&test_directory).unwrap();
+    set_rom_directory(PathBuf::from(rom_directory.path()));
+    let tmp_directory = TempDir::new_in(&test_directory).unwrap();
+    let tmp_directory = set_tmp_directory(PathBuf::from(tmp_directory.path()));
+
+    let matches = import_dats::subcommand()
+        .get_matches_from(&["import-dats", "tests/Test System (20200721).dat"]);
+    import_dats::main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    let zip_path = tmp_directory.join("Test Game (USA, Europe).rom.zip");
+    fs::copy(
+        test_directory.join("Test Game (USA, Europe).rom.zip"),
+        &zip_path,
+    )
+    .await
+    .unwrap();
+    let original_path = tmp_directory.join("Test Game (Japan).rom");
+    fs::copy(test_directory.join("Test Game (Japan).rom"), &original_path)
+        .await
+        .unwrap();
+
+    let system = find_systems(&mut connection).await.remove(0);
+
+    let matches = import_roms::subcommand().get_matches_from(&[
+        "import-roms",
+        zip_path.as_os_str().to_str().unwrap(),
+        original_path.as_os_str().to_str().unwrap(),
+    ]);
+    import_roms::main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    let games = find_complete_games_by_system_id(&mut connection, system.id).await;
+    let roms = find_roms_with_romfile_by_game_ids(
+        &mut connection,
+        &games.iter().map(|game| game.id).collect::<Vec<i64>>(),
+    )
+    .await;
+    let romfiles = find_romfiles(&mut connection).await;
+    let zip_romfile = romfiles
+        .iter()
+        .find(|romfile| romfile.path.ends_with(ZIP_EXTENSION))
+        .unwrap();
+    let zip_romfile_id = zip_romfile.id;
+    let old_mtime = fs::metadata(rom_directory.path().join(&zip_romfile.path))
+        .await
+        .unwrap()
+        .modified()
+        .unwrap();
+    let mut roms_by_game_id: IndexMap<i64, Vec<Rom>> = IndexMap::new();
+    roms.into_iter().for_each(|rom| {
+        roms_by_game_id.entry(rom.game_id).or_default().push(rom);
+    });
+    let games_by_id: HashMap<i64, Game> = games.into_iter().map(|game| (game.id, game)).collect();
+    let romfiles_by_id: HashMap<i64, Romfile> = romfiles
+        .into_iter()
+        .map(|romfile| (romfile.id, romfile))
+        .collect();
+
+    // when
+    let compliant_count = trim_compliant_games("ZIP", &mut roms_by_game_id, &romfiles_by_id);
+    to_archive(
+        &mut connection,
+        &progress_bar,
+        &system,
+        games_by_id,
+        roms_by_game_id,
+        romfiles_by_id,
+        sevenzip::ArchiveType::Zip,
+        false,
+        false,
+        true,
+        &None,
+        false,
+        false,
+    )
+    .await
+    .unwrap();
+
+    // then
+    assert_eq!(compliant_count, 1);
+
+    let romfiles = find_romfiles(&mut connection).await;
+    assert_eq!(romfiles.len(), 2);
+    assert!(romfiles
+        .iter()
+        .all(|romfile| romfile.path.ends_with(ZIP_EXTENSION)));
+
+    let zip_romfile = romfiles
+        .iter()
+        .find(|romfile| romfile.id == zip_romfile_id)
+        .unwrap();
+    let new_mtime = fs::metadata(rom_directory.path().join(&zip_romfile.path))
+        .await
+        .unwrap()
+        .modified()
+        .unwrap();
+    assert_eq!(old_mtime, new_mtime);
+}
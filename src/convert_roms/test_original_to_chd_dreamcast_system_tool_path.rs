@@ -0,0 +1,91 @@
+use super::super::database::*;
+use super::super::import_dats;
+use super::super::import_roms;
+use super::*;
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+use tempfile::{NamedTempFile, TempDir};
+use tokio::fs;
+
+#[tokio::test]
+async fn test() {
+    // given
+    let _guard = MUTEX.lock().await;
+
+    let test_directory = Path::new("tests");
+    let progress_bar = ProgressBar::hidden();
+
+    let db_file = NamedTempFile::new().unwrap();
+    let pool = establish_connection(db_file.path().to_str().unwrap()).await;
+    let mut connection = pool.acquire().await.unwrap();
+
+    let rom_directory = TempDir::new_in(&test_directory).unwrap();
+    set_rom_directory(PathBuf::from(rom_directory.path()));
+    let tmp_directory = TempDir::new_in(&test_directory).unwrap();
+    let tmp_directory = set_tmp_directory(PathBuf::from(tmp_directory.path()));
+
+    let dat_path = tmp_directory.join("Sega - Dreamcast (20200721).dat");
+    fs::write(
+        &dat_path,
+        fs::read_to_string(test_directory.join("Test System (20200721).dat"))
+            .await
+            .unwrap()
+            .replace("Test System", "Sega - Dreamcast"),
+    )
+    .await
+    .unwrap();
+    let matches = import_dats::subcommand()
+        .get_matches_from(&["import-dats", dat_path.as_os_str().to_str().unwrap()]);
+    import_dats::main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    let romfile_path = tmp_directory.join("Test Game (USA, Europe).rom");
+    fs::copy(
+        test_directory.join("Test Game (USA, Europe).rom"),
+        &romfile_path,
+    )
+    .await
+    .unwrap();
+
+    let matches = import_roms::subcommand()
+        .get_matches_from(&["import-roms", romfile_path.as_os_str().to_str().unwrap()]);
+    import_roms::main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    // a chdman too old for Dreamcast games, whatever version is in PATH
+    let tool_path = tmp_directory.canonicalize().unwrap().join("chdman-old");
+    fs::write(
+        &tool_path,
+        "#!/bin/sh\necho \"chdman - MAME Compressed Hunks of Data (CHD) manager 0.200\"\n",
+    )
+    .await
+    .unwrap();
+    fs::set_permissions(&tool_path, std::fs::Permissions::from_mode(0o755))
+        .await
+        .unwrap();
+    add_to_list(
+        &mut connection,
+        "CHDMAN_PATHS",
+        &format!(
+            "Sega - Dreamcast={}",
+            tool_path.as_os_str().to_str().unwrap()
+        ),
+    )
+    .await;
+
+    // when
+    let matches =
+        subcommand().get_matches_from(&["convert-roms", "-f", "CHD", "-s", "Sega - Dreamcast"]);
+    let summary = main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    // then
+    assert_eq!(summary.converted, 0);
+
+    let romfiles = find_romfiles(&mut connection).await;
+    assert_eq!(romfiles.len(), 1);
+    assert!(romfiles[0].path.ends_with(".rom"));
+}
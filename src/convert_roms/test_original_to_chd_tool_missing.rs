@@ -0,0 +1,74 @@
+use super::super::database::*;
+use super::super::import_dats;
+use super::super::import_roms;
+use super::*;
+use std::path::{Path, PathBuf};
+use tempfile::{NamedTempFile, TempDir};
+use tokio::fs;
+
+#[tokio::test]
+async fn test() {
+    // given
+    let _guard = MUTEX.lock().await;
+
+    let test_directory = Path::new("tests");
+    let progress_bar = ProgressBar::hidden();
+
+    let db_file = NamedTempFile::new().unwrap();
+    let pool = establish_connection(db_file.path().to_str().unwrap()).await;
+    let mut connection = pool.acquire().await.unwrap();
+
+    let rom_directory = TempDir::new_in(&test_directory).unwrap();
+    set_rom_directory(PathBuf::from(rom_directory.path()));
+    let tmp_directory = TempDir::new_in(&test_directory).unwrap();
+    let tmp_directory = set_tmp_directory(PathBuf::from(tmp_directory.path()));
+
+    let matches = import_dats::subcommand()
+        .get_matches_from(&["import-dats", "tests/Test System (20200721).dat"]);
+    import_dats::main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    let romfile_path = tmp_directory.join("Test Game (USA, Europe).rom");
+    fs::copy(
+        test_directory.join("Test Game (USA, Europe).rom"),
+        &romfile_path,
+    )
+    .await
+    .unwrap();
+
+    let matches = import_roms::subcommand()
+        .get_matches_from(&["import-roms", romfile_path.as_os_str().to_str().unwrap()]);
+    import_roms::main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    // point chdman to a binary that doesn't exist
+    add_to_list(
+        &mut connection,
+        "CHDMAN_PATHS",
+        &format!(
+            "Test System={}",
+            tmp_directory.join("chdman").as_os_str().to_str().unwrap()
+        ),
+    )
+    .await;
+
+    reset_outcome();
+
+    let matches =
+        subcommand().get_matches_from(&["convert-roms", "-f", "CHD", "-s", "Test System"]);
+
+    // when
+    main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    // then
+    assert_eq!(get_outcome(), Outcome::ToolMissing);
+    assert_eq!(get_outcome() as u8, 4);
+
+    let romfiles = find_romfiles(&mut connection).await;
+    assert_eq!(romfiles.len(), 1);
+    assert!(romfiles[0].path.ends_with(".rom"));
+}
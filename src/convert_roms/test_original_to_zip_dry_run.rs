@@ -0,0 +1,92 @@
+use super::super::database::*;
+use super::super::import_dats;
+use super::super::import_roms;
+use super::*;
+use std::path::{Path, PathBuf};
+use tempfile::{NamedTempFile, TempDir};
+use tokio::fs;
+
+#[tokio::test]
+async fn test() {
+    // given
+    let _guard = MUTEX.lock().await;
+
+    let test_directory = Path::new("tests");
+    let progress_bar = ProgressBar::hidden();
+
+    let db_file = NamedTempFile::new().unwrap();
+    let pool = establish_connection(db_file.path().to_str().unwrap()).await;
+    let mut connection = pool.acquire().await.unwrap();
+
+    let rom_directory = TempDir::new_in(&test_directory).unwrap();
+    set_rom_directory(PathBuf::from(rom_directory.path()));
+    let tmp_directory = TempDir::new_in(&test_directory).unwrap();
+    let tmp_directory = set_tmp_directory(PathBuf::from(tmp_directory.path()));
+
+    let matches = import_dats::subcommand()
+        .get_matches_from(&["import-dats", "tests/Test System (20200721).dat"]);
+    import_dats::main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    let mut romfile_paths: Vec<PathBuf> = vec![];
+    for romfile_name in &["Test Game (Japan).rom", "Test Game (USA, Europe).rom"] {
+        let romfile_path = tmp_directory.join(romfile_name);
+        fs::copy(test_directory.join(romfile_name), &romfile_path)
+            .await
+            .unwrap();
+        romfile_paths.push(romfile_path);
+    }
+
+    let matches = import_roms::subcommand().get_matches_from(&[
+        "import-roms",
+        romfile_paths[0].as_os_str().to_str().unwrap(),
+        romfile_paths[1].as_os_str().to_str().unwrap(),
+    ]);
+    import_roms::main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    let original_romfiles: Vec<(String, i64)> = find_romfiles(&mut connection)
+        .await
+        .into_iter()
+        .map(|romfile| (romfile.path, romfile.size))
+        .collect();
+
+    let matches = subcommand().get_matches_from(&[
+        "convert-roms",
+        "-f",
+        "ZIP",
+        "-s",
+        "Test System",
+        "--dry-run",
+    ]);
+
+    // when
+    let summary = main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    // then
+    assert_eq!(summary.converted, 0);
+    assert_eq!(
+        find_romfiles(&mut connection)
+            .await
+            .into_iter()
+            .map(|romfile| (romfile.path, romfile.size))
+            .collect::<Vec<(String, i64)>>(),
+        original_romfiles
+    );
+    for (path, _) in &original_romfiles {
+        assert!(rom_directory.path().join(path).is_file());
+    }
+
+    assert_eq!(summary.count, 2);
+    assert_eq!(
+        summary.bytes_in,
+        original_romfiles
+            .iter()
+            .map(|(_, size)| *size as u64)
+            .sum::<u64>()
+    );
+}
@@ -0,0 +1,134 @@
+use super::super::database::*;
+use super::super::import_dats;
+use super::super::import_roms;
+use super::*;
+use std::path::{Path, PathBuf};
+use tempfile::{NamedTempFile, TempDir};
+use tokio::fs;
+
+#[tokio::test]
+async fn test() {
+    // given
+    let _guard = MUTEX.lock().await;
+
+    let test_directory = Path::new("tests");
+    let progress_bar = ProgressBar::hidden();
+
+    let db_file = NamedTempFile::new().unwrap();
+    let pool = establish_connection(db_file.path().to_str().unwrap()).await;
+    let mut connection = pool.acquire().await.unwrap();
+
+    let rom_directory = TempDir::new_in(&test_directory).unwrap();
+    set_rom_directory(PathBuf::from(rom_directory.path()));
+    let tmp_directory = TempDir::new_in(&test_directory).unwrap();
+    let tmp_directory = set_tmp_directory(PathBuf::from(tmp_directory.path()));
+
+    let matches = import_dats::subcommand()
+        .get_matches_from(&["import-dats", "tests/Test System (20200721).dat"]);
+    import_dats::main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    let mut romfile_paths: Vec<PathBuf> = vec![];
+    for romfile_name in &["Test Game (Japan).rom", "Test Game (USA, Europe).rom"] {
+        let romfile_path = tmp_directory.join(romfile_name);
+        fs::copy(test_directory.join(romfile_name), &romfile_path)
+            .await
+            .unwrap();
+        romfile_paths.push(romfile_path);
+    }
+
+    let matches = import_roms::subcommand().get_matches_from(&[
+        "import-roms",
+        romfile_paths[0].as_os_str().to_str().unwrap(),
+        romfile_paths[1].as_os_str().to_str().unwrap(),
+    ]);
+    import_roms::main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    // the japanese game is already compliant
+    let matches = subcommand().get_matches_from(&[
+        "convert-roms",
+        "-f",
+        "ZIP",
+        "-s",
+        "Test System",
+        "-g",
+        "Test Game (Japan)",
+    ]);
+    main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    let system = find_systems(&mut connection).await.remove(0);
+    let games = find_complete_games_by_system_id(&mut connection, system.id).await;
+    let roms = find_roms_with_romfile_by_system_id(&mut connection, system.id).await;
+    let original_paths: Vec<String> = find_romfiles(&mut connection)
+        .await
+        .into_iter()
+        .map(|romfile| romfile.path)
+        .collect();
+    let mut roms_by_game_id: IndexMap<i64, Vec<Rom>> = IndexMap::new();
+    roms.into_iter().for_each(|rom| {
+        let group = roms_by_game_id.entry(rom.game_id).or_default();
+        group.push(rom);
+    });
+    let games_by_id: HashMap<i64, Game> = games.into_iter().map(|game| (game.id, game)).collect();
+    let romfiles_by_id: HashMap<i64, Romfile> = find_romfiles(&mut connection)
+        .await
+        .into_iter()
+        .map(|romfile| (romfile.id, romfile))
+        .collect();
+
+    let matches = subcommand().get_matches_from(&[
+        "convert-roms",
+        "-f",
+        "ZIP",
+        "-s",
+        "Test System",
+        "--list",
+    ]);
+
+    // when
+    let summary = main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+    let game_ids: Vec<i64> = list_conversions(
+        &progress_bar,
+        "ZIP",
+        false,
+        &games_by_id,
+        roms_by_game_id,
+        &romfiles_by_id,
+        false,
+    )
+    .into_keys()
+    .collect();
+
+    // then
+    assert_eq!(summary.converted, 0);
+    assert_eq!(
+        find_romfiles(&mut connection)
+            .await
+            .into_iter()
+            .map(|romfile| romfile.path)
+            .collect::<Vec<String>>(),
+        original_paths
+    );
+
+    assert_eq!(game_ids.len(), 1);
+    let game = games_by_id.get(game_ids.first().unwrap()).unwrap();
+    assert_eq!(game.name, "Test Game (USA, Europe)");
+
+    // a real run converts exactly the listed games
+    let matches =
+        subcommand().get_matches_from(&["convert-roms", "-f", "ZIP", "-s", "Test System"]);
+    let summary = main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+    assert_eq!(summary.converted, game_ids.len());
+    let roms = find_roms_with_romfile_by_game_ids(&mut connection, &game_ids).await;
+    let romfile = find_romfile_by_id(&mut connection, roms[0].romfile_id.unwrap()).await;
+    assert!(romfile.path.ends_with("Test Game (USA, Europe).zip"));
+}
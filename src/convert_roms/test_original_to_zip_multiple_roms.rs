@@ -80,6 +80,7 @@ async fn test() {
         true,
         &None,
         false,
+        false,
     )
     .await
     .unwrap();
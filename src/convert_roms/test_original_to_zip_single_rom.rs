@@ -0,0 +1,98 @@
+use super::super::database::*;
+use super::super::import_dats;
+use super::super::import_roms;
+use super::*;
+use std::path::{Path, PathBuf};
+use tempfile::{NamedTempFile, TempDir};
+use tokio::fs;
+
+#[tokio::test]
+async fn test() {
+    // given
+    let _guard = MUTEX.lock().await;
+
+    let test_directory = Path::new("tests");
+    let progress_bar = ProgressBar::hidden();
+
+    let db_file = NamedTempFile::new().unwrap();
+    let pool = establish_connection(db_file.path().to_str().unwrap()).await;
+    let mut connection = pool.acquire().await.unwrap();
+
+    let rom_directory = TempDir::new_in(&test_directory).unwrap();
+    set_rom_directory(PathBuf::from(rom_directory.path()));
+    let tmp_directory = TempDir::new_in(&test_directory).unwrap();
+    let tmp_directory = set_tmp_directory(PathBuf::from(tmp_directory.path()));
+
+    let matches = import_dats::subcommand()
+        .get_matches_from(&["import-dats", "tests/Test System (20230527) (PSN).dat"]);
+    import_dats::main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    let mut romfile_paths: Vec<PathBuf> = vec![];
+    let romfile_path = tmp_directory.join("UP0001-BLUS00001.pkg");
+    fs::copy(test_directory.join("UP0001-BLUS00001.pkg"), &romfile_path)
+        .await
+        .unwrap();
+    romfile_paths.push(romfile_path);
+    let romfile_path = tmp_directory.join("prfgmHWxGNxsfJ.rap");
+    fs::copy(test_directory.join("prfgmHWxGNxsfJ.rap"), &romfile_path)
+        .await
+        .unwrap();
+    romfile_paths.push(romfile_path);
+
+    let system = find_systems(&mut connection).await.remove(0);
+    let system_directory = get_system_directory(&mut connection, &system)
+        .await
+        .unwrap();
+
+    for romfile_path in romfile_paths {
+        let matches = import_roms::subcommand()
+            .get_matches_from(&["import-roms", romfile_path.as_os_str().to_str().unwrap()]);
+        import_roms::main(&mut connection, &matches, &progress_bar)
+            .await
+            .unwrap();
+    }
+
+    let matches = subcommand().get_matches_from(&[
+        "convert-roms",
+        "-f",
+        "ZIP",
+        "-s",
+        "Test System (PSN)",
+        "--rom",
+        "UP0001-BLUS00001.pkg",
+    ]);
+
+    // when
+    main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    // then
+    let roms = find_roms_with_romfile_by_system_id(&mut connection, system.id).await;
+    assert_eq!(roms.len(), 2);
+    let romfiles = find_romfiles(&mut connection).await;
+    assert_eq!(romfiles.len(), 2);
+
+    let rom = roms.first().unwrap();
+    assert_eq!(rom.name, "UP0001-BLUS00001.pkg");
+    let romfile = find_romfile_by_id(&mut connection, rom.romfile_id.unwrap()).await;
+    assert!(romfile.path.ends_with(ZIP_EXTENSION));
+    assert!(rom_directory.path().join(&romfile.path).is_file());
+
+    let rom = roms.get(1).unwrap();
+    assert_eq!(rom.name, "prfgmHWxGNxsfJ.rap");
+    let romfile = find_romfile_by_id(&mut connection, rom.romfile_id.unwrap()).await;
+    assert_eq!(
+        romfile.path,
+        system_directory
+            .join("prfgmHWxGNxsfJ.rap")
+            .strip_prefix(&rom_directory)
+            .unwrap()
+            .as_os_str()
+            .to_str()
+            .unwrap(),
+    );
+    assert!(rom_directory.path().join(&romfile.path).is_file());
+}
@@ -0,0 +1,88 @@
+use super::super::database::*;
+use super::super::import_dats;
+use super::super::import_roms;
+use super::*;
+use std::path::{Path, PathBuf};
+use tempfile::{NamedTempFile, TempDir};
+use tokio::fs;
+
+#[tokio::test]
+async fn test() {
+    // given
+    let _guard = MUTEX.lock().await;
+
+    let test_directory = Path::new("tests");
+    let progress_bar = ProgressBar::hidden();
+
+    let db_file = NamedTempFile::new().unwrap();
+    let pool = establish_connection(db_file.path().to_str().unwrap()).await;
+    let mut connection = pool.acquire().await.unwrap();
+
+    let rom_directory = TempDir::new_in(&test_directory).unwrap();
+    set_rom_directory(PathBuf::from(rom_directory.path()));
+    let tmp_directory = TempDir::new_in(&test_directory).unwrap();
+    let tmp_directory = set_tmp_directory(PathBuf::from(tmp_directory.path()));
+
+    let matches = import_dats::subcommand()
+        .get_matches_from(&["import-dats", "tests/Test System (20200721).dat"]);
+    import_dats::main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    let mut romfile_paths: Vec<PathBuf> = vec![];
+    for romfile_name in &["Test Game (Japan).rom", "Test Game (USA, Europe).rom"] {
+        let romfile_path = tmp_directory.join(romfile_name);
+        fs::copy(test_directory.join(romfile_name), &romfile_path)
+            .await
+            .unwrap();
+        romfile_paths.push(romfile_path);
+    }
+
+    let matches = import_roms::subcommand().get_matches_from(&[
+        "import-roms",
+        romfile_paths[0].as_os_str().to_str().unwrap(),
+        romfile_paths[1].as_os_str().to_str().unwrap(),
+    ]);
+    import_roms::main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    // corrupt the japanese copy in place
+    let corrupt_romfile = find_romfiles(&mut connection)
+        .await
+        .into_iter()
+        .find(|romfile| romfile.path.ends_with("Test Game (Japan).rom"))
+        .unwrap();
+    let corrupt_path = rom_directory.path().join(&corrupt_romfile.path);
+    let mut data = fs::read(&corrupt_path).await.unwrap();
+    data[0] = !data[0];
+    fs::write(&corrupt_path, data).await.unwrap();
+
+    let matches = subcommand().get_matches_from(&[
+        "convert-roms",
+        "-f",
+        "ZIP",
+        "-s",
+        "Test System",
+        "--verify-source",
+    ]);
+
+    // when
+    let summary = main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    // then
+    assert_eq!(summary.converted, 1);
+    assert_eq!(summary.skipped, 1);
+
+    let romfiles = find_romfiles(&mut connection).await;
+    assert_eq!(romfiles.len(), 2);
+    assert!(romfiles
+        .iter()
+        .any(|romfile| romfile.path.ends_with("Test Game (USA, Europe).zip")));
+    assert!(romfiles
+        .iter()
+        .any(|romfile| romfile.path == corrupt_romfile.path));
+    assert!(corrupt_path.is_file());
+}
@@ -0,0 +1,85 @@
+use super::super::database::*;
+use super::super::import_dats;
+use super::super::import_roms;
+use super::*;
+use std::path::{Path, PathBuf};
+use tempfile::{NamedTempFile, TempDir};
+use tokio::fs;
+
+#[tokio::test]
+async fn test() {
+    // given
+    let _guard = MUTEX.lock().await;
+
+    let test_directory = Path::new("tests");
+    let progress_bar = ProgressBar::hidden();
+
+    let db_file = NamedTempFile::new().unwrap();
+    let pool = establish_connection(db_file.path().to_str().unwrap()).await;
+    let mut connection = pool.acquire().await.unwrap();
+
+    let rom_directory = TempDir::new_in(&test_directory).unwrap();
+    set_rom_directory(PathBuf::from(rom_directory.path()));
+    let tmp_directory = TempDir::new_in(&test_directory).unwrap();
+    let tmp_directory = set_tmp_directory(PathBuf::from(tmp_directory.path()));
+
+    set_bool(&mut connection, "CONVERT_WRITE_MANIFEST", true).await;
+
+    let matches = import_dats::subcommand()
+        .get_matches_from(&["import-dats", "tests/Test System (20200721).dat"]);
+    import_dats::main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    let romfile_path = tmp_directory.join("Test Game (USA, Europe).rom");
+    fs::copy(
+        test_directory.join("Test Game (USA, Europe).rom"),
+        &romfile_path,
+    )
+    .await
+    .unwrap();
+
+    let system = find_systems(&mut connection).await.remove(0);
+    let system_directory = get_system_directory(&mut connection, &system)
+        .await
+        .unwrap();
+
+    let matches = import_roms::subcommand()
+        .get_matches_from(&["import-roms", romfile_path.as_os_str().to_str().unwrap()]);
+    import_roms::main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    let manifest_path = system_directory.join(CHECKSUMS_FILE_NAME);
+    write_checksum_manifests(&mut connection, &progress_bar)
+        .await
+        .unwrap();
+    assert_eq!(
+        fs::read_to_string(&manifest_path).await.unwrap(),
+        "; Generated by oxyromon\nTest Game (USA, Europe).rom CC721E14\n"
+    );
+
+    let matches =
+        subcommand().get_matches_from(&["convert-roms", "-f", "ZIP", "-s", "Test System"]);
+
+    // when
+    main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    // then
+    let archive_path = system_directory.join("Test Game (USA, Europe).zip");
+    assert!(archive_path.is_file());
+    let (crc, _) = CommonRomfile::from_path(&archive_path)
+        .unwrap()
+        .get_hash_and_size(&mut connection, &progress_bar, 1, 1, &HashAlgorithm::Crc)
+        .await
+        .unwrap();
+    assert_eq!(
+        fs::read_to_string(&manifest_path).await.unwrap(),
+        format!(
+            "; Generated by oxyromon\nTest Game (USA, Europe).zip {}\n",
+            crc.to_uppercase()
+        )
+    );
+}
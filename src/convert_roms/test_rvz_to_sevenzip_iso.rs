@@ -72,6 +72,7 @@ async fn test() {
         true,
         &None,
         false,
+        false,
     )
     .await
     .unwrap();
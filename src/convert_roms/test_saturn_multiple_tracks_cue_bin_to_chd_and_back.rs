@@ -0,0 +1,123 @@
+use super::super::database::*;
+use super::super::import_dats;
+use super::super::import_roms;
+use super::*;
+use std::path::{Path, PathBuf};
+use tempfile::{NamedTempFile, TempDir};
+use tokio::fs;
+
+#[tokio::test]
+async fn test() {
+    if let Ok(version) = chdman::get_version().await {
+        if version.as_str().cmp(chdman::MIN_SPLITBIN_VERSION) == Ordering::Less {
+            return;
+        }
+    }
+
+    // given
+    let _guard = MUTEX.lock().await;
+
+    let test_directory = Path::new("tests");
+    let progress_bar = ProgressBar::hidden();
+
+    let db_file = NamedTempFile::new().unwrap();
+    let pool = establish_connection(db_file.path().to_str().unwrap()).await;
+    let mut connection = pool.acquire().await.unwrap();
+
+    let rom_directory = TempDir::new_in(&test_directory).unwrap();
+    set_rom_directory(PathBuf::from(rom_directory.path()));
+    let tmp_directory = TempDir::new_in(&test_directory).unwrap();
+    let tmp_directory = set_tmp_directory(PathBuf::from(tmp_directory.path()));
+
+    let matches = import_dats::subcommand()
+        .get_matches_from(&["import-dats", "tests/Test System (20250301) (Saturn).dat"]);
+    import_dats::main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    // a mode 2 data track followed by an audio track with a pregap
+    for file_name in [
+        "Test Game (Japan) (Saturn).cue",
+        "Test Game (Japan) (Saturn) (Track 1).bin",
+        "Test Game (Japan) (Saturn) (Track 2).bin",
+    ] {
+        let romfile_path = tmp_directory.join(file_name);
+        fs::copy(test_directory.join(file_name), &romfile_path)
+            .await
+            .unwrap();
+        let matches = import_roms::subcommand()
+            .get_matches_from(&["import-roms", romfile_path.as_os_str().to_str().unwrap()]);
+        import_roms::main(&mut connection, &matches, &progress_bar)
+            .await
+            .unwrap();
+    }
+
+    let system = find_systems(&mut connection).await.remove(0);
+
+    // when
+    let matches =
+        subcommand().get_matches_from(&["convert-roms", "-f", "CHD", "-s", "Test System"]);
+    main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    // then
+    let romfiles = find_romfiles(&mut connection).await;
+    assert_eq!(romfiles.len(), 2);
+    assert!(romfiles[0].path.ends_with(".chd"));
+
+    // the cue chdman generates keeps the track modes and points to the DAT bin names
+    let bin_roms: Vec<Rom> = find_roms_with_romfile_by_system_id(&mut connection, system.id)
+        .await
+        .into_iter()
+        .filter(|rom| rom.name.ends_with(".bin"))
+        .collect();
+    let cue_bin_romfile = romfiles[0]
+        .as_common(&mut connection)
+        .await
+        .unwrap()
+        .as_chd()
+        .await
+        .unwrap()
+        .to_cue_bin(
+            &progress_bar,
+            &tmp_directory,
+            None,
+            &bin_roms.iter().collect::<Vec<&Rom>>(),
+            true,
+        )
+        .await
+        .unwrap();
+    let cue = fs::read_to_string(&cue_bin_romfile.cue_romfile.path)
+        .await
+        .unwrap();
+    assert!(cue.contains("FILE \"Test Game (Japan) (Saturn) (Track 1).bin\" BINARY"));
+    assert!(cue.contains("TRACK 01 MODE2/2352"));
+    assert!(cue.contains("FILE \"Test Game (Japan) (Saturn) (Track 2).bin\" BINARY"));
+    assert!(cue.contains("TRACK 02 AUDIO"));
+
+    // when
+    let matches =
+        subcommand().get_matches_from(&["convert-roms", "-f", "ORIGINAL", "-s", "Test System"]);
+    main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    // then
+    let roms = find_roms_with_romfile_by_system_id(&mut connection, system.id).await;
+    assert_eq!(roms.len(), 3);
+    let romfiles = find_romfiles(&mut connection).await;
+    assert_eq!(romfiles.len(), 3);
+
+    for rom in roms {
+        let romfile = find_romfile_by_id(&mut connection, rom.romfile_id.unwrap()).await;
+        let (sha1, size) = CommonRomfile::from_path(&rom_directory.path().join(&romfile.path))
+            .unwrap()
+            .get_hash_and_size(&mut connection, &progress_bar, 1, 1, &HashAlgorithm::Sha1)
+            .await
+            .unwrap();
+        assert_eq!(romfile.path.rsplit('/').next().unwrap(), rom.name);
+        assert_eq!(Some(sha1), rom.sha1);
+        assert_eq!(size as i64, rom.size);
+    }
+}
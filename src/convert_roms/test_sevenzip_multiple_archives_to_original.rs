@@ -0,0 +1,137 @@
+use super::super::database::*;
+use super::super::import_dats;
+use super::super::import_roms;
+use super::*;
+use std::path::{Path, PathBuf};
+use tempfile::{NamedTempFile, TempDir};
+use tokio::fs;
+
+#[tokio::test]
+async fn test() {
+    // given
+    let _guard = MUTEX.lock().await;
+
+    let test_directory = Path::new("tests");
+    let progress_bar = ProgressBar::hidden();
+
+    let db_file = NamedTempFile::new().unwrap();
+    let pool = establish_connection(db_file.path().to_str().unwrap()).await;
+    let mut connection = pool.acquire().await.unwrap();
+
+    let rom_directory = TempDir::new_in(&test_directory).unwrap();
+    set_rom_directory(PathBuf::from(rom_directory.path()));
+    let tmp_directory = TempDir::new_in(&test_directory).unwrap();
+    let tmp_directory = set_tmp_directory(PathBuf::from(tmp_directory.path()));
+
+    let matches = import_dats::subcommand()
+        .get_matches_from(&["import-dats", "tests/Test System (20200721).dat"]);
+    import_dats::main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    for romfile_name in &[
+        "Test Game (USA, Europe) (Full).7z",
+        "Test Game (USA, Europe).rom.7z",
+    ] {
+        let romfile_path = tmp_directory.join(romfile_name);
+        fs::copy(test_directory.join(romfile_name), &romfile_path)
+            .await
+            .unwrap();
+        let matches = import_roms::subcommand()
+            .get_matches_from(&["import-roms", romfile_path.as_os_str().to_str().unwrap()]);
+        import_roms::main(&mut connection, &matches, &progress_bar)
+            .await
+            .unwrap();
+    }
+
+    let system = find_systems(&mut connection).await.remove(0);
+    let system_directory = get_system_directory(&mut connection, &system)
+        .await
+        .unwrap();
+
+    // move the last track of the CUE BIN game to a second archive
+    let split_archive_path = system_directory.join("Test Game (USA, Europe) (Split).7z");
+    fs::copy(
+        test_directory.join("Test Game (USA, Europe) (Full).7z"),
+        &split_archive_path,
+    )
+    .await
+    .unwrap();
+    let split_romfile_id = create_romfile(
+        &mut connection,
+        split_archive_path
+            .strip_prefix(&rom_directory)
+            .unwrap()
+            .as_os_str()
+            .to_str()
+            .unwrap(),
+        split_archive_path.metadata().unwrap().len(),
+        RomfileType::Romfile,
+    )
+    .await;
+    let roms = find_roms_with_romfile_by_system_id(&mut connection, system.id).await;
+    let split_rom = roms
+        .iter()
+        .find(|rom| rom.name == "Test Game (USA, Europe) (CUE BIN) (Track 02).bin")
+        .unwrap();
+    update_rom_romfile(&mut connection, split_rom.id, Some(split_romfile_id)).await;
+
+    let roms = find_roms_with_romfile_by_system_id(&mut connection, system.id).await;
+    let mut roms_by_game_id: IndexMap<i64, Vec<Rom>> = IndexMap::new();
+    for rom in roms {
+        roms_by_game_id.entry(rom.game_id).or_default().push(rom);
+    }
+    let romfiles_by_id: HashMap<i64, Romfile> = find_romfiles(&mut connection)
+        .await
+        .into_iter()
+        .map(|romfile| (romfile.id, romfile))
+        .collect();
+
+    // when
+    to_original(
+        &mut connection,
+        &progress_bar,
+        &system,
+        roms_by_game_id,
+        romfiles_by_id,
+        true,
+        false,
+    )
+    .await
+    .unwrap();
+
+    // then
+    let roms = find_roms_with_romfile_by_system_id(&mut connection, system.id).await;
+    assert_eq!(roms.len(), 4);
+    let romfiles = find_romfiles(&mut connection).await;
+    assert_eq!(romfiles.len(), 3);
+
+    for rom in roms.iter().filter(|rom| rom.name.contains("(CUE BIN)")) {
+        let romfile = romfiles
+            .iter()
+            .find(|romfile| romfile.id == rom.romfile_id.unwrap())
+            .unwrap();
+        assert!(romfile.path.ends_with(SEVENZIP_EXTENSION));
+        assert!(rom_directory.path().join(&romfile.path).is_file());
+    }
+
+    let rom = roms
+        .iter()
+        .find(|rom| rom.name == "Test Game (USA, Europe).rom")
+        .unwrap();
+    let romfile = romfiles
+        .iter()
+        .find(|romfile| romfile.id == rom.romfile_id.unwrap())
+        .unwrap();
+    assert_eq!(
+        romfile.path,
+        system_directory
+            .join("Test Game (USA, Europe).rom")
+            .strip_prefix(&rom_directory)
+            .unwrap()
+            .as_os_str()
+            .to_str()
+            .unwrap(),
+    );
+    assert!(rom_directory.path().join(&romfile.path).is_file());
+}
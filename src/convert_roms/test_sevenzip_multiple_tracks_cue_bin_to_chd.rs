@@ -85,6 +85,7 @@ async fn test() {
         &None,
         false,
         false,
+        false,
     )
     .await
     .unwrap();
@@ -0,0 +1,148 @@
+use super::super::database::*;
+use super::super::import_dats;
+use super::super::import_roms;
+use super::*;
+use std::env;
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+use tempfile::{NamedTempFile, TempDir};
+use tokio::fs;
+
+// restores PATH even when an assertion fails
+struct PathGuard(String);
+
+impl Drop for PathGuard {
+    fn drop(&mut self) {
+        env::set_var("PATH", &self.0);
+    }
+}
+
+#[tokio::test]
+async fn test() {
+    if let Ok(version) = chdman::get_version().await {
+        if version.as_str().cmp(chdman::MIN_SPLITBIN_VERSION) == Ordering::Less {
+            return;
+        }
+    }
+
+    // given
+    let _guard = MUTEX.lock().await;
+
+    let test_directory = Path::new("tests");
+    let progress_bar = ProgressBar::hidden();
+
+    let db_file = NamedTempFile::new().unwrap();
+    let pool = establish_connection(db_file.path().to_str().unwrap()).await;
+    let mut connection = pool.acquire().await.unwrap();
+
+    let rom_directory = TempDir::new_in(&test_directory).unwrap();
+    set_rom_directory(PathBuf::from(rom_directory.path()));
+    let tmp_directory = TempDir::new_in(&test_directory).unwrap();
+    let tmp_directory = set_tmp_directory(PathBuf::from(tmp_directory.path()));
+
+    let matches = import_dats::subcommand()
+        .get_matches_from(&["import-dats", "tests/Test System (20200721).dat"]);
+    import_dats::main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    let romfile_path = tmp_directory.join("Test Game (USA, Europe) (Full).7z");
+    fs::copy(
+        test_directory.join("Test Game (USA, Europe) (Full).7z"),
+        &romfile_path,
+    )
+    .await
+    .unwrap();
+
+    let system = find_systems(&mut connection).await.remove(0);
+    let system_directory = get_system_directory(&mut connection, &system)
+        .await
+        .unwrap();
+
+    let matches = import_roms::subcommand()
+        .get_matches_from(&["import-roms", romfile_path.as_os_str().to_str().unwrap()]);
+    import_roms::main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    let games = find_complete_games_by_system_id(&mut connection, system.id).await;
+    let roms = find_roms_with_romfile_by_system_id(&mut connection, system.id).await;
+    let games_by_id: HashMap<i64, Game> = games.into_iter().map(|game| (game.id, game)).collect();
+    let mut romfiles_by_id: HashMap<i64, Romfile> = HashMap::new();
+    for rom in &roms {
+        let romfile = find_romfile_by_id(&mut connection, rom.romfile_id.unwrap()).await;
+        romfiles_by_id.insert(romfile.id, romfile);
+    }
+    let mut roms_by_game_id: IndexMap<i64, Vec<Rom>> = IndexMap::new();
+    roms_by_game_id.insert(roms[0].game_id, roms);
+
+    // a wrapper logging extractions before handing over to the 7-Zip in PATH
+    let sevenzip_path = get_executable_path(sevenzip::SEVENZIP_EXECUTABLES).unwrap();
+    let tool_directory = tmp_directory.join("bin");
+    fs::create_dir(&tool_directory).await.unwrap();
+    let tool_directory = tool_directory.canonicalize().unwrap();
+    let log_path = tool_directory.join("extractions.log");
+    for executable in sevenzip::SEVENZIP_EXECUTABLES {
+        let tool_path = tool_directory.join(executable);
+        fs::write(
+            &tool_path,
+            format!(
+                "#!/bin/sh\n[ \"$1\" = x ] && echo x >> \"{}\"\nexec \"{}\" \"$@\"\n",
+                log_path.as_os_str().to_str().unwrap(),
+                sevenzip_path.as_os_str().to_str().unwrap()
+            ),
+        )
+        .await
+        .unwrap();
+        fs::set_permissions(&tool_path, std::fs::Permissions::from_mode(0o755))
+            .await
+            .unwrap();
+    }
+    let path_guard = PathGuard(env::var("PATH").unwrap());
+    env::set_var(
+        "PATH",
+        format!(
+            "{}:{}",
+            tool_directory.as_os_str().to_str().unwrap(),
+            path_guard.0
+        ),
+    );
+
+    // when
+    to_chd(
+        &mut connection,
+        &progress_bar,
+        games_by_id,
+        roms_by_game_id,
+        romfiles_by_id,
+        false,
+        true,
+        true,
+        &[],
+        &None,
+        &[],
+        &None,
+        &[],
+        &None,
+        &[],
+        &None,
+        false,
+        false,
+        false,
+    )
+    .await
+    .unwrap();
+
+    // then
+    drop(path_guard);
+    let extractions = fs::read_to_string(&log_path).await.unwrap();
+    assert_eq!(extractions.lines().count(), 1);
+
+    let roms = find_roms_with_romfile_by_system_id(&mut connection, system.id).await;
+    assert_eq!(roms.len(), 3);
+    let romfiles = find_romfiles(&mut connection).await;
+    assert_eq!(romfiles.len(), 2);
+    assert!(romfiles
+        .iter()
+        .all(|romfile| rom_directory.path().join(&romfile.path).is_file()));
+}
@@ -71,6 +71,7 @@ async fn test() {
         true,
         &None,
         false,
+        false,
     )
     .await
     .unwrap();
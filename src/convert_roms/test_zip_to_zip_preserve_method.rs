@@ -0,0 +1,125 @@
+use super::super::database::*;
+use super::super::import_dats;
+use super::super::import_roms;
+use super::*;
+use std::path::{Path, PathBuf};
+use tempfile::{NamedTempFile, TempDir};
+use tokio::fs;
+
+#[tokio::test]
+async fn test() {
+    // given
+    let _guard = MUTEX.lock().await;
+
+    let test_directory = Path::new("tests");
+    let progress_bar = ProgressBar::hidden();
+
+    let db_file = NamedTempFile::new().unwrap();
+    let pool = establish_connection(db_file.path().to_str().unwrap()).await;
+    let mut connection = pool.acquire().await.unwrap();
+
+    let rom_directory = TempDir::new_in(&test_directory).unwrap();
+    set_rom_directory(PathBuf::from(rom_directory.path()));
+    let tmp_directory = TempDir::new_in(&test_directory).unwrap();
+    let tmp_directory = set_tmp_directory(PathBuf::from(tmp_directory.path()));
+
+    let matches = import_dats::subcommand()
+        .get_matches_from(&["import-dats", "tests/Test System (20200721).dat"]);
+    import_dats::main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    let original_path = tmp_directory.join("Test Game (USA, Europe).rom");
+    fs::copy(
+        test_directory.join("Test Game (USA, Europe).rom"),
+        &original_path,
+    )
+    .await
+    .unwrap();
+    let romfile_path = CommonRomfile::from_path(&original_path)
+        .unwrap()
+        .to_archive(
+            &progress_bar,
+            &tmp_directory,
+            &tmp_directory,
+            "Test Game (USA, Europe).rom",
+            &sevenzip::ArchiveType::Zip,
+            &None,
+            &Some(String::from("Copy")),
+            false,
+        )
+        .await
+        .unwrap()
+        .romfile
+        .path;
+
+    let system = find_systems(&mut connection).await.remove(0);
+    let system_directory = get_system_directory(&mut connection, &system)
+        .await
+        .unwrap();
+
+    let matches = import_roms::subcommand()
+        .get_matches_from(&["import-roms", romfile_path.as_os_str().to_str().unwrap()]);
+    import_roms::main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    let games = find_complete_games_by_system_id(&mut connection, system.id).await;
+    let roms = find_roms_with_romfile_by_game_ids(&mut connection, &[games[0].id]).await;
+    let romfile = find_romfile_by_id(&mut connection, roms[0].romfile_id.unwrap()).await;
+    let mut roms_by_game_id: IndexMap<i64, Vec<Rom>> = IndexMap::new();
+    roms_by_game_id.insert(roms[0].game_id, roms);
+    let games_by_id: HashMap<i64, Game> = games.into_iter().map(|game| (game.id, game)).collect();
+    let mut romfiles_by_id: HashMap<i64, Romfile> = HashMap::new();
+    romfiles_by_id.insert(romfile.id, romfile);
+
+    // when
+    to_archive(
+        &mut connection,
+        &progress_bar,
+        &system,
+        games_by_id,
+        roms_by_game_id,
+        romfiles_by_id,
+        sevenzip::ArchiveType::Zip,
+        true,
+        false,
+        true,
+        &Some(9),
+        false,
+        true,
+    )
+    .await
+    .unwrap();
+
+    // then
+    let roms = find_roms_with_romfile_by_system_id(&mut connection, system.id).await;
+    assert_eq!(roms.len(), 1);
+    let romfiles = find_romfiles(&mut connection).await;
+    assert_eq!(romfiles.len(), 1);
+
+    let rom = roms.first().unwrap();
+    assert_eq!(rom.name, "Test Game (USA, Europe).rom");
+
+    let romfile = romfiles.first().unwrap();
+    assert_eq!(
+        romfile.path,
+        system_directory
+            .join("Test Game (USA, Europe).zip")
+            .strip_prefix(&rom_directory)
+            .unwrap()
+            .as_os_str()
+            .to_str()
+            .unwrap(),
+    );
+    assert!(rom_directory.path().join(&romfile.path).is_file());
+    assert_eq!(rom.romfile_id, Some(romfile.id));
+
+    let compression_method = sevenzip::get_compression_method(
+        &progress_bar,
+        &romfile.as_common(&mut connection).await.unwrap(),
+    )
+    .await
+    .unwrap();
+    assert_eq!(compression_method.as_deref(), Some("Copy"));
+}
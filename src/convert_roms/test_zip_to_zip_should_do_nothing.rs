@@ -76,6 +76,7 @@ async fn test() {
         true,
         &None,
         false,
+        false,
     )
     .await
     .unwrap();
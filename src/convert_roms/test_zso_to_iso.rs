@@ -72,6 +72,7 @@ async fn test() {
         roms_by_game_id,
         romfiles_by_id,
         true,
+        false,
     )
     .await
     .unwrap();
@@ -184,8 +184,10 @@ pub async fn create_dat<P: AsRef<Path>, Q: AsRef<Path>>(
                         .await?
                         .0,
                 ),
+                sha256: None,
                 merge: None,
                 status: None,
+                alt_names: vec![],
             };
             let game_name = romfile
                 .path
@@ -199,6 +201,7 @@ pub async fn create_dat<P: AsRef<Path>, Q: AsRef<Path>>(
                 description: game_name.clone(),
                 roms: vec![rom_xml],
                 disks: vec![],
+                releases: vec![],
                 isbios: false,
                 isdevice: false,
                 cloneof: None,
@@ -1,4 +1,5 @@
 use super::progress::*;
+use super::util::*;
 use super::SimpleResult;
 use indicatif::ProgressBar;
 use regex::Regex;
@@ -30,7 +31,7 @@ pub struct ArchiveInfo {
 
 pub async fn get_version() -> SimpleResult<String> {
     let output = try_with!(
-        Command::new(CTRTOOL).output().await,
+        Command::new(CTRTOOL).output_with_retries().await,
         "Failed to spawn ctrtool"
     );
 
@@ -57,7 +58,7 @@ pub async fn parse_cia<P: AsRef<Path>>(
         .arg("-p")
         .arg("-v")
         .arg(cia_path.as_ref())
-        .output()
+        .output_with_retries()
         .await
         .expect("Failed to parse cia");
 
@@ -134,7 +135,7 @@ pub async fn extract_files_from_cia<P: AsRef<Path>, Q: AsRef<Path>>(
         .arg("--contents=content")
         .arg(archive_path.as_ref())
         .current_dir(directory)
-        .output()
+        .output_with_retries()
         .await
         .expect("Failed to extract cia");
 
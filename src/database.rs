@@ -1,12 +1,15 @@
 use super::model::*;
+use super::progress::*;
 use cfg_if::cfg_if;
+use indicatif::ProgressBar;
 use itertools::Itertools;
-use sqlx::migrate::Migrator;
+use sqlx::migrate::{Migrate, Migrator};
 use sqlx::prelude::*;
-use sqlx::sqlite::{SqliteConnection, SqlitePool, SqlitePoolOptions};
+use sqlx::sqlite::{SqliteConnectOptions, SqliteConnection, SqlitePool, SqlitePoolOptions};
 use sqlx::{Acquire, Sqlite, Transaction};
+use std::collections::HashSet;
 use std::convert::TryFrom;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 static MIGRATOR: Migrator = sqlx::migrate!();
 
@@ -59,6 +62,29 @@ pub async fn establish_connection(url: &str) -> SqlitePool {
     pool
 }
 
+// used for databases that aren't ours to lock or migrate
+pub async fn establish_read_only_connection(path: &str) -> SqlitePool {
+    SqlitePoolOptions::new()
+        .max_connections(1)
+        .acquire_timeout(Duration::from_secs(5))
+        .connect_with(SqliteConnectOptions::new().filename(path).read_only(true))
+        .await
+        .unwrap_or_else(|_| panic!("Error connecting to {}", path))
+}
+
+pub async fn is_migrated(connection: &mut SqliteConnection) -> bool {
+    let applied_versions: HashSet<i64> = match connection.list_applied_migrations().await {
+        Ok(applied_migrations) => applied_migrations
+            .into_iter()
+            .map(|applied_migration| applied_migration.version)
+            .collect(),
+        Err(_) => return false,
+    };
+    MIGRATOR
+        .iter()
+        .all(|migration| applied_versions.contains(&migration.version))
+}
+
 pub async fn begin_transaction(connection: &mut SqliteConnection) -> Transaction<'_, Sqlite> {
     Acquire::begin(connection)
         .await
@@ -79,7 +105,13 @@ pub async fn rollback_transaction(transaction: Transaction<'_, Sqlite>) {
         .expect("Failed to rollback transaction");
 }
 
-pub async fn optimize_database(pool: SqlitePool) {
+pub async fn optimize_database(pool: SqlitePool, progress_bar: &ProgressBar) -> Duration {
+    // vacuuming large databases takes a while, don't let it look like a hang
+    progress_bar.set_style(get_none_progress_style());
+    progress_bar.enable_steady_tick(Duration::from_millis(100));
+    progress_bar.set_message("Optimizing database");
+
+    let start = Instant::now();
     pool.execute(
         "
             PRAGMA incremental_vacuum;
@@ -89,6 +121,13 @@ pub async fn optimize_database(pool: SqlitePool) {
     )
     .await
     .expect("Failed to optimize the database");
+    let elapsed = start.elapsed();
+
+    progress_bar.set_message("");
+    progress_bar.disable_steady_tick();
+    log::debug!("Optimized database in {:?}", elapsed);
+
+    elapsed
 }
 
 pub async fn create_system_from_xml(
@@ -116,6 +155,26 @@ pub async fn create_system_from_xml(
     .last_insert_rowid()
 }
 
+pub async fn create_system_from_system(connection: &mut SqliteConnection, system: &System) -> i64 {
+    sqlx::query!(
+        "
+        INSERT INTO systems (name, custom_name, description, version, url, arcade, merging)
+        VALUES (?, ?, ?, ?, ?, ?, ?)
+        ",
+        system.name,
+        system.custom_name,
+        system.description,
+        system.version,
+        system.url,
+        system.arcade,
+        system.merging,
+    )
+    .execute(connection)
+    .await
+    .expect("Error while creating system")
+    .last_insert_rowid()
+}
+
 pub async fn update_system_from_xml(
     connection: &mut SqliteConnection,
     id: i64,
@@ -231,6 +290,25 @@ pub async fn find_arcade_systems(connection: &mut SqliteConnection) -> Vec<Syste
     .expect("Error while finding arcade systems")
 }
 
+pub async fn find_system_ids_by_rom_extension(
+    connection: &mut SqliteConnection,
+    extension: &str,
+) -> Vec<i64> {
+    let pattern = format!("%.{}", extension);
+    sqlx::query_scalar!(
+        "
+        SELECT DISTINCT g.system_id
+        FROM roms AS r
+        JOIN games AS g ON r.game_id = g.id
+        WHERE r.name LIKE ?
+        ",
+        pattern,
+    )
+    .fetch_all(connection)
+    .await
+    .expect("Error while finding systems by ROM extension")
+}
+
 pub async fn find_systems_by_url(connection: &mut SqliteConnection, url: &str) -> Vec<System> {
     sqlx::query_as!(
         System,
@@ -247,6 +325,39 @@ pub async fn find_systems_by_url(connection: &mut SqliteConnection, url: &str) -
     .unwrap_or_else(|_| panic!("Error while finding systems with url {}", url))
 }
 
+pub async fn find_systems_by_name(connection: &mut SqliteConnection, name: &str) -> Vec<System> {
+    sqlx::query_as!(
+        System,
+        "
+        SELECT *
+        FROM systems
+        WHERE name = ?
+        ",
+        name,
+    )
+    .fetch_all(connection)
+    .await
+    .unwrap_or_else(|_| panic!("Error while finding system with name {}", name))
+}
+
+pub async fn find_systems_by_name_prefix(
+    connection: &mut SqliteConnection,
+    name: &str,
+) -> Vec<System> {
+    sqlx::query_as!(
+        System,
+        "
+        SELECT *
+        FROM systems
+        WHERE instr(name, ?) = 1
+        ",
+        name,
+    )
+    .fetch_all(connection)
+    .await
+    .unwrap_or_else(|_| panic!("Error while finding system with name {}", name))
+}
+
 pub async fn find_systems_by_name_like(
     connection: &mut SqliteConnection,
     name: &str,
@@ -313,14 +424,15 @@ pub async fn create_game_from_xml(
     connection: &mut SqliteConnection,
     game_xml: &GameXml,
     regions: &str,
+    languages: &str,
     system_id: i64,
     parent_id: Option<i64>,
     bios_id: Option<i64>,
 ) -> i64 {
     sqlx::query!(
         "
-        INSERT INTO games (name, description, comment, device, bios, regions, system_id, parent_id, bios_id)
-        VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)
+        INSERT INTO games (name, description, comment, device, bios, regions, languages, system_id, parent_id, bios_id)
+        VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
         ",
         game_xml.name,
         game_xml.description,
@@ -328,6 +440,7 @@ pub async fn create_game_from_xml(
         game_xml.isdevice,
         game_xml.isbios,
         regions,
+        languages,
         system_id,
         parent_id,
         bios_id,
@@ -338,11 +451,44 @@ pub async fn create_game_from_xml(
     .last_insert_rowid()
 }
 
+pub async fn create_game_from_game(
+    connection: &mut SqliteConnection,
+    game: &Game,
+    system_id: i64,
+    parent_id: Option<i64>,
+    bios_id: Option<i64>,
+) -> i64 {
+    sqlx::query!(
+        "
+        INSERT INTO games (name, description, comment, external_id, device, bios, jbfolder, regions, languages, sorting, system_id, parent_id, bios_id)
+        VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+        ",
+        game.name,
+        game.description,
+        game.comment,
+        game.external_id,
+        game.device,
+        game.bios,
+        game.jbfolder,
+        game.regions,
+        game.languages,
+        game.sorting,
+        system_id,
+        parent_id,
+        bios_id,
+    )
+    .execute(connection)
+    .await
+    .unwrap_or_else(|_| panic!("Error while creating game with name '{}'", game.name))
+    .last_insert_rowid()
+}
+
 pub async fn update_game_from_xml(
     connection: &mut SqliteConnection,
     id: i64,
     game_xml: &GameXml,
     regions: &str,
+    languages: &str,
     system_id: i64,
     parent_id: Option<i64>,
     bios_id: Option<i64>,
@@ -350,7 +496,7 @@ pub async fn update_game_from_xml(
     sqlx::query!(
         "
         UPDATE games
-        SET name = ?, description = ?, comment = ?, device = ?, bios = ?, regions = ?, system_id = ?, parent_id = ?, bios_id = ?
+        SET name = ?, description = ?, comment = ?, device = ?, bios = ?, regions = ?, languages = ?, system_id = ?, parent_id = ?, bios_id = ?
         WHERE id = ?
         ",
         game_xml.name,
@@ -359,6 +505,7 @@ pub async fn update_game_from_xml(
         game_xml.isdevice,
         game_xml.isbios,
         regions,
+        languages,
         system_id,
         parent_id,
         bios_id,
@@ -592,6 +739,25 @@ pub async fn count_games(connection: &mut SqliteConnection) -> i64 {
     .count
 }
 
+pub async fn count_games_by_completion(
+    connection: &mut SqliteConnection,
+    completion: Completion,
+) -> i64 {
+    let completion = completion as i8;
+    sqlx::query!(
+        "
+        SELECT COUNT(id) AS 'count!'
+        FROM games
+        WHERE completion = ?
+        ",
+        completion,
+    )
+    .fetch_one(connection)
+    .await
+    .expect("Error while counting games")
+    .count
+}
+
 pub async fn find_games(connection: &mut SqliteConnection) -> Vec<Game> {
     sqlx::query_as!(
         Game,
@@ -799,6 +965,71 @@ pub async fn find_complete_games_by_name_and_system_id(
     .expect("Error while finding complete games")
 }
 
+pub async fn find_partial_or_complete_games_by_system_id(
+    connection: &mut SqliteConnection,
+    system_id: i64,
+) -> Vec<Game> {
+    sqlx::query_as!(
+        Game,
+        "
+        SELECT *
+        FROM games
+        WHERE system_id = ?
+        AND completion > 0
+        ORDER BY name
+        ",
+        system_id,
+    )
+    .fetch_all(connection)
+    .await
+    .expect("Error while finding partial or complete games")
+}
+
+pub async fn find_partial_or_complete_games_by_name_and_system_id(
+    connection: &mut SqliteConnection,
+    name: &str,
+    system_id: i64,
+) -> Vec<Game> {
+    sqlx::query_as!(
+        Game,
+        "
+        SELECT *
+        FROM games
+        WHERE name LIKE ?
+        AND system_id = ?
+        AND completion > 0
+        ORDER BY name
+        ",
+        name,
+        system_id,
+    )
+    .fetch_all(connection)
+    .await
+    .expect("Error while finding partial or complete games")
+}
+
+pub async fn find_games_by_name_and_system_id(
+    connection: &mut SqliteConnection,
+    name: &str,
+    system_id: i64,
+) -> Vec<Game> {
+    sqlx::query_as!(
+        Game,
+        "
+        SELECT *
+        FROM games
+        WHERE name LIKE ?
+        AND system_id = ?
+        ORDER BY name
+        ",
+        name,
+        system_id,
+    )
+    .fetch_all(connection)
+    .await
+    .expect("Error while finding games")
+}
+
 pub async fn find_first_game_by_playlist_id(
     connection: &mut SqliteConnection,
     playlist_id: i64,
@@ -879,10 +1110,11 @@ pub async fn create_rom_from_xml(
     let crc = rom_xml.crc.as_ref().map(|crc| crc.to_lowercase());
     let md5 = rom_xml.md5.as_ref().map(|md5| md5.to_lowercase());
     let sha1 = rom_xml.sha1.as_ref().map(|sha1| sha1.to_lowercase());
+    let sha256 = rom_xml.sha256.as_ref().map(|sha256| sha256.to_lowercase());
     sqlx::query!(
         "
-        INSERT INTO roms (name, bios, disk, size, crc, md5, sha1, rom_status, game_id, parent_id)
-        VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+        INSERT INTO roms (name, bios, disk, size, crc, md5, sha1, sha256, rom_status, game_id, parent_id)
+        VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
         ",
         rom_xml.name,
         bios,
@@ -891,6 +1123,7 @@ pub async fn create_rom_from_xml(
         crc,
         md5,
         sha1,
+        sha256,
         rom_xml.status,
         game_id,
         parent_id,
@@ -901,6 +1134,36 @@ pub async fn create_rom_from_xml(
     .last_insert_rowid()
 }
 
+pub async fn create_rom_from_rom(
+    connection: &mut SqliteConnection,
+    rom: &Rom,
+    game_id: i64,
+    parent_id: Option<i64>,
+) -> i64 {
+    sqlx::query!(
+        "
+        INSERT INTO roms (name, bios, disk, size, crc, md5, sha1, sha256, rom_status, header_title, game_id, parent_id)
+        VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+        ",
+        rom.name,
+        rom.bios,
+        rom.disk,
+        rom.size,
+        rom.crc,
+        rom.md5,
+        rom.sha1,
+        rom.sha256,
+        rom.rom_status,
+        rom.header_title,
+        game_id,
+        parent_id,
+    )
+    .execute(connection)
+    .await
+    .unwrap_or_else(|_| panic!("Error while creating rom with name {}", rom.name))
+    .last_insert_rowid()
+}
+
 pub async fn update_rom(
     connection: &mut SqliteConnection,
     id: i64,
@@ -937,13 +1200,14 @@ pub async fn update_rom_from_xml(
     game_id: i64,
     parent_id: Option<i64>,
 ) {
-    let crc = rom_xml.crc.as_ref().unwrap().to_lowercase();
+    let crc = rom_xml.crc.as_ref().map(|crc| crc.to_lowercase());
     let md5 = rom_xml.md5.as_ref().map(|md5| md5.to_lowercase());
     let sha1 = rom_xml.sha1.as_ref().map(|sha1| sha1.to_lowercase());
+    let sha256 = rom_xml.sha256.as_ref().map(|sha256| sha256.to_lowercase());
     sqlx::query!(
         "
         UPDATE roms
-        SET name = ?, bios = ?, disk = ?, size = ?, crc = ?, md5 = ?, sha1 = ?, rom_status = ?, game_id = ?, parent_id = ?
+        SET name = ?, bios = ?, disk = ?, size = ?, crc = ?, md5 = ?, sha1 = ?, sha256 = ?, rom_status = ?, game_id = ?, parent_id = ?
         WHERE id = ?
         ",
         rom_xml.name,
@@ -953,6 +1217,7 @@ pub async fn update_rom_from_xml(
         crc,
         md5,
         sha1,
+        sha256,
         rom_xml.status,
         game_id,
         parent_id,
@@ -982,6 +1247,25 @@ pub async fn update_rom_romfile(
     .unwrap_or_else(|_| panic!("Error while updating rom with id {}", id));
 }
 
+pub async fn update_rom_header_title(
+    connection: &mut SqliteConnection,
+    id: i64,
+    header_title: Option<&str>,
+) {
+    sqlx::query!(
+        "
+        UPDATE roms
+        SET header_title = ?
+        WHERE id = ?
+        ",
+        header_title,
+        id,
+    )
+    .execute(connection)
+    .await
+    .unwrap_or_else(|_| panic!("Error while updating rom with id {}", id));
+}
+
 pub async fn find_rom_by_id(connection: &mut SqliteConnection, id: i64) -> Rom {
     sqlx::query_as!(
         Rom,
@@ -1010,6 +1294,20 @@ pub async fn count_roms(connection: &mut SqliteConnection) -> i64 {
     .count
 }
 
+pub async fn count_roms_with_header_title(connection: &mut SqliteConnection) -> i64 {
+    sqlx::query!(
+        "
+        SELECT COUNT(id) AS 'count!'
+        FROM roms
+        WHERE header_title IS NOT NULL
+        ",
+    )
+    .fetch_one(connection)
+    .await
+    .expect("Error while counting roms")
+    .count
+}
+
 pub async fn find_roms(connection: &mut SqliteConnection) -> Vec<Rom> {
     sqlx::query_as!(
         Rom,
@@ -1172,6 +1470,30 @@ pub async fn find_roms_with_romfile_by_system_id(
     .expect("Error while finding roms with romfile")
 }
 
+pub async fn find_roms_shared_across_systems(connection: &mut SqliteConnection) -> Vec<Rom> {
+    sqlx::query_as!(
+        Rom,
+        "
+        SELECT r1.*
+        FROM roms AS r1
+        JOIN games AS g1 ON r1.game_id = g1.id
+        WHERE r1.crc IS NOT NULL
+        AND EXISTS (
+            SELECT 1
+            FROM roms AS r2
+            JOIN games AS g2 ON r2.game_id = g2.id
+            WHERE r2.size = r1.size
+            AND r2.crc = r1.crc
+            AND g2.system_id != g1.system_id
+        )
+        ORDER BY r1.size, r1.crc, g1.system_id, g1.name
+        ",
+    )
+    .fetch_all(connection)
+    .await
+    .expect("Error while finding roms shared across systems")
+}
+
 pub async fn find_roms_with_romfile_by_game_ids(
     connection: &mut SqliteConnection,
     game_ids: &[i64],
@@ -1535,67 +1857,125 @@ pub async fn find_roms_without_romfile_by_size_and_sha1(
     })
 }
 
-pub async fn find_roms_without_romfile_by_size_and_sha1_and_system_id(
+pub async fn find_roms_by_size_and_crc(
     connection: &mut SqliteConnection,
     size: u64,
-    sha1: &str,
-    system_id: i64,
+    crc: &str,
 ) -> Vec<Rom> {
     let size = i64::try_from(size).unwrap();
-    let sha1 = sha1.to_lowercase();
+    let crc = crc.to_lowercase();
     sqlx::query_as!(
         Rom,
         "
-        SELECT r.*
-        FROM roms AS r
-        JOIN games AS g ON r.game_id = g.id
-        WHERE r.romfile_id IS NULL
-        AND r.size = ?
-        AND r.sha1 = ?
-        AND g.system_id = ?
-        ORDER BY r.name
+        SELECT *
+        FROM roms
+        WHERE size = ?
+        AND crc = ?
+        ORDER BY name
         ",
         size,
-        sha1,
-        system_id,
+        crc,
     )
     .fetch_all(connection)
     .await
     .unwrap_or_else(|_| {
         panic!(
-            "Error while finding roms with size {} and SHA1 {} and system id {}",
-            size, sha1, system_id
+            "Error while finding roms with size {} and CRC {}",
+            size, crc
         )
     })
 }
 
-pub async fn find_roms_without_romfile_by_size_and_sha1_and_game_names(
+pub async fn find_roms_by_size_and_sha1(
     connection: &mut SqliteConnection,
     size: u64,
     sha1: &str,
-    game_names: &[&str],
 ) -> Vec<Rom> {
     let size = i64::try_from(size).unwrap();
     let sha1 = sha1.to_lowercase();
-    let sql = format!(
+    sqlx::query_as!(
+        Rom,
         "
-        SELECT r.*
-        FROM roms AS r
-        JOIN games AS g ON r.game_id = g.id
-        WHERE r.romfile_id IS NULL
-        AND r.size = {}
-        AND r.sha1 = '{}'
-        AND g.name IN ({})
-        ORDER BY r.name
+        SELECT *
+        FROM roms
+        WHERE size = ?
+        AND sha1 = ?
+        ORDER BY name
         ",
         size,
         sha1,
-        game_names
-            .iter()
-            .map(|game_name| format!("'{}'", game_name.replace('\'', "''")))
-            .join(","),
-    );
-    sqlx::query_as::<_, Rom>(&sql)
+    )
+    .fetch_all(connection)
+    .await
+    .unwrap_or_else(|_| {
+        panic!(
+            "Error while finding roms with size {} and SHA1 {}",
+            size, sha1
+        )
+    })
+}
+
+pub async fn find_roms_without_romfile_by_size_and_sha1_and_system_id(
+    connection: &mut SqliteConnection,
+    size: u64,
+    sha1: &str,
+    system_id: i64,
+) -> Vec<Rom> {
+    let size = i64::try_from(size).unwrap();
+    let sha1 = sha1.to_lowercase();
+    sqlx::query_as!(
+        Rom,
+        "
+        SELECT r.*
+        FROM roms AS r
+        JOIN games AS g ON r.game_id = g.id
+        WHERE r.romfile_id IS NULL
+        AND r.size = ?
+        AND r.sha1 = ?
+        AND g.system_id = ?
+        ORDER BY r.name
+        ",
+        size,
+        sha1,
+        system_id,
+    )
+    .fetch_all(connection)
+    .await
+    .unwrap_or_else(|_| {
+        panic!(
+            "Error while finding roms with size {} and SHA1 {} and system id {}",
+            size, sha1, system_id
+        )
+    })
+}
+
+pub async fn find_roms_without_romfile_by_size_and_sha1_and_game_names(
+    connection: &mut SqliteConnection,
+    size: u64,
+    sha1: &str,
+    game_names: &[&str],
+) -> Vec<Rom> {
+    let size = i64::try_from(size).unwrap();
+    let sha1 = sha1.to_lowercase();
+    let sql = format!(
+        "
+        SELECT r.*
+        FROM roms AS r
+        JOIN games AS g ON r.game_id = g.id
+        WHERE r.romfile_id IS NULL
+        AND r.size = {}
+        AND r.sha1 = '{}'
+        AND g.name IN ({})
+        ORDER BY r.name
+        ",
+        size,
+        sha1,
+        game_names
+            .iter()
+            .map(|game_name| format!("'{}'", game_name.replace('\'', "''")))
+            .join(","),
+    );
+    sqlx::query_as::<_, Rom>(&sql)
         .fetch_all(connection)
         .await
         .unwrap_or_else(|_| {
@@ -1793,13 +2173,13 @@ pub async fn count_roms_with_romfile_by_size_and_sha1_and_system_id(
     .count
 }
 
-pub async fn find_roms_without_romfile_by_size_and_crc(
+pub async fn find_roms_without_romfile_by_size_and_sha256(
     connection: &mut SqliteConnection,
     size: u64,
-    crc: &str,
+    sha256: &str,
 ) -> Vec<Rom> {
     let size = i64::try_from(size).unwrap();
-    let crc = crc.to_lowercase();
+    let sha256 = sha256.to_lowercase();
     sqlx::query_as!(
         Rom,
         "
@@ -1807,30 +2187,30 @@ pub async fn find_roms_without_romfile_by_size_and_crc(
         FROM roms
         WHERE romfile_id IS NULL
         AND size = ?
-        AND crc = ?
+        AND sha256 = ?
         ORDER BY name
         ",
         size,
-        crc,
+        sha256,
     )
     .fetch_all(connection)
     .await
     .unwrap_or_else(|_| {
         panic!(
-            "Error while finding roms with size {} and CRC {}",
-            size, crc
+            "Error while finding roms with size {} and SHA256 {}",
+            size, sha256
         )
     })
 }
 
-pub async fn find_roms_without_romfile_by_size_and_crc_and_system_id(
+pub async fn find_roms_without_romfile_by_size_and_sha256_and_system_id(
     connection: &mut SqliteConnection,
     size: u64,
-    crc: &str,
+    sha256: &str,
     system_id: i64,
 ) -> Vec<Rom> {
     let size = i64::try_from(size).unwrap();
-    let crc = crc.to_lowercase();
+    let sha256 = sha256.to_lowercase();
     sqlx::query_as!(
         Rom,
         "
@@ -1839,32 +2219,32 @@ pub async fn find_roms_without_romfile_by_size_and_crc_and_system_id(
         JOIN games AS g ON r.game_id = g.id
         WHERE r.romfile_id IS NULL
         AND r.size = ?
-        AND r.crc = ?
+        AND r.sha256 = ?
         AND g.system_id = ?
         ORDER BY r.name
         ",
         size,
-        crc,
+        sha256,
         system_id,
     )
     .fetch_all(connection)
     .await
     .unwrap_or_else(|_| {
         panic!(
-            "Error while finding roms with size {} and CRC {} and system id {}",
-            size, crc, system_id
+            "Error while finding roms with size {} and SHA256 {} and system id {}",
+            size, sha256, system_id
         )
     })
 }
 
-pub async fn find_roms_without_romfile_by_size_and_crc_and_game_names(
+pub async fn find_roms_without_romfile_by_size_and_sha256_and_game_names(
     connection: &mut SqliteConnection,
     size: u64,
-    crc: &str,
+    sha256: &str,
     game_names: &[&str],
 ) -> Vec<Rom> {
     let size = i64::try_from(size).unwrap();
-    let crc = crc.to_lowercase();
+    let sha256 = sha256.to_lowercase();
     let sql = format!(
         "
         SELECT r.*
@@ -1872,12 +2252,12 @@ pub async fn find_roms_without_romfile_by_size_and_crc_and_game_names(
         JOIN games AS g ON r.game_id = g.id
         WHERE r.romfile_id IS NULL
         AND r.size = {}
-        AND r.crc = '{}'
+        AND r.sha256 = '{}'
         AND g.name IN ({})
         ORDER BY r.name
         ",
         size,
-        crc,
+        sha256,
         game_names
             .iter()
             .map(|game_name| format!("'{}'", game_name.replace('\'', "''")))
@@ -1888,21 +2268,21 @@ pub async fn find_roms_without_romfile_by_size_and_crc_and_game_names(
         .await
         .unwrap_or_else(|_| {
             panic!(
-                "Error while finding roms with size {} and CRC {} and game names {:?}",
-                size, crc, game_names
+                "Error while finding roms with size {} and SHA256 {} and game names {:?}",
+                size, sha256, game_names
             )
         })
 }
 
-pub async fn find_roms_without_romfile_by_size_and_crc_and_game_names_and_system_id(
+pub async fn find_roms_without_romfile_by_size_and_sha256_and_game_names_and_system_id(
     connection: &mut SqliteConnection,
     size: u64,
-    crc: &str,
+    sha256: &str,
     game_names: &[&str],
     system_id: i64,
 ) -> Vec<Rom> {
     let size = i64::try_from(size).unwrap();
-    let crc = crc.to_lowercase();
+    let sha256 = sha256.to_lowercase();
     let sql = format!(
         "
         SELECT r.*
@@ -1910,13 +2290,13 @@ pub async fn find_roms_without_romfile_by_size_and_crc_and_game_names_and_system
         JOIN games AS g ON r.game_id = g.id
         WHERE r.romfile_id IS NULL
         AND r.size = {}
-        AND r.crc = '{}'
+        AND r.sha256 = '{}'
         AND g.name IN ({})
         AND g.system_id = {}
         ORDER BY r.name
         ",
         size,
-        crc,
+        sha256,
         game_names
             .iter()
             .map(|game_name| format!("'{}'", game_name.replace('\'', "''")))
@@ -1928,21 +2308,21 @@ pub async fn find_roms_without_romfile_by_size_and_crc_and_game_names_and_system
         .await
         .unwrap_or_else(|_| {
             panic!(
-            "Error while finding roms with size {} and CRC {} and game names {:?} and system id {}",
-            size, crc, game_names, system_id
+            "Error while finding roms with size {} and SHA256 {} and game names {:?} and system id {}",
+            size, sha256, game_names, system_id
         )
-        })
+    })
 }
 
-pub async fn find_roms_without_romfile_by_name_and_size_and_crc_and_game_names(
+pub async fn find_roms_without_romfile_by_name_and_size_and_sha256_and_game_names(
     connection: &mut SqliteConnection,
     name: &str,
     size: u64,
-    crc: &str,
+    sha256: &str,
     game_names: &[&str],
 ) -> Vec<Rom> {
     let size = i64::try_from(size).unwrap();
-    let crc = crc.to_lowercase();
+    let sha256 = sha256.to_lowercase();
     let sql = format!(
         "
         SELECT r.*
@@ -1951,13 +2331,13 @@ pub async fn find_roms_without_romfile_by_name_and_size_and_crc_and_game_names(
         WHERE r.romfile_id IS NULL
         AND r.name = '{}'
         AND r.size = {}
-        AND r.crc = '{}'
+        AND r.sha256 = '{}'
         AND g.name IN ({})
         ORDER BY r.name
         ",
         name.replace('\'', "''"),
         size,
-        crc,
+        sha256,
         game_names
             .iter()
             .map(|game_name| format!("'{}'", game_name.replace('\'', "''")))
@@ -1968,22 +2348,22 @@ pub async fn find_roms_without_romfile_by_name_and_size_and_crc_and_game_names(
         .await
         .unwrap_or_else(|_| {
             panic!(
-                "Error while finding roms with name {} and size {} and CRC {} and game names {:?}",
-                name, size, crc, game_names
+                "Error while finding roms with name {} and size {} and SHA256 {} and game names {:?}",
+                name, size, sha256, game_names
             )
         })
 }
 
-pub async fn find_roms_without_romfile_by_name_and_size_and_crc_and_game_names_and_system_id(
+pub async fn find_roms_without_romfile_by_name_and_size_and_sha256_and_game_names_and_system_id(
     connection: &mut SqliteConnection,
     name: &str,
     size: u64,
-    crc: &str,
+    sha256: &str,
     game_names: &[&str],
     system_id: i64,
 ) -> Vec<Rom> {
     let size = i64::try_from(size).unwrap();
-    let crc = crc.to_lowercase();
+    let sha256 = sha256.to_lowercase();
     let sql = format!(
         "
         SELECT r.*
@@ -1992,14 +2372,14 @@ pub async fn find_roms_without_romfile_by_name_and_size_and_crc_and_game_names_a
         WHERE r.romfile_id IS NULL
         AND r.name = '{}'
         AND r.size = {}
-        AND r.crc = '{}'
+        AND r.sha256 = '{}'
         AND g.name IN ({})
         AND g.system_id = {}
         ORDER BY r.name
         ",
         name.replace('\'', "''"),
         size,
-        crc,
+        sha256,
         game_names
             .iter()
             .map(|game_name| format!("'{}'", game_name.replace('\'', "''")))
@@ -2011,49 +2391,50 @@ pub async fn find_roms_without_romfile_by_name_and_size_and_crc_and_game_names_a
         .await
         .unwrap_or_else(|_| {
             panic!(
-            "Error while finding roms with name {} and size {} and CRC {} and game names {:?} and system id {}",
-            name, size, crc, game_names, system_id
+            "Error while finding roms with name {} and size {} and SHA256 {} and game names {:?} and system id {}",
+            name, size, sha256, game_names, system_id
         )
         })
 }
 
-pub async fn count_roms_with_romfile_by_size_and_crc(
+pub async fn count_roms_with_romfile_by_size_and_sha256(
     connection: &mut SqliteConnection,
     size: u64,
-    crc: &str,
+    sha256: &str,
 ) -> i64 {
     let size = i64::try_from(size).unwrap();
-    let crc = crc.to_lowercase();
+    let sha256 = sha256.to_lowercase();
     sqlx::query!(
         "
         SELECT COUNT(id) AS 'count!'
         FROM roms
         WHERE romfile_id IS NOT NULL
         AND size = ?
-        AND crc = ?
+        AND sha256 = ?
+        ORDER BY name
         ",
         size,
-        crc,
+        sha256,
     )
     .fetch_one(connection)
     .await
     .unwrap_or_else(|_| {
         panic!(
-            "Error while finding roms with size {} and CRC {}",
-            size, crc
+            "Error while finding roms with size {} and SHA256 {}",
+            size, sha256
         )
     })
     .count
 }
 
-pub async fn count_roms_with_romfile_by_size_and_crc_and_system_id(
+pub async fn count_roms_with_romfile_by_size_and_sha256_and_system_id(
     connection: &mut SqliteConnection,
     size: u64,
-    crc: &str,
+    sha256: &str,
     system_id: i64,
 ) -> i64 {
     let size = i64::try_from(size).unwrap();
-    let crc = crc.to_lowercase();
+    let sha256 = sha256.to_lowercase();
     sqlx::query!(
         "
         SELECT COUNT(r.id) AS 'count!'
@@ -2061,785 +2442,1655 @@ pub async fn count_roms_with_romfile_by_size_and_crc_and_system_id(
         JOIN games AS g ON r.game_id = g.id
         WHERE r.romfile_id IS NOT NULL
         AND r.size = ?
-        AND r.crc = ?
+        AND r.sha256 = ?
         AND g.system_id = ?
+        ORDER BY r.name
         ",
         size,
-        crc,
+        sha256,
         system_id,
     )
     .fetch_one(connection)
     .await
     .unwrap_or_else(|_| {
         panic!(
-            "Error while finding roms with size {} and CRC {} and system id {}",
-            size, crc, system_id
+            "Error while finding roms with size {} and SHA256 {} and system id {}",
+            size, sha256, system_id
         )
     })
     .count
 }
 
-pub async fn find_roms_without_romfile_by_name_and_size_and_md5_and_system_id(
+pub async fn find_roms_without_romfile_by_size_and_crc(
     connection: &mut SqliteConnection,
-    name: &str,
     size: u64,
-    md5: &str,
-    system_id: i64,
+    crc: &str,
 ) -> Vec<Rom> {
     let size = i64::try_from(size).unwrap();
-    let md5 = md5.to_lowercase();
+    let crc = crc.to_lowercase();
     sqlx::query_as!(
         Rom,
         "
-        SELECT r.*
-        FROM roms AS r
-        JOIN games AS g ON r.game_id = g.id
-        WHERE r.romfile_id IS NULL
-        AND r.name = ?
-        AND r.size = ?
-        AND r.md5 = ?
-        AND r.parent_id IS NOT NULL
-        AND g.system_id = ?
-        ORDER BY g.name
+        SELECT *
+        FROM roms
+        WHERE romfile_id IS NULL
+        AND size = ?
+        AND crc = ?
+        ORDER BY name
         ",
-        name,
         size,
-        md5,
-        system_id,
+        crc,
     )
     .fetch_all(connection)
     .await
     .unwrap_or_else(|_| {
         panic!(
-            "Error while finding roms with name {} and size {} and MD5 {} and system id {}",
-            name, size, md5, system_id
+            "Error while finding roms with size {} and CRC {}",
+            size, crc
         )
     })
 }
 
-pub async fn count_roms_with_romfile_by_name_and_size_and_md5_and_system_id(
+pub async fn find_roms_without_romfile_by_size_and_crc_and_system_id(
     connection: &mut SqliteConnection,
-    name: &str,
     size: u64,
-    md5: &str,
+    crc: &str,
     system_id: i64,
-) -> i64 {
+) -> Vec<Rom> {
     let size = i64::try_from(size).unwrap();
-    let md5 = md5.to_lowercase();
-    sqlx::query!(
+    let crc = crc.to_lowercase();
+    sqlx::query_as!(
+        Rom,
         "
-        SELECT COUNT(r.id) AS 'count!'
+        SELECT r.*
         FROM roms AS r
         JOIN games AS g ON r.game_id = g.id
         WHERE r.romfile_id IS NULL
-        AND r.name = ?
         AND r.size = ?
-        AND r.md5 = ?
-        AND r.parent_id IS NOT NULL
+        AND r.crc = ?
         AND g.system_id = ?
+        ORDER BY r.name
         ",
-        name,
         size,
-        md5,
+        crc,
         system_id,
     )
-    .fetch_one(connection)
+    .fetch_all(connection)
     .await
     .unwrap_or_else(|_| {
         panic!(
-            "Error while finding roms with name {} and size {} and MD5 {} and system id {}",
-            name, size, md5, system_id
+            "Error while finding roms with size {} and CRC {} and system id {}",
+            size, crc, system_id
         )
     })
-    .count
 }
 
-pub async fn find_roms_without_romfile_by_size_and_md5_and_parent_id(
+pub async fn find_roms_without_romfile_by_size_and_crc_and_game_names(
     connection: &mut SqliteConnection,
     size: u64,
-    md5: &str,
-    parent_id: i64,
+    crc: &str,
+    game_names: &[&str],
 ) -> Vec<Rom> {
     let size = i64::try_from(size).unwrap();
-    let md5 = md5.to_lowercase();
-    sqlx::query_as!(
-        Rom,
+    let crc = crc.to_lowercase();
+    let sql = format!(
         "
-        SELECT *
-        FROM roms
-        WHERE romfile_id IS NULL
-        AND size = ?
-        AND md5 = ?
-        AND parent_id = ?
-        ORDER BY name
+        SELECT r.*
+        FROM roms AS r
+        JOIN games AS g ON r.game_id = g.id
+        WHERE r.romfile_id IS NULL
+        AND r.size = {}
+        AND r.crc = '{}'
+        AND g.name IN ({})
+        ORDER BY r.name
         ",
         size,
-        md5,
-        parent_id,
-    )
-    .fetch_all(connection)
-    .await
-    .unwrap_or_else(|_| {
-        panic!(
-            "Error while finding roms with size {} and MD5 {} and parent id {}",
-            size, md5, parent_id
-        )
-    })
+        crc,
+        game_names
+            .iter()
+            .map(|game_name| format!("'{}'", game_name.replace('\'', "''")))
+            .join(","),
+    );
+    sqlx::query_as::<_, Rom>(&sql)
+        .fetch_all(connection)
+        .await
+        .unwrap_or_else(|_| {
+            panic!(
+                "Error while finding roms with size {} and CRC {} and game names {:?}",
+                size, crc, game_names
+            )
+        })
 }
 
-pub async fn count_roms_with_romfile_by_size_and_md5_and_parent_id(
+pub async fn find_roms_without_romfile_by_size_and_crc_and_game_names_and_system_id(
     connection: &mut SqliteConnection,
     size: u64,
-    md5: &str,
-    parent_id: i64,
-) -> i64 {
+    crc: &str,
+    game_names: &[&str],
+    system_id: i64,
+) -> Vec<Rom> {
     let size = i64::try_from(size).unwrap();
-    let md5 = md5.to_lowercase();
-    sqlx::query!(
+    let crc = crc.to_lowercase();
+    let sql = format!(
         "
-        SELECT count(id) AS 'count!'
+        SELECT r.*
+        FROM roms AS r
+        JOIN games AS g ON r.game_id = g.id
+        WHERE r.romfile_id IS NULL
+        AND r.size = {}
+        AND r.crc = '{}'
+        AND g.name IN ({})
+        AND g.system_id = {}
+        ORDER BY r.name
+        ",
+        size,
+        crc,
+        game_names
+            .iter()
+            .map(|game_name| format!("'{}'", game_name.replace('\'', "''")))
+            .join(","),
+        system_id,
+    );
+    sqlx::query_as::<_, Rom>(&sql)
+        .fetch_all(connection)
+        .await
+        .unwrap_or_else(|_| {
+            panic!(
+            "Error while finding roms with size {} and CRC {} and game names {:?} and system id {}",
+            size, crc, game_names, system_id
+        )
+        })
+}
+
+pub async fn find_roms_without_romfile_by_name_and_size_and_crc_and_game_names(
+    connection: &mut SqliteConnection,
+    name: &str,
+    size: u64,
+    crc: &str,
+    game_names: &[&str],
+) -> Vec<Rom> {
+    let size = i64::try_from(size).unwrap();
+    let crc = crc.to_lowercase();
+    let sql = format!(
+        "
+        SELECT r.*
+        FROM roms AS r
+        JOIN games AS g ON r.game_id = g.id
+        WHERE r.romfile_id IS NULL
+        AND r.name = '{}'
+        AND r.size = {}
+        AND r.crc = '{}'
+        AND g.name IN ({})
+        ORDER BY r.name
+        ",
+        name.replace('\'', "''"),
+        size,
+        crc,
+        game_names
+            .iter()
+            .map(|game_name| format!("'{}'", game_name.replace('\'', "''")))
+            .join(","),
+    );
+    sqlx::query_as::<_, Rom>(&sql)
+        .fetch_all(connection)
+        .await
+        .unwrap_or_else(|_| {
+            panic!(
+                "Error while finding roms with name {} and size {} and CRC {} and game names {:?}",
+                name, size, crc, game_names
+            )
+        })
+}
+
+pub async fn find_roms_without_romfile_by_name_and_size_and_crc_and_game_names_and_system_id(
+    connection: &mut SqliteConnection,
+    name: &str,
+    size: u64,
+    crc: &str,
+    game_names: &[&str],
+    system_id: i64,
+) -> Vec<Rom> {
+    let size = i64::try_from(size).unwrap();
+    let crc = crc.to_lowercase();
+    let sql = format!(
+        "
+        SELECT r.*
+        FROM roms AS r
+        JOIN games AS g ON r.game_id = g.id
+        WHERE r.romfile_id IS NULL
+        AND r.name = '{}'
+        AND r.size = {}
+        AND r.crc = '{}'
+        AND g.name IN ({})
+        AND g.system_id = {}
+        ORDER BY r.name
+        ",
+        name.replace('\'', "''"),
+        size,
+        crc,
+        game_names
+            .iter()
+            .map(|game_name| format!("'{}'", game_name.replace('\'', "''")))
+            .join(","),
+        system_id,
+    );
+    sqlx::query_as::<_, Rom>(&sql)
+        .fetch_all(connection)
+        .await
+        .unwrap_or_else(|_| {
+            panic!(
+            "Error while finding roms with name {} and size {} and CRC {} and game names {:?} and system id {}",
+            name, size, crc, game_names, system_id
+        )
+        })
+}
+
+pub async fn count_roms_with_romfile_by_size_and_crc(
+    connection: &mut SqliteConnection,
+    size: u64,
+    crc: &str,
+) -> i64 {
+    let size = i64::try_from(size).unwrap();
+    let crc = crc.to_lowercase();
+    sqlx::query!(
+        "
+        SELECT COUNT(id) AS 'count!'
         FROM roms
         WHERE romfile_id IS NOT NULL
         AND size = ?
-        AND md5 = ?
-        AND parent_id = ?
+        AND crc = ?
         ",
         size,
-        md5,
-        parent_id,
+        crc,
     )
     .fetch_one(connection)
     .await
     .unwrap_or_else(|_| {
         panic!(
-            "Error while finding roms with size {} and MD5 {} and parent id {}",
-            size, md5, parent_id
+            "Error while finding roms with size {} and CRC {}",
+            size, crc
         )
     })
     .count
 }
 
-pub async fn find_rom_by_name_and_game_id(
+pub async fn count_roms_with_romfile_by_size_and_crc_and_system_id(
     connection: &mut SqliteConnection,
-    name: &str,
-    game_id: i64,
-) -> Option<Rom> {
-    sqlx::query_as!(
-        Rom,
+    size: u64,
+    crc: &str,
+    system_id: i64,
+) -> i64 {
+    let size = i64::try_from(size).unwrap();
+    let crc = crc.to_lowercase();
+    sqlx::query!(
         "
-        SELECT *
-        FROM roms
-        WHERE name = ?
-        AND game_id = ?
+        SELECT COUNT(r.id) AS 'count!'
+        FROM roms AS r
+        JOIN games AS g ON r.game_id = g.id
+        WHERE r.romfile_id IS NOT NULL
+        AND r.size = ?
+        AND r.crc = ?
+        AND g.system_id = ?
         ",
-        name,
-        game_id,
+        size,
+        crc,
+        system_id,
     )
-    .fetch_optional(connection)
+    .fetch_one(connection)
     .await
     .unwrap_or_else(|_| {
         panic!(
-            "Error while finding rom with name {} and game id {}",
-            name, game_id
+            "Error while finding roms with size {} and CRC {} and system id {}",
+            size, crc, system_id
         )
     })
+    .count
 }
 
-pub async fn find_rom_by_size_and_crc_and_game_id(
+pub async fn find_roms_without_romfile_by_name_and_size_and_md5_and_system_id(
     connection: &mut SqliteConnection,
-    size: i64,
-    crc: &str,
-    game_id: i64,
-) -> Option<Rom> {
-    let crc = crc.to_lowercase();
+    name: &str,
+    size: u64,
+    md5: &str,
+    system_id: i64,
+) -> Vec<Rom> {
+    let size = i64::try_from(size).unwrap();
+    let md5 = md5.to_lowercase();
     sqlx::query_as!(
         Rom,
         "
-        SELECT *
-        FROM roms
-        WHERE size = ?
-        AND crc = ?
-        AND game_id = ?
+        SELECT r.*
+        FROM roms AS r
+        JOIN games AS g ON r.game_id = g.id
+        WHERE r.romfile_id IS NULL
+        AND r.name = ?
+        AND r.size = ?
+        AND r.md5 = ?
+        AND r.parent_id IS NOT NULL
+        AND g.system_id = ?
+        ORDER BY g.name
         ",
+        name,
         size,
-        crc,
-        game_id,
+        md5,
+        system_id,
     )
-    .fetch_optional(connection)
+    .fetch_all(connection)
     .await
     .unwrap_or_else(|_| {
         panic!(
-            "Error while finding rom with size {} and CRC {} and game id {}",
-            size, crc, game_id
+            "Error while finding roms with name {} and size {} and MD5 {} and system id {}",
+            name, size, md5, system_id
         )
     })
 }
 
-pub async fn delete_rom_by_name_and_game_id(
+pub async fn count_roms_with_romfile_by_name_and_size_and_md5_and_system_id(
     connection: &mut SqliteConnection,
     name: &str,
-    game_id: i64,
-) {
+    size: u64,
+    md5: &str,
+    system_id: i64,
+) -> i64 {
+    let size = i64::try_from(size).unwrap();
+    let md5 = md5.to_lowercase();
     sqlx::query!(
         "
-        DELETE FROM roms
-        WHERE name = ?
-        AND game_id = ?
+        SELECT COUNT(r.id) AS 'count!'
+        FROM roms AS r
+        JOIN games AS g ON r.game_id = g.id
+        WHERE r.romfile_id IS NULL
+        AND r.name = ?
+        AND r.size = ?
+        AND r.md5 = ?
+        AND r.parent_id IS NOT NULL
+        AND g.system_id = ?
+        ",
+        name,
+        size,
+        md5,
+        system_id,
+    )
+    .fetch_one(connection)
+    .await
+    .unwrap_or_else(|_| {
+        panic!(
+            "Error while finding roms with name {} and size {} and MD5 {} and system id {}",
+            name, size, md5, system_id
+        )
+    })
+    .count
+}
+
+pub async fn find_roms_without_romfile_by_size_and_md5_and_parent_id(
+    connection: &mut SqliteConnection,
+    size: u64,
+    md5: &str,
+    parent_id: i64,
+) -> Vec<Rom> {
+    let size = i64::try_from(size).unwrap();
+    let md5 = md5.to_lowercase();
+    sqlx::query_as!(
+        Rom,
+        "
+        SELECT *
+        FROM roms
+        WHERE romfile_id IS NULL
+        AND size = ?
+        AND md5 = ?
+        AND parent_id = ?
+        ORDER BY name
+        ",
+        size,
+        md5,
+        parent_id,
+    )
+    .fetch_all(connection)
+    .await
+    .unwrap_or_else(|_| {
+        panic!(
+            "Error while finding roms with size {} and MD5 {} and parent id {}",
+            size, md5, parent_id
+        )
+    })
+}
+
+pub async fn count_roms_with_romfile_by_size_and_md5_and_parent_id(
+    connection: &mut SqliteConnection,
+    size: u64,
+    md5: &str,
+    parent_id: i64,
+) -> i64 {
+    let size = i64::try_from(size).unwrap();
+    let md5 = md5.to_lowercase();
+    sqlx::query!(
+        "
+        SELECT count(id) AS 'count!'
+        FROM roms
+        WHERE romfile_id IS NOT NULL
+        AND size = ?
+        AND md5 = ?
+        AND parent_id = ?
+        ",
+        size,
+        md5,
+        parent_id,
+    )
+    .fetch_one(connection)
+    .await
+    .unwrap_or_else(|_| {
+        panic!(
+            "Error while finding roms with size {} and MD5 {} and parent id {}",
+            size, md5, parent_id
+        )
+    })
+    .count
+}
+
+pub async fn find_rom_by_name_and_game_id(
+    connection: &mut SqliteConnection,
+    name: &str,
+    game_id: i64,
+) -> Option<Rom> {
+    sqlx::query_as!(
+        Rom,
+        "
+        SELECT *
+        FROM roms
+        WHERE name = ?
+        AND game_id = ?
+        ",
+        name,
+        game_id,
+    )
+    .fetch_optional(connection)
+    .await
+    .unwrap_or_else(|_| {
+        panic!(
+            "Error while finding rom with name {} and game id {}",
+            name, game_id
+        )
+    })
+}
+
+pub async fn find_rom_by_size_and_crc_and_game_id(
+    connection: &mut SqliteConnection,
+    size: i64,
+    crc: &str,
+    game_id: i64,
+) -> Option<Rom> {
+    let crc = crc.to_lowercase();
+    sqlx::query_as!(
+        Rom,
+        "
+        SELECT *
+        FROM roms
+        WHERE size = ?
+        AND crc = ?
+        AND game_id = ?
+        ",
+        size,
+        crc,
+        game_id,
+    )
+    .fetch_optional(connection)
+    .await
+    .unwrap_or_else(|_| {
+        panic!(
+            "Error while finding rom with size {} and CRC {} and game id {}",
+            size, crc, game_id
+        )
+    })
+}
+
+pub async fn delete_rom_by_name_and_game_id(
+    connection: &mut SqliteConnection,
+    name: &str,
+    game_id: i64,
+) {
+    sqlx::query!(
+        "
+        DELETE FROM roms
+        WHERE name = ?
+        AND game_id = ?
+        ",
+        name,
+        game_id,
+    )
+    .execute(connection)
+    .await
+    .unwrap_or_else(|_| {
+        panic!(
+            "Error while deleting rom with name {} and game_id {}",
+            name, game_id
+        )
+    });
+}
+
+pub async fn create_patch(
+    connection: &mut SqliteConnection,
+    name: &str,
+    index: i64,
+    rom_id: i64,
+    romfile_id: i64,
+) -> i64 {
+    sqlx::query!(
+        "
+        INSERT INTO patches (name, \"index\", rom_id, romfile_id)
+        VALUES (?, ?, ?, ?)
+        ",
+        name,
+        index,
+        rom_id,
+        romfile_id,
+    )
+    .execute(connection)
+    .await
+    .expect("Error while creating patch")
+    .last_insert_rowid()
+}
+
+pub async fn update_patch(
+    connection: &mut SqliteConnection,
+    id: i64,
+    name: &str,
+    index: i64,
+    rom_id: i64,
+    romfile_id: i64,
+) {
+    sqlx::query!(
+        "
+        UPDATE patches 
+        SET name = ?, \"index\" = ?, rom_id = ?, romfile_id = ?
+        WHERE id = ?
+        ",
+        name,
+        index,
+        rom_id,
+        romfile_id,
+        id,
+    )
+    .execute(connection)
+    .await
+    .unwrap_or_else(|_| panic!("Error while updating patch with id {}", id));
+}
+
+pub async fn find_patches_by_rom_id(connection: &mut SqliteConnection, rom_id: i64) -> Vec<Patch> {
+    sqlx::query_as!(
+        Patch,
+        "
+        SELECT *
+        FROM patches
+        WHERE rom_id = ?
+        ORDER BY \"index\"
+        ",
+        rom_id,
+    )
+    .fetch_all(connection)
+    .await
+    .unwrap_or_else(|_| panic!("Error while finding patches with rom id {}", rom_id))
+}
+
+pub async fn create_romfile(
+    connection: &mut SqliteConnection,
+    path: &str,
+    size: u64,
+    romfile_type: RomfileType,
+) -> i64 {
+    let size = i64::try_from(size).unwrap();
+    let romfile_type = romfile_type as i8;
+    sqlx::query!(
+        "
+        INSERT INTO romfiles (path, size, romfile_type, updated_at)
+        VALUES (?, ?, ?, CAST(strftime('%s', 'now') AS INTEGER))
+        ",
+        path,
+        size,
+        romfile_type,
+    )
+    .execute(connection)
+    .await
+    .expect("Error while creating romfile")
+    .last_insert_rowid()
+}
+
+pub async fn update_romfile(connection: &mut SqliteConnection, id: i64, path: &str, size: u64) {
+    let size = i64::try_from(size).unwrap();
+    sqlx::query!(
+        "
+        UPDATE romfiles 
+        SET path = ?, size = ?, updated_at = CAST(strftime('%s', 'now') AS INTEGER)
+        WHERE id = ?
+        ",
+        path,
+        size,
+        id,
+    )
+    .execute(connection)
+    .await
+    .unwrap_or_else(|_| panic!("Error while updating romfile with id {}", id));
+}
+
+pub async fn update_romfile_parent(
+    connection: &mut SqliteConnection,
+    id: i64,
+    parent_id: Option<i64>,
+) {
+    sqlx::query!(
+        "
+        UPDATE romfiles
+        SET parent_id = ?
+        WHERE id = ?
+        ",
+        parent_id,
+        id,
+    )
+    .execute(connection)
+    .await
+    .unwrap_or_else(|_| panic!("Error while updating romfile with id {}", id));
+}
+
+pub async fn find_romfiles(connection: &mut SqliteConnection) -> Vec<Romfile> {
+    sqlx::query_as!(
+        Romfile,
+        "
+        SELECT *
+        FROM romfiles
+        ORDER BY path
+        ",
+    )
+    .fetch_all(connection)
+    .await
+    .expect("Error while finding romfiles")
+}
+
+pub async fn find_romfiles_by_ids(connection: &mut SqliteConnection, ids: &[i64]) -> Vec<Romfile> {
+    let sql = format!(
+        "
+    SELECT *
+    FROM romfiles
+    WHERE id IN ({})
+    ORDER BY path
+    ",
+        ids.iter().join(",")
+    );
+    sqlx::query_as::<_, Romfile>(&sql)
+        .fetch_all(connection)
+        .await
+        .expect("Error while finding romfiles")
+}
+
+pub async fn find_romfiles_by_system_id(
+    connection: &mut SqliteConnection,
+    system_id: i64,
+) -> Vec<Romfile> {
+    sqlx::query_as!(
+        Romfile,
+        "
+        SELECT *
+        FROM romfiles
+        WHERE id IN (
+            SELECT DISTINCT(romfile_id)
+            FROM roms
+            WHERE game_id IN (
+                SELECT id
+                FROM games
+                WHERE system_id = ?
+            )
+        )
+        ORDER BY path
+        ",
+        system_id,
+    )
+    .fetch_all(connection)
+    .await
+    .unwrap_or_else(|_| panic!("Error while finding romfiles with system id {}", system_id))
+}
+
+pub async fn find_romfiles_by_system_id_and_extension_and_no_parent_id(
+    connection: &mut SqliteConnection,
+    system_id: i64,
+    extension: &str,
+) -> Vec<Romfile> {
+    let path_like = format!("%.{}", extension);
+    sqlx::query_as!(
+        Romfile,
+        "
+        SELECT *
+        FROM romfiles
+        WHERE id IN (
+            SELECT DISTINCT(romfile_id)
+            FROM roms
+            WHERE game_id IN (
+                SELECT id
+                FROM games
+                WHERE system_id = ?
+            )
+        )
+        AND path LIKE ?
+        AND parent_id IS NULL
+        ORDER BY path
+        ",
+        system_id,
+        path_like
+    )
+    .fetch_all(connection)
+    .await
+    .unwrap_or_else(|_| panic!("Error while finding romfiles with system id {}", system_id))
+}
+
+pub async fn find_romfiles_by_parent_id(
+    connection: &mut SqliteConnection,
+    parent_id: i64,
+) -> Vec<Romfile> {
+    sqlx::query_as!(
+        Romfile,
+        "
+        SELECT *
+        FROM romfiles
+        WHERE parent_id = ?
+        ORDER BY path
+        ",
+        parent_id,
+    )
+    .fetch_all(connection)
+    .await
+    .unwrap_or_else(|_| panic!("Error while finding romfiles with parent id {}", parent_id))
+}
+
+pub async fn find_orphan_romfiles(connection: &mut SqliteConnection) -> Vec<Romfile> {
+    sqlx::query_as!(
+        Romfile,
+        "
+        SELECT *
+        FROM romfiles rf
+        WHERE NOT EXISTS (
+            SELECT r.id
+            FROM roms r
+            WHERE r.romfile_id = rf.id
+        )
+        AND NOT EXISTS (
+            SELECT g.id
+            FROM games g
+            WHERE g.playlist_id = rf.id
+        )
+        ORDER BY path
+        ",
+    )
+    .fetch_all(connection)
+    .await
+    .expect("Error while finding orphan romfiles")
+}
+
+pub async fn find_romfiles_in_trash(connection: &mut SqliteConnection) -> Vec<Romfile> {
+    sqlx::query_as!(
+        Romfile,
+        "
+        SELECT *
+        FROM romfiles
+        WHERE path LIKE '%/Trash/%'
+        ORDER BY path
+        "
+    )
+    .fetch_all(connection)
+    .await
+    .expect("Error while finding romfiles in trash")
+}
+
+pub async fn find_romfiles_in_trash_older_than(
+    connection: &mut SqliteConnection,
+    days: i64,
+) -> Vec<Romfile> {
+    sqlx::query_as!(
+        Romfile,
+        "
+        SELECT *
+        FROM romfiles
+        WHERE path LIKE '%/Trash/%'
+        AND updated_at < CAST(strftime('%s', 'now') AS INTEGER) - ? * 86400
+        ORDER BY path
+        ",
+        days,
+    )
+    .fetch_all(connection)
+    .await
+    .unwrap_or_else(|_| {
+        panic!(
+            "Error while finding romfiles in trash older than {} days",
+            days
+        )
+    })
+}
+
+#[cfg(test)]
+pub async fn update_romfile_updated_at(
+    connection: &mut SqliteConnection,
+    id: i64,
+    updated_at: i64,
+) {
+    sqlx::query!(
+        "
+        UPDATE romfiles
+        SET updated_at = ?
+        WHERE id = ?
+        ",
+        updated_at,
+        id,
+    )
+    .execute(connection)
+    .await
+    .unwrap_or_else(|_| panic!("Error while updating romfile with id {}", id));
+}
+
+pub async fn find_playlist_romfiles_by_system_id(
+    connection: &mut SqliteConnection,
+    system_id: i64,
+) -> Vec<Romfile> {
+    sqlx::query_as!(
+        Romfile,
+        "
+        SELECT *
+        FROM romfiles
+        WHERE id IN (
+            SELECT DISTINCT(playlist_id)
+            FROM games
+            WHERE system_id = ?
+        )
+        ORDER BY path
+        ",
+        system_id,
+    )
+    .fetch_all(connection)
+    .await
+    .unwrap_or_else(|_| {
+        panic!(
+            "Error while finding playlist romfiles with system id {}",
+            system_id
+        )
+    })
+}
+
+pub async fn find_patch_romfiles_by_system_id(
+    connection: &mut SqliteConnection,
+    system_id: i64,
+) -> Vec<Romfile> {
+    sqlx::query_as!(
+        Romfile,
+        "
+        SELECT rf.*
+        FROM romfiles rf
+        JOIN patches p ON p.romfile_id = rf.id
+        JOIN roms r ON r.id = p.rom_id
+        JOIN games g ON g.id = r.game_id
+        WHERE g.system_id = ?
+        ORDER BY rf.path;
+        ",
+        system_id,
+    )
+    .fetch_all(connection)
+    .await
+    .unwrap_or_else(|_| {
+        panic!(
+            "Error while finding patch romfiles with system id {}",
+            system_id
+        )
+    })
+}
+
+pub async fn find_romfile_by_path(
+    connection: &mut SqliteConnection,
+    path: &str,
+) -> Option<Romfile> {
+    sqlx::query_as!(
+        Romfile,
+        "
+        SELECT *
+        FROM romfiles
+        WHERE path = ?
+        ",
+        path,
+    )
+    .fetch_optional(connection)
+    .await
+    .unwrap_or_else(|_| panic!("Error while finding romfile with path {}", path))
+}
+
+pub async fn find_romfile_by_id(connection: &mut SqliteConnection, id: i64) -> Romfile {
+    sqlx::query_as!(
+        Romfile,
+        "
+        SELECT *
+        FROM romfiles
+        WHERE id = ?
+        ",
+        id,
+    )
+    .fetch_one(connection)
+    .await
+    .unwrap_or_else(|_| panic!("Error while finding romfile with id {}", id))
+}
+
+pub async fn delete_romfile_by_id(connection: &mut SqliteConnection, id: i64) {
+    sqlx::query!(
+        "
+        DELETE FROM romfiles
+        WHERE id = ?
+        ",
+        id,
+    )
+    .execute(connection)
+    .await
+    .unwrap_or_else(|_| panic!("Error while deleting romfile with id {}", id));
+}
+
+pub async fn create_header_from_xml(
+    connection: &mut SqliteConnection,
+    detector_xml: &DetectorXml,
+    system_id: i64,
+) -> i64 {
+    let size = i64::from_str_radix(&detector_xml.rule.start_offset, 16).unwrap();
+    sqlx::query!(
+        "
+        INSERT INTO headers (name, version, size, system_id)
+        VALUES (?, ?, ?, ?)
+        ",
+        detector_xml.name,
+        detector_xml.version,
+        size,
+        system_id,
+    )
+    .execute(connection)
+    .await
+    .expect("Error while creating header")
+    .last_insert_rowid()
+}
+
+pub async fn create_header_from_header(
+    connection: &mut SqliteConnection,
+    header: &Header,
+    system_id: i64,
+) -> i64 {
+    sqlx::query!(
+        "
+        INSERT INTO headers (name, version, size, system_id)
+        VALUES (?, ?, ?, ?)
+        ",
+        header.name,
+        header.version,
+        header.size,
+        system_id,
+    )
+    .execute(connection)
+    .await
+    .expect("Error while creating header")
+    .last_insert_rowid()
+}
+
+pub async fn update_header_from_xml(
+    connection: &mut SqliteConnection,
+    id: i64,
+    detector_xml: &DetectorXml,
+    system_id: i64,
+) {
+    let size = i64::from_str_radix(&detector_xml.rule.start_offset, 16).unwrap();
+    sqlx::query!(
+        "
+        UPDATE headers
+        SET name = ?, version = ?, size = ?, system_id = ?
+        WHERE id = ?
+        ",
+        detector_xml.name,
+        detector_xml.version,
+        size,
+        system_id,
+        id,
+    )
+    .execute(connection)
+    .await
+    .unwrap_or_else(|_| panic!("Error while updating header with id {}", id));
+}
+
+pub async fn find_header_by_system_id(
+    connection: &mut SqliteConnection,
+    system_id: i64,
+) -> Option<Header> {
+    sqlx::query_as!(
+        Header,
+        "
+        SELECT *
+        FROM headers
+        WHERE system_id = ?
+        ",
+        system_id,
+    )
+    .fetch_optional(connection)
+    .await
+    .unwrap_or_else(|_| panic!("Error while finding header with system id {}", system_id))
+}
+
+pub async fn create_rule_from_xml(
+    connection: &mut SqliteConnection,
+    data_xml: &DataXml,
+    header_id: i64,
+) -> i64 {
+    let start_byte = i64::from_str_radix(&data_xml.offset, 16).unwrap();
+    sqlx::query!(
+        "
+        INSERT INTO rules (start_byte, hex_value, header_id)
+        VALUES (?, ?, ?)
+        ",
+        start_byte,
+        data_xml.value,
+        header_id,
+    )
+    .execute(connection)
+    .await
+    .expect("Error while creating rule")
+    .last_insert_rowid()
+}
+
+pub async fn create_rule_from_rule(
+    connection: &mut SqliteConnection,
+    rule: &Rule,
+    header_id: i64,
+) -> i64 {
+    sqlx::query!(
+        "
+        INSERT INTO rules (start_byte, hex_value, header_id)
+        VALUES (?, ?, ?)
+        ",
+        rule.start_byte,
+        rule.hex_value,
+        header_id,
+    )
+    .execute(connection)
+    .await
+    .expect("Error while creating rule")
+    .last_insert_rowid()
+}
+
+pub async fn find_rules_by_header_id(
+    connection: &mut SqliteConnection,
+    header_id: i64,
+) -> Vec<Rule> {
+    sqlx::query_as!(
+        Rule,
+        "
+        SELECT *
+        FROM rules
+        WHERE header_id = ?
+        ",
+        header_id,
+    )
+    .fetch_all(connection)
+    .await
+    .unwrap_or_else(|_| panic!("Error while finding rules with header id {}", header_id))
+}
+
+pub async fn delete_rules_by_header_id(connection: &mut SqliteConnection, header_id: i64) {
+    sqlx::query!(
+        "
+        DELETE FROM rules
+        WHERE header_id = ?
         ",
-        name,
-        game_id,
+        header_id,
     )
     .execute(connection)
     .await
-    .unwrap_or_else(|_| {
-        panic!(
-            "Error while deleting rom with name {} and game_id {}",
-            name, game_id
-        )
-    });
+    .unwrap_or_else(|_| panic!("Error while deleting rules with header_id {}", header_id));
 }
 
-pub async fn create_patch(
-    connection: &mut SqliteConnection,
-    name: &str,
-    index: i64,
-    rom_id: i64,
-    romfile_id: i64,
-) -> i64 {
+pub async fn create_setting(connection: &mut SqliteConnection, key: &str, value: Option<String>) {
     sqlx::query!(
         "
-        INSERT INTO patches (name, \"index\", rom_id, romfile_id)
-        VALUES (?, ?, ?, ?)
+        INSERT INTO settings (key, value)
+        VALUES (?, ?)
         ",
-        name,
-        index,
-        rom_id,
-        romfile_id,
+        key,
+        value,
     )
     .execute(connection)
     .await
-    .expect("Error while creating patch")
-    .last_insert_rowid()
+    .expect("Error while creating setting");
 }
 
-pub async fn update_patch(
-    connection: &mut SqliteConnection,
-    id: i64,
-    name: &str,
-    index: i64,
-    rom_id: i64,
-    romfile_id: i64,
-) {
+pub async fn update_setting(connection: &mut SqliteConnection, id: i64, value: Option<String>) {
     sqlx::query!(
         "
-        UPDATE patches 
-        SET name = ?, \"index\" = ?, rom_id = ?, romfile_id = ?
+        UPDATE settings
+        SET value = ?
         WHERE id = ?
         ",
-        name,
-        index,
-        rom_id,
-        romfile_id,
+        value,
         id,
     )
     .execute(connection)
     .await
-    .unwrap_or_else(|_| panic!("Error while updating patch with id {}", id));
+    .unwrap_or_else(|_| panic!("Error while updating setting with id {}", id));
 }
 
-pub async fn find_patches_by_rom_id(connection: &mut SqliteConnection, rom_id: i64) -> Vec<Patch> {
+pub async fn find_settings(connection: &mut SqliteConnection) -> Vec<Setting> {
     sqlx::query_as!(
-        Patch,
+        Setting,
         "
         SELECT *
-        FROM patches
-        WHERE rom_id = ?
-        ORDER BY \"index\"
+        FROM settings
+        ORDER BY key
         ",
-        rom_id,
     )
     .fetch_all(connection)
     .await
-    .unwrap_or_else(|_| panic!("Error while finding patches with rom id {}", rom_id))
+    .expect("Error while finding settings")
 }
 
-pub async fn create_romfile(
+pub async fn find_setting_by_key(connection: &mut SqliteConnection, key: &str) -> Option<Setting> {
+    sqlx::query_as!(
+        Setting,
+        "
+        SELECT *
+        FROM settings
+        WHERE key = ?
+        ",
+        key,
+    )
+    .fetch_optional(connection)
+    .await
+    .unwrap_or_else(|_| panic!("Error while finding setting with key {}", key))
+}
+
+pub async fn create_dat_source(
     connection: &mut SqliteConnection,
-    path: &str,
-    size: u64,
-    romfile_type: RomfileType,
+    url: &str,
+    header: Option<&String>,
 ) -> i64 {
-    let size = i64::try_from(size).unwrap();
-    let romfile_type = romfile_type as i8;
     sqlx::query!(
         "
-        INSERT INTO romfiles (path, size, romfile_type)
-        VALUES (?, ?, ?)
+        INSERT INTO dat_sources (url, header)
+        VALUES (?, ?)
         ",
-        path,
-        size,
-        romfile_type,
+        url,
+        header,
     )
     .execute(connection)
     .await
-    .expect("Error while creating romfile")
+    .expect("Error while creating DAT source")
     .last_insert_rowid()
 }
 
-pub async fn update_romfile(connection: &mut SqliteConnection, id: i64, path: &str, size: u64) {
-    let size = i64::try_from(size).unwrap();
-    sqlx::query!(
+pub async fn find_dat_sources(connection: &mut SqliteConnection) -> Vec<DatSource> {
+    sqlx::query_as!(
+        DatSource,
         "
-        UPDATE romfiles 
-        SET path = ?, size = ?
-        WHERE id = ?
+        SELECT *
+        FROM dat_sources
+        ORDER BY url
         ",
-        path,
-        size,
-        id,
     )
-    .execute(connection)
+    .fetch_all(connection)
     .await
-    .unwrap_or_else(|_| panic!("Error while updating romfile with id {}", id));
+    .expect("Error while finding DAT sources")
 }
 
-pub async fn update_romfile_parent(
+pub async fn find_dat_source_by_url(
     connection: &mut SqliteConnection,
-    id: i64,
-    parent_id: Option<i64>,
-) {
+    url: &str,
+) -> Option<DatSource> {
+    sqlx::query_as!(
+        DatSource,
+        "
+        SELECT *
+        FROM dat_sources
+        WHERE url = ?
+        ",
+        url,
+    )
+    .fetch_optional(connection)
+    .await
+    .unwrap_or_else(|_| panic!("Error while finding DAT source with url {}", url))
+}
+
+pub async fn delete_dat_source_by_id(connection: &mut SqliteConnection, id: i64) {
     sqlx::query!(
         "
-        UPDATE romfiles
-        SET parent_id = ?
+        DELETE FROM dat_sources
         WHERE id = ?
         ",
-        parent_id,
         id,
     )
     .execute(connection)
     .await
-    .unwrap_or_else(|_| panic!("Error while updating romfile with id {}", id));
+    .unwrap_or_else(|_| panic!("Error while deleting DAT source with id {}", id));
 }
 
-pub async fn find_romfiles(connection: &mut SqliteConnection) -> Vec<Romfile> {
-    sqlx::query_as!(
-        Romfile,
+pub async fn create_romfile_move(
+    connection: &mut SqliteConnection,
+    romfile_id: i64,
+    old_path: &str,
+    new_path: &str,
+) -> i64 {
+    sqlx::query!(
         "
-        SELECT *
-        FROM romfiles
-        ORDER BY path
+        INSERT OR REPLACE INTO romfile_moves (old_path, new_path, romfile_id)
+        VALUES (?, ?, ?)
         ",
+        old_path,
+        new_path,
+        romfile_id,
     )
-    .fetch_all(connection)
+    .execute(connection)
     .await
-    .expect("Error while finding romfiles")
-}
-
-pub async fn find_romfiles_by_ids(connection: &mut SqliteConnection, ids: &[i64]) -> Vec<Romfile> {
-    let sql = format!(
-        "
-    SELECT *
-    FROM romfiles
-    WHERE id IN ({})
-    ORDER BY path
-    ",
-        ids.iter().join(",")
-    );
-    sqlx::query_as::<_, Romfile>(&sql)
-        .fetch_all(connection)
-        .await
-        .expect("Error while finding romfiles")
+    .expect("Error while creating romfile move")
+    .last_insert_rowid()
 }
 
-pub async fn find_romfiles_by_system_id(
-    connection: &mut SqliteConnection,
-    system_id: i64,
-) -> Vec<Romfile> {
+pub async fn find_romfile_moves(connection: &mut SqliteConnection) -> Vec<RomfileMove> {
     sqlx::query_as!(
-        Romfile,
+        RomfileMove,
         "
         SELECT *
-        FROM romfiles
-        WHERE id IN (
-            SELECT DISTINCT(romfile_id)
-            FROM roms
-            WHERE game_id IN (
-                SELECT id
-                FROM games
-                WHERE system_id = ?
-            )
-        )
-        ORDER BY path
+        FROM romfile_moves
+        ORDER BY id
         ",
-        system_id,
     )
     .fetch_all(connection)
     .await
-    .unwrap_or_else(|_| panic!("Error while finding romfiles with system id {}", system_id))
+    .expect("Error while finding romfile moves")
 }
 
-pub async fn find_romfiles_by_system_id_and_extension_and_no_parent_id(
-    connection: &mut SqliteConnection,
-    system_id: i64,
-    extension: &str,
-) -> Vec<Romfile> {
-    let path_like = format!("%.{}", extension);
-    sqlx::query_as!(
-        Romfile,
+pub async fn delete_romfile_move_by_romfile_id(connection: &mut SqliteConnection, romfile_id: i64) {
+    sqlx::query!(
         "
-        SELECT *
-        FROM romfiles
-        WHERE id IN (
-            SELECT DISTINCT(romfile_id)
-            FROM roms
-            WHERE game_id IN (
-                SELECT id
-                FROM games
-                WHERE system_id = ?
-            )
-        )
-        AND path LIKE ?
-        AND parent_id IS NULL
-        ORDER BY path
+        DELETE FROM romfile_moves
+        WHERE romfile_id = ?
         ",
-        system_id,
-        path_like
+        romfile_id,
     )
-    .fetch_all(connection)
+    .execute(connection)
     .await
-    .unwrap_or_else(|_| panic!("Error while finding romfiles with system id {}", system_id))
+    .expect("Error while deleting romfile move");
 }
 
-pub async fn find_romfiles_by_parent_id(
+pub async fn create_rom_alt_name(
     connection: &mut SqliteConnection,
-    parent_id: i64,
-) -> Vec<Romfile> {
-    sqlx::query_as!(
-        Romfile,
+    name: &str,
+    region: Option<&str>,
+    rom_id: i64,
+) -> i64 {
+    sqlx::query!(
         "
-        SELECT *
-        FROM romfiles
-        WHERE parent_id = ?
-        ORDER BY path
+        INSERT INTO rom_alt_names (name, region, rom_id)
+        VALUES (?, ?, ?)
         ",
-        parent_id,
+        name,
+        region,
+        rom_id,
     )
-    .fetch_all(connection)
+    .execute(connection)
     .await
-    .unwrap_or_else(|_| panic!("Error while finding romfiles with parent id {}", parent_id))
+    .unwrap_or_else(|_| panic!("Error while creating rom alt name {}", name))
+    .last_insert_rowid()
 }
 
-pub async fn find_orphan_romfiles(connection: &mut SqliteConnection) -> Vec<Romfile> {
-    sqlx::query_as!(
-        Romfile,
+pub async fn find_rom_alt_names_by_rom_ids(
+    connection: &mut SqliteConnection,
+    rom_ids: &[i64],
+) -> Vec<RomAltName> {
+    let sql = format!(
         "
-        SELECT *
-        FROM romfiles rf
-        WHERE NOT EXISTS (
-            SELECT r.id
-            FROM roms r
-            WHERE r.romfile_id = rf.id
-        )
-        AND NOT EXISTS (
-            SELECT g.id
-            FROM games g
-            WHERE g.playlist_id = rf.id
-        )
-        ORDER BY path
+    SELECT *
+    FROM rom_alt_names
+    WHERE rom_id IN ({})
+    ORDER BY id
+    ",
+        rom_ids.iter().join(",")
+    );
+    sqlx::query_as::<_, RomAltName>(&sql)
+        .fetch_all(connection)
+        .await
+        .expect("Error while finding rom alt names")
+}
+
+pub async fn delete_rom_alt_names_by_rom_id(connection: &mut SqliteConnection, rom_id: i64) {
+    sqlx::query!(
+        "
+        DELETE FROM rom_alt_names
+        WHERE rom_id = ?
         ",
+        rom_id,
     )
-    .fetch_all(connection)
+    .execute(connection)
     .await
-    .expect("Error while finding orphan romfiles")
+    .unwrap_or_else(|_| panic!("Error while deleting rom alt names with rom id {}", rom_id));
 }
 
-pub async fn find_romfiles_in_trash(connection: &mut SqliteConnection) -> Vec<Romfile> {
-    sqlx::query_as!(
-        Romfile,
-        "
-        SELECT *
-        FROM romfiles
-        WHERE path LIKE '%/Trash/%'
-        ORDER BY path
+pub async fn create_game_release(
+    connection: &mut SqliteConnection,
+    release_xml: &ReleaseXml,
+    game_id: i64,
+) -> i64 {
+    sqlx::query!(
         "
+        INSERT INTO game_releases (name, region, language, date, game_id)
+        VALUES (?, ?, ?, ?, ?)
+        ",
+        release_xml.name,
+        release_xml.region,
+        release_xml.language,
+        release_xml.date,
+        game_id,
     )
-    .fetch_all(connection)
+    .execute(connection)
     .await
-    .expect("Error while finding romfiles in trash")
+    .unwrap_or_else(|_| panic!("Error while creating game release {}", release_xml.name))
+    .last_insert_rowid()
 }
 
-pub async fn find_playlist_romfiles_by_system_id(
+pub async fn find_game_releases_by_game_ids(
     connection: &mut SqliteConnection,
-    system_id: i64,
-) -> Vec<Romfile> {
-    sqlx::query_as!(
-        Romfile,
+    game_ids: &[i64],
+) -> Vec<GameRelease> {
+    let sql = format!(
         "
-        SELECT *
-        FROM romfiles
-        WHERE id IN (
-            SELECT DISTINCT(playlist_id)
-            FROM games
-            WHERE system_id = ?
-        )
-        ORDER BY path
+    SELECT *
+    FROM game_releases
+    WHERE game_id IN ({})
+    ORDER BY id
+    ",
+        game_ids.iter().join(",")
+    );
+    sqlx::query_as::<_, GameRelease>(&sql)
+        .fetch_all(connection)
+        .await
+        .expect("Error while finding game releases")
+}
+
+pub async fn delete_game_releases_by_game_id(connection: &mut SqliteConnection, game_id: i64) {
+    sqlx::query!(
+        "
+        DELETE FROM game_releases
+        WHERE game_id = ?
         ",
-        system_id,
+        game_id,
     )
-    .fetch_all(connection)
+    .execute(connection)
     .await
     .unwrap_or_else(|_| {
         panic!(
-            "Error while finding playlist romfiles with system id {}",
-            system_id
+            "Error while deleting game releases with game id {}",
+            game_id
         )
-    })
+    });
 }
 
-pub async fn find_patch_romfiles_by_system_id(
+pub async fn create_or_update_romfile_partial_hash(
     connection: &mut SqliteConnection,
-    system_id: i64,
-) -> Vec<Romfile> {
-    sqlx::query_as!(
-        Romfile,
+    romfile_id: i64,
+    window_size: i64,
+    crc: &str,
+    updated_at: i64,
+) {
+    sqlx::query!(
         "
-        SELECT rf.*
-        FROM romfiles rf
-        JOIN patches p ON p.romfile_id = rf.id
-        JOIN roms r ON r.id = p.rom_id
-        JOIN games g ON g.id = r.game_id
-        WHERE g.system_id = ?
-        ORDER BY rf.path;
+        INSERT INTO romfile_partial_hashes (romfile_id, window_size, crc, updated_at)
+        VALUES (?, ?, ?, ?)
+        ON CONFLICT(romfile_id) DO UPDATE
+        SET window_size = excluded.window_size, crc = excluded.crc, updated_at = excluded.updated_at
         ",
-        system_id,
+        romfile_id,
+        window_size,
+        crc,
+        updated_at,
     )
-    .fetch_all(connection)
+    .execute(connection)
     .await
     .unwrap_or_else(|_| {
         panic!(
-            "Error while finding patch romfiles with system id {}",
-            system_id
+            "Error while creating or updating partial hash with romfile id {}",
+            romfile_id
         )
-    })
+    });
 }
 
-pub async fn find_romfile_by_path(
+pub async fn find_romfile_partial_hash_by_romfile_id(
     connection: &mut SqliteConnection,
-    path: &str,
-) -> Option<Romfile> {
+    romfile_id: i64,
+) -> Option<RomfilePartialHash> {
     sqlx::query_as!(
-        Romfile,
+        RomfilePartialHash,
         "
         SELECT *
-        FROM romfiles
-        WHERE path = ?
+        FROM romfile_partial_hashes
+        WHERE romfile_id = ?
         ",
-        path,
+        romfile_id,
     )
     .fetch_optional(connection)
     .await
-    .unwrap_or_else(|_| panic!("Error while finding romfile with path {}", path))
+    .expect("Error while finding partial hash")
 }
 
-pub async fn find_romfile_by_id(connection: &mut SqliteConnection, id: i64) -> Romfile {
-    sqlx::query_as!(
-        Romfile,
+pub async fn create_or_update_unmatched(
+    connection: &mut SqliteConnection,
+    path: &str,
+    size: i64,
+    goodtools_status: Option<&str>,
+) {
+    sqlx::query!(
         "
-        SELECT *
-        FROM romfiles
-        WHERE id = ?
+        INSERT INTO unmatched (path, size, goodtools_status)
+        VALUES (?, ?, ?)
+        ON CONFLICT(path) DO UPDATE
+        SET size = excluded.size,
+        goodtools_status = excluded.goodtools_status
         ",
-        id,
+        path,
+        size,
+        goodtools_status,
     )
-    .fetch_one(connection)
+    .execute(connection)
     .await
-    .unwrap_or_else(|_| panic!("Error while finding romfile with id {}", id))
+    .unwrap_or_else(|_| panic!("Error while creating or updating unmatched file {}", path));
 }
 
-pub async fn delete_romfile_by_id(connection: &mut SqliteConnection, id: i64) {
-    sqlx::query!(
+pub async fn find_unmatched(connection: &mut SqliteConnection) -> Vec<Unmatched> {
+    sqlx::query_as!(
+        Unmatched,
         "
-        DELETE FROM romfiles
-        WHERE id = ?
+        SELECT *
+        FROM unmatched
+        ORDER BY path
         ",
-        id,
     )
-    .execute(connection)
+    .fetch_all(connection)
     .await
-    .unwrap_or_else(|_| panic!("Error while deleting romfile with id {}", id));
+    .expect("Error while finding unmatched files")
 }
 
-pub async fn create_header_from_xml(
-    connection: &mut SqliteConnection,
-    detector_xml: &DetectorXml,
-    system_id: i64,
-) -> i64 {
-    let size = i64::from_str_radix(&detector_xml.rule.start_offset, 16).unwrap();
+pub async fn delete_unmatched_by_path(connection: &mut SqliteConnection, path: &str) {
     sqlx::query!(
         "
-        INSERT INTO headers (name, version, size, system_id)
-        VALUES (?, ?, ?, ?)
+        DELETE FROM unmatched
+        WHERE path = ?
         ",
-        detector_xml.name,
-        detector_xml.version,
-        size,
-        system_id,
+        path,
     )
     .execute(connection)
     .await
-    .expect("Error while creating header")
-    .last_insert_rowid()
+    .unwrap_or_else(|_| panic!("Error while deleting unmatched file {}", path));
 }
 
-pub async fn update_header_from_xml(
+pub async fn create_or_update_cuesheet(
     connection: &mut SqliteConnection,
-    id: i64,
-    detector_xml: &DetectorXml,
-    system_id: i64,
+    name: &str,
+    content: &str,
+    game_id: i64,
 ) {
-    let size = i64::from_str_radix(&detector_xml.rule.start_offset, 16).unwrap();
     sqlx::query!(
         "
-        UPDATE headers
-        SET name = ?, version = ?, size = ?, system_id = ?
-        WHERE id = ?
+        INSERT INTO cuesheets (name, content, game_id)
+        VALUES (?, ?, ?)
+        ON CONFLICT(game_id) DO UPDATE
+        SET name = excluded.name, content = excluded.content
         ",
-        detector_xml.name,
-        detector_xml.version,
-        size,
-        system_id,
-        id,
+        name,
+        content,
+        game_id,
     )
     .execute(connection)
     .await
-    .unwrap_or_else(|_| panic!("Error while updating header with id {}", id));
+    .unwrap_or_else(|_| panic!("Error while creating or updating cuesheet {}", name));
 }
 
-pub async fn find_header_by_system_id(
+pub async fn find_cuesheet_by_game_id(
     connection: &mut SqliteConnection,
-    system_id: i64,
-) -> Option<Header> {
+    game_id: i64,
+) -> Option<Cuesheet> {
     sqlx::query_as!(
-        Header,
+        Cuesheet,
         "
         SELECT *
-        FROM headers
-        WHERE system_id = ?
+        FROM cuesheets
+        WHERE game_id = ?
         ",
-        system_id,
+        game_id,
     )
     .fetch_optional(connection)
     .await
-    .unwrap_or_else(|_| panic!("Error while finding header with system id {}", system_id))
+    .unwrap_or_else(|_| panic!("Error while finding cuesheet with game id {}", game_id))
 }
 
-pub async fn create_rule_from_xml(
-    connection: &mut SqliteConnection,
-    data_xml: &DataXml,
-    header_id: i64,
-) -> i64 {
-    let start_byte = i64::from_str_radix(&data_xml.offset, 16).unwrap();
+pub async fn create_tag(connection: &mut SqliteConnection, name: &str) -> i64 {
     sqlx::query!(
         "
-        INSERT INTO rules (start_byte, hex_value, header_id)
-        VALUES (?, ?, ?)
+        INSERT INTO tags (name)
+        VALUES (?)
         ",
-        start_byte,
-        data_xml.value,
-        header_id,
+        name,
     )
     .execute(connection)
     .await
-    .expect("Error while creating rule")
+    .unwrap_or_else(|_| panic!("Error while creating tag {}", name))
     .last_insert_rowid()
 }
 
-pub async fn find_rules_by_header_id(
-    connection: &mut SqliteConnection,
-    header_id: i64,
-) -> Vec<Rule> {
+pub async fn find_tag_by_name(connection: &mut SqliteConnection, name: &str) -> Option<Tag> {
     sqlx::query_as!(
-        Rule,
+        Tag,
         "
         SELECT *
-        FROM rules
-        WHERE header_id = ?
+        FROM tags
+        WHERE name = ?
         ",
-        header_id,
+        name,
     )
-    .fetch_all(connection)
+    .fetch_optional(connection)
     .await
-    .unwrap_or_else(|_| panic!("Error while finding rules with header id {}", header_id))
+    .unwrap_or_else(|_| panic!("Error while finding tag {}", name))
 }
 
-pub async fn delete_rules_by_header_id(connection: &mut SqliteConnection, header_id: i64) {
+pub async fn create_game_tag(connection: &mut SqliteConnection, game_id: i64, tag_id: i64) -> u64 {
     sqlx::query!(
         "
-        DELETE FROM rules
-        WHERE header_id = ?
+        INSERT OR IGNORE INTO games_tags (game_id, tag_id)
+        VALUES (?, ?)
         ",
-        header_id,
+        game_id,
+        tag_id,
     )
     .execute(connection)
     .await
-    .unwrap_or_else(|_| panic!("Error while deleting rules with header_id {}", header_id));
+    .unwrap_or_else(|_| panic!("Error while tagging game with id {}", game_id))
+    .rows_affected()
 }
 
-pub async fn create_setting(connection: &mut SqliteConnection, key: &str, value: Option<String>) {
-    sqlx::query!(
+pub async fn find_games_by_tag_name(connection: &mut SqliteConnection, name: &str) -> Vec<Game> {
+    sqlx::query_as!(
+        Game,
         "
-        INSERT INTO settings (key, value)
-        VALUES (?, ?)
+        SELECT g.*
+        FROM games AS g
+        JOIN games_tags AS gt ON gt.game_id = g.id
+        JOIN tags AS t ON t.id = gt.tag_id
+        WHERE t.name = ?
+        ORDER BY g.name
         ",
-        key,
-        value,
+        name,
     )
-    .execute(connection)
+    .fetch_all(connection)
     .await
-    .expect("Error while creating setting");
+    .unwrap_or_else(|_| panic!("Error while finding games with tag {}", name))
 }
 
-pub async fn update_setting(connection: &mut SqliteConnection, id: i64, value: Option<String>) {
+pub async fn create_event(
+    connection: &mut SqliteConnection,
+    game_id: i64,
+    event_type: EventType,
+    source: Option<&str>,
+    destination: &str,
+) -> i64 {
+    let event_type = event_type as i8;
     sqlx::query!(
         "
-        UPDATE settings
-        SET value = ?
-        WHERE id = ?
+        INSERT INTO events (event_type, source, destination, created_at, game_id)
+        VALUES (?, ?, ?, CAST(strftime('%s', 'now') AS INTEGER), ?)
         ",
-        value,
-        id,
+        event_type,
+        source,
+        destination,
+        game_id,
     )
     .execute(connection)
     .await
-    .unwrap_or_else(|_| panic!("Error while updating setting with id {}", id));
+    .unwrap_or_else(|_| panic!("Error while creating event for game with id {}", game_id))
+    .last_insert_rowid()
 }
 
-pub async fn find_settings(connection: &mut SqliteConnection) -> Vec<Setting> {
-    sqlx::query_as!(
-        Setting,
+pub async fn create_events_by_romfile_id(
+    connection: &mut SqliteConnection,
+    romfile_id: i64,
+    event_type: EventType,
+    source: Option<&str>,
+    destination: &str,
+) -> u64 {
+    let event_type = event_type as i8;
+    sqlx::query!(
         "
-        SELECT *
-        FROM settings
-        ORDER BY key
+        INSERT INTO events (event_type, source, destination, created_at, game_id)
+        SELECT DISTINCT ?, ?, ?, CAST(strftime('%s', 'now') AS INTEGER), game_id
+        FROM roms
+        WHERE romfile_id = ?
         ",
+        event_type,
+        source,
+        destination,
+        romfile_id,
     )
-    .fetch_all(connection)
+    .execute(connection)
     .await
-    .expect("Error while finding settings")
+    .unwrap_or_else(|_| {
+        panic!(
+            "Error while creating events for romfile with id {}",
+            romfile_id
+        )
+    })
+    .rows_affected()
 }
 
-pub async fn find_setting_by_key(connection: &mut SqliteConnection, key: &str) -> Option<Setting> {
+pub async fn find_events_by_game_id(connection: &mut SqliteConnection, game_id: i64) -> Vec<Event> {
     sqlx::query_as!(
-        Setting,
+        Event,
         "
         SELECT *
-        FROM settings
-        WHERE key = ?
+        FROM events
+        WHERE game_id = ?
+        ORDER BY created_at, id
         ",
-        key,
+        game_id,
     )
-    .fetch_optional(connection)
+    .fetch_all(connection)
     .await
-    .unwrap_or_else(|_| panic!("Error while finding setting with key {}", key))
+    .unwrap_or_else(|_| panic!("Error while finding events for game with id {}", game_id))
 }
+
+#[cfg(test)]
+mod test_optimize_database;
@@ -0,0 +1,60 @@
+use super::super::config::MUTEX;
+use super::super::import_dats;
+use super::*;
+use std::time::Instant;
+use tempfile::NamedTempFile;
+
+async fn get_pragma(connection: &mut SqliteConnection, pragma: &str) -> i64 {
+    sqlx::query_scalar(&format!("PRAGMA {}", pragma))
+        .fetch_one(connection)
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn test() {
+    // given
+    let _guard = MUTEX.lock().await;
+
+    let progress_bar = ProgressBar::hidden();
+
+    let db_file = NamedTempFile::new().unwrap();
+    let pool = establish_connection(db_file.path().to_str().unwrap()).await;
+    let mut connection = pool.acquire().await.unwrap();
+
+    let matches = import_dats::subcommand()
+        .get_matches_from(&["import-dats", "tests/Test System (20200721).dat"]);
+    import_dats::main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    // free a few hundred pages for the incremental vacuum to reclaim
+    (&mut *connection)
+        .execute(
+            "
+            CREATE TABLE scratch (data BLOB);
+            INSERT INTO scratch VALUES (randomblob(1000000));
+            DROP TABLE scratch;
+            ",
+        )
+        .await
+        .unwrap();
+    let page_count = get_pragma(&mut connection, "page_count").await;
+    assert!(get_pragma(&mut connection, "freelist_count").await > 0);
+    drop(connection);
+
+    progress_bar.set_message("Importing DATs");
+
+    // when
+    let start = Instant::now();
+    let elapsed = optimize_database(pool.clone(), &progress_bar).await;
+
+    // then
+    assert!(elapsed <= start.elapsed());
+    assert_eq!(progress_bar.message(), "");
+
+    let mut connection = pool.acquire().await.unwrap();
+    assert_eq!(get_pragma(&mut connection, "freelist_count").await, 0);
+    assert!(get_pragma(&mut connection, "page_count").await < page_count);
+    assert_eq!(find_systems(&mut connection).await.len(), 1);
+}
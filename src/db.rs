@@ -0,0 +1,342 @@
+use super::config::*;
+use super::database::*;
+use super::model::*;
+use super::util::*;
+use super::SimpleResult;
+use clap::{value_parser, Arg, ArgMatches, Command};
+use indicatif::ProgressBar;
+use num_traits::FromPrimitive;
+use sqlx::sqlite::SqliteConnection;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+pub fn subcommand() -> Command {
+    Command::new("db")
+        .about("Manage the oxyromon database")
+        .arg(
+            Arg::new("MERGE")
+                .short('m')
+                .long("merge")
+                .help("Merge another oxyromon database into this one")
+                .required(true)
+                .num_args(1)
+                .value_parser(value_parser!(PathBuf)),
+        )
+}
+
+pub async fn main(
+    connection: &mut SqliteConnection,
+    matches: &ArgMatches,
+    progress_bar: &ProgressBar,
+) -> SimpleResult<()> {
+    let database_path =
+        get_canonicalized_path(matches.get_one::<PathBuf>("MERGE").unwrap()).await?;
+    if !database_path.is_file() {
+        bail!(
+            "\"{}\" is not a file",
+            database_path.as_os_str().to_str().unwrap()
+        );
+    }
+    // the other database is only read, it may belong to another version or still be in use
+    let pool = establish_read_only_connection(database_path.as_os_str().to_str().unwrap()).await;
+    let mut other_connection = pool.acquire().await.unwrap();
+    if !is_migrated(&mut other_connection).await {
+        bail!(
+            "\"{}\" is out of date, please run this version of oxyromon against it first",
+            database_path.as_os_str().to_str().unwrap()
+        );
+    }
+    let other_rom_directory = match get_directory(&mut other_connection, "ROM_DIRECTORY").await {
+        Some(other_rom_directory) => other_rom_directory,
+        None => bail!(
+            "ROM_DIRECTORY of \"{}\" is not available",
+            database_path.as_os_str().to_str().unwrap()
+        ),
+    };
+    merge_database(
+        connection,
+        &mut other_connection,
+        progress_bar,
+        &other_rom_directory,
+    )
+    .await
+}
+
+async fn merge_database(
+    connection: &mut SqliteConnection,
+    other_connection: &mut SqliteConnection,
+    progress_bar: &ProgressBar,
+    other_rom_directory: &Path,
+) -> SimpleResult<()> {
+    let other_systems = find_systems(other_connection).await;
+    let mut other_games_by_system_id: HashMap<i64, Vec<Game>> = HashMap::new();
+    find_games(other_connection)
+        .await
+        .into_iter()
+        .for_each(|game| {
+            let group = other_games_by_system_id.entry(game.system_id).or_default();
+            group.push(game);
+        });
+    let mut other_roms_by_game_id: HashMap<i64, Vec<Rom>> = HashMap::new();
+    find_roms(other_connection)
+        .await
+        .into_iter()
+        .for_each(|rom| {
+            let group = other_roms_by_game_id.entry(rom.game_id).or_default();
+            group.push(rom);
+        });
+    let other_romfiles_by_id: HashMap<i64, Romfile> = find_romfiles(other_connection)
+        .await
+        .into_iter()
+        .map(|romfile| (romfile.id, romfile))
+        .collect();
+
+    let mut system_ids: Vec<i64> = vec![];
+    // conflicting romfiles are mapped to none
+    let mut romfile_ids: HashMap<i64, Option<i64>> = HashMap::new();
+    let mut new_romfile_ids: Vec<(i64, i64)> = vec![];
+    let mut new_systems_count = 0;
+    let mut new_games_count = 0;
+    let mut new_roms_count = 0;
+    let mut duplicate_roms_count = 0;
+
+    let mut transaction = begin_transaction(connection).await;
+
+    for other_system in other_systems {
+        progress_bar.println(format!("Merging \"{}\"", other_system.name));
+
+        let system_id = match find_system_by_name(&mut transaction, &other_system.name).await {
+            Some(system) => system.id,
+            None => {
+                let system_id = create_system_from_system(&mut transaction, &other_system).await;
+                if let Some(header) =
+                    find_header_by_system_id(other_connection, other_system.id).await
+                {
+                    let header_id =
+                        create_header_from_header(&mut transaction, &header, system_id).await;
+                    for rule in find_rules_by_header_id(other_connection, header.id).await {
+                        create_rule_from_rule(&mut transaction, &rule, header_id).await;
+                    }
+                }
+                new_systems_count += 1;
+                system_id
+            }
+        };
+        system_ids.push(system_id);
+
+        // games are matched by name, bioses and parents come first so that clones can reference them
+        let mut other_games = other_games_by_system_id
+            .remove(&other_system.id)
+            .unwrap_or_default();
+        other_games.sort_by_key(|game| (game.parent_id.is_some(), game.bios_id.is_some()));
+        let mut game_ids_by_name: HashMap<String, i64> =
+            find_games_by_system_id(&mut transaction, system_id)
+                .await
+                .into_iter()
+                .map(|game| (game.name, game.id))
+                .collect();
+        let mut game_ids: HashMap<i64, i64> = HashMap::new();
+        for other_game in &other_games {
+            let game_id = match game_ids_by_name.get(&other_game.name) {
+                Some(game_id) => *game_id,
+                None => {
+                    let game_id = create_game_from_game(
+                        &mut transaction,
+                        other_game,
+                        system_id,
+                        other_game
+                            .parent_id
+                            .and_then(|parent_id| game_ids.get(&parent_id).copied()),
+                        other_game
+                            .bios_id
+                            .and_then(|bios_id| game_ids.get(&bios_id).copied()),
+                    )
+                    .await;
+                    game_ids_by_name.insert(other_game.name.clone(), game_id);
+                    new_games_count += 1;
+                    game_id
+                }
+            };
+            game_ids.insert(other_game.id, game_id);
+        }
+
+        // ROMs are matched by name and content
+        let game_ids_values: Vec<i64> = game_ids.values().copied().collect();
+        let mut roms = find_roms_with_romfile_by_game_ids(&mut transaction, &game_ids_values).await;
+        roms.append(
+            &mut find_roms_without_romfile_by_game_ids(&mut transaction, &game_ids_values).await,
+        );
+        let mut roms_by_game_id: HashMap<i64, Vec<Rom>> = HashMap::new();
+        roms.into_iter().for_each(|rom| {
+            let group = roms_by_game_id.entry(rom.game_id).or_default();
+            group.push(rom);
+        });
+        let mut rom_ids: HashMap<i64, i64> = HashMap::new();
+        for other_game in &other_games {
+            let game_id = *game_ids.get(&other_game.id).unwrap();
+            for other_rom in other_roms_by_game_id
+                .remove(&other_game.id)
+                .unwrap_or_default()
+            {
+                let rom = roms_by_game_id
+                    .get(&game_id)
+                    .and_then(|roms| roms.iter().find(|rom| is_same_rom(rom, &other_rom)));
+                let (rom_id, has_romfile) = match rom {
+                    Some(rom) => (rom.id, rom.romfile_id.is_some()),
+                    None => {
+                        let rom_id = create_rom_from_rom(
+                            &mut transaction,
+                            &other_rom,
+                            game_id,
+                            other_rom
+                                .parent_id
+                                .and_then(|parent_id| rom_ids.get(&parent_id).copied()),
+                        )
+                        .await;
+                        new_roms_count += 1;
+                        (rom_id, false)
+                    }
+                };
+                rom_ids.insert(other_rom.id, rom_id);
+
+                let other_romfile_id = match other_rom.romfile_id {
+                    Some(other_romfile_id) => other_romfile_id,
+                    None => continue,
+                };
+                if has_romfile {
+                    duplicate_roms_count += 1;
+                    continue;
+                }
+                let romfile_id = match romfile_ids.get(&other_romfile_id) {
+                    Some(romfile_id) => *romfile_id,
+                    None => {
+                        let other_romfile = other_romfiles_by_id.get(&other_romfile_id).unwrap();
+                        let romfile_id = match merge_romfile(
+                            &mut transaction,
+                            progress_bar,
+                            other_rom_directory,
+                            other_romfile,
+                        )
+                        .await?
+                        {
+                            Some((romfile_id, created)) => {
+                                if created {
+                                    new_romfile_ids.push((other_romfile_id, romfile_id));
+                                }
+                                Some(romfile_id)
+                            }
+                            None => None,
+                        };
+                        romfile_ids.insert(other_romfile_id, romfile_id);
+                        romfile_id
+                    }
+                };
+                let romfile_id = match romfile_id {
+                    Some(romfile_id) => romfile_id,
+                    None => continue,
+                };
+                update_rom_romfile(&mut transaction, rom_id, Some(romfile_id)).await;
+            }
+        }
+    }
+
+    // restore CHD parents once all romfiles exist
+    for (other_romfile_id, romfile_id) in &new_romfile_ids {
+        if let Some(parent_id) = other_romfiles_by_id
+            .get(other_romfile_id)
+            .and_then(|romfile| romfile.parent_id)
+            .and_then(|parent_id| romfile_ids.get(&parent_id).copied().flatten())
+        {
+            update_romfile_parent(&mut transaction, *romfile_id, Some(parent_id)).await;
+        }
+    }
+
+    commit_transaction(transaction).await;
+
+    for system_id in system_ids {
+        let system = find_system_by_id(connection, system_id).await;
+        compute_system_completion(connection, progress_bar, &system).await;
+    }
+
+    progress_bar.println(format!(
+        "New systems: {}, new games: {}, new ROMs: {}, new ROM files: {}, duplicate ROMs: {}",
+        new_systems_count,
+        new_games_count,
+        new_roms_count,
+        new_romfile_ids.len(),
+        duplicate_roms_count
+    ));
+
+    Ok(())
+}
+
+fn is_same_rom(rom: &Rom, other_rom: &Rom) -> bool {
+    rom.name == other_rom.name
+        && rom.size == other_rom.size
+        && match (&rom.sha1, &other_rom.sha1) {
+            (Some(sha1), Some(other_sha1)) => sha1 == other_sha1,
+            _ => {
+                rom.md5 == other_rom.md5
+                    && rom.crc == other_rom.crc
+                    && rom.sha256 == other_rom.sha256
+            }
+        }
+}
+
+async fn merge_romfile(
+    connection: &mut SqliteConnection,
+    progress_bar: &ProgressBar,
+    other_rom_directory: &Path,
+    other_romfile: &Romfile,
+) -> SimpleResult<Option<(i64, bool)>> {
+    let romfile_type = RomfileType::from_i64(other_romfile.romfile_type).unwrap();
+    match find_romfile_by_path(connection, &other_romfile.path).await {
+        // same path and size, assume both databases point to the same file
+        Some(romfile) if romfile.size == other_romfile.size => {
+            return Ok(Some((romfile.id, false)))
+        }
+        Some(_) => {
+            progress_bar.println(format!(
+                "\"{}\" already points to a different file, skipping",
+                other_romfile.path
+            ));
+            return Ok(None);
+        }
+        None => {}
+    }
+
+    // paths are relative to each database's ROM_DIRECTORY, files are copied over when they differ
+    let other_path = other_rom_directory.join(&other_romfile.path);
+    let path = get_rom_directory(connection)
+        .await
+        .join(&other_romfile.path);
+    if !other_path.is_file() {
+        progress_bar.println(format!(
+            "\"{}\" is missing, skipping",
+            other_path.as_os_str().to_str().unwrap()
+        ));
+        return Ok(None);
+    }
+    if other_path != path {
+        if path.exists() {
+            progress_bar.println(format!(
+                "\"{}\" already exists, skipping",
+                path.as_os_str().to_str().unwrap()
+            ));
+            return Ok(None);
+        }
+        copy_file(progress_bar, &other_path, &path, false).await?;
+    }
+
+    let romfile_id = create_romfile(
+        connection,
+        &other_romfile.path,
+        other_romfile.size as u64,
+        romfile_type,
+    )
+    .await;
+    Ok(Some((romfile_id, true)))
+}
+
+#[cfg(test)]
+mod test_merge;
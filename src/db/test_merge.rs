@@ -0,0 +1,139 @@
+use super::super::config::*;
+use super::super::import_dats;
+use super::super::import_roms;
+use super::*;
+use tempfile::{NamedTempFile, TempDir};
+use tokio::fs;
+
+#[tokio::test]
+async fn test() {
+    // given
+    let _guard = MUTEX.lock().await;
+
+    let test_directory = Path::new("tests");
+    let progress_bar = ProgressBar::hidden();
+
+    let db_file = NamedTempFile::new().unwrap();
+    let pool = establish_connection(db_file.path().to_str().unwrap()).await;
+    let mut connection = pool.acquire().await.unwrap();
+
+    let other_db_file = NamedTempFile::new().unwrap();
+    let other_pool = establish_connection(other_db_file.path().to_str().unwrap()).await;
+    let mut other_connection = other_pool.acquire().await.unwrap();
+
+    let rom_tmp_directory = TempDir::new_in(&test_directory).unwrap();
+    let rom_directory = PathBuf::from(rom_tmp_directory.path());
+    let other_rom_tmp_directory = TempDir::new_in(&test_directory).unwrap();
+    let other_rom_directory = PathBuf::from(other_rom_tmp_directory.path());
+    let tmp_directory = TempDir::new_in(&test_directory).unwrap();
+    let tmp_directory = set_tmp_directory(PathBuf::from(tmp_directory.path()));
+
+    let matches = import_dats::subcommand()
+        .get_matches_from(&["import-dats", "tests/Test System (20200721).dat"]);
+    import_dats::main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+    import_dats::main(&mut other_connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+    let matches = import_dats::subcommand()
+        .get_matches_from(&["import-dats", "tests/Test System (20250208) (NES).dat"]);
+    import_dats::main(&mut other_connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    for (connection, romfile_directory, romfile_names) in [
+        (
+            &mut connection,
+            rom_directory.clone(),
+            vec!["Test Game (USA, Europe).rom"],
+        ),
+        (
+            &mut other_connection,
+            other_rom_directory.clone(),
+            vec!["Test Game (USA, Europe).rom", "Test Game (Japan).rom"],
+        ),
+    ] {
+        set_rom_directory(romfile_directory.clone());
+        set_directory(connection, "ROM_DIRECTORY", &romfile_directory).await;
+        for romfile_name in romfile_names {
+            let romfile_path = tmp_directory.join(romfile_name);
+            fs::copy(test_directory.join(romfile_name), &romfile_path)
+                .await
+                .unwrap();
+            let matches = import_roms::subcommand()
+                .get_matches_from(&["import-roms", romfile_path.as_os_str().to_str().unwrap()]);
+            import_roms::main(connection, &matches, &progress_bar)
+                .await
+                .unwrap();
+        }
+    }
+    set_rom_directory(rom_directory.clone());
+
+    // the other database is released and reopened like main does
+    drop(other_connection);
+    other_pool.close().await;
+    let other_pool = establish_read_only_connection(other_db_file.path().to_str().unwrap()).await;
+    let mut other_connection = other_pool.acquire().await.unwrap();
+    assert!(is_migrated(&mut other_connection).await);
+    let other_romfiles = find_romfiles(&mut other_connection).await;
+    let other_rom_directory = get_directory(&mut other_connection, "ROM_DIRECTORY")
+        .await
+        .unwrap();
+
+    let system = find_system_by_name(&mut connection, "Test System")
+        .await
+        .unwrap();
+    let games_count = find_games_by_system_id(&mut connection, system.id)
+        .await
+        .len();
+
+    // when
+    merge_database(
+        &mut connection,
+        &mut other_connection,
+        &progress_bar,
+        &other_rom_directory,
+    )
+    .await
+    .unwrap();
+
+    // then
+    let systems = find_systems(&mut connection).await;
+    assert_eq!(systems.len(), 2);
+
+    let games = find_games_by_system_id(&mut connection, system.id).await;
+    assert_eq!(games.len(), games_count);
+
+    let nes_system = systems
+        .iter()
+        .find(|system| system.name.starts_with("Nintendo"))
+        .unwrap();
+    assert_eq!(
+        find_games_by_system_id(&mut connection, nes_system.id)
+            .await
+            .len(),
+        1
+    );
+    assert!(find_header_by_system_id(&mut connection, nes_system.id)
+        .await
+        .is_some());
+
+    let romfiles = find_romfiles(&mut connection).await;
+    assert_eq!(romfiles.len(), 2);
+
+    let roms = find_roms_with_romfile_by_system_id(&mut connection, system.id).await;
+    assert_eq!(roms.len(), 2);
+    assert!(roms
+        .iter()
+        .any(|rom| rom.name == "Test Game (USA, Europe).rom"));
+    assert!(roms.iter().any(|rom| rom.name == "Test Game (Japan).rom"));
+
+    // the missing file is copied over, the shared one is left untouched
+    for romfile in romfiles {
+        assert!(rom_directory.join(&romfile.path).is_file());
+    }
+    for other_romfile in other_romfiles {
+        assert!(other_rom_directory.join(&other_romfile.path).is_file());
+    }
+}
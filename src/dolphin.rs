@@ -97,7 +97,7 @@ impl ToIso for RvzRomfile {
             .arg(&self.romfile.path)
             .arg("-o")
             .arg(&path)
-            .output()
+            .output_with_retries()
             .await
             .expect("Failed to extract rvz");
 
@@ -148,25 +148,17 @@ impl ToRvz for IsoRomfile {
             path.file_name().unwrap().to_str().unwrap()
         ));
 
-        let mut command = Command::new(get_executable_path(DOLPHIN_TOOL_EXECUTABLES)?);
-        command
-            .arg("convert")
-            .arg("-f")
-            .arg("rvz")
-            .arg("-c")
-            .arg(compression_algorithm.to_string())
-            .arg("-l")
-            .arg(compression_level.to_string())
-            .arg("-b")
-            .arg((block_size * 1024).to_string())
-            .arg("-i")
-            .arg(&self.romfile.path)
-            .arg("-o")
-            .arg(&path);
-        if scrub {
-            command.arg("-s");
-        }
-        let output = command.output().await.expect("Failed to create rvz");
+        let output = get_rvz_command(
+            &self.romfile.path,
+            &path,
+            compression_algorithm,
+            compression_level,
+            block_size,
+            scrub,
+        )?
+        .output_with_retries()
+        .await
+        .expect("Failed to create rvz");
 
         if !output.status.success() {
             bail!(String::from_utf8(output.stderr).unwrap().as_str())
@@ -203,7 +195,7 @@ impl AsRvz for CommonRomfile {
 pub async fn get_version() -> SimpleResult<String> {
     let output = try_with!(
         Command::new(get_executable_path(DOLPHIN_TOOL_EXECUTABLES)?)
-            .output()
+            .output_with_retries()
             .await,
         "Failed to spawn dolphin-tool"
     );
@@ -212,3 +204,32 @@ pub async fn get_version() -> SimpleResult<String> {
     let version = String::from("unknown");
     Ok(version)
 }
+
+fn get_rvz_command<P: AsRef<Path>, Q: AsRef<Path>>(
+    input_path: &P,
+    output_path: &Q,
+    compression_algorithm: &RvzCompressionAlgorithm,
+    compression_level: usize,
+    block_size: usize,
+    scrub: bool,
+) -> SimpleResult<Command> {
+    let mut command = Command::new(get_executable_path(DOLPHIN_TOOL_EXECUTABLES)?);
+    command
+        .arg("convert")
+        .arg("-f")
+        .arg("rvz")
+        .arg("-c")
+        .arg(compression_algorithm.to_string())
+        .arg("-l")
+        .arg(compression_level.to_string())
+        .arg("-b")
+        .arg((block_size * 1024).to_string())
+        .arg("-i")
+        .arg(input_path.as_ref())
+        .arg("-o")
+        .arg(output_path.as_ref());
+    if scrub {
+        command.arg("-s");
+    }
+    Ok(command)
+}
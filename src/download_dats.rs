@@ -1,5 +1,7 @@
+use super::common::compute_hash;
+use super::config::*;
 use super::database::*;
-use super::import_dats::{import_dat, parse_dat};
+use super::import_dats::{import_dat, parse_dat, store_dat};
 use super::model::*;
 use super::prompt::*;
 use super::util::*;
@@ -13,12 +15,15 @@ use rayon::prelude::*;
 use sqlx::sqlite::SqliteConnection;
 use std::collections::HashSet;
 use std::io::Cursor;
+use std::path::PathBuf;
+use tokio::io::AsyncWriteExt;
 use tokio::time::{sleep, Duration};
 use zip::read::ZipArchive;
 
 const NOINTRO_BASE_URL: &str = "https://datomatic.no-intro.org";
 const NOINTRO_PROFILE_URL: &str = "/profile.xml";
 const REDUMP_BASE_URL: &str = "http://redump.org";
+const CHECKSUM_EXTENSION: &str = "sha1";
 
 pub const NOINTRO_SYSTEM_URL: &str = "www.no-intro.org";
 pub const REDUMP_SYSTEM_URL: &str = "http://redump.org/";
@@ -96,8 +101,8 @@ pub fn subcommand() -> Command {
                 .help("Download No-Intro DAT files")
                 .required(false)
                 .action(ArgAction::SetTrue)
-                .conflicts_with("REDUMP")
-                .required_unless_present("REDUMP"),
+                .conflicts_with_all(["REDUMP", "CUSTOM"])
+                .required_unless_present_any(["REDUMP", "CUSTOM", "ADD_SOURCE", "REMOVE_SOURCE"]),
         )
         .arg(
             Arg::new("REDUMP")
@@ -106,8 +111,40 @@ pub fn subcommand() -> Command {
                 .help("Download Redump DAT files")
                 .required(false)
                 .action(ArgAction::SetTrue)
-                .conflicts_with("NOINTRO")
-                .required_unless_present("NOINTRO"),
+                .conflicts_with_all(["NOINTRO", "CUSTOM"])
+                .required_unless_present_any(["NOINTRO", "CUSTOM", "ADD_SOURCE", "REMOVE_SOURCE"]),
+        )
+        .arg(
+            Arg::new("CUSTOM")
+                .short('c')
+                .long("custom")
+                .help("Download DAT files from custom sources")
+                .required(false)
+                .action(ArgAction::SetTrue)
+                .conflicts_with_all(["NOINTRO", "REDUMP"])
+                .required_unless_present_any(["NOINTRO", "REDUMP", "ADD_SOURCE", "REMOVE_SOURCE"]),
+        )
+        .arg(
+            Arg::new("ADD_SOURCE")
+                .long("add-source")
+                .help("Add a custom DAT source URL")
+                .required(false)
+                .num_args(1),
+        )
+        .arg(
+            Arg::new("HEADER")
+                .long("header")
+                .help("Set an HTTP header sent to the custom DAT source (e.g. \"Authorization: Bearer token\")")
+                .required(false)
+                .num_args(1)
+                .requires("ADD_SOURCE"),
+        )
+        .arg(
+            Arg::new("REMOVE_SOURCE")
+                .long("remove-source")
+                .help("Remove a custom DAT source URL")
+                .required(false)
+                .num_args(1),
         )
         .arg(
             Arg::new("UPDATE")
@@ -133,6 +170,14 @@ pub fn subcommand() -> Command {
                 .required(false)
                 .action(ArgAction::SetTrue),
         )
+        .arg(
+            Arg::new("VERIFY_CHECKSUM")
+                .long("verify-checksum")
+                .help("Refuse downloaded DAT files not matching the given CRC32, MD5 or SHA1")
+                .required(false)
+                .num_args(1)
+                .conflicts_with("NOINTRO"),
+        )
 }
 
 pub async fn main(
@@ -140,6 +185,22 @@ pub async fn main(
     matches: &ArgMatches,
     progress_bar: &ProgressBar,
 ) -> SimpleResult<()> {
+    if let Some(url) = matches.get_one::<String>("ADD_SOURCE") {
+        add_dat_source(
+            connection,
+            progress_bar,
+            url,
+            matches.get_one::<String>("HEADER"),
+        )
+        .await?;
+    }
+    if let Some(url) = matches.get_one::<String>("REMOVE_SOURCE") {
+        remove_dat_source(connection, progress_bar, url).await?;
+    }
+    let checksum = matches.get_one::<String>("VERIFY_CHECKSUM");
+    if let Some(checksum) = checksum {
+        get_checksum_algorithm(checksum)?;
+    }
     if matches.get_flag("NOINTRO") {
         if matches.get_flag("UPDATE") {
             update_nointro_dats(
@@ -160,6 +221,7 @@ pub async fn main(
                 REDUMP_BASE_URL,
                 matches.get_flag("ALL"),
                 matches.get_flag("FORCE"),
+                checksum,
             )
             .await?
         } else {
@@ -168,9 +230,19 @@ pub async fn main(
                 progress_bar,
                 REDUMP_BASE_URL,
                 matches.get_flag("ALL"),
+                checksum,
             )
             .await?
         }
+    } else if matches.get_flag("CUSTOM") {
+        download_custom_dats(
+            connection,
+            progress_bar,
+            matches.get_flag("ALL"),
+            matches.get_flag("FORCE"),
+            checksum,
+        )
+        .await?
     }
     Ok(())
 }
@@ -213,6 +285,7 @@ async fn download_redump_dats(
     progress_bar: &ProgressBar,
     base_url: &str,
     all: bool,
+    checksum: Option<&String>,
 ) -> SimpleResult<()> {
     let system_names: HashSet<String> = find_systems_by_url(connection, REDUMP_SYSTEM_URL)
         .await
@@ -230,6 +303,9 @@ async fn download_redump_dats(
     } else {
         multiselect(&items, "Please select systems", None, None)?
     };
+    if checksum.is_some() && indices.len() > 1 {
+        bail!("A checksum can only be verified for a single DAT");
+    }
     for i in indices {
         download_redump_dat(
             connection,
@@ -237,6 +313,7 @@ async fn download_redump_dats(
             base_url,
             items.get(i).unwrap(),
             false,
+            checksum,
         )
         .await?;
     }
@@ -249,10 +326,22 @@ async fn update_redump_dats(
     base_url: &str,
     all: bool,
     force: bool,
+    checksum: Option<&String>,
 ) -> SimpleResult<()> {
     let systems = prompt_for_systems(connection, Some(REDUMP_SYSTEM_URL), false, all).await?;
+    if checksum.is_some() && systems.len() > 1 {
+        bail!("A checksum can only be verified for a single DAT");
+    }
     for system in systems {
-        download_redump_dat(connection, progress_bar, base_url, &system.name, force).await?;
+        download_redump_dat(
+            connection,
+            progress_bar,
+            base_url,
+            &system.name,
+            force,
+            checksum,
+        )
+        .await?;
     }
     Ok(())
 }
@@ -263,6 +352,7 @@ async fn download_redump_dat(
     base_url: &str,
     system_name: &str,
     force: bool,
+    checksum: Option<&String>,
 ) -> SimpleResult<()> {
     progress_bar.println(format!("Processing \"{}\"", system_name));
     let code = *REDUMP_SYSTEMS_CODES.get(system_name).unwrap();
@@ -273,6 +363,14 @@ async fn download_redump_dat(
         .bytes()
         .await
     {
+        if let Some(checksum) = checksum {
+            if !verify_checksum(&response, checksum)? {
+                progress_bar.println("Checksum mismatch, refusing ZIP");
+                sleep(Duration::from_secs(1)).await;
+                progress_bar.println("");
+                return Ok(());
+            }
+        }
         let tmp_directory = create_tmp_directory(connection).await?;
         let mut zip_archive = try_with!(
             ZipArchive::new(Cursor::new(response)),
@@ -282,14 +380,11 @@ async fn download_redump_dat(
             0 => progress_bar.println("ZIP is empty"),
             1 => {
                 try_with!(zip_archive.extract(&tmp_directory), "Failed to extract ZIP");
-                let (datfile_xml, detector_xml) = parse_dat(
-                    progress_bar,
-                    &tmp_directory
-                        .path()
-                        .join(zip_archive.file_names().next().unwrap()),
-                    true,
-                )
-                .await?;
+                let dat_path = tmp_directory
+                    .path()
+                    .join(zip_archive.file_names().next().unwrap());
+                let (datfile_xml, detector_xml) = parse_dat(progress_bar, &dat_path, true).await?;
+                store_dat(connection, progress_bar, &dat_path, &datfile_xml).await?;
                 import_dat(
                     connection,
                     progress_bar,
@@ -311,6 +406,216 @@ async fn download_redump_dat(
     Ok(())
 }
 
+async fn add_dat_source(
+    connection: &mut SqliteConnection,
+    progress_bar: &ProgressBar,
+    url: &str,
+    header: Option<&String>,
+) -> SimpleResult<()> {
+    if find_dat_source_by_url(connection, url).await.is_some() {
+        progress_bar.println("DAT source already exists");
+        return Ok(());
+    }
+    if let Some(header) = header {
+        if !header.contains(':') {
+            bail!("Header must be formatted as \"Name: value\"");
+        }
+    }
+    create_dat_source(connection, url, header).await;
+    progress_bar.println(format!("Added DAT source \"{}\"", url));
+    Ok(())
+}
+
+async fn remove_dat_source(
+    connection: &mut SqliteConnection,
+    progress_bar: &ProgressBar,
+    url: &str,
+) -> SimpleResult<()> {
+    match find_dat_source_by_url(connection, url).await {
+        Some(dat_source) => {
+            delete_dat_source_by_id(connection, dat_source.id).await;
+            progress_bar.println(format!("Removed DAT source \"{}\"", url));
+        }
+        None => progress_bar.println("Unknown DAT source"),
+    }
+    Ok(())
+}
+
+async fn download_custom_dats(
+    connection: &mut SqliteConnection,
+    progress_bar: &ProgressBar,
+    all: bool,
+    force: bool,
+    checksum: Option<&String>,
+) -> SimpleResult<()> {
+    let dat_sources = find_dat_sources(connection).await;
+    if dat_sources.is_empty() {
+        progress_bar.println("No custom DAT source, please add one with --add-source");
+        return Ok(());
+    }
+    let items: Vec<&str> = dat_sources
+        .iter()
+        .map(|dat_source| dat_source.url.as_str())
+        .collect();
+    let indices: Vec<usize> = if all {
+        (0..items.len()).collect()
+    } else {
+        multiselect(&items, "Please select DAT sources", None, None)?
+    };
+    if checksum.is_some() && indices.len() > 1 {
+        bail!("A checksum can only be verified for a single DAT");
+    }
+    for i in indices {
+        download_custom_dat(
+            connection,
+            progress_bar,
+            dat_sources.get(i).unwrap(),
+            force,
+            checksum,
+        )
+        .await?;
+    }
+    Ok(())
+}
+
+async fn download_custom_dat(
+    connection: &mut SqliteConnection,
+    progress_bar: &ProgressBar,
+    dat_source: &DatSource,
+    force: bool,
+    checksum: Option<&String>,
+) -> SimpleResult<()> {
+    progress_bar.println(format!("Processing \"{}\"", &dat_source.url));
+    let response = match get_request(dat_source, &dat_source.url)
+        .send()
+        .await
+        .and_then(|response| response.error_for_status())
+    {
+        Ok(response) => try_with!(response.bytes().await, "Failed to download DAT"),
+        Err(_) => {
+            progress_bar.println("Failed to download DAT");
+            progress_bar.println("");
+            return Ok(());
+        }
+    };
+
+    // fall back to the checksum published alongside the DAT, if any
+    let checksum = match checksum {
+        Some(checksum) => Some(checksum.to_owned()),
+        None => get_published_checksum(dat_source).await,
+    };
+    if let Some(checksum) = checksum {
+        if !verify_checksum(&response, &checksum)? {
+            progress_bar.println("Checksum mismatch, refusing DAT");
+            progress_bar.println("");
+            return Ok(());
+        }
+    }
+
+    let tmp_directory = create_tmp_directory(connection).await?;
+    let mut dat_paths: Vec<PathBuf> = vec![];
+    // DATs are commonly shipped in ZIP archives
+    if response.starts_with(b"PK") {
+        let mut zip_archive =
+            try_with!(ZipArchive::new(Cursor::new(response)), "Failed to read ZIP");
+        try_with!(zip_archive.extract(&tmp_directory), "Failed to extract ZIP");
+        for file_name in zip_archive.file_names() {
+            if file_name.to_lowercase().ends_with(DAT_EXTENSION) {
+                dat_paths.push(tmp_directory.path().join(file_name));
+            }
+        }
+    } else {
+        let dat_path = tmp_directory
+            .path()
+            .join(format!("source.{}", DAT_EXTENSION));
+        let mut dat_file = create_file(progress_bar, &dat_path, true).await?;
+        try_with!(
+            dat_file.write_all(&response).await,
+            "Failed to write \"{}\"",
+            dat_path.as_os_str().to_str().unwrap()
+        );
+        dat_paths.push(dat_path);
+    }
+
+    if dat_paths.is_empty() {
+        progress_bar.println("No DAT file found");
+    }
+    for dat_path in dat_paths {
+        let (datfile_xml, detector_xml) = parse_dat(progress_bar, &dat_path, false).await?;
+        store_dat(connection, progress_bar, &dat_path, &datfile_xml).await?;
+        import_dat(
+            connection,
+            progress_bar,
+            &datfile_xml,
+            &detector_xml,
+            None,
+            force,
+        )
+        .await?;
+    }
+    progress_bar.println("");
+    Ok(())
+}
+
+fn get_request(dat_source: &DatSource, url: &str) -> reqwest::RequestBuilder {
+    let mut request = reqwest::Client::new().get(url);
+    if let Some((name, value)) = dat_source
+        .header
+        .as_ref()
+        .and_then(|header| header.split_once(':'))
+    {
+        request = request.header(name.trim(), value.trim());
+    }
+    request
+}
+
+async fn get_published_checksum(dat_source: &DatSource) -> Option<String> {
+    let response = get_request(
+        dat_source,
+        &format!("{}.{}", dat_source.url, CHECKSUM_EXTENSION),
+    )
+    .send()
+    .await
+    .and_then(|response| response.error_for_status())
+    .ok()?
+    .text()
+    .await
+    .ok()?;
+    // sha1sum format, the hash comes first
+    response
+        .split_whitespace()
+        .next()
+        .filter(|checksum| checksum.len() == 40 && checksum.chars().all(|c| c.is_ascii_hexdigit()))
+        .map(|checksum| checksum.to_lowercase())
+}
+
+fn get_checksum_algorithm(checksum: &str) -> SimpleResult<HashAlgorithm> {
+    match checksum.len() {
+        8 => Ok(HashAlgorithm::Crc),
+        32 => Ok(HashAlgorithm::Md5),
+        40 => Ok(HashAlgorithm::Sha1),
+        64 => Ok(HashAlgorithm::Sha256),
+        _ => bail!("Unsupported checksum, please use a CRC32, MD5, SHA1 or SHA256"),
+    }
+}
+
+fn verify_checksum(data: &[u8], checksum: &str) -> SimpleResult<bool> {
+    let hash = compute_hash(
+        &mut Cursor::new(data),
+        &ProgressBar::hidden(),
+        &get_checksum_algorithm(checksum)?,
+    )?;
+    Ok(hash == checksum.to_lowercase())
+}
+
+#[cfg(test)]
+mod test_custom;
+#[cfg(test)]
+mod test_custom_checksum_mismatch;
+#[cfg(test)]
+mod test_custom_checksum_multiple;
+#[cfg(test)]
+mod test_custom_store_compressed;
 #[cfg(test)]
 mod test_nointro;
 #[cfg(test)]
@@ -0,0 +1,79 @@
+extern crate wiremock;
+
+use super::super::config::*;
+use super::super::database::*;
+use super::super::util::*;
+use super::*;
+use std::path::{Path, PathBuf};
+use tempfile::{NamedTempFile, TempDir};
+use tokio::io::AsyncReadExt;
+use wiremock::matchers::{header, method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+#[tokio::test]
+async fn test() {
+    // given
+    let _guard = MUTEX.lock().await;
+
+    let test_directory = Path::new("tests");
+    let progress_bar = ProgressBar::hidden();
+
+    let db_file = NamedTempFile::new().unwrap();
+    let pool = establish_connection(db_file.path().to_str().unwrap()).await;
+    let mut connection = pool.acquire().await.unwrap();
+
+    let rom_directory = TempDir::new_in(&test_directory).unwrap();
+    set_rom_directory(PathBuf::from(rom_directory.path()));
+    let tmp_directory = TempDir::new_in(&test_directory).unwrap();
+    set_tmp_directory(PathBuf::from(tmp_directory.path()));
+
+    let zip_path = test_directory.join("Test System (20200721).zip");
+    let mut zip_data = vec![];
+    open_file(&zip_path)
+        .await
+        .unwrap()
+        .read_to_end(&mut zip_data)
+        .await
+        .unwrap();
+
+    let mock_server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/dats/test.zip"))
+        .and(header("Authorization", "Bearer token"))
+        .respond_with(ResponseTemplate::new(200).set_body_bytes(zip_data))
+        .mount(&mock_server)
+        .await;
+
+    let url = format!("{}/dats/test.zip", mock_server.uri());
+    let matches = subcommand().get_matches_from(&[
+        "download-dats",
+        "--add-source",
+        &url,
+        "--header",
+        "Authorization: Bearer token",
+    ]);
+    main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    let dat_sources = find_dat_sources(&mut connection).await;
+    assert_eq!(dat_sources.len(), 1);
+    assert_eq!(dat_sources.first().unwrap().url, url);
+
+    let matches = subcommand().get_matches_from(&[
+        "download-dats",
+        "-c",
+        "-a",
+        "--verify-checksum",
+        "0000000000000000000000000000000000000000",
+    ]);
+
+    // when
+    main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    // then
+    assert!(find_systems(&mut connection).await.is_empty());
+    assert!(find_games(&mut connection).await.is_empty());
+}
@@ -0,0 +1,57 @@
+extern crate wiremock;
+
+use super::super::config::*;
+use super::super::database::*;
+use super::*;
+use std::path::{Path, PathBuf};
+use tempfile::{NamedTempFile, TempDir};
+use wiremock::matchers::method;
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+#[tokio::test]
+async fn test() {
+    // given
+    let _guard = MUTEX.lock().await;
+
+    let test_directory = Path::new("tests");
+    let progress_bar = ProgressBar::hidden();
+
+    let db_file = NamedTempFile::new().unwrap();
+    let pool = establish_connection(db_file.path().to_str().unwrap()).await;
+    let mut connection = pool.acquire().await.unwrap();
+
+    let rom_directory = TempDir::new_in(&test_directory).unwrap();
+    set_rom_directory(PathBuf::from(rom_directory.path()));
+    let tmp_directory = TempDir::new_in(&test_directory).unwrap();
+    set_tmp_directory(PathBuf::from(tmp_directory.path()));
+
+    let mock_server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(0)
+        .mount(&mock_server)
+        .await;
+
+    for name in ["first", "second"] {
+        let url = format!("{}/dats/{}.zip", mock_server.uri(), name);
+        let matches = subcommand().get_matches_from(&["download-dats", "--add-source", &url]);
+        main(&mut connection, &matches, &progress_bar)
+            .await
+            .unwrap();
+    }
+
+    let matches = subcommand().get_matches_from(&[
+        "download-dats",
+        "-c",
+        "-a",
+        "--verify-checksum",
+        "0000000000000000000000000000000000000000",
+    ]);
+
+    // when
+    let result = main(&mut connection, &matches, &progress_bar).await;
+
+    // then
+    assert!(result.is_err());
+    assert!(find_systems(&mut connection).await.is_empty());
+}
@@ -1,17 +1,24 @@
 use super::bchunk;
 use super::chdman;
-use super::chdman::{AsChd, ChdType, ToChd, ToRdsk, ToRiff};
+use super::chdman::{
+    AsChd, ChdCdCompressionAlgorithm, ChdDvdCompressionAlgorithm, ChdType, ToChd, ToRdsk, ToRiff,
+};
 use super::common::*;
 use super::config::*;
+use super::create_dats::DOCTYPE;
 use super::database::*;
 use super::dolphin;
 use super::dolphin::{AsRvz, RvzCompressionAlgorithm, ToRvz};
+use super::generate_playlists::add_sibling_discs;
+use super::import_cuesheets::write_cuesheet;
+use super::import_dats::get_regions_from_game_name;
 use super::maxcso;
 use super::maxcso::{AsXso, ToXso, XsoType};
 use super::mimetype::*;
 use super::model::*;
 use super::nsz;
 use super::nsz::{AsNsp, AsNsz, ToNsp, ToNsz};
+use super::progress::*;
 use super::prompt::*;
 use super::sevenzip;
 use super::sevenzip::{AsArchive, ToArchive};
@@ -19,22 +26,42 @@ use super::util::*;
 use super::wit;
 use super::wit::ToWbfs;
 use super::SimpleResult;
+use chrono::Local;
 use clap::builder::PossibleValuesParser;
-use clap::{Arg, ArgAction, ArgMatches, Command};
+use clap::{value_parser, Arg, ArgAction, ArgMatches, Command};
 use indexmap::map::IndexMap;
-use indicatif::ProgressBar;
+use indicatif::{HumanBytes, ProgressBar};
+use quick_xml::se;
 use rayon::prelude::*;
+use serde::Serialize;
 use sqlx::sqlite::SqliteConnection;
 use std::cmp::Ordering;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::mem::drop;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::str::FromStr;
+use std::time::SystemTime;
+use strum::{EnumString, VariantNames};
+use tokio::io::AsyncWriteExt;
+use walkdir::WalkDir;
 
 const ALL_FORMATS: &[&str] = &[
     "ORIGINAL", "7Z", "CHD", "CSO", "ISO", "NSZ", "RVZ", "WBFS", "ZIP", "ZSO",
 ];
 const ARCADE_FORMATS: &[&str] = &["ORIGINAL", "ZIP"];
+const SOURCE_FORMATS: &[&str] = &["ORIGINAL", "7Z", "CHD", "CSO", "NSZ", "RVZ", "ZIP", "ZSO"];
+const GAMELIST_FILE_NAME: &str = "gamelist.xml";
+const INDEX_FILE_NAME: &str = "index.tsv";
+const XML_DECLARATION: &str = "<?xml version=\"1.0\"?>\n";
+
+#[derive(Clone, Copy, PartialEq, EnumString, VariantNames)]
+#[strum(serialize_all = "lowercase")]
+pub enum ArchiveGrouping {
+    Game,
+    System,
+    Region,
+    Letter,
+}
 
 pub fn subcommand() -> Command {
     Command::new("export-roms")
@@ -64,14 +91,37 @@ pub fn subcommand() -> Command {
                 .required(false)
                 .action(ArgAction::Append),
         )
+        .arg(
+            Arg::new("EXACT_SYSTEM")
+                .long("exact-system")
+                .help("Match system names exactly")
+                .required(false)
+                .action(ArgAction::SetTrue),
+        )
         .arg(
             Arg::new("DIRECTORY")
                 .short('d')
                 .long("directory")
-                .help("Set the output directory")
-                .required(true)
+                .help("Set the output directory, defaults to EXPORT_DIRECTORY")
+                .required(false)
+                .num_args(1),
+        )
+        .arg(
+            Arg::new("TAG")
+                .short('t')
+                .long("tag")
+                .help("Only export games with the given tag")
+                .required(false)
                 .num_args(1),
         )
+        .arg(
+            Arg::new("GROUP_BY_TAG")
+                .long("group-by-tag")
+                .help("Export into a subdirectory named after the tag")
+                .required(false)
+                .action(ArgAction::SetTrue)
+                .requires("TAG"),
+        )
         .arg(
             Arg::new("1G1R")
                 .short('o')
@@ -80,6 +130,130 @@ pub fn subcommand() -> Command {
                 .required(false)
                 .action(ArgAction::SetTrue),
         )
+        .arg(
+            Arg::new("LINK")
+                .long("link")
+                .help("Hardlink ORIGINAL files instead of copying them")
+                .required(false)
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("ANNOTATE_REGION")
+                .long("annotate-region")
+                .help("Make sure exported file names carry their selected region")
+                .required(false)
+                .action(ArgAction::SetTrue)
+                .requires("1G1R"),
+        )
+        .arg(
+            Arg::new("FAT_SAFE")
+                .long("fat-safe")
+                .help("Sanitize file names for FAT and exFAT destinations")
+                .required(false)
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("GAMELIST")
+                .long("gamelist")
+                .help("Write a gamelist.xml for EmulationStation and similar frontends")
+                .required(false)
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("INDEX")
+                .long("index")
+                .help("Write an index.tsv mapping games to their CHD files and hashes")
+                .required(false)
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("MANIFEST")
+                .long("manifest")
+                .help("Write a DAT listing the exported files and their hashes")
+                .required(false)
+                .num_args(1)
+                .value_parser(value_parser!(PathBuf)),
+        )
+        .arg(
+            Arg::new("MATCH_DAT")
+                .long("match-dat")
+                .help("TorrentZip ZIP files with their DAT names so they match the set exactly")
+                .required(false)
+                .action(ArgAction::SetTrue)
+                .conflicts_with_all(["ANNOTATE_REGION", "COMPRESSION_LEVEL", "FAT_SAFE"]),
+        )
+        .arg(
+            Arg::new("GROUP_ARCHIVES_BY")
+                .long("group-archives-by")
+                .help("Set how 7Z and ZIP files are aggregated, defaults to one archive per game")
+                .required(false)
+                .num_args(1)
+                .value_parser(PossibleValuesParser::new(ArchiveGrouping::VARIANTS))
+                .conflicts_with_all(["ANNOTATE_REGION", "MATCH_DAT", "SKIP_EXISTING"]),
+        )
+        .arg(
+            Arg::new("SKIP_EXISTING")
+                .long("skip-existing-destination")
+                .help("Skip games already present in the output directory")
+                .required(false)
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("VERIFY_EXISTING")
+                .long("verify-existing")
+                .help("Check the hashes of existing files before skipping them")
+                .required(false)
+                .action(ArgAction::SetTrue)
+                .requires("SKIP_EXISTING"),
+        )
+        .arg(
+            Arg::new("MAX_SIZE")
+                .long("max-size")
+                .help("Skip games larger than the given size in bytes")
+                .required(false)
+                .num_args(1)
+                .value_parser(value_parser!(u64)),
+        )
+        .arg(
+            Arg::new("CHANGED_SINCE")
+                .long("changed-since")
+                .help("Only export games whose ROM files changed since the given UNIX timestamp")
+                .required(false)
+                .num_args(1)
+                .value_parser(value_parser!(i64)),
+        )
+        .arg(
+            Arg::new("COMPRESSION_LEVEL")
+                .long("compression-level")
+                .help("Override the 7Z, RVZ or ZIP compression level for this export")
+                .required(false)
+                .num_args(1)
+                .value_parser(value_parser!(usize)),
+        )
+        .arg(
+            Arg::new("CHD_ALGORITHMS")
+                .long("chd-algorithms")
+                .help("Override the CD and DVD CHD compression algorithms for this export")
+                .required(false)
+                .value_delimiter(',')
+                .action(ArgAction::Append)
+                .value_parser(PossibleValuesParser::new(
+                    ChdCdCompressionAlgorithm::VARIANTS.iter().chain(
+                        ChdDvdCompressionAlgorithm::VARIANTS
+                            .iter()
+                            .filter(|&&algorithm| algorithm != "none"),
+                    ),
+                )),
+        )
+        .arg(
+            Arg::new("PREFER")
+                .long("prefer")
+                .help("Export each ROM from its copy in the most preferred format")
+                .required(false)
+                .value_delimiter(',')
+                .action(ArgAction::Append)
+                .value_parser(PossibleValuesParser::new(SOURCE_FORMATS.iter())),
+        )
 }
 
 pub async fn main(
@@ -91,7 +265,14 @@ pub async fn main(
         Some(system_names) => {
             let mut systems: Vec<System> = vec![];
             for system_name in system_names {
-                systems.append(&mut find_systems_by_name_like(connection, system_name).await);
+                systems.append(
+                    &mut find_systems_by_name_match(
+                        connection,
+                        system_name,
+                        matches.get_flag("EXACT_SYSTEM"),
+                    )
+                    .await,
+                );
             }
             systems.dedup_by_key(|system| system.id);
             systems
@@ -106,56 +287,70 @@ pub async fn main(
             .unwrap(),
     };
 
-    let destination_directory =
-        get_canonicalized_path(matches.get_one::<String>("DIRECTORY").unwrap()).await?;
+    let destination_directory = match matches.get_one::<String>("DIRECTORY") {
+        Some(directory) => get_canonicalized_path(directory).await?,
+        None => match find_setting_by_key(connection, "EXPORT_DIRECTORY")
+            .await
+            .and_then(|setting| setting.value)
+        {
+            Some(directory) => match get_canonicalized_path(&directory).await {
+                Ok(directory) => directory,
+                Err(_) => bail!(
+                    "EXPORT_DIRECTORY \"{}\" doesn't exist or isn't mounted",
+                    directory
+                ),
+            },
+            None => bail!("Please set EXPORT_DIRECTORY or specify an output directory"),
+        },
+    };
     create_directory(progress_bar, &destination_directory, true).await?;
 
     match format.as_str() {
         "7Z" | "ZIP" => {
             if sevenzip::get_version().await.is_err() {
-                progress_bar.println("Please install sevenzip");
+                print_missing_tool(progress_bar, "sevenzip");
                 return Ok(());
             }
         }
         "CHD" => {
             if chdman::get_version().await.is_err() {
-                progress_bar.println("Please install chdman");
+                print_missing_tool(progress_bar, "chdman");
                 return Ok(());
             }
         }
         "CSO" => {
             if maxcso::get_version().await.is_err() {
-                progress_bar.println("Please install maxcso");
+                print_missing_tool(progress_bar, "maxcso");
                 return Ok(());
             }
         }
         "ISO" => {
             if bchunk::get_version().await.is_err() {
-                progress_bar.println("Please install bchunk");
+                print_missing_tool(progress_bar, "bchunk");
                 return Ok(());
             }
         }
         "NSZ" => {
             if nsz::get_version().await.is_err() {
-                progress_bar.println("Please install nsz");
+                print_missing_tool(progress_bar, "nsz");
                 return Ok(());
             }
         }
         "RVZ" => {
             if dolphin::get_version().await.is_err() {
-                progress_bar.println("Please install dolphin-tool");
+                print_missing_tool(progress_bar, "dolphin-tool");
                 return Ok(());
             }
         }
         "WBFS" => {
             if wit::get_version().await.is_err() {
-                progress_bar.println("Please install wit");
+                print_missing_tool(progress_bar, "wit");
                 return Ok(());
             }
         }
         "ZSO" => {
             if maxcso::get_version().await.is_err() {
-                progress_bar.println("Please install maxcso");
+                print_missing_tool(progress_bar, "maxcso");
                 return Ok(());
             }
         }
@@ -163,8 +358,129 @@ pub async fn main(
         _ => bail!("Not supported"),
     }
 
-    for system in systems {
+    let link = matches.get_flag("LINK");
+    if link && format != "ORIGINAL" {
+        progress_bar.println("Only ORIGINAL files can be hardlinked");
+        return Ok(());
+    }
+
+    let index = matches.get_flag("INDEX");
+    if index && format != "CHD" {
+        progress_bar.println("Only CHD files can be indexed");
+        return Ok(());
+    }
+
+    let compression_level = matches.get_one::<usize>("COMPRESSION_LEVEL").copied();
+    if let Some(compression_level) = compression_level {
+        let range = match format.as_str() {
+            "7Z" => sevenzip::SEVENZIP_COMPRESSION_LEVEL_RANGE,
+            "RVZ" => dolphin::RVZ_COMPRESSION_LEVEL_RANGE,
+            "ZIP" => sevenzip::ZIP_COMPRESSION_LEVEL_RANGE,
+            _ => {
+                progress_bar.println("Only 7Z, RVZ and ZIP files have a compression level");
+                return Ok(());
+            }
+        };
+        if compression_level < range[0] || range[1] < compression_level {
+            progress_bar.println(format!("Valid range: {:?}", range));
+            return Ok(());
+        }
+    }
+
+    let chd_algorithms: Option<Vec<String>> = matches
+        .get_many::<String>("CHD_ALGORITHMS")
+        .map(|algorithms| algorithms.cloned().collect());
+    if chd_algorithms.is_some() && format != "CHD" {
+        progress_bar.println("Only CHD files have compression algorithms");
+        return Ok(());
+    }
+
+    let match_dat = matches.get_flag("MATCH_DAT");
+    if match_dat && format != "ZIP" {
+        progress_bar.println("Only ZIP files can match a DAT");
+        return Ok(());
+    }
+
+    let group_archives_by = matches
+        .get_one::<String>("GROUP_ARCHIVES_BY")
+        .map(|group_archives_by| ArchiveGrouping::from_str(group_archives_by).unwrap())
+        .unwrap_or(ArchiveGrouping::Game);
+    if group_archives_by != ArchiveGrouping::Game && format != "7Z" && format != "ZIP" {
+        progress_bar.println("Only 7Z and ZIP files can be grouped");
+        return Ok(());
+    }
+
+    let fat_safe = if matches.get_flag("FAT_SAFE") {
+        true
+    } else if !match_dat && is_fat_filesystem(&destination_directory) {
+        progress_bar.println("FAT destination detected, sanitizing file names");
+        true
+    } else {
+        false
+    };
+
+    let tagged_game_ids: Option<HashSet<i64>> = match matches.get_one::<String>("TAG") {
+        Some(tag) => {
+            if find_tag_by_name(connection, tag).await.is_none() {
+                progress_bar.println(format!("No tag named \"{}\"", tag));
+                return Ok(());
+            }
+            Some(
+                find_games_by_tag_name(connection, tag)
+                    .await
+                    .into_iter()
+                    .map(|game| game.id)
+                    .collect(),
+            )
+        }
+        None => None,
+    };
+    let destination_directory = if matches.get_flag("GROUP_BY_TAG") {
+        let tag = matches.get_one::<String>("TAG").unwrap();
+        let directory = destination_directory.join(if fat_safe {
+            get_fat_safe_name(tag)
+        } else {
+            tag.to_owned()
+        });
+        create_directory(progress_bar, &directory, true).await?;
+        directory
+    } else {
+        destination_directory
+    };
+
+    // DAT names are kept verbatim when matching a set
+    let uppercase_extensions: Vec<String> = if match_dat {
+        vec![]
+    } else {
+        get_list(connection, "EXPORT_UPPERCASE_EXTENSIONS")
+            .await
+            .iter()
+            .map(|extension| extension.to_lowercase())
+            .collect()
+    };
+    let gamelist = matches.get_flag("GAMELIST");
+    let manifest_path = matches.get_one::<PathBuf>("MANIFEST");
+    let mut exported_names: HashSet<String> = HashSet::new();
+    let mut exported_games: Vec<(String, String)> = vec![];
+    let mut chd_paths_by_name: HashMap<String, PathBuf> = HashMap::new();
+    // the manifest only lists files written by this run
+    let existing_files = if manifest_path.is_some() {
+        get_modified_times(&destination_directory)
+    } else {
+        HashMap::new()
+    };
+
+    for mut system in systems {
         progress_bar.println(format!("Processing \"{}\"", system.name));
+        system.arcade = is_arcade(connection, &system).await;
+        // only files written for this system get their extension cased
+        let system_existing_files = if uppercase_extensions.is_empty() {
+            HashMap::new()
+        } else {
+            get_modified_times(&destination_directory)
+        };
+        // CHDs written for this system, indexed once their extension is cased
+        let mut system_chd_paths_by_name: HashMap<String, PathBuf> = HashMap::new();
 
         if format == "CHD"
             && system.name.contains("Dreamcast")
@@ -198,7 +514,8 @@ pub async fn main(
                     );
                 }
                 games.dedup_by_key(|game| game.id);
-                prompt_for_games(games, cfg!(test))?
+                let games = prompt_for_games(games, cfg!(test))?;
+                add_sibling_discs(connection, games, system.id).await
             }
             None => find_complete_games_by_system_id(connection, system.id).await,
         };
@@ -207,6 +524,10 @@ pub async fn main(
             games.retain(|game| game.sorting == Sorting::OneRegion as i64);
         }
 
+        if let Some(tagged_game_ids) = &tagged_game_ids {
+            games.retain(|game| tagged_game_ids.contains(&game.id));
+        }
+
         if games.is_empty() {
             if matches.index_of("GAME").is_some() {
                 progress_bar.println("No matching game");
@@ -233,165 +554,1293 @@ pub async fn main(
             let group = roms_by_game_id.entry(rom.game_id).or_default();
             group.push(rom);
         });
-        let games_by_id: HashMap<i64, Game> =
+        let mut games_by_id: HashMap<i64, Game> =
             games.into_par_iter().map(|game| (game.id, game)).collect();
-        let romfiles_by_id: HashMap<i64, Romfile> = romfiles
+        let mut romfiles_by_id: HashMap<i64, Romfile> = romfiles
             .into_par_iter()
             .map(|romfile| (romfile.id, romfile))
             .collect();
 
-        match format.as_str() {
-            "ORIGINAL" => {
-                to_original(
-                    connection,
-                    progress_bar,
-                    &destination_directory,
-                    &system,
-                    games_by_id,
-                    roms_by_game_id,
-                    romfiles_by_id,
-                )
-                .await?
-            }
-            "7Z" => {
-                let compression_level = get_integer(connection, "SEVENZIP_COMPRESSION_LEVEL").await;
-                let solid = get_bool(connection, "SEVENZIP_SOLID_COMPRESSION").await;
-                to_archive(
-                    connection,
-                    progress_bar,
-                    &destination_directory,
-                    &system,
-                    games_by_id,
-                    roms_by_game_id,
-                    romfiles_by_id,
-                    sevenzip::ArchiveType::Sevenzip,
-                    &compression_level,
-                    solid,
-                )
-                .await?
-            }
-            "ZIP" => {
-                let compression_level = get_integer(connection, "ZIP_COMPRESSION_LEVEL").await;
-                to_archive(
+        if let Some(max_size) = matches.get_one::<u64>("MAX_SIZE") {
+            roms_by_game_id.retain(|game_id, roms| {
+                let size = roms.iter().map(|rom| rom.size as u64).sum::<u64>();
+                if size > *max_size {
+                    progress_bar.println(format!(
+                        "Skipping \"{}\" ({} > {})",
+                        games_by_id.get(game_id).unwrap().name,
+                        HumanBytes(size),
+                        HumanBytes(*max_size)
+                    ));
+                    return false;
+                }
+                true
+            });
+        }
+
+        if let Some(changed_since) = matches.get_one::<i64>("CHANGED_SINCE") {
+            roms_by_game_id.retain(|_, roms| {
+                roms.iter().any(|rom| {
+                    romfiles_by_id
+                        .get(&rom.romfile_id.unwrap())
+                        .unwrap()
+                        .updated_at
+                        >= *changed_since
+                })
+            });
+        }
+
+        // borrowed copies are staged under the name of the ROM they're exported for
+        let _prefer_directory = match matches.get_many::<String>("PREFER") {
+            Some(preferred_formats) => {
+                let preferred_formats: Vec<&str> =
+                    preferred_formats.map(|format| format.as_str()).collect();
+                let prefer_directory = create_tmp_directory(connection).await?;
+                prefer_romfiles(
                     connection,
                     progress_bar,
-                    &destination_directory,
-                    &system,
-                    games_by_id,
-                    roms_by_game_id,
-                    romfiles_by_id,
-                    sevenzip::ArchiveType::Zip,
-                    &compression_level,
-                    false,
+                    prefer_directory.path(),
+                    &mut roms_by_game_id,
+                    &mut romfiles_by_id,
+                    &preferred_formats,
                 )
-                .await?
+                .await?;
+                Some(prefer_directory)
             }
-            "ISO" => {
-                to_iso(
-                    connection,
-                    progress_bar,
-                    &destination_directory,
-                    roms_by_game_id,
-                    romfiles_by_id,
-                )
-                .await?
+            None => None,
+        };
+
+        // same-named games would overwrite each other in the destination directory
+        let conflicting_game_ids: Vec<i64> = roms_by_game_id
+            .keys()
+            .filter(|game_id| {
+                !exported_names.insert(games_by_id.get(*game_id).unwrap().name.clone())
+            })
+            .copied()
+            .collect();
+        let mut renamed_games: Vec<(i64, String)> = vec![];
+        for game_id in conflicting_game_ids {
+            let game = games_by_id.get(&game_id).unwrap();
+            let name = get_disambiguated_name(
+                game,
+                roms_by_game_id.get(&game_id).unwrap(),
+                &exported_names,
+            );
+            progress_bar.println(format!(
+                "\"{}\" conflicts with another selected game, exporting as \"{}\"",
+                game.name, name
+            ));
+            exported_names.insert(name.clone());
+            renamed_games.push((game_id, name));
+        }
+
+        if matches.get_flag("ANNOTATE_REGION") {
+            for (game_id, roms) in &roms_by_game_id {
+                if renamed_games.iter().any(|(id, _)| id == game_id) {
+                    continue;
+                }
+                let game = games_by_id.get(game_id).unwrap();
+                if let Some(name) = get_annotated_name(game, roms) {
+                    exported_names.insert(name.clone());
+                    renamed_games.push((*game_id, name));
+                }
             }
-            "CHD" => {
-                let cd_compression_algorithms =
-                    get_list(connection, "CHD_CD_COMPRESSION_ALGORITHMS").await;
-                let cd_hunk_size = get_integer(connection, "CHD_CD_HUNK_SIZE").await;
-                let dvd_compression_algorithms =
-                    get_list(connection, "CHD_DVD_COMPRESSION_ALGORITHMS").await;
-                let dvd_hunk_size = get_integer(connection, "CHD_DVD_HUNK_SIZE").await;
-                to_chd(
-                    connection,
-                    progress_bar,
-                    &destination_directory,
-                    games_by_id,
-                    roms_by_game_id,
-                    romfiles_by_id,
-                    &cd_compression_algorithms,
-                    &cd_hunk_size,
-                    &dvd_compression_algorithms,
-                    &dvd_hunk_size,
-                )
-                .await?
+        }
+
+        if fat_safe {
+            for (game_id, roms) in &roms_by_game_id {
+                let index = renamed_games.iter().position(|(id, _)| id == game_id);
+                let name = match index {
+                    Some(index) => renamed_games[index].1.clone(),
+                    None => games_by_id.get(game_id).unwrap().name.clone(),
+                };
+                let fat_safe_name = get_fat_safe_name(&name);
+                if fat_safe_name == name {
+                    if index.is_none()
+                        && roms
+                            .iter()
+                            .any(|rom| get_fat_safe_name(&rom.name) != rom.name)
+                    {
+                        renamed_games.push((*game_id, name));
+                    }
+                    continue;
+                }
+                let mut unique_name = fat_safe_name.clone();
+                let mut i = 2;
+                while exported_names.contains(&unique_name) {
+                    unique_name = format!("{} ({})", fat_safe_name, i);
+                    i += 1;
+                }
+                progress_bar.println(format!(
+                    "\"{}\" is not a valid FAT file name, exporting as \"{}\"",
+                    name, unique_name
+                ));
+                exported_names.insert(unique_name.clone());
+                match index {
+                    Some(index) => renamed_games[index].1 = unique_name,
+                    None => renamed_games.push((*game_id, unique_name)),
+                }
             }
-            "CSO" => {
-                to_cso(
-                    connection,
-                    progress_bar,
-                    &destination_directory,
-                    roms_by_game_id,
-                    romfiles_by_id,
-                )
-                .await?
+        }
+
+        // grouped archives aren't named after games, their members are renamed in place instead
+        if group_archives_by != ArchiveGrouping::Game {
+            for (game_id, name) in renamed_games.drain(..) {
+                games_by_id.get_mut(&game_id).unwrap().name = name;
             }
-            "NSZ" => {
-                to_nsz(
-                    connection,
-                    progress_bar,
-                    &destination_directory,
-                    roms_by_game_id,
-                    romfiles_by_id,
-                )
-                .await?
+        }
+
+        for (game_id, name) in renamed_games {
+            let mut game = games_by_id.remove(&game_id).unwrap();
+            let roms = roms_by_game_id.shift_remove(&game_id).unwrap();
+            let mut romfile_ids: Vec<i64> =
+                roms.iter().map(|rom| rom.romfile_id.unwrap()).collect();
+            romfile_ids.dedup();
+            // staged copies picked by --prefer aren't in the database
+            let romfiles_by_id: HashMap<i64, Romfile> = romfile_ids
+                .iter()
+                .map(|romfile_id| {
+                    let romfile = romfiles_by_id.get(romfile_id).unwrap();
+                    (
+                        *romfile_id,
+                        Romfile {
+                            id: romfile.id,
+                            path: romfile.path.clone(),
+                            size: romfile.size,
+                            parent_id: romfile.parent_id,
+                            romfile_type: romfile.romfile_type,
+                            updated_at: romfile.updated_at,
+                        },
+                    )
+                })
+                .collect();
+            if gamelist || index || manifest_path.is_some() {
+                exported_games.push((name.clone(), game.description.clone()));
             }
-            "RVZ" => {
-                let compression_algorithm = RvzCompressionAlgorithm::from_str(
-                    &get_string(connection, "RVZ_COMPRESSION_ALGORITHM")
-                        .await
-                        .unwrap(),
-                )
-                .unwrap();
-                let compression_level = get_integer(connection, "RVZ_COMPRESSION_LEVEL")
-                    .await
-                    .unwrap();
-                let block_size = get_integer(connection, "RVZ_BLOCK_SIZE").await.unwrap();
-                let scrub = get_bool(connection, "RVZ_SCRUB").await;
-                to_rvz(
-                    connection,
-                    progress_bar,
-                    &destination_directory,
-                    roms_by_game_id,
-                    romfiles_by_id,
-                    &compression_algorithm,
-                    compression_level,
-                    block_size,
-                    scrub,
-                )
-                .await?
+            game.name = name.clone();
+            let tmp_directory = create_tmp_directory(connection).await?;
+            let tmp_path = tmp_directory.path().to_path_buf();
+            let chd_paths_by_game_id = export_system(
+                connection,
+                progress_bar,
+                &tmp_path,
+                &system,
+                &format,
+                HashMap::from([(game_id, game)]),
+                IndexMap::from([(game_id, roms)]),
+                romfiles_by_id,
+                link,
+                compression_level,
+                &chd_algorithms,
+                group_archives_by,
+            )
+            .await?;
+            let new_paths_by_path = move_disambiguated_files(
+                progress_bar,
+                &tmp_path,
+                &destination_directory,
+                &name,
+                fat_safe,
+            )
+            .await?;
+            if let Some(chd_path) = chd_paths_by_game_id
+                .get(&game_id)
+                .and_then(|chd_path| new_paths_by_path.get(chd_path))
+            {
+                system_chd_paths_by_name.insert(name.clone(), chd_path.clone());
             }
-            "WBFS" => {
-                to_wbfs(
-                    connection,
-                    progress_bar,
-                    &destination_directory,
-                    roms_by_game_id,
-                    romfiles_by_id,
-                )
-                .await?
+            if match_dat {
+                torrentzip_games(progress_bar, &destination_directory, &[name]).await?;
             }
-            "ZSO" => {
-                to_zso(
-                    connection,
-                    progress_bar,
+        }
+
+        if gamelist || index || manifest_path.is_some() {
+            exported_games.extend(roms_by_game_id.keys().map(|game_id| {
+                let game = games_by_id.get(game_id).unwrap();
+                (game.name.clone(), game.description.clone())
+            }));
+        }
+
+        if matches.get_flag("SKIP_EXISTING") {
+            let verify = matches.get_flag("VERIFY_EXISTING");
+            let mut existing_game_ids: Vec<i64> = vec![];
+            for (game_id, roms) in &roms_by_game_id {
+                let game = games_by_id.get(game_id).unwrap();
+                let mut expected_files = get_expected_files(
                     &destination_directory,
-                    roms_by_game_id,
-                    romfiles_by_id,
-                )
-                .await?
+                    &format,
+                    &system,
+                    game,
+                    roms,
+                    &romfiles_by_id,
+                );
+                // tracks and discs listed in a sheet keep their lowercase extension
+                let is_sheet = |path: &Path| {
+                    path.extension().is_some_and(|extension| {
+                        let extension = extension.to_str().unwrap().to_lowercase();
+                        extension == CUE_EXTENSION || extension == M3U_EXTENSION
+                    })
+                };
+                let has_sheet = expected_files
+                    .iter()
+                    .any(|expected_file| is_sheet(&expected_file.path));
+                for expected_file in &mut expected_files {
+                    if !has_sheet || is_sheet(&expected_file.path) {
+                        expected_file.path =
+                            get_cased_path(&expected_file.path, &uppercase_extensions);
+                    }
+                }
+                if is_already_exported(connection, progress_bar, &expected_files, verify).await? {
+                    progress_bar.println(format!("\"{}\" already exported, skipping", game.name));
+                    existing_game_ids.push(*game_id);
+                    if let Some(expected_file) = expected_files.iter().find(|expected_file| {
+                        expected_file
+                            .path
+                            .extension()
+                            .is_some_and(|extension| extension.eq_ignore_ascii_case(CHD_EXTENSION))
+                    }) {
+                        chd_paths_by_name.insert(game.name.clone(), expected_file.path.clone());
+                    }
+                    continue;
+                }
+                // stale archives would be appended to instead of replaced
+                for expected_file in &expected_files {
+                    if matches!(expected_file.content, ExpectedContent::Archive(_))
+                        && expected_file.path.is_file()
+                    {
+                        remove_file(progress_bar, &expected_file.path, false).await?;
+                    }
+                }
             }
-            _ => bail!("Not supported"),
+            roms_by_game_id.retain(|game_id, _| !existing_game_ids.contains(game_id));
+        }
+
+        let exported_game_names: Vec<String> = roms_by_game_id
+            .keys()
+            .map(|game_id| games_by_id.get(game_id).unwrap().name.clone())
+            .collect();
+        let game_names_by_id: HashMap<i64, String> = roms_by_game_id
+            .keys()
+            .map(|game_id| (*game_id, games_by_id.get(game_id).unwrap().name.clone()))
+            .collect();
+
+        let chd_paths_by_game_id = export_system(
+            connection,
+            progress_bar,
+            &destination_directory,
+            &system,
+            &format,
+            games_by_id,
+            roms_by_game_id,
+            romfiles_by_id,
+            link,
+            compression_level,
+            &chd_algorithms,
+            group_archives_by,
+        )
+        .await?;
+        for (game_id, chd_path) in chd_paths_by_game_id {
+            system_chd_paths_by_name
+                .insert(game_names_by_id.get(&game_id).unwrap().clone(), chd_path);
+        }
+
+        if match_dat {
+            torrentzip_games(progress_bar, &destination_directory, &exported_game_names).await?;
+        }
+
+        if !uppercase_extensions.is_empty() {
+            case_extensions(
+                progress_bar,
+                &destination_directory,
+                &system_existing_files,
+                &uppercase_extensions,
+            )
+            .await?;
         }
+        chd_paths_by_name.extend(
+            system_chd_paths_by_name
+                .into_iter()
+                .map(|(name, chd_path)| (name, get_cased_path(&chd_path, &uppercase_extensions))),
+        );
 
         progress_bar.println("");
     }
 
+    if gamelist {
+        write_gamelist(progress_bar, &destination_directory, &exported_games).await?;
+    }
+
+    if index {
+        write_index(
+            progress_bar,
+            &destination_directory,
+            &exported_games,
+            &chd_paths_by_name,
+        )
+        .await?;
+    }
+
+    if let Some(manifest_path) = manifest_path {
+        write_manifest(
+            progress_bar,
+            &destination_directory,
+            manifest_path,
+            &exported_games,
+            &existing_files,
+        )
+        .await?;
+    }
+
     Ok(())
 }
 
-#[allow(clippy::too_many_arguments)]
+async fn torrentzip_games(
+    progress_bar: &ProgressBar,
+    destination_directory: &Path,
+    game_names: &[String],
+) -> SimpleResult<()> {
+    for game_name in game_names {
+        let archive_path = destination_directory.join(format!("{}.{}", game_name, ZIP_EXTENSION));
+        if archive_path.is_file() {
+            sevenzip::torrentzip(progress_bar, &archive_path).await?;
+        }
+    }
+    Ok(())
+}
+
+async fn prefer_romfiles(
+    connection: &mut SqliteConnection,
+    progress_bar: &ProgressBar,
+    prefer_directory: &Path,
+    roms_by_game_id: &mut IndexMap<i64, Vec<Rom>>,
+    romfiles_by_id: &mut HashMap<i64, Romfile>,
+    preferred_formats: &[&str],
+) -> SimpleResult<()> {
+    let get_rank = |romfile: &Romfile| {
+        let format = get_romfile_format(&romfile.path);
+        preferred_formats
+            .iter()
+            .position(|preferred_format| *preferred_format == format)
+            .unwrap_or(preferred_formats.len())
+    };
+
+    let mut rom_counts: HashMap<i64, usize> = HashMap::new();
+    for rom in roms_by_game_id.values().flatten() {
+        *rom_counts.entry(rom.romfile_id.unwrap()).or_default() += 1;
+    }
+
+    // only files holding a single ROM can stand in for another ROM with the same content
+    let mut best_romfile_ids: HashMap<(i64, Option<String>, Option<String>), i64> = HashMap::new();
+    for rom in roms_by_game_id.values().flatten() {
+        if rom.crc.is_none() && rom.sha1.is_none() {
+            continue;
+        }
+        let romfile = romfiles_by_id.get(&rom.romfile_id.unwrap()).unwrap();
+        if rom_counts.get(&romfile.id) != Some(&1)
+            || !["ORIGINAL", "7Z", "ZIP"].contains(&get_romfile_format(&romfile.path))
+        {
+            continue;
+        }
+        let best_romfile_id = best_romfile_ids
+            .entry((rom.size, rom.crc.clone(), rom.sha1.clone()))
+            .or_insert(romfile.id);
+        if get_rank(romfile) < get_rank(romfiles_by_id.get(best_romfile_id).unwrap()) {
+            *best_romfile_id = romfile.id;
+        }
+    }
+
+    for rom in roms_by_game_id.values_mut().flatten() {
+        let romfile = romfiles_by_id.get(&rom.romfile_id.unwrap()).unwrap();
+        let best_romfile = match best_romfile_ids
+            .get(&(rom.size, rom.crc.clone(), rom.sha1.clone()))
+            .and_then(|best_romfile_id| romfiles_by_id.get(best_romfile_id))
+        {
+            Some(best_romfile) if get_rank(best_romfile) < get_rank(romfile) => best_romfile,
+            _ => continue,
+        };
+        progress_bar.println(format!(
+            "Exporting \"{}\" from \"{}\"",
+            rom.name, best_romfile.path
+        ));
+        let best_path = best_romfile.as_common(connection).await?.path;
+        let staged_path = prefer_directory.join(&rom.name);
+        if ARCHIVE_EXTENSIONS.contains(&best_path.extension().unwrap().to_str().unwrap()) {
+            let extracted_romfile = CommonRomfile::from_path(&best_path)?
+                .as_archive(progress_bar, None)
+                .await?
+                .remove(0)
+                .to_common(progress_bar, &prefer_directory)
+                .await?;
+            rename_file(progress_bar, &extracted_romfile.path, &staged_path, true).await?;
+        } else {
+            copy_file(progress_bar, &best_path, &staged_path, true).await?;
+        }
+        // staged copies never reach the database, negative ids can't clash with real ones
+        let staged_romfile = Romfile {
+            id: -rom.id,
+            path: staged_path.as_os_str().to_str().unwrap().to_owned(),
+            size: best_romfile.size,
+            parent_id: None,
+            romfile_type: best_romfile.romfile_type,
+            updated_at: best_romfile.updated_at,
+        };
+        rom.romfile_id = Some(staged_romfile.id);
+        romfiles_by_id.insert(staged_romfile.id, staged_romfile);
+    }
+
+    Ok(())
+}
+
+fn get_disambiguated_name(game: &Game, roms: &[Rom], exported_names: &HashSet<String>) -> String {
+    if !game.regions.is_empty() {
+        let name = format!("{} ({})", game.name, game.regions);
+        if !exported_names.contains(&name) {
+            return name;
+        }
+    }
+    let hash = roms
+        .iter()
+        .find_map(|rom| {
+            rom.sha1
+                .as_ref()
+                .or(rom.md5.as_ref())
+                .or(rom.crc.as_ref())
+                .or(rom.sha256.as_ref())
+        })
+        .map(|hash| hash.chars().take(8).collect::<String>())
+        .unwrap_or_else(|| game.id.to_string());
+    let name = format!("{} [{}]", game.name, hash);
+    let mut i = 2;
+    let mut unique_name = name.clone();
+    while exported_names.contains(&unique_name) {
+        unique_name = format!("{} ({})", name, i);
+        i += 1;
+    }
+    unique_name
+}
+
+enum ExpectedContent<'a> {
+    // a ROM exported as-is, e.g. to ORIGINAL
+    Rom(&'a Rom),
+    // ROM files already in the destination format are copied
+    Romfile(&'a Romfile),
+    // archive members are listed with their size and CRC
+    Archive(Vec<&'a Rom>),
+}
+
+struct ExpectedFile<'a> {
+    path: PathBuf,
+    content: ExpectedContent<'a>,
+}
+
+// only files that can be checked against the database are predicted, other games are always exported
+fn get_expected_files<'a>(
+    destination_directory: &Path,
+    format: &str,
+    system: &System,
+    game: &Game,
+    roms: &'a [Rom],
+    romfiles_by_id: &'a HashMap<i64, Romfile>,
+) -> Vec<ExpectedFile<'a>> {
+    if format == "ORIGINAL" {
+        return roms
+            .iter()
+            .map(|rom| ExpectedFile {
+                path: if system.arcade {
+                    destination_directory.join(&game.name).join(&rom.name)
+                } else {
+                    destination_directory.join(&rom.name)
+                },
+                content: ExpectedContent::Rom(rom),
+            })
+            .collect();
+    }
+
+    let extension = format.to_lowercase();
+    let mut romfiles: Vec<&Romfile> = roms
+        .iter()
+        .map(|rom| romfiles_by_id.get(&rom.romfile_id.unwrap()).unwrap())
+        .collect();
+    romfiles.dedup_by_key(|romfile| romfile.id);
+
+    // files already in the destination format are copied as-is
+    if romfiles
+        .iter()
+        .all(|romfile| romfile.path.ends_with(&format!(".{}", extension)))
+    {
+        return romfiles
+            .into_iter()
+            .map(|romfile| ExpectedFile {
+                path: destination_directory.join(Path::new(&romfile.path).file_name().unwrap()),
+                content: ExpectedContent::Romfile(romfile),
+            })
+            .collect();
+    }
+
+    if ARCHIVE_EXTENSIONS.contains(&extension.as_str()) {
+        return vec![ExpectedFile {
+            path: destination_directory.join(format!("{}.{}", game.name, extension)),
+            content: ExpectedContent::Archive(roms.iter().collect()),
+        }];
+    }
+
+    // other conversions, e.g. CHD to ISO, only reproduce single ROMs verbatim
+    match roms {
+        [rom]
+            if Path::new(&rom.name)
+                .extension()
+                .is_some_and(|rom_extension| rom_extension.to_str().unwrap() == extension) =>
+        {
+            vec![ExpectedFile {
+                path: destination_directory.join(&rom.name),
+                content: ExpectedContent::Rom(rom),
+            }]
+        }
+        _ => vec![],
+    }
+}
+
+async fn is_already_exported(
+    connection: &mut SqliteConnection,
+    progress_bar: &ProgressBar,
+    expected_files: &[ExpectedFile<'_>],
+    verify: bool,
+) -> SimpleResult<bool> {
+    if expected_files.is_empty() {
+        return Ok(false);
+    }
+    for expected_file in expected_files {
+        if !expected_file.path.is_file() {
+            return Ok(false);
+        }
+        let size = try_with!(
+            expected_file.path.metadata(),
+            "Failed to read \"{}\" metadata",
+            expected_file.path.as_os_str().to_str().unwrap()
+        )
+        .len();
+        if size == 0 {
+            return Ok(false);
+        }
+        let common_romfile = CommonRomfile::from_path(&expected_file.path)?;
+        match &expected_file.content {
+            ExpectedContent::Rom(rom) => {
+                if size != rom.size as u64 {
+                    return Ok(false);
+                }
+                if verify
+                    && !is_rom_hash_matching(connection, progress_bar, &common_romfile, rom).await?
+                {
+                    return Ok(false);
+                }
+            }
+            ExpectedContent::Romfile(romfile) => {
+                if size != romfile.size as u64 {
+                    return Ok(false);
+                }
+                if verify {
+                    let (hash, _) = common_romfile
+                        .get_hash_and_size(connection, progress_bar, 1, 1, &HashAlgorithm::Sha1)
+                        .await?;
+                    let (expected_hash, _) = romfile
+                        .as_common(connection)
+                        .await?
+                        .get_hash_and_size(connection, progress_bar, 1, 1, &HashAlgorithm::Sha1)
+                        .await?;
+                    if hash != expected_hash {
+                        return Ok(false);
+                    }
+                }
+            }
+            ExpectedContent::Archive(roms) => {
+                let archive_romfiles = match common_romfile.as_archive(progress_bar, None).await {
+                    Ok(archive_romfiles) => archive_romfiles,
+                    Err(_) => return Ok(false),
+                };
+                let all_present = roms.iter().all(|rom| {
+                    archive_romfiles.iter().any(|archive_romfile| {
+                        archive_romfile.path == rom.name
+                            && archive_romfile.size == rom.size as u64
+                            && rom
+                                .crc
+                                .as_ref()
+                                .map_or(true, |crc| archive_romfile.crc == crc.to_lowercase())
+                    })
+                });
+                if !all_present {
+                    return Ok(false);
+                }
+                // the listed CRCs come from the archive headers, testing checks them against the data
+                if verify
+                    && sevenzip::test_archive(progress_bar, &expected_file.path)
+                        .await
+                        .is_err()
+                {
+                    return Ok(false);
+                }
+            }
+        }
+    }
+    Ok(true)
+}
+
+async fn is_rom_hash_matching(
+    connection: &mut SqliteConnection,
+    progress_bar: &ProgressBar,
+    romfile: &CommonRomfile,
+    rom: &Rom,
+) -> SimpleResult<bool> {
+    let (hash_algorithm, expected_hash) = match (&rom.crc, &rom.md5, &rom.sha1, &rom.sha256) {
+        (Some(crc), _, _, _) => (HashAlgorithm::Crc, crc),
+        (_, Some(md5), _, _) => (HashAlgorithm::Md5, md5),
+        (_, _, Some(sha1), _) => (HashAlgorithm::Sha1, sha1),
+        (_, _, _, Some(sha256)) => (HashAlgorithm::Sha256, sha256),
+        _ => return Ok(true),
+    };
+    let (hash, _) = romfile
+        .get_hash_and_size(connection, progress_bar, 1, 1, &hash_algorithm)
+        .await?;
+    Ok(&hash == expected_hash)
+}
+
+fn get_annotated_name(game: &Game, roms: &[Rom]) -> Option<String> {
+    if game.regions.is_empty() {
+        return None;
+    }
+    let name = if get_regions_from_game_name(&game.name).is_ok_and(|regions| !regions.is_empty()) {
+        game.name.clone()
+    } else {
+        format!("{} ({})", game.name, game.regions)
+    };
+    // ROM file names are kept as-is by some formats, e.g. PSN packages
+    if name == game.name
+        && roms.iter().all(|rom| {
+            get_regions_from_game_name(Path::new(&rom.name).file_stem().unwrap().to_str().unwrap())
+                .is_ok_and(|regions| !regions.is_empty())
+        })
+    {
+        return None;
+    }
+    Some(name)
+}
+
+async fn write_gamelist<P: AsRef<Path>>(
+    progress_bar: &ProgressBar,
+    destination_directory: &P,
+    games: &[(String, String)],
+) -> SimpleResult<()> {
+    let paths: Vec<PathBuf> = WalkDir::new(destination_directory)
+        .min_depth(1)
+        .max_depth(1)
+        .sort_by_file_name()
+        .into_iter()
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.into_path())
+        .collect();
+
+    let mut games_xml: Vec<GamelistGameXml> = vec![];
+    for (name, description) in games {
+        // prefer the main file, e.g. the CUE over its BIN tracks, then fall back to a game directory
+        let path = paths
+            .iter()
+            .find(|path| {
+                path.is_file() && path.file_stem().unwrap().to_str().unwrap() == name.as_str()
+            })
+            .or_else(|| {
+                paths.iter().find(|path| {
+                    path.is_dir() && path.file_name().unwrap().to_str().unwrap() == name.as_str()
+                })
+            });
+        match path {
+            Some(path) => games_xml.push(GamelistGameXml {
+                path: format!("./{}", path.file_name().unwrap().to_str().unwrap()),
+                name: name.clone(),
+                desc: if description != name {
+                    Some(description.clone())
+                } else {
+                    None
+                },
+            }),
+            None => progress_bar.println(format!("No exported file found for \"{}\"", name)),
+        }
+    }
+
+    let gamelist_xml = GamelistXml { games: games_xml };
+    let mut buffer = String::new();
+    let mut serializer = se::Serializer::new(&mut buffer);
+    serializer.indent(' ', 2);
+    try_with!(
+        gamelist_xml.serialize(serializer),
+        "Failed to serialize gamelist"
+    );
+
+    let gamelist_path = destination_directory.as_ref().join(GAMELIST_FILE_NAME);
+    let mut gamelist_file = create_file(progress_bar, &gamelist_path, false).await?;
+    try_with!(
+        gamelist_file.write_all(XML_DECLARATION.as_bytes()).await,
+        "Failed to write gamelist"
+    );
+    try_with!(
+        gamelist_file.write_all(buffer.as_bytes()).await,
+        "Failed to write gamelist"
+    );
+
+    Ok(())
+}
+
+// CHD headers carry the SHA1 of their content, so exported files don't need to be hashed again
+async fn write_index<P: AsRef<Path>>(
+    progress_bar: &ProgressBar,
+    destination_directory: &P,
+    games: &[(String, String)],
+    chd_paths_by_name: &HashMap<String, PathBuf>,
+) -> SimpleResult<()> {
+    let mut lines: Vec<String> = vec![String::from("name\tfile\tsize\tsha1")];
+    for (name, _) in games {
+        let path = match chd_paths_by_name.get(name) {
+            Some(path) => path,
+            None => {
+                progress_bar.println(format!("No exported CHD found for \"{}\"", name));
+                continue;
+            }
+        };
+        let size = try_with!(
+            path.metadata(),
+            "Failed to read \"{}\" metadata",
+            path.as_os_str().to_str().unwrap()
+        )
+        .len();
+        let chd_romfile = CommonRomfile::from_path(path)?.as_chd().await?;
+        lines.push(format!(
+            "{}\t{}\t{}\t{}",
+            name,
+            path.strip_prefix(destination_directory)
+                .unwrap()
+                .as_os_str()
+                .to_str()
+                .unwrap(),
+            size,
+            chd_romfile.chd_sha1
+        ));
+    }
+
+    let index_path = destination_directory.as_ref().join(INDEX_FILE_NAME);
+    let mut index_file = create_file(progress_bar, &index_path, false).await?;
+    try_with!(
+        index_file
+            .write_all(format!("{}\n", lines.join("\n")).as_bytes())
+            .await,
+        "Failed to write index"
+    );
+
+    Ok(())
+}
+
+async fn write_manifest<P: AsRef<Path>, Q: AsRef<Path>>(
+    progress_bar: &ProgressBar,
+    destination_directory: &P,
+    manifest_path: &Q,
+    games: &[(String, String)],
+    existing_files: &HashMap<PathBuf, SystemTime>,
+) -> SimpleResult<()> {
+    // longest names first so that "Game (Rev 1)" files aren't attributed to "Game"
+    let mut games: Vec<&(String, String)> = games.iter().collect();
+    games.sort_by(|(a, _), (b, _)| b.len().cmp(&a.len()));
+
+    let mut games_xml: Vec<GameXml> = vec![];
+    for entry in WalkDir::new(destination_directory)
+        .min_depth(1)
+        .sort_by_file_name()
+        .into_iter()
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.path().is_file())
+    {
+        let metadata = try_with!(
+            entry.metadata(),
+            "Failed to read \"{}\" metadata",
+            entry.path().as_os_str().to_str().unwrap()
+        );
+        let modified_time = try_with!(
+            metadata.modified(),
+            "Failed to read \"{}\" modification time",
+            entry.path().as_os_str().to_str().unwrap()
+        );
+        if existing_files.get(entry.path()) == Some(&modified_time) {
+            continue;
+        }
+        let relative_path = entry
+            .path()
+            .strip_prefix(destination_directory)
+            .unwrap()
+            .as_os_str()
+            .to_str()
+            .unwrap()
+            .to_string();
+        let (game_name, game_description) = match games
+            .iter()
+            .find(|(name, _)| relative_path.starts_with(name.as_str()))
+        {
+            Some(game) => game,
+            None => continue,
+        };
+        progress_bar.println(format!("Processing \"{}\"", &relative_path));
+        let size = metadata.len();
+        progress_bar.reset();
+        progress_bar.set_message("Computing hashes");
+        progress_bar.set_style(get_bytes_progress_style());
+        progress_bar.set_length(size);
+        let mut file = open_file_sync(&entry.path())?;
+        let (crc, md5, sha1) = compute_crc_md5_sha1(&mut file, progress_bar)?;
+        progress_bar.set_message("");
+        progress_bar.set_style(get_none_progress_style());
+        let rom_xml = RomXml {
+            name: relative_path,
+            size: size as i64,
+            crc: Some(crc),
+            md5: Some(md5),
+            sha1: Some(sha1),
+            sha256: None,
+            merge: None,
+            status: None,
+            alt_names: vec![],
+        };
+        match games_xml
+            .iter_mut()
+            .find(|game_xml| &game_xml.name == game_name)
+        {
+            Some(game_xml) => game_xml.roms.push(rom_xml),
+            None => games_xml.push(GameXml {
+                name: game_name.clone(),
+                description: game_description.clone(),
+                roms: vec![rom_xml],
+                disks: vec![],
+                releases: vec![],
+                isbios: false,
+                isdevice: false,
+                cloneof: None,
+                romof: None,
+                comment: None,
+            }),
+        }
+    }
+
+    let date = format!("{}", Local::now().format("%Y%m%d-%H%M%S"));
+    let datfile_xml = DatfileXml {
+        system: SystemXml {
+            name: String::from("oxyromon export"),
+            description: destination_directory
+                .as_ref()
+                .as_os_str()
+                .to_str()
+                .unwrap()
+                .to_string(),
+            version: date.clone(),
+            date: Some(date),
+            author: String::from("oxyromon"),
+            url: None,
+            clrmamepros: vec![],
+        },
+        games: games_xml,
+        machines: vec![],
+    };
+
+    let mut buffer = String::new();
+    let mut serializer = se::Serializer::new(&mut buffer);
+    serializer.indent(' ', 2);
+    try_with!(
+        datfile_xml.serialize(serializer),
+        "Failed to serialize manifest"
+    );
+
+    let mut manifest_file = create_file(progress_bar, manifest_path, false).await?;
+    for doctype in DOCTYPE {
+        try_with!(
+            manifest_file.write_all(doctype.as_bytes()).await,
+            "Failed to write manifest"
+        );
+    }
+    try_with!(
+        manifest_file.write_all(buffer.as_bytes()).await,
+        "Failed to write manifest"
+    );
+
+    Ok(())
+}
+
+fn get_modified_times<P: AsRef<Path>>(directory: &P) -> HashMap<PathBuf, SystemTime> {
+    WalkDir::new(directory)
+        .min_depth(1)
+        .into_iter()
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.path().is_file())
+        .filter_map(|entry| {
+            let modified_time = entry.metadata().ok()?.modified().ok()?;
+            Some((entry.into_path(), modified_time))
+        })
+        .collect()
+}
+
+async fn move_disambiguated_files(
+    progress_bar: &ProgressBar,
+    source_directory: &Path,
+    destination_directory: &Path,
+    name: &str,
+    fat_safe: bool,
+) -> SimpleResult<HashMap<PathBuf, PathBuf>> {
+    let paths: Vec<PathBuf> = WalkDir::new(source_directory)
+        .into_iter()
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.path().is_file())
+        .map(|entry| entry.into_path())
+        .collect();
+    let mut new_paths_by_path: HashMap<PathBuf, PathBuf> = HashMap::new();
+    for path in &paths {
+        let relative_path: PathBuf = if fat_safe {
+            path.strip_prefix(source_directory)
+                .unwrap()
+                .iter()
+                .map(|component| get_fat_safe_name(component.to_str().unwrap()))
+                .collect()
+        } else {
+            path.strip_prefix(source_directory).unwrap().to_path_buf()
+        };
+        let new_path = if paths.len() == 1 && relative_path.parent() == Some(Path::new("")) {
+            // a single file is renamed, keeping its extension
+            match path.extension() {
+                Some(extension) => {
+                    destination_directory.join(format!("{}.{}", name, extension.to_str().unwrap()))
+                }
+                None => destination_directory.join(name),
+            }
+        } else if relative_path.starts_with(name) {
+            destination_directory.join(relative_path)
+        } else {
+            // multiple files are grouped in a directory so that references between them still work
+            destination_directory.join(name).join(relative_path)
+        };
+        rename_file(progress_bar, path, &new_path, false).await?;
+        new_paths_by_path.insert(path.to_path_buf(), new_path);
+    }
+    // sheets must reference the sanitized names of their tracks and discs
+    if fat_safe {
+        for new_path in new_paths_by_path.values() {
+            rewrite_fat_safe_references(new_path).await?;
+        }
+    }
+    Ok(new_paths_by_path)
+}
+
+async fn rewrite_fat_safe_references(path: &Path) -> SimpleResult<()> {
+    let extension = match path.extension() {
+        Some(extension) => extension.to_str().unwrap().to_lowercase(),
+        None => return Ok(()),
+    };
+    if extension != CUE_EXTENSION && extension != M3U_EXTENSION {
+        return Ok(());
+    }
+    let sheet = try_with!(
+        tokio::fs::read_to_string(path).await,
+        "Failed to read \"{}\"",
+        path.as_os_str().to_str().unwrap()
+    );
+    let line_ending = if sheet.contains("\r\n") { "\r\n" } else { "\n" };
+    let mut lines: Vec<String> = vec![];
+    let mut rewritten = false;
+    for line in sheet.lines() {
+        let file_names = if extension == CUE_EXTENSION {
+            get_cue_file_names(line)
+        } else {
+            get_m3u_file_names(line)
+        };
+        match file_names.first() {
+            Some(file_name) => {
+                let fat_safe_name = file_name
+                    .split('/')
+                    .map(get_fat_safe_name)
+                    .collect::<Vec<String>>()
+                    .join("/");
+                rewritten |= fat_safe_name != *file_name;
+                lines.push(line.replacen(file_name, &fat_safe_name, 1));
+            }
+            None => lines.push(line.to_owned()),
+        }
+    }
+    if rewritten {
+        try_with!(
+            tokio::fs::write(path, format!("{}{}", lines.join(line_ending), line_ending)).await,
+            "Failed to write \"{}\"",
+            path.as_os_str().to_str().unwrap()
+        );
+    }
+    Ok(())
+}
+
+// files referenced by a CUE or M3U sheet keep their name so that the references still work
+async fn case_extensions(
+    progress_bar: &ProgressBar,
+    destination_directory: &Path,
+    existing_files: &HashMap<PathBuf, SystemTime>,
+    uppercase_extensions: &[String],
+) -> SimpleResult<()> {
+    let new_files: Vec<PathBuf> = get_modified_times(&destination_directory)
+        .into_iter()
+        .filter(|(path, modified_time)| existing_files.get(path) != Some(modified_time))
+        .map(|(path, _)| path)
+        .collect();
+    let mut referenced_files: HashSet<PathBuf> = HashSet::new();
+    for path in &new_files {
+        let extension = match path.extension() {
+            Some(extension) => extension.to_str().unwrap().to_lowercase(),
+            None => continue,
+        };
+        if extension != CUE_EXTENSION && extension != M3U_EXTENSION {
+            continue;
+        }
+        let sheet = match tokio::fs::read_to_string(path).await {
+            Ok(sheet) => sheet,
+            Err(_) => continue,
+        };
+        let file_names = if extension == CUE_EXTENSION {
+            get_cue_file_names(&sheet)
+        } else {
+            get_m3u_file_names(&sheet)
+        };
+        referenced_files.extend(file_names.into_iter().map(|name| path.with_file_name(name)));
+    }
+    for path in new_files
+        .iter()
+        .filter(|path| !referenced_files.contains(*path))
+    {
+        let new_path = get_cased_path(path, uppercase_extensions);
+        rename_file(progress_bar, path, &new_path, false).await?;
+    }
+    Ok(())
+}
+
+fn get_cased_path(path: &Path, uppercase_extensions: &[String]) -> PathBuf {
+    match path.extension().and_then(|extension| extension.to_str()) {
+        Some(extension) if uppercase_extensions.contains(&extension.to_lowercase()) => {
+            path.with_extension(extension.to_uppercase())
+        }
+        _ => path.to_path_buf(),
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+pub async fn export_system(
+    connection: &mut SqliteConnection,
+    progress_bar: &ProgressBar,
+    destination_directory: &PathBuf,
+    system: &System,
+    format: &str,
+    games_by_id: HashMap<i64, Game>,
+    roms_by_game_id: IndexMap<i64, Vec<Rom>>,
+    mut romfiles_by_id: HashMap<i64, Romfile>,
+    link: bool,
+    compression_level: Option<usize>,
+    chd_algorithms: &Option<Vec<String>>,
+    group_archives_by: ArchiveGrouping,
+) -> SimpleResult<HashMap<i64, PathBuf>> {
+    // CHD exports report the file written for each game so that they can be indexed
+    let mut chd_paths_by_game_id: HashMap<i64, PathBuf> = HashMap::new();
+
+    // content-addressed files are staged under their ROM names before exporting
+    let staging_directory = create_tmp_directory(connection).await?;
+    for rom in roms_by_game_id.values().flatten() {
+        let romfile = match rom
+            .romfile_id
+            .and_then(|romfile_id| romfiles_by_id.get_mut(&romfile_id))
+        {
+            Some(romfile) => romfile,
+            None => continue,
+        };
+        if rom.sha1.is_some()
+            && Path::new(&romfile.path).file_stem().unwrap().to_str() == rom.sha1.as_deref()
+        {
+            let staged_path = staging_directory.path().join(&rom.name);
+            copy_file(
+                progress_bar,
+                &romfile.as_common(connection).await?.path,
+                &staged_path,
+                true,
+            )
+            .await?;
+            romfile.path = staged_path.as_os_str().to_str().unwrap().to_owned();
+        }
+    }
+
+    match format {
+        "ORIGINAL" => {
+            to_original(
+                connection,
+                progress_bar,
+                destination_directory,
+                system,
+                games_by_id,
+                roms_by_game_id,
+                romfiles_by_id,
+                link,
+            )
+            .await?
+        }
+        "7Z" => {
+            let compression_level = match compression_level {
+                Some(compression_level) => Some(compression_level),
+                None => get_integer(connection, "SEVENZIP_COMPRESSION_LEVEL").await,
+            };
+            let solid = get_bool(connection, "SEVENZIP_SOLID_COMPRESSION").await;
+            to_archive(
+                connection,
+                progress_bar,
+                destination_directory,
+                system,
+                games_by_id,
+                roms_by_game_id,
+                romfiles_by_id,
+                sevenzip::ArchiveType::Sevenzip,
+                &compression_level,
+                solid,
+                group_archives_by,
+            )
+            .await?
+        }
+        "ZIP" => {
+            let compression_level = match compression_level {
+                Some(compression_level) => Some(compression_level),
+                None => get_integer(connection, "ZIP_COMPRESSION_LEVEL").await,
+            };
+            to_archive(
+                connection,
+                progress_bar,
+                destination_directory,
+                system,
+                games_by_id,
+                roms_by_game_id,
+                romfiles_by_id,
+                sevenzip::ArchiveType::Zip,
+                &compression_level,
+                false,
+                group_archives_by,
+            )
+            .await?
+        }
+        "ISO" => {
+            to_iso(
+                connection,
+                progress_bar,
+                destination_directory,
+                roms_by_game_id,
+                romfiles_by_id,
+            )
+            .await?
+        }
+        "CHD" => {
+            // overridden algorithms are dispatched to the CHD types they're valid for
+            let cd_compression_algorithms = match chd_algorithms {
+                Some(chd_algorithms) => chd_algorithms
+                    .iter()
+                    .filter(|&algorithm| ChdCdCompressionAlgorithm::from_str(algorithm).is_ok())
+                    .cloned()
+                    .collect(),
+                None => get_list(connection, "CHD_CD_COMPRESSION_ALGORITHMS").await,
+            };
+            let cd_hunk_size = get_integer(connection, "CHD_CD_HUNK_SIZE").await;
+            let dvd_compression_algorithms = match chd_algorithms {
+                Some(chd_algorithms) => chd_algorithms
+                    .iter()
+                    .filter(|&algorithm| ChdDvdCompressionAlgorithm::from_str(algorithm).is_ok())
+                    .cloned()
+                    .collect(),
+                None => get_list(connection, "CHD_DVD_COMPRESSION_ALGORITHMS").await,
+            };
+            let dvd_hunk_size = get_integer(connection, "CHD_DVD_HUNK_SIZE").await;
+            chd_paths_by_game_id = to_chd(
+                connection,
+                progress_bar,
+                destination_directory,
+                games_by_id,
+                roms_by_game_id,
+                romfiles_by_id,
+                &cd_compression_algorithms,
+                &cd_hunk_size,
+                &dvd_compression_algorithms,
+                &dvd_hunk_size,
+            )
+            .await?;
+        }
+        "CSO" => {
+            to_cso(
+                connection,
+                progress_bar,
+                destination_directory,
+                roms_by_game_id,
+                romfiles_by_id,
+            )
+            .await?
+        }
+        "NSZ" => {
+            to_nsz(
+                connection,
+                progress_bar,
+                destination_directory,
+                roms_by_game_id,
+                romfiles_by_id,
+            )
+            .await?
+        }
+        "RVZ" => {
+            let compression_algorithm = RvzCompressionAlgorithm::from_str(
+                &get_string(connection, "RVZ_COMPRESSION_ALGORITHM")
+                    .await
+                    .unwrap(),
+            )
+            .unwrap();
+            let compression_level = match compression_level {
+                Some(compression_level) => compression_level,
+                None => get_integer(connection, "RVZ_COMPRESSION_LEVEL")
+                    .await
+                    .unwrap(),
+            };
+            let block_size = get_integer(connection, "RVZ_BLOCK_SIZE").await.unwrap();
+            let scrub = get_bool(connection, "RVZ_SCRUB").await;
+            to_rvz(
+                connection,
+                progress_bar,
+                destination_directory,
+                roms_by_game_id,
+                romfiles_by_id,
+                &compression_algorithm,
+                compression_level,
+                block_size,
+                scrub,
+            )
+            .await?
+        }
+        "WBFS" => {
+            to_wbfs(
+                connection,
+                progress_bar,
+                destination_directory,
+                roms_by_game_id,
+                romfiles_by_id,
+            )
+            .await?
+        }
+        "ZSO" => {
+            to_zso(
+                connection,
+                progress_bar,
+                destination_directory,
+                roms_by_game_id,
+                romfiles_by_id,
+            )
+            .await?
+        }
+        _ => bail!("Not supported"),
+    }
+    Ok(chd_paths_by_game_id)
+}
+
+#[allow(clippy::too_many_arguments)]
 async fn to_archive(
     connection: &mut SqliteConnection,
     progress_bar: &ProgressBar,
@@ -403,6 +1852,7 @@ async fn to_archive(
     archive_type: sevenzip::ArchiveType,
     compression_level: &Option<usize>,
     solid: bool,
+    group_archives_by: ArchiveGrouping,
 ) -> SimpleResult<()> {
     // partition CHDs
     let (chds, roms_by_game_id): (IndexMap<i64, Vec<Rom>>, IndexMap<i64, Vec<Rom>>) =
@@ -514,12 +1964,26 @@ async fn to_archive(
                 ));
                     continue;
                 }
-                let cue_rom = cue_roms.first().unwrap();
-                let cue_romfile = romfiles_by_id
-                    .get(&cue_rom.romfile_id.unwrap())
-                    .unwrap()
-                    .as_common(connection)
-                    .await?;
+                // prefer canonical cuesheets over the stored CUE files
+                let cuesheet_directory = create_tmp_directory(connection).await?;
+                let cue_romfile = match write_cuesheet(
+                    connection,
+                    progress_bar,
+                    game.id,
+                    &cuesheet_directory.path(),
+                )
+                .await?
+                {
+                    Some(cue_romfile) => cue_romfile,
+                    None => {
+                        let cue_rom = cue_roms.first().unwrap();
+                        romfiles_by_id
+                            .get(&cue_rom.romfile_id.unwrap())
+                            .unwrap()
+                            .as_common(connection)
+                            .await?
+                    }
+                };
                 let cue_bin_romfile = chd_romfile
                     .to_cue_bin(
                         progress_bar,
@@ -529,79 +1993,96 @@ async fn to_archive(
                         true,
                     )
                     .await?;
-                cue_bin_romfile
-                    .cue_romfile
-                    .to_archive(
+                add_to_archive(
+                    connection,
+                    progress_bar,
+                    &cue_bin_romfile.cue_romfile,
+                    &cue_bin_romfile.cue_romfile.path.parent().unwrap(),
+                    destination_directory,
+                    system,
+                    game,
+                    group_archives_by,
+                    &archive_type,
+                    compression_level,
+                    solid,
+                )
+                .await?;
+                for bin_romfile in &cue_bin_romfile.bin_romfiles {
+                    add_to_archive(
+                        connection,
                         progress_bar,
-                        &cue_bin_romfile.cue_romfile.path.parent().unwrap(),
+                        bin_romfile,
+                        &tmp_directory.path(),
                         destination_directory,
-                        &game.name,
+                        system,
+                        game,
+                        group_archives_by,
                         &archive_type,
                         compression_level,
                         solid,
                     )
                     .await?;
-                for bin_romfile in cue_bin_romfile.bin_romfiles {
-                    bin_romfile
-                        .to_archive(
-                            progress_bar,
-                            &tmp_directory.path(),
-                            destination_directory,
-                            &game.name,
-                            &archive_type,
-                            compression_level,
-                            solid,
-                        )
-                        .await?;
                 }
             }
             ChdType::Dvd => {
-                chd_romfile
+                let iso_romfile = chd_romfile
                     .to_iso(progress_bar, &tmp_directory.path())
                     .await?
-                    .romfile
-                    .to_archive(
-                        progress_bar,
-                        &tmp_directory.path(),
-                        destination_directory,
-                        &game.name,
-                        &archive_type,
-                        compression_level,
-                        solid,
-                    )
-                    .await?;
+                    .romfile;
+                add_to_archive(
+                    connection,
+                    progress_bar,
+                    &iso_romfile,
+                    &tmp_directory.path(),
+                    destination_directory,
+                    system,
+                    game,
+                    group_archives_by,
+                    &archive_type,
+                    compression_level,
+                    solid,
+                )
+                .await?;
             }
             ChdType::Hd => {
-                chd_romfile
+                let rdsk_romfile = chd_romfile
                     .to_rdsk(progress_bar, &tmp_directory.path())
                     .await?
-                    .romfile
-                    .to_archive(
-                        progress_bar,
-                        &tmp_directory.path(),
-                        destination_directory,
-                        &game.name,
-                        &archive_type,
-                        compression_level,
-                        solid,
-                    )
-                    .await?;
+                    .romfile;
+                add_to_archive(
+                    connection,
+                    progress_bar,
+                    &rdsk_romfile,
+                    &tmp_directory.path(),
+                    destination_directory,
+                    system,
+                    game,
+                    group_archives_by,
+                    &archive_type,
+                    compression_level,
+                    solid,
+                )
+                .await?;
             }
             ChdType::Ld => {
-                chd_romfile
+                let riff_romfile = chd_romfile
                     .to_riff(progress_bar, &tmp_directory.path())
                     .await?
-                    .romfile
-                    .to_archive(
-                        progress_bar,
-                        &tmp_directory.path(),
-                        destination_directory,
-                        &game.name,
-                        &archive_type,
-                        compression_level,
-                        solid,
-                    )
-                    .await?;
+                    .romfile;
+                add_to_archive(
+                    connection,
+                    progress_bar,
+                    &riff_romfile,
+                    &tmp_directory.path(),
+                    destination_directory,
+                    system,
+                    game,
+                    group_archives_by,
+                    &archive_type,
+                    compression_level,
+                    solid,
+                )
+                .await?;
             }
         }
     }
@@ -612,24 +2093,28 @@ async fn to_archive(
         let rom = roms.first().unwrap();
         let game = games_by_id.get(&rom.game_id).unwrap();
         let romfile = romfiles_by_id.get(&rom.romfile_id.unwrap()).unwrap();
-        romfile
+        let iso_romfile = romfile
             .as_common(connection)
             .await?
-            .as_xso()
-            .await?
-            .to_iso(progress_bar, &tmp_directory.path())
-            .await?
-            .romfile
-            .to_archive(
-                progress_bar,
-                &tmp_directory.path(),
-                destination_directory,
-                &game.name,
-                &archive_type,
-                compression_level,
-                solid,
-            )
-            .await?;
+            .as_xso()
+            .await?
+            .to_iso(progress_bar, &tmp_directory.path())
+            .await?
+            .romfile;
+        add_to_archive(
+            connection,
+            progress_bar,
+            &iso_romfile,
+            &tmp_directory.path(),
+            destination_directory,
+            system,
+            game,
+            group_archives_by,
+            &archive_type,
+            compression_level,
+            solid,
+        )
+        .await?;
     }
 
     // export NSZs
@@ -638,23 +2123,27 @@ async fn to_archive(
         let rom = roms.first().unwrap();
         let game = games_by_id.get(&rom.game_id).unwrap();
         let romfile = romfiles_by_id.get(&rom.romfile_id.unwrap()).unwrap();
-        romfile
+        let nsp_romfile = romfile
             .as_common(connection)
             .await?
             .as_nsz()?
             .to_nsp(progress_bar, &tmp_directory.path())
             .await?
-            .romfile
-            .to_archive(
-                progress_bar,
-                &tmp_directory.path(),
-                destination_directory,
-                &game.name,
-                &archive_type,
-                compression_level,
-                solid,
-            )
-            .await?;
+            .romfile;
+        add_to_archive(
+            connection,
+            progress_bar,
+            &nsp_romfile,
+            &tmp_directory.path(),
+            destination_directory,
+            system,
+            game,
+            group_archives_by,
+            &archive_type,
+            compression_level,
+            solid,
+        )
+        .await?;
     }
 
     // export RVZs
@@ -663,23 +2152,27 @@ async fn to_archive(
         let rom = roms.first().unwrap();
         let game = games_by_id.get(&rom.game_id).unwrap();
         let romfile = romfiles_by_id.get(&rom.romfile_id.unwrap()).unwrap();
-        romfile
+        let iso_romfile = romfile
             .as_common(connection)
             .await?
             .as_rvz()?
             .to_iso(progress_bar, &tmp_directory.path())
             .await?
-            .romfile
-            .to_archive(
-                progress_bar,
-                &tmp_directory.path(),
-                destination_directory,
-                &game.name,
-                &archive_type,
-                compression_level,
-                solid,
-            )
-            .await?;
+            .romfile;
+        add_to_archive(
+            connection,
+            progress_bar,
+            &iso_romfile,
+            &tmp_directory.path(),
+            destination_directory,
+            system,
+            game,
+            group_archives_by,
+            &archive_type,
+            compression_level,
+            solid,
+        )
+        .await?;
     }
 
     // export ZSOs
@@ -688,24 +2181,28 @@ async fn to_archive(
         let rom = roms.first().unwrap();
         let game = games_by_id.get(&rom.game_id).unwrap();
         let romfile = romfiles_by_id.get(&rom.romfile_id.unwrap()).unwrap();
-        romfile
+        let iso_romfile = romfile
             .as_common(connection)
             .await?
             .as_xso()
             .await?
             .to_iso(progress_bar, &tmp_directory.path())
             .await?
-            .romfile
-            .to_archive(
-                progress_bar,
-                &tmp_directory.path(),
-                destination_directory,
-                &game.name,
-                &archive_type,
-                compression_level,
-                solid,
-            )
-            .await?;
+            .romfile;
+        add_to_archive(
+            connection,
+            progress_bar,
+            &iso_romfile,
+            &tmp_directory.path(),
+            destination_directory,
+            system,
+            game,
+            group_archives_by,
+            &archive_type,
+            compression_level,
+            solid,
+        )
+        .await?;
     }
 
     // export archives
@@ -721,8 +2218,10 @@ async fn to_archive(
                 .await?
                 .pop()
                 .unwrap();
-            // skip archives that are the same type
-            if archive_romfile.archive_type == archive_type {
+            // skip archives that are the same type, unless they get merged
+            if archive_romfile.archive_type == archive_type
+                && group_archives_by == ArchiveGrouping::Game
+            {
                 copy_file(
                     progress_bar,
                     &archive_romfile.romfile.path,
@@ -732,17 +2231,24 @@ async fn to_archive(
                 .await?;
                 continue;
             }
-            archive_romfile
-                .to_archive(
-                    progress_bar,
-                    &tmp_directory.path(),
-                    destination_directory,
-                    &game.name,
-                    &archive_type,
-                    compression_level,
-                    solid,
-                )
+            let original_romfile = archive_romfile
+                .to_common(progress_bar, &tmp_directory.path())
                 .await?;
+            add_to_archive(
+                connection,
+                progress_bar,
+                &original_romfile,
+                &tmp_directory.path(),
+                destination_directory,
+                system,
+                game,
+                group_archives_by,
+                &archive_type,
+                compression_level,
+                solid,
+            )
+            .await?;
+            original_romfile.delete(progress_bar, true).await?;
         }
     }
 
@@ -752,19 +2258,21 @@ async fn to_archive(
             let rom = roms.first().unwrap();
             let game = games_by_id.get(&rom.game_id).unwrap();
             let romfile = romfiles_by_id.get(&rom.romfile_id.unwrap()).unwrap();
-            romfile
-                .as_common(connection)
-                .await?
-                .to_archive(
-                    progress_bar,
-                    &romfile.as_common(connection).await?.path.parent().unwrap(),
-                    destination_directory,
-                    &game.name,
-                    &archive_type,
-                    compression_level,
-                    solid,
-                )
-                .await?;
+            let common_romfile = romfile.as_common(connection).await?;
+            add_to_archive(
+                connection,
+                progress_bar,
+                &common_romfile,
+                &common_romfile.path.parent().unwrap(),
+                destination_directory,
+                system,
+                game,
+                group_archives_by,
+                &archive_type,
+                compression_level,
+                solid,
+            )
+            .await?;
         } else {
             let game = games_by_id.get(&game_id).unwrap();
             roms.retain(|rom| {
@@ -779,26 +2287,99 @@ async fn to_archive(
                 .map(|rom| romfiles_by_id.get(&rom.romfile_id.unwrap()).unwrap())
                 .collect::<Vec<&Romfile>>();
             for romfile in &romfiles {
-                romfile
-                    .as_common(connection)
-                    .await?
-                    .to_archive(
-                        progress_bar,
-                        &romfile.as_common(connection).await?.path.parent().unwrap(),
-                        destination_directory,
-                        &game.name,
-                        &archive_type,
-                        compression_level,
-                        solid,
-                    )
-                    .await?;
+                let common_romfile = romfile.as_common(connection).await?;
+                add_to_archive(
+                    connection,
+                    progress_bar,
+                    &common_romfile,
+                    &common_romfile.path.parent().unwrap(),
+                    destination_directory,
+                    system,
+                    game,
+                    group_archives_by,
+                    &archive_type,
+                    compression_level,
+                    solid,
+                )
+                .await?;
             }
         }
     }
     Ok(())
 }
 
+// grouped archives hold several games, each game's files go in their own directory so that same-named ROMs don't collide
+#[allow(clippy::too_many_arguments)]
+async fn add_to_archive<P: AsRef<Path>>(
+    connection: &mut SqliteConnection,
+    progress_bar: &ProgressBar,
+    romfile: &CommonRomfile,
+    working_directory: &P,
+    destination_directory: &PathBuf,
+    system: &System,
+    game: &Game,
+    group_archives_by: ArchiveGrouping,
+    archive_type: &sevenzip::ArchiveType,
+    compression_level: &Option<usize>,
+    solid: bool,
+) -> SimpleResult<()> {
+    let archive_name = get_archive_name(system, game, group_archives_by);
+    if group_archives_by == ArchiveGrouping::Game {
+        romfile
+            .to_archive(
+                progress_bar,
+                working_directory,
+                destination_directory,
+                &archive_name,
+                archive_type,
+                compression_level,
+                &None,
+                solid,
+            )
+            .await?;
+        return Ok(());
+    }
+    let tmp_directory = create_tmp_directory(connection).await?;
+    let path = tmp_directory
+        .path()
+        .join(&game.name)
+        .join(romfile.path.strip_prefix(working_directory).unwrap());
+    link_file(progress_bar, &romfile.path, &path, true).await?;
+    CommonRomfile::from_path(&path)?
+        .to_archive(
+            progress_bar,
+            &tmp_directory.path(),
+            destination_directory,
+            &archive_name,
+            archive_type,
+            compression_level,
+            &None,
+            solid,
+        )
+        .await?;
+    Ok(())
+}
+
 #[allow(clippy::too_many_arguments)]
+fn get_archive_name(system: &System, game: &Game, group_archives_by: ArchiveGrouping) -> String {
+    match group_archives_by {
+        ArchiveGrouping::Game => game.name.clone(),
+        ArchiveGrouping::System => system.name.clone(),
+        ArchiveGrouping::Region => {
+            if game.regions.is_empty() {
+                String::from("Unknown")
+            } else {
+                game.regions.clone()
+            }
+        }
+        // digits and symbols share a single archive
+        ArchiveGrouping::Letter => match game.name.chars().next() {
+            Some(letter) if letter.is_alphabetic() => letter.to_uppercase().to_string(),
+            _ => String::from("#"),
+        },
+    }
+}
+
 async fn to_chd(
     connection: &mut SqliteConnection,
     progress_bar: &ProgressBar,
@@ -810,7 +2391,9 @@ async fn to_chd(
     cd_hunk_size: &Option<usize>,
     dvd_compression_algorithms: &[String],
     dvd_hunk_size: &Option<usize>,
-) -> SimpleResult<()> {
+) -> SimpleResult<HashMap<i64, PathBuf>> {
+    let mut chd_paths_by_game_id: HashMap<i64, PathBuf> = HashMap::new();
+
     // partition archives
     let (archives, others): (IndexMap<i64, Vec<Rom>>, IndexMap<i64, Vec<Rom>>) =
         roms_by_game_id.into_iter().partition(|(_, roms)| {
@@ -955,7 +2538,7 @@ async fn to_chd(
             );
         }
 
-        match cue_romfile {
+        let chd_romfile = match cue_romfile {
             Some(cue_romfile) => {
                 cue_romfile
                     .as_cue_bin(bin_iso_romfiles)?
@@ -989,6 +2572,7 @@ async fn to_chd(
                     .await?
             }
         };
+        chd_paths_by_game_id.insert(game.id, chd_romfile.romfile.path);
     }
 
     // export CUE/BIN
@@ -1013,7 +2597,7 @@ async fn to_chd(
                     .await?,
             );
         }
-        cue_romfile
+        let chd_romfile = cue_romfile
             .as_cue_bin(bin_romfiles)?
             .to_chd(
                 progress_bar,
@@ -1026,6 +2610,7 @@ async fn to_chd(
                 },
             )
             .await?;
+        chd_paths_by_game_id.insert(game.id, chd_romfile.romfile.path);
     }
 
     // export ISOs
@@ -1034,7 +2619,7 @@ async fn to_chd(
         let romfile = romfiles_by_id.get(&rom.romfile_id.unwrap()).unwrap();
         let game = games_by_id.get(&rom.game_id).unwrap();
         let parent_chd_romfile = find_parent_chd_romfile_by_game(connection, game).await;
-        romfile
+        let chd_romfile = romfile
             .as_common(connection)
             .await?
             .as_iso()?
@@ -1049,6 +2634,7 @@ async fn to_chd(
                 },
             )
             .await?;
+        chd_paths_by_game_id.insert(game.id, chd_romfile.romfile.path);
     }
 
     // export CSOs
@@ -1058,7 +2644,7 @@ async fn to_chd(
         let romfile = romfiles_by_id.get(&rom.romfile_id.unwrap()).unwrap();
         let game = games_by_id.get(&rom.game_id).unwrap();
         let parent_chd_romfile = find_parent_chd_romfile_by_game(connection, game).await;
-        romfile
+        let chd_romfile = romfile
             .as_common(connection)
             .await?
             .as_xso()
@@ -1076,6 +2662,7 @@ async fn to_chd(
                 },
             )
             .await?;
+        chd_paths_by_game_id.insert(game.id, chd_romfile.romfile.path);
     }
 
     // export ZSOs
@@ -1085,7 +2672,7 @@ async fn to_chd(
         let romfile = romfiles_by_id.get(&rom.romfile_id.unwrap()).unwrap();
         let game = games_by_id.get(&rom.game_id).unwrap();
         let parent_chd_romfile = find_parent_chd_romfile_by_game(connection, game).await;
-        romfile
+        let chd_romfile = romfile
             .as_common(connection)
             .await?
             .as_xso()
@@ -1103,6 +2690,7 @@ async fn to_chd(
                 },
             )
             .await?;
+        chd_paths_by_game_id.insert(game.id, chd_romfile.romfile.path);
     }
 
     // export CHDs
@@ -1120,23 +2708,25 @@ async fn to_chd(
             bail!("Multiple CHDs found");
         }
         let romfile = romfiles.first().unwrap();
+        let chd_path = destination_directory.join(
+            romfile
+                .as_common(connection)
+                .await?
+                .path
+                .file_name()
+                .unwrap(),
+        );
         copy_file(
             progress_bar,
             &romfile.as_common(connection).await?.path,
-            &destination_directory.join(
-                romfile
-                    .as_common(connection)
-                    .await?
-                    .path
-                    .file_name()
-                    .unwrap(),
-            ),
+            &chd_path,
             false,
         )
         .await?;
+        chd_paths_by_game_id.insert(roms.first().unwrap().game_id, chd_path);
     }
 
-    Ok(())
+    Ok(chd_paths_by_game_id)
 }
 
 async fn to_cso(
@@ -1887,6 +3477,13 @@ async fn to_iso(
                 .unwrap()
                 .to_common(progress_bar, &tmp_directory.path())
                 .await?;
+            if !bchunk::is_single_data_track(&cue_romfile.path).await? {
+                progress_bar.println(format!(
+                    "\"{}\" has multiple tracks, ISO can only hold a single data track",
+                    cue_rom.name
+                ));
+                continue;
+            }
             let bin_rom = bin_roms.pop().unwrap();
             let bin_romfile = romfile
                 .as_common(connection)
@@ -1901,6 +3498,14 @@ async fn to_iso(
                 .as_cue_bin(vec![bin_romfile])?
                 .to_iso(progress_bar, destination_directory)
                 .await?;
+        } else if roms.par_iter().any(|rom| rom.name.ends_with(CUE_EXTENSION)) {
+            progress_bar.println(format!(
+                "\"{}\" has multiple tracks, ISO can only hold a single data track",
+                roms.iter()
+                    .find(|rom| rom.name.ends_with(CUE_EXTENSION))
+                    .unwrap()
+                    .name
+            ));
         }
     }
 
@@ -1909,14 +3514,19 @@ async fn to_iso(
         let (cue_roms, bin_roms): (Vec<&Rom>, Vec<&Rom>) = roms
             .iter()
             .partition(|rom| rom.name.ends_with(CUE_EXTENSION));
-        if bin_roms.len() > 1 {
-            continue;
-        }
         let cue_romfile = romfiles_by_id
             .get(&cue_roms.first().unwrap().romfile_id.unwrap())
             .unwrap()
             .as_common(connection)
             .await?;
+        // mixed-mode discs would lose their audio tracks, or worse
+        if bin_roms.len() > 1 || !bchunk::is_single_data_track(&cue_romfile.path).await? {
+            progress_bar.println(format!(
+                "\"{}\" has multiple tracks, ISO can only hold a single data track",
+                cue_roms.first().unwrap().name
+            ));
+            continue;
+        }
         let mut bin_romfiles: Vec<CommonRomfile> = vec![];
         for bin_rom in &bin_roms {
             bin_romfiles.push(
@@ -1936,12 +3546,9 @@ async fn to_iso(
     // export CHDs
     for roms in chds.values() {
         if chdman::get_version().await.is_err() {
-            progress_bar.println("Please install chdman");
+            print_missing_tool(progress_bar, "chdman");
             break;
         }
-        if roms.len() > 2 {
-            continue;
-        }
         let (cue_roms, bin_roms): (Vec<&Rom>, Vec<&Rom>) = roms
             .iter()
             .partition(|rom| rom.name.ends_with(CUE_EXTENSION));
@@ -1953,13 +3560,20 @@ async fn to_iso(
         if romfiles.len() > 1 {
             bail!("Multiple CHDs found");
         }
-        let tmp_directory = create_tmp_directory(connection).await?;
         let romfile = romfiles.first().unwrap();
-        let cue_romfile = romfiles_by_id
-            .get(&cue_roms.first().unwrap().romfile_id.unwrap())
-            .unwrap()
-            .as_common(connection)
-            .await?;
+        // CHDs can be imported without their cue, name them after the file instead
+        let name = match cue_roms.first() {
+            Some(cue_rom) => cue_rom.name.as_str(),
+            None => romfile.path.as_str(),
+        };
+        if bin_roms.len() > 1 {
+            progress_bar.println(format!(
+                "\"{}\" has multiple tracks, ISO can only hold a single data track",
+                name
+            ));
+            continue;
+        }
+        let tmp_directory = create_tmp_directory(connection).await?;
         let chd_romfile = match romfile.parent_id {
             Some(parent_id) => {
                 let parent_chd_romfile = find_romfile_by_id(connection, parent_id)
@@ -1978,6 +3592,26 @@ async fn to_iso(
         };
         match chd_romfile.chd_type {
             ChdType::Cd => {
+                let cue_romfile = match cue_roms.first() {
+                    Some(cue_rom) => {
+                        romfiles_by_id
+                            .get(&cue_rom.romfile_id.unwrap())
+                            .unwrap()
+                            .as_common(connection)
+                            .await?
+                    }
+                    None => {
+                        progress_bar.println(format!("\"{}\" has no cue, skipping", name));
+                        continue;
+                    }
+                };
+                if !bchunk::is_single_data_track(&cue_romfile.path).await? {
+                    progress_bar.println(format!(
+                        "\"{}\" has multiple tracks, ISO can only hold a single data track",
+                        name
+                    ));
+                    continue;
+                }
                 chd_romfile
                     .to_cue_bin(
                         progress_bar,
@@ -2001,10 +3635,6 @@ async fn to_iso(
 
     // export CSOs
     for roms in csos.values() {
-        if maxcso::get_version().await.is_err() {
-            progress_bar.println("Please install maxcso");
-            break;
-        }
         let rom = roms.first().unwrap();
         let romfile = romfiles_by_id.get(&rom.romfile_id.unwrap()).unwrap();
         romfile
@@ -2018,10 +3648,6 @@ async fn to_iso(
 
     // export ZSOs
     for roms in zsos.values() {
-        if maxcso::get_version().await.is_err() {
-            progress_bar.println("Please install maxcso");
-            break;
-        }
         let rom = roms.first().unwrap();
         let romfile = romfiles_by_id.get(&rom.romfile_id.unwrap()).unwrap();
         romfile
@@ -2055,6 +3681,7 @@ async fn to_iso(
     Ok(())
 }
 
+#[allow(clippy::too_many_arguments)]
 async fn to_original(
     connection: &mut SqliteConnection,
     progress_bar: &ProgressBar,
@@ -2063,6 +3690,7 @@ async fn to_original(
     games_by_id: HashMap<i64, Game>,
     roms_by_game_id: IndexMap<i64, Vec<Rom>>,
     romfiles_by_id: HashMap<i64, Romfile>,
+    link: bool,
 ) -> SimpleResult<()> {
     // partition archives
     let (archives, others): (IndexMap<i64, Vec<Rom>>, IndexMap<i64, Vec<Rom>>) =
@@ -2136,7 +3764,7 @@ async fn to_original(
     // export archives
     for roms in archives.values() {
         if sevenzip::get_version().await.is_err() {
-            progress_bar.println("Please install sevenzip");
+            print_missing_tool(progress_bar, "sevenzip");
             break;
         }
         let mut romfiles: Vec<&Romfile> = roms
@@ -2176,7 +3804,7 @@ async fn to_original(
     // export CHDs
     for roms in chds.values() {
         if chdman::get_version().await.is_err() {
-            progress_bar.println("Please install chdman");
+            print_missing_tool(progress_bar, "chdman");
             break;
         }
         let (cue_roms, bin_roms): (Vec<&Rom>, Vec<&Rom>) = roms
@@ -2222,17 +3850,30 @@ async fn to_original(
                 ));
                     continue;
                 }
-                let cue_romfile = match cue_roms.first() {
-                    Some(cue_rom) => Some(
-                        romfiles_by_id
-                            .get(&cue_rom.romfile_id.unwrap())
-                            .unwrap()
-                            .as_common(connection)
-                            .await?,
-                    ),
-                    None => None,
+                // prefer canonical cuesheets over the stored CUE files
+                let cuesheet_directory = create_tmp_directory(connection).await?;
+                let cue_romfile = match write_cuesheet(
+                    connection,
+                    progress_bar,
+                    bin_roms.first().unwrap().game_id,
+                    &cuesheet_directory.path(),
+                )
+                .await?
+                {
+                    Some(cue_romfile) => Some(cue_romfile),
+                    None => match cue_roms.first() {
+                        Some(cue_rom) => Some(
+                            romfiles_by_id
+                                .get(&cue_rom.romfile_id.unwrap())
+                                .unwrap()
+                                .as_common(connection)
+                                .await?,
+                        ),
+                        None => None,
+                    },
                 };
-                let cue_bin_romfile = chd_romfile
+                let generated = cue_romfile.is_none();
+                let mut cue_bin_romfile = chd_romfile
                     .to_cue_bin(
                         progress_bar,
                         destination_directory,
@@ -2241,6 +3882,25 @@ async fn to_original(
                         false,
                     )
                     .await?;
+                if generated {
+                    let game = games_by_id.get(&bin_roms.first().unwrap().game_id).unwrap();
+                    let name = chdman::get_cue_bin_name(
+                        &get_string(connection, "CUE_BIN_NAME_TEMPLATE")
+                            .await
+                            .unwrap(),
+                        system,
+                        game,
+                    );
+                    cue_bin_romfile = chdman::rename_cue_bin(
+                        progress_bar,
+                        cue_bin_romfile,
+                        destination_directory,
+                        &bin_roms,
+                        &name,
+                        false,
+                    )
+                    .await?;
+                }
                 cue_bin_romfile
                     .cue_romfile
                     .rename(
@@ -2271,10 +3931,6 @@ async fn to_original(
 
     // export CSOs
     for roms in csos.values() {
-        if maxcso::get_version().await.is_err() {
-            progress_bar.println("Please install maxcso");
-            break;
-        }
         let rom = roms.first().unwrap();
         let romfile = romfiles_by_id.get(&rom.romfile_id.unwrap()).unwrap();
         romfile
@@ -2289,7 +3945,7 @@ async fn to_original(
     // export NSZs
     for roms in nszs.values() {
         if nsz::get_version().await.is_err() {
-            progress_bar.println("Please install nsz");
+            print_missing_tool(progress_bar, "nsz");
             break;
         }
         let rom = roms.first().unwrap();
@@ -2305,7 +3961,7 @@ async fn to_original(
     // export RVZs
     for roms in rvzs.values() {
         if dolphin::get_version().await.is_err() {
-            progress_bar.println("Please install dolphin-tool");
+            print_missing_tool(progress_bar, "dolphin-tool");
             break;
         }
         let rom = roms.first().unwrap();
@@ -2320,10 +3976,6 @@ async fn to_original(
 
     // export ZSOs
     for roms in zsos.values() {
-        if maxcso::get_version().await.is_err() {
-            progress_bar.println("Please install maxcso");
-            break;
-        }
         let rom = roms.first().unwrap();
         let romfile = romfiles_by_id.get(&rom.romfile_id.unwrap()).unwrap();
         romfile
@@ -2339,20 +3991,13 @@ async fn to_original(
     for roms in others.values() {
         for rom in roms {
             let romfile = romfiles_by_id.get(&rom.romfile_id.unwrap()).unwrap();
-            copy_file(
-                progress_bar,
-                &romfile.as_common(connection).await?.path,
-                &destination_directory.join(
-                    romfile
-                        .as_common(connection)
-                        .await?
-                        .path
-                        .file_name()
-                        .unwrap(),
-                ),
-                false,
-            )
-            .await?;
+            let path = romfile.as_common(connection).await?.path;
+            let new_path = destination_directory.join(path.file_name().unwrap());
+            if link {
+                link_file(progress_bar, &path, &new_path, false).await?;
+            } else {
+                copy_file(progress_bar, &path, &new_path, false).await?;
+            }
         }
     }
 
@@ -2368,6 +4013,8 @@ mod test_cso_to_iso;
 #[cfg(test)]
 mod test_cso_to_sevenzip_iso;
 #[cfg(test)]
+mod test_iso_and_cue_bin_to_chd_index;
+#[cfg(test)]
 mod test_iso_chd_to_chd_should_copy;
 #[cfg(test)]
 mod test_iso_chd_to_cso;
@@ -2392,20 +4039,72 @@ mod test_multiple_tracks_chd_to_cso_should_do_nothing;
 #[cfg(test)]
 mod test_multiple_tracks_chd_to_cue_bin;
 #[cfg(test)]
+mod test_multiple_tracks_chd_to_cue_bin_with_cuesheet;
+#[cfg(test)]
 mod test_multiple_tracks_chd_to_sevenzip_cue_bin;
 #[cfg(test)]
 mod test_multiple_tracks_chd_to_zso_should_do_nothing;
 #[cfg(test)]
+mod test_multiple_tracks_chd_without_cue_to_iso_should_skip;
+#[cfg(test)]
 mod test_multiple_tracks_cue_bin_to_chd;
 #[cfg(test)]
+mod test_multiple_tracks_cue_bin_to_iso_should_skip;
+#[cfg(test)]
+mod test_multiple_tracks_cue_bin_to_original_fat_safe;
+#[cfg(test)]
+mod test_original_cue_bin_uppercase_extension;
+#[cfg(test)]
+mod test_original_to_original_annotate_region;
+#[cfg(test)]
+mod test_original_to_original_changed_since;
+#[cfg(test)]
+mod test_original_to_original_content_addressed;
+#[cfg(test)]
+mod test_original_to_original_export_directory;
+#[cfg(test)]
+mod test_original_to_original_export_directory_missing;
+#[cfg(test)]
+mod test_original_to_original_fat_safe;
+#[cfg(test)]
+mod test_original_to_original_gamelist;
+#[cfg(test)]
+mod test_original_to_original_group_by_tag;
+#[cfg(all(test, unix))]
+mod test_original_to_original_link;
+#[cfg(test)]
+mod test_original_to_original_manifest;
+#[cfg(test)]
+mod test_original_to_original_prefer;
+#[cfg(test)]
 mod test_original_to_original_should_copy;
 #[cfg(test)]
+mod test_original_to_original_skip_existing;
+#[cfg(test)]
 mod test_original_to_sevenzip;
 #[cfg(test)]
+mod test_original_to_sevenzip_compression_level;
+#[cfg(test)]
+mod test_original_to_sevenzip_group_archives_by_region;
+#[cfg(test)]
 mod test_original_to_zip;
 #[cfg(test)]
+mod test_original_to_zip_group_archives_by_system;
+#[cfg(test)]
+mod test_original_to_zip_group_archives_by_system_shared_names;
+#[cfg(test)]
+mod test_original_to_zip_match_dat;
+#[cfg(test)]
+mod test_original_to_zip_max_size_should_skip;
+#[cfg(test)]
 mod test_original_to_zip_multiple_roms;
 #[cfg(test)]
+mod test_original_to_zip_same_name_should_disambiguate;
+#[cfg(test)]
+mod test_original_to_zip_skip_existing;
+#[cfg(test)]
+mod test_original_to_zip_uppercase_extension;
+#[cfg(test)]
 mod test_original_to_zip_with_correct_name;
 #[cfg(test)]
 mod test_original_to_zip_with_incorrect_name;
@@ -2432,6 +4131,8 @@ mod test_sevenzip_to_original;
 #[cfg(test)]
 mod test_sevenzip_to_zip;
 #[cfg(test)]
+mod test_single_track_chd_to_cue_bin_name_template;
+#[cfg(test)]
 mod test_single_track_chd_to_iso;
 #[cfg(test)]
 mod test_single_track_cue_bin_to_iso;
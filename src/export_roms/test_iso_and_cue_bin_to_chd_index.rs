@@ -0,0 +1,104 @@
+use super::super::database::*;
+use super::super::import_dats;
+use super::super::import_roms;
+use super::*;
+use std::path::{Path, PathBuf};
+use tempfile::{NamedTempFile, TempDir};
+use tokio::fs;
+
+#[tokio::test]
+async fn test() {
+    match chdman::get_version().await {
+        Ok(version) => {
+            if version.as_str().cmp(chdman::MIN_SPLITBIN_VERSION) == Ordering::Less {
+                return;
+            }
+        }
+        Err(_) => return,
+    }
+
+    // given
+    let _guard = MUTEX.lock().await;
+
+    let test_directory = Path::new("tests");
+    let progress_bar = ProgressBar::hidden();
+
+    let db_file = NamedTempFile::new().unwrap();
+    let pool = establish_connection(db_file.path().to_str().unwrap()).await;
+    let mut connection = pool.acquire().await.unwrap();
+
+    let rom_directory = TempDir::new_in(&test_directory).unwrap();
+    set_rom_directory(PathBuf::from(rom_directory.path()));
+    let tmp_directory = TempDir::new_in(&test_directory).unwrap();
+    let tmp_directory = set_tmp_directory(PathBuf::from(tmp_directory.path()));
+
+    let matches = import_dats::subcommand()
+        .get_matches_from(&["import-dats", "tests/Test System (20200721).dat"]);
+    import_dats::main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    for romfile_name in [
+        "Test Game (USA, Europe).iso",
+        "Test Game (USA, Europe) (Multiple Tracks).cue",
+        "Test Game (USA, Europe) (CUE BIN) (Track 01).bin",
+        "Test Game (USA, Europe) (CUE BIN) (Track 02).bin",
+    ] {
+        let romfile_path = tmp_directory.join(romfile_name);
+        fs::copy(test_directory.join(romfile_name), &romfile_path)
+            .await
+            .unwrap();
+        let matches = import_roms::subcommand()
+            .get_matches_from(&["import-roms", romfile_path.as_os_str().to_str().unwrap()]);
+        import_roms::main(&mut connection, &matches, &progress_bar)
+            .await
+            .unwrap();
+    }
+
+    let destination_directory = tmp_directory.join("destination");
+
+    // when
+    let matches = subcommand().get_matches_from(&[
+        "export-roms",
+        "-s",
+        "Test System",
+        "-f",
+        "CHD",
+        "--index",
+        "-d",
+        destination_directory.as_os_str().to_str().unwrap(),
+    ]);
+    main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    // then
+    let index = fs::read_to_string(destination_directory.join(INDEX_FILE_NAME))
+        .await
+        .unwrap();
+    let lines: Vec<&str> = index.lines().collect();
+    assert_eq!(lines.len(), 3);
+    assert_eq!(lines[0], "name\tfile\tsize\tsha1");
+
+    let mut entries: Vec<Vec<&str>> = lines[1..]
+        .iter()
+        .map(|line| line.split('\t').collect())
+        .collect();
+    entries.sort();
+
+    for (entry, name) in entries.iter().zip([
+        "Test Game (USA, Europe) (CUE BIN)",
+        "Test Game (USA, Europe) (ISO)",
+    ]) {
+        let path = destination_directory.join(format!("{}.chd", name));
+        let chd_romfile = CommonRomfile::from_path(&path)
+            .unwrap()
+            .as_chd()
+            .await
+            .unwrap();
+        assert_eq!(entry[0], name);
+        assert_eq!(entry[1], format!("{}.chd", name));
+        assert_eq!(entry[2], path.metadata().unwrap().len().to_string());
+        assert_eq!(entry[3], chd_romfile.chd_sha1);
+    }
+}
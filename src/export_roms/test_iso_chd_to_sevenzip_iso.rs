@@ -71,6 +71,7 @@ async fn test() {
         sevenzip::ArchiveType::Sevenzip,
         &None,
         false,
+        ArchiveGrouping::Game,
     )
     .await
     .unwrap();
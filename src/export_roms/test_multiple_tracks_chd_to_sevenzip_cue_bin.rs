@@ -89,6 +89,7 @@ async fn test() {
         sevenzip::ArchiveType::Sevenzip,
         &None,
         false,
+        ArchiveGrouping::Game,
     )
     .await
     .unwrap();
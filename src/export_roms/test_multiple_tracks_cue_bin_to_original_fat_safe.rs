@@ -0,0 +1,97 @@
+use super::super::database::*;
+use super::super::import_dats;
+use super::super::import_roms;
+use super::*;
+use std::path::{Path, PathBuf};
+use tempfile::{NamedTempFile, TempDir};
+use tokio::fs;
+
+#[tokio::test]
+async fn test() {
+    // given
+    let _guard = MUTEX.lock().await;
+
+    let test_directory = Path::new("tests");
+    let progress_bar = ProgressBar::hidden();
+
+    let db_file = NamedTempFile::new().unwrap();
+    let pool = establish_connection(db_file.path().to_str().unwrap()).await;
+    let mut connection = pool.acquire().await.unwrap();
+
+    let rom_directory = TempDir::new_in(&test_directory).unwrap();
+    set_rom_directory(PathBuf::from(rom_directory.path()));
+    let tmp_directory = TempDir::new_in(&test_directory).unwrap();
+    let tmp_directory = set_tmp_directory(PathBuf::from(tmp_directory.path()));
+
+    let matches = import_dats::subcommand().get_matches_from(&[
+        "import-dats",
+        "tests/Test System (20250206) (FAT Multiple Tracks).dat",
+    ]);
+    import_dats::main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    for file_name in [
+        "Test Game (USA, Europe) (FAT).cue",
+        "Test Game (USA, Europe) (CUE BIN) (Track 01).bin",
+        "Test Game (USA, Europe) (CUE BIN) (Track 02).bin",
+    ] {
+        let romfile_path = tmp_directory.join(file_name);
+        fs::copy(test_directory.join(file_name), &romfile_path)
+            .await
+            .unwrap();
+        let matches = import_roms::subcommand()
+            .get_matches_from(&["import-roms", romfile_path.as_os_str().to_str().unwrap()]);
+        import_roms::main(&mut connection, &matches, &progress_bar)
+            .await
+            .unwrap();
+    }
+
+    let destination_directory = tmp_directory.join("destination");
+
+    // when
+    let matches = subcommand().get_matches_from(&[
+        "export-roms",
+        "-s",
+        "Test System (FAT Multiple Tracks)",
+        "-f",
+        "ORIGINAL",
+        "--fat-safe",
+        "-d",
+        destination_directory.as_os_str().to_str().unwrap(),
+    ]);
+    main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    // then
+    let game_directory = destination_directory.join("Test Game_ Part 1 (USA, Europe)");
+    let mut file_names: Vec<String> = std::fs::read_dir(&game_directory)
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+        .collect();
+    file_names.sort();
+    assert_eq!(
+        file_names,
+        vec![
+            "Test Game_ Part 1 (USA, Europe) (Track 01).bin",
+            "Test Game_ Part 1 (USA, Europe) (Track 02).bin",
+            "Test Game_ Part 1 (USA, Europe).cue",
+        ]
+    );
+
+    let cue = fs::read_to_string(game_directory.join("Test Game_ Part 1 (USA, Europe).cue"))
+        .await
+        .unwrap();
+    let file_names = get_cue_file_names(&cue);
+    assert_eq!(
+        file_names,
+        vec![
+            "Test Game_ Part 1 (USA, Europe) (Track 01).bin",
+            "Test Game_ Part 1 (USA, Europe) (Track 02).bin",
+        ]
+    );
+    for file_name in file_names {
+        assert!(game_directory.join(file_name).is_file());
+    }
+}
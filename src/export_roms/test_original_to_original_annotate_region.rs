@@ -0,0 +1,91 @@
+use super::super::database::*;
+use super::super::import_dats;
+use super::super::import_roms;
+use super::*;
+use std::path::{Path, PathBuf};
+use tempfile::{NamedTempFile, TempDir};
+use tokio::fs;
+
+#[tokio::test]
+async fn test() {
+    // given
+    let _guard = MUTEX.lock().await;
+
+    let test_directory = Path::new("tests");
+    let progress_bar = ProgressBar::hidden();
+
+    let db_file = NamedTempFile::new().unwrap();
+    let pool = establish_connection(db_file.path().to_str().unwrap()).await;
+    let mut connection = pool.acquire().await.unwrap();
+
+    let rom_directory = TempDir::new_in(&test_directory).unwrap();
+    set_rom_directory(PathBuf::from(rom_directory.path()));
+    let tmp_directory = TempDir::new_in(&test_directory).unwrap();
+    let tmp_directory = set_tmp_directory(PathBuf::from(tmp_directory.path()));
+
+    let matches = import_dats::subcommand().get_matches_from(&[
+        "import-dats",
+        "tests/Test System (20200721).dat",
+        "tests/Test System (20230527) (PSN).dat",
+    ]);
+    import_dats::main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    for romfile_name in &[
+        "Test Game (USA, Europe).rom",
+        "UP0001-BLUS00001.pkg",
+        "prfgmHWxGNxsfJ.rap",
+    ] {
+        let romfile_path = tmp_directory.join(romfile_name);
+        fs::copy(test_directory.join(romfile_name), &romfile_path)
+            .await
+            .unwrap();
+        let matches = import_roms::subcommand()
+            .get_matches_from(&["import-roms", romfile_path.as_os_str().to_str().unwrap()]);
+        import_roms::main(&mut connection, &matches, &progress_bar)
+            .await
+            .unwrap();
+    }
+
+    let game_ids: Vec<i64> = find_games(&mut connection)
+        .await
+        .into_iter()
+        .map(|game| game.id)
+        .collect();
+    update_games_sorting(&mut connection, &game_ids, Sorting::OneRegion).await;
+
+    let destination_directory = tmp_directory.join("destination");
+
+    // when
+    let matches = subcommand().get_matches_from(&[
+        "export-roms",
+        "-s",
+        "Test System",
+        "-s",
+        "Test System (PSN)",
+        "-f",
+        "ORIGINAL",
+        "-o",
+        "--annotate-region",
+        "-d",
+        destination_directory.as_os_str().to_str().unwrap(),
+    ]);
+    main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    // then
+    assert!(destination_directory
+        .join("Test Game (USA, Europe).rom")
+        .is_file());
+    assert!(destination_directory
+        .join("Test Game (USA, Europe) (DLC)")
+        .join("UP0001-BLUS00001.pkg")
+        .is_file());
+    assert!(destination_directory
+        .join("Test Game (USA, Europe) (DLC)")
+        .join("prfgmHWxGNxsfJ.rap")
+        .is_file());
+    assert!(!destination_directory.join("UP0001-BLUS00001.pkg").exists());
+}
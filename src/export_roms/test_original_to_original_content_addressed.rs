@@ -0,0 +1,93 @@
+use super::super::database::*;
+use super::super::import_dats;
+use super::super::import_roms;
+use super::super::sort_roms;
+use super::*;
+use std::path::{Path, PathBuf};
+use tempfile::{NamedTempFile, TempDir};
+use tokio::fs;
+
+#[tokio::test]
+async fn test() {
+    // given
+    let _guard = MUTEX.lock().await;
+
+    let test_directory = Path::new("tests");
+    let progress_bar = ProgressBar::hidden();
+
+    let db_file = NamedTempFile::new().unwrap();
+    let pool = establish_connection(db_file.path().to_str().unwrap()).await;
+    let mut connection = pool.acquire().await.unwrap();
+
+    let rom_directory = TempDir::new_in(&test_directory).unwrap();
+    set_rom_directory(PathBuf::from(rom_directory.path()));
+    let tmp_directory = TempDir::new_in(&test_directory).unwrap();
+    let tmp_directory = set_tmp_directory(PathBuf::from(tmp_directory.path()));
+
+    let matches = import_dats::subcommand()
+        .get_matches_from(&["import-dats", "tests/Test System (20200721).dat"]);
+    import_dats::main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    let romfile_name = "Test Game (USA, Europe).rom";
+    let romfile_path = tmp_directory.join(romfile_name);
+    fs::copy(test_directory.join(romfile_name), &romfile_path)
+        .await
+        .unwrap();
+    let matches = import_roms::subcommand()
+        .get_matches_from(&["import-roms", romfile_path.as_os_str().to_str().unwrap()]);
+    import_roms::main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    set_string(&mut connection, "STORAGE_MODE", "content-addressed").await;
+    let matches = sort_roms::subcommand().get_matches_from(&["sort-roms", "-a", "-y"]);
+    sort_roms::main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    let rom = find_roms_with_romfile_by_system_id(
+        &mut connection,
+        find_systems(&mut connection).await.remove(0).id,
+    )
+    .await
+    .remove(0);
+    let romfile = find_romfile_by_id(&mut connection, rom.romfile_id.unwrap()).await;
+    assert_eq!(
+        Path::new(&romfile.path)
+            .file_name()
+            .unwrap()
+            .to_str()
+            .unwrap(),
+        format!("{}.rom", rom.sha1.as_ref().unwrap())
+    );
+
+    let destination_directory = tmp_directory.join("destination");
+    create_directory(&progress_bar, &destination_directory, true)
+        .await
+        .unwrap();
+
+    let matches = subcommand().get_matches_from(&[
+        "export-roms",
+        "-f",
+        "ORIGINAL",
+        "-s",
+        "Test System",
+        "-d",
+        destination_directory.as_os_str().to_str().unwrap(),
+    ]);
+
+    // when
+    main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    // then
+    assert!(destination_directory.join(romfile_name).is_file());
+    assert_eq!(
+        std::fs::read_dir(&destination_directory).unwrap().count(),
+        1
+    );
+    assert!(rom_directory.path().join(&romfile.path).is_file());
+}
@@ -0,0 +1,45 @@
+use super::super::database::*;
+use super::super::import_dats;
+use super::*;
+use std::path::{Path, PathBuf};
+use tempfile::{NamedTempFile, TempDir};
+
+#[tokio::test]
+async fn test() {
+    // given
+    let _guard = MUTEX.lock().await;
+
+    let test_directory = Path::new("tests");
+    let progress_bar = ProgressBar::hidden();
+
+    let db_file = NamedTempFile::new().unwrap();
+    let pool = establish_connection(db_file.path().to_str().unwrap()).await;
+    let mut connection = pool.acquire().await.unwrap();
+
+    let rom_directory = TempDir::new_in(&test_directory).unwrap();
+    set_rom_directory(PathBuf::from(rom_directory.path()));
+    let tmp_directory = TempDir::new_in(&test_directory).unwrap();
+    let tmp_directory = set_tmp_directory(PathBuf::from(tmp_directory.path()));
+
+    let matches = import_dats::subcommand()
+        .get_matches_from(&["import-dats", "tests/Test System (20200721).dat"]);
+    import_dats::main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    let destination_directory = tmp_directory.join("unmounted");
+    set_directory(&mut connection, "EXPORT_DIRECTORY", &destination_directory).await;
+
+    let matches =
+        subcommand().get_matches_from(&["export-roms", "-f", "ORIGINAL", "-s", "Test System"]);
+
+    // when
+    let result = main(&mut connection, &matches, &progress_bar).await;
+
+    // then
+    assert!(result
+        .unwrap_err()
+        .to_string()
+        .contains("doesn't exist or isn't mounted"));
+    assert!(!destination_directory.exists());
+}
@@ -0,0 +1,75 @@
+use super::super::database::*;
+use super::super::import_dats;
+use super::super::import_roms;
+use super::*;
+use std::path::{Path, PathBuf};
+use tempfile::{NamedTempFile, TempDir};
+use tokio::fs;
+
+#[tokio::test]
+async fn test() {
+    // given
+    let _guard = MUTEX.lock().await;
+
+    let test_directory = Path::new("tests");
+    let progress_bar = ProgressBar::hidden();
+
+    let db_file = NamedTempFile::new().unwrap();
+    let pool = establish_connection(db_file.path().to_str().unwrap()).await;
+    let mut connection = pool.acquire().await.unwrap();
+
+    let rom_directory = TempDir::new_in(&test_directory).unwrap();
+    set_rom_directory(PathBuf::from(rom_directory.path()));
+    let tmp_directory = TempDir::new_in(&test_directory).unwrap();
+    let tmp_directory = set_tmp_directory(PathBuf::from(tmp_directory.path()));
+
+    let matches = import_dats::subcommand()
+        .get_matches_from(&["import-dats", "tests/Test System (20250206) (FAT).dat"]);
+    import_dats::main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    for romfile_name in &[
+        "Test Game (USA, Europe).rom",
+        "Test Game (Japan).rom",
+        "Test Game (Asia).rom",
+    ] {
+        let romfile_path = tmp_directory.join(romfile_name);
+        fs::copy(test_directory.join(romfile_name), &romfile_path)
+            .await
+            .unwrap();
+        let matches = import_roms::subcommand()
+            .get_matches_from(&["import-roms", romfile_path.as_os_str().to_str().unwrap()]);
+        import_roms::main(&mut connection, &matches, &progress_bar)
+            .await
+            .unwrap();
+    }
+
+    let destination_directory = tmp_directory.join("destination");
+
+    // when
+    let matches = subcommand().get_matches_from(&[
+        "export-roms",
+        "-s",
+        "Test System (FAT)",
+        "-f",
+        "ORIGINAL",
+        "--fat-safe",
+        "-d",
+        destination_directory.as_os_str().to_str().unwrap(),
+    ]);
+    main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    // then
+    let mut file_names: Vec<String> = std::fs::read_dir(&destination_directory)
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+        .collect();
+    file_names.sort();
+    assert_eq!(file_names.len(), 3);
+    assert_eq!(file_names[0], "Test Game_ Part 1 (USA, Europe) (2).rom");
+    assert_eq!(file_names[1], "Test Game_ Part 1 (USA, Europe) (3).rom");
+    assert_eq!(file_names[2], "Test Game_ Part 1 (USA, Europe).rom");
+}
@@ -0,0 +1,85 @@
+use super::super::database::*;
+use super::super::import_dats;
+use super::super::import_roms;
+use super::super::tag;
+use super::*;
+use std::path::{Path, PathBuf};
+use tempfile::{NamedTempFile, TempDir};
+use tokio::fs;
+
+#[tokio::test]
+async fn test() {
+    // given
+    let _guard = MUTEX.lock().await;
+
+    let test_directory = Path::new("tests");
+    let progress_bar = ProgressBar::hidden();
+
+    let db_file = NamedTempFile::new().unwrap();
+    let pool = establish_connection(db_file.path().to_str().unwrap()).await;
+    let mut connection = pool.acquire().await.unwrap();
+
+    let rom_directory = TempDir::new_in(&test_directory).unwrap();
+    set_rom_directory(PathBuf::from(rom_directory.path()));
+    let tmp_directory = TempDir::new_in(&test_directory).unwrap();
+    let tmp_directory = set_tmp_directory(PathBuf::from(tmp_directory.path()));
+
+    let matches = import_dats::subcommand()
+        .get_matches_from(&["import-dats", "tests/Test System (20200721).dat"]);
+    import_dats::main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    for romfile_name in ["Test Game (Japan).rom", "Test Game (USA, Europe).rom"] {
+        let romfile_path = tmp_directory.join(romfile_name);
+        fs::copy(test_directory.join(romfile_name), &romfile_path)
+            .await
+            .unwrap();
+        let matches = import_roms::subcommand()
+            .get_matches_from(&["import-roms", romfile_path.as_os_str().to_str().unwrap()]);
+        import_roms::main(&mut connection, &matches, &progress_bar)
+            .await
+            .unwrap();
+    }
+
+    let matches = tag::subcommand().get_matches_from(&["tag", "-a", "favorites", "-m", "*USA*"]);
+    tag::main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    let destination_directory = tmp_directory.join("destination");
+    create_directory(&progress_bar, &destination_directory, true)
+        .await
+        .unwrap();
+
+    let matches = subcommand().get_matches_from(&[
+        "export-roms",
+        "-f",
+        "ORIGINAL",
+        "-s",
+        "Test System",
+        "--tag",
+        "favorites",
+        "--group-by-tag",
+        "-d",
+        destination_directory.as_os_str().to_str().unwrap(),
+    ]);
+
+    // when
+    main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    // then
+    assert!(destination_directory
+        .join("favorites")
+        .join("Test Game (USA, Europe).rom")
+        .is_file());
+    assert!(!destination_directory
+        .join("favorites")
+        .join("Test Game (Japan).rom")
+        .exists());
+    assert!(!destination_directory
+        .join("Test Game (USA, Europe).rom")
+        .exists());
+}
@@ -0,0 +1,99 @@
+use super::super::database::*;
+use super::super::import_dats;
+use super::super::import_roms;
+use super::*;
+use std::path::{Path, PathBuf};
+use tempfile::{NamedTempFile, TempDir};
+use tokio::fs;
+
+#[tokio::test]
+async fn test() {
+    // given
+    let _guard = MUTEX.lock().await;
+
+    let test_directory = Path::new("tests");
+    let progress_bar = ProgressBar::hidden();
+
+    let db_file = NamedTempFile::new().unwrap();
+    let pool = establish_connection(db_file.path().to_str().unwrap()).await;
+    let mut connection = pool.acquire().await.unwrap();
+
+    let rom_directory = TempDir::new_in(&test_directory).unwrap();
+    set_rom_directory(PathBuf::from(rom_directory.path()));
+    let tmp_directory = TempDir::new_in(&test_directory).unwrap();
+    let tmp_directory = set_tmp_directory(PathBuf::from(tmp_directory.path()));
+
+    let matches = import_dats::subcommand()
+        .get_matches_from(&["import-dats", "tests/Test System (20200721).dat"]);
+    import_dats::main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    for romfile_name in ["Test Game (Japan).rom", "Test Game (USA, Europe).rom"] {
+        let romfile_path = tmp_directory.join(romfile_name);
+        fs::copy(test_directory.join(romfile_name), &romfile_path)
+            .await
+            .unwrap();
+        let matches = import_roms::subcommand()
+            .get_matches_from(&["import-roms", romfile_path.as_os_str().to_str().unwrap()]);
+        import_roms::main(&mut connection, &matches, &progress_bar)
+            .await
+            .unwrap();
+    }
+
+    let system = find_systems(&mut connection).await.remove(0);
+
+    let destination_directory = tmp_directory.join("destination");
+    create_directory(&progress_bar, &destination_directory, true)
+        .await
+        .unwrap();
+
+    // left over from an earlier export
+    fs::write(
+        destination_directory.join("Test Game (Japan).txt"),
+        b"stale",
+    )
+    .await
+    .unwrap();
+
+    let manifest_path = tmp_directory.join("manifest.dat");
+
+    let matches = subcommand().get_matches_from(&[
+        "export-roms",
+        "-f",
+        "ORIGINAL",
+        "-s",
+        "Test System",
+        "-d",
+        destination_directory.as_os_str().to_str().unwrap(),
+        "--manifest",
+        manifest_path.as_os_str().to_str().unwrap(),
+    ]);
+
+    // when
+    main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    // then
+    assert!(manifest_path.is_file());
+
+    let manifest = fs::read_to_string(&manifest_path).await.unwrap();
+    assert_eq!(manifest.matches("<game ").count(), 2);
+    assert_eq!(manifest.matches("<rom ").count(), 2);
+    assert!(!manifest.contains("Test Game (Japan).txt"));
+
+    let roms = find_roms_with_romfile_by_system_id(&mut connection, system.id).await;
+    assert_eq!(roms.len(), 2);
+    for rom in roms {
+        assert!(destination_directory.join(&rom.name).is_file());
+        assert!(manifest.contains(&format!(
+            "<rom name=\"{}\" size=\"{}\" crc=\"{}\" md5=\"{}\" sha1=\"{}\"/>",
+            rom.name,
+            rom.size,
+            rom.crc.unwrap(),
+            rom.md5.unwrap(),
+            rom.sha1.unwrap()
+        )));
+    }
+}
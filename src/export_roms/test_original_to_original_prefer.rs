@@ -0,0 +1,123 @@
+use super::super::database::*;
+use super::super::import_dats;
+use super::*;
+use std::path::{Path, PathBuf};
+use tempfile::{NamedTempFile, TempDir};
+use tokio::fs;
+
+#[tokio::test]
+async fn test() {
+    // given
+    let _guard = MUTEX.lock().await;
+
+    let test_directory = Path::new("tests");
+    let progress_bar = ProgressBar::hidden();
+
+    let db_file = NamedTempFile::new().unwrap();
+    let pool = establish_connection(db_file.path().to_str().unwrap()).await;
+    let mut connection = pool.acquire().await.unwrap();
+
+    let rom_directory = TempDir::new_in(&test_directory).unwrap();
+    set_rom_directory(PathBuf::from(rom_directory.path()));
+    let tmp_directory = TempDir::new_in(&test_directory).unwrap();
+    let tmp_directory = set_tmp_directory(PathBuf::from(tmp_directory.path()));
+
+    let matches = import_dats::subcommand().get_matches_from(&[
+        "import-dats",
+        "tests/Test System (20250212) (Duplicates).dat",
+    ]);
+    import_dats::main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    let system = find_systems(&mut connection).await.remove(0);
+    let system_directory = get_system_directory(&mut connection, &system)
+        .await
+        .unwrap();
+    let roms = find_roms_with_romfile_by_system_id(&mut connection, system.id).await;
+    assert!(roms.is_empty());
+
+    // link the same content to both games, once as is and once archived
+    for (source_name, romfile_name, game_name) in [
+        (
+            "Test Game (USA, Europe).rom",
+            "Test Game (USA, Europe).rom",
+            "Test Game (USA, Europe)",
+        ),
+        (
+            "Test Game (USA, Europe).rom.zip",
+            "Test Game (USA, Europe) (Alt).zip",
+            "Test Game (USA, Europe) (Alt)",
+        ),
+    ] {
+        let romfile_path = system_directory.join(romfile_name);
+        fs::copy(test_directory.join(source_name), &romfile_path)
+            .await
+            .unwrap();
+        let romfile_id = create_romfile(
+            &mut connection,
+            romfile_path
+                .strip_prefix(&rom_directory)
+                .unwrap()
+                .as_os_str()
+                .to_str()
+                .unwrap(),
+            romfile_path.metadata().unwrap().len(),
+            RomfileType::Romfile,
+        )
+        .await;
+        let game = find_games(&mut connection)
+            .await
+            .into_iter()
+            .find(|game| game.name == game_name)
+            .unwrap();
+        let rom = find_roms_by_game_id_no_parents(&mut connection, game.id)
+            .await
+            .remove(0);
+        update_rom_romfile(&mut connection, rom.id, Some(romfile_id)).await;
+    }
+    compute_system_completion(&mut connection, &progress_bar, &system).await;
+
+    // the archived copy can't be read, only the preferred one is used
+    fs::remove_file(system_directory.join("Test Game (USA, Europe) (Alt).zip"))
+        .await
+        .unwrap();
+
+    let destination_directory = tmp_directory.join("destination");
+    create_directory(&progress_bar, &destination_directory, true)
+        .await
+        .unwrap();
+
+    let matches = subcommand().get_matches_from(&[
+        "export-roms",
+        "-f",
+        "ORIGINAL",
+        "-s",
+        "Test System",
+        "--prefer",
+        "ORIGINAL,ZIP",
+        "-d",
+        destination_directory.as_os_str().to_str().unwrap(),
+    ]);
+
+    // when
+    main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    // then
+    assert!(destination_directory
+        .join("Test Game (USA, Europe).rom")
+        .is_file());
+    assert!(destination_directory
+        .join("Test Game (USA, Europe) (Alt).rom")
+        .is_file());
+    assert_eq!(
+        std::fs::read_dir(&destination_directory).unwrap().count(),
+        2
+    );
+    assert_eq!(
+        std::fs::read(destination_directory.join("Test Game (USA, Europe) (Alt).rom")).unwrap(),
+        std::fs::read(test_directory.join("Test Game (USA, Europe).rom")).unwrap()
+    );
+}
@@ -0,0 +1,115 @@
+use super::super::database::*;
+use super::super::import_dats;
+use super::super::import_roms;
+use super::*;
+use std::path::{Path, PathBuf};
+use tempfile::{NamedTempFile, TempDir};
+use tokio::fs;
+
+#[tokio::test]
+async fn test() {
+    // given
+    let _guard = MUTEX.lock().await;
+
+    let test_directory = Path::new("tests");
+    let progress_bar = ProgressBar::hidden();
+
+    let db_file = NamedTempFile::new().unwrap();
+    let pool = establish_connection(db_file.path().to_str().unwrap()).await;
+    let mut connection = pool.acquire().await.unwrap();
+
+    let rom_directory = TempDir::new_in(&test_directory).unwrap();
+    set_rom_directory(PathBuf::from(rom_directory.path()));
+    let tmp_directory = TempDir::new_in(&test_directory).unwrap();
+    let tmp_directory = set_tmp_directory(PathBuf::from(tmp_directory.path()));
+
+    let matches = import_dats::subcommand()
+        .get_matches_from(&["import-dats", "tests/Test System (20200721).dat"]);
+    import_dats::main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    for file_name in [
+        "Test Game (USA, Europe).rom",
+        "Test Game (USA, Europe) (Beta).rom",
+        "Test Game (Japan).rom",
+    ] {
+        let romfile_path = tmp_directory.join(file_name);
+        fs::copy(test_directory.join(file_name), &romfile_path)
+            .await
+            .unwrap();
+        let matches = import_roms::subcommand()
+            .get_matches_from(&["import-roms", romfile_path.as_os_str().to_str().unwrap()]);
+        import_roms::main(&mut connection, &matches, &progress_bar)
+            .await
+            .unwrap();
+    }
+
+    let destination_directory = tmp_directory.join("destination");
+    create_directory(&progress_bar, &destination_directory, true)
+        .await
+        .unwrap();
+
+    // when
+    let matches = subcommand().get_matches_from(&[
+        "export-roms",
+        "-f",
+        "7Z",
+        "-s",
+        "Test System",
+        "--group-archives-by",
+        "region",
+        "-d",
+        destination_directory.as_os_str().to_str().unwrap(),
+    ]);
+    main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    // then
+    let paths: Vec<PathBuf> = std::fs::read_dir(&destination_directory)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .collect();
+    assert_eq!(paths.len(), 2);
+
+    let system = find_systems(&mut connection).await.remove(0);
+    for game in find_complete_games_by_system_id(&mut connection, system.id).await {
+        let path = destination_directory.join(format!("{}.7z", game.regions));
+        assert!(paths.contains(&path));
+        let names: Vec<String> = CommonRomfile::from_path(&path)
+            .unwrap()
+            .parse_archive(&progress_bar, None)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|(name, _, _)| name)
+            .collect();
+        // members are stored in a directory named after their game
+        assert!(names.contains(
+            &Path::new(&game.name)
+                .join(format!("{}.rom", game.name))
+                .as_os_str()
+                .to_str()
+                .unwrap()
+                .to_string()
+        ));
+    }
+
+    let us_eu_path = destination_directory.join(format!(
+        "{}.7z",
+        find_games_by_name_and_system_id(&mut connection, "Test Game (USA, Europe)", system.id)
+            .await
+            .remove(0)
+            .regions
+    ));
+    assert_eq!(
+        CommonRomfile::from_path(&us_eu_path)
+            .unwrap()
+            .parse_archive(&progress_bar, None)
+            .await
+            .unwrap()
+            .len(),
+        2
+    );
+}
@@ -72,6 +72,7 @@ async fn test() {
         sevenzip::ArchiveType::Zip,
         &None,
         false,
+        ArchiveGrouping::Game,
     )
     .await
     .unwrap();
@@ -0,0 +1,104 @@
+use super::super::database::*;
+use super::super::import_dats;
+use super::super::import_roms;
+use super::*;
+use std::path::{Path, PathBuf};
+use tempfile::{NamedTempFile, TempDir};
+use tokio::fs;
+
+#[tokio::test]
+async fn test() {
+    // given
+    let _guard = MUTEX.lock().await;
+
+    let test_directory = Path::new("tests");
+    let progress_bar = ProgressBar::hidden();
+
+    let db_file = NamedTempFile::new().unwrap();
+    let pool = establish_connection(db_file.path().to_str().unwrap()).await;
+    let mut connection = pool.acquire().await.unwrap();
+
+    let rom_directory = TempDir::new_in(&test_directory).unwrap();
+    set_rom_directory(PathBuf::from(rom_directory.path()));
+    let tmp_directory = TempDir::new_in(&test_directory).unwrap();
+    let tmp_directory = set_tmp_directory(PathBuf::from(tmp_directory.path()));
+
+    let matches = import_dats::subcommand()
+        .get_matches_from(&["import-dats", "tests/Test System (20200721).dat"]);
+    import_dats::main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    for file_name in [
+        "Test Game (USA, Europe).rom",
+        "Test Game (USA, Europe) (Beta).rom",
+        "Test Game (Japan).rom",
+    ] {
+        let romfile_path = tmp_directory.join(file_name);
+        fs::copy(test_directory.join(file_name), &romfile_path)
+            .await
+            .unwrap();
+        let matches = import_roms::subcommand()
+            .get_matches_from(&["import-roms", romfile_path.as_os_str().to_str().unwrap()]);
+        import_roms::main(&mut connection, &matches, &progress_bar)
+            .await
+            .unwrap();
+    }
+
+    let destination_directory = tmp_directory.join("destination");
+    create_directory(&progress_bar, &destination_directory, true)
+        .await
+        .unwrap();
+
+    // when
+    let matches = subcommand().get_matches_from(&[
+        "export-roms",
+        "-f",
+        "ZIP",
+        "-s",
+        "Test System",
+        "--group-archives-by",
+        "system",
+        "-d",
+        destination_directory.as_os_str().to_str().unwrap(),
+    ]);
+    main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    // then
+    let mut paths: Vec<PathBuf> = std::fs::read_dir(&destination_directory)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .collect();
+    assert_eq!(paths.len(), 1);
+
+    let path = paths.remove(0);
+    assert_eq!(path, destination_directory.join("Test System.zip"));
+
+    let mut names: Vec<String> = CommonRomfile::from_path(&path)
+        .unwrap()
+        .parse_archive(&progress_bar, None)
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|(name, _, _)| name)
+        .collect();
+    names.sort();
+    assert_eq!(
+        names,
+        [
+            "Test Game (Japan)",
+            "Test Game (USA, Europe) (Beta)",
+            "Test Game (USA, Europe)",
+        ]
+        .iter()
+        .map(|name| Path::new(name)
+            .join(format!("{}.rom", name))
+            .as_os_str()
+            .to_str()
+            .unwrap()
+            .to_string())
+        .collect::<Vec<String>>()
+    );
+}
@@ -0,0 +1,97 @@
+use super::super::database::*;
+use super::super::import_dats;
+use super::super::import_roms;
+use super::*;
+use std::path::{Path, PathBuf};
+use tempfile::{NamedTempFile, TempDir};
+use tokio::fs;
+
+#[tokio::test]
+async fn test() {
+    // given
+    let _guard = MUTEX.lock().await;
+
+    let test_directory = Path::new("tests");
+    let progress_bar = ProgressBar::hidden();
+
+    let db_file = NamedTempFile::new().unwrap();
+    let pool = establish_connection(db_file.path().to_str().unwrap()).await;
+    let mut connection = pool.acquire().await.unwrap();
+
+    let rom_directory = TempDir::new_in(&test_directory).unwrap();
+    set_rom_directory(PathBuf::from(rom_directory.path()));
+    let tmp_directory = TempDir::new_in(&test_directory).unwrap();
+    let tmp_directory = set_tmp_directory(PathBuf::from(tmp_directory.path()));
+
+    let matches = import_dats::subcommand().get_matches_from(&[
+        "import-dats",
+        "tests/Test System (20250310) (MAME Shared Names).dat",
+    ]);
+    import_dats::main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    for file_name in ["Test Game (USA, Europe).rom", "Test Game (Japan).rom"] {
+        let romfile_path = tmp_directory.join(file_name);
+        fs::copy(test_directory.join(file_name), &romfile_path)
+            .await
+            .unwrap();
+        let matches = import_roms::subcommand()
+            .get_matches_from(&["import-roms", romfile_path.as_os_str().to_str().unwrap()]);
+        import_roms::main(&mut connection, &matches, &progress_bar)
+            .await
+            .unwrap();
+    }
+
+    let destination_directory = tmp_directory.join("destination");
+    create_directory(&progress_bar, &destination_directory, true)
+        .await
+        .unwrap();
+
+    // when
+    let matches = subcommand().get_matches_from(&[
+        "export-roms",
+        "-f",
+        "ZIP",
+        "-s",
+        "Test System (MAME Shared Names)",
+        "--group-archives-by",
+        "system",
+        "-d",
+        destination_directory.as_os_str().to_str().unwrap(),
+    ]);
+    main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    // then
+    let path = destination_directory.join("Test System (MAME Shared Names).zip");
+    assert!(path.is_file());
+
+    // same-named ROMs from different games don't overwrite each other
+    let mut archive_romfiles = CommonRomfile::from_path(&path)
+        .unwrap()
+        .as_archive(&progress_bar, None)
+        .await
+        .unwrap();
+    archive_romfiles.sort_by(|a, b| a.path.cmp(&b.path));
+    assert_eq!(archive_romfiles.len(), 2);
+    assert_eq!(
+        archive_romfiles[0].path,
+        Path::new("Test Game (Japan)")
+            .join("Test Game.rom")
+            .as_os_str()
+            .to_str()
+            .unwrap()
+    );
+    assert_eq!(archive_romfiles[0].crc, "310212e8");
+    assert_eq!(
+        archive_romfiles[1].path,
+        Path::new("Test Game (USA, Europe)")
+            .join("Test Game.rom")
+            .as_os_str()
+            .to_str()
+            .unwrap()
+    );
+    assert_eq!(archive_romfiles[1].crc, "cc721e14");
+}
@@ -80,6 +80,7 @@ async fn test() {
         sevenzip::ArchiveType::Zip,
         &None,
         false,
+        ArchiveGrouping::Game,
     )
     .await
     .unwrap();
@@ -72,6 +72,7 @@ async fn test() {
         sevenzip::ArchiveType::Sevenzip,
         &None,
         false,
+        ArchiveGrouping::Game,
     )
     .await
     .unwrap();
@@ -0,0 +1,99 @@
+use super::super::database::*;
+use super::super::import_dats;
+use super::super::import_roms;
+use super::*;
+use std::path::{Path, PathBuf};
+use tempfile::{NamedTempFile, TempDir};
+use tokio::fs;
+
+#[tokio::test]
+async fn test() {
+    // given
+    let _guard = MUTEX.lock().await;
+
+    let test_directory = Path::new("tests");
+    let progress_bar = ProgressBar::hidden();
+
+    let db_file = NamedTempFile::new().unwrap();
+    let pool = establish_connection(db_file.path().to_str().unwrap()).await;
+    let mut connection = pool.acquire().await.unwrap();
+
+    let rom_directory = TempDir::new_in(&test_directory).unwrap();
+    set_rom_directory(PathBuf::from(rom_directory.path()));
+    let tmp_directory = TempDir::new_in(&test_directory).unwrap();
+    let tmp_directory = set_tmp_directory(PathBuf::from(tmp_directory.path()));
+
+    set_string(
+        &mut connection,
+        "CUE_BIN_NAME_TEMPLATE",
+        "{game} [{system}]",
+    )
+    .await;
+
+    let matches = import_dats::subcommand()
+        .get_matches_from(&["import-dats", "tests/Test System (20250302) (Cue-less).dat"]);
+    import_dats::main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    let chd_romfile_path = tmp_directory.join("Test Game (USA, Europe) (Single Track).chd");
+    fs::copy(
+        test_directory.join("Test Game (USA, Europe) (Single Track).chd"),
+        &chd_romfile_path,
+    )
+    .await
+    .unwrap();
+
+    let system = find_systems(&mut connection).await.remove(0);
+
+    let matches = import_roms::subcommand().get_matches_from(&[
+        "import-roms",
+        chd_romfile_path.as_os_str().to_str().unwrap(),
+    ]);
+    import_roms::main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    let mut roms_by_game_id: IndexMap<i64, Vec<Rom>> = IndexMap::new();
+    let mut romfiles_by_id: HashMap<i64, Romfile> = HashMap::new();
+    let games = find_complete_games_by_system_id(&mut connection, system.id).await;
+    let games_by_id: HashMap<i64, Game> = games.into_iter().map(|game| (game.id, game)).collect();
+    let roms = find_roms_with_romfile_by_system_id(&mut connection, system.id).await;
+    for rom in &roms {
+        let romfile = find_romfile_by_id(&mut connection, rom.romfile_id.unwrap()).await;
+        romfiles_by_id.insert(romfile.id, romfile);
+    }
+    roms_by_game_id.insert(roms[0].game_id, roms);
+
+    let destination_directory = tmp_directory.join("destination");
+    create_directory(&progress_bar, &destination_directory, true)
+        .await
+        .unwrap();
+
+    // when
+    to_original(
+        &mut connection,
+        &progress_bar,
+        &destination_directory,
+        &system,
+        games_by_id,
+        roms_by_game_id,
+        romfiles_by_id,
+        false,
+    )
+    .await
+    .unwrap();
+
+    // then
+    assert!(destination_directory
+        .join("Test Game (USA, Europe) (CUE BIN) (Track 01).bin")
+        .is_file());
+    let cue_path =
+        destination_directory.join("Test Game (USA, Europe) (CUE BIN) [Test System].cue");
+    assert!(cue_path.is_file());
+    let cue = fs::read_to_string(&cue_path).await.unwrap();
+    assert!(cue.contains("FILE \"Test Game (USA, Europe) (CUE BIN) (Track 01).bin\" BINARY"));
+    assert!(!destination_directory
+        .join("Test Game (USA, Europe) (CUE BIN).cue")
+        .exists());
+}
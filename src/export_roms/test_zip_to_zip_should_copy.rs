@@ -71,6 +71,7 @@ async fn test() {
         sevenzip::ArchiveType::Zip,
         &None,
         false,
+        ArchiveGrouping::Game,
     )
     .await
     .unwrap();
@@ -77,6 +77,7 @@ async fn test() {
         games_by_id,
         roms_by_game_id,
         romfiles_by_id,
+        false,
     )
     .await
     .unwrap();
@@ -0,0 +1,149 @@
+use super::common::*;
+use super::config::*;
+use super::database::*;
+use super::mimetype::*;
+use super::model::*;
+use super::prompt::*;
+use super::util::*;
+use super::SimpleResult;
+use clap::{Arg, ArgAction, ArgMatches, Command};
+use indicatif::ProgressBar;
+use sqlx::sqlite::SqliteConnection;
+use std::collections::HashMap;
+use std::path::Path;
+
+// extensions the conversion and export logic relies on to tell formats apart
+static FORMAT_EXTENSIONS: [&str; 7] = [
+    CHD_EXTENSION,
+    CIA_EXTENSION,
+    CSO_EXTENSION,
+    RVZ_EXTENSION,
+    SEVENZIP_EXTENSION,
+    ZIP_EXTENSION,
+    ZSO_EXTENSION,
+];
+
+pub fn subcommand() -> Command {
+    Command::new("fix-roms")
+        .about("Fix ROM files that don't look like what they are")
+        .arg(
+            Arg::new("FIX_EXTENSIONS")
+                .long("fix-extensions")
+                .help("Rename ROM files whose extension doesn't match their content")
+                .required(true)
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("ALL")
+                .short('a')
+                .long("all")
+                .help("Fix all systems")
+                .required(false)
+                .action(ArgAction::SetTrue),
+        )
+}
+
+pub async fn main(
+    connection: &mut SqliteConnection,
+    matches: &ArgMatches,
+    progress_bar: &ProgressBar,
+) -> SimpleResult<()> {
+    let systems = prompt_for_systems(connection, None, false, matches.get_flag("ALL")).await?;
+    for system in systems {
+        progress_bar.println(format!("Processing \"{}\"", system.name));
+        if matches.get_flag("FIX_EXTENSIONS") {
+            fix_extensions(connection, progress_bar, &system).await?;
+        }
+        progress_bar.println("");
+    }
+    Ok(())
+}
+
+async fn fix_extensions(
+    connection: &mut SqliteConnection,
+    progress_bar: &ProgressBar,
+    system: &System,
+) -> SimpleResult<()> {
+    let mut roms_by_romfile_id: HashMap<i64, Vec<Rom>> = HashMap::new();
+    for rom in find_roms_with_romfile_by_system_id(connection, system.id).await {
+        roms_by_romfile_id
+            .entry(rom.romfile_id.unwrap())
+            .or_default()
+            .push(rom);
+    }
+
+    let mut transaction = begin_transaction(connection).await;
+    let mut count = 0;
+
+    for romfile in find_romfiles_by_system_id(&mut transaction, system.id).await {
+        let common_romfile = romfile.as_common(&mut transaction).await?;
+        if !common_romfile.path.is_file() {
+            continue;
+        }
+        let extension = common_romfile
+            .path
+            .extension()
+            .map(|extension| extension.to_str().unwrap().to_lowercase())
+            .unwrap_or_default();
+        let detected_extension = get_mimetype(&common_romfile.path)
+            .await?
+            .map(|mimetype| mimetype.extension().to_string())
+            .filter(|extension| FORMAT_EXTENSIONS.contains(&extension.as_str()));
+        let new_extension = match detected_extension {
+            Some(detected_extension) => detected_extension,
+            // not a container after all, fall back to the extension of the single ROM it holds
+            None if FORMAT_EXTENSIONS.contains(&extension.as_str()) => {
+                match roms_by_romfile_id.get(&romfile.id).map(Vec::as_slice) {
+                    Some([rom]) => match Path::new(&rom.name).extension() {
+                        Some(rom_extension) => rom_extension.to_str().unwrap().to_lowercase(),
+                        None => continue,
+                    },
+                    _ => {
+                        progress_bar.println(format!(
+                            "\"{}\" is not a {} file",
+                            romfile.path,
+                            extension.to_uppercase()
+                        ));
+                        continue;
+                    }
+                }
+            }
+            None => continue,
+        };
+        if new_extension == extension {
+            continue;
+        }
+        let new_path = common_romfile.path.with_extension(&new_extension);
+        if new_path.exists() {
+            progress_bar.println(format!(
+                "\"{}\" already exists, skipping",
+                new_path.as_os_str().to_str().unwrap()
+            ));
+            continue;
+        }
+        progress_bar.println(format!(
+            "\"{}\" is a {} file",
+            romfile.path,
+            new_extension.to_uppercase()
+        ));
+        common_romfile
+            .rename(progress_bar, &new_path, false)
+            .await?
+            .update(&mut transaction, progress_bar, romfile.id)
+            .await?;
+        count += 1;
+    }
+
+    commit_transaction(transaction).await;
+
+    if count > 0 {
+        progress_bar.println(format!("Fixed {} ROM file extension(s)", count));
+    } else {
+        progress_bar.println("All ROM file extensions are correct");
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test_fix_extensions_chd;
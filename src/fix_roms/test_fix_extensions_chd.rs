@@ -0,0 +1,81 @@
+use super::super::import_dats;
+use super::super::import_roms;
+use super::*;
+use std::path::PathBuf;
+use tempfile::{NamedTempFile, TempDir};
+use tokio::fs;
+
+#[tokio::test]
+async fn test() {
+    // given
+    let _guard = MUTEX.lock().await;
+
+    let test_directory = Path::new("tests");
+    let progress_bar = ProgressBar::hidden();
+
+    let db_file = NamedTempFile::new().unwrap();
+    let pool = establish_connection(db_file.path().to_str().unwrap()).await;
+    let mut connection = pool.acquire().await.unwrap();
+
+    let rom_directory = TempDir::new_in(&test_directory).unwrap();
+    let rom_directory = set_rom_directory(PathBuf::from(rom_directory.path()));
+    let tmp_directory = TempDir::new_in(&test_directory).unwrap();
+    let tmp_directory = set_tmp_directory(PathBuf::from(tmp_directory.path()));
+
+    let matches = import_dats::subcommand()
+        .get_matches_from(&["import-dats", "tests/Test System (20200721).dat"]);
+    import_dats::main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    let romfile_path = tmp_directory.join("Test Game (USA, Europe).iso");
+    fs::copy(
+        test_directory.join("Test Game (USA, Europe).iso"),
+        &romfile_path,
+    )
+    .await
+    .unwrap();
+    let matches = import_roms::subcommand()
+        .get_matches_from(&["import-roms", romfile_path.as_os_str().to_str().unwrap()]);
+    import_roms::main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    // a CHD that was renamed to ISO behind oxyromon's back
+    let romfile = find_romfiles(&mut connection).await.remove(0);
+    let iso_path = rom_directory.join(&romfile.path);
+    fs::copy(
+        test_directory.join("Test Game (USA, Europe) (ISO).chd"),
+        &iso_path,
+    )
+    .await
+    .unwrap();
+
+    // when
+    let matches = subcommand().get_matches_from(&["fix-roms", "--fix-extensions", "-a"]);
+    main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    // then
+    let romfiles = find_romfiles(&mut connection).await;
+    assert_eq!(romfiles.len(), 1);
+    let romfile = romfiles.first().unwrap();
+    assert_eq!(
+        romfile.path,
+        Path::new(&romfile.path)
+            .with_file_name("Test Game (USA, Europe) (ISO).chd")
+            .as_os_str()
+            .to_str()
+            .unwrap()
+    );
+    assert!(rom_directory.join(&romfile.path).is_file());
+    assert!(!iso_path.exists());
+    assert_eq!(
+        romfile.size,
+        fs::metadata(test_directory.join("Test Game (USA, Europe) (ISO).chd"))
+            .await
+            .unwrap()
+            .len() as i64
+    );
+}
@@ -1,5 +1,6 @@
 use super::common::*;
 use super::progress::*;
+use super::util::*;
 use super::SimpleResult;
 use indicatif::ProgressBar;
 use std::path::Path;
@@ -50,7 +51,7 @@ impl PatchFile for XpsRomfile {
             .arg(&self.romfile.path)
             .arg(&romfile.path)
             .arg(&path)
-            .output()
+            .output_with_retries()
             .await
             .unwrap_or_else(|_| {
                 panic!(
@@ -97,7 +98,7 @@ impl AsXps for CommonRomfile {
 
 pub async fn get_version() -> SimpleResult<String> {
     let output = try_with!(
-        Command::new(FLIPS).arg("-v").output().await,
+        Command::new(FLIPS).arg("-v").output_with_retries().await,
         "Failed to spawn flips"
     );
 
@@ -10,15 +10,18 @@ use super::SimpleResult;
 use clap::{Arg, ArgAction, ArgMatches, Command};
 use indicatif::ProgressBar;
 use regex::Regex;
+use shiratsu_naming::region::Region;
 use sqlx::sqlite::SqliteConnection;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::path::PathBuf;
+use std::slice;
 use tokio::fs::File;
 use tokio::io::AsyncWriteExt;
 use tokio::io::BufWriter;
 
 lazy_static! {
-    pub static ref DISC_REGEX: Regex = Regex::new(r" \(Disc \d+\)").unwrap();
+    pub static ref DISC_REGEX: Regex = Regex::new(r" \(Dis[ck] (\d+|[A-Z])( of \d+)?\)").unwrap();
+    static ref REGION_REGEX: Regex = Regex::new(r"^(.+?) \([^)]+\)(.*)$").unwrap();
 }
 
 pub fn subcommand() -> Command {
@@ -32,6 +35,13 @@ pub fn subcommand() -> Command {
                 .required(false)
                 .action(ArgAction::SetTrue),
         )
+        .arg(
+            Arg::new("PER_REGION")
+                .long("per-region")
+                .help("Generate one playlist per region of multi-region games")
+                .required(false)
+                .action(ArgAction::SetTrue),
+        )
 }
 
 pub async fn main(
@@ -48,27 +58,193 @@ pub async fn main(
     .await?;
     for system in systems {
         progress_bar.println(format!("Processing \"{}\"", system.name));
-        process_system(connection, progress_bar, &system).await?;
+        process_system(
+            connection,
+            progress_bar,
+            &system,
+            matches.get_flag("PER_REGION"),
+        )
+        .await?;
         progress_bar.println("");
     }
     Ok(())
 }
 
+pub async fn add_sibling_discs(
+    connection: &mut SqliteConnection,
+    mut games: Vec<Game>,
+    system_id: i64,
+) -> Vec<Game> {
+    let playlist_names: HashSet<String> = games
+        .iter()
+        .filter(|game| DISC_REGEX.is_match(&game.name))
+        .map(|game| DISC_REGEX.replace(&game.name, "").to_string())
+        .collect();
+    if playlist_names.is_empty() {
+        return games;
+    }
+    let game_ids: HashSet<i64> = games.iter().map(|game| game.id).collect();
+    games.extend(
+        find_complete_games_by_system_id(connection, system_id)
+            .await
+            .into_iter()
+            .filter(|game| {
+                !game_ids.contains(&game.id)
+                    && DISC_REGEX.is_match(&game.name)
+                    && playlist_names.contains(DISC_REGEX.replace(&game.name, "").as_ref())
+            }),
+    );
+    games.sort_by(|a, b| a.name.cmp(&b.name));
+    games
+}
+
+pub fn get_disc_archive_name(game_name: &str, per_game: bool) -> String {
+    // discs of the same game share an archive named after their playlist
+    if per_game && DISC_REGEX.is_match(game_name) {
+        DISC_REGEX.replace(game_name, "").to_string()
+    } else {
+        game_name.to_string()
+    }
+}
+
+fn get_playlist_names(game: &Game, per_region: bool) -> Vec<String> {
+    let name = DISC_REGEX.replace(&game.name, "");
+    if per_region {
+        // discs shared by several regions belong to each region's playlist
+        let regions = Region::try_from_tosec_region(&game.regions).unwrap_or_default();
+        if let Some(captures) = REGION_REGEX.captures(&name) {
+            if !regions.is_empty() {
+                return regions
+                    .iter()
+                    .map(|region| {
+                        format!(
+                            "{} ({}){}.{}",
+                            &captures[1],
+                            Region::to_normalized_region_string(slice::from_ref(region)),
+                            &captures[2],
+                            M3U_EXTENSION
+                        )
+                    })
+                    .collect();
+            }
+        }
+    }
+    vec![format!("{}.{}", name, M3U_EXTENSION)]
+}
+
+fn get_disc_sorting_key(game_name: &str) -> (usize, String) {
+    match DISC_REGEX.captures(game_name) {
+        Some(captures) => (captures[1].len(), captures[1].to_string()),
+        None => (0, String::new()),
+    }
+}
+
+pub async fn update_playlists(
+    connection: &mut SqliteConnection,
+    progress_bar: &ProgressBar,
+    system: &System,
+    game_ids: &[i64],
+) -> SimpleResult<()> {
+    let disc_games: Vec<Game> = find_games_by_system_id(connection, system.id)
+        .await
+        .into_iter()
+        .filter(|game| DISC_REGEX.is_match(&game.name))
+        .collect();
+    let playlist_names: HashSet<String> = disc_games
+        .iter()
+        .filter(|game| game_ids.contains(&game.id))
+        .map(|game| DISC_REGEX.replace(&game.name, "").to_string())
+        .collect();
+
+    // only regenerate existing playlists, in the flavor they were generated in
+    let mut games: Vec<Game> = vec![];
+    let mut per_region_games: Vec<Game> = vec![];
+    for game in disc_games {
+        if !playlist_names.contains(DISC_REGEX.replace(&game.name, "").as_ref()) {
+            continue;
+        }
+        if let Some(playlist_id) = game.playlist_id {
+            let playlist = find_romfile_by_id(connection, playlist_id).await;
+            let playlist_name = PathBuf::from(&playlist.path)
+                .file_name()
+                .unwrap()
+                .to_str()
+                .unwrap()
+                .to_string();
+            if get_playlist_names(&game, false).contains(&playlist_name) {
+                games.push(game);
+            } else {
+                per_region_games.push(game);
+            }
+        }
+    }
+
+    process_games(connection, progress_bar, &games, false).await?;
+    process_games(connection, progress_bar, &per_region_games, true).await?;
+    Ok(())
+}
+
 async fn process_system(
     connection: &mut SqliteConnection,
     progress_bar: &ProgressBar,
     system: &System,
+    per_region: bool,
 ) -> SimpleResult<()> {
-    let mut grouped_games: HashMap<String, Vec<Game>> = HashMap::new();
-    find_games_by_system_id(connection, system.id)
+    let disc_games: Vec<Game> = find_games_by_system_id(connection, system.id)
         .await
         .into_iter()
         .filter(|game| DISC_REGEX.is_match(&game.name))
-        .for_each(|game| {
-            let playlist_name = format!("{}.{}", DISC_REGEX.replace(&game.name, ""), M3U_EXTENSION);
+        .collect();
+    process_games(connection, progress_bar, &disc_games, per_region).await
+}
+
+async fn process_games(
+    connection: &mut SqliteConnection,
+    progress_bar: &ProgressBar,
+    disc_games: &[Game],
+    per_region: bool,
+) -> SimpleResult<()> {
+    let mut grouped_games: HashMap<String, Vec<&Game>> = HashMap::new();
+    disc_games.iter().for_each(|game| {
+        for playlist_name in get_playlist_names(game, per_region) {
             let group = grouped_games.entry(playlist_name).or_default();
             group.push(game);
-        });
+        }
+    });
+
+    // games keep a single playlist, one made only of shared discs would end up orphaned
+    if per_region {
+        let shared_playlist_names: Vec<String> = grouped_games
+            .iter()
+            .filter(|(_, games)| {
+                games
+                    .iter()
+                    .all(|game| get_playlist_names(game, true).len() > 1)
+            })
+            .map(|(playlist_name, _)| playlist_name.clone())
+            .collect();
+        let mut shared_games: Vec<&Game> = vec![];
+        for playlist_name in shared_playlist_names {
+            progress_bar.println(format!(
+                "Skipping \"{}\", all its discs are shared with other regions",
+                &playlist_name
+            ));
+            shared_games.extend(grouped_games.remove(&playlist_name).unwrap());
+        }
+        shared_games.sort_by_key(|game| game.id);
+        shared_games.dedup_by_key(|game| game.id);
+        for game in shared_games {
+            if !grouped_games
+                .values()
+                .any(|games| games.iter().any(|other| other.id == game.id))
+            {
+                let group = grouped_games
+                    .entry(get_playlist_names(game, false).remove(0))
+                    .or_default();
+                group.push(game);
+            }
+        }
+    }
 
     for (playlist_name, games) in grouped_games.into_iter() {
         if games
@@ -104,6 +280,8 @@ async fn process_system(
             CUE_EXTENSION,
             ISO_EXTENSION,
             RVZ_EXTENSION,
+            SEVENZIP_EXTENSION,
+            ZIP_EXTENSION,
         ] {
             existing_romfiles = romfiles
                 .iter()
@@ -118,6 +296,17 @@ async fn process_system(
             continue;
         }
 
+        // discs of different regions don't sort by path
+        if per_region {
+            existing_romfiles.sort_by_key(|romfile| {
+                roms.iter()
+                    .find(|rom| rom.romfile_id == Some(romfile.id))
+                    .and_then(|rom| games.iter().find(|game| game.id == rom.game_id))
+                    .map(|game| get_disc_sorting_key(&game.name))
+                    .unwrap_or_default()
+            });
+        }
+
         let mut playlist_path = existing_romfiles
             .first()
             .unwrap()
@@ -133,20 +322,41 @@ async fn process_system(
         progress_bar.println(format!("Creating \"{}\"", &playlist_name));
 
         for romfile in existing_romfiles {
-            writer
-                .write_all(
-                    format!(
-                        "{}\n",
-                        PathBuf::from(&romfile.path)
-                            .file_name()
-                            .unwrap()
-                            .to_str()
-                            .unwrap()
-                    )
-                    .as_bytes(),
-                )
-                .await
-                .expect("Failed to write to M3U file");
+            let file_name = PathBuf::from(&romfile.path)
+                .file_name()
+                .unwrap()
+                .to_str()
+                .unwrap()
+                .to_string();
+            // discs sharing an archive are addressed inside it
+            let mut disc_roms: Vec<&Rom> = vec![];
+            for game in &games {
+                let game_roms: Vec<&Rom> = roms
+                    .iter()
+                    .filter(|rom| rom.game_id == game.id && rom.romfile_id == Some(romfile.id))
+                    .collect();
+                if let Some(rom) = game_roms
+                    .iter()
+                    .find(|rom| rom.name.ends_with(CUE_EXTENSION))
+                    .or(game_roms.first())
+                {
+                    disc_roms.push(*rom);
+                }
+            }
+            let lines: Vec<String> = if disc_roms.len() > 1 {
+                disc_roms
+                    .iter()
+                    .map(|rom| format!("{}#{}", file_name, rom.name))
+                    .collect()
+            } else {
+                vec![file_name]
+            };
+            for line in lines {
+                writer
+                    .write_all(format!("{}\n", line).as_bytes())
+                    .await
+                    .expect("Failed to write to M3U file");
+            }
         }
         writer.flush().await.expect("Failed to write to M3U file");
 
@@ -178,8 +388,20 @@ async fn process_system(
     Ok(())
 }
 
+#[cfg(test)]
+mod test_gamecube_iso_complete;
+
+#[cfg(test)]
+mod test_gamecube_sibling_discs;
+
 #[cfg(test)]
 mod test_iso_complete;
 
+#[cfg(test)]
+mod test_iso_complete_per_region;
+
+#[cfg(test)]
+mod test_iso_complete_per_region_shared;
+
 #[cfg(test)]
 mod test_iso_incomplete;
@@ -0,0 +1,81 @@
+use super::super::database::*;
+use super::super::import_dats;
+use super::super::import_roms;
+use super::*;
+use std::env;
+use std::path::Path;
+use tempfile::{NamedTempFile, TempDir};
+use tokio::fs;
+
+#[tokio::test]
+async fn test() {
+    // given
+    let _guard = MUTEX.lock().await;
+
+    let test_directory = Path::new("tests");
+    env::set_var(
+        "PATH",
+        format!(
+            "{}:{}",
+            test_directory.as_os_str().to_str().unwrap(),
+            env::var("PATH").unwrap()
+        ),
+    );
+    let progress_bar = ProgressBar::hidden();
+
+    let db_file = NamedTempFile::new().unwrap();
+    let pool = establish_connection(db_file.path().to_str().unwrap()).await;
+    let mut connection = pool.acquire().await.unwrap();
+
+    let rom_directory = TempDir::new_in(&test_directory).unwrap();
+    set_rom_directory(PathBuf::from(rom_directory.path()));
+    let tmp_directory = TempDir::new_in(&test_directory).unwrap();
+    let tmp_directory = set_tmp_directory(PathBuf::from(tmp_directory.path()));
+
+    let matches = import_dats::subcommand().get_matches_from(&[
+        "import-dats",
+        "tests/Test System (20250203) (GameCube Multiple Discs).dat",
+    ]);
+    import_dats::main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    let system = find_systems(&mut connection).await.remove(0);
+
+    for i in 1..=2 {
+        let romfile_path = tmp_directory.join(format!("Test Game (USA) (Disc {}) (Rev 1).iso", i));
+        fs::copy(
+            test_directory.join(format!("Test Game (USA, Europe) (Disc {}).iso", i)),
+            &romfile_path,
+        )
+        .await
+        .unwrap();
+        let matches = import_roms::subcommand()
+            .get_matches_from(&["import-roms", romfile_path.as_os_str().to_str().unwrap()]);
+        import_roms::main(&mut connection, &matches, &progress_bar)
+            .await
+            .unwrap();
+    }
+
+    let games = find_complete_games_by_name_and_system_id(
+        &mut connection,
+        "Test Game (USA) (Disc 1) (Rev 1)",
+        system.id,
+    )
+    .await;
+    assert_eq!(games.len(), 1);
+
+    // when
+    let games = add_sibling_discs(&mut connection, games, system.id).await;
+
+    // then
+    assert_eq!(games.len(), 2);
+    assert_eq!(
+        games.first().unwrap().name,
+        "Test Game (USA) (Disc 1) (Rev 1)"
+    );
+    assert_eq!(
+        games.get(1).unwrap().name,
+        "Test Game (USA) (Disc 2) (Rev 1)"
+    );
+}
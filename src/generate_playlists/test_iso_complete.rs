@@ -59,7 +59,7 @@ async fn test() {
     }
 
     // when
-    process_system(&mut connection, &progress_bar, &system)
+    process_system(&mut connection, &progress_bar, &system, false)
         .await
         .unwrap();
 
@@ -0,0 +1,99 @@
+use super::super::database::*;
+use super::super::import_dats;
+use super::super::import_roms;
+use super::*;
+use std::path::Path;
+use tempfile::{NamedTempFile, TempDir};
+use tokio::fs;
+
+#[tokio::test]
+async fn test() {
+    // given
+    let _guard = MUTEX.lock().await;
+
+    let test_directory = Path::new("tests");
+    let progress_bar = ProgressBar::hidden();
+
+    let db_file = NamedTempFile::new().unwrap();
+    let pool = establish_connection(db_file.path().to_str().unwrap()).await;
+    let mut connection = pool.acquire().await.unwrap();
+
+    let rom_directory = TempDir::new_in(&test_directory).unwrap();
+    set_rom_directory(PathBuf::from(rom_directory.path()));
+    let tmp_directory = TempDir::new_in(&test_directory).unwrap();
+    let tmp_directory = set_tmp_directory(PathBuf::from(tmp_directory.path()));
+
+    let matches = import_dats::subcommand().get_matches_from(&[
+        "import-dats",
+        "tests/Test System (20250218) (Multiple Regions).dat",
+    ]);
+    import_dats::main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    let system = find_systems(&mut connection).await.remove(0);
+    let system_directory = get_system_directory(&mut connection, &system)
+        .await
+        .unwrap();
+
+    for romfile_name in [
+        "Test Game (USA).iso",
+        "Test Game (USA, Europe) (Disc 1).iso",
+        "Test Game (USA, Europe) (Disc 2).iso",
+    ] {
+        let romfile_path = tmp_directory.join(romfile_name);
+        fs::copy(test_directory.join(romfile_name), &romfile_path)
+            .await
+            .unwrap();
+        let matches = import_roms::subcommand()
+            .get_matches_from(&["import-roms", romfile_path.as_os_str().to_str().unwrap()]);
+        import_roms::main(&mut connection, &matches, &progress_bar)
+            .await
+            .unwrap();
+    }
+
+    // when
+    process_system(&mut connection, &progress_bar, &system, true)
+        .await
+        .unwrap();
+
+    // then
+    let games = find_complete_games_by_system_id(&mut connection, system.id).await;
+    assert_eq!(games.len(), 3);
+    assert!(games.iter().all(|game| game.playlist_id.is_some()));
+
+    for (playlist_name, first_disc_name) in [
+        ("Test Game (EU).m3u", "Test Game (Europe) (Disc 1).iso"),
+        ("Test Game (US).m3u", "Test Game (USA) (Disc 1).iso"),
+    ] {
+        let playlist_path = system_directory.join(playlist_name);
+        assert!(playlist_path.is_file());
+
+        let playlist = find_romfile_by_path(
+            &mut connection,
+            playlist_path
+                .strip_prefix(&rom_directory)
+                .unwrap()
+                .as_os_str()
+                .to_str()
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(playlist.romfile_type, RomfileType::Playlist as i64);
+
+        let lines = fs::read_to_string(playlist_path)
+            .await
+            .unwrap()
+            .split("\n")
+            .map(|s| s.to_owned())
+            .collect::<Vec<String>>();
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines.first().unwrap(), first_disc_name);
+        assert_eq!(
+            lines.get(1).unwrap(),
+            "Test Game (USA, Europe) (Disc 2).iso"
+        );
+        assert_eq!(lines.get(2).unwrap(), "");
+    }
+}
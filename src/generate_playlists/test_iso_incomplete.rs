@@ -54,7 +54,7 @@ async fn test() {
         .unwrap();
 
     // when
-    process_system(&mut connection, &progress_bar, &system)
+    process_system(&mut connection, &progress_bar, &system, false)
         .await
         .unwrap();
 
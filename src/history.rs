@@ -0,0 +1,123 @@
+use super::database::*;
+use super::model::*;
+use super::util::*;
+use super::SimpleResult;
+use chrono::{DateTime, Local};
+use clap::{Arg, ArgAction, ArgMatches, Command};
+use indicatif::ProgressBar;
+use num_traits::FromPrimitive;
+use sqlx::sqlite::SqliteConnection;
+
+pub fn subcommand() -> Command {
+    Command::new("history")
+        .about("Print where games came from and what happened to them")
+        .arg(
+            Arg::new("GAME")
+                .help("Select games by name pattern")
+                .required(true)
+                .index(1),
+        )
+        .arg(
+            Arg::new("SYSTEM")
+                .short('s')
+                .long("system")
+                .help("Select systems by name")
+                .required(false)
+                .action(ArgAction::Append),
+        )
+        .arg(
+            Arg::new("EXACT_SYSTEM")
+                .long("exact-system")
+                .help("Match system names exactly")
+                .required(false)
+                .action(ArgAction::SetTrue),
+        )
+}
+
+pub async fn main(
+    connection: &mut SqliteConnection,
+    matches: &ArgMatches,
+    progress_bar: &ProgressBar,
+) -> SimpleResult<()> {
+    let systems = match matches.get_many::<String>("SYSTEM") {
+        Some(system_names) => {
+            let mut systems: Vec<System> = vec![];
+            for system_name in system_names {
+                systems.append(
+                    &mut find_systems_by_name_match(
+                        connection,
+                        system_name,
+                        matches.get_flag("EXACT_SYSTEM"),
+                    )
+                    .await,
+                );
+            }
+            systems.dedup_by_key(|system| system.id);
+            systems
+        }
+        None => find_systems(connection).await,
+    };
+
+    // globs use the same wildcards as LIKE patterns
+    let pattern = matches
+        .get_one::<String>("GAME")
+        .unwrap()
+        .trim()
+        .replace('*', "%")
+        .replace('?', "_");
+
+    let mut found = false;
+    for system in &systems {
+        for game in find_games_by_name_and_system_id(connection, &pattern, system.id).await {
+            found = true;
+            print_history(connection, progress_bar, system, &game).await;
+        }
+    }
+    if !found {
+        progress_bar.println("No matching game");
+        set_outcome(Outcome::NothingMatched);
+    }
+
+    Ok(())
+}
+
+pub async fn print_history(
+    connection: &mut SqliteConnection,
+    progress_bar: &ProgressBar,
+    system: &System,
+    game: &Game,
+) -> Vec<Event> {
+    progress_bar.println(format!("\"{}\" ({}):", game.name, system.name));
+    let events = find_events_by_game_id(connection, game.id).await;
+    if events.is_empty() {
+        progress_bar.println("  No history");
+    }
+    for event in &events {
+        let date = DateTime::from_timestamp(event.created_at, 0)
+            .map(|date| {
+                date.with_timezone(&Local)
+                    .format("%Y-%m-%d %H:%M:%S")
+                    .to_string()
+            })
+            .unwrap_or_default();
+        let action = match EventType::from_i64(event.event_type) {
+            Some(EventType::Import) => "Imported",
+            Some(EventType::Convert) => "Converted",
+            Some(EventType::Sort) => "Sorted",
+            None => "Unknown",
+        };
+        match &event.source {
+            Some(source) => progress_bar.println(format!(
+                "  {} {} \"{}\" -> \"{}\"",
+                date, action, source, event.destination
+            )),
+            None => {
+                progress_bar.println(format!("  {} {} \"{}\"", date, action, event.destination))
+            }
+        }
+    }
+    events
+}
+
+#[cfg(test)]
+mod test_history;
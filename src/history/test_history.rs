@@ -0,0 +1,78 @@
+use super::super::config::{set_rom_directory, set_tmp_directory, MUTEX};
+use super::super::convert_roms;
+use super::super::import_dats;
+use super::super::import_roms;
+use super::*;
+use std::path::{Path, PathBuf};
+use tempfile::{NamedTempFile, TempDir};
+use tokio::fs;
+
+#[tokio::test]
+async fn test() {
+    // given
+    let _guard = MUTEX.lock().await;
+
+    let test_directory = Path::new("tests");
+    let progress_bar = ProgressBar::hidden();
+
+    let db_file = NamedTempFile::new().unwrap();
+    let pool = establish_connection(db_file.path().to_str().unwrap()).await;
+    let mut connection = pool.acquire().await.unwrap();
+
+    let rom_directory = TempDir::new_in(&test_directory).unwrap();
+    set_rom_directory(PathBuf::from(rom_directory.path()));
+    let tmp_directory = TempDir::new_in(&test_directory).unwrap();
+    let tmp_directory = set_tmp_directory(PathBuf::from(tmp_directory.path()));
+
+    let matches = import_dats::subcommand()
+        .get_matches_from(&["import-dats", "tests/Test System (20200721).dat"]);
+    import_dats::main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    let romfile_path = tmp_directory.join("Test Game (USA, Europe).rom");
+    fs::copy(
+        test_directory.join("Test Game (USA, Europe).rom"),
+        &romfile_path,
+    )
+    .await
+    .unwrap();
+
+    let source_path = fs::canonicalize(&romfile_path).await.unwrap();
+
+    let matches = import_roms::subcommand()
+        .get_matches_from(&["import-roms", romfile_path.as_os_str().to_str().unwrap()]);
+    import_roms::main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+    let original_path = find_romfiles(&mut connection).await.remove(0).path;
+
+    let matches = convert_roms::subcommand().get_matches_from(&["convert-roms", "-f", "ZIP", "-a"]);
+    convert_roms::main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+    let converted_path = find_romfiles(&mut connection).await.remove(0).path;
+
+    let system = find_systems(&mut connection).await.remove(0);
+    let game =
+        find_games_by_name_and_system_id(&mut connection, "Test Game (USA, Europe)", system.id)
+            .await
+            .remove(0);
+
+    // when
+    let events = print_history(&mut connection, &progress_bar, &system, &game).await;
+
+    // then
+    assert_eq!(events.len(), 2);
+
+    let event = events.first().unwrap();
+    assert_eq!(event.event_type, EventType::Import as i64);
+    assert_eq!(event.source.as_deref(), source_path.as_os_str().to_str());
+    assert_eq!(event.destination, original_path);
+
+    let event = events.last().unwrap();
+    assert_eq!(event.event_type, EventType::Convert as i64);
+    assert_eq!(event.source.as_deref(), Some(original_path.as_str()));
+    assert_eq!(event.destination, converted_path);
+    assert!(event.destination.ends_with(".zip"));
+}
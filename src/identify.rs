@@ -0,0 +1,110 @@
+use super::common::*;
+use super::config::*;
+use super::SimpleResult;
+use indicatif::ProgressBar;
+use lazy_static::lazy_static;
+use reqwest::StatusCode;
+use sqlx::sqlite::SqliteConnection;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+use strum::IntoEnumIterator;
+use tokio::sync::Mutex;
+use tokio::time::{sleep_until, Instant};
+
+const IDENTIFY_TIMEOUT: Duration = Duration::from_secs(10);
+
+pub const IDENTIFY_INTERVAL_RANGE: [usize; 2] = [0, 60000];
+
+lazy_static! {
+    static ref NEXT_REQUEST: Mutex<Option<Instant>> = Mutex::new(None);
+}
+
+// an unreachable service is not retried for the rest of the run
+static OFFLINE: AtomicBool = AtomicBool::new(false);
+
+pub fn get_identify_placeholder(hash_algorithm: &HashAlgorithm) -> String {
+    format!("{{{}}}", hash_algorithm)
+}
+
+pub async fn identify_romfile(
+    connection: &mut SqliteConnection,
+    progress_bar: &ProgressBar,
+    romfile: &CommonRomfile,
+) -> SimpleResult<Option<String>> {
+    let mut url = match get_string(connection, "IDENTIFY_URL").await {
+        Some(url) => url,
+        None => return Ok(None),
+    };
+    if OFFLINE.load(Ordering::Relaxed) {
+        return Ok(None);
+    }
+
+    // only compute the hashes the service asks for
+    for hash_algorithm in HashAlgorithm::iter() {
+        let placeholder = get_identify_placeholder(&hash_algorithm);
+        if url.contains(&placeholder) {
+            let (hash, _) = romfile
+                .get_hash_and_size(connection, progress_bar, 1, 1, &hash_algorithm)
+                .await?;
+            url = url.replace(&placeholder, &hash);
+        }
+    }
+
+    // space requests out to stay within the service's rate limit
+    let interval = Duration::from_millis(
+        get_integer(connection, "IDENTIFY_INTERVAL")
+            .await
+            .unwrap_or(0) as u64,
+    );
+    {
+        let mut next_request = NEXT_REQUEST.lock().await;
+        if let Some(next_request) = *next_request {
+            sleep_until(next_request).await;
+        }
+        *next_request = Some(Instant::now() + interval);
+    }
+
+    let client = try_with!(
+        reqwest::Client::builder().timeout(IDENTIFY_TIMEOUT).build(),
+        "Failed to build HTTP client"
+    );
+    let response = match client.get(&url).send().await {
+        Ok(response) => response,
+        Err(_) => {
+            progress_bar.println("Identification service unreachable, skipping further lookups");
+            OFFLINE.store(true, Ordering::Relaxed);
+            return Ok(None);
+        }
+    };
+
+    match response.status() {
+        StatusCode::OK => {
+            let name = response.text().await.unwrap_or_default();
+            let name = name.lines().next().unwrap_or_default().trim();
+            if name.is_empty() {
+                return Ok(None);
+            }
+            progress_bar.println(format!("Identified as \"{}\"", name));
+            Ok(Some(name.to_string()))
+        }
+        StatusCode::TOO_MANY_REQUESTS => {
+            // honor the delay requested by the service, in seconds
+            let retry_after = response
+                .headers()
+                .get(reqwest::header::RETRY_AFTER)
+                .and_then(|value| value.to_str().ok())
+                .and_then(|value| value.parse::<u64>().ok())
+                .map(Duration::from_secs)
+                .unwrap_or(interval);
+            *NEXT_REQUEST.lock().await = Some(Instant::now() + retry_after);
+            progress_bar.println("Identification service rate limit reached, skipping");
+            Ok(None)
+        }
+        _ => Ok(None),
+    }
+}
+
+#[cfg(test)]
+mod test_identify;
+#[cfg(test)]
+mod test_identify_unknown;
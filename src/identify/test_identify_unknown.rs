@@ -0,0 +1,53 @@
+extern crate wiremock;
+
+use super::super::database::*;
+use super::super::util::*;
+use super::*;
+use std::path::{Path, PathBuf};
+use tempfile::{NamedTempFile, TempDir};
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+#[tokio::test]
+async fn test() {
+    // given
+    let _guard = MUTEX.lock().await;
+
+    let test_directory = Path::new("tests");
+    let progress_bar = ProgressBar::hidden();
+
+    let db_file = NamedTempFile::new().unwrap();
+    let pool = establish_connection(db_file.path().to_str().unwrap()).await;
+    let mut connection = pool.acquire().await.unwrap();
+
+    let rom_directory = TempDir::new_in(&test_directory).unwrap();
+    set_rom_directory(PathBuf::from(rom_directory.path()));
+    let tmp_directory = TempDir::new_in(&test_directory).unwrap();
+    set_tmp_directory(PathBuf::from(tmp_directory.path()));
+
+    let mock_server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/identify"))
+        .respond_with(ResponseTemplate::new(404))
+        .expect(1)
+        .mount(&mock_server)
+        .await;
+
+    set_string(
+        &mut connection,
+        "IDENTIFY_URL",
+        &format!("{}/identify?crc={{crc}}", mock_server.uri()),
+    )
+    .await;
+
+    let romfile =
+        CommonRomfile::from_path(&test_directory.join("Test Game (USA, Europe).rom")).unwrap();
+
+    // when
+    let name = identify_romfile(&mut connection, &progress_bar, &romfile)
+        .await
+        .unwrap();
+
+    // then
+    assert_eq!(name, None);
+}
@@ -0,0 +1,145 @@
+use super::common::*;
+use super::config::*;
+use super::database::*;
+use super::download_dats::REDUMP_SYSTEM_URL;
+use super::model::*;
+use super::prompt::*;
+use super::util::*;
+use super::SimpleResult;
+use clap::{Arg, ArgAction, ArgMatches, Command};
+use indicatif::ProgressBar;
+use sqlx::sqlite::SqliteConnection;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use tokio::fs;
+use tokio::io::AsyncWriteExt;
+use zip::ZipArchive;
+
+pub fn subcommand() -> Command {
+    Command::new("import-cuesheets")
+        .about("Import Redump cuesheets to restore canonical CUE files")
+        .arg(
+            Arg::new("CUESHEETS")
+                .help("Set the CUE files or ZIP packs to import")
+                .required(true)
+                .num_args(1..)
+                .index(1)
+                .value_parser(value_parser!(PathBuf)),
+        )
+        .arg(
+            Arg::new("SYSTEM")
+                .short('s')
+                .long("system")
+                .help("Select systems by name")
+                .required(false)
+                .action(ArgAction::Append),
+        )
+        .arg(
+            Arg::new("EXACT_SYSTEM")
+                .long("exact-system")
+                .help("Match system names exactly")
+                .required(false)
+                .action(ArgAction::SetTrue),
+        )
+}
+
+pub async fn main(
+    connection: &mut SqliteConnection,
+    matches: &ArgMatches,
+    progress_bar: &ProgressBar,
+) -> SimpleResult<()> {
+    let systems = match matches.get_many::<String>("SYSTEM") {
+        Some(system_names) => {
+            let mut systems: Vec<System> = vec![];
+            for system_name in system_names {
+                systems.append(
+                    &mut find_systems_by_name_match(
+                        connection,
+                        system_name,
+                        matches.get_flag("EXACT_SYSTEM"),
+                    )
+                    .await,
+                );
+            }
+            systems.dedup_by_key(|system| system.id);
+            systems
+        }
+        None => prompt_for_systems(connection, Some(REDUMP_SYSTEM_URL), false, false).await?,
+    };
+
+    let (zip_paths, mut cue_paths): (Vec<PathBuf>, Vec<PathBuf>) = matches
+        .get_many::<PathBuf>("CUESHEETS")
+        .unwrap()
+        .cloned()
+        .partition(|path| {
+            path.extension()
+                .is_some_and(|extension| extension.to_ascii_lowercase() == ZIP_EXTENSION)
+        });
+
+    // cuesheets packs are distributed as ZIP archives
+    let tmp_directory = create_tmp_directory(connection).await?;
+    for zip_path in zip_paths {
+        let mut reader = get_reader_sync(&zip_path)?;
+        let mut zip_archive = try_with!(ZipArchive::new(&mut reader), "Failed to read ZIP");
+        try_with!(zip_archive.extract(&tmp_directory), "Failed to extract ZIP");
+        for file_name in zip_archive.file_names() {
+            if file_name.to_lowercase().ends_with(CUE_EXTENSION) {
+                cue_paths.push(tmp_directory.path().join(file_name));
+            }
+        }
+    }
+
+    let mut games_by_name: HashMap<String, Game> = HashMap::new();
+    for system in &systems {
+        for game in find_games_by_system_id(connection, system.id).await {
+            games_by_name.insert(game.name.clone(), game);
+        }
+    }
+
+    let mut count = 0;
+    for cue_path in cue_paths {
+        let file_name = cue_path.file_name().unwrap().to_str().unwrap();
+        let game_name = cue_path.file_stem().unwrap().to_str().unwrap();
+        match games_by_name.get(game_name) {
+            Some(game) => {
+                let content = try_with!(
+                    fs::read_to_string(&cue_path).await,
+                    "Failed to read \"{}\"",
+                    file_name
+                );
+                create_or_update_cuesheet(connection, file_name, &content, game.id).await;
+                count += 1;
+            }
+            None => progress_bar.println(format!("No matching game for \"{}\"", file_name)),
+        }
+    }
+    progress_bar.println(format!("Imported {} cuesheet(s)", count));
+
+    Ok(())
+}
+
+pub async fn write_cuesheet<P: AsRef<Path>>(
+    connection: &mut SqliteConnection,
+    progress_bar: &ProgressBar,
+    game_id: i64,
+    destination_directory: &P,
+) -> SimpleResult<Option<CommonRomfile>> {
+    match find_cuesheet_by_game_id(connection, game_id).await {
+        Some(cuesheet) => {
+            let cue_path = destination_directory.as_ref().join(&cuesheet.name);
+            let mut cue_file = create_file(progress_bar, &cue_path, true).await?;
+            try_with!(
+                cue_file.write_all(cuesheet.content.as_bytes()).await,
+                "Failed to write \"{}\"",
+                cue_path.as_os_str().to_str().unwrap()
+            );
+            try_with!(
+                cue_file.flush().await,
+                "Failed to write \"{}\"",
+                cue_path.as_os_str().to_str().unwrap()
+            );
+            Ok(Some(CommonRomfile::from_path(&cue_path)?))
+        }
+        None => Ok(None),
+    }
+}
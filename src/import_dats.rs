@@ -1,9 +1,11 @@
 use super::common::*;
 use super::config::*;
 use super::database::*;
-use super::import_roms::import_rom;
+use super::import_roms::{import_rom, ImportMode};
 use super::model::*;
 use super::progress::*;
+use super::sevenzip;
+use super::sevenzip::AsArchive;
 use super::util::*;
 use super::SimpleResult;
 use clap::{Arg, ArgAction, ArgMatches, Command};
@@ -17,6 +19,7 @@ use shiratsu_naming::naming::TokenizedName;
 use shiratsu_naming::region::Region;
 use simple_error::SimpleError;
 use sqlx::sqlite::SqliteConnection;
+use std::collections::HashMap;
 use std::io;
 use std::path::Path;
 use std::path::PathBuf;
@@ -28,6 +31,24 @@ use zip::ZipArchive;
 #[folder = "data/"]
 struct Assets;
 
+// bundled header skippers for DATs that don't reference theirs, keyed by exact DAT name
+const SYSTEM_HEADERS: &[(&str, &str)] = &[
+    ("Atari - 7800 (A78)", "No-Intro_A7800.xml"),
+    ("Atari - Atari 7800 (A78)", "No-Intro_A7800.xml"),
+    ("Atari - Jaguar (ROM)", "No-Intro_JAG.xml"),
+    ("Atari - Atari Jaguar (ROM)", "No-Intro_JAG.xml"),
+    ("Atari - Lynx (LNX)", "No-Intro_LNX.xml"),
+    ("Atari - Atari Lynx (LNX)", "No-Intro_LNX.xml"),
+    (
+        "Nintendo - Family Computer Disk System (FDS)",
+        "No-Intro_FDS.xml",
+    ),
+    (
+        "Nintendo - Nintendo Entertainment System (Headerless)",
+        "No-Intro_NES.xml",
+    ),
+];
+
 pub fn subcommand() -> Command {
     Command::new("import-dats")
         .about("Parse and import Logiqx DAT files into oxyromon")
@@ -78,22 +99,55 @@ pub async fn main(
     matches: &ArgMatches,
     progress_bar: &ProgressBar,
 ) -> SimpleResult<()> {
-    let (zip_paths, mut dat_paths): (Vec<PathBuf>, Vec<PathBuf>) = matches
+    let (archive_paths, mut dat_paths): (Vec<PathBuf>, Vec<PathBuf>) = matches
         .get_many::<PathBuf>("DATS")
         .unwrap()
         .cloned()
         .partition(|path| {
-            path.extension().unwrap().to_str().unwrap().to_lowercase() == ZIP_EXTENSION
+            ARCHIVE_EXTENSIONS.contains(
+                &path
+                    .extension()
+                    .unwrap()
+                    .to_str()
+                    .unwrap()
+                    .to_lowercase()
+                    .as_str(),
+            )
         });
 
     let tmp_directory = create_tmp_directory(connection).await?;
-    for zip_path in zip_paths {
-        let mut reader = get_reader_sync(&zip_path)?;
-        let mut zip_archive = try_with!(ZipArchive::new(&mut reader), "Failed to read ZIP");
-        try_with!(zip_archive.extract(&tmp_directory), "Failed to extract ZIP");
-        for file_name in zip_archive.file_names() {
-            if file_name.ends_with(DAT_EXTENSION) {
-                dat_paths.push(tmp_directory.path().join(file_name));
+    for archive_path in archive_paths {
+        let extension = archive_path
+            .extension()
+            .unwrap()
+            .to_str()
+            .unwrap()
+            .to_lowercase();
+        if extension == ZIP_EXTENSION {
+            let mut reader = get_reader_sync(&archive_path)?;
+            let mut zip_archive = try_with!(ZipArchive::new(&mut reader), "Failed to read ZIP");
+            try_with!(zip_archive.extract(&tmp_directory), "Failed to extract ZIP");
+            for file_name in zip_archive.file_names() {
+                if file_name.to_lowercase().ends_with(DAT_EXTENSION) {
+                    dat_paths.push(tmp_directory.path().join(file_name));
+                }
+            }
+        } else {
+            // other archives are handled by sevenzip
+            if sevenzip::get_version().await.is_err() {
+                print_missing_tool(progress_bar, "sevenzip");
+                continue;
+            }
+            let archive_romfiles = CommonRomfile::from_path(&archive_path)?
+                .as_archive(progress_bar, None)
+                .await?;
+            for archive_romfile in archive_romfiles {
+                if archive_romfile.path.to_lowercase().ends_with(DAT_EXTENSION) {
+                    let romfile = archive_romfile
+                        .to_common(progress_bar, &tmp_directory.path())
+                        .await?;
+                    dat_paths.push(romfile.path);
+                }
             }
         }
     }
@@ -147,10 +201,25 @@ pub async fn parse_dat<P: AsRef<Path>>(
     dat_path: &P,
     skip_header: bool,
 ) -> SimpleResult<(DatfileXml, Option<DetectorXml>)> {
-    let datfile_xml: DatfileXml = try_with!(
-        de::from_reader(&mut get_reader_sync(dat_path)?),
-        "Failed to deserialize DAT file"
-    );
+    let datfile_xml: DatfileXml = if dat_path
+        .as_ref()
+        .extension()
+        .is_some_and(|extension| extension == ZST_EXTENSION)
+    {
+        let decoder = try_with!(
+            zstd::Decoder::new(open_file_sync(dat_path)?),
+            "Failed to decompress DAT file"
+        );
+        try_with!(
+            de::from_reader(io::BufReader::new(decoder)),
+            "Failed to deserialize DAT file"
+        )
+    } else {
+        try_with!(
+            de::from_reader(&mut get_reader_sync(dat_path)?),
+            "Failed to deserialize DAT file"
+        )
+    };
 
     // print information
     progress_bar.println(format!("System: {}", datfile_xml.system.name));
@@ -163,24 +232,28 @@ pub async fn parse_dat<P: AsRef<Path>>(
 
     let mut detector_xml = None;
     if !skip_header {
-        if let Some(clr_mame_pro_xml) = &datfile_xml
+        let header_file_name = datfile_xml
             .system
             .clrmamepros
             .iter()
-            .find(|clrmamepro| clrmamepro.header.is_some())
-        {
+            .find_map(|clrmamepro| clrmamepro.header.as_deref())
+            .or_else(|| {
+                SYSTEM_HEADERS
+                    .iter()
+                    .find(|(system_name, _)| datfile_xml.system.name == *system_name)
+                    .map(|(_, header_file_name)| *header_file_name)
+            });
+        if let Some(header_file_name) = header_file_name {
             progress_bar.println("Processing header");
-            if let Some(header_file_name) = &clr_mame_pro_xml.header {
-                let header_file_path = dat_path.as_ref().parent().unwrap().join(header_file_name);
-                if header_file_path.is_file() {
-                    let header_file = open_file_sync(&header_file_path.as_path())?;
-                    let reader = io::BufReader::new(header_file);
-                    detector_xml = de::from_reader(reader).expect("Failed to parse header file");
-                } else {
-                    let header_file = Assets::get(header_file_name).unwrap();
-                    detector_xml = de::from_str(str::from_utf8(header_file.data.as_ref()).unwrap())
-                        .expect("Failed to parse header file");
-                }
+            let header_file_path = dat_path.as_ref().parent().unwrap().join(header_file_name);
+            if header_file_path.is_file() {
+                let header_file = open_file_sync(&header_file_path.as_path())?;
+                let reader = io::BufReader::new(header_file);
+                detector_xml = de::from_reader(reader).expect("Failed to parse header file");
+            } else {
+                let header_file = Assets::get(header_file_name).unwrap();
+                detector_xml = de::from_str(str::from_utf8(header_file.data.as_ref()).unwrap())
+                    .expect("Failed to parse header file");
             }
         }
     };
@@ -188,6 +261,38 @@ pub async fn parse_dat<P: AsRef<Path>>(
     Ok((datfile_xml, detector_xml))
 }
 
+pub async fn store_dat<P: AsRef<Path>>(
+    connection: &mut SqliteConnection,
+    progress_bar: &ProgressBar,
+    dat_path: &P,
+    datfile_xml: &DatfileXml,
+) -> SimpleResult<()> {
+    if !get_bool(connection, "DAT_STORE_COMPRESSED").await {
+        return Ok(());
+    }
+    let dat_directory = get_dat_directory(connection).await;
+    create_directory(progress_bar, &dat_directory, true).await?;
+    let stored_dat_path = dat_directory.join(format!(
+        "{} ({}).{}.{}",
+        datfile_xml.system.name, datfile_xml.system.version, DAT_EXTENSION, ZST_EXTENSION
+    ));
+    let stored_dat_file = try_with!(
+        std::fs::File::create(&stored_dat_path),
+        "Failed to create \"{}\"",
+        stored_dat_path.as_os_str().to_str().unwrap()
+    );
+    try_with!(
+        zstd::stream::copy_encode(get_reader_sync(dat_path)?, stored_dat_file, 0),
+        "Failed to compress \"{}\"",
+        stored_dat_path.as_os_str().to_str().unwrap()
+    );
+    progress_bar.println(format!(
+        "Stored \"{}\"",
+        stored_dat_path.file_name().unwrap().to_str().unwrap()
+    ));
+    Ok(())
+}
+
 pub async fn import_dat(
     connection: &mut SqliteConnection,
     progress_bar: &ProgressBar,
@@ -277,7 +382,7 @@ pub async fn import_dat(
     Ok(())
 }
 
-fn get_regions_from_game_name(name: &str) -> SimpleResult<String> {
+pub fn get_regions_from_game_name(name: &str) -> SimpleResult<String> {
     match NoIntroName::try_parse(name) {
         Ok(v) => {
             for token in v.iter() {
@@ -302,6 +407,90 @@ fn get_regions_from_game_name(name: &str) -> SimpleResult<String> {
     Ok(String::from(""))
 }
 
+// logiqx release region codes that aren't no-intro or tosec regions
+const RELEASE_REGIONS: &[(&str, &str)] = &[
+    ("ASI", "AS"),
+    ("AUS", "AU"),
+    ("BRA", "BR"),
+    ("CAN", "CA"),
+    ("CHN", "CN"),
+    ("DEN", "DK"),
+    ("EUR", "EU"),
+    ("FIN", "FI"),
+    ("FRA", "FR"),
+    ("GER", "DE"),
+    ("GRE", "GR"),
+    ("HOL", "NL"),
+    ("ITA", "IT"),
+    ("JPN", "JP"),
+    ("KOR", "KR"),
+    ("NOR", "NO"),
+    ("POL", "PL"),
+    ("POR", "PT"),
+    ("RUS", "RU"),
+    ("SPA", "ES"),
+    ("SWE", "SE"),
+    ("TAI", "TW"),
+    ("UK", "GB"),
+];
+
+fn get_regions_from_release(region: &str) -> Vec<Region> {
+    if let Some((_, code)) = RELEASE_REGIONS.iter().find(|(name, _)| *name == region) {
+        return Region::try_from_tosec_region(code).unwrap_or_default();
+    }
+    if let Ok(regions) = Region::try_from_tosec_region(region) {
+        return regions;
+    }
+    // full region names, e.g. "USA" or "Europe", parse like no-intro name tokens
+    match get_regions_from_game_name(&format!("Release ({})", region)) {
+        Ok(s) if !s.is_empty() => Region::try_from_tosec_region(&s).unwrap_or_default(),
+        _ => vec![],
+    }
+}
+
+// release regions complete the ones found in the name, or replace them when the name has none
+pub fn get_regions_from_game_name_and_releases(
+    name: &str,
+    releases_xml: &[ReleaseXml],
+) -> SimpleResult<String> {
+    let (name_regions, name_error) = match get_regions_from_game_name(name) {
+        Ok(s) => (s, None),
+        Err(e) => (String::new(), Some(e)),
+    };
+    let mut regions = Region::try_from_tosec_region(&name_regions).unwrap_or_default();
+    let mut updated = false;
+    for release_xml in releases_xml {
+        for region in get_regions_from_release(&release_xml.region) {
+            if region != Region::Unknown && !regions.contains(&region) {
+                regions.push(region);
+                updated = true;
+            }
+        }
+    }
+    if !updated {
+        return match name_error {
+            Some(e) => Err(e),
+            None => Ok(name_regions),
+        };
+    }
+    Ok(Region::to_normalized_region_string(&regions))
+}
+
+fn get_languages_from_game_name(name: &str) -> String {
+    if let Ok(v) = NoIntroName::try_parse(name) {
+        for token in v.iter() {
+            if let NoIntroToken::Languages(languages) = token {
+                return languages
+                    .iter()
+                    .map(|(language, _)| language.to_string())
+                    .collect::<Vec<String>>()
+                    .join(",");
+            }
+        }
+    }
+    String::from("")
+}
+
 async fn create_or_update_system(
     connection: &mut SqliteConnection,
     progress_bar: &ProgressBar,
@@ -362,8 +551,9 @@ async fn create_or_update_games(
         )
         .await;
         let mut regions = String::new();
+        let mut languages = String::new();
         if !arcade {
-            match get_regions_from_game_name(&game_xml.name) {
+            match get_regions_from_game_name_and_releases(&game_xml.name, &game_xml.releases) {
                 Ok(s) => regions.push_str(&s),
                 Err(err) => {
                     progress_bar.println(err.as_str());
@@ -371,19 +561,24 @@ async fn create_or_update_games(
                     continue;
                 }
             }
+            languages.push_str(&get_languages_from_game_name(&game_xml.name));
         }
         let game_id = match game {
             Some(game) => {
                 update_game_from_xml(
-                    connection, game.id, game_xml, &regions, system_id, None, None,
+                    connection, game.id, game_xml, &regions, &languages, system_id, None, None,
                 )
                 .await;
                 game.id
             }
             None => {
-                create_game_from_xml(connection, game_xml, &regions, system_id, None, None).await
+                create_game_from_xml(
+                    connection, game_xml, &regions, &languages, system_id, None, None,
+                )
+                .await
             }
         };
+        create_or_update_releases(connection, &game_xml.releases, game_id).await;
         if !game_xml.roms.is_empty() {
             orphan_romfile_ids.append(
                 &mut create_or_update_roms(
@@ -471,8 +666,9 @@ async fn create_or_update_games(
                 None => None,
             };
             let mut regions = String::new();
+            let mut languages = String::new();
             if !arcade {
-                match get_regions_from_game_name(&game_xml.name) {
+                match get_regions_from_game_name_and_releases(&game_xml.name, &game_xml.releases) {
                     Ok(s) => regions.push_str(&s),
                     Err(err) => {
                         progress_bar.println(err.as_str());
@@ -480,6 +676,7 @@ async fn create_or_update_games(
                         continue;
                     }
                 }
+                languages.push_str(&get_languages_from_game_name(&game_xml.name));
             }
             let game_id = match game {
                 Some(game) => {
@@ -488,6 +685,7 @@ async fn create_or_update_games(
                         game.id,
                         game_xml,
                         &regions,
+                        &languages,
                         system_id,
                         parent_game.map(|game| game.id),
                         bios_game.map(|game| game.id),
@@ -500,6 +698,7 @@ async fn create_or_update_games(
                         connection,
                         game_xml,
                         &regions,
+                        &languages,
                         system_id,
                         parent_game.map(|game| game.id),
                         bios_game.map(|game| game.id),
@@ -507,6 +706,7 @@ async fn create_or_update_games(
                     .await
                 }
             };
+            create_or_update_releases(connection, &game_xml.releases, game_id).await;
             if !game_xml.roms.is_empty() {
                 orphan_romfile_ids.append(
                     &mut create_or_update_roms(
@@ -555,7 +755,7 @@ async fn create_or_update_roms(
     connection: &mut SqliteConnection,
     progress_bar: &ProgressBar,
     roms_xml: &[RomXml],
-    mut bios: bool,
+    bios: bool,
     disk: bool,
     game_id: i64,
 ) -> Vec<i64> {
@@ -566,6 +766,7 @@ async fn create_or_update_roms(
             continue;
         }
         // find parent rom if needed
+        let mut bios = bios;
         let mut parent_id = None;
         if rom_xml.merge.is_some() && rom_xml.crc.is_some() {
             let game = find_game_by_id(connection, game_id).await;
@@ -593,7 +794,8 @@ async fn create_or_update_roms(
             };
             if let Some(rom) = parent_rom.or(bios_rom) {
                 bios = rom.bios;
-                parent_id = rom.parent_id;
+                // point to the original rom, parents can share theirs with a bios too
+                parent_id = Some(rom.parent_id.unwrap_or(rom.id));
             } else {
                 progress_bar.println(format!(
                     "Rom \"{}\" not found in game \"{}\" parent/bios, please fix your DAT file",
@@ -601,14 +803,19 @@ async fn create_or_update_roms(
                 ));
             }
         }
-        match find_rom_by_name_and_game_id(connection, &rom_xml.name, game_id).await {
+        let rom_id = match find_rom_by_name_and_game_id(connection, &rom_xml.name, game_id).await {
             Some(rom) => {
                 update_rom_from_xml(connection, rom.id, rom_xml, bios, disk, game_id, parent_id)
                     .await;
-                if rom_xml.size != rom.size
-                    || rom_xml.crc.is_some()
-                        && rom_xml.crc.as_ref().unwrap() != rom.crc.as_ref().unwrap()
-                {
+                let crc_changed = matches!(
+                    (&rom_xml.crc, &rom.crc),
+                    (Some(crc), Some(old_crc)) if crc != old_crc
+                );
+                let sha256_changed = matches!(
+                    (&rom_xml.sha256, &rom.sha256),
+                    (Some(sha256), Some(old_sha256)) if &sha256.to_lowercase() != old_sha256
+                );
+                if rom_xml.size != rom.size || crc_changed || sha256_changed {
                     if let Some(romfile_id) = rom.romfile_id {
                         orphan_romfile_ids.push(romfile_id);
                         update_rom_romfile(connection, rom.id, None).await;
@@ -618,10 +825,31 @@ async fn create_or_update_roms(
             }
             None => create_rom_from_xml(connection, rom_xml, bios, disk, game_id, parent_id).await,
         };
+        delete_rom_alt_names_by_rom_id(connection, rom_id).await;
+        for alt_name_xml in &rom_xml.alt_names {
+            create_rom_alt_name(
+                connection,
+                &alt_name_xml.name,
+                alt_name_xml.region.as_deref(),
+                rom_id,
+            )
+            .await;
+        }
     }
     orphan_romfile_ids
 }
 
+async fn create_or_update_releases(
+    connection: &mut SqliteConnection,
+    releases_xml: &[ReleaseXml],
+    game_id: i64,
+) {
+    delete_game_releases_by_game_id(connection, game_id).await;
+    for release_xml in releases_xml {
+        create_game_release(connection, release_xml, game_id).await;
+    }
+}
+
 async fn delete_old_games(
     connection: &mut SqliteConnection,
     games_xml: &[GameXml],
@@ -693,7 +921,9 @@ pub async fn reimport_orphan_romfiles(
                 false,
                 false,
                 false,
+                ImportMode::Move,
                 &None,
+                &HashMap::new(),
             )
             .await?;
             if game_ids.is_empty() {
@@ -738,6 +968,8 @@ mod test_dat_outdated_should_do_nothing;
 #[cfg(test)]
 mod test_dat_parent_clone;
 #[cfg(test)]
+mod test_dat_releases;
+#[cfg(test)]
 mod test_dat_updated;
 #[cfg(test)]
 mod test_dat_updated_orphan_archive;
@@ -748,6 +980,10 @@ mod test_dat_updated_orphan_chd;
 #[cfg(test)]
 mod test_dat_updated_orphan_chd_mismatch;
 #[cfg(test)]
+mod test_dat_zip;
+#[cfg(test)]
+mod test_regions_and_languages_usa_europe;
+#[cfg(test)]
 mod test_regions_france_germany;
 #[cfg(test)]
 mod test_regions_world;
@@ -0,0 +1,72 @@
+use super::super::database::*;
+use super::*;
+use std::path::PathBuf;
+use tempfile::{NamedTempFile, TempDir};
+
+#[tokio::test]
+async fn test() {
+    // given
+    let _guard = MUTEX.lock().await;
+
+    let test_directory = Path::new("tests");
+    let progress_bar = ProgressBar::hidden();
+
+    let db_file = NamedTempFile::new().unwrap();
+    let pool = establish_connection(db_file.path().to_str().unwrap()).await;
+    let mut connection = pool.acquire().await.unwrap();
+
+    let rom_directory = TempDir::new_in(&test_directory).unwrap();
+    set_rom_directory(PathBuf::from(rom_directory.path()));
+    let tmp_directory = TempDir::new_in(&test_directory).unwrap();
+    set_tmp_directory(PathBuf::from(tmp_directory.path()));
+
+    let dat_path = test_directory.join("Test System (20250219) (Releases).dat");
+    let (datfile_xml, detector_xml) = parse_dat(&progress_bar, &dat_path, false).await.unwrap();
+
+    // when
+    import_dat(
+        &mut connection,
+        &progress_bar,
+        &datfile_xml,
+        &detector_xml,
+        None,
+        false,
+    )
+    .await
+    .unwrap();
+
+    // then
+    let games = find_games(&mut connection).await;
+    assert_eq!(games.len(), 3);
+
+    let game = games.iter().find(|game| game.name == "Test Game").unwrap();
+    assert_eq!(game.regions, "US-EU");
+    let releases = find_game_releases_by_game_ids(&mut connection, &[game.id]).await;
+    assert_eq!(releases.len(), 2);
+    assert_eq!(releases[0].region, "USA");
+    assert_eq!(releases[0].date.as_deref(), Some("1995-11-22"));
+    assert_eq!(releases[0].language, None);
+    assert_eq!(releases[1].region, "EUR");
+    assert_eq!(releases[1].language.as_deref(), Some("En"));
+
+    let game = games
+        .iter()
+        .find(|game| game.name == "Test Game (Japan)")
+        .unwrap();
+    assert_eq!(game.regions, "JP-KR");
+    assert_eq!(
+        find_game_releases_by_game_ids(&mut connection, &[game.id])
+            .await
+            .len(),
+        2
+    );
+
+    let game = games
+        .iter()
+        .find(|game| game.name == "Test Game (Asia)")
+        .unwrap();
+    assert_eq!(game.regions, "AS");
+    assert!(find_game_releases_by_game_ids(&mut connection, &[game.id])
+        .await
+        .is_empty());
+}
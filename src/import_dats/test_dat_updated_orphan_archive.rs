@@ -1,5 +1,6 @@
 use super::super::database::*;
 use super::*;
+use std::collections::HashMap;
 use std::path::PathBuf;
 use tempfile::{NamedTempFile, TempDir};
 use tokio::fs;
@@ -55,7 +56,9 @@ async fn test() {
             true,
             true,
             false,
+            ImportMode::Move,
             &None,
+            &HashMap::new(),
         )
         .await
         .unwrap();
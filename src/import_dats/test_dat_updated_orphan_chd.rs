@@ -2,6 +2,7 @@ use super::super::chdman;
 use super::super::database::*;
 use super::*;
 use std::cmp::Ordering;
+use std::collections::HashMap;
 use std::path::PathBuf;
 use tempfile::{NamedTempFile, TempDir};
 use tokio::fs;
@@ -68,7 +69,9 @@ async fn test() {
         true,
         true,
         false,
+        ImportMode::Move,
         &None,
+        &HashMap::new(),
     )
     .await
     .unwrap();
@@ -81,7 +84,9 @@ async fn test() {
         true,
         true,
         false,
+        ImportMode::Move,
         &None,
+        &HashMap::new(),
     )
     .await
     .unwrap();
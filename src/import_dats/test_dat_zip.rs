@@ -0,0 +1,40 @@
+use super::super::database::*;
+use super::*;
+use std::path::PathBuf;
+use tempfile::{NamedTempFile, TempDir};
+
+#[tokio::test]
+async fn test() {
+    // given
+    let _guard = MUTEX.lock().await;
+
+    let test_directory = Path::new("tests");
+    let progress_bar = ProgressBar::hidden();
+
+    let db_file = NamedTempFile::new().unwrap();
+    let pool = establish_connection(db_file.path().to_str().unwrap()).await;
+    let mut connection = pool.acquire().await.unwrap();
+
+    let rom_directory = TempDir::new_in(&test_directory).unwrap();
+    set_rom_directory(PathBuf::from(rom_directory.path()));
+    let tmp_directory = TempDir::new_in(&test_directory).unwrap();
+    set_tmp_directory(PathBuf::from(tmp_directory.path()));
+
+    let matches =
+        subcommand().get_matches_from(&["import-dats", "tests/Test System (20200721).zip"]);
+
+    // when
+    main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    // then
+    let systems = find_systems(&mut connection).await;
+    assert_eq!(systems.len(), 1);
+
+    let system = systems.first().unwrap();
+    assert_eq!(system.name, "Test System");
+
+    assert_eq!(find_games(&mut connection).await.len(), 6);
+    assert_eq!(find_roms(&mut connection).await.len(), 8);
+}
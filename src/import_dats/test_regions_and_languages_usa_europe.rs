@@ -0,0 +1,15 @@
+use super::*;
+
+#[test]
+fn test() {
+    // given
+    let game_name = "Game (USA, Europe) (En,Fr)";
+
+    // when
+    let regions = get_regions_from_game_name(game_name).unwrap();
+    let languages = get_languages_from_game_name(game_name);
+
+    // then
+    assert_eq!(regions, "US-EU");
+    assert_eq!(languages, "En,Fr");
+}
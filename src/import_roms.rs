@@ -1,17 +1,20 @@
 use super::chdman;
-use super::chdman::{AsChd, ChdType};
+use super::chdman::{AsChd, ChdRomfile, ChdType};
 use super::common::*;
 use super::config::*;
+use super::convert_roms;
+use super::convert_roms::ALL_FORMATS;
 use super::ctrtool;
 use super::database::*;
 use super::dolphin;
 use super::dolphin::AsRvz;
-use super::maxcso;
+use super::identify::identify_romfile;
 use super::maxcso::AsXso;
 use super::mimetype::*;
 use super::model::*;
 use super::nsz;
 use super::nsz::AsNsz;
+use super::progress::*;
 use super::prompt::*;
 use super::sevenzip;
 use super::sevenzip::{ArchiveFile, AsArchive};
@@ -20,16 +23,27 @@ use super::SimpleResult;
 use clap::builder::PossibleValuesParser;
 use clap::{Arg, ArgAction, ArgMatches, Command};
 use indicatif::ProgressBar;
+use itertools::Itertools;
 use rayon::prelude::*;
 use sqlx::sqlite::SqliteConnection;
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::ffi::OsString;
 use std::path::Path;
 use std::path::PathBuf;
 use std::{cmp::Ordering, str::FromStr};
-use strum::{IntoEnumIterator, VariantNames};
+use strum::VariantNames;
+use tempfile::TempDir;
+use tokio::fs;
 use walkdir::WalkDir;
 
+#[derive(Clone, Copy, PartialEq)]
+pub enum ImportMode {
+    Move,
+    RenameInPlace,
+    // files are recorded where they are, as reindex does
+    Record,
+}
+
 pub fn subcommand() -> Command {
     Command::new("import-roms")
         .about("Validate and import ROM files or directories into oxyromon")
@@ -49,6 +63,13 @@ pub fn subcommand() -> Command {
                 .required(false)
                 .action(ArgAction::Append),
         )
+        .arg(
+            Arg::new("EXACT_SYSTEM")
+                .long("exact-system")
+                .help("Match system names exactly")
+                .required(false)
+                .action(ArgAction::SetTrue),
+        )
         .arg(
             Arg::new("TRASH")
                 .short('t')
@@ -81,6 +102,13 @@ pub fn subcommand() -> Command {
                 .required(false)
                 .action(ArgAction::SetTrue),
         )
+        .arg(
+            Arg::new("DESWAP")
+                .long("deswap")
+                .help("Convert byte-swapped N64 ROM files to big-endian before importing them")
+                .required(false)
+                .action(ArgAction::SetTrue),
+        )
         .arg(
             Arg::new("HASH")
                 .short('a')
@@ -90,17 +118,39 @@ pub fn subcommand() -> Command {
                 .num_args(1)
                 .value_parser(PossibleValuesParser::new(HashAlgorithm::VARIANTS)),
         )
+        .arg(
+            Arg::new("CONVERT")
+                .long("convert")
+                .help("Convert imported games to their system's format from SYSTEM_FORMATS")
+                .required(false)
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("RENAME_IN_PLACE")
+                .long("rename-in-place")
+                .help("Rename loose ROM files where they are instead of moving them")
+                .required(false)
+                .action(ArgAction::SetTrue)
+                .conflicts_with_all(["CONVERT", "DESWAP", "EXTRACT"]),
+        )
 }
 
 pub async fn main(
     connection: &mut SqliteConnection,
     matches: &ArgMatches,
     progress_bar: &ProgressBar,
-) -> SimpleResult<()> {
+) -> SimpleResult<Summary> {
     let mut systems: Vec<System> = vec![];
     if let Some(system_names) = matches.get_many::<String>("SYSTEM") {
         for system_name in system_names {
-            systems.append(&mut find_systems_by_name_like(connection, system_name).await);
+            systems.append(
+                &mut find_systems_by_name_match(
+                    connection,
+                    system_name,
+                    matches.get_flag("EXACT_SYSTEM"),
+                )
+                .await,
+            );
         }
     }
     systems.dedup_by_key(|system| system.id);
@@ -112,6 +162,18 @@ pub async fn main(
     let trash = matches.get_flag("TRASH");
     let force = matches.get_flag("FORCE");
     let unattended = matches.get_flag("UNATTENDED");
+    let deswap = matches.get_flag("DESWAP");
+    // only systems whose DAT lists big-endian N64 ROMs get deswapped files
+    let deswap_system_ids = if deswap {
+        find_system_ids_by_rom_extension(connection, Z64_EXTENSION).await
+    } else {
+        vec![]
+    };
+    let import_mode = if matches.get_flag("RENAME_IN_PLACE") {
+        ImportMode::RenameInPlace
+    } else {
+        ImportMode::Move
+    };
     let hash_algorithm = matches
         .get_one::<String>("HASH")
         .map(String::as_str)
@@ -121,6 +183,28 @@ pub async fn main(
     let mut system_ids: HashSet<i64> = HashSet::new();
     let mut game_ids: HashSet<i64> = HashSet::new();
 
+    let mut summary = Summary::new();
+    let romfile_ids: HashSet<i64> = find_romfiles(connection)
+        .await
+        .into_par_iter()
+        .map(|romfile| romfile.id)
+        .collect();
+
+    // files outside of the ROM directory can't be recorded where they are
+    if import_mode == ImportMode::RenameInPlace {
+        let rom_directory = get_canonicalized_path(&get_rom_directory(connection).await).await?;
+        for path in matches.get_many::<PathBuf>("ROMS").unwrap() {
+            let path = get_canonicalized_path(&path).await?;
+            if !path.starts_with(&rom_directory) {
+                bail!(
+                    "\"{}\" is outside of the ROM directory, only files under \"{}\" can be renamed in place",
+                    path.as_os_str().to_str().unwrap(),
+                    rom_directory.as_os_str().to_str().unwrap()
+                );
+            }
+        }
+    }
+
     for path in matches.get_many::<PathBuf>("ROMS").unwrap() {
         let tmp_directory = create_tmp_directory(connection).await?;
         let mut path = get_canonicalized_path(&path).await?;
@@ -139,6 +223,21 @@ pub async fn main(
                         .await?;
                 }
                 path = tmp_directory.path().to_path_buf();
+            } else if matches.get_flag("EXTRACT") && is_tar_zst(&path).await? {
+                extract_tar_zst(progress_bar, &path, &tmp_directory.path())?;
+                // a lone top-level directory, e.g. a PS3 JB folder, is imported as such
+                let entries: Vec<PathBuf> = try_with!(
+                    tmp_directory.path().read_dir(),
+                    "Failed to read \"{}\"",
+                    tmp_directory.path().as_os_str().to_str().unwrap()
+                )
+                .filter_map(|entry| entry.ok())
+                .map(|entry| entry.path())
+                .collect();
+                path = match entries.as_slice() {
+                    [entry] if entry.is_dir() => entry.clone(),
+                    _ => tmp_directory.path().to_path_buf(),
+                };
             }
         }
         for system in &systems {
@@ -149,6 +248,11 @@ pub async fn main(
                 Some(system) => find_header_by_system_id(connection, system.id).await,
                 None => None,
             };
+            let deswap = deswap
+                && match system {
+                    Some(system) => deswap_system_ids.contains(&system.id),
+                    None => !deswap_system_ids.is_empty(),
+                };
             if path.is_dir() {
                 if path.join(PS3_DISC_SFB).is_file() {
                     progress_bar.println(format!(
@@ -168,54 +272,235 @@ pub async fn main(
                         }
                     }
                 } else {
-                    let walker = WalkDir::new(&path).into_iter();
-                    for entry in walker.filter_map(|e| e.ok()) {
-                        if entry.path().is_file() {
-                            let (new_system_ids, new_game_ids) = import_rom(
-                                connection,
-                                progress_bar,
-                                &system.as_ref(),
-                                &header,
-                                &entry.path(),
-                                trash,
-                                force,
-                                unattended,
-                                &hash_algorithm,
-                            )
-                            .await?;
-                            system_ids.extend(new_system_ids);
-                            game_ids.extend(new_game_ids);
-                        }
+                    let entries: Vec<walkdir::DirEntry> = WalkDir::new(&path)
+                        .into_iter()
+                        .filter_map(|e| e.ok())
+                        .filter(|entry| entry.path().is_file())
+                        .collect();
+                    let track_hashes_and_sizes = compute_track_hashes_and_sizes(
+                        connection,
+                        progress_bar,
+                        &entries
+                            .iter()
+                            .map(|entry| entry.path().to_path_buf())
+                            .collect::<Vec<PathBuf>>(),
+                        &header,
+                        &hash_algorithm,
+                    )
+                    .await?;
+                    for entry in entries {
+                        summary.add(1, entry.metadata().map_or(0, |m| m.len()), 0);
+                        let entry_path = match deswap {
+                            true => {
+                                get_deswapped_path(progress_bar, entry.path(), &tmp_directory)
+                                    .await?
+                            }
+                            false => entry.path().to_path_buf(),
+                        };
+                        let (new_system_ids, new_game_ids) = import_rom(
+                            connection,
+                            progress_bar,
+                            &system.as_ref(),
+                            &header,
+                            &entry_path,
+                            trash,
+                            force,
+                            unattended,
+                            import_mode,
+                            &hash_algorithm,
+                            &track_hashes_and_sizes,
+                        )
+                        .await?;
+                        system_ids.extend(new_system_ids);
+                        game_ids.extend(new_game_ids);
+                        remove_swapped_source(progress_bar, entry.path(), &entry_path).await?;
                     }
                 }
             } else {
+                summary.add(1, path.metadata().map_or(0, |m| m.len()), 0);
+                let entry_path = match deswap {
+                    true => get_deswapped_path(progress_bar, &path, &tmp_directory).await?,
+                    false => path.clone(),
+                };
                 let (new_system_ids, new_game_ids) = import_rom(
                     connection,
                     progress_bar,
                     &system.as_ref(),
                     &header,
-                    &path,
+                    &entry_path,
                     trash,
                     force,
                     unattended,
+                    import_mode,
                     &hash_algorithm,
+                    &HashMap::new(),
                 )
                 .await?;
                 system_ids.extend(new_system_ids);
                 game_ids.extend(new_game_ids);
+                remove_swapped_source(progress_bar, &path, &entry_path).await?;
             }
             progress_bar.println("");
         }
     }
 
-    for system_id in system_ids {
-        let system = find_system_by_id(connection, system_id).await;
+    for system_id in &system_ids {
+        let system = find_system_by_id(connection, *system_id).await;
         compute_system_completion(connection, progress_bar, &system).await;
     }
 
+    // PS3 JB folders aren't counted as files
+    if summary.count > 0 && game_ids.is_empty() {
+        set_outcome(Outcome::NothingMatched);
+    }
+
+    if matches.get_flag("CONVERT") {
+        convert_games(connection, progress_bar, &system_ids, &game_ids).await?;
+    }
+
+    summary.bytes_out = find_romfiles(connection)
+        .await
+        .into_par_iter()
+        .filter(|romfile| !romfile_ids.contains(&romfile.id))
+        .map(|romfile| romfile.size as u64)
+        .sum();
+    summary.print(progress_bar);
+
+    Ok(summary)
+}
+
+async fn convert_games(
+    connection: &mut SqliteConnection,
+    progress_bar: &ProgressBar,
+    system_ids: &HashSet<i64>,
+    game_ids: &HashSet<i64>,
+) -> SimpleResult<()> {
+    // only the defaults are needed, conversions are driven by the settings
+    let matches = convert_roms::subcommand().get_matches_from(["convert-roms"]);
+    for system_id in system_ids {
+        let mut system = find_system_by_id(connection, *system_id).await;
+        let format = match get_system_value(connection, "SYSTEM_FORMATS", &system.name).await {
+            Some(format) => format.to_uppercase(),
+            None => {
+                progress_bar.println(format!("No format configured for \"{}\"", system.name));
+                continue;
+            }
+        };
+        if !ALL_FORMATS.contains(&format.as_str()) {
+            bail!("Unsupported format \"{}\"", format);
+        }
+        system.arcade = is_arcade(connection, &system).await;
+        let tool_paths = get_system_tool_paths(connection, &system.name).await;
+        if !with_tool_paths(
+            tool_paths.clone(),
+            convert_roms::check_format(progress_bar, &system, &format),
+        )
+        .await?
+        {
+            continue;
+        }
+
+        // games imported during this run, complete ones only like convert-roms
+        let games: Vec<Game> = find_complete_games_by_system_id(connection, system.id)
+            .await
+            .into_iter()
+            .filter(|game| game_ids.contains(&game.id))
+            .collect();
+        if games.is_empty() {
+            continue;
+        }
+
+        progress_bar.println(format!("Converting \"{}\" to {}", system.name, format));
+        with_tool_paths(
+            tool_paths,
+            convert_roms::convert_games(
+                connection,
+                &matches,
+                progress_bar,
+                &system,
+                games,
+                &format,
+                &mut Summary::new(),
+            ),
+        )
+        .await?;
+        progress_bar.println("");
+    }
+    Ok(())
+}
+
+// the deswapped copy stands for the source, which goes away once the copy left the TMP directory
+async fn remove_swapped_source(
+    progress_bar: &ProgressBar,
+    path: &Path,
+    deswapped_path: &Path,
+) -> SimpleResult<()> {
+    if deswapped_path != path && !deswapped_path.is_file() && path.is_file() {
+        remove_file(progress_bar, &path, false).await?;
+    }
     Ok(())
 }
 
+async fn get_deswapped_path(
+    progress_bar: &ProgressBar,
+    path: &Path,
+    tmp_directory: &TempDir,
+) -> SimpleResult<PathBuf> {
+    let deswapped_path = tmp_directory.path().join(format!(
+        "{}.{}",
+        path.file_stem().unwrap().to_str().unwrap(),
+        Z64_EXTENSION
+    ));
+    match deswap_n64(progress_bar, &path, &deswapped_path).await? {
+        true => Ok(deswapped_path),
+        false => Ok(path.to_path_buf()),
+    }
+}
+
+// tracks of loose CUE files are hashed all at once, with the first algorithm tried during import
+async fn compute_track_hashes_and_sizes(
+    connection: &mut SqliteConnection,
+    progress_bar: &ProgressBar,
+    paths: &[PathBuf],
+    header: &Option<Header>,
+    hash_algorithm: &Option<HashAlgorithm>,
+) -> SimpleResult<HashMap<PathBuf, (String, u64)>> {
+    let mut track_hashes_and_sizes: HashMap<PathBuf, (String, u64)> = HashMap::new();
+    if header.is_some() {
+        return Ok(track_hashes_and_sizes);
+    }
+    let hash_algorithms = get_hash_algorithms(connection).await;
+    let hash_algorithm = hash_algorithm
+        .as_ref()
+        .unwrap_or_else(|| hash_algorithms.first().unwrap());
+    for cue_path in paths.iter().filter(|path| {
+        path.extension()
+            .is_some_and(|extension| extension.to_str().unwrap().to_lowercase() == CUE_EXTENSION)
+    }) {
+        let cue = match fs::read_to_string(cue_path).await {
+            Ok(cue) => cue,
+            Err(_) => continue,
+        };
+        let track_romfiles: Vec<CommonRomfile> = get_cue_file_names(&cue)
+            .into_iter()
+            .map(|name| cue_path.with_file_name(name))
+            .filter(|path| path.is_file() && paths.contains(path))
+            .map(|path| CommonRomfile::from_path(&path))
+            .collect::<SimpleResult<Vec<CommonRomfile>>>()?;
+        if track_romfiles.len() > 1 {
+            let hashes_and_sizes =
+                compute_hashes_and_sizes(progress_bar, &track_romfiles, hash_algorithm)?;
+            track_hashes_and_sizes.extend(
+                track_romfiles
+                    .into_iter()
+                    .map(|romfile| romfile.path)
+                    .zip(hashes_and_sizes),
+            );
+        }
+    }
+    Ok(track_hashes_and_sizes)
+}
+
 #[allow(clippy::too_many_arguments)]
 pub async fn import_rom<P: AsRef<Path>>(
     connection: &mut SqliteConnection,
@@ -226,7 +511,9 @@ pub async fn import_rom<P: AsRef<Path>>(
     trash: bool,
     force: bool,
     unattended: bool,
+    import_mode: ImportMode,
     hash_algorithm: &Option<HashAlgorithm>,
+    track_hashes_and_sizes: &HashMap<PathBuf, (String, u64)>,
 ) -> SimpleResult<(HashSet<i64>, HashSet<i64>)> {
     progress_bar.println(format!(
         "Processing \"{}\"",
@@ -267,7 +554,7 @@ pub async fn import_rom<P: AsRef<Path>>(
 
     if ARCHIVE_EXTENSIONS.contains(&extension.as_str()) {
         if sevenzip::get_version().await.is_err() {
-            progress_bar.println("Please install sevenzip");
+            print_missing_tool(progress_bar, "sevenzip");
             return Ok((system_ids, game_ids));
         }
         let (new_system_ids, new_game_ids) = import_archive(
@@ -280,6 +567,7 @@ pub async fn import_rom<P: AsRef<Path>>(
             &extension,
             trash,
             unattended,
+            import_mode,
             hash_algorithm,
         )
         .await?;
@@ -287,7 +575,7 @@ pub async fn import_rom<P: AsRef<Path>>(
         game_ids.extend(new_game_ids);
     } else if CHD_EXTENSION == extension {
         if chdman::get_version().await.is_err() {
-            progress_bar.println("Please install chdman");
+            print_missing_tool(progress_bar, "chdman");
             return Ok((system_ids, game_ids));
         }
         if let Some(ids) = import_chd(
@@ -298,6 +586,7 @@ pub async fn import_rom<P: AsRef<Path>>(
             romfile,
             trash,
             unattended,
+            import_mode,
             hash_algorithm,
         )
         .await?
@@ -307,7 +596,7 @@ pub async fn import_rom<P: AsRef<Path>>(
         };
     } else if CIA_EXTENSION == extension {
         if ctrtool::get_version().await.is_err() {
-            progress_bar.println("Please install ctrtool");
+            print_missing_tool(progress_bar, "ctrtool");
             return Ok((system_ids, game_ids));
         }
         let (new_system_ids, new_game_ids) = import_cia(
@@ -318,15 +607,12 @@ pub async fn import_rom<P: AsRef<Path>>(
             romfile,
             trash,
             unattended,
+            import_mode,
         )
         .await?;
         system_ids.extend(new_system_ids);
         game_ids.extend(new_game_ids);
     } else if CSO_EXTENSION == extension {
-        if maxcso::get_version().await.is_err() {
-            progress_bar.println("Please install maxcso");
-            return Ok((system_ids, game_ids));
-        }
         if let Some(ids) = import_cso(
             &mut transaction,
             progress_bar,
@@ -335,6 +621,7 @@ pub async fn import_rom<P: AsRef<Path>>(
             romfile,
             trash,
             unattended,
+            import_mode,
         )
         .await?
         {
@@ -343,7 +630,7 @@ pub async fn import_rom<P: AsRef<Path>>(
         };
     } else if NSZ_EXTENSION == extension {
         if nsz::get_version().await.is_err() {
-            progress_bar.println("Please install nsz");
+            print_missing_tool(progress_bar, "nsz");
             return Ok((system_ids, game_ids));
         }
         if let Some(ids) = import_nsz(
@@ -354,6 +641,7 @@ pub async fn import_rom<P: AsRef<Path>>(
             romfile,
             trash,
             unattended,
+            import_mode,
         )
         .await?
         {
@@ -362,7 +650,7 @@ pub async fn import_rom<P: AsRef<Path>>(
         };
     } else if RVZ_EXTENSION == extension {
         if dolphin::get_version().await.is_err() {
-            progress_bar.println("Please install dolphin-tool");
+            print_missing_tool(progress_bar, "dolphin-tool");
             return Ok((system_ids, game_ids));
         }
         if let Some(ids) = import_rvz(
@@ -373,6 +661,7 @@ pub async fn import_rom<P: AsRef<Path>>(
             romfile,
             trash,
             unattended,
+            import_mode,
         )
         .await?
         {
@@ -380,10 +669,6 @@ pub async fn import_rom<P: AsRef<Path>>(
             game_ids.insert(ids[1]);
         };
     } else if ZSO_EXTENSION == extension {
-        if maxcso::get_version().await.is_err() {
-            progress_bar.println("Please install maxcso");
-            return Ok((system_ids, game_ids));
-        }
         if let Some(ids) = import_zso(
             &mut transaction,
             progress_bar,
@@ -392,6 +677,7 @@ pub async fn import_rom<P: AsRef<Path>>(
             romfile,
             trash,
             unattended,
+            import_mode,
         )
         .await?
         {
@@ -407,7 +693,9 @@ pub async fn import_rom<P: AsRef<Path>>(
         romfile,
         trash,
         unattended,
+        import_mode,
         hash_algorithm,
+        track_hashes_and_sizes,
     )
     .await?
     {
@@ -415,6 +703,36 @@ pub async fn import_rom<P: AsRef<Path>>(
         game_ids.insert(ids[1]);
     };
 
+    // keep track of where games came from
+    for game_id in &game_ids {
+        let roms = find_roms_with_romfile_by_game_ids(&mut transaction, &[*game_id]).await;
+        let romfile_ids: Vec<i64> = roms.iter().map(|rom| rom.romfile_id.unwrap()).collect();
+        let destination = find_romfiles_by_ids(&mut transaction, &romfile_ids)
+            .await
+            .into_iter()
+            .map(|romfile| romfile.path)
+            .sorted()
+            .dedup()
+            .join(", ");
+        create_event(
+            &mut transaction,
+            *game_id,
+            EventType::Import,
+            path.as_ref().as_os_str().to_str(),
+            &destination,
+        )
+        .await;
+    }
+
+    // forget files that finally found a match
+    if !game_ids.is_empty() {
+        delete_unmatched_by_path(
+            &mut transaction,
+            path.as_ref().as_os_str().to_str().unwrap(),
+        )
+        .await;
+    }
+
     commit_transaction(transaction).await;
 
     Ok((system_ids, game_ids))
@@ -572,10 +890,11 @@ async fn import_archive(
     romfile_extension: &str,
     trash: bool,
     unattended: bool,
+    import_mode: ImportMode,
     hash_algorithm: &Option<HashAlgorithm>,
 ) -> SimpleResult<(HashSet<i64>, HashSet<i64>)> {
     let tmp_directory = create_tmp_directory(connection).await?;
-    let hash_variants = HashAlgorithm::iter().collect::<Vec<HashAlgorithm>>();
+    let hash_variants = get_hash_algorithms(connection).await;
     let hash_algorithms = if hash_algorithm.is_some() {
         vec![hash_algorithm.as_ref().unwrap()]
     } else {
@@ -609,29 +928,27 @@ async fn import_archive(
 
         let mut matched = false;
         for hash_algorithm in &hash_algorithms {
-            let (hash, size) = match header {
+            let hashes_and_sizes = match header {
                 Some(header) => {
                     let romfile = archive_romfile
                         .to_common(progress_bar, &tmp_directory.path())
                         .await?;
-                    let (hash, size) = romfile
-                        .get_headered_hash_and_size(
-                            connection,
-                            progress_bar,
-                            header,
-                            1,
-                            1,
-                            hash_algorithm,
-                        )
-                        .await?;
+                    let hashes_and_sizes = get_header_variant_hashes_and_sizes(
+                        connection,
+                        progress_bar,
+                        &romfile,
+                        header,
+                        hash_algorithm,
+                    )
+                    .await?;
                     romfile.delete(progress_bar, true).await?;
-                    (hash, size)
+                    hashes_and_sizes
                 }
-                None => {
+                None => vec![
                     archive_romfile
                         .get_hash_and_size(connection, progress_bar, 1, 1, hash_algorithm)
-                        .await?
-                }
+                        .await?,
+                ],
             };
 
             let mut game_names: Vec<&str> = vec![];
@@ -645,30 +962,46 @@ async fn import_archive(
                     .unwrap(),
             );
 
+            // rom names can contain directories themselves, e.g. MAME software lists, so the
+            // whole member path is tried first, then without a leading game directory
             let rom_path = Path::new(&archive_romfile.path);
+            let mut rom_names: Vec<&str> = vec![&archive_romfile.path];
+            if let Some((game_name, rom_name)) = archive_romfile.path.split_once('/') {
+                game_names.push(game_name);
+                rom_names.push(rom_name);
+            }
             if let Some(path) = rom_path.parent() {
                 if let Some(file_name) = path.file_name() {
                     let game_name = file_name.to_str().unwrap();
-                    if !game_name.is_empty() {
+                    if !game_name.is_empty() && !game_names.contains(&game_name) {
                         game_names.push(game_name);
                     }
                 }
             }
-            let rom_name = rom_path.file_name().unwrap().to_str();
+            let rom_name = rom_path.file_name().unwrap().to_str().unwrap();
+            if !rom_names.contains(&rom_name) {
+                rom_names.push(rom_name);
+            }
 
-            let rom_game_system = find_rom_by_size_and_hash(
-                connection,
-                progress_bar,
-                size,
-                &hash,
-                system,
-                game_ids,
-                game_names.as_slice(),
-                rom_name,
-                hash_algorithm,
-                unattended,
-            )
-            .await?;
+            let mut rom_game_system = None;
+            for (hash, size) in hashes_and_sizes {
+                rom_game_system = find_rom_by_size_and_hash(
+                    connection,
+                    progress_bar,
+                    size,
+                    &hash,
+                    system,
+                    game_ids,
+                    game_names.as_slice(),
+                    &rom_names,
+                    hash_algorithm,
+                    unattended,
+                )
+                .await?;
+                if rom_game_system.is_some() {
+                    break;
+                }
+            }
             if let Some((rom, game, system)) = rom_game_system {
                 matched = true;
                 new_system_ids.insert(system.id);
@@ -677,8 +1010,8 @@ async fn import_archive(
                 break;
             }
         }
-        if !matched && trash && romfiles_count == 1 {
-            move_to_trash(connection, progress_bar, &romfile).await?;
+        if !matched && romfiles_count == 1 {
+            quarantine(connection, progress_bar, &romfile, trash).await?;
         }
     }
 
@@ -730,6 +1063,7 @@ async fn import_archive(
                 _ => system_directory.join(format!("{}.{}", &game.name, &romfile_extension)),
             };
 
+            let new_path = get_import_path(import_mode, &romfile, new_path);
             // move file
             romfile.rename(progress_bar, &new_path, false).await?;
 
@@ -749,6 +1083,14 @@ async fn import_archive(
         }
     }
 
+    // recorded archives can't be split
+    if import_mode == ImportMode::Record {
+        if !roms_games_systems_archive_romfiles.is_empty() {
+            progress_bar.println("Archive doesn't contain a single full game, skipping");
+        }
+        return Ok((HashSet::new(), HashSet::new()));
+    }
+
     // all other cases
     for (rom, game, system, archive_romfile) in roms_games_systems_archive_romfiles {
         let original_romfile = archive_romfile
@@ -785,16 +1127,47 @@ async fn import_chd(
     romfile: CommonRomfile,
     trash: bool,
     unattended: bool,
+    import_mode: ImportMode,
     hash_algorithm: &Option<HashAlgorithm>,
 ) -> SimpleResult<Option<[i64; 2]>> {
     let tmp_directory = create_tmp_directory(connection).await?;
-    let hash_variants = HashAlgorithm::iter().collect::<Vec<HashAlgorithm>>();
+    let mut hash_variants = get_hash_algorithms(connection).await;
+    // CHD headers carry the SHA1 of their data, try it first
+    hash_variants.sort_by_key(|hash_algorithm| hash_algorithm != &HashAlgorithm::Sha1);
     let hash_algorithms = if hash_algorithm.is_some() {
         vec![hash_algorithm.as_ref().unwrap()]
     } else {
-        hash_variants.iter().rev().collect() // reverse iterator so SHA1 is tried first
+        hash_variants.iter().collect()
     };
     let chd_romfile = romfile.as_chd().await?;
+    // MAME's CHD DATs have no size information and use the CHD SHA1
+    let disks = match system {
+        Some(system) => {
+            find_roms_without_romfile_by_size_and_sha1_and_system_id(
+                connection,
+                0,
+                &chd_romfile.chd_sha1,
+                system.id,
+            )
+            .await
+        }
+        None => {
+            find_roms_without_romfile_by_size_and_sha1(connection, 0, &chd_romfile.chd_sha1).await
+        }
+    };
+    if disks.iter().any(|rom| rom.disk) {
+        return import_mame_disk(
+            connection,
+            progress_bar,
+            system,
+            game_ids,
+            chd_romfile,
+            trash,
+            unattended,
+            import_mode,
+        )
+        .await;
+    }
     match chd_romfile.chd_type {
         ChdType::Cd => {
             if chd_romfile.track_count > 1
@@ -814,18 +1187,26 @@ async fn import_chd(
                 .to_cue_bin(progress_bar, &tmp_directory.path(), None, &[], true)
                 .await?;
 
+            // tracks are hashed all at once, one algorithm at a time and only when needed
+            let mut hashes_and_sizes_by_algorithm: Vec<Vec<(String, u64)>> = vec![];
+
             let mut roms_games_systems: Vec<(Rom, Game, System)> = vec![];
             let mut new_game_ids: HashSet<i64> = HashSet::new();
-            for bin_romfile in &cue_bin_romfile.bin_romfiles {
-                for hash_algorithm in &hash_algorithms {
-                    let (hash, size) = bin_romfile
-                        .get_hash_and_size(connection, progress_bar, 1, 1, hash_algorithm)
-                        .await?;
+            for i in 0..cue_bin_romfile.bin_romfiles.len() {
+                for (j, hash_algorithm) in hash_algorithms.iter().enumerate() {
+                    if hashes_and_sizes_by_algorithm.len() == j {
+                        hashes_and_sizes_by_algorithm.push(compute_hashes_and_sizes(
+                            progress_bar,
+                            &cue_bin_romfile.bin_romfiles,
+                            hash_algorithm,
+                        )?);
+                    }
+                    let (hash, size) = &hashes_and_sizes_by_algorithm[j][i];
                     if let Some(rom_game_system) = find_rom_by_size_and_hash(
                         connection,
                         progress_bar,
-                        size,
-                        &hash,
+                        *size,
+                        hash,
                         system,
                         if new_game_ids.is_empty() {
                             game_ids
@@ -833,7 +1214,7 @@ async fn import_chd(
                             &new_game_ids
                         },
                         &[],
-                        None,
+                        &[],
                         hash_algorithm,
                         unattended,
                     )
@@ -871,6 +1252,7 @@ async fn import_chd(
                 let new_chd_path =
                     system_directory.join(format!("{}.{}", game.name, CHD_EXTENSION));
 
+                let new_chd_path = get_import_path(import_mode, &chd_romfile.romfile, new_chd_path);
                 // move chd if needed
                 chd_romfile
                     .romfile
@@ -894,7 +1276,7 @@ async fn import_chd(
                 let (hash, size) = chd_romfile
                     .get_hash_and_size(connection, progress_bar, 1, 1, hash_algorithm)
                     .await?;
-                let mut rom_game_system = find_rom_by_size_and_hash(
+                let rom_game_system = find_rom_by_size_and_hash(
                     connection,
                     progress_bar,
                     size,
@@ -902,27 +1284,11 @@ async fn import_chd(
                     system,
                     game_ids,
                     &[],
-                    None,
+                    &[],
                     hash_algorithm,
                     unattended,
                 )
                 .await?;
-                // MAME's CHD DATs have no size information and use the CHD SHA1
-                if rom_game_system.is_none() && *hash_algorithm == &HashAlgorithm::Sha1 {
-                    rom_game_system = find_rom_by_size_and_hash(
-                        connection,
-                        progress_bar,
-                        0,
-                        &chd_romfile.chd_sha1,
-                        system,
-                        game_ids,
-                        &[],
-                        None,
-                        hash_algorithm,
-                        unattended,
-                    )
-                    .await?;
-                }
                 if let Some((rom, game, system)) = rom_game_system {
                     let system_directory = get_system_directory(connection, &system).await?;
 
@@ -930,6 +1296,8 @@ async fn import_chd(
                         .join(&rom.name)
                         .with_extension(CHD_EXTENSION);
 
+                    let new_chd_path =
+                        get_import_path(import_mode, &chd_romfile.romfile, new_chd_path);
                     // move CHD if needed
                     chd_romfile
                         .romfile
@@ -943,9 +1311,7 @@ async fn import_chd(
                     return Ok(Some([system.id, game.id]));
                 }
             }
-            if trash {
-                move_to_trash(connection, progress_bar, &chd_romfile.romfile).await?;
-            }
+            quarantine(connection, progress_bar, &chd_romfile.romfile, trash).await?;
             Ok(None)
         }
         ChdType::Hd => {
@@ -953,7 +1319,7 @@ async fn import_chd(
                 let (hash, size) = chd_romfile
                     .get_hash_and_size(connection, progress_bar, 1, 1, hash_algorithm)
                     .await?;
-                let mut rom_game_system = find_rom_by_size_and_hash(
+                let rom_game_system = find_rom_by_size_and_hash(
                     connection,
                     progress_bar,
                     size,
@@ -961,27 +1327,11 @@ async fn import_chd(
                     system,
                     game_ids,
                     &[],
-                    None,
+                    &[],
                     hash_algorithm,
                     unattended,
                 )
                 .await?;
-                // MAME's CHD DATs have no size information and use the CHD SHA1
-                if rom_game_system.is_none() && *hash_algorithm == &HashAlgorithm::Sha1 {
-                    rom_game_system = find_rom_by_size_and_hash(
-                        connection,
-                        progress_bar,
-                        0,
-                        &chd_romfile.chd_sha1,
-                        system,
-                        game_ids,
-                        &[],
-                        None,
-                        hash_algorithm,
-                        unattended,
-                    )
-                    .await?;
-                }
                 if let Some((rom, game, system)) = rom_game_system {
                     let system_directory = get_system_directory(connection, &system).await?;
 
@@ -998,6 +1348,8 @@ async fn import_chd(
                             .with_extension(CHD_EXTENSION)
                     };
 
+                    let new_chd_path =
+                        get_import_path(import_mode, &chd_romfile.romfile, new_chd_path);
                     // move CHD if needed
                     chd_romfile
                         .romfile
@@ -1011,9 +1363,7 @@ async fn import_chd(
                     return Ok(Some([system.id, game.id]));
                 }
             }
-            if trash {
-                move_to_trash(connection, progress_bar, &chd_romfile.romfile).await?;
-            }
+            quarantine(connection, progress_bar, &chd_romfile.romfile, trash).await?;
             Ok(None)
         }
         ChdType::Ld => {
@@ -1021,7 +1371,7 @@ async fn import_chd(
                 let (hash, size) = chd_romfile
                     .get_hash_and_size(connection, progress_bar, 1, 1, hash_algorithm)
                     .await?;
-                let mut rom_game_system = find_rom_by_size_and_hash(
+                let rom_game_system = find_rom_by_size_and_hash(
                     connection,
                     progress_bar,
                     size,
@@ -1029,27 +1379,11 @@ async fn import_chd(
                     system,
                     game_ids,
                     &[],
-                    None,
+                    &[],
                     hash_algorithm,
                     unattended,
                 )
                 .await?;
-                // MAME's CHD DATs have no size information and use the CHD SHA1
-                if rom_game_system.is_none() && *hash_algorithm == &HashAlgorithm::Sha1 {
-                    rom_game_system = find_rom_by_size_and_hash(
-                        connection,
-                        progress_bar,
-                        0,
-                        &chd_romfile.chd_sha1,
-                        system,
-                        game_ids,
-                        &[],
-                        None,
-                        hash_algorithm,
-                        unattended,
-                    )
-                    .await?;
-                }
                 if let Some((rom, game, system)) = rom_game_system {
                     let system_directory = get_system_directory(connection, &system).await?;
 
@@ -1066,6 +1400,8 @@ async fn import_chd(
                             .with_extension(CHD_EXTENSION)
                     };
 
+                    let new_chd_path =
+                        get_import_path(import_mode, &chd_romfile.romfile, new_chd_path);
                     // move CHD if needed
                     chd_romfile
                         .romfile
@@ -1079,9 +1415,7 @@ async fn import_chd(
                     return Ok(Some([system.id, game.id]));
                 }
             }
-            if trash {
-                move_to_trash(connection, progress_bar, &chd_romfile.romfile).await?;
-            }
+            quarantine(connection, progress_bar, &chd_romfile.romfile, trash).await?;
             Ok(None)
         }
     }
@@ -1096,6 +1430,7 @@ async fn import_cia(
     romfile: CommonRomfile,
     trash: bool,
     unattended: bool,
+    import_mode: ImportMode,
 ) -> SimpleResult<(HashSet<i64>, HashSet<i64>)> {
     let tmp_directory = create_tmp_directory(connection).await?;
     let cia_infos = ctrtool::parse_cia(progress_bar, &romfile.path).await?;
@@ -1115,7 +1450,7 @@ async fn import_cia(
         ));
 
         let extracted_romfile = CommonRomfile::from_path(&extracted_path)?;
-        for hash_algorithm in HashAlgorithm::iter() {
+        for hash_algorithm in get_hash_algorithms(connection).await {
             let (hash, size) = extracted_romfile
                 .get_hash_and_size(connection, progress_bar, 1, 1, &hash_algorithm)
                 .await?;
@@ -1130,7 +1465,7 @@ async fn import_cia(
                     game_names.push(game_name);
                 }
             }
-            let rom_name = path.file_name().unwrap().to_str();
+            let rom_name = path.file_name().unwrap().to_str().unwrap();
 
             if let Some((rom, game, system)) = find_rom_by_size_and_hash(
                 connection,
@@ -1140,7 +1475,7 @@ async fn import_cia(
                 system,
                 game_ids,
                 game_names.as_slice(),
-                rom_name,
+                &[rom_name],
                 &hash_algorithm,
                 unattended,
             )
@@ -1174,6 +1509,7 @@ async fn import_cia(
 
             let new_path = system_directory.join(format!("{}.cia", &game.name));
 
+            let new_path = get_import_path(import_mode, &romfile, new_path);
             // move file
             romfile.rename(progress_bar, &new_path, false).await?;
 
@@ -1193,13 +1529,81 @@ async fn import_cia(
         }
     }
 
-    if trash {
-        move_to_trash(connection, progress_bar, &romfile).await?;
-    }
+    quarantine(connection, progress_bar, &romfile, trash).await?;
 
     Ok((new_system_ids, new_game_ids))
 }
 
+#[allow(clippy::too_many_arguments)]
+async fn import_mame_disk(
+    connection: &mut SqliteConnection,
+    progress_bar: &ProgressBar,
+    system: &Option<&System>,
+    game_ids: &HashSet<i64>,
+    chd_romfile: ChdRomfile,
+    trash: bool,
+    unattended: bool,
+    import_mode: ImportMode,
+) -> SimpleResult<Option<[i64; 2]>> {
+    // the CHD SHA1 is only trustworthy if the data matches it
+    if let Err(error) = chdman::verify_chd(
+        progress_bar,
+        &chd_romfile.romfile,
+        chd_romfile.parent_romfile.as_ref(),
+    )
+    .await
+    {
+        progress_bar.println(format!("Invalid CHD: {}", error));
+        if trash {
+            move_to_trash(connection, progress_bar, &chd_romfile.romfile).await?;
+        }
+        return Ok(None);
+    }
+
+    if let Some((rom, game, system)) = find_rom_by_size_and_hash(
+        connection,
+        progress_bar,
+        0,
+        &chd_romfile.chd_sha1,
+        system,
+        game_ids,
+        &[],
+        &[],
+        &HashAlgorithm::Sha1,
+        unattended,
+    )
+    .await?
+    {
+        let system_directory = get_system_directory(connection, &system).await?;
+
+        // put MAME CHDs in a subdirectory
+        let new_chd_path = if system.arcade {
+            system_directory
+                .join(&game.name)
+                .join(&rom.name)
+                .with_extension(CHD_EXTENSION)
+        } else {
+            system_directory
+                .join(&rom.name)
+                .with_extension(CHD_EXTENSION)
+        };
+
+        let new_chd_path = get_import_path(import_mode, &chd_romfile.romfile, new_chd_path);
+        // move CHD if needed
+        chd_romfile
+            .romfile
+            .rename(progress_bar, &new_chd_path, false)
+            .await?;
+
+        // persist in database
+        create_or_update_romfile(connection, progress_bar, &new_chd_path, &[&rom]).await?;
+
+        return Ok(Some([system.id, game.id]));
+    }
+
+    Ok(None)
+}
+
 #[allow(clippy::too_many_arguments)]
 async fn import_cso(
     connection: &mut SqliteConnection,
@@ -1209,9 +1613,10 @@ async fn import_cso(
     romfile: CommonRomfile,
     trash: bool,
     unattended: bool,
+    import_mode: ImportMode,
 ) -> SimpleResult<Option<[i64; 2]>> {
     let cso_romfile = romfile.as_xso().await?;
-    for hash_algorithm in HashAlgorithm::iter() {
+    for hash_algorithm in get_hash_algorithms(connection).await {
         let (hash, size) = cso_romfile
             .get_hash_and_size(connection, progress_bar, 1, 1, &hash_algorithm)
             .await?;
@@ -1223,7 +1628,7 @@ async fn import_cso(
             system,
             game_ids,
             &[],
-            None,
+            &[],
             &hash_algorithm,
             unattended,
         )
@@ -1233,6 +1638,7 @@ async fn import_cso(
             let new_path = system_directory
                 .join(&rom.name)
                 .with_extension(CSO_EXTENSION);
+            let new_path = get_import_path(import_mode, &cso_romfile.romfile, new_path);
             // move CSO if needed
             cso_romfile
                 .romfile
@@ -1243,9 +1649,7 @@ async fn import_cso(
             return Ok(Some([system.id, game.id]));
         }
     }
-    if trash {
-        move_to_trash(connection, progress_bar, &cso_romfile.romfile).await?;
-    }
+    quarantine(connection, progress_bar, &cso_romfile.romfile, trash).await?;
     Ok(None)
 }
 
@@ -1258,9 +1662,10 @@ async fn import_nsz(
     romfile: CommonRomfile,
     trash: bool,
     unattended: bool,
+    import_mode: ImportMode,
 ) -> SimpleResult<Option<[i64; 2]>> {
     let nsz_romfile = romfile.as_nsz()?;
-    for hash_algorithm in HashAlgorithm::iter() {
+    for hash_algorithm in get_hash_algorithms(connection).await {
         let (hash, size) = nsz_romfile
             .get_hash_and_size(connection, progress_bar, 1, 1, &hash_algorithm)
             .await?;
@@ -1272,7 +1677,7 @@ async fn import_nsz(
             system,
             game_ids,
             &[],
-            None,
+            &[],
             &hash_algorithm,
             unattended,
         )
@@ -1282,6 +1687,7 @@ async fn import_nsz(
             let new_nsz_path = system_directory
                 .join(&rom.name)
                 .with_extension(NSZ_EXTENSION);
+            let new_nsz_path = get_import_path(import_mode, &nsz_romfile.romfile, new_nsz_path);
             // move NSZ if needed
             nsz_romfile
                 .romfile
@@ -1292,9 +1698,7 @@ async fn import_nsz(
             return Ok(Some([system.id, game.id]));
         }
     }
-    if trash {
-        move_to_trash(connection, progress_bar, &nsz_romfile.romfile).await?;
-    }
+    quarantine(connection, progress_bar, &nsz_romfile.romfile, trash).await?;
     Ok(None)
 }
 
@@ -1307,9 +1711,10 @@ async fn import_rvz(
     romfile: CommonRomfile,
     trash: bool,
     unattended: bool,
+    import_mode: ImportMode,
 ) -> SimpleResult<Option<[i64; 2]>> {
     let rvz_romfile = romfile.as_rvz()?;
-    for hash_algorithm in HashAlgorithm::iter() {
+    for hash_algorithm in get_hash_algorithms(connection).await {
         let (hash, size) = rvz_romfile
             .get_hash_and_size(connection, progress_bar, 1, 1, &hash_algorithm)
             .await?;
@@ -1321,7 +1726,7 @@ async fn import_rvz(
             system,
             game_ids,
             &[],
-            None,
+            &[],
             &hash_algorithm,
             unattended,
         )
@@ -1331,6 +1736,7 @@ async fn import_rvz(
             let new_rvz_path = system_directory
                 .join(&rom.name)
                 .with_extension(RVZ_EXTENSION);
+            let new_rvz_path = get_import_path(import_mode, &rvz_romfile.romfile, new_rvz_path);
             // move RVZ if needed
             rvz_romfile
                 .romfile
@@ -1341,9 +1747,7 @@ async fn import_rvz(
             return Ok(Some([system.id, game.id]));
         }
     }
-    if trash {
-        move_to_trash(connection, progress_bar, &rvz_romfile.romfile).await?;
-    }
+    quarantine(connection, progress_bar, &rvz_romfile.romfile, trash).await?;
     Ok(None)
 }
 
@@ -1356,9 +1760,10 @@ async fn import_zso(
     romfile: CommonRomfile,
     trash: bool,
     unattended: bool,
+    import_mode: ImportMode,
 ) -> SimpleResult<Option<[i64; 2]>> {
     let zso_romfile = romfile.as_xso().await?;
-    for hash_algorithm in HashAlgorithm::iter() {
+    for hash_algorithm in get_hash_algorithms(connection).await {
         let (hash, size) = zso_romfile
             .get_hash_and_size(connection, progress_bar, 1, 1, &hash_algorithm)
             .await?;
@@ -1370,7 +1775,7 @@ async fn import_zso(
             system,
             game_ids,
             &[],
-            None,
+            &[],
             &hash_algorithm,
             unattended,
         )
@@ -1380,6 +1785,7 @@ async fn import_zso(
             let new_zso_path = system_directory
                 .join(&rom.name)
                 .with_extension(ZSO_EXTENSION);
+            let new_zso_path = get_import_path(import_mode, &zso_romfile.romfile, new_zso_path);
             // move ZSO if needed
             zso_romfile
                 .romfile
@@ -1390,9 +1796,7 @@ async fn import_zso(
             return Ok(Some([system.id, game.id]));
         }
     }
-    if trash {
-        move_to_trash(connection, progress_bar, &zso_romfile.romfile).await?;
-    }
+    quarantine(connection, progress_bar, &zso_romfile.romfile, trash).await?;
     Ok(None)
 }
 
@@ -1406,49 +1810,100 @@ async fn import_other(
     romfile: CommonRomfile,
     trash: bool,
     unattended: bool,
+    import_mode: ImportMode,
     hash_algorithm: &Option<HashAlgorithm>,
+    track_hashes_and_sizes: &HashMap<PathBuf, (String, u64)>,
 ) -> SimpleResult<Option<[i64; 2]>> {
-    let hash_variants = HashAlgorithm::iter().collect::<Vec<HashAlgorithm>>();
+    let hash_variants = get_hash_algorithms(connection).await;
     let hash_algorithms = if hash_algorithm.is_some() {
         vec![hash_algorithm.as_ref().unwrap()]
     } else {
         hash_variants.iter().collect()
     };
+    let header_title = read_header_title(&romfile.path).await?;
 
-    for hash_algorithm in &hash_algorithms {
-        let (hash, size) = match header {
-            Some(header) => {
-                romfile
-                    .get_headered_hash_and_size(
-                        connection,
-                        progress_bar,
-                        header,
-                        1,
-                        1,
-                        hash_algorithm,
-                    )
-                    .await?
+    if get_bool(connection, "CHECK_WII_PARTITIONS").await
+        && romfile.path.extension().is_some_and(|extension| {
+            extension.to_str().unwrap().to_lowercase() == ISO_EXTENSION
+        })
+    {
+        if let Some(partitions) = read_wii_partitions(&romfile.path).await? {
+            for partition in partitions {
+                progress_bar.println(format!(
+                    "Partition {} ({}) at 0x{:x}: {}",
+                    partition.index,
+                    partition.get_type_name(),
+                    partition.offset,
+                    if partition.valid {
+                        "H3 table matches TMD"
+                    } else {
+                        "H3 table doesn't match TMD"
+                    }
+                ));
             }
-            None => {
-                romfile
-                    .get_hash_and_size(connection, progress_bar, 1, 1, hash_algorithm)
-                    .await?
+        }
+    }
+
+    for (i, hash_algorithm) in hash_algorithms.iter().enumerate() {
+        let hashes_and_sizes = match header {
+            Some(header) => {
+                get_header_variant_hashes_and_sizes(
+                    connection,
+                    progress_bar,
+                    &romfile,
+                    header,
+                    hash_algorithm,
+                )
+                .await?
             }
+            None => match track_hashes_and_sizes.get(&romfile.path) {
+                // loose tracks were hashed ahead of time with the first algorithm
+                Some(hash_and_size) if i == 0 => vec![hash_and_size.clone()],
+                _ => vec![
+                    romfile
+                        .get_hash_and_size(connection, progress_bar, 1, 1, hash_algorithm)
+                        .await?,
+                ],
+            },
         };
-        let rom_game_system = find_rom_by_size_and_hash(
-            connection,
-            progress_bar,
-            size,
-            &hash,
-            system,
-            game_ids,
-            &[],
-            None,
-            hash_algorithm,
-            unattended,
-        )
-        .await?;
+        let mut rom_game_system = None;
+        for (hash, size) in hashes_and_sizes {
+            // all empty files share the same hashes, only their names can tell them apart
+            let mut game_names: Vec<&str> = vec![];
+            let mut rom_names: Vec<&str> = vec![];
+            if size == 0 {
+                game_names.push(romfile.path.file_stem().unwrap().to_str().unwrap());
+                if let Some(file_name) = romfile.path.parent().and_then(|path| path.file_name()) {
+                    game_names.push(file_name.to_str().unwrap());
+                }
+                rom_names.push(romfile.path.file_name().unwrap().to_str().unwrap());
+            }
+            rom_game_system = find_rom_by_size_and_hash(
+                connection,
+                progress_bar,
+                size,
+                &hash,
+                system,
+                game_ids,
+                game_names.as_slice(),
+                rom_names.as_slice(),
+                hash_algorithm,
+                unattended,
+            )
+            .await?;
+            if rom_game_system.is_some() {
+                break;
+            }
+        }
         if let Some((rom, game, system)) = rom_game_system {
+            print_entropy_warning(connection, progress_bar, &romfile, &system).await?;
+            if import_mode == ImportMode::RenameInPlace {
+                let new_path = romfile.path.with_file_name(&rom.name);
+                romfile.rename(progress_bar, &new_path, false).await?;
+                create_or_update_romfile(connection, progress_bar, &new_path, &[&rom]).await?;
+                update_rom_header_title(connection, rom.id, header_title.as_deref()).await;
+                return Ok(Some([system.id, game.id]));
+            }
             let system_directory = get_system_directory(connection, &system).await?;
             let new_path;
             // put arcade roms and JB folders in subdirectories
@@ -1458,19 +1913,75 @@ async fn import_other(
             } else {
                 new_path = system_directory.join(&rom.name);
             }
+            let new_path = get_import_path(import_mode, &romfile, new_path);
             // move file if needed
             romfile.rename(progress_bar, &new_path, false).await?;
             // persist in database
             create_or_update_romfile(connection, progress_bar, &new_path, &[&rom]).await?;
+            update_rom_header_title(connection, rom.id, header_title.as_deref()).await;
             return Ok(Some([system.id, game.id]));
         }
     }
-    if trash {
-        move_to_trash(connection, progress_bar, &romfile).await?;
+    if let Some(header_title) = header_title {
+        progress_bar.println(format!("Header title: \"{}\"", header_title));
+    }
+    if get_bool(connection, "CHECK_SECTOR_ALIGNMENT").await {
+        if let Some(warning) = get_sector_alignment_warning(&romfile.path) {
+            progress_bar.println(warning);
+        }
+    }
+    if let Some(system) = system {
+        print_entropy_warning(connection, progress_bar, &romfile, system).await?;
     }
+    quarantine(connection, progress_bar, &romfile, trash).await?;
     Ok(None)
 }
 
+async fn print_entropy_warning(
+    connection: &mut SqliteConnection,
+    progress_bar: &ProgressBar,
+    romfile: &CommonRomfile,
+    system: &System,
+) -> SimpleResult<()> {
+    // only read the whole file for systems with known expectations
+    if get_bool(connection, "CHECK_ENTROPY").await && get_entropy_range(&system.name).is_some() {
+        let entropy = compute_file_entropy(&romfile.path).await?;
+        if let Some(warning) = get_entropy_warning(&romfile.path, &system.name, entropy) {
+            progress_bar.println(warning);
+        }
+    }
+    Ok(())
+}
+
+async fn get_header_variant_hashes_and_sizes(
+    connection: &mut SqliteConnection,
+    progress_bar: &ProgressBar,
+    romfile: &CommonRomfile,
+    header: &Header,
+    hash_algorithm: &HashAlgorithm,
+) -> SimpleResult<Vec<(String, u64)>> {
+    // DATs may list headerless or headered dumps, try the whole file as well when a header is found
+    let mut hashes_and_sizes = vec![
+        romfile
+            .get_headered_hash_and_size(connection, progress_bar, header, 1, 1, hash_algorithm)
+            .await?,
+    ];
+    let size = try_with!(
+        romfile.path.metadata(),
+        "Failed to read \"{}\" metadata",
+        romfile.path.as_os_str().to_str().unwrap()
+    )
+    .len();
+    if hashes_and_sizes.first().unwrap().1 != size {
+        hashes_and_sizes.push(
+            romfile
+                .get_hash_and_size(connection, progress_bar, 1, 1, hash_algorithm)
+                .await?,
+        );
+    }
+    Ok(hashes_and_sizes)
+}
+
 #[allow(clippy::too_many_arguments)]
 async fn find_rom_by_size_and_hash(
     connection: &mut SqliteConnection,
@@ -1480,47 +1991,66 @@ async fn find_rom_by_size_and_hash(
     system: &Option<&System>,
     game_ids: &HashSet<i64>,
     game_names: &[&str],
-    rom_name: Option<&str>,
+    rom_names: &[&str],
     hash_algorithm: &HashAlgorithm,
     unattended: bool,
 ) -> SimpleResult<Option<(Rom, Game, System)>> {
     let mut rom_game_system: Option<(Rom, Game, System)> = None;
     let mut roms: Vec<Rom> = vec![];
 
-    // first try matching with game and rom names
-    if !game_names.is_empty() && rom_name.is_some() {
-        match hash_algorithm {
-            HashAlgorithm::Crc => {
-                if let Some(system) = system {
-                    find_roms_without_romfile_by_name_and_size_and_crc_and_game_names_and_system_id(
-                        connection,
-                        rom_name.unwrap(),
-                        size,
-                        hash,
-                        game_names,
-                        system.id,
-                    )
-                    .await
-                    .into_iter()
-                    .for_each(|rom| roms.push(rom))
-                } else {
-                    find_roms_without_romfile_by_name_and_size_and_crc_and_game_names(
-                        connection,
-                        rom_name.unwrap(),
-                        size,
-                        hash,
-                        game_names,
-                    )
-                    .await
-                    .into_iter()
-                    .for_each(|rom| roms.push(rom))
+    // first try matching with game and rom names, most specific rom name first
+    if !game_names.is_empty() {
+        for rom_name in rom_names {
+            match hash_algorithm {
+                HashAlgorithm::Crc => {
+                    if let Some(system) = system {
+                        find_roms_without_romfile_by_name_and_size_and_crc_and_game_names_and_system_id(
+                            connection,
+                            rom_name,
+                            size,
+                            hash,
+                            game_names,
+                            system.id,
+                        )
+                        .await
+                        .into_iter()
+                        .for_each(|rom| roms.push(rom))
+                    } else {
+                        find_roms_without_romfile_by_name_and_size_and_crc_and_game_names(
+                            connection, rom_name, size, hash, game_names,
+                        )
+                        .await
+                        .into_iter()
+                        .for_each(|rom| roms.push(rom))
+                    }
                 }
-            }
-            HashAlgorithm::Md5 => {
-                if let Some(system) = system {
-                    find_roms_without_romfile_by_name_and_size_and_md5_and_game_names_and_system_id(
+                HashAlgorithm::Md5 => {
+                    if let Some(system) = system {
+                        find_roms_without_romfile_by_name_and_size_and_md5_and_game_names_and_system_id(
+                            connection,
+                            rom_name,
+                            size,
+                            hash,
+                            game_names,
+                            system.id,
+                        )
+                        .await
+                        .into_iter()
+                        .for_each(|rom| roms.push(rom))
+                    } else {
+                        find_roms_without_romfile_by_name_and_size_and_md5_and_game_names(
+                            connection, rom_name, size, hash, game_names,
+                        )
+                        .await
+                        .into_iter()
+                        .for_each(|rom| roms.push(rom))
+                    }
+                }
+                HashAlgorithm::Sha1 => {
+                    if let Some(system) = system {
+                        find_roms_without_romfile_by_name_and_size_and_sha1_and_game_names_and_system_id(
                         connection,
-                        rom_name.unwrap(),
+                        rom_name,
                         size,
                         hash,
                         game_names,
@@ -1529,46 +2059,42 @@ async fn find_rom_by_size_and_hash(
                     .await
                     .into_iter()
                     .for_each(|rom| roms.push(rom))
-                } else {
-                    find_roms_without_romfile_by_name_and_size_and_md5_and_game_names(
-                        connection,
-                        rom_name.unwrap(),
-                        size,
-                        hash,
-                        game_names,
-                    )
-                    .await
-                    .into_iter()
-                    .for_each(|rom| roms.push(rom))
+                    } else {
+                        find_roms_without_romfile_by_name_and_size_and_sha1_and_game_names(
+                            connection, rom_name, size, hash, game_names,
+                        )
+                        .await
+                        .into_iter()
+                        .for_each(|rom| roms.push(rom))
+                    }
                 }
-            }
-            HashAlgorithm::Sha1 => {
-                if let Some(system) = system {
-                    find_roms_without_romfile_by_name_and_size_and_sha1_and_game_names_and_system_id(
-                    connection,
-                    rom_name.unwrap(),
-                    size,
-                    hash,
-                    game_names,
-                    system.id,
-                )
-                .await
-                .into_iter()
-                .for_each(|rom| roms.push(rom))
-                } else {
-                    find_roms_without_romfile_by_name_and_size_and_sha1_and_game_names(
+                HashAlgorithm::Sha256 => {
+                    if let Some(system) = system {
+                        find_roms_without_romfile_by_name_and_size_and_sha256_and_game_names_and_system_id(
                         connection,
-                        rom_name.unwrap(),
+                        rom_name,
                         size,
                         hash,
                         game_names,
+                        system.id,
                     )
                     .await
                     .into_iter()
                     .for_each(|rom| roms.push(rom))
+                    } else {
+                        find_roms_without_romfile_by_name_and_size_and_sha256_and_game_names(
+                            connection, rom_name, size, hash, game_names,
+                        )
+                        .await
+                        .into_iter()
+                        .for_each(|rom| roms.push(rom))
+                    }
                 }
+            };
+            if !roms.is_empty() {
+                break;
             }
-        };
+        }
     }
 
     // then with game name only
@@ -1625,6 +2151,23 @@ async fn find_rom_by_size_and_hash(
                     .for_each(|rom| roms.push(rom))
                 }
             }
+            HashAlgorithm::Sha256 => {
+                if let Some(system) = system {
+                    find_roms_without_romfile_by_size_and_sha256_and_game_names_and_system_id(
+                        connection, size, hash, game_names, system.id,
+                    )
+                    .await
+                    .into_iter()
+                    .for_each(|rom| roms.push(rom))
+                } else {
+                    find_roms_without_romfile_by_size_and_sha256_and_game_names(
+                        connection, size, hash, game_names,
+                    )
+                    .await
+                    .into_iter()
+                    .for_each(|rom| roms.push(rom))
+                }
+            }
         };
     }
 
@@ -1676,6 +2219,21 @@ async fn find_rom_by_size_and_hash(
                         .for_each(|rom| roms.push(rom))
                 }
             }
+            HashAlgorithm::Sha256 => {
+                if let Some(system) = system {
+                    find_roms_without_romfile_by_size_and_sha256_and_system_id(
+                        connection, size, hash, system.id,
+                    )
+                    .await
+                    .into_iter()
+                    .for_each(|rom| roms.push(rom))
+                } else {
+                    find_roms_without_romfile_by_size_and_sha256(connection, size, hash)
+                        .await
+                        .into_iter()
+                        .for_each(|rom| roms.push(rom))
+                }
+            }
         };
     }
 
@@ -1709,6 +2267,15 @@ async fn find_rom_by_size_and_hash(
                 }
                 None => count_roms_with_romfile_by_size_and_sha1(connection, size, hash).await,
             },
+            HashAlgorithm::Sha256 => match system {
+                Some(system) => {
+                    count_roms_with_romfile_by_size_and_sha256_and_system_id(
+                        connection, size, hash, system.id,
+                    )
+                    .await
+                }
+                None => count_roms_with_romfile_by_size_and_sha256(connection, size, hash).await,
+            },
         };
         if rom_count > 0 {
             progress_bar.println("Already imported");
@@ -1867,11 +2434,69 @@ async fn create_or_update_romfile<P: AsRef<Path>>(
     Ok(())
 }
 
-async fn move_to_trash(
+async fn quarantine(
     connection: &mut SqliteConnection,
     progress_bar: &ProgressBar,
     romfile: &CommonRomfile,
+    trash: bool,
 ) -> SimpleResult<()> {
+    // legacy collections may still carry GoodTools flags in their file names
+    let goodtools_status = if get_bool(connection, "GOODTOOLS_FLAGS").await {
+        get_goodtools_status(romfile.path.file_name().unwrap().to_str().unwrap())
+    } else {
+        None
+    };
+    if let Some(status) = goodtools_status {
+        progress_bar.println(format!(
+            "GoodTools flags mark \"{}\" as {}",
+            romfile.path.as_os_str().to_str().unwrap(),
+            match status {
+                GoodToolsStatus::Bad => "a bad dump",
+                GoodToolsStatus::Overdump => "an overdump",
+                GoodToolsStatus::Hack => "a hack",
+                GoodToolsStatus::Good => "a good dump",
+            }
+        ));
+    }
+    // files are only identified, importing them still requires a DAT
+    identify_romfile(connection, progress_bar, romfile).await?;
+    let path = if trash {
+        move_to_trash(connection, progress_bar, romfile).await?
+    } else {
+        romfile.path.clone()
+    };
+    // keep track of unmatched files so they can be revisited after importing more DATs, classified ones are always kept
+    if goodtools_status.is_some() || get_bool(connection, "TRACK_UNMATCHED").await {
+        let size = try_with!(
+            path.metadata(),
+            "Failed to read \"{}\" metadata",
+            path.as_os_str().to_str().unwrap()
+        )
+        .len();
+        create_or_update_unmatched(
+            connection,
+            path.as_os_str().to_str().unwrap(),
+            size as i64,
+            goodtools_status.map(|status| status.to_string()).as_deref(),
+        )
+        .await;
+    }
+    Ok(())
+}
+
+// recorded files keep their current path
+fn get_import_path(import_mode: ImportMode, romfile: &CommonRomfile, new_path: PathBuf) -> PathBuf {
+    match import_mode {
+        ImportMode::Record => romfile.path.clone(),
+        ImportMode::Move | ImportMode::RenameInPlace => new_path,
+    }
+}
+
+async fn move_to_trash(
+    connection: &mut SqliteConnection,
+    progress_bar: &ProgressBar,
+    romfile: &CommonRomfile,
+) -> SimpleResult<PathBuf> {
     let new_path = get_trash_directory(connection, None)
         .await?
         .join(romfile.path.file_name().unwrap());
@@ -1888,28 +2513,72 @@ async fn move_to_trash(
                 .await?;
         }
     }
-    Ok(())
+    Ok(new_romfile.path)
 }
 
+#[cfg(test)]
+mod test_3ds;
 #[cfg(test)]
 mod test_cia;
 #[cfg(test)]
 mod test_cso;
 #[cfg(test)]
+mod test_directory_summary;
+#[cfg(test)]
 mod test_iso_chd;
 #[cfg(test)]
+mod test_iso_convert_chd;
+#[cfg(test)]
 mod test_mame;
 #[cfg(test)]
+mod test_mame_cd_chd;
+#[cfg(test)]
 mod test_mame_chd;
 #[cfg(test)]
 mod test_mame_mixed;
 #[cfg(test)]
 mod test_multiple_tracks_chd;
 #[cfg(test)]
+mod test_multiple_tracks_chd_parallelism;
+#[cfg(test)]
+mod test_multiple_tracks_cue_bin_directory;
+#[cfg(test)]
 mod test_original;
 #[cfg(test)]
+mod test_original_deswap;
+#[cfg(test)]
+mod test_original_deswap_convert;
+#[cfg(test)]
+mod test_original_deswap_non_n64;
+#[cfg(test)]
+mod test_original_goodtools_flags;
+#[cfg(test)]
+mod test_original_header_title;
+#[cfg(test)]
 mod test_original_headered;
 #[cfg(test)]
+mod test_original_headered_a7800;
+#[cfg(test)]
+mod test_original_headered_fds;
+#[cfg(test)]
+mod test_original_headered_full;
+#[cfg(test)]
+mod test_original_headered_jaguar;
+#[cfg(test)]
+mod test_original_headered_lynx;
+#[cfg(test)]
+mod test_original_headered_nes;
+#[cfg(test)]
+mod test_original_no_match;
+#[cfg(test)]
+mod test_original_rename_in_place;
+#[cfg(test)]
+mod test_original_rename_in_place_outside;
+#[cfg(test)]
+mod test_original_sha256;
+#[cfg(test)]
+mod test_original_subdirectory;
+#[cfg(test)]
 mod test_rvz;
 #[cfg(test)]
 mod test_sevenzip_multiple_files_full_game;
@@ -1920,16 +2589,22 @@ mod test_sevenzip_multiple_files_mixed_games;
 #[cfg(test)]
 mod test_sevenzip_multiple_files_partial_game;
 #[cfg(test)]
+mod test_sevenzip_partial_game_completion;
+#[cfg(test)]
 mod test_sevenzip_single_file;
 #[cfg(test)]
 mod test_sevenzip_single_file_headered;
 #[cfg(test)]
 mod test_single_track_chd;
 #[cfg(test)]
+mod test_tar_zst_extract;
+#[cfg(test)]
 mod test_zip_single_file;
 #[cfg(test)]
 mod test_zip_single_file_extract;
 #[cfg(test)]
 mod test_zip_special_characters;
 #[cfg(test)]
+mod test_zip_subdirectory;
+#[cfg(test)]
 mod test_zso;
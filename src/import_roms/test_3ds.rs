@@ -0,0 +1,79 @@
+use super::super::database::*;
+use super::super::import_dats;
+use super::*;
+use std::path::PathBuf;
+use tempfile::{NamedTempFile, TempDir};
+use tokio::fs;
+
+#[tokio::test]
+async fn test() {
+    // given
+    let _guard = MUTEX.lock().await;
+
+    let test_directory = Path::new("tests");
+    let progress_bar = ProgressBar::hidden();
+
+    let db_file = NamedTempFile::new().unwrap();
+    let pool = establish_connection(db_file.path().to_str().unwrap()).await;
+    let mut connection = pool.acquire().await.unwrap();
+
+    let rom_directory = TempDir::new_in(&test_directory).unwrap();
+    set_rom_directory(PathBuf::from(rom_directory.path()));
+    let tmp_directory = TempDir::new_in(&test_directory).unwrap();
+    let tmp_directory = set_tmp_directory(PathBuf::from(tmp_directory.path()));
+
+    let matches = import_dats::subcommand()
+        .get_matches_from(&["import-dats", "tests/Test System (20250220) (3DS).dat"]);
+    import_dats::main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    let romfile_path = tmp_directory.join("Test Game (USA).3ds");
+    fs::copy(test_directory.join("Test Game (USA).3ds"), &romfile_path)
+        .await
+        .unwrap();
+
+    let system = find_systems(&mut connection).await.remove(0);
+    let system_directory = get_system_directory(&mut connection, &system)
+        .await
+        .unwrap();
+
+    // when
+    let mimetype = get_mimetype(&romfile_path).await.unwrap();
+    let matches =
+        subcommand().get_matches_from(&["import-roms", romfile_path.as_os_str().to_str().unwrap()]);
+    main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    // then
+    assert_eq!(mimetype.unwrap().extension(), CCI_EXTENSION);
+    assert_eq!(
+        get_mimetype(&test_directory.join("Test Game (USA, Europe).cia"))
+            .await
+            .unwrap()
+            .unwrap()
+            .extension(),
+        CIA_EXTENSION
+    );
+
+    let games = find_games(&mut connection).await;
+    assert_eq!(games.len(), 1);
+    let game = games.first().unwrap();
+    assert_eq!(game.completion, Completion::Full as i64);
+
+    let romfiles = find_romfiles(&mut connection).await;
+    assert_eq!(romfiles.len(), 1);
+    let romfile = romfiles.first().unwrap();
+    assert_eq!(
+        romfile.path,
+        system_directory
+            .join("Test Game (USA).3ds")
+            .strip_prefix(&rom_directory)
+            .unwrap()
+            .as_os_str()
+            .to_str()
+            .unwrap(),
+    );
+    assert!(rom_directory.path().join(&romfile.path).is_file());
+}
@@ -63,6 +63,7 @@ async fn test() {
         CommonRomfile::from_path(&romfile_path).unwrap(),
         true,
         false,
+        ImportMode::Move,
     )
     .await
     .unwrap();
@@ -59,6 +59,7 @@ async fn test() {
         CommonRomfile::from_path(&romfile_path).unwrap(),
         true,
         false,
+        ImportMode::Move,
     )
     .await
     .unwrap();
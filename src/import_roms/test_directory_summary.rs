@@ -0,0 +1,57 @@
+use super::super::database::*;
+use super::super::import_dats;
+use super::*;
+use std::path::PathBuf;
+use tempfile::{NamedTempFile, TempDir};
+use tokio::fs;
+
+#[tokio::test]
+async fn test() {
+    // given
+    let _guard = MUTEX.lock().await;
+
+    let test_directory = Path::new("tests");
+    let progress_bar = ProgressBar::hidden();
+
+    let db_file = NamedTempFile::new().unwrap();
+    let pool = establish_connection(db_file.path().to_str().unwrap()).await;
+    let mut connection = pool.acquire().await.unwrap();
+
+    let rom_directory = TempDir::new_in(&test_directory).unwrap();
+    set_rom_directory(PathBuf::from(rom_directory.path()));
+    let tmp_directory = TempDir::new_in(&test_directory).unwrap();
+    set_tmp_directory(PathBuf::from(tmp_directory.path()));
+
+    let matches = import_dats::subcommand()
+        .get_matches_from(&["import-dats", "tests/Test System (20200721).dat"]);
+    import_dats::main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    let import_directory = TempDir::new_in(&test_directory).unwrap();
+    let romfile_names = vec!["Test Game (Asia).rom", "Test Game (USA, Europe).rom"];
+    for romfile_name in &romfile_names {
+        fs::copy(
+            test_directory.join(romfile_name),
+            import_directory.path().join(romfile_name),
+        )
+        .await
+        .unwrap();
+    }
+
+    let matches = subcommand().get_matches_from(&[
+        "import-roms",
+        import_directory.path().as_os_str().to_str().unwrap(),
+    ]);
+
+    // when
+    let summary = main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    // then
+    assert_eq!(summary.count, 2);
+    assert_eq!(summary.bytes_in, 512);
+    assert_eq!(summary.bytes_out, 512);
+    assert_eq!(find_romfiles(&mut connection).await.len(), 2);
+}
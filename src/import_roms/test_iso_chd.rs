@@ -50,6 +50,7 @@ async fn test() {
         CommonRomfile::from_path(&romfile_path).unwrap(),
         true,
         false,
+        ImportMode::Move,
         &None,
     )
     .await
@@ -58,7 +58,9 @@ async fn test() {
         CommonRomfile::from_path(&romfile_path).unwrap(),
         true,
         false,
+        ImportMode::Move,
         &None,
+        &HashMap::new(),
     )
     .await
     .unwrap();
@@ -70,6 +72,7 @@ async fn test() {
         CommonRomfile::from_path(&chd_romfile_path).unwrap(),
         true,
         false,
+        ImportMode::Move,
         &None,
     )
     .await
@@ -0,0 +1,129 @@
+use super::super::database::*;
+use super::super::import_dats;
+use super::*;
+use std::path::PathBuf;
+use tempfile::{NamedTempFile, TempDir};
+use tokio::fs;
+
+#[tokio::test]
+async fn test() {
+    if let Ok(version) = chdman::get_version().await {
+        if version.as_str().cmp(chdman::MIN_SPLITBIN_VERSION) == Ordering::Less {
+            return;
+        }
+    }
+
+    // given
+    let _guard = MUTEX.lock().await;
+
+    let test_directory = Path::new("tests");
+    let progress_bar = ProgressBar::hidden();
+
+    // tracks are hashed in parallel, each import should still pair them with the same roms
+    let mut associations: Vec<Vec<(String, String)>> = vec![];
+    for _ in 0..2 {
+        let db_file = NamedTempFile::new().unwrap();
+        let pool = establish_connection(db_file.path().to_str().unwrap()).await;
+        let mut connection = pool.acquire().await.unwrap();
+
+        let rom_directory = TempDir::new_in(&test_directory).unwrap();
+        set_rom_directory(PathBuf::from(rom_directory.path()));
+        let tmp_directory = TempDir::new_in(&test_directory).unwrap();
+        let tmp_directory = set_tmp_directory(PathBuf::from(tmp_directory.path()));
+
+        let matches = import_dats::subcommand()
+            .get_matches_from(&["import-dats", "tests/Test System (20200721).dat"]);
+        import_dats::main(&mut connection, &matches, &progress_bar)
+            .await
+            .unwrap();
+
+        let romfile_path = tmp_directory.join("Test Game (USA, Europe) (Multiple Tracks).chd");
+        fs::copy(
+            test_directory.join("Test Game (USA, Europe) (Multiple Tracks).chd"),
+            &romfile_path.as_os_str().to_str().unwrap(),
+        )
+        .await
+        .unwrap();
+
+        let setting = find_setting_by_key(&mut connection, "PARALLELISM")
+            .await
+            .unwrap();
+        update_setting(&mut connection, setting.id, Some(String::from("2"))).await;
+
+        let system = find_systems(&mut connection).await.remove(0);
+        let system_directory = get_system_directory(&mut connection, &system)
+            .await
+            .unwrap();
+
+        // when
+        import_chd(
+            &mut connection,
+            &progress_bar,
+            &Some(&system),
+            &HashSet::new(),
+            CommonRomfile::from_path(&romfile_path).unwrap(),
+            true,
+            false,
+            ImportMode::Move,
+            &None,
+        )
+        .await
+        .unwrap();
+
+        // then
+        let roms = find_roms_with_romfile_by_system_id(&mut connection, system.id).await;
+        assert_eq!(roms.len(), 2);
+        let romfiles = find_romfiles(&mut connection).await;
+        assert_eq!(romfiles.len(), 1);
+
+        let rom = roms.first().unwrap();
+        assert_eq!(rom.name, "Test Game (USA, Europe) (CUE BIN) (Track 01).bin");
+
+        let rom = roms.get(1).unwrap();
+        assert_eq!(rom.name, "Test Game (USA, Europe) (CUE BIN) (Track 02).bin");
+
+        let romfile = romfiles.first().unwrap();
+        assert_eq!(
+            romfile.path,
+            system_directory
+                .join("Test Game (USA, Europe) (CUE BIN).chd")
+                .strip_prefix(&rom_directory)
+                .unwrap()
+                .as_os_str()
+                .to_str()
+                .unwrap(),
+        );
+        assert!(roms.iter().all(|rom| rom.romfile_id == Some(romfile.id)));
+
+        // each track of the imported CHD hashes to the rom it was matched with
+        let cue_bin_romfile = romfile
+            .as_common(&mut connection)
+            .await
+            .unwrap()
+            .as_chd()
+            .await
+            .unwrap()
+            .to_cue_bin(&progress_bar, &tmp_directory, None, &[], true)
+            .await
+            .unwrap();
+        let hashes_and_sizes = compute_hashes_and_sizes(
+            &progress_bar,
+            &cue_bin_romfile.bin_romfiles,
+            &HashAlgorithm::Crc,
+        )
+        .unwrap();
+        assert_eq!(hashes_and_sizes.len(), roms.len());
+        for (rom, (crc, size)) in roms.iter().zip(&hashes_and_sizes) {
+            assert_eq!(rom.crc.as_ref(), Some(crc));
+            assert_eq!(rom.size as u64, *size);
+        }
+        associations.push(
+            roms.iter()
+                .zip(hashes_and_sizes)
+                .map(|(rom, (crc, _))| (rom.name.clone(), crc))
+                .collect(),
+        );
+    }
+
+    assert_eq!(associations[0], associations[1]);
+}
@@ -0,0 +1,108 @@
+use super::super::database::*;
+use super::super::import_dats;
+use super::*;
+use std::path::PathBuf;
+use tempfile::{NamedTempFile, TempDir};
+use tokio::fs;
+
+#[tokio::test]
+async fn test() {
+    // given
+    let _guard = MUTEX.lock().await;
+
+    let test_directory = Path::new("tests");
+    let progress_bar = ProgressBar::hidden();
+
+    let db_file = NamedTempFile::new().unwrap();
+    let pool = establish_connection(db_file.path().to_str().unwrap()).await;
+    let mut connection = pool.acquire().await.unwrap();
+
+    let rom_directory = TempDir::new_in(&test_directory).unwrap();
+    set_rom_directory(PathBuf::from(rom_directory.path()));
+    let tmp_directory = TempDir::new_in(&test_directory).unwrap();
+    let tmp_directory = set_tmp_directory(PathBuf::from(tmp_directory.path()));
+
+    let matches = import_dats::subcommand()
+        .get_matches_from(&["import-dats", "tests/Test System (20200721).dat"]);
+    import_dats::main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    let game_directory = tmp_directory.join("game");
+    fs::create_dir_all(&game_directory).await.unwrap();
+    let game_directory = game_directory.canonicalize().unwrap();
+    fs::copy(
+        test_directory.join("Test Game (USA, Europe) (Multiple Tracks).cue"),
+        game_directory.join("Test Game (USA, Europe) (CUE BIN).cue"),
+    )
+    .await
+    .unwrap();
+    let mut track_paths: Vec<PathBuf> = vec![];
+    for track_name in [
+        "Test Game (USA, Europe) (CUE BIN) (Track 01).bin",
+        "Test Game (USA, Europe) (CUE BIN) (Track 02).bin",
+    ] {
+        let track_path = game_directory.join(track_name);
+        fs::copy(test_directory.join(track_name), &track_path)
+            .await
+            .unwrap();
+        track_paths.push(track_path);
+    }
+
+    let system = find_systems(&mut connection).await.remove(0);
+    let system_directory = get_system_directory(&mut connection, &system)
+        .await
+        .unwrap();
+
+    let mut paths = track_paths.clone();
+    paths.push(game_directory.join("Test Game (USA, Europe) (CUE BIN).cue"));
+
+    let matches = subcommand()
+        .get_matches_from(&["import-roms", game_directory.as_os_str().to_str().unwrap()]);
+
+    // when
+    let track_hashes_and_sizes = compute_track_hashes_and_sizes(
+        &mut connection,
+        &progress_bar,
+        &paths,
+        &None,
+        &Some(HashAlgorithm::Crc),
+    )
+    .await
+    .unwrap();
+
+    main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    // then
+    assert_eq!(track_hashes_and_sizes.len(), 2);
+    assert_eq!(
+        track_hashes_and_sizes.get(&track_paths[0]).unwrap(),
+        &(String::from("9fe63aa2"), 20309520)
+    );
+    assert_eq!(
+        track_hashes_and_sizes.get(&track_paths[1]).unwrap(),
+        &(String::from("b979500c"), 7914480)
+    );
+
+    let roms = find_roms_with_romfile_by_system_id(&mut connection, system.id).await;
+    assert_eq!(roms.len(), 3);
+    let romfiles = find_romfiles(&mut connection).await;
+    assert_eq!(romfiles.len(), 3);
+
+    for rom in roms {
+        let romfile = find_romfile_by_id(&mut connection, rom.romfile_id.unwrap()).await;
+        assert_eq!(
+            romfile.path,
+            system_directory
+                .join(&rom.name)
+                .strip_prefix(&rom_directory)
+                .unwrap()
+                .as_os_str()
+                .to_str()
+                .unwrap(),
+        );
+        assert!(rom_directory.path().join(&romfile.path).is_file());
+    }
+}
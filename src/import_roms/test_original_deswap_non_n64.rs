@@ -0,0 +1,54 @@
+use super::super::database::*;
+use super::super::import_dats;
+use super::*;
+use std::path::PathBuf;
+use tempfile::{NamedTempFile, TempDir};
+use tokio::fs;
+
+#[tokio::test]
+async fn test() {
+    // given
+    let _guard = MUTEX.lock().await;
+
+    let test_directory = Path::new("tests");
+    let progress_bar = ProgressBar::hidden();
+
+    let db_file = NamedTempFile::new().unwrap();
+    let pool = establish_connection(db_file.path().to_str().unwrap()).await;
+    let mut connection = pool.acquire().await.unwrap();
+
+    let rom_directory = TempDir::new_in(&test_directory).unwrap();
+    set_rom_directory(PathBuf::from(rom_directory.path()));
+    let tmp_directory = TempDir::new_in(&test_directory).unwrap();
+    let tmp_directory = set_tmp_directory(PathBuf::from(tmp_directory.path()));
+
+    // no DAT lists big-endian N64 ROMs
+    let matches = import_dats::subcommand()
+        .get_matches_from(&["import-dats", "tests/Test System (20200721).dat"]);
+    import_dats::main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    let romfile_path = tmp_directory.join("Header Title N64 (USA).v64");
+    fs::copy(
+        test_directory.join("Header Title N64 (USA).v64"),
+        &romfile_path,
+    )
+    .await
+    .unwrap();
+
+    // when
+    let matches = subcommand().get_matches_from(&[
+        "import-roms",
+        "--deswap",
+        romfile_path.as_os_str().to_str().unwrap(),
+    ]);
+    main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    // then
+    assert!(find_romfiles(&mut connection).await.is_empty());
+    let bytes = fs::read(&romfile_path).await.unwrap();
+    assert_eq!(&bytes[0..4], &[0x37, 0x80, 0x40, 0x12]);
+}
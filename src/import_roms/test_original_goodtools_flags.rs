@@ -0,0 +1,64 @@
+use super::super::database::*;
+use super::super::import_dats;
+use super::*;
+use std::path::{Path, PathBuf};
+use tempfile::{NamedTempFile, TempDir};
+use tokio::fs;
+
+#[tokio::test]
+async fn test() {
+    // given
+    let _guard = MUTEX.lock().await;
+
+    let test_directory = Path::new("tests");
+    let progress_bar = ProgressBar::hidden();
+
+    let db_file = NamedTempFile::new().unwrap();
+    let pool = establish_connection(db_file.path().to_str().unwrap()).await;
+    let mut connection = pool.acquire().await.unwrap();
+
+    let rom_directory = TempDir::new_in(&test_directory).unwrap();
+    set_rom_directory(PathBuf::from(rom_directory.path()));
+    let tmp_directory = TempDir::new_in(&test_directory).unwrap();
+    let tmp_directory = set_tmp_directory(PathBuf::from(tmp_directory.path()));
+
+    let matches = import_dats::subcommand()
+        .get_matches_from(&["import-dats", "tests/Test System (20200721).dat"]);
+    import_dats::main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    set_bool(&mut connection, "GOODTOOLS_FLAGS", true).await;
+    set_bool(&mut connection, "TRACK_UNMATCHED", false).await;
+
+    // the Asia ROM doesn't match its DAT entry
+    let mut romfile_paths: Vec<PathBuf> = vec![];
+    for romfile_name in [
+        "Test Game (Asia) [b1].rom",
+        "Test Game (Asia) [h1C].rom",
+        "Test Game (Asia).rom",
+    ] {
+        let romfile_path = tmp_directory.join(romfile_name);
+        fs::copy(test_directory.join("Test Game (Asia).rom"), &romfile_path)
+            .await
+            .unwrap();
+        romfile_paths.push(romfile_path);
+    }
+
+    // when
+    for romfile_path in &romfile_paths {
+        let matches = subcommand()
+            .get_matches_from(&["import-roms", romfile_path.as_os_str().to_str().unwrap()]);
+        main(&mut connection, &matches, &progress_bar)
+            .await
+            .unwrap();
+    }
+
+    // then
+    let unmatched = find_unmatched(&mut connection).await;
+    assert_eq!(unmatched.len(), 2);
+    assert!(unmatched[0].path.ends_with("Test Game (Asia) [b1].rom"));
+    assert_eq!(unmatched[0].goodtools_status.as_deref(), Some("bad"));
+    assert!(unmatched[1].path.ends_with("Test Game (Asia) [h1C].rom"));
+    assert_eq!(unmatched[1].goodtools_status.as_deref(), Some("hack"));
+}
@@ -0,0 +1,64 @@
+use super::super::database::*;
+use super::super::import_dats;
+use super::*;
+use std::path::PathBuf;
+use tempfile::{NamedTempFile, TempDir};
+use tokio::fs;
+
+#[tokio::test]
+async fn test() {
+    // given
+    let _guard = MUTEX.lock().await;
+
+    let test_directory = Path::new("tests");
+    let progress_bar = ProgressBar::hidden();
+
+    let db_file = NamedTempFile::new().unwrap();
+    let pool = establish_connection(db_file.path().to_str().unwrap()).await;
+    let mut connection = pool.acquire().await.unwrap();
+
+    let rom_directory = TempDir::new_in(&test_directory).unwrap();
+    set_rom_directory(PathBuf::from(rom_directory.path()));
+    let tmp_directory = TempDir::new_in(&test_directory).unwrap();
+    let tmp_directory = set_tmp_directory(PathBuf::from(tmp_directory.path()));
+
+    let matches = import_dats::subcommand().get_matches_from(&[
+        "import-dats",
+        "tests/Test System (20250204) (Header Titles).dat",
+    ]);
+    import_dats::main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    let romfile_path = tmp_directory.join("Header Title (USA).gb");
+    fs::copy(test_directory.join("Header Title (USA).gb"), &romfile_path)
+        .await
+        .unwrap();
+
+    let system = find_systems(&mut connection).await.remove(0);
+
+    // when
+    import_other(
+        &mut connection,
+        &progress_bar,
+        &Some(&system),
+        &None,
+        &HashSet::new(),
+        CommonRomfile::from_path(&romfile_path).unwrap(),
+        true,
+        false,
+        ImportMode::Move,
+        &None,
+        &HashMap::new(),
+    )
+    .await
+    .unwrap();
+
+    // then
+    let roms = find_roms_with_romfile_by_system_id(&mut connection, system.id).await;
+    assert_eq!(roms.len(), 1);
+
+    let rom = roms.first().unwrap();
+    assert_eq!(rom.name, "Header Title (USA).gb");
+    assert_eq!(rom.header_title.as_deref(), Some("HEADER TITLE"));
+}
@@ -52,7 +52,9 @@ async fn test() {
         CommonRomfile::from_path(&romfile_path).unwrap(),
         true,
         false,
+        ImportMode::Move,
         &None,
+        &HashMap::new(),
     )
     .await
     .unwrap();
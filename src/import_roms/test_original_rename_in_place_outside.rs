@@ -0,0 +1,67 @@
+use super::super::database::*;
+use super::super::import_dats;
+use super::*;
+use std::path::PathBuf;
+use tempfile::{NamedTempFile, TempDir};
+use tokio::fs;
+
+#[tokio::test]
+async fn test() {
+    // given
+    let _guard = MUTEX.lock().await;
+
+    let test_directory = Path::new("tests");
+    let progress_bar = ProgressBar::hidden();
+
+    let db_file = NamedTempFile::new().unwrap();
+    let pool = establish_connection(db_file.path().to_str().unwrap()).await;
+    let mut connection = pool.acquire().await.unwrap();
+
+    let rom_directory = TempDir::new_in(&test_directory).unwrap();
+    set_rom_directory(rom_directory.path().canonicalize().unwrap());
+    let tmp_directory = TempDir::new_in(&test_directory).unwrap();
+    set_tmp_directory(PathBuf::from(tmp_directory.path()));
+    let loose_directory = TempDir::new_in(&test_directory).unwrap();
+    let loose_directory = loose_directory.path().canonicalize().unwrap();
+
+    let matches = import_dats::subcommand()
+        .get_matches_from(&["import-dats", "tests/Test System (20200721).dat"]);
+    import_dats::main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    let romfile_path = loose_directory.join("test game.rom");
+    fs::copy(
+        test_directory.join("Test Game (USA, Europe).rom"),
+        &romfile_path,
+    )
+    .await
+    .unwrap();
+
+    let system = find_systems(&mut connection).await.remove(0);
+    let system_directory = get_system_directory(&mut connection, &system)
+        .await
+        .unwrap();
+
+    let matches = subcommand().get_matches_from(&[
+        "import-roms",
+        "--rename-in-place",
+        romfile_path.as_os_str().to_str().unwrap(),
+    ]);
+
+    // when
+    let result = main(&mut connection, &matches, &progress_bar).await;
+
+    // then
+    assert!(result.is_err());
+    let roms = find_roms_with_romfile_by_system_id(&mut connection, system.id).await;
+    assert!(roms.is_empty());
+    let romfiles = find_romfiles(&mut connection).await;
+    assert!(romfiles.is_empty());
+
+    assert!(romfile_path.is_file());
+    assert!(!loose_directory.join("Test Game (USA, Europe).rom").exists());
+    assert!(!system_directory
+        .join("Test Game (USA, Europe).rom")
+        .exists());
+}
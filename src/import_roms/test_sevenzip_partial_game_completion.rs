@@ -0,0 +1,78 @@
+use super::super::database::*;
+use super::super::import_dats;
+use super::*;
+use std::path::PathBuf;
+use tempfile::{NamedTempFile, TempDir};
+use tokio::fs;
+
+#[tokio::test]
+async fn test() {
+    // given
+    let _guard = MUTEX.lock().await;
+
+    let test_directory = Path::new("tests");
+    let progress_bar = ProgressBar::hidden();
+
+    let db_file = NamedTempFile::new().unwrap();
+    let pool = establish_connection(db_file.path().to_str().unwrap()).await;
+    let mut connection = pool.acquire().await.unwrap();
+
+    let rom_directory = TempDir::new_in(&test_directory).unwrap();
+    set_rom_directory(PathBuf::from(rom_directory.path()));
+    let tmp_directory = TempDir::new_in(&test_directory).unwrap();
+    let tmp_directory = set_tmp_directory(PathBuf::from(tmp_directory.path()));
+
+    let matches = import_dats::subcommand()
+        .get_matches_from(&["import-dats", "tests/Test System (20200721).dat"]);
+    import_dats::main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    let romfile_path = tmp_directory.join("Test Game (USA, Europe) (Partial).7z");
+    fs::copy(
+        test_directory.join("Test Game (USA, Europe) (Partial).7z"),
+        &romfile_path,
+    )
+    .await
+    .unwrap();
+
+    let matches =
+        subcommand().get_matches_from(&["import-roms", romfile_path.as_os_str().to_str().unwrap()]);
+    main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    let game = find_games(&mut connection)
+        .await
+        .into_iter()
+        .find(|game| game.name == "Test Game (USA, Europe) (CUE BIN)")
+        .unwrap();
+    assert_eq!(game.completion, Completion::Partial as i64);
+
+    let romfile_path = tmp_directory.join("Test Game (USA, Europe) (CUE BIN) (Track 02).bin");
+    fs::copy(
+        test_directory.join("Test Game (USA, Europe) (CUE BIN) (Track 02).bin"),
+        &romfile_path,
+    )
+    .await
+    .unwrap();
+
+    // when
+    let matches =
+        subcommand().get_matches_from(&["import-roms", romfile_path.as_os_str().to_str().unwrap()]);
+    main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    // then
+    let game = find_game_by_id(&mut connection, game.id).await;
+    assert_eq!(game.completion, Completion::Full as i64);
+    assert_eq!(
+        count_games_by_completion(&mut connection, Completion::Full).await,
+        1
+    );
+    assert_eq!(
+        count_games_by_completion(&mut connection, Completion::Partial).await,
+        0
+    );
+}
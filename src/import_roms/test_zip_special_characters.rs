@@ -54,6 +54,7 @@ async fn test() {
         romfile_path.extension().unwrap().to_str().unwrap(),
         true,
         false,
+        ImportMode::Move,
         &None,
     )
     .await
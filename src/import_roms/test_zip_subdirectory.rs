@@ -0,0 +1,110 @@
+use super::super::database::*;
+use super::super::import_dats;
+use super::*;
+use std::io::Write;
+use std::path::PathBuf;
+use tempfile::{NamedTempFile, TempDir};
+use tokio::fs;
+use zip::write::SimpleFileOptions;
+use zip::ZipWriter;
+
+#[tokio::test]
+async fn test() {
+    // given
+    let _guard = MUTEX.lock().await;
+
+    let test_directory = Path::new("tests");
+    let progress_bar = ProgressBar::hidden();
+
+    let db_file = NamedTempFile::new().unwrap();
+    let pool = establish_connection(db_file.path().to_str().unwrap()).await;
+    let mut connection = pool.acquire().await.unwrap();
+
+    let rom_directory = TempDir::new_in(&test_directory).unwrap();
+    set_rom_directory(PathBuf::from(rom_directory.path()));
+    let tmp_directory = TempDir::new_in(&test_directory).unwrap();
+    let tmp_directory = set_tmp_directory(PathBuf::from(tmp_directory.path()));
+
+    let matches = import_dats::subcommand().get_matches_from(&[
+        "import-dats",
+        "tests/Test System (20250224) (Subdirectories).dat",
+    ]);
+    import_dats::main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    // the member lives in the same subdirectory as in the DAT
+    let romfile_path = tmp_directory.join("Test Game (USA, Europe).zip");
+    let mut archive = ZipWriter::new(std::fs::File::create(&romfile_path).unwrap());
+    archive
+        .start_file(
+            "data/Test Game (USA, Europe).rom",
+            SimpleFileOptions::default(),
+        )
+        .unwrap();
+    archive
+        .write_all(
+            &fs::read(test_directory.join("Test Game (USA, Europe).rom"))
+                .await
+                .unwrap(),
+        )
+        .unwrap();
+    archive.finish().unwrap();
+
+    let system = find_systems(&mut connection).await.remove(0);
+    let system_directory = get_system_directory(&mut connection, &system)
+        .await
+        .unwrap();
+
+    // when
+    import_archive(
+        &mut connection,
+        &progress_bar,
+        &Some(&system),
+        &None,
+        &HashSet::new(),
+        CommonRomfile::from_path(&romfile_path).unwrap(),
+        romfile_path.extension().unwrap().to_str().unwrap(),
+        true,
+        false,
+        ImportMode::Move,
+        &None,
+    )
+    .await
+    .unwrap();
+
+    // then
+    let roms = find_roms_with_romfile_by_system_id(&mut connection, system.id).await;
+    assert_eq!(roms.len(), 1);
+    let romfiles = find_romfiles(&mut connection).await;
+    assert_eq!(romfiles.len(), 1);
+
+    let rom = roms.first().unwrap();
+    assert_eq!(rom.name, "data/Test Game (USA, Europe).rom");
+
+    let romfile = romfiles.first().unwrap();
+    assert_eq!(
+        romfile.path,
+        system_directory
+            .join("data/Test Game (USA, Europe).zip")
+            .strip_prefix(&rom_directory)
+            .unwrap()
+            .as_os_str()
+            .to_str()
+            .unwrap(),
+    );
+    assert_eq!(rom.romfile_id, Some(romfile.id));
+
+    let archive_romfiles = romfile
+        .as_common(&mut connection)
+        .await
+        .unwrap()
+        .as_archive(&progress_bar, None)
+        .await
+        .unwrap();
+    assert_eq!(archive_romfiles.len(), 1);
+    assert_eq!(
+        archive_romfiles.first().unwrap().path,
+        "data/Test Game (USA, Europe).rom"
+    );
+}
@@ -5,6 +5,7 @@ use super::database::*;
 use super::dolphin;
 use super::flips;
 use super::maxcso;
+use super::model::*;
 use super::nsz;
 use super::progress::*;
 use super::sevenzip;
@@ -86,11 +87,17 @@ pub async fn main(
 
     let system_count = count_systems(connection).await;
     let game_count = count_games(connection).await;
+    let complete_game_count = count_games_by_completion(connection, Completion::Full).await;
+    let partial_game_count = count_games_by_completion(connection, Completion::Partial).await;
     let rom_count = count_roms(connection).await;
+    let header_title_count = count_roms_with_header_title(connection).await;
 
     progress_bar.println(format!("Systems: {}", system_count));
     progress_bar.println(format!("Games: {}", game_count));
+    progress_bar.println(format!("  Complete: {}", complete_game_count));
+    progress_bar.println(format!("  Partial: {}", partial_game_count));
     progress_bar.println(format!("Roms: {}", rom_count));
+    progress_bar.println(format!("Roms with a header title: {}", header_title_count));
 
     Ok(())
 }
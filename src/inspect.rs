@@ -0,0 +1,258 @@
+use super::chdman;
+use super::chdman::{AsChd, ChdType};
+use super::common::*;
+use super::config::*;
+use super::ctrtool;
+use super::database::*;
+use super::dolphin;
+use super::mimetype::*;
+use super::model::*;
+use super::nsz;
+use super::sevenzip;
+use super::sevenzip::AsArchive;
+use super::util::*;
+use super::SimpleResult;
+use clap::{Arg, ArgMatches, Command};
+use indicatif::{HumanBytes, ProgressBar};
+use sqlx::sqlite::SqliteConnection;
+use std::cmp::Ordering;
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+
+pub fn subcommand() -> Command {
+    Command::new("inspect")
+        .about("Show how oxyromon would handle a file")
+        .arg(
+            Arg::new("FILE")
+                .help("Set the file to inspect")
+                .required(true)
+                .index(1)
+                .value_parser(value_parser!(PathBuf)),
+        )
+}
+
+pub async fn main(
+    connection: &mut SqliteConnection,
+    matches: &ArgMatches,
+    progress_bar: &ProgressBar,
+) -> SimpleResult<()> {
+    let path = get_canonicalized_path(matches.get_one::<PathBuf>("FILE").unwrap()).await?;
+    for line in inspect_file(connection, progress_bar, &path).await? {
+        progress_bar.println(line);
+    }
+    Ok(())
+}
+
+async fn inspect_file(
+    connection: &mut SqliteConnection,
+    progress_bar: &ProgressBar,
+    path: &Path,
+) -> SimpleResult<Vec<String>> {
+    let mut lines: Vec<String> = vec![format!(
+        "Inspecting \"{}\"",
+        path.file_name().unwrap().to_str().unwrap()
+    )];
+
+    if path.is_dir() {
+        if path.join(PS3_DISC_SFB).is_file() {
+            lines.push(String::from("Type: JB folder"));
+            lines.push(String::from("Required tool: none"));
+            lines.push(String::from("Import with: import-roms"));
+        } else {
+            lines.push(String::from("Type: directory"));
+            lines.push(String::from(
+                "Import with: import-roms, every file is processed",
+            ));
+        }
+        return Ok(lines);
+    }
+
+    let size = try_with!(path.metadata(), "Failed to read metadata").len();
+    lines.push(format!("Size: {}", HumanBytes(size)));
+
+    // same detection as import-roms, falling back to the file extension
+    let mimetype = get_mimetype(&path).await?;
+    let extension = match &mimetype {
+        Some(mimetype) => {
+            lines.push(format!(
+                "Type: {} ({})",
+                mimetype.extension(),
+                mimetype.mime_type()
+            ));
+            mimetype.extension().to_string()
+        }
+        None => {
+            lines.push(String::from("Type: unknown"));
+            path.extension()
+                .unwrap_or(&OsString::new())
+                .to_str()
+                .unwrap()
+                .to_lowercase()
+        }
+    };
+
+    let extension = extension.as_str();
+    let (tool, installed) = if ARCHIVE_EXTENSIONS.contains(&extension) {
+        (Some("sevenzip"), sevenzip::get_version().await.is_ok())
+    } else if CHD_EXTENSION == extension {
+        (Some("chdman"), chdman::get_version().await.is_ok())
+    } else if CIA_EXTENSION == extension {
+        (Some("ctrtool"), ctrtool::get_version().await.is_ok())
+    } else if NSZ_EXTENSION == extension {
+        (Some("nsz"), nsz::get_version().await.is_ok())
+    } else if RVZ_EXTENSION == extension {
+        (Some("dolphin-tool"), dolphin::get_version().await.is_ok())
+    } else {
+        (None, true)
+    };
+    lines.push(match tool {
+        Some(tool) if installed => format!("Required tool: {}", tool),
+        Some(tool) => format!("Required tool: {} (not installed)", tool),
+        None => String::from("Required tool: none"),
+    });
+
+    if DAT_EXTENSION == extension {
+        lines.push(String::from("Import with: import-dats"));
+        return Ok(lines);
+    }
+    if [BPS_EXTENSION, IPS_EXTENSION, XDELTA_EXTENSION].contains(&extension) {
+        lines.push(String::from("Import with: import-patches"));
+        return Ok(lines);
+    }
+    if IRD_EXTENSION == extension {
+        lines.push(String::from("Import with: import-irds"));
+        return Ok(lines);
+    }
+    if CHD_EXTENSION == extension && installed {
+        lines.push(String::from("Import with: import-roms"));
+        inspect_chd(connection, progress_bar, path, &mut lines).await?;
+        return Ok(lines);
+    }
+    if ARCHIVE_EXTENSIONS.contains(&extension) && installed {
+        lines.push(String::from("Import with: import-roms"));
+        inspect_archive(connection, progress_bar, path, &mut lines).await?;
+        return Ok(lines);
+    }
+    if tool.is_some() || [CSO_EXTENSION, ZSO_EXTENSION].contains(&extension) {
+        // contents can only be matched once extracted, which is left to import-roms
+        lines.push(String::from(
+            "Import with: import-roms, contents are matched after extraction",
+        ));
+        return Ok(lines);
+    }
+    lines.push(String::from("Import with: import-roms"));
+
+    let (sha1, size) = CommonRomfile::from_path(&path)?
+        .get_hash_and_size(connection, progress_bar, 1, 1, &HashAlgorithm::Sha1)
+        .await?;
+    lines.push(format!("SHA1: {}", sha1));
+    let roms = find_roms_by_size_and_sha1(connection, size, &sha1).await;
+    push_matches(connection, &mut lines, roms).await;
+
+    Ok(lines)
+}
+
+async fn inspect_chd(
+    connection: &mut SqliteConnection,
+    progress_bar: &ProgressBar,
+    path: &Path,
+    lines: &mut Vec<String>,
+) -> SimpleResult<()> {
+    let chd_romfile = CommonRomfile::from_path(&path)?.as_chd().await?;
+    // MAME's CHD DATs have no size information and use the CHD SHA1
+    let mut roms = find_roms_by_size_and_sha1(connection, 0, &chd_romfile.chd_sha1).await;
+    if chd_romfile.chd_type == ChdType::Cd {
+        // CD headers only carry the SHA1 of the whole disc, tracks are hashed once extracted
+        if chd_romfile.track_count > 1
+            && chdman::get_version()
+                .await?
+                .as_str()
+                .cmp(chdman::MIN_SPLITBIN_VERSION)
+                == Ordering::Less
+        {
+            lines.push(format!(
+                "Older chdman versions don't support splitbin, please update to {} or newer",
+                chdman::MIN_SPLITBIN_VERSION
+            ));
+        } else {
+            let tmp_directory = create_tmp_directory(connection).await?;
+            let cue_bin_romfile = chd_romfile
+                .to_cue_bin(progress_bar, &tmp_directory.path(), None, &[], true)
+                .await?;
+            let hashes_and_sizes = compute_hashes_and_sizes(
+                progress_bar,
+                &cue_bin_romfile.bin_romfiles,
+                &HashAlgorithm::Sha1,
+            )?;
+            for (bin_romfile, (sha1, size)) in
+                cue_bin_romfile.bin_romfiles.iter().zip(hashes_and_sizes)
+            {
+                lines.push(format!(
+                    "Track \"{}\" SHA1: {}",
+                    bin_romfile.path.file_name().unwrap().to_str().unwrap(),
+                    sha1
+                ));
+                roms.append(&mut find_roms_by_size_and_sha1(connection, size, &sha1).await);
+            }
+        }
+    } else {
+        lines.push(format!("SHA1: {}", chd_romfile.sha1));
+        roms.append(
+            &mut find_roms_by_size_and_sha1(connection, chd_romfile.size, &chd_romfile.sha1).await,
+        );
+    }
+    push_matches(connection, lines, roms).await;
+    Ok(())
+}
+
+async fn inspect_archive(
+    connection: &mut SqliteConnection,
+    progress_bar: &ProgressBar,
+    path: &Path,
+    lines: &mut Vec<String>,
+) -> SimpleResult<()> {
+    // archive listings carry the CRC of each member
+    let archive_romfiles = CommonRomfile::from_path(&path)?
+        .as_archive(progress_bar, None)
+        .await?;
+    for archive_romfile in archive_romfiles {
+        lines.push(format!(
+            "Member \"{}\" CRC: {}",
+            archive_romfile.path, archive_romfile.crc
+        ));
+        let roms =
+            find_roms_by_size_and_crc(connection, archive_romfile.size, &archive_romfile.crc).await;
+        push_matches(connection, lines, roms).await;
+    }
+    Ok(())
+}
+
+async fn push_matches(connection: &mut SqliteConnection, lines: &mut Vec<String>, roms: Vec<Rom>) {
+    if roms.is_empty() {
+        lines.push(String::from("No matching ROM in the imported DATs"));
+    }
+    for rom in roms {
+        let game = find_game_by_id(connection, rom.game_id).await;
+        let system = find_system_by_id(connection, game.system_id).await;
+        lines.push(format!(
+            "Matches \"{}\" from \"{}\" in \"{}\"{}",
+            rom.name,
+            game.name,
+            system.name,
+            if rom.romfile_id.is_some() {
+                " (already imported)"
+            } else {
+                ""
+            }
+        ));
+    }
+}
+
+#[cfg(test)]
+mod test_chd;
+#[cfg(test)]
+mod test_chd_single_track;
+#[cfg(test)]
+mod test_unknown;
+#[cfg(test)]
+mod test_zip;
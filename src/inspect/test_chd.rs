@@ -0,0 +1,47 @@
+use super::super::database::*;
+use super::super::import_dats;
+use super::*;
+use std::path::{Path, PathBuf};
+use tempfile::{NamedTempFile, TempDir};
+
+#[tokio::test]
+async fn test() {
+    // given
+    let _guard = MUTEX.lock().await;
+
+    let test_directory = Path::new("tests");
+    let progress_bar = ProgressBar::hidden();
+
+    let db_file = NamedTempFile::new().unwrap();
+    let pool = establish_connection(db_file.path().to_str().unwrap()).await;
+    let mut connection = pool.acquire().await.unwrap();
+
+    let rom_directory = TempDir::new_in(&test_directory).unwrap();
+    set_rom_directory(PathBuf::from(rom_directory.path()));
+    let tmp_directory = TempDir::new_in(&test_directory).unwrap();
+    set_tmp_directory(PathBuf::from(tmp_directory.path()));
+
+    let matches = import_dats::subcommand()
+        .get_matches_from(&["import-dats", "tests/Test System (20200721).dat"]);
+    import_dats::main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    let romfile_path = test_directory.join("Test Game (USA, Europe) (ISO).chd");
+
+    // when
+    let lines = inspect_file(&mut connection, &progress_bar, &romfile_path)
+        .await
+        .unwrap();
+
+    // then
+    assert!(lines.contains(&String::from("Type: chd (application/x-chd)")));
+    assert!(lines
+        .iter()
+        .any(|line| line.starts_with("Required tool: chdman")));
+    assert!(lines.contains(&String::from("Import with: import-roms")));
+    assert!(lines.iter().any(|line| line.starts_with("SHA1: ")));
+    assert!(lines.contains(&String::from(
+        "Matches \"Test Game (USA, Europe) (ISO).iso\" from \"Test Game (USA, Europe) (ISO)\" in \"Test System\""
+    )));
+}
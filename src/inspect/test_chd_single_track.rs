@@ -0,0 +1,46 @@
+use super::super::database::*;
+use super::super::import_dats;
+use super::*;
+use std::path::{Path, PathBuf};
+use tempfile::{NamedTempFile, TempDir};
+
+#[tokio::test]
+async fn test() {
+    // given
+    let _guard = MUTEX.lock().await;
+
+    let test_directory = Path::new("tests");
+    let progress_bar = ProgressBar::hidden();
+
+    let db_file = NamedTempFile::new().unwrap();
+    let pool = establish_connection(db_file.path().to_str().unwrap()).await;
+    let mut connection = pool.acquire().await.unwrap();
+
+    let rom_directory = TempDir::new_in(&test_directory).unwrap();
+    set_rom_directory(PathBuf::from(rom_directory.path()));
+    let tmp_directory = TempDir::new_in(&test_directory).unwrap();
+    set_tmp_directory(PathBuf::from(tmp_directory.path()));
+
+    let matches = import_dats::subcommand().get_matches_from(&[
+        "import-dats",
+        "tests/Test System (20240229) (Single Track).dat",
+    ]);
+    import_dats::main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    let romfile_path = test_directory.join("Test Game (USA, Europe) (Single Track).chd");
+
+    // when
+    let lines = inspect_file(&mut connection, &progress_bar, &romfile_path)
+        .await
+        .unwrap();
+
+    // then
+    assert!(lines
+        .iter()
+        .any(|line| line.starts_with("Track \"") && line.contains(" SHA1: ")));
+    assert!(lines.contains(&String::from(
+        "Matches \"Test Game (USA, Europe) (CUE BIN) (Track 01).bin\" from \"Test Game (USA, Europe) (CUE BIN)\" in \"Test System\""
+    )));
+}
@@ -0,0 +1,54 @@
+use super::super::database::*;
+use super::super::import_dats;
+use super::*;
+use std::path::{Path, PathBuf};
+use tempfile::{NamedTempFile, TempDir};
+use tokio::fs;
+
+#[tokio::test]
+async fn test() {
+    // given
+    let _guard = MUTEX.lock().await;
+
+    let test_directory = Path::new("tests");
+    let progress_bar = ProgressBar::hidden();
+
+    let db_file = NamedTempFile::new().unwrap();
+    let pool = establish_connection(db_file.path().to_str().unwrap()).await;
+    let mut connection = pool.acquire().await.unwrap();
+
+    let rom_directory = TempDir::new_in(&test_directory).unwrap();
+    set_rom_directory(PathBuf::from(rom_directory.path()));
+    let tmp_directory = TempDir::new_in(&test_directory).unwrap();
+    let tmp_directory = set_tmp_directory(PathBuf::from(tmp_directory.path()));
+
+    let matches = import_dats::subcommand()
+        .get_matches_from(&["import-dats", "tests/Test System (20200721).dat"]);
+    import_dats::main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    let romfile_path = tmp_directory.join("Unknown.rom");
+    fs::write(&romfile_path, "Not a ROM file\n".repeat(16))
+        .await
+        .unwrap();
+
+    // when
+    let lines = inspect_file(&mut connection, &progress_bar, &romfile_path)
+        .await
+        .unwrap();
+
+    // then
+    assert_eq!(
+        lines,
+        vec![
+            String::from("Inspecting \"Unknown.rom\""),
+            String::from("Size: 240 B"),
+            String::from("Type: unknown"),
+            String::from("Required tool: none"),
+            String::from("Import with: import-roms"),
+            String::from("SHA1: 1b063a697a52d40c8a3290e1a4e293eb13eba490"),
+            String::from("No matching ROM in the imported DATs"),
+        ]
+    );
+}
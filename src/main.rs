@@ -35,11 +35,13 @@ extern crate reqwest;
 extern crate rust_embed;
 extern crate serde;
 extern crate sha1;
+extern crate sha2;
 #[macro_use]
 extern crate simple_error;
 extern crate sqlx;
 extern crate strsim;
 extern crate strum;
+extern crate tar;
 extern crate tempfile;
 extern crate tokio;
 extern crate vec_drain_where;
@@ -50,6 +52,7 @@ mod bchunk;
 mod benchmark;
 mod chdman;
 mod check_roms;
+mod commit;
 mod common;
 mod config;
 mod convert_roms;
@@ -57,16 +60,22 @@ mod crc32;
 mod create_dats;
 mod ctrtool;
 mod database;
+mod db;
 mod dolphin;
 mod download_dats;
 mod export_roms;
+mod fix_roms;
 mod flips;
 mod generate_playlists;
+mod history;
+mod identify;
+mod import_cuesheets;
 mod import_dats;
 mod import_irds;
 mod import_patches;
 mod import_roms;
 mod info;
+mod inspect;
 mod maxcso;
 mod mimetype;
 mod model;
@@ -80,10 +89,14 @@ mod purge_systems;
 #[cfg(feature = "server")]
 mod query;
 mod rebuild_roms;
+mod reindex;
+mod report;
 #[cfg(feature = "server")]
 mod server;
 mod sevenzip;
 mod sort_roms;
+mod tag;
+mod unmatched;
 mod util;
 #[cfg(feature = "server")]
 mod validator;
@@ -91,8 +104,8 @@ mod wit;
 mod xdelta3;
 
 use cfg_if::cfg_if;
-use clap::Command;
-use config::{get_rom_directory, get_tmp_directory};
+use clap::{Arg, ArgAction, Command};
+use config::{get_directory, get_integer, get_rom_directory, get_string, get_tmp_directory};
 use database::*;
 use dotenvy::dotenv;
 use env_logger::{Builder, Target};
@@ -100,30 +113,51 @@ use progress::*;
 use simple_error::SimpleError;
 use std::env;
 use std::path::PathBuf;
+use std::process::ExitCode;
 use util::*;
 
 type SimpleResult<T> = Result<T, SimpleError>;
 
 #[tokio::main]
+async fn main() -> ExitCode {
+    match run().await {
+        Ok(()) => get_outcome().into(),
+        Err(error) => {
+            eprintln!("Error: {:?}", error);
+            Outcome::Error.into()
+        }
+    }
+}
+
 #[allow(unused_mut)]
-async fn main() -> SimpleResult<()> {
+async fn run() -> SimpleResult<()> {
     let mut subcommands = vec![
         info::subcommand(),
         config::subcommand(),
+        db::subcommand(),
         create_dats::subcommand(),
         import_dats::subcommand(),
         download_dats::subcommand(),
         import_irds::subcommand(),
+        import_cuesheets::subcommand(),
         import_patches::subcommand(),
         import_roms::subcommand(),
+        inspect::subcommand(),
+        unmatched::subcommand(),
         sort_roms::subcommand(),
         convert_roms::subcommand(),
         export_roms::subcommand(),
         rebuild_roms::subcommand(),
+        reindex::subcommand(),
         check_roms::subcommand(),
+        fix_roms::subcommand(),
         purge_roms::subcommand(),
         purge_systems::subcommand(),
+        commit::subcommand(),
         generate_playlists::subcommand(),
+        tag::subcommand(),
+        report::subcommand(),
+        history::subcommand(),
         benchmark::subcommand(),
     ];
     cfg_if! {
@@ -135,6 +169,22 @@ async fn main() -> SimpleResult<()> {
         .version(env!("CARGO_PKG_VERSION"))
         .about(env!("CARGO_PKG_DESCRIPTION"))
         .author(env!("CARGO_PKG_AUTHORS"))
+        .arg(
+            Arg::new("SAFE")
+                .long("safe")
+                .help("Stage deleted ROM files until commit is run")
+                .required(false)
+                .global(true)
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("NICE")
+                .long("nice")
+                .help("Lower the priority of external tools and hashing threads")
+                .required(false)
+                .global(true)
+                .action(ArgAction::SetTrue),
+        )
         .subcommands(subcommands)
         .get_matches();
 
@@ -166,8 +216,55 @@ async fn main() -> SimpleResult<()> {
         let pool = establish_connection(db_file.as_os_str().to_str().unwrap()).await;
 
         // make sure rom and tmp directories are initialized
-        get_rom_directory(&mut pool.acquire().await.unwrap()).await;
-        get_tmp_directory(&mut pool.acquire().await.unwrap()).await;
+        let rom_directory = get_rom_directory(&mut pool.acquire().await.unwrap()).await;
+        let tmp_directory = get_tmp_directory(&mut pool.acquire().await.unwrap()).await;
+
+        if matches.get_flag("SAFE") {
+            set_safe_mode(Some((rom_directory.clone(), tmp_directory.clone())));
+        }
+
+        // warn early about a configured export directory that went missing, e.g. an unmounted device
+        if let Some(export_directory) =
+            get_string(&mut pool.acquire().await.unwrap(), "EXPORT_DIRECTORY").await
+        {
+            if get_directory(&mut pool.acquire().await.unwrap(), "EXPORT_DIRECTORY")
+                .await
+                .is_none()
+            {
+                progress_bar.println(format!(
+                    "EXPORT_DIRECTORY \"{}\" doesn't exist",
+                    export_directory
+                ));
+            }
+        }
+
+        set_tool_retries(
+            get_integer(&mut pool.acquire().await.unwrap(), "TOOL_RETRIES")
+                .await
+                .unwrap_or(0),
+        );
+        let mut tool_niceness = get_integer(&mut pool.acquire().await.unwrap(), "TOOL_NICENESS")
+            .await
+            .unwrap_or(0);
+        if matches.get_flag("NICE") && tool_niceness == 0 {
+            tool_niceness = DEFAULT_NICENESS;
+        }
+        set_tool_niceness(tool_niceness);
+        // all hashing goes through the global pool, rayon threads are spawned lazily so they're sized and niced before the first hash
+        rayon::ThreadPoolBuilder::new()
+            .num_threads(
+                get_integer(&mut pool.acquire().await.unwrap(), "PARALLELISM")
+                    .await
+                    .unwrap_or(0),
+            )
+            .start_handler(move |_| lower_thread_priority(tool_niceness))
+            .build_global()
+            .ok();
+        set_max_tool_processes(
+            get_integer(&mut pool.acquire().await.unwrap(), "MAX_TOOL_PROCESSES")
+                .await
+                .unwrap_or(0),
+        );
 
         match matches.subcommand_name() {
             Some("info") => info::main(&mut pool.acquire().await.unwrap(), &progress_bar).await?,
@@ -179,6 +276,14 @@ async fn main() -> SimpleResult<()> {
                 )
                 .await?
             }
+            Some("db") => {
+                db::main(
+                    &mut pool.acquire().await.unwrap(),
+                    matches.subcommand_matches("db").unwrap(),
+                    &progress_bar,
+                )
+                .await?
+            }
             Some("create-dats") => {
                 create_dats::main(
                     &mut pool.acquire().await.unwrap(),
@@ -211,6 +316,14 @@ async fn main() -> SimpleResult<()> {
                 )
                 .await?
             }
+            Some("import-cuesheets") => {
+                import_cuesheets::main(
+                    &mut pool.acquire().await.unwrap(),
+                    matches.subcommand_matches("import-cuesheets").unwrap(),
+                    &progress_bar,
+                )
+                .await?
+            }
             Some("import-patches") => {
                 import_patches::main(
                     &mut pool.acquire().await.unwrap(),
@@ -225,6 +338,22 @@ async fn main() -> SimpleResult<()> {
                     matches.subcommand_matches("import-roms").unwrap(),
                     &progress_bar,
                 )
+                .await?;
+            }
+            Some("inspect") => {
+                inspect::main(
+                    &mut pool.acquire().await.unwrap(),
+                    matches.subcommand_matches("inspect").unwrap(),
+                    &progress_bar,
+                )
+                .await?
+            }
+            Some("unmatched") => {
+                unmatched::main(
+                    &mut pool.acquire().await.unwrap(),
+                    matches.subcommand_matches("unmatched").unwrap(),
+                    &progress_bar,
+                )
                 .await?
             }
             Some("sort-roms") => {
@@ -241,7 +370,7 @@ async fn main() -> SimpleResult<()> {
                     matches.subcommand_matches("convert-roms").unwrap(),
                     &progress_bar,
                 )
-                .await?
+                .await?;
             }
             Some("export-roms") => {
                 export_roms::main(
@@ -259,6 +388,14 @@ async fn main() -> SimpleResult<()> {
                 )
                 .await?
             }
+            Some("reindex") => {
+                reindex::main(
+                    &mut pool.acquire().await.unwrap(),
+                    matches.subcommand_matches("reindex").unwrap(),
+                    &progress_bar,
+                )
+                .await?
+            }
             Some("check-roms") => {
                 check_roms::main(
                     &mut pool.acquire().await.unwrap(),
@@ -267,6 +404,14 @@ async fn main() -> SimpleResult<()> {
                 )
                 .await?
             }
+            Some("fix-roms") => {
+                fix_roms::main(
+                    &mut pool.acquire().await.unwrap(),
+                    matches.subcommand_matches("fix-roms").unwrap(),
+                    &progress_bar,
+                )
+                .await?
+            }
             Some("purge-roms") => {
                 purge_roms::main(
                     &mut pool.acquire().await.unwrap(),
@@ -276,7 +421,20 @@ async fn main() -> SimpleResult<()> {
                 .await?
             }
             Some("purge-systems") => {
-                purge_systems::main(&mut pool.acquire().await.unwrap(), &progress_bar).await?
+                purge_systems::main(
+                    &mut pool.acquire().await.unwrap(),
+                    matches.subcommand_matches("purge-systems").unwrap(),
+                    &progress_bar,
+                )
+                .await?
+            }
+            Some("commit") => {
+                commit::main(
+                    &mut pool.acquire().await.unwrap(),
+                    matches.subcommand_matches("commit").unwrap(),
+                    &progress_bar,
+                )
+                .await?
             }
             Some("generate-playlists") => {
                 generate_playlists::main(
@@ -286,6 +444,30 @@ async fn main() -> SimpleResult<()> {
                 )
                 .await?
             }
+            Some("tag") => {
+                tag::main(
+                    &mut pool.acquire().await.unwrap(),
+                    matches.subcommand_matches("tag").unwrap(),
+                    &progress_bar,
+                )
+                .await?
+            }
+            Some("report") => {
+                report::main(
+                    &mut pool.acquire().await.unwrap(),
+                    matches.subcommand_matches("report").unwrap(),
+                    &progress_bar,
+                )
+                .await?
+            }
+            Some("history") => {
+                history::main(
+                    &mut pool.acquire().await.unwrap(),
+                    matches.subcommand_matches("history").unwrap(),
+                    &progress_bar,
+                )
+                .await?
+            }
             Some("benchmark") => {
                 benchmark::main(
                     &mut pool.acquire().await.unwrap(),
@@ -303,7 +485,7 @@ async fn main() -> SimpleResult<()> {
             }
             _ => (),
         }
-        optimize_database(pool).await;
+        optimize_database(pool, &progress_bar).await;
     }
 
     Ok(())
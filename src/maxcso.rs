@@ -5,9 +5,11 @@ use super::model::*;
 use super::progress::*;
 use super::util::*;
 use super::SimpleResult;
+use flate2::read::DeflateDecoder;
 use indicatif::ProgressBar;
 use regex::Regex;
 use sqlx::SqliteConnection;
+use std::io::{BufWriter, Read, Seek, SeekFrom, Write};
 use std::path::Path;
 use std::str::FromStr;
 use std::time::Duration;
@@ -15,6 +17,10 @@ use strum::{Display, EnumString};
 use tokio::process::Command;
 
 const MAXCSO: &str = "maxcso";
+const CSO_MAGIC: &[u8] = b"CISO";
+const ZSO_MAGIC: &[u8] = b"ZISO";
+const XSO_HEADER_SIZE: usize = 24;
+const XSO_PLAIN_FLAG: u32 = 0x80000000;
 
 lazy_static! {
     static ref VERSION_REGEX: Regex = Regex::new(r"\d+\.\d+\.\d+").unwrap();
@@ -91,12 +97,20 @@ impl ToIso for XsoRomfile {
             .join(self.romfile.path.file_name().unwrap())
             .with_extension(ISO_EXTENSION);
 
-        let output = Command::new(MAXCSO)
+        // extraction doesn't need maxcso, only compression does
+        if get_version().await.is_err() {
+            decompress_xso(&self.romfile.path, &path, self.xso_type)?;
+            progress_bar.set_message("");
+            progress_bar.disable_steady_tick();
+            return CommonRomfile::from_path(&path)?.as_iso();
+        }
+
+        let output = Command::new(get_tool_path(MAXCSO))
             .arg("--decompress")
             .arg(&self.romfile.path)
             .arg("-o")
             .arg(&path)
-            .output()
+            .output_with_retries()
             .await
             .unwrap_or_else(|_| panic!("Failed to extract {}", self.xso_type));
 
@@ -111,6 +125,179 @@ impl ToIso for XsoRomfile {
     }
 }
 
+pub fn decompress_xso<P: AsRef<Path>, Q: AsRef<Path>>(
+    xso_path: &P,
+    iso_path: &Q,
+    xso_type: XsoType,
+) -> SimpleResult<()> {
+    let xso_path = xso_path.as_ref().as_os_str().to_str().unwrap();
+    let mut reader = get_reader_sync(&xso_path)?;
+
+    let mut header = [0u8; XSO_HEADER_SIZE];
+    try_with!(
+        reader.read_exact(&mut header),
+        "Failed to read \"{}\"",
+        xso_path
+    );
+    let magic = match xso_type {
+        XsoType::Cso => CSO_MAGIC,
+        XsoType::Zso => ZSO_MAGIC,
+    };
+    if &header[0..4] != magic {
+        bail!("Not a valid {}", xso_type);
+    }
+    let header_size = u32::from_le_bytes(header[4..8].try_into().unwrap()) as u64;
+    let total_bytes = u64::from_le_bytes(header[8..16].try_into().unwrap());
+    let block_size = u32::from_le_bytes(header[16..20].try_into().unwrap()) as u64;
+    let version = header[20];
+    let align = header[21];
+    if block_size == 0 || (xso_type == XsoType::Cso && version > 1) {
+        bail!("Unsupported {} version", xso_type);
+    }
+
+    // one more entry than blocks to get the size of the last one
+    let block_count = total_bytes.div_ceil(block_size) as usize;
+    try_with!(
+        reader.seek(SeekFrom::Start(header_size)),
+        "Failed to read \"{}\"",
+        xso_path
+    );
+    let mut index = vec![0u8; (block_count + 1) * 4];
+    try_with!(
+        reader.read_exact(&mut index),
+        "Failed to read \"{}\"",
+        xso_path
+    );
+    let index: Vec<u32> = index
+        .chunks_exact(4)
+        .map(|chunk| u32::from_le_bytes(chunk.try_into().unwrap()))
+        .collect();
+
+    let mut writer = BufWriter::new(try_with!(
+        std::fs::File::create(iso_path),
+        "Failed to create \"{}\"",
+        iso_path.as_ref().as_os_str().to_str().unwrap()
+    ));
+    let mut data: Vec<u8> = vec![];
+    let mut block: Vec<u8> = Vec::with_capacity(block_size as usize);
+    for i in 0..block_count {
+        let plain = index[i] & XSO_PLAIN_FLAG != 0;
+        let position = ((index[i] & !XSO_PLAIN_FLAG) as u64) << align;
+        let next_position = ((index[i + 1] & !XSO_PLAIN_FLAG) as u64) << align;
+        let expected_size = block_size.min(total_bytes - i as u64 * block_size) as usize;
+        if next_position < position {
+            bail!("Invalid {} index", xso_type);
+        }
+
+        data.resize((next_position - position) as usize, 0);
+        try_with!(
+            reader.seek(SeekFrom::Start(position)),
+            "Failed to read \"{}\"",
+            xso_path
+        );
+        try_with!(
+            reader.read_exact(&mut data),
+            "Failed to read \"{}\"",
+            xso_path
+        );
+
+        block.clear();
+        if plain {
+            block.extend_from_slice(&data[..expected_size.min(data.len())]);
+        } else {
+            match xso_type {
+                XsoType::Cso => {
+                    try_with!(
+                        DeflateDecoder::new(data.as_slice())
+                            .take(expected_size as u64)
+                            .read_to_end(&mut block),
+                        "Failed to decompress \"{}\"",
+                        xso_path
+                    );
+                }
+                XsoType::Zso => decompress_lz4_block(&data, &mut block, expected_size)?,
+            }
+        }
+        if block.len() != expected_size {
+            bail!("Failed to decompress \"{}\"", xso_path);
+        }
+        try_with!(
+            writer.write_all(&block),
+            "Failed to write \"{}\"",
+            iso_path.as_ref().as_os_str().to_str().unwrap()
+        );
+    }
+    try_with!(
+        writer.flush(),
+        "Failed to write \"{}\"",
+        iso_path.as_ref().as_os_str().to_str().unwrap()
+    );
+
+    Ok(())
+}
+
+fn decompress_lz4_block(input: &[u8], output: &mut Vec<u8>, size: usize) -> SimpleResult<()> {
+    let read_length = |i: &mut usize, length: &mut usize| -> SimpleResult<()> {
+        loop {
+            let byte = match input.get(*i) {
+                Some(byte) => *byte,
+                None => bail!("Invalid LZ4 block"),
+            };
+            *i += 1;
+            *length += byte as usize;
+            if byte != 255 {
+                return Ok(());
+            }
+        }
+    };
+
+    let mut i = 0;
+    // aligned blocks may be followed by padding, stop as soon as the block is complete
+    while i < input.len() && output.len() < size {
+        let token = input[i];
+        i += 1;
+
+        let mut literal_length = (token >> 4) as usize;
+        if literal_length == 15 {
+            read_length(&mut i, &mut literal_length)?;
+        }
+        let literals = match input.get(i..i + literal_length) {
+            Some(literals) => literals,
+            None => bail!("Invalid LZ4 block"),
+        };
+        output.extend_from_slice(literals);
+        i += literal_length;
+
+        // the last sequence only has literals
+        if i >= input.len() || output.len() >= size {
+            break;
+        }
+
+        let offset = match input.get(i..i + 2) {
+            Some(offset) => u16::from_le_bytes([offset[0], offset[1]]) as usize,
+            None => bail!("Invalid LZ4 block"),
+        };
+        i += 2;
+        if offset == 0 || offset > output.len() {
+            bail!("Invalid LZ4 block");
+        }
+
+        let mut match_length = (token & 0x0f) as usize;
+        if match_length == 15 {
+            read_length(&mut i, &mut match_length)?;
+        }
+        match_length += 4;
+
+        // matches may overlap with the bytes they produce
+        let start = output.len() - offset;
+        for j in 0..match_length {
+            output.push(output[start + j]);
+        }
+    }
+
+    Ok(())
+}
+
 pub trait ToXso {
     async fn to_xso<P: AsRef<Path>>(
         &self,
@@ -144,7 +331,7 @@ impl ToXso for IsoRomfile {
             path.file_name().unwrap().to_str().unwrap()
         ));
 
-        let output = Command::new(MAXCSO)
+        let output = Command::new(get_tool_path(MAXCSO))
             .arg("--block=2048")
             .arg(format!(
                 "--format={}",
@@ -156,7 +343,7 @@ impl ToXso for IsoRomfile {
             .arg(&self.romfile.path)
             .arg("-o")
             .arg(&path)
-            .output()
+            .output_with_retries()
             .await
             .unwrap_or_else(|_| panic!("Failed to create {}", xso_type));
 
@@ -194,7 +381,9 @@ impl AsXso for CommonRomfile {
 
 pub async fn get_version() -> SimpleResult<String> {
     let output = try_with!(
-        Command::new(MAXCSO).output().await,
+        Command::new(get_tool_path(MAXCSO))
+            .output_with_retries()
+            .await,
         "Failed to spawn maxcso"
     );
 
@@ -208,3 +397,8 @@ pub async fn get_version() -> SimpleResult<String> {
 
     Ok(version)
 }
+
+#[cfg(test)]
+mod test_cso_to_iso_without_maxcso;
+#[cfg(test)]
+mod test_zso_to_iso_without_maxcso;
@@ -0,0 +1,35 @@
+use super::super::database::*;
+use super::*;
+use std::path::PathBuf;
+use tempfile::{NamedTempFile, TempDir};
+
+#[tokio::test]
+async fn test() {
+    // given
+    let test_directory = Path::new("tests");
+    let progress_bar = ProgressBar::hidden();
+
+    let db_file = NamedTempFile::new().unwrap();
+    let pool = establish_connection(db_file.path().to_str().unwrap()).await;
+    let mut connection = pool.acquire().await.unwrap();
+
+    let tmp_directory = TempDir::new_in(&test_directory).unwrap();
+    let iso_path = PathBuf::from(tmp_directory.path()).join("Test Game (USA, Europe).iso");
+
+    // when
+    decompress_xso(
+        &test_directory.join("Test Game (USA, Europe).cso"),
+        &iso_path,
+        XsoType::Cso,
+    )
+    .unwrap();
+
+    // then
+    let (hash, size) = CommonRomfile::from_path(&iso_path)
+        .unwrap()
+        .get_hash_and_size(&mut connection, &progress_bar, 1, 1, &HashAlgorithm::Sha1)
+        .await
+        .unwrap();
+    assert_eq!(size, 358400);
+    assert_eq!(hash, "762a227d4d157c20e671b53041741ba6c22c552b");
+}
@@ -1,11 +1,15 @@
+use super::config::CIA_EXTENSION;
 use async_once_cell::OnceCell;
 use infer::{Infer, Type};
 use simple_error::SimpleResult;
+use std::io::Read;
 use std::path::Path;
 
 pub const BPS_EXTENSION: &str = "bps";
+pub const CCI_EXTENSION: &str = "3ds";
 pub const CHD_EXTENSION: &str = "chd";
 pub const CSO_EXTENSION: &str = "cso";
+pub const CXI_EXTENSION: &str = "cxi";
 pub const GZ_EXTENSION: &str = "gz";
 pub const IPS_EXTENSION: &str = "ips";
 pub const IRD_EXTENSION: &str = "ird";
@@ -15,6 +19,10 @@ pub const RIFF_EXTENSION: &str = "riff";
 pub const RVZ_EXTENSION: &str = "rvz";
 pub const XDELTA_EXTENSION: &str = "xdelta";
 pub const ZSO_EXTENSION: &str = "zso";
+pub const ZST_EXTENSION: &str = "zst";
+
+const NCSD_NCCH_MAGIC_OFFSET: usize = 0x100;
+const TAR_MAGIC_OFFSET: usize = 0x101;
 
 static MATCHER: OnceCell<Infer> = OnceCell::new();
 
@@ -22,6 +30,11 @@ fn bps_matcher(buf: &[u8]) -> bool {
     buf.len() >= 4 && buf[0] == 0x42 && buf[1] == 0x50 && buf[2] == 0x53 && buf[3] == 0x31
 }
 
+fn cci_matcher(buf: &[u8]) -> bool {
+    buf.len() >= NCSD_NCCH_MAGIC_OFFSET + 4
+        && &buf[NCSD_NCCH_MAGIC_OFFSET..NCSD_NCCH_MAGIC_OFFSET + 4] == b"NCSD"
+}
+
 fn chd_matcher(buf: &[u8]) -> bool {
     buf.len() >= 8
         && buf[0] == 0x4D
@@ -34,10 +47,28 @@ fn chd_matcher(buf: &[u8]) -> bool {
         && buf[7] == 0x44
 }
 
+// header size, type and version
+fn cia_matcher(buf: &[u8]) -> bool {
+    buf.len() >= 8
+        && buf[0] == 0x20
+        && buf[1] == 0x20
+        && buf[2] == 0x00
+        && buf[3] == 0x00
+        && buf[4] == 0x00
+        && buf[5] == 0x00
+        && buf[6] == 0x00
+        && buf[7] == 0x00
+}
+
 fn cso_matcher(buf: &[u8]) -> bool {
     buf.len() >= 4 && buf[0] == 0x43 && buf[1] == 0x49 && buf[2] == 0x53 && buf[3] == 0x4F
 }
 
+fn cxi_matcher(buf: &[u8]) -> bool {
+    buf.len() >= NCSD_NCCH_MAGIC_OFFSET + 4
+        && &buf[NCSD_NCCH_MAGIC_OFFSET..NCSD_NCCH_MAGIC_OFFSET + 4] == b"NCCH"
+}
+
 fn ips_matcher(buf: &[u8]) -> bool {
     buf.len() >= 5
         && buf[0] == 0x50
@@ -63,6 +94,10 @@ fn rvz_matcher(buf: &[u8]) -> bool {
     buf.len() >= 4 && buf[0] == 0x52 && buf[1] == 0x56 && buf[2] == 0x5A && buf[3] == 0x01
 }
 
+fn tar_matcher(buf: &[u8]) -> bool {
+    buf.len() >= TAR_MAGIC_OFFSET + 5 && &buf[TAR_MAGIC_OFFSET..TAR_MAGIC_OFFSET + 5] == b"ustar"
+}
+
 fn xdelta_matcher(buf: &[u8]) -> bool {
     buf.len() >= 3 && buf[0] == 0xD6 && buf[1] == 0xC3 && buf[2] == 0xC4
 }
@@ -74,8 +109,11 @@ fn zso_matcher(buf: &[u8]) -> bool {
 async fn init_matcher() -> Infer {
     let mut matcher = Infer::new();
     matcher.add("application/x-bps", BPS_EXTENSION, bps_matcher);
+    matcher.add("application/x-cci", CCI_EXTENSION, cci_matcher);
     matcher.add("application/x-chd", CHD_EXTENSION, chd_matcher);
+    matcher.add("application/x-cia", CIA_EXTENSION, cia_matcher);
     matcher.add("application/x-cso", CSO_EXTENSION, cso_matcher);
+    matcher.add("application/x-cxi", CXI_EXTENSION, cxi_matcher);
     matcher.add("application/x-ips", IPS_EXTENSION, ips_matcher);
     matcher.add("application/x-ird", IRD_EXTENSION, ird_matcher);
     matcher.add("application/x-rdsk", RDSK_EXTENSION, rdsk_matcher);
@@ -93,3 +131,22 @@ pub async fn get_mimetype<P: AsRef<Path>>(path: &P) -> SimpleResult<Option<Type>
         "Failed to infer MIME type"
     ))
 }
+
+// zstd frames don't tell what they hold, peek at the decompressed header instead
+pub async fn is_tar_zst<P: AsRef<Path>>(path: &P) -> SimpleResult<bool> {
+    if get_mimetype(path)
+        .await?
+        .map(|mimetype| mimetype.extension())
+        != Some(ZST_EXTENSION)
+    {
+        return Ok(false);
+    }
+    let file = try_with!(
+        std::fs::File::open(path.as_ref()),
+        "Failed to open \"{}\"",
+        path.as_ref().as_os_str().to_str().unwrap()
+    );
+    let mut decoder = try_with!(zstd::Decoder::new(file), "Failed to read zstd stream");
+    let mut buffer = vec![0u8; TAR_MAGIC_OFFSET + 5];
+    Ok(decoder.read_exact(&mut buffer).is_ok() && tar_matcher(&buffer))
+}
@@ -79,6 +79,7 @@ pub struct Game {
     pub bios: bool,
     pub jbfolder: bool,
     pub regions: String,
+    pub languages: String,
     pub sorting: i64,
     pub completion: i64,
     pub system_id: i64,
@@ -109,7 +110,9 @@ pub struct Rom {
     pub crc: Option<String>,
     pub md5: Option<String>,
     pub sha1: Option<String>,
+    pub sha256: Option<String>,
     pub rom_status: Option<String>,
+    pub header_title: Option<String>,
     pub game_id: i64,
     pub romfile_id: Option<i64>,
     pub parent_id: Option<i64>,
@@ -132,6 +135,7 @@ pub enum RomfileType {
     Romfile = 0,
     Playlist = 1,
     Patch = 2,
+    Cuesheet = 3,
 }
 
 #[derive(FromRow, PartialEq, Eq)]
@@ -142,6 +146,27 @@ pub struct Romfile {
     pub size: i64,
     pub parent_id: Option<i64>,
     pub romfile_type: i64,
+    pub updated_at: i64,
+}
+
+#[derive(FromPrimitive, Type)]
+#[cfg_attr(feature = "server", derive(Clone, Copy, Enum, Eq, PartialEq))]
+#[repr(i8)]
+pub enum EventType {
+    Import = 0,
+    Convert = 1,
+    Sort = 2,
+}
+
+#[derive(FromRow)]
+#[cfg_attr(feature = "server", derive(Clone, SimpleObject))]
+pub struct Event {
+    pub id: i64,
+    pub event_type: i64,
+    pub source: Option<String>,
+    pub destination: String,
+    pub created_at: i64,
+    pub game_id: i64,
 }
 
 #[cfg_attr(feature = "server", derive(Clone, SimpleObject))]
@@ -151,6 +176,66 @@ pub struct Setting {
     pub value: Option<String>,
 }
 
+pub struct DatSource {
+    pub id: i64,
+    pub url: String,
+    pub header: Option<String>,
+}
+
+pub struct RomfileMove {
+    pub id: i64,
+    pub old_path: String,
+    pub new_path: String,
+    pub romfile_id: i64,
+}
+
+#[derive(FromRow)]
+pub struct RomfilePartialHash {
+    pub romfile_id: i64,
+    pub window_size: i64,
+    pub crc: String,
+    pub updated_at: i64,
+}
+
+#[derive(FromRow)]
+pub struct RomAltName {
+    pub id: i64,
+    pub name: String,
+    pub region: Option<String>,
+    pub rom_id: i64,
+}
+
+#[derive(FromRow)]
+#[cfg_attr(feature = "server", derive(Clone, SimpleObject))]
+pub struct GameRelease {
+    pub id: i64,
+    pub name: String,
+    pub region: String,
+    pub language: Option<String>,
+    pub date: Option<String>,
+    pub game_id: i64,
+}
+
+#[derive(FromRow)]
+pub struct Unmatched {
+    pub id: i64,
+    pub path: String,
+    pub size: i64,
+    pub goodtools_status: Option<String>,
+}
+
+pub struct Cuesheet {
+    pub id: i64,
+    pub name: String,
+    pub content: String,
+    pub game_id: i64,
+}
+
+pub struct Tag {
+    pub id: i64,
+    pub name: String,
+}
+
 #[derive(Deserialize)]
 pub struct ProfileXml {
     #[serde(alias = "datfile")]
@@ -168,6 +253,22 @@ pub struct DatfileXml {
     pub machines: Vec<GameXml>,
 }
 
+#[derive(Serialize)]
+#[serde(rename = "gameList")]
+pub struct GamelistXml {
+    #[serde(rename = "game")]
+    pub games: Vec<GamelistGameXml>,
+}
+
+#[derive(Serialize)]
+#[serde(rename = "game")]
+pub struct GamelistGameXml {
+    pub path: String,
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub desc: Option<String>,
+}
+
 #[derive(Deserialize, Serialize)]
 #[serde(rename = "header")]
 pub struct SystemXml {
@@ -226,6 +327,20 @@ pub struct GameXml {
     pub roms: Vec<RomXml>,
     #[serde(rename = "disk", default)]
     pub disks: Vec<RomXml>,
+    #[serde(rename = "release", default, skip_serializing)]
+    pub releases: Vec<ReleaseXml>,
+}
+
+#[derive(Deserialize)]
+pub struct ReleaseXml {
+    #[serde(rename = "@name")]
+    pub name: String,
+    #[serde(rename = "@region")]
+    pub region: String,
+    #[serde(rename = "@language")]
+    pub language: Option<String>,
+    #[serde(rename = "@date")]
+    pub date: Option<String>,
 }
 
 fn empty_string_to_zero<'de, D>(deserializer: D) -> Result<i64, D::Error>
@@ -251,8 +366,20 @@ pub struct RomXml {
     pub md5: Option<String>,
     #[serde(rename = "@sha1")]
     pub sha1: Option<String>,
+    #[serde(rename = "@sha256", skip_serializing_if = "Option::is_none")]
+    pub sha256: Option<String>,
     #[serde(rename = "@status", skip_serializing)]
     pub status: Option<String>,
+    #[serde(rename = "altname", default, skip_serializing)]
+    pub alt_names: Vec<AltNameXml>,
+}
+
+#[derive(Deserialize)]
+pub struct AltNameXml {
+    #[serde(rename = "@name")]
+    pub name: String,
+    #[serde(rename = "@region")]
+    pub region: Option<String>,
 }
 
 #[derive(Deserialize)]
@@ -93,7 +93,7 @@ impl ToNsp for NszRomfile {
             .arg("-o")
             .arg(destination_directory.as_ref())
             .arg(&self.romfile.path)
-            .output()
+            .output_with_retries()
             .await
             .expect("Failed to extract nsz");
 
@@ -144,7 +144,7 @@ impl ToNsz for NspRomfile {
             .arg("-o")
             .arg(destination_directory.as_ref())
             .arg(&self.romfile.path)
-            .output()
+            .output_with_retries()
             .await
             .expect("Failed to create nsz");
 
@@ -203,7 +203,7 @@ impl AsNsz for CommonRomfile {
 
 pub async fn get_version() -> SimpleResult<String> {
     let output = try_with!(
-        Command::new(NSZ).arg("-h").output().await,
+        Command::new(NSZ).arg("-h").output_with_retries().await,
         "Failed to spawn nsz"
     );
 
@@ -1,4 +1,5 @@
-use indicatif::{ProgressBar, ProgressStyle};
+use indicatif::{HumanBytes, HumanDuration, ProgressBar, ProgressStyle};
+use std::time::{Duration, Instant};
 
 const PROGRESS_CHARS: &str = "#-";
 const NONE_TEMPLATE: &str = "{spinner} {wide_msg}";
@@ -30,3 +31,66 @@ pub fn get_bytes_progress_style() -> ProgressStyle {
         .expect("Failed to create progress bar")
         .progress_chars(PROGRESS_CHARS)
 }
+
+pub struct Summary {
+    start: Instant,
+    pub count: usize,
+    pub bytes_in: u64,
+    pub bytes_out: u64,
+    pub converted: usize,
+    pub compliant: usize,
+    pub skipped: usize,
+}
+
+impl Summary {
+    pub fn new() -> Self {
+        Summary {
+            start: Instant::now(),
+            count: 0,
+            bytes_in: 0,
+            bytes_out: 0,
+            converted: 0,
+            compliant: 0,
+            skipped: 0,
+        }
+    }
+
+    pub fn add(&mut self, count: usize, bytes_in: u64, bytes_out: u64) {
+        self.count += count;
+        self.bytes_in += bytes_in;
+        self.bytes_out += bytes_out;
+    }
+
+    pub fn add_games(&mut self, converted: usize, compliant: usize, skipped: usize) {
+        self.converted += converted;
+        self.compliant += compliant;
+        self.skipped += skipped;
+    }
+
+    pub fn print(&self, progress_bar: &ProgressBar) {
+        let elapsed = self.start.elapsed();
+        let average = if self.count > 0 {
+            elapsed / self.count as u32
+        } else {
+            Duration::ZERO
+        };
+        progress_bar.println("Summary");
+        progress_bar.println(format!("Processed: {} file(s)", self.count));
+        progress_bar.println(format!(
+            "Elapsed: {} ({} per file)",
+            HumanDuration(elapsed),
+            HumanDuration(average)
+        ));
+        progress_bar.println(format!(
+            "In: {}; Out: {}",
+            HumanBytes(self.bytes_in),
+            HumanBytes(self.bytes_out)
+        ));
+    }
+}
+
+impl Default for Summary {
+    fn default() -> Self {
+        Self::new()
+    }
+}
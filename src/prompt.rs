@@ -1,9 +1,11 @@
+use super::config::*;
 use super::database::*;
 use super::model::*;
 use dialoguer::{Confirm, Editor, FuzzySelect, MultiSelect};
 use simple_error::SimpleResult;
 use sqlx::sqlite::SqliteConnection;
 use std::path::PathBuf;
+use std::str::FromStr;
 use strsim::jaro_winkler;
 
 pub async fn prompt_for_systems(
@@ -42,6 +44,23 @@ pub async fn prompt_for_systems(
         .collect())
 }
 
+pub async fn find_systems_by_name_match(
+    connection: &mut SqliteConnection,
+    name: &str,
+    exact: bool,
+) -> Vec<System> {
+    let system_match = if exact {
+        SystemMatch::Exact
+    } else {
+        SystemMatch::from_str(&get_string(connection, "SYSTEM_MATCH").await.unwrap()).unwrap()
+    };
+    match system_match {
+        SystemMatch::Exact => find_systems_by_name(connection, name).await,
+        SystemMatch::Prefix => find_systems_by_name_prefix(connection, name).await,
+        SystemMatch::Like => find_systems_by_name_like(connection, name).await,
+    }
+}
+
 pub async fn prompt_for_system(
     connection: &mut SqliteConnection,
     default: Option<usize>,
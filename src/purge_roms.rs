@@ -1,14 +1,21 @@
 use super::common::*;
 use super::config::*;
 use super::database::*;
+use super::model::*;
 use super::prompt::*;
 use super::util::*;
 use super::SimpleResult;
 use clap::{Arg, ArgAction, ArgMatches, Command};
 use indicatif::ProgressBar;
 use sqlx::sqlite::SqliteConnection;
+use std::collections::HashSet;
+use std::path::Path;
+use tokio::fs;
 use walkdir::WalkDir;
 
+pub const TRASH_MAX_ITEMS_RANGE: [usize; 2] = [0, 100000];
+pub const TRASH_RETENTION_DAYS_RANGE: [usize; 2] = [1, 3650];
+
 pub fn subcommand() -> Command {
     Command::new("purge-roms")
         .about("Purge trashed, missing, and orphan ROM files")
@@ -36,6 +43,14 @@ pub fn subcommand() -> Command {
                 .required(false)
                 .action(ArgAction::SetTrue),
         )
+        .arg(
+            Arg::new("RETENTION")
+                .short('r')
+                .long("retention")
+                .help("Physically delete trashed ROM files exceeding the trash retention policy")
+                .required(false)
+                .action(ArgAction::SetTrue),
+        )
         .arg(
             Arg::new("FOREIGN")
                 .short('f')
@@ -44,6 +59,13 @@ pub fn subcommand() -> Command {
                 .required(false)
                 .action(ArgAction::SetTrue),
         )
+        .arg(
+            Arg::new("CLEAN_EMPTY_DIRS")
+                .long("clean-empty-dirs")
+                .help("Remove empty directories afterwards")
+                .required(false)
+                .action(ArgAction::SetTrue),
+        )
         .arg(
             Arg::new("YES")
                 .short('y')
@@ -65,6 +87,8 @@ pub async fn main(
     }
     if matches.get_flag("TRASH") {
         purge_trashed_romfiles(connection, progress_bar, answer_yes).await?;
+    } else if matches.get_flag("RETENTION") {
+        prune_trashed_romfiles(connection, progress_bar, answer_yes).await?;
     }
     if matches.get_flag("ORPHAN") {
         purge_orphan_romfiles(connection, progress_bar, answer_yes).await?;
@@ -75,6 +99,12 @@ pub async fn main(
     for system in find_systems(connection).await {
         compute_system_completion(connection, progress_bar, &system).await;
     }
+    if get_bool(connection, "CONVERT_WRITE_MANIFEST").await {
+        write_checksum_manifests(connection, progress_bar).await?;
+    }
+    if matches.get_flag("CLEAN_EMPTY_DIRS") || get_bool(connection, "CLEAN_EMPTY_DIRS").await {
+        remove_empty_directories(connection, progress_bar).await?;
+    }
     Ok(())
 }
 
@@ -146,6 +176,82 @@ async fn purge_trashed_romfiles(
     Ok(())
 }
 
+pub async fn prune_trashed_romfiles(
+    connection: &mut SqliteConnection,
+    progress_bar: &ProgressBar,
+    answer_yes: bool,
+) -> SimpleResult<()> {
+    let retention_days = get_integer(connection, "TRASH_RETENTION_DAYS").await;
+    let max_items = get_integer(connection, "TRASH_MAX_ITEMS").await;
+    if retention_days.is_none() && max_items.is_none() {
+        return Ok(());
+    }
+
+    progress_bar.println("Processing trash retention");
+
+    // ages are compared against the database clock, the one that stamped the ROM files
+    let expired_romfile_ids: HashSet<i64> = match retention_days {
+        Some(days) => find_romfiles_in_trash_older_than(connection, days as i64)
+            .await
+            .into_iter()
+            .map(|romfile| romfile.id)
+            .collect(),
+        None => HashSet::new(),
+    };
+
+    // newest first, so that the oldest ROM files are the ones pruned past the limit
+    let mut romfiles = find_romfiles_in_trash(connection).await;
+    romfiles.sort_by(|a, b| {
+        b.updated_at
+            .cmp(&a.updated_at)
+            .then_with(|| b.id.cmp(&a.id))
+    });
+    let romfiles: Vec<Romfile> = romfiles
+        .into_iter()
+        .enumerate()
+        .filter(|(i, romfile)| {
+            expired_romfile_ids.contains(&romfile.id)
+                || max_items.is_some_and(|max_items| *i >= max_items)
+        })
+        .map(|(_, romfile)| romfile)
+        .collect();
+    let mut count = 0;
+
+    if !romfiles.is_empty() {
+        progress_bar.println("Summary:");
+        for romfile in &romfiles {
+            progress_bar.println(&romfile.path);
+        }
+
+        if answer_yes || confirm(true)? {
+            let mut transaction = begin_transaction(connection).await;
+
+            for romfile in &romfiles {
+                let common_romfile = romfile.as_common(&mut transaction).await?;
+                if common_romfile.path.is_file() {
+                    common_romfile.delete(progress_bar, false).await?;
+                }
+                // trashed files may also be tracked as unmatched
+                delete_unmatched_by_path(
+                    &mut transaction,
+                    common_romfile.path.as_os_str().to_str().unwrap(),
+                )
+                .await;
+                delete_romfile_by_id(&mut transaction, romfile.id).await;
+                count += 1;
+            }
+
+            commit_transaction(transaction).await;
+
+            if count > 0 {
+                progress_bar.println(format!("Pruned {} trashed ROM file(s)", count));
+            }
+        }
+    }
+
+    Ok(())
+}
+
 async fn purge_orphan_romfiles(
     connection: &mut SqliteConnection,
     progress_bar: &ProgressBar,
@@ -153,7 +259,16 @@ async fn purge_orphan_romfiles(
 ) -> SimpleResult<()> {
     progress_bar.println("Processing orphan ROM files");
 
-    let romfiles = find_orphan_romfiles(connection).await;
+    let orphan_romfiles = find_orphan_romfiles(connection).await;
+    let mut romfiles: Vec<&Romfile> = vec![];
+    for romfile in &orphan_romfiles {
+        if romfile.romfile_type == RomfileType::Cuesheet as i64
+            && is_cuesheet_in_use(connection, romfile, &orphan_romfiles).await?
+        {
+            continue;
+        }
+        romfiles.push(romfile);
+    }
     let mut count = 0;
 
     if !romfiles.is_empty() {
@@ -188,6 +303,37 @@ async fn purge_orphan_romfiles(
     Ok(())
 }
 
+// generated cuesheets aren't linked to any ROM, they're kept as long as one of their BIN files is
+async fn is_cuesheet_in_use(
+    connection: &mut SqliteConnection,
+    romfile: &Romfile,
+    orphan_romfiles: &[Romfile],
+) -> SimpleResult<bool> {
+    let cue_romfile = romfile.as_common(connection).await?;
+    if !cue_romfile.path.is_file() {
+        return Ok(false);
+    }
+    let cue = try_with!(
+        fs::read_to_string(&cue_romfile.path).await,
+        "Failed to read cue"
+    );
+    let directory = Path::new(&romfile.path).parent().unwrap();
+    for bin_name in get_cue_file_names(&cue) {
+        let bin_path = directory.join(bin_name);
+        if let Some(bin_romfile) =
+            find_romfile_by_path(connection, bin_path.as_os_str().to_str().unwrap()).await
+        {
+            if !orphan_romfiles
+                .iter()
+                .any(|orphan_romfile| orphan_romfile.id == bin_romfile.id)
+            {
+                return Ok(true);
+            }
+        }
+    }
+    Ok(false)
+}
+
 async fn purge_foreign_romfiles(
     connection: &mut SqliteConnection,
     progress_bar: &ProgressBar,
@@ -195,10 +341,13 @@ async fn purge_foreign_romfiles(
 ) -> SimpleResult<()> {
     progress_bar.println("Processing foreign ROM files");
     let rom_directory = get_rom_directory(connection).await;
-    let walker = WalkDir::new(rom_directory).into_iter();
+    let staging_directory = rom_directory.join(STAGING_DIRECTORY_NAME);
+    let walker = WalkDir::new(rom_directory)
+        .into_iter()
+        .filter_entry(|entry| entry.path() != staging_directory);
     let mut count = 0;
     for entry in walker.filter_map(|e| e.ok()) {
-        if entry.path().is_file() {
+        if entry.path().is_file() && entry.file_name() != CHECKSUMS_FILE_NAME {
             let relative_path = try_with!(
                 entry.path().strip_prefix(rom_directory),
                 "Failed to retrieve relative path"
@@ -234,4 +383,10 @@ mod test_missing;
 #[cfg(test)]
 mod test_orphans;
 #[cfg(test)]
+mod test_orphans_generated_cuesheet;
+#[cfg(test)]
 mod test_trashed;
+#[cfg(test)]
+mod test_trashed_clean_empty_dirs;
+#[cfg(test)]
+mod test_trashed_retention;
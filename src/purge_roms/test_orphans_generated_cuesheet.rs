@@ -0,0 +1,86 @@
+use super::super::config::{set_rom_directory, set_tmp_directory, MUTEX};
+use super::super::convert_roms;
+use super::super::database::*;
+use super::super::import_dats;
+use super::super::import_roms;
+use super::*;
+use std::path::{Path, PathBuf};
+use tempfile::{NamedTempFile, TempDir};
+
+#[tokio::test]
+async fn test() {
+    // given
+    let _guard = MUTEX.lock().await;
+
+    let test_directory = Path::new("tests");
+    let progress_bar = ProgressBar::hidden();
+
+    let db_file = NamedTempFile::new().unwrap();
+    let pool = establish_connection(db_file.path().to_str().unwrap()).await;
+    let mut connection = pool.acquire().await.unwrap();
+
+    let rom_directory = TempDir::new_in(&test_directory).unwrap();
+    set_rom_directory(PathBuf::from(rom_directory.path()));
+    let tmp_directory = TempDir::new_in(&test_directory).unwrap();
+    let tmp_directory = set_tmp_directory(PathBuf::from(tmp_directory.path()));
+
+    let matches = import_dats::subcommand()
+        .get_matches_from(&["import-dats", "tests/Test System (20250302) (Cue-less).dat"]);
+    import_dats::main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    let chd_romfile_path = tmp_directory.join("Test Game (USA, Europe) (Single Track).chd");
+    fs::copy(
+        test_directory.join("Test Game (USA, Europe) (Single Track).chd"),
+        &chd_romfile_path,
+    )
+    .await
+    .unwrap();
+
+    let matches = import_roms::subcommand().get_matches_from(&[
+        "import-roms",
+        chd_romfile_path.as_os_str().to_str().unwrap(),
+    ]);
+    import_roms::main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    let matches =
+        convert_roms::subcommand().get_matches_from(&["convert-roms", "-f", "ORIGINAL", "-a"]);
+    convert_roms::main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    let system = find_systems(&mut connection).await.remove(0);
+    let romfiles = find_romfiles(&mut connection).await;
+    let cuesheet_romfile = romfiles
+        .iter()
+        .find(|romfile| romfile.romfile_type == RomfileType::Cuesheet as i64)
+        .unwrap();
+    let cuesheet_path = rom_directory.path().join(&cuesheet_romfile.path);
+
+    // when
+    purge_orphan_romfiles(&mut connection, &progress_bar, true)
+        .await
+        .unwrap();
+
+    // then
+    assert_eq!(find_romfiles(&mut connection).await.len(), 2);
+    assert!(cuesheet_path.is_file());
+
+    // when
+    delete_game_by_name_and_system_id(
+        &mut connection,
+        "Test Game (USA, Europe) (CUE BIN)",
+        system.id,
+    )
+    .await;
+    purge_orphan_romfiles(&mut connection, &progress_bar, true)
+        .await
+        .unwrap();
+
+    // then
+    assert!(find_romfiles(&mut connection).await.is_empty());
+    assert!(!cuesheet_path.is_file());
+}
@@ -0,0 +1,200 @@
+use super::super::config::{set_rom_directory, set_tmp_directory, MUTEX};
+use super::super::database::*;
+use super::super::import_dats;
+use super::super::import_roms;
+use super::super::sort_roms;
+use super::*;
+use std::path::{Path, PathBuf};
+use tempfile::{NamedTempFile, TempDir};
+use tokio::fs;
+
+#[tokio::test]
+async fn test() {
+    // given
+    let _guard = MUTEX.lock().await;
+
+    let test_directory = Path::new("tests");
+    let progress_bar = ProgressBar::hidden();
+
+    let db_file = NamedTempFile::new().unwrap();
+    let pool = establish_connection(db_file.path().to_str().unwrap()).await;
+    let mut connection = pool.acquire().await.unwrap();
+
+    let rom_directory = TempDir::new_in(&test_directory).unwrap();
+    set_rom_directory(PathBuf::from(rom_directory.path()));
+    let tmp_directory = TempDir::new_in(&test_directory).unwrap();
+    let tmp_directory = set_tmp_directory(PathBuf::from(tmp_directory.path()));
+
+    let matches = import_dats::subcommand()
+        .get_matches_from(&["import-dats", "tests/Test System (20200721).dat"]);
+    import_dats::main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    let romfile_path = tmp_directory.join("Test Game (USA, Europe).rom");
+    fs::copy(
+        test_directory.join("Test Game (USA, Europe).rom"),
+        &romfile_path,
+    )
+    .await
+    .unwrap();
+
+    let matches = import_roms::subcommand()
+        .get_matches_from(&["import-roms", romfile_path.as_os_str().to_str().unwrap()]);
+    import_roms::main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    let matches = sort_roms::subcommand().get_matches_from(&["sort-roms", "-a", "-y", "-o", "JP"]);
+    sort_roms::main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    let setting = find_setting_by_key(&mut connection, "TRASH_RETENTION_DAYS")
+        .await
+        .unwrap();
+    update_setting(&mut connection, setting.id, Some(String::from("30"))).await;
+
+    let romfile = find_romfiles_in_trash(&mut connection).await.remove(0);
+
+    // when
+    prune_trashed_romfiles(&mut connection, &progress_bar, true)
+        .await
+        .unwrap();
+
+    // then
+    let romfiles = find_romfiles_in_trash(&mut connection).await;
+    assert_eq!(romfiles.len(), 1);
+    assert!(rom_directory.path().join(&romfile.path).is_file());
+
+    // when
+    update_romfile_updated_at(&mut connection, romfile.id, romfile.updated_at - 31 * 86400).await;
+    prune_trashed_romfiles(&mut connection, &progress_bar, true)
+        .await
+        .unwrap();
+
+    // then
+    let romfiles = find_romfiles_in_trash(&mut connection).await;
+    assert!(romfiles.is_empty());
+    assert!(!rom_directory.path().join(&romfile.path).is_file());
+}
+
+#[tokio::test]
+async fn test_max_items() {
+    // given
+    let _guard = MUTEX.lock().await;
+
+    let test_directory = Path::new("tests");
+    let progress_bar = ProgressBar::hidden();
+
+    let db_file = NamedTempFile::new().unwrap();
+    let pool = establish_connection(db_file.path().to_str().unwrap()).await;
+    let mut connection = pool.acquire().await.unwrap();
+
+    let rom_directory = TempDir::new_in(&test_directory).unwrap();
+    set_rom_directory(PathBuf::from(rom_directory.path()));
+    let tmp_directory = TempDir::new_in(&test_directory).unwrap();
+    let tmp_directory = set_tmp_directory(PathBuf::from(tmp_directory.path()));
+
+    let matches = import_dats::subcommand()
+        .get_matches_from(&["import-dats", "tests/Test System (20200721).dat"]);
+    import_dats::main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    let romfile_path = tmp_directory.join("Test Game (USA, Europe).rom");
+    fs::copy(
+        test_directory.join("Test Game (USA, Europe).rom"),
+        &romfile_path,
+    )
+    .await
+    .unwrap();
+
+    let matches = import_roms::subcommand()
+        .get_matches_from(&["import-roms", romfile_path.as_os_str().to_str().unwrap()]);
+    import_roms::main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    let matches = sort_roms::subcommand().get_matches_from(&["sort-roms", "-a", "-y", "-o", "JP"]);
+    sort_roms::main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    let setting = find_setting_by_key(&mut connection, "TRASH_MAX_ITEMS")
+        .await
+        .unwrap();
+    update_setting(&mut connection, setting.id, Some(String::from("0"))).await;
+
+    let romfile = find_romfiles_in_trash(&mut connection).await.remove(0);
+
+    // when
+    prune_trashed_romfiles(&mut connection, &progress_bar, true)
+        .await
+        .unwrap();
+
+    // then
+    let romfiles = find_romfiles_in_trash(&mut connection).await;
+    assert!(romfiles.is_empty());
+    assert!(!rom_directory.path().join(&romfile.path).is_file());
+}
+
+#[tokio::test]
+async fn test_unmatched() {
+    // given
+    let _guard = MUTEX.lock().await;
+
+    let test_directory = Path::new("tests");
+    let progress_bar = ProgressBar::hidden();
+
+    let db_file = NamedTempFile::new().unwrap();
+    let pool = establish_connection(db_file.path().to_str().unwrap()).await;
+    let mut connection = pool.acquire().await.unwrap();
+
+    let rom_directory = TempDir::new_in(&test_directory).unwrap();
+    set_rom_directory(PathBuf::from(rom_directory.path()));
+    let tmp_directory = TempDir::new_in(&test_directory).unwrap();
+    let tmp_directory = set_tmp_directory(PathBuf::from(tmp_directory.path()));
+
+    let matches = import_dats::subcommand()
+        .get_matches_from(&["import-dats", "tests/Test System (20200721).dat"]);
+    import_dats::main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    set_bool(&mut connection, "TRACK_UNMATCHED", true).await;
+
+    // the Asia ROM doesn't match its DAT entry
+    let romfile_path = tmp_directory.join("Test Game (Asia).rom");
+    fs::copy(test_directory.join("Test Game (Asia).rom"), &romfile_path)
+        .await
+        .unwrap();
+
+    let matches = import_roms::subcommand().get_matches_from(&[
+        "import-roms",
+        "-t",
+        romfile_path.as_os_str().to_str().unwrap(),
+    ]);
+    import_roms::main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    let setting = find_setting_by_key(&mut connection, "TRASH_RETENTION_DAYS")
+        .await
+        .unwrap();
+    update_setting(&mut connection, setting.id, Some(String::from("30"))).await;
+
+    let romfile = find_romfiles_in_trash(&mut connection).await.remove(0);
+    assert_eq!(find_unmatched(&mut connection).await.len(), 1);
+
+    // when
+    update_romfile_updated_at(&mut connection, romfile.id, romfile.updated_at - 31 * 86400).await;
+    prune_trashed_romfiles(&mut connection, &progress_bar, true)
+        .await
+        .unwrap();
+
+    // then
+    assert!(find_romfiles_in_trash(&mut connection).await.is_empty());
+    assert!(find_unmatched(&mut connection).await.is_empty());
+    assert!(!rom_directory.path().join(&romfile.path).is_file());
+}
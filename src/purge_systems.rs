@@ -4,26 +4,80 @@ use super::model::*;
 use super::prompt::*;
 use super::util::*;
 use super::SimpleResult;
-use clap::Command;
+use clap::{Arg, ArgAction, ArgMatches, Command};
 use indicatif::ProgressBar;
 use sqlx::sqlite::SqliteConnection;
 use std::path::Path;
 use std::time::Duration;
 
 pub fn subcommand() -> Command {
-    Command::new("purge-systems").about("Purge systems")
+    Command::new("purge-systems")
+        .about("Purge systems")
+        .arg(
+            Arg::new("SYSTEM")
+                .short('s')
+                .long("system")
+                .help("Select systems by name")
+                .required(false)
+                .action(ArgAction::Append),
+        )
+        .arg(
+            Arg::new("EXACT_SYSTEM")
+                .long("exact-system")
+                .help("Match system names exactly")
+                .required(false)
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("YES")
+                .short('y')
+                .long("yes")
+                .help("Automatically say yes to prompts")
+                .required(false)
+                .action(ArgAction::SetTrue),
+        )
 }
 
 pub async fn main(
     connection: &mut SqliteConnection,
+    matches: &ArgMatches,
     progress_bar: &ProgressBar,
 ) -> SimpleResult<()> {
-    let systems = prompt_for_systems(connection, None, false, false).await?;
-    progress_bar.enable_steady_tick(Duration::from_millis(100));
-    for system in systems {
-        purge_system(connection, progress_bar, &system).await?;
-        progress_bar.println("");
+    let systems = match matches.get_many::<String>("SYSTEM") {
+        Some(system_names) => {
+            let mut systems: Vec<System> = vec![];
+            for system_name in system_names {
+                systems.append(
+                    &mut find_systems_by_name_match(
+                        connection,
+                        system_name,
+                        matches.get_flag("EXACT_SYSTEM"),
+                    )
+                    .await,
+                );
+            }
+            systems.dedup_by_key(|system| system.id);
+            systems
+        }
+        None => prompt_for_systems(connection, None, false, false).await?,
+    };
+
+    if systems.is_empty() {
+        return Ok(());
+    }
+
+    progress_bar.println("Summary:");
+    for system in &systems {
+        progress_bar.println(&system.name);
+    }
+
+    if matches.get_flag("YES") || confirm(true)? {
+        for system in systems {
+            purge_system(connection, progress_bar, &system).await?;
+            progress_bar.println("");
+        }
     }
+
     Ok(())
 }
 
@@ -60,3 +114,13 @@ async fn purge_system(
 
 #[cfg(test)]
 mod test_purge_systems;
+#[cfg(test)]
+mod test_purge_systems_exact;
+#[cfg(test)]
+mod test_purge_systems_exact_system;
+#[cfg(test)]
+mod test_purge_systems_like;
+#[cfg(test)]
+mod test_purge_systems_prefix;
+#[cfg(test)]
+mod test_purge_systems_yes;
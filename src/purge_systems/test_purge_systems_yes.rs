@@ -0,0 +1,72 @@
+use super::super::config::*;
+use super::super::database::*;
+use super::super::import_dats;
+use super::super::import_roms;
+use super::*;
+use std::path::PathBuf;
+use tempfile::{NamedTempFile, TempDir};
+use tokio::fs;
+
+#[tokio::test]
+async fn test() {
+    // given
+    let _guard = MUTEX.lock().await;
+
+    let test_directory = Path::new("tests");
+    let progress_bar = ProgressBar::hidden();
+
+    let db_file = NamedTempFile::new().unwrap();
+    let pool = establish_connection(db_file.path().to_str().unwrap()).await;
+    let mut connection = pool.acquire().await.unwrap();
+
+    let rom_directory = TempDir::new_in(&test_directory).unwrap();
+    set_rom_directory(PathBuf::from(rom_directory.path()));
+    let tmp_directory = TempDir::new_in(&test_directory).unwrap();
+    let tmp_directory = set_tmp_directory(PathBuf::from(tmp_directory.path()));
+
+    let matches = import_dats::subcommand()
+        .get_matches_from(&["import-dats", "tests/Test System (20200721).dat"]);
+    import_dats::main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    let romfile_names = vec![
+        "Test Game (Asia).rom",
+        "Test Game (Japan).rom",
+        "Test Game (USA, Europe).rom",
+        "Test Game (USA, Europe) (Beta).rom",
+    ];
+    for romfile_name in &romfile_names {
+        let romfile_path = tmp_directory.join(romfile_name);
+        fs::copy(test_directory.join(romfile_name), &romfile_path)
+            .await
+            .unwrap();
+        let matches = import_roms::subcommand()
+            .get_matches_from(&["import-roms", romfile_path.as_os_str().to_str().unwrap()]);
+        import_roms::main(&mut connection, &matches, &progress_bar)
+            .await
+            .unwrap();
+    }
+
+    // when
+    let matches = subcommand().get_matches_from(&["purge-systems", "-s", "Test System", "-y"]);
+    main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    // then
+    let systems = find_systems(&mut connection).await;
+    assert_eq!(systems.len(), 0);
+
+    let games = find_games(&mut connection).await;
+    assert_eq!(games.len(), 0);
+
+    let roms = find_roms(&mut connection).await;
+    assert_eq!(roms.len(), 0);
+
+    let romfiles = find_romfiles(&mut connection).await;
+
+    for romfile in romfiles {
+        assert!(romfile.path.contains("Trash"))
+    }
+}
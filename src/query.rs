@@ -26,6 +26,16 @@ impl Game {
             .load_one(self.system_id)
             .await
     }
+
+    async fn releases(&self, ctx: &Context<'_>) -> Result<Vec<GameRelease>> {
+        let pool = ctx.data_unchecked::<SqlitePool>();
+        Ok(find_game_releases_by_game_ids(&mut pool.acquire().await.unwrap(), &[self.id]).await)
+    }
+
+    async fn history(&self, ctx: &Context<'_>) -> Result<Vec<Event>> {
+        let pool = ctx.data_unchecked::<SqlitePool>();
+        Ok(find_events_by_game_id(&mut pool.acquire().await.unwrap(), self.id).await)
+    }
 }
 
 #[ComplexObject]
@@ -269,6 +269,7 @@ async fn add_rom(
                         .unwrap(),
                     &ArchiveType::Zip,
                     compression_level,
+                    &None,
                     false,
                 )
                 .await?;
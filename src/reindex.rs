@@ -0,0 +1,95 @@
+use super::config::*;
+use super::database::*;
+use super::import_roms::{import_rom, ImportMode};
+use super::util::*;
+use super::SimpleResult;
+use clap::{Arg, ArgAction, ArgMatches, Command};
+use indicatif::ProgressBar;
+use sqlx::sqlite::SqliteConnection;
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+use walkdir::WalkDir;
+
+pub fn subcommand() -> Command {
+    Command::new("reindex")
+        .about("Rebuild the ROM files catalog from an existing collection")
+        .arg(
+            Arg::new("FROM_ROM_DIR")
+                .short('r')
+                .long("from-rom-dir")
+                .help("Match ROM files found in the ROM directory against the imported DATs")
+                .required(false)
+                .action(ArgAction::SetTrue),
+        )
+}
+
+pub async fn main(
+    connection: &mut SqliteConnection,
+    matches: &ArgMatches,
+    progress_bar: &ProgressBar,
+) -> SimpleResult<()> {
+    if !matches.get_flag("FROM_ROM_DIR") {
+        progress_bar.println("Please select a source to reindex from");
+        return Ok(());
+    }
+
+    if find_systems(connection).await.is_empty() {
+        progress_bar.println("Please import DAT files first");
+        return Ok(());
+    }
+
+    reindex_rom_directory(connection, progress_bar).await
+}
+
+async fn reindex_rom_directory(
+    connection: &mut SqliteConnection,
+    progress_bar: &ProgressBar,
+) -> SimpleResult<()> {
+    progress_bar.println("Processing ROM directory");
+
+    let rom_directory = get_rom_directory(connection).await;
+    let tmp_directory = get_tmp_directory(connection).await;
+
+    let paths: Vec<PathBuf> = WalkDir::new(rom_directory)
+        .into_iter()
+        .filter_entry(|entry| {
+            entry.path() != tmp_directory.as_path() && entry.file_name() != "Trash"
+        })
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.path().is_file())
+        .map(|entry| entry.into_path())
+        .collect();
+
+    let mut system_ids: HashSet<i64> = HashSet::new();
+    for path in paths {
+        let (new_system_ids, _) = import_rom(
+            connection,
+            progress_bar,
+            &None,
+            &None,
+            &path,
+            false,
+            false,
+            true,
+            ImportMode::Record,
+            &None,
+            &HashMap::new(),
+        )
+        .await?;
+        system_ids.extend(new_system_ids);
+    }
+
+    for system_id in system_ids {
+        let system = find_system_by_id(connection, system_id).await;
+        compute_system_completion(connection, progress_bar, &system).await;
+    }
+
+    progress_bar.println("Please run sort-roms to restore the sorted layout");
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test_from_rom_dir;
+#[cfg(test)]
+mod test_from_rom_dir_unsorted;
@@ -0,0 +1,81 @@
+use super::super::import_dats;
+use super::super::import_roms;
+use super::*;
+use std::path::Path;
+use tempfile::{NamedTempFile, TempDir};
+use tokio::fs;
+
+#[tokio::test]
+async fn test() {
+    // given
+    let _guard = MUTEX.lock().await;
+
+    let test_directory = Path::new("tests");
+    let progress_bar = ProgressBar::hidden();
+
+    let db_file = NamedTempFile::new().unwrap();
+    let pool = establish_connection(db_file.path().to_str().unwrap()).await;
+    let mut connection = pool.acquire().await.unwrap();
+
+    let rom_directory = TempDir::new_in(&test_directory).unwrap();
+    set_rom_directory(PathBuf::from(rom_directory.path()));
+    let tmp_directory = TempDir::new_in(&test_directory).unwrap();
+    let tmp_directory = set_tmp_directory(PathBuf::from(tmp_directory.path()));
+
+    let matches = import_dats::subcommand()
+        .get_matches_from(&["import-dats", "tests/Test System (20200721).dat"]);
+    import_dats::main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    let romfile_names = vec!["Test Game (Asia).rom", "Test Game (USA, Europe).rom"];
+    for romfile_name in &romfile_names {
+        let romfile_path = tmp_directory.join(romfile_name);
+        fs::copy(test_directory.join(romfile_name), &romfile_path)
+            .await
+            .unwrap();
+        let matches = import_roms::subcommand()
+            .get_matches_from(&["import-roms", romfile_path.as_os_str().to_str().unwrap()]);
+        import_roms::main(&mut connection, &matches, &progress_bar)
+            .await
+            .unwrap();
+    }
+
+    let system = find_systems(&mut connection).await.remove(0);
+    let old_romfiles = find_romfiles(&mut connection).await;
+    assert_eq!(old_romfiles.len(), 2);
+    for romfile in &old_romfiles {
+        delete_romfile_by_id(&mut connection, romfile.id).await;
+    }
+    assert!(
+        find_roms_with_romfile_by_system_id(&mut connection, system.id)
+            .await
+            .is_empty()
+    );
+
+    let matches = subcommand().get_matches_from(&["reindex", "--from-rom-dir"]);
+
+    // when
+    main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    // then
+    let roms = find_roms_with_romfile_by_system_id(&mut connection, system.id).await;
+    assert_eq!(roms.len(), 2);
+    let romfiles = find_romfiles(&mut connection).await;
+    assert_eq!(romfiles.len(), 2);
+    assert_eq!(
+        romfiles
+            .iter()
+            .map(|romfile| romfile.path.as_str())
+            .collect::<Vec<&str>>(),
+        old_romfiles
+            .iter()
+            .map(|romfile| romfile.path.as_str())
+            .collect::<Vec<&str>>()
+    );
+    for romfile in &romfiles {
+        assert!(rom_directory.path().join(&romfile.path).is_file());
+    }
+}
@@ -0,0 +1,72 @@
+use super::super::import_dats;
+use super::super::import_roms;
+use super::*;
+use std::path::Path;
+use tempfile::{NamedTempFile, TempDir};
+use tokio::fs;
+
+#[tokio::test]
+async fn test() {
+    // given
+    let _guard = MUTEX.lock().await;
+
+    let test_directory = Path::new("tests");
+    let progress_bar = ProgressBar::hidden();
+
+    let db_file = NamedTempFile::new().unwrap();
+    let pool = establish_connection(db_file.path().to_str().unwrap()).await;
+    let mut connection = pool.acquire().await.unwrap();
+
+    let rom_directory = TempDir::new_in(&test_directory).unwrap();
+    let rom_directory = set_rom_directory(PathBuf::from(rom_directory.path()));
+    let tmp_directory = TempDir::new_in(&test_directory).unwrap();
+    let tmp_directory = set_tmp_directory(PathBuf::from(tmp_directory.path()));
+
+    let matches = import_dats::subcommand()
+        .get_matches_from(&["import-dats", "tests/Test System (20200721).dat"]);
+    import_dats::main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    let romfile_path = tmp_directory.join("Test Game (USA, Europe).rom");
+    fs::copy(
+        test_directory.join("Test Game (USA, Europe).rom"),
+        &romfile_path,
+    )
+    .await
+    .unwrap();
+    let matches = import_roms::subcommand()
+        .get_matches_from(&["import-roms", romfile_path.as_os_str().to_str().unwrap()]);
+    import_roms::main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    let system = find_systems(&mut connection).await.remove(0);
+    let old_romfile = find_romfiles(&mut connection).await.remove(0);
+    delete_romfile_by_id(&mut connection, old_romfile.id).await;
+
+    // files moved around by hand aren't where sort-roms would put them
+    let unsorted_directory = rom_directory.join("unsorted");
+    fs::create_dir_all(&unsorted_directory).await.unwrap();
+    let unsorted_path = unsorted_directory.join("test game.rom");
+    fs::rename(rom_directory.join(&old_romfile.path), &unsorted_path)
+        .await
+        .unwrap();
+
+    let matches = subcommand().get_matches_from(&["reindex", "--from-rom-dir"]);
+
+    // when
+    main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    // then
+    let roms = find_roms_with_romfile_by_system_id(&mut connection, system.id).await;
+    assert_eq!(roms.len(), 1);
+    let romfiles = find_romfiles(&mut connection).await;
+    assert_eq!(romfiles.len(), 1);
+    assert_eq!(romfiles[0].path, "unsorted/test game.rom");
+    assert_eq!(roms[0].romfile_id, Some(romfiles[0].id));
+    assert!(unsorted_path.is_file());
+    assert!(!rom_directory.join(&old_romfile.path).exists());
+}
@@ -0,0 +1,107 @@
+use super::database::*;
+use super::model::*;
+use super::SimpleResult;
+use clap::{Arg, ArgAction, ArgMatches, Command};
+use indexmap::map::IndexMap;
+use indicatif::{HumanBytes, ProgressBar};
+use sqlx::sqlite::SqliteConnection;
+use std::collections::{HashMap, HashSet};
+
+pub fn subcommand() -> Command {
+    Command::new("report")
+        .about("Report on the ROMs stored in oxyromon")
+        .arg(
+            Arg::new("CROSS_DUPES")
+                .short('c')
+                .long("cross-dupes")
+                .help("Report identical ROMs referenced by games in different systems")
+                .required(false)
+                .action(ArgAction::SetTrue),
+        )
+}
+
+pub async fn main(
+    connection: &mut SqliteConnection,
+    matches: &ArgMatches,
+    progress_bar: &ProgressBar,
+) -> SimpleResult<()> {
+    if matches.get_flag("CROSS_DUPES") {
+        report_cross_dupes(connection, progress_bar).await;
+    }
+    Ok(())
+}
+
+async fn report_cross_dupes(
+    connection: &mut SqliteConnection,
+    progress_bar: &ProgressBar,
+) -> IndexMap<(i64, String), Vec<Rom>> {
+    progress_bar.println("Processing cross-system duplicates");
+
+    let mut roms_by_size_and_crc: IndexMap<(i64, String), Vec<Rom>> = IndexMap::new();
+    for rom in find_roms_shared_across_systems(connection).await {
+        roms_by_size_and_crc
+            .entry((rom.size, rom.crc.as_ref().unwrap().to_owned()))
+            .or_default()
+            .push(rom);
+    }
+
+    if roms_by_size_and_crc.is_empty() {
+        progress_bar.println("No cross-system duplicate");
+        return roms_by_size_and_crc;
+    }
+
+    let game_ids: Vec<i64> = roms_by_size_and_crc
+        .values()
+        .flatten()
+        .map(|rom| rom.game_id)
+        .collect();
+    let games_by_id: HashMap<i64, Game> = find_games_by_ids(connection, &game_ids)
+        .await
+        .into_iter()
+        .map(|game| (game.id, game))
+        .collect();
+    let systems_by_id: HashMap<i64, System> = find_systems(connection)
+        .await
+        .into_iter()
+        .map(|system| (system.id, system))
+        .collect();
+
+    // every stored copy beyond the first one is a waste of space
+    let mut wasted_size: u64 = 0;
+    for ((size, crc), roms) in &roms_by_size_and_crc {
+        progress_bar.println(format!("{} ({}):", crc, HumanBytes(*size as u64)));
+        for rom in roms {
+            let game = games_by_id.get(&rom.game_id).unwrap();
+            let system = systems_by_id.get(&game.system_id).unwrap();
+            progress_bar.println(format!(
+                "  \"{}\" in \"{}\" ({}){}",
+                rom.name,
+                game.name,
+                system.name,
+                if rom.romfile_id.is_some() {
+                    " [stored]"
+                } else {
+                    ""
+                }
+            ));
+        }
+        let stored_count = roms
+            .iter()
+            .filter_map(|rom| rom.romfile_id)
+            .collect::<HashSet<i64>>()
+            .len();
+        if stored_count > 1 {
+            wasted_size += (stored_count as u64 - 1) * *size as u64;
+        }
+    }
+    progress_bar.println(format!(
+        "{} ROM(s) shared across systems, {} stored more than once",
+        roms_by_size_and_crc.len(),
+        HumanBytes(wasted_size)
+    ));
+
+    roms_by_size_and_crc
+}
+
+#[cfg(test)]
+mod test_cross_dupes;
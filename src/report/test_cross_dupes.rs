@@ -0,0 +1,54 @@
+use super::super::config::{set_rom_directory, set_tmp_directory, MUTEX};
+use super::super::import_dats;
+use super::*;
+use std::path::{Path, PathBuf};
+use tempfile::{NamedTempFile, TempDir};
+
+#[tokio::test]
+async fn test() {
+    // given
+    let _guard = MUTEX.lock().await;
+
+    let test_directory = Path::new("tests");
+    let progress_bar = ProgressBar::hidden();
+
+    let db_file = NamedTempFile::new().unwrap();
+    let pool = establish_connection(db_file.path().to_str().unwrap()).await;
+    let mut connection = pool.acquire().await.unwrap();
+
+    let rom_directory = TempDir::new_in(&test_directory).unwrap();
+    set_rom_directory(PathBuf::from(rom_directory.path()));
+    let tmp_directory = TempDir::new_in(&test_directory).unwrap();
+    set_tmp_directory(PathBuf::from(tmp_directory.path()));
+
+    let matches = import_dats::subcommand().get_matches_from(&[
+        "import-dats",
+        "tests/Test System (20200721).dat",
+        "tests/Test System (20200721) (MAME).dat",
+    ]);
+    import_dats::main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    // when
+    let roms_by_size_and_crc = report_cross_dupes(&mut connection, &progress_bar).await;
+
+    // then
+    assert_eq!(roms_by_size_and_crc.len(), 1);
+
+    let ((size, crc), roms) = roms_by_size_and_crc.first().unwrap();
+    assert_eq!(*size, 256);
+    assert_eq!(crc, "cc721e14");
+    assert_eq!(roms.len(), 2);
+    assert!(roms
+        .iter()
+        .all(|rom| rom.name == "Test Game (USA, Europe).rom"));
+
+    let games = find_games_by_ids(
+        &mut connection,
+        &roms.iter().map(|rom| rom.game_id).collect::<Vec<i64>>(),
+    )
+    .await;
+    assert_eq!(games.len(), 2);
+    assert_ne!(games[0].system_id, games[1].system_id);
+}
@@ -14,6 +14,7 @@ use axum::{
 use clap::{Arg, ArgMatches, Command};
 use http_types::mime::{BYTE_STREAM, HTML};
 use http_types::Mime;
+use indicatif::ProgressBar;
 use rust_embed::RustEmbed;
 use simple_error::SimpleResult;
 use sqlx::sqlite::SqlitePool;
@@ -101,10 +102,10 @@ async fn shutdown_signal(pool: SqlitePool) {
 
     select! {
         _ = ctrl_c => {
-            optimize_database(pool).await;
+            optimize_database(pool, &ProgressBar::hidden()).await;
         },
         _ = terminate => {
-            optimize_database(pool).await;
+            optimize_database(pool, &ProgressBar::hidden()).await;
         },
     }
 }
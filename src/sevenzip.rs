@@ -4,11 +4,15 @@ use super::model::*;
 use super::progress::*;
 use super::util::*;
 use super::SimpleResult;
+use crc32fast::Hasher;
+use flate2::write::DeflateEncoder;
+use flate2::Compression;
 use indicatif::ProgressBar;
 use itertools::izip;
 use regex::Regex;
 use sqlx::SqliteConnection;
 use std::fs::{File, OpenOptions};
+use std::io::{self, BufWriter, Read, Seek, SeekFrom, Write};
 use std::iter::zip;
 use std::path::Path;
 use std::str::FromStr;
@@ -20,6 +24,11 @@ use zip::{ZipArchive, ZipWriter};
 pub const SEVENZIP_EXECUTABLES: &[&str] = &["7zz", "7z"];
 pub const SEVENZIP_COMPRESSION_LEVEL_RANGE: [usize; 2] = [1, 9];
 pub const ZIP_COMPRESSION_LEVEL_RANGE: [usize; 2] = [1, 9];
+pub const TORRENTZIP_COMMENT_PREFIX: &str = "TORRENTZIPPED-";
+const TORRENTZIP_TMP_EXTENSION: &str = "tmp";
+// TorrentZip pins every member to 1996-12-24 23:32 in MS-DOS format
+const TORRENTZIP_DOS_TIME: u16 = 0xbc00;
+const TORRENTZIP_DOS_DATE: u16 = 0x2198;
 
 lazy_static! {
     static ref VERSION_REGEX: Regex = Regex::new(r"\d+\.\d+").unwrap();
@@ -67,7 +76,7 @@ impl ArchiveFile for ArchiveRomfile {
             .arg(&self.romfile.path)
             .arg(&self.path)
             .arg(new_path)
-            .output()
+            .output_with_retries()
             .await
             .expect("Failed to rename file in archive");
 
@@ -99,7 +108,7 @@ impl ArchiveFile for ArchiveRomfile {
             .arg("--")
             .arg(&self.romfile.path)
             .arg(&self.path)
-            .output()
+            .output_with_retries()
             .await
             .expect("Failed to remove files from archive");
 
@@ -207,7 +216,10 @@ impl ToCommon for ArchiveRomfile {
 
         log::debug!("{:?}", command);
 
-        let output = command.output().await.expect("Failed to extract archive");
+        let output = command
+            .output_with_retries()
+            .await
+            .expect("Failed to extract archive");
 
         if !output.status.success() {
             bail!(String::from_utf8(output.stderr).unwrap().as_str())
@@ -230,6 +242,7 @@ pub trait ToArchive {
         archive_name: &str,
         archive_type: &ArchiveType,
         compression_level: &Option<usize>,
+        compression_method: &Option<String>,
         solid: bool,
     ) -> SimpleResult<ArchiveRomfile>;
 }
@@ -243,6 +256,7 @@ impl ToArchive for CommonRomfile {
         archive_name: &str,
         archive_type: &ArchiveType,
         compression_level: &Option<usize>,
+        compression_method: &Option<String>,
         solid: bool,
     ) -> SimpleResult<ArchiveRomfile> {
         progress_bar.set_message(format!("Creating {}", archive_type));
@@ -266,6 +280,12 @@ impl ToArchive for CommonRomfile {
         if let Some(compression_level) = compression_level {
             command.arg(format!("-mx={}", compression_level));
         }
+        if let Some(compression_method) = compression_method {
+            command.arg(match archive_type {
+                ArchiveType::Sevenzip => format!("-m0={}", compression_method),
+                ArchiveType::Zip => format!("-mm={}", compression_method),
+            });
+        }
         if solid {
             command.arg("-ms=on");
         }
@@ -275,7 +295,7 @@ impl ToArchive for CommonRomfile {
             .arg(path)
             .current_dir(working_directory.as_ref());
         let output = command
-            .output()
+            .output_with_retries()
             .await
             .expect("Failed to add files to archive");
 
@@ -305,6 +325,7 @@ impl ToArchive for ArchiveRomfile {
         archive_name: &str,
         archive_type: &ArchiveType,
         compression_level: &Option<usize>,
+        compression_method: &Option<String>,
         solid: bool,
     ) -> SimpleResult<ArchiveRomfile> {
         let original_romfile = self.to_common(progress_bar, source_directory).await?;
@@ -316,6 +337,7 @@ impl ToArchive for ArchiveRomfile {
                 archive_name,
                 archive_type,
                 compression_level,
+                compression_method,
                 solid,
             )
             .await?;
@@ -355,7 +377,10 @@ impl AsArchive for CommonRomfile {
 
         log::debug!("{:?}", command);
 
-        let output = command.output().await.expect("Failed to parse archive");
+        let output = command
+            .output_with_retries()
+            .await
+            .expect("Failed to parse archive");
 
         if !output.status.success() {
             bail!(String::from_utf8(output.stderr).unwrap().as_str());
@@ -421,6 +446,139 @@ impl AsArchive for CommonRomfile {
     }
 }
 
+pub async fn get_compression_method(
+    progress_bar: &ProgressBar,
+    romfile: &CommonRomfile,
+) -> SimpleResult<Option<String>> {
+    progress_bar.set_message("Parsing archive");
+    progress_bar.set_style(get_none_progress_style());
+    progress_bar.enable_steady_tick(Duration::from_millis(100));
+
+    let mut command = Command::new(get_executable_path(SEVENZIP_EXECUTABLES)?);
+    command.arg("l").arg("-slt").arg("--").arg(&romfile.path);
+
+    log::debug!("{:?}", command);
+
+    let output = command
+        .output_with_retries()
+        .await
+        .expect("Failed to parse archive");
+
+    if !output.status.success() {
+        bail!(String::from_utf8(output.stderr).unwrap().as_str());
+    }
+
+    // entries follow the separator, the archive block before it may have its own method
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let compression_method = stdout
+        .lines()
+        .skip_while(|&line| !line.starts_with("----------"))
+        .find(|&line| line.starts_with("Method ="))
+        .map(|line| line.to_string().split_off(9)) // keep only the rhs
+        .and_then(|method| {
+            // drop method properties (eg: LZMA2:24) and filters (eg: BCJ)
+            method
+                .split([':', ' '])
+                .next()
+                .filter(|method| !method.is_empty())
+                .map(|method| match method {
+                    "Store" => String::from("Copy"),
+                    method => method.to_string(),
+                })
+        });
+
+    progress_bar.set_message("");
+    progress_bar.disable_steady_tick();
+
+    Ok(compression_method)
+}
+
+pub async fn test_archive<P: AsRef<Path>>(
+    progress_bar: &ProgressBar,
+    path: &P,
+) -> SimpleResult<()> {
+    progress_bar.println(format!(
+        "Testing \"{}\"",
+        path.as_ref().file_name().unwrap().to_str().unwrap()
+    ));
+    progress_bar.set_message("Testing archive");
+    progress_bar.set_style(get_none_progress_style());
+    progress_bar.enable_steady_tick(Duration::from_millis(100));
+
+    let mut command = Command::new(get_executable_path(SEVENZIP_EXECUTABLES)?);
+    command.arg("t").arg("--").arg(path.as_ref());
+
+    log::debug!("{:?}", command);
+
+    let output = command
+        .output_with_retries()
+        .await
+        .expect("Failed to test archive");
+
+    progress_bar.set_message("");
+    progress_bar.disable_steady_tick();
+
+    if !output.status.success() {
+        progress_bar.println("Archive container is corrupt");
+        bail!(String::from_utf8(output.stderr).unwrap().as_str());
+    }
+
+    Ok(())
+}
+
+pub async fn extract_files<P: AsRef<Path>>(
+    progress_bar: &ProgressBar,
+    archive_romfiles: &[&ArchiveRomfile],
+    directory: &P,
+) -> SimpleResult<Vec<CommonRomfile>> {
+    let archive_romfile = match archive_romfiles.first() {
+        Some(archive_romfile) => archive_romfile,
+        None => return Ok(vec![]),
+    };
+
+    progress_bar.set_message("Extracting files");
+    progress_bar.set_style(get_none_progress_style());
+    progress_bar.enable_steady_tick(Duration::from_millis(100));
+
+    for archive_romfile in archive_romfiles {
+        progress_bar.println(format!("Extracting \"{}\"", &archive_romfile.path));
+    }
+
+    let mut command = Command::new(get_executable_path(SEVENZIP_EXECUTABLES)?);
+    command
+        .arg("x")
+        .arg("-aoa")
+        .arg("--")
+        .arg(&archive_romfile.romfile.path)
+        .args(
+            archive_romfiles
+                .iter()
+                .map(|archive_romfile| &archive_romfile.path),
+        )
+        .current_dir(directory.as_ref());
+
+    log::debug!("{:?}", command);
+
+    let output = command
+        .output_with_retries()
+        .await
+        .expect("Failed to extract archive");
+
+    if !output.status.success() {
+        bail!(String::from_utf8(output.stderr).unwrap().as_str())
+    }
+
+    progress_bar.set_message("");
+    progress_bar.disable_steady_tick();
+
+    archive_romfiles
+        .iter()
+        .map(|archive_romfile| {
+            CommonRomfile::from_path(&directory.as_ref().join(&archive_romfile.path))
+        })
+        .collect()
+}
+
 pub async fn copy_files_between_archives<P: AsRef<Path>, Q: AsRef<Path>>(
     progress_bar: &ProgressBar,
     source_path: &P,
@@ -474,10 +632,164 @@ pub async fn copy_files_between_archives<P: AsRef<Path>, Q: AsRef<Path>>(
     Ok(())
 }
 
+pub async fn torrentzip<P: AsRef<Path>>(progress_bar: &ProgressBar, path: &P) -> SimpleResult<()> {
+    progress_bar.set_message("TorrentZipping archive");
+    progress_bar.set_style(get_none_progress_style());
+    progress_bar.enable_steady_tick(Duration::from_millis(100));
+
+    progress_bar.println(format!(
+        "TorrentZipping \"{}\"",
+        path.as_ref().file_name().unwrap().to_str().unwrap()
+    ));
+
+    let source_file = try_with!(
+        File::open(path.as_ref()),
+        "Failed to read \"{}\"",
+        path.as_ref().as_os_str().to_str().unwrap()
+    );
+    let mut source_archive = try_with!(
+        ZipArchive::new(source_file),
+        "Failed to open \"{}\"",
+        path.as_ref().as_os_str().to_str().unwrap()
+    );
+
+    // members are sorted case-insensitively, directories are implied by their paths
+    let mut names: Vec<String> = source_archive
+        .file_names()
+        .filter(|name| !name.ends_with('/'))
+        .map(String::from)
+        .collect();
+    names.sort_by(|a, b| a.to_lowercase().cmp(&b.to_lowercase()).then(a.cmp(b)));
+
+    let tmp_path = path.as_ref().with_extension(TORRENTZIP_TMP_EXTENSION);
+    try_with!(
+        write_torrentzip(&mut source_archive, &names, &tmp_path),
+        "Failed to TorrentZip \"{}\"",
+        path.as_ref().as_os_str().to_str().unwrap()
+    );
+    try_with!(
+        std::fs::rename(&tmp_path, path.as_ref()),
+        "Failed to replace \"{}\"",
+        path.as_ref().as_os_str().to_str().unwrap()
+    );
+
+    progress_bar.set_message("");
+    progress_bar.disable_steady_tick();
+
+    Ok(())
+}
+
+fn write_torrentzip(
+    source_archive: &mut ZipArchive<File>,
+    names: &[String],
+    path: &Path,
+) -> io::Result<()> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    let mut central_directory: Vec<u8> = vec![];
+
+    for name in names {
+        let mut source = source_archive.by_name(name)?;
+        let offset = writer.stream_position()?;
+        // the UTF-8 flag is only set when needed, like TorrentZip does
+        let flags: u16 = if name.is_ascii() { 0x0002 } else { 0x0802 };
+
+        // the checksum and sizes are filled in once the data has been compressed
+        writer.write_all(&0x04034b50u32.to_le_bytes())?;
+        write_torrentzip_fields(&mut writer, flags, 0, 0, 0, name)?;
+        writer.write_all(name.as_bytes())?;
+        let mut hasher = Hasher::new();
+        let mut encoder = DeflateEncoder::new(&mut writer, Compression::best());
+        let mut buffer = vec![0u8; 1024 * 1024];
+        loop {
+            let count = source.read(&mut buffer)?;
+            if count == 0 {
+                break;
+            }
+            hasher.update(&buffer[..count]);
+            encoder.write_all(&buffer[..count])?;
+        }
+        encoder.finish()?;
+
+        let end = writer.stream_position()?;
+        let data_offset = offset + 30 + name.len() as u64;
+        let compressed_size = u32::try_from(end - data_offset)
+            .map_err(|_| io::Error::other("ZIP64 is not supported"))?;
+        let size =
+            u32::try_from(source.size()).map_err(|_| io::Error::other("ZIP64 is not supported"))?;
+        let crc = hasher.finalize();
+        writer.seek(SeekFrom::Start(offset + 14))?;
+        writer.write_all(&crc.to_le_bytes())?;
+        writer.write_all(&compressed_size.to_le_bytes())?;
+        writer.write_all(&size.to_le_bytes())?;
+        writer.seek(SeekFrom::Start(end))?;
+
+        central_directory.write_all(&0x02014b50u32.to_le_bytes())?;
+        central_directory.write_all(&0u16.to_le_bytes())?; // version made by, MS-DOS
+        write_torrentzip_fields(
+            &mut central_directory,
+            flags,
+            crc,
+            compressed_size,
+            size,
+            name,
+        )?;
+        central_directory.write_all(&0u16.to_le_bytes())?; // comment length
+        central_directory.write_all(&0u16.to_le_bytes())?; // disk number
+        central_directory.write_all(&0u16.to_le_bytes())?; // internal attributes
+        central_directory.write_all(&0u32.to_le_bytes())?; // external attributes
+        central_directory.write_all(
+            &u32::try_from(offset)
+                .map_err(|_| io::Error::other("ZIP64 is not supported"))?
+                .to_le_bytes(),
+        )?;
+        central_directory.write_all(name.as_bytes())?;
+    }
+
+    let central_directory_offset = u32::try_from(writer.stream_position()?)
+        .map_err(|_| io::Error::other("ZIP64 is not supported"))?;
+    writer.write_all(&central_directory)?;
+
+    // the comment holds the checksum of the central directory
+    let mut hasher = Hasher::new();
+    hasher.update(&central_directory);
+    let comment = format!("{}{:08X}", TORRENTZIP_COMMENT_PREFIX, hasher.finalize());
+    let count = names.len() as u16;
+    writer.write_all(&0x06054b50u32.to_le_bytes())?;
+    writer.write_all(&0u16.to_le_bytes())?; // disk number
+    writer.write_all(&0u16.to_le_bytes())?; // central directory disk number
+    writer.write_all(&count.to_le_bytes())?;
+    writer.write_all(&count.to_le_bytes())?;
+    writer.write_all(&(central_directory.len() as u32).to_le_bytes())?;
+    writer.write_all(&central_directory_offset.to_le_bytes())?;
+    writer.write_all(&(comment.len() as u16).to_le_bytes())?;
+    writer.write_all(comment.as_bytes())?;
+    writer.flush()
+}
+
+fn write_torrentzip_fields<W: Write>(
+    writer: &mut W,
+    flags: u16,
+    crc: u32,
+    compressed_size: u32,
+    size: u32,
+    name: &str,
+) -> io::Result<()> {
+    writer.write_all(&20u16.to_le_bytes())?; // version needed to extract
+    writer.write_all(&flags.to_le_bytes())?;
+    writer.write_all(&8u16.to_le_bytes())?; // deflate
+    writer.write_all(&TORRENTZIP_DOS_TIME.to_le_bytes())?;
+    writer.write_all(&TORRENTZIP_DOS_DATE.to_le_bytes())?;
+    writer.write_all(&crc.to_le_bytes())?;
+    writer.write_all(&compressed_size.to_le_bytes())?;
+    writer.write_all(&size.to_le_bytes())?;
+    writer.write_all(&(name.len() as u16).to_le_bytes())?;
+    writer.write_all(&0u16.to_le_bytes()) // extra field length
+}
+
 pub async fn get_version() -> SimpleResult<String> {
     let output = try_with!(
         Command::new(get_executable_path(SEVENZIP_EXECUTABLES)?)
-            .output()
+            .output_with_retries()
             .await,
         "Failed to spawn executable"
     );
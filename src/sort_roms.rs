@@ -1,7 +1,7 @@
 use super::common::*;
 use super::config::*;
 use super::database::*;
-use super::generate_playlists::DISC_REGEX;
+use super::generate_playlists::{get_disc_archive_name, update_playlists, DISC_REGEX};
 use super::mimetype::*;
 use super::model::*;
 use super::prompt::*;
@@ -80,6 +80,20 @@ pub fn subcommand() -> Command {
                 .required(false)
                 .action(ArgAction::SetTrue),
         )
+        .arg(
+            Arg::new("CLEAN_EMPTY_DIRS")
+                .long("clean-empty-dirs")
+                .help("Remove empty directories afterwards")
+                .required(false)
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("UPDATE_PLAYLISTS")
+                .long("update-playlists")
+                .help("Regenerate affected playlists afterwards")
+                .required(false)
+                .action(ArgAction::SetTrue),
+        )
         .arg(
             Arg::new("YES")
                 .short('y')
@@ -132,10 +146,15 @@ pub async fn main(
     .unwrap();
     let one_regions_strict = get_bool(connection, "REGIONS_ONE_STRICT").await;
 
+    recover_romfile_moves(connection, progress_bar).await?;
+
     let answer_yes = matches.get_flag("YES");
     let print_wanted = matches.get_flag("WANTED");
+    let regenerate_playlists =
+        matches.get_flag("UPDATE_PLAYLISTS") || get_bool(connection, "UPDATE_PLAYLISTS").await;
 
-    for system in systems {
+    for mut system in systems {
+        system.arcade = is_arcade(connection, &system).await;
         sort_system(
             connection,
             progress_bar,
@@ -157,9 +176,25 @@ pub async fn main(
         )
         .await?;
 
+        if regenerate_playlists {
+            let game_ids: Vec<i64> = find_games_by_system_id(connection, system.id)
+                .await
+                .iter()
+                .map(|game| game.id)
+                .collect();
+            update_playlists(connection, progress_bar, &system, &game_ids).await?;
+        }
+
         progress_bar.println("");
     }
 
+    if get_bool(connection, "CONVERT_WRITE_MANIFEST").await {
+        write_checksum_manifests(connection, progress_bar).await?;
+    }
+    if matches.get_flag("CLEAN_EMPTY_DIRS") || get_bool(connection, "CLEAN_EMPTY_DIRS").await {
+        remove_empty_directories(connection, progress_bar).await?;
+    }
+
     Ok(())
 }
 
@@ -412,9 +447,13 @@ async fn sort_system(
         }
     }
 
+    let rom_directory = get_rom_directory(connection).await;
     let system_directory = get_system_directory(connection, system).await?;
     let one_region_directory = get_one_region_directory(connection, system).await?;
     let trash_directory = get_trash_directory(connection, Some(system)).await?;
+    let content_addressed =
+        StorageMode::from_str(&get_string(connection, "STORAGE_MODE").await.unwrap()).unwrap()
+            == StorageMode::ContentAddressed;
 
     let mut transaction = begin_transaction(connection).await;
 
@@ -438,6 +477,7 @@ async fn sort_system(
             &system_directory,
             &romfiles_by_id,
             all_regions_subfolders,
+            content_addressed,
         )
         .await?,
     );
@@ -460,6 +500,7 @@ async fn sort_system(
             &one_region_directory,
             &romfiles_by_id,
             one_regions_subfolders,
+            content_addressed,
         )
         .await?,
     );
@@ -491,6 +532,7 @@ async fn sort_system(
             &system_directory,
             &romfiles_by_id,
             one_regions_subfolders,
+            content_addressed,
         )
         .await?,
     );
@@ -502,6 +544,7 @@ async fn sort_system(
             &system_directory,
             &romfiles_by_id,
             all_regions_subfolders,
+            content_addressed,
         )
         .await?,
     );
@@ -524,6 +567,7 @@ async fn sort_system(
             &trash_directory,
             &romfiles_by_id,
             &SubfolderScheme::None,
+            false,
         )
         .await?,
     );
@@ -550,26 +594,50 @@ async fn sort_system(
 
         // prompt user for confirmation
         if answer_yes || confirm(true)? {
+            // journal moves so that an interrupted sort can be recovered
+            for romfile_move in &romfile_moves {
+                let new_romfile = CommonRomfile::from_path(&romfile_move.1)?;
+                let new_path = new_romfile.get_relative_path(&mut transaction).await?;
+                create_romfile_move(
+                    &mut transaction,
+                    romfile_move.0.id,
+                    &romfile_move.0.path,
+                    new_path.as_os_str().to_str().unwrap(),
+                )
+                .await;
+            }
+            commit_transaction(transaction).await;
             for romfile_move in romfile_moves {
                 romfile_move
                     .0
-                    .as_common(&mut transaction)
+                    .as_common(connection)
                     .await?
                     .rename(progress_bar, &romfile_move.1, true)
                     .await?
-                    .update(&mut transaction, progress_bar, romfile_move.0.id)
+                    .update(connection, progress_bar, romfile_move.0.id)
                     .await?;
+                let new_romfile = CommonRomfile::from_path(&romfile_move.1)?;
+                let new_path = new_romfile.get_relative_path(connection).await?;
+                create_events_by_romfile_id(
+                    connection,
+                    romfile_move.0.id,
+                    EventType::Sort,
+                    Some(&romfile_move.0.path),
+                    new_path.as_os_str().to_str().unwrap(),
+                )
+                .await;
+                delete_romfile_move_by_romfile_id(connection, romfile_move.0.id).await;
                 // delete empty directories
                 let mut directory = romfile_move
                     .0
-                    .as_common(&mut transaction)
+                    .as_common(connection)
                     .await?
                     .path
                     .parent()
                     .unwrap()
                     .to_path_buf();
                 while directory.read_dir().unwrap().next().is_none() {
-                    if directory == system_directory {
+                    if directory == system_directory || directory == *rom_directory {
                         break;
                     } else {
                         remove_directory(progress_bar, &directory, true).await?;
@@ -577,7 +645,6 @@ async fn sort_system(
                     }
                 }
             }
-            commit_transaction(transaction).await;
         } else {
             rollback_transaction(transaction).await;
         }
@@ -594,6 +661,33 @@ async fn sort_system(
     Ok(())
 }
 
+async fn recover_romfile_moves(
+    connection: &mut SqliteConnection,
+    progress_bar: &ProgressBar,
+) -> SimpleResult<()> {
+    let romfile_moves = find_romfile_moves(connection).await;
+    if romfile_moves.is_empty() {
+        return Ok(());
+    }
+
+    progress_bar.println("Recovering from an interrupted sort");
+    let rom_directory = get_rom_directory(connection).await;
+    for romfile_move in romfile_moves {
+        let old_path = rom_directory.join(&romfile_move.old_path);
+        let new_path = rom_directory.join(&romfile_move.new_path);
+        if new_path.is_file() && !old_path.is_file() {
+            // the file was moved but the database still points to its old path
+            CommonRomfile::from_path(&new_path)?
+                .update(connection, progress_bar, romfile_move.romfile_id)
+                .await?;
+        }
+        // otherwise the file never left, sorting again will retry the move
+        delete_romfile_move_by_romfile_id(connection, romfile_move.romfile_id).await;
+    }
+
+    Ok(())
+}
+
 async fn sort_games<'a, P: AsRef<Path>>(
     connection: &mut SqliteConnection,
     system: &System,
@@ -601,8 +695,15 @@ async fn sort_games<'a, P: AsRef<Path>>(
     destination_directory: &P,
     romfiles_by_id: &'a HashMap<i64, Romfile>,
     subfolders: &SubfolderScheme,
+    content_addressed: bool,
 ) -> SimpleResult<Vec<(&'a Romfile, PathBuf)>> {
     let mut romfile_moves: Vec<(&Romfile, PathBuf)> = vec![];
+    let rom_directory = get_rom_directory(connection).await;
+    let per_game_archives =
+        MultidiscArchive::from_str(&get_string(connection, "MULTIDISC_ARCHIVE").await.unwrap())
+            .unwrap()
+            == MultidiscArchive::PerGame;
+    let alt_name_regions = get_list(connection, "ALT_NAME_REGIONS").await;
 
     let roms = find_roms_with_romfile_by_game_ids(
         connection,
@@ -614,6 +715,23 @@ async fn sort_games<'a, P: AsRef<Path>>(
     )
     .await;
 
+    let mut alt_names_by_rom_id: HashMap<i64, Vec<RomAltName>> = HashMap::new();
+    if !alt_name_regions.is_empty() {
+        find_rom_alt_names_by_rom_ids(
+            connection,
+            roms.iter()
+                .map(|rom| rom.id)
+                .collect::<Vec<i64>>()
+                .as_slice(),
+        )
+        .await
+        .into_iter()
+        .for_each(|alt_name| {
+            let group = alt_names_by_rom_id.entry(alt_name.rom_id).or_default();
+            group.push(alt_name);
+        });
+    }
+
     let mut roms_by_game_id: HashMap<i64, Vec<Rom>> = HashMap::new();
     roms.into_iter().for_each(|rom| {
         let group = roms_by_game_id.entry(rom.game_id).or_default();
@@ -631,15 +749,38 @@ async fn sort_games<'a, P: AsRef<Path>>(
             let extension = Path::new(&romfile.path)
                 .extension()
                 .map(|extension| extension.to_str().unwrap());
-            let new_romfile_path = compute_new_romfile_path(
-                system,
-                &game,
-                rom,
-                extension,
-                destination_directory,
-                subfolders,
-            )
-            .await?;
+            let new_romfile_path = if content_addressed
+                && rom.sha1.is_some()
+                && is_content_addressable(system, &game, roms.len(), extension)
+            {
+                compute_content_addressed_path(
+                    rom_directory,
+                    rom.sha1.as_ref().unwrap(),
+                    extension.unwrap(),
+                )
+            } else {
+                let alt_name = alt_names_by_rom_id
+                    .get(&rom.id)
+                    .and_then(|alt_names| get_alt_name(alt_names, &alt_name_regions));
+                let mut new_romfile_path = compute_new_romfile_path(
+                    system,
+                    &game,
+                    rom,
+                    alt_name,
+                    extension,
+                    destination_directory,
+                    subfolders,
+                )
+                .await?;
+                if extension.is_some_and(|extension| ARCHIVE_EXTENSIONS.contains(&extension)) {
+                    new_romfile_path.set_file_name(format!(
+                        "{}.{}",
+                        get_disc_archive_name(&game.name, per_game_archives),
+                        extension.unwrap()
+                    ));
+                }
+                new_romfile_path
+            };
             if romfile.as_common(connection).await?.path != new_romfile_path {
                 let patches = find_patches_by_rom_id(connection, rom.id).await;
                 for patch in patches {
@@ -856,10 +997,21 @@ fn sort_games_by_weight(
     weight_b.partial_cmp(&weight_a).unwrap()
 }
 
+fn get_alt_name<'a>(alt_names: &'a [RomAltName], alt_name_regions: &[String]) -> Option<&'a str> {
+    // regions are listed by order of preference
+    alt_name_regions.iter().find_map(|region| {
+        alt_names
+            .iter()
+            .find(|alt_name| alt_name.region.as_ref() == Some(region))
+            .map(|alt_name| alt_name.name.as_str())
+    })
+}
+
 async fn compute_new_romfile_path<P: AsRef<Path>>(
     system: &System,
     game: &Game,
     rom: &Rom,
+    alt_name: Option<&str>,
     extension: Option<&str>,
     destination_directory: &P,
     subfolders: &SubfolderScheme,
@@ -874,16 +1026,18 @@ async fn compute_new_romfile_path<P: AsRef<Path>>(
     non_original_extensions.append(&mut ARCHIVE_EXTENSIONS.to_vec());
 
     let mut new_romfile_path: PathBuf = destination_directory.as_ref().to_path_buf();
+    let rom_name = alt_name.unwrap_or(&rom.name);
 
     // subfolders
     if subfolders == &SubfolderScheme::Alpha {
         if extension.is_some() && non_original_extensions.contains(extension.as_ref().unwrap())
             || system.arcade
             || game.jbfolder
+            || rom_name.contains('/')
         {
             new_romfile_path = new_romfile_path.join(compute_alpha_subfolder(&game.name));
         } else {
-            new_romfile_path = new_romfile_path.join(compute_alpha_subfolder(&rom.name));
+            new_romfile_path = new_romfile_path.join(compute_alpha_subfolder(rom_name));
         }
     }
 
@@ -906,12 +1060,47 @@ async fn compute_new_romfile_path<P: AsRef<Path>>(
                 new_romfile_path.join(format!("{}.{}", &game.name, extension.as_ref().unwrap()));
         }
     } else {
-        new_romfile_path = new_romfile_path.join(&rom.name);
+        new_romfile_path = new_romfile_path.join(rom_name);
     }
 
     Ok(new_romfile_path)
 }
 
+fn is_content_addressable(
+    system: &System,
+    game: &Game,
+    roms_count: usize,
+    extension: Option<&str>,
+) -> bool {
+    // only single original files can be stored under their hash, multi-file games reference each other by name
+    !system.arcade
+        && !game.jbfolder
+        && roms_count == 1
+        && extension.is_some_and(|extension| {
+            ![
+                CHD_EXTENSION,
+                CSO_EXTENSION,
+                NSZ_EXTENSION,
+                RVZ_EXTENSION,
+                ZSO_EXTENSION,
+            ]
+            .contains(&extension)
+                && !ARCHIVE_EXTENSIONS.contains(&extension)
+        })
+}
+
+fn compute_content_addressed_path<P: AsRef<Path>>(
+    rom_directory: &P,
+    sha1: &str,
+    extension: &str,
+) -> PathBuf {
+    rom_directory
+        .as_ref()
+        .join(&sha1[0..2])
+        .join(&sha1[2..4])
+        .join(format!("{}.{}", sha1, extension))
+}
+
 async fn compute_new_playlist_path<P: AsRef<Path>>(
     game: &Game,
     destination_directory: &P,
@@ -1009,6 +1198,8 @@ mod test_path_subfolder_alpha_letter;
 #[cfg(test)]
 mod test_path_subfolder_alpha_other;
 #[cfg(test)]
+mod test_recover_interrupted_moves;
+#[cfg(test)]
 mod test_sort;
 #[cfg(test)]
 mod test_sort_1g1r;
@@ -1031,10 +1222,22 @@ mod test_sort_1g1r_without_parent_clone;
 #[cfg(test)]
 mod test_sort_1g1r_without_roms;
 #[cfg(test)]
+mod test_sort_alt_names;
+#[cfg(test)]
+mod test_sort_alt_names_primary;
+#[cfg(test)]
+mod test_sort_content_addressed;
+#[cfg(test)]
+mod test_sort_content_addressed_multiple_files;
+#[cfg(test)]
 mod test_sort_discard_asia;
 #[cfg(test)]
 mod test_sort_discard_asia_and_beta;
 #[cfg(test)]
 mod test_sort_discard_beta;
 #[cfg(test)]
+mod test_sort_non_arcade_override;
+#[cfg(test)]
+mod test_sort_renamed_in_place;
+#[cfg(test)]
 mod test_trim_ignored;
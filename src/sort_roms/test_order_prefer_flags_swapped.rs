@@ -13,6 +13,7 @@ async fn test() {
         bios: false,
         jbfolder: false,
         regions: String::from("US"),
+        languages: String::from(""),
         sorting: Sorting::AllRegions as i64,
         completion: 2,
         system_id: 1,
@@ -30,6 +31,7 @@ async fn test() {
         bios: false,
         jbfolder: false,
         regions: String::from("US"),
+        languages: String::from(""),
         sorting: Sorting::AllRegions as i64,
         completion: 2,
         system_id: 1,
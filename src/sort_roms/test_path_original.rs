@@ -25,6 +25,7 @@ async fn test() {
         bios: false,
         jbfolder: false,
         regions: String::from(""),
+        languages: String::from(""),
         sorting: Sorting::AllRegions as i64,
         completion: 0,
         system_id: 1,
@@ -41,7 +42,9 @@ async fn test() {
         crc: Some(String::from("")),
         md5: Some(String::from("")),
         sha1: Some(String::from("")),
+        sha256: None,
         rom_status: None,
+        header_title: None,
         game_id: 1,
         romfile_id: Some(1),
         parent_id: None,
@@ -64,6 +67,7 @@ async fn test() {
         &system,
         &game,
         &rom,
+        None,
         Some(extension),
         &test_directory,
         &SubfolderScheme::None,
@@ -14,6 +14,7 @@ async fn test() {
         bios: false,
         jbfolder: false,
         regions: String::from(""),
+        languages: String::from(""),
         sorting: Sorting::AllRegions as i64,
         completion: 0,
         system_id: 1,
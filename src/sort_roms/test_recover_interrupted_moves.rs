@@ -0,0 +1,107 @@
+use super::super::database::*;
+use super::super::import_dats;
+use super::super::import_roms;
+use super::super::util::*;
+use super::*;
+use tempfile::{NamedTempFile, TempDir};
+use tokio::fs;
+
+#[tokio::test]
+async fn test() {
+    // given
+    let _guard = MUTEX.lock().await;
+
+    let test_directory = Path::new("tests");
+    let progress_bar = ProgressBar::hidden();
+
+    let db_file = NamedTempFile::new().unwrap();
+    let pool = establish_connection(db_file.path().to_str().unwrap()).await;
+    let mut connection = pool.acquire().await.unwrap();
+
+    let rom_directory = TempDir::new_in(&test_directory).unwrap();
+    set_rom_directory(PathBuf::from(rom_directory.path()));
+    let tmp_directory = TempDir::new_in(&test_directory).unwrap();
+    let tmp_directory = set_tmp_directory(PathBuf::from(tmp_directory.path()));
+
+    let matches = import_dats::subcommand()
+        .get_matches_from(&["import-dats", "tests/Test System (20200721).dat"]);
+    import_dats::main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    let romfile_names = vec!["Test Game (Asia).rom", "Test Game (Japan).rom"];
+    for romfile_name in &romfile_names {
+        let romfile_path = tmp_directory.join(romfile_name);
+        fs::copy(test_directory.join(romfile_name), &romfile_path)
+            .await
+            .unwrap();
+        let matches = import_roms::subcommand()
+            .get_matches_from(&["import-roms", romfile_path.as_os_str().to_str().unwrap()]);
+        import_roms::main(&mut connection, &matches, &progress_bar)
+            .await
+            .unwrap();
+    }
+
+    let system = find_systems(&mut connection).await.remove(0);
+    let system_directory = get_system_directory(&mut connection, &system)
+        .await
+        .unwrap();
+    let one_region_directory = get_one_region_directory(&mut connection, &system)
+        .await
+        .unwrap();
+
+    // simulate a sort interrupted after the first move
+    let romfiles = find_romfiles_by_system_id(&mut connection, system.id).await;
+    for (i, romfile) in romfiles.iter().enumerate() {
+        let new_path = one_region_directory.join(romfile_names.get(i).unwrap());
+        create_romfile_move(
+            &mut connection,
+            romfile.id,
+            &romfile.path,
+            new_path
+                .strip_prefix(&rom_directory)
+                .unwrap()
+                .as_os_str()
+                .to_str()
+                .unwrap(),
+        )
+        .await;
+        if i == 0 {
+            rename_file(
+                &progress_bar,
+                &rom_directory.path().join(&romfile.path),
+                &new_path,
+                true,
+            )
+            .await
+            .unwrap();
+        }
+    }
+
+    let matches = subcommand().get_matches_from(&["sort-roms", "-a", "-y"]);
+
+    // when
+    main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    // then
+    assert!(find_romfile_moves(&mut connection).await.is_empty());
+
+    let romfiles = find_romfiles_by_system_id(&mut connection, system.id).await;
+    assert_eq!(2, romfiles.len());
+
+    for (i, romfile) in romfiles.iter().enumerate() {
+        assert_eq!(
+            &system_directory
+                .join(&romfile_names.get(i).unwrap())
+                .strip_prefix(&rom_directory)
+                .unwrap()
+                .as_os_str()
+                .to_str()
+                .unwrap(),
+            &romfile.path
+        );
+        assert!(rom_directory.path().join(&romfile.path).is_file());
+    }
+}
@@ -0,0 +1,95 @@
+use super::super::database::*;
+use super::super::import_dats;
+use super::super::import_roms;
+use super::super::util::*;
+use super::*;
+use tempfile::{NamedTempFile, TempDir};
+use tokio::fs;
+
+#[tokio::test]
+async fn test() {
+    // given
+    let _guard = MUTEX.lock().await;
+
+    let test_directory = Path::new("tests");
+    let progress_bar = ProgressBar::hidden();
+
+    let db_file = NamedTempFile::new().unwrap();
+    let pool = establish_connection(db_file.path().to_str().unwrap()).await;
+    let mut connection = pool.acquire().await.unwrap();
+
+    let rom_directory = TempDir::new_in(&test_directory).unwrap();
+    set_rom_directory(PathBuf::from(rom_directory.path()));
+    let tmp_directory = TempDir::new_in(&test_directory).unwrap();
+    let tmp_directory = set_tmp_directory(PathBuf::from(tmp_directory.path()));
+
+    let matches = import_dats::subcommand()
+        .get_matches_from(&["import-dats", "tests/Test System (20200721).dat"]);
+    import_dats::main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    let romfile_name = "Test Game (USA, Europe).rom";
+    let romfile_path = tmp_directory.join(romfile_name);
+    fs::copy(test_directory.join(romfile_name), &romfile_path)
+        .await
+        .unwrap();
+    let matches = import_roms::subcommand()
+        .get_matches_from(&["import-roms", romfile_path.as_os_str().to_str().unwrap()]);
+    import_roms::main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    set_string(&mut connection, "STORAGE_MODE", "content-addressed").await;
+
+    let system = find_systems(&mut connection).await.remove(0);
+
+    // when
+    sort_system(
+        &mut connection,
+        &progress_bar,
+        true,
+        false,
+        &system,
+        &[],
+        &[],
+        &[],
+        &[],
+        &[],
+        true,
+        &PreferredRegion::None,
+        &PreferredVersion::None,
+        &[],
+        &SubfolderScheme::None,
+        &SubfolderScheme::None,
+        false,
+    )
+    .await
+    .unwrap();
+
+    // then
+    let roms = find_roms_with_romfile_by_system_id(&mut connection, system.id).await;
+    assert_eq!(1, roms.len());
+    let rom = roms.first().unwrap();
+    let sha1 = rom.sha1.as_ref().unwrap();
+
+    let romfile = find_romfile_by_id(&mut connection, rom.romfile_id.unwrap()).await;
+    assert_eq!(
+        &Path::new(&sha1[0..2])
+            .join(&sha1[2..4])
+            .join(format!("{}.rom", sha1))
+            .as_os_str()
+            .to_str()
+            .unwrap(),
+        &romfile.path
+    );
+
+    let (hash, _) = romfile
+        .as_common(&mut connection)
+        .await
+        .unwrap()
+        .get_hash_and_size(&mut connection, &progress_bar, 1, 1, &HashAlgorithm::Sha1)
+        .await
+        .unwrap();
+    assert_eq!(sha1, &hash);
+}
@@ -14,6 +14,7 @@ async fn test() {
             bios: false,
             jbfolder: false,
             regions: String::from(""),
+            languages: String::from(""),
             sorting: Sorting::AllRegions as i64,
             completion: 2,
             system_id: 1,
@@ -31,6 +32,7 @@ async fn test() {
             bios: false,
             jbfolder: false,
             regions: String::from(""),
+            languages: String::from(""),
             sorting: Sorting::AllRegions as i64,
             completion: 2,
             system_id: 1,
@@ -48,6 +50,7 @@ async fn test() {
             bios: false,
             jbfolder: false,
             regions: String::from(""),
+            languages: String::from(""),
             sorting: Sorting::AllRegions as i64,
             completion: 2,
             system_id: 1,
@@ -65,6 +68,7 @@ async fn test() {
             bios: false,
             jbfolder: false,
             regions: String::from(""),
+            languages: String::from(""),
             sorting: Sorting::AllRegions as i64,
             completion: 2,
             system_id: 1,
@@ -82,6 +86,7 @@ async fn test() {
             bios: false,
             jbfolder: false,
             regions: String::from(""),
+            languages: String::from(""),
             sorting: Sorting::AllRegions as i64,
             completion: 2,
             system_id: 1,
@@ -99,6 +104,7 @@ async fn test() {
             bios: false,
             jbfolder: false,
             regions: String::from(""),
+            languages: String::from(""),
             sorting: Sorting::AllRegions as i64,
             completion: 2,
             system_id: 1,
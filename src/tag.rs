@@ -0,0 +1,169 @@
+use super::database::*;
+use super::model::*;
+use super::prompt::*;
+use super::util::*;
+use super::SimpleResult;
+use clap::{Arg, ArgAction, ArgMatches, Command};
+use indicatif::ProgressBar;
+use sqlx::sqlite::SqliteConnection;
+use std::collections::HashSet;
+use std::io::BufRead;
+use std::path::PathBuf;
+
+pub fn subcommand() -> Command {
+    Command::new("tag")
+        .about("Tag games in bulk")
+        .arg(
+            Arg::new("ADD")
+                .short('a')
+                .long("add")
+                .help("Set the tag to add")
+                .required(true)
+                .num_args(1),
+        )
+        .arg(
+            Arg::new("MATCH")
+                .short('m')
+                .long("match")
+                .help("Select games by name patterns, separated by commas")
+                .required_unless_present("FROM_FILE")
+                .value_delimiter(',')
+                .action(ArgAction::Append),
+        )
+        .arg(
+            Arg::new("FROM_FILE")
+                .short('f')
+                .long("from-file")
+                .help("Select games listed in a file, one name per line")
+                .required(false)
+                .num_args(1)
+                .value_parser(value_parser!(PathBuf)),
+        )
+        .arg(
+            Arg::new("SYSTEM")
+                .short('s')
+                .long("system")
+                .help("Select systems by name")
+                .required(false)
+                .action(ArgAction::Append),
+        )
+        .arg(
+            Arg::new("EXACT_SYSTEM")
+                .long("exact-system")
+                .help("Match system names exactly")
+                .required(false)
+                .action(ArgAction::SetTrue),
+        )
+}
+
+pub async fn main(
+    connection: &mut SqliteConnection,
+    matches: &ArgMatches,
+    progress_bar: &ProgressBar,
+) -> SimpleResult<()> {
+    let systems = match matches.get_many::<String>("SYSTEM") {
+        Some(system_names) => {
+            let mut systems: Vec<System> = vec![];
+            for system_name in system_names {
+                systems.append(
+                    &mut find_systems_by_name_match(
+                        connection,
+                        system_name,
+                        matches.get_flag("EXACT_SYSTEM"),
+                    )
+                    .await,
+                );
+            }
+            systems.dedup_by_key(|system| system.id);
+            systems
+        }
+        None => find_systems(connection).await,
+    };
+
+    let tag_name = matches.get_one::<String>("ADD").unwrap();
+
+    // globs use the same wildcards as LIKE patterns
+    let patterns: Vec<String> = matches
+        .get_many::<String>("MATCH")
+        .map(|patterns| {
+            patterns
+                .map(|pattern| pattern.trim().replace('*', "%").replace('?', "_"))
+                .filter(|pattern| !pattern.is_empty())
+                .collect()
+        })
+        .unwrap_or_default();
+
+    // names from a file are matched literally
+    let mut names: HashSet<String> = HashSet::new();
+    if let Some(path) = matches.get_one::<PathBuf>("FROM_FILE") {
+        let reader = get_reader_sync(path)?;
+        for line in reader.lines() {
+            let line = try_with!(
+                line,
+                "Failed to read \"{}\"",
+                path.as_os_str().to_str().unwrap()
+            );
+            let name = line.trim();
+            if !name.is_empty() {
+                names.insert(name.to_string());
+            }
+        }
+    }
+
+    let mut game_ids: Vec<i64> = vec![];
+    let mut found_names: HashSet<String> = HashSet::new();
+    for system in &systems {
+        for pattern in &patterns {
+            game_ids.extend(
+                find_games_by_name_and_system_id(connection, pattern, system.id)
+                    .await
+                    .into_iter()
+                    .map(|game| game.id),
+            );
+        }
+        if !names.is_empty() {
+            for game in find_games_by_system_id(connection, system.id).await {
+                if names.contains(&game.name) {
+                    found_names.insert(game.name.clone());
+                    game_ids.push(game.id);
+                }
+            }
+        }
+    }
+    game_ids.sort_unstable();
+    game_ids.dedup();
+
+    let mut missing_names: Vec<&String> = names.difference(&found_names).collect();
+    missing_names.sort();
+    for name in missing_names {
+        progress_bar.println(format!("No matching game for \"{}\"", name));
+    }
+
+    if game_ids.is_empty() {
+        progress_bar.println("No matching game");
+        set_outcome(Outcome::NothingMatched);
+        return Ok(());
+    }
+
+    let tag_id = match find_tag_by_name(connection, tag_name).await {
+        Some(tag) => tag.id,
+        None => create_tag(connection, tag_name).await,
+    };
+    let mut count = 0;
+    for game_id in &game_ids {
+        count += create_game_tag(connection, *game_id, tag_id).await;
+    }
+    progress_bar.println(format!(
+        "Tagged {} game(s) with \"{}\", {} already tagged",
+        count,
+        tag_name,
+        game_ids.len() as u64 - count
+    ));
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test_tag_from_file;
+#[cfg(test)]
+mod test_tag_match;
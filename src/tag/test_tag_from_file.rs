@@ -0,0 +1,52 @@
+use super::super::config::*;
+use super::super::import_dats;
+use super::*;
+use std::path::Path;
+use tempfile::{NamedTempFile, TempDir};
+use tokio::fs;
+
+#[tokio::test]
+async fn test() {
+    // given
+    let _guard = MUTEX.lock().await;
+
+    let test_directory = Path::new("tests");
+    let progress_bar = ProgressBar::hidden();
+
+    let db_file = NamedTempFile::new().unwrap();
+    let pool = establish_connection(db_file.path().to_str().unwrap()).await;
+    let mut connection = pool.acquire().await.unwrap();
+
+    let matches = import_dats::subcommand()
+        .get_matches_from(&["import-dats", "tests/Test System (20200721).dat"]);
+    import_dats::main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    let tmp_directory = TempDir::new_in(&test_directory).unwrap();
+    let list_path = tmp_directory.path().join("list.txt");
+    fs::write(
+        &list_path,
+        "Test Game (USA, Europe)\n\nTest Game (USA, Europe) (Beta)\nUnknown Game (USA)\n",
+    )
+    .await
+    .unwrap();
+
+    // when
+    let matches = subcommand().get_matches_from(&[
+        "tag",
+        "-a",
+        "western",
+        "-f",
+        list_path.as_os_str().to_str().unwrap(),
+    ]);
+    main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    // then
+    let games = find_games_by_tag_name(&mut connection, "western").await;
+    assert_eq!(games.len(), 2);
+    assert_eq!(games.first().unwrap().name, "Test Game (USA, Europe)");
+    assert_eq!(games.get(1).unwrap().name, "Test Game (USA, Europe) (Beta)");
+}
@@ -0,0 +1,34 @@
+use super::super::config::*;
+use super::super::import_dats;
+use super::*;
+use tempfile::NamedTempFile;
+
+#[tokio::test]
+async fn test() {
+    // given
+    let _guard = MUTEX.lock().await;
+
+    let progress_bar = ProgressBar::hidden();
+
+    let db_file = NamedTempFile::new().unwrap();
+    let pool = establish_connection(db_file.path().to_str().unwrap()).await;
+    let mut connection = pool.acquire().await.unwrap();
+
+    let matches = import_dats::subcommand()
+        .get_matches_from(&["import-dats", "tests/Test System (20200721).dat"]);
+    import_dats::main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    // when
+    let matches = subcommand().get_matches_from(&["tag", "-a", "asian", "-m", "*Japan*,*(Asia)"]);
+    main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    // then
+    let games = find_games_by_tag_name(&mut connection, "asian").await;
+    assert_eq!(games.len(), 2);
+    assert_eq!(games.first().unwrap().name, "Test Game (Asia)");
+    assert_eq!(games.get(1).unwrap().name, "Test Game (Japan)");
+}
@@ -0,0 +1,70 @@
+use super::database::*;
+use super::model::*;
+use super::SimpleResult;
+use clap::{Arg, ArgAction, ArgMatches, Command};
+use indicatif::{HumanBytes, ProgressBar};
+use sqlx::sqlite::SqliteConnection;
+use std::path::Path;
+
+pub fn subcommand() -> Command {
+    Command::new("unmatched")
+        .about("List imported files that didn't match any DAT")
+        .arg(
+            Arg::new("PRUNE")
+                .short('p')
+                .long("prune")
+                .help("Forget files that no longer exist")
+                .required(false)
+                .action(ArgAction::SetTrue),
+        )
+}
+
+pub async fn main(
+    connection: &mut SqliteConnection,
+    matches: &ArgMatches,
+    progress_bar: &ProgressBar,
+) -> SimpleResult<()> {
+    let unmatched = list_unmatched(connection, matches.get_flag("PRUNE")).await;
+
+    if unmatched.is_empty() {
+        progress_bar.println("No unmatched file");
+        return Ok(());
+    }
+
+    for unmatched in &unmatched {
+        progress_bar.println(format!(
+            "\"{}\" ({}){}{}",
+            unmatched.path,
+            HumanBytes(unmatched.size as u64),
+            match &unmatched.goodtools_status {
+                Some(goodtools_status) => format!(" [{}]", goodtools_status),
+                None => String::new(),
+            },
+            if Path::new(&unmatched.path).is_file() {
+                ""
+            } else {
+                " [missing]"
+            }
+        ));
+    }
+    progress_bar.println(format!("{} unmatched file(s)", unmatched.len()));
+
+    Ok(())
+}
+
+async fn list_unmatched(connection: &mut SqliteConnection, prune: bool) -> Vec<Unmatched> {
+    let mut unmatched = find_unmatched(connection).await;
+    if prune {
+        for missing in unmatched
+            .iter()
+            .filter(|unmatched| !Path::new(&unmatched.path).is_file())
+        {
+            delete_unmatched_by_path(connection, &missing.path).await;
+        }
+        unmatched.retain(|unmatched| Path::new(&unmatched.path).is_file());
+    }
+    unmatched
+}
+
+#[cfg(test)]
+mod test_unmatched;
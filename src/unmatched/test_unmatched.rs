@@ -0,0 +1,80 @@
+use super::super::config::*;
+use super::super::import_dats;
+use super::super::import_roms;
+use super::super::util::*;
+use super::*;
+use std::path::{Path, PathBuf};
+use tempfile::{NamedTempFile, TempDir};
+use tokio::fs;
+
+#[tokio::test]
+async fn test() {
+    // given
+    let _guard = MUTEX.lock().await;
+
+    let test_directory = Path::new("tests");
+    let progress_bar = ProgressBar::hidden();
+
+    let db_file = NamedTempFile::new().unwrap();
+    let pool = establish_connection(db_file.path().to_str().unwrap()).await;
+    let mut connection = pool.acquire().await.unwrap();
+
+    let rom_directory = TempDir::new_in(&test_directory).unwrap();
+    set_rom_directory(PathBuf::from(rom_directory.path()));
+    let tmp_directory = TempDir::new_in(&test_directory).unwrap();
+    let tmp_directory = set_tmp_directory(PathBuf::from(tmp_directory.path()));
+
+    let matches = import_dats::subcommand()
+        .get_matches_from(&["import-dats", "tests/Test System (20200721).dat"]);
+    import_dats::main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    // the Asia ROM doesn't match its DAT entry
+    let mut romfile_paths: Vec<PathBuf> = vec![];
+    for romfile_name in ["Test Game (Asia).rom", "Test Game (USA, Europe).rom"] {
+        let romfile_path = tmp_directory.join(romfile_name);
+        fs::copy(test_directory.join(romfile_name), &romfile_path)
+            .await
+            .unwrap();
+        romfile_paths.push(romfile_path);
+    }
+    let matches = import_roms::subcommand().get_matches_from(&[
+        "import-roms",
+        romfile_paths[0].as_os_str().to_str().unwrap(),
+        romfile_paths[1].as_os_str().to_str().unwrap(),
+    ]);
+    import_roms::main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    // when
+    let unmatched = list_unmatched(&mut connection, false).await;
+
+    // then
+    assert_eq!(unmatched.len(), 1);
+    assert_eq!(
+        unmatched[0].path,
+        get_canonicalized_path(&romfile_paths[0])
+            .await
+            .unwrap()
+            .as_os_str()
+            .to_str()
+            .unwrap()
+    );
+    assert_eq!(unmatched[0].size, 256);
+
+    // when
+    fs::remove_file(&romfile_paths[0]).await.unwrap();
+    let unmatched = list_unmatched(&mut connection, false).await;
+
+    // then
+    assert_eq!(unmatched.len(), 1);
+
+    // when
+    let unmatched = list_unmatched(&mut connection, true).await;
+
+    // then
+    assert!(unmatched.is_empty());
+    assert!(find_unmatched(&mut connection).await.is_empty());
+}
@@ -1,28 +1,147 @@
+use super::common::*;
 use super::config::*;
 use super::database::*;
 use super::mimetype::*;
 use super::model::*;
 use super::progress::*;
 use super::SimpleResult;
+use cfg_if::cfg_if;
 use indicatif::ProgressBar;
+use num_derive::FromPrimitive;
 use num_traits::FromPrimitive;
 use rayon::prelude::*;
 use regex::Regex;
+use sha1::{Digest, Sha1};
 use simple_error::SimpleError;
 use sqlx::sqlite::SqliteConnection;
 use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::future::Future;
+use std::io;
 use std::path::{Path, PathBuf};
-use std::time::Duration;
+use std::process::{ExitCode, Output};
+use std::sync::atomic::{AtomicU8, AtomicUsize, Ordering as AtomicOrdering};
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, UNIX_EPOCH};
+use strum::Display;
 use tempfile::TempDir;
 use tokio::fs;
 use tokio::fs::File;
+use tokio::io::{AsyncReadExt, AsyncSeekExt, AsyncWriteExt, SeekFrom};
+use tokio::process::Command;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+use walkdir::WalkDir;
 use which::which;
 
 lazy_static! {
     static ref SYSTEM_NAME_REGEX: Regex =
         Regex::new(r"^(Non-Redump - |Unofficial - )?([^()]+)( \(.*\))?$").unwrap();
+    static ref GOODTOOLS_FLAG_REGEX: Regex =
+        Regex::new(r"\[(!|[abfhoptx]\d*|T[+-])[^\]]*\]").unwrap();
 }
 
+const GB_LOGO: &[u8] = &[0xce, 0xed, 0x66, 0x66];
+const GBA_LOGO: &[u8] = &[0x24, 0xff, 0xae, 0x51];
+const MEGA_DRIVE_MAGIC: &[u8] = b"SEGA";
+const N64_MAGIC_Z64: &[u8] = &[0x80, 0x37, 0x12, 0x40];
+const N64_MAGIC_V64: &[u8] = &[0x37, 0x80, 0x40, 0x12];
+const N64_MAGIC_N64: &[u8] = &[0x40, 0x12, 0x37, 0x80];
+const HEADER_TITLE_SIZE: usize = 0x180;
+const DISC_SECTOR_SIZES: &[u64] = &[2048, 2352];
+const WII_MAGIC: [u8; 4] = [0x5d, 0x1c, 0x9e, 0xa3];
+const WII_MAGIC_OFFSET: u64 = 0x18;
+const WII_PARTITION_INFO_OFFSET: u64 = 0x40000;
+const WII_MAX_PARTITION_COUNT: usize = 64;
+const WII_PARTITION_HEADER_SIZE: usize = 0x2c0;
+const WII_TICKET_MAGIC: [u8; 4] = [0x00, 0x01, 0x00, 0x01];
+const WII_TMD_CONTENT_HASH_OFFSET: u64 = 0x1f4;
+const WII_H3_TABLE_SIZE: usize = 0x18000;
+const ENTROPY_BUFFER_SIZE: usize = 1024 * 1024;
+// cartridge dumps are neither this random unless encrypted, nor this uniform unless blank,
+// system names are matched as prefixes (eg: Game Boy also covers Game Boy Advance and Color)
+const ENTROPY_RANGES: &[(&str, [f64; 2])] = &[
+    ("Atari - 2600", [0.5, 7.95]),
+    ("NEC - PC Engine - TurboGrafx-16", [0.5, 7.95]),
+    ("Nintendo - Game Boy", [0.5, 7.95]),
+    ("Nintendo - Nintendo 64", [0.5, 7.95]),
+    ("Nintendo - Nintendo Entertainment System", [0.5, 7.95]),
+    (
+        "Nintendo - Super Nintendo Entertainment System",
+        [0.5, 7.95],
+    ),
+    ("Sega - Game Gear", [0.5, 7.95]),
+    ("Sega - Master System - Mark III", [0.5, 7.95]),
+    ("Sega - Mega Drive - Genesis", [0.5, 7.95]),
+];
+const FAT_FILESYSTEMS: &[&str] = &["exfat", "msdos", "vfat"];
+const FAT_INVALID_CHARACTERS: &[char] = &['"', '*', '/', ':', '<', '>', '?', '\\', '|'];
+const TOOL_RETRY_BACKOFF: Duration = Duration::from_millis(500);
+// exit codes tools use for transient failures, e.g. 7-Zip's warning for files locked by another process
+const TOOL_TRANSIENT_EXIT_CODES: &[(&str, &[i32])] = &[("7z", &[1, 8]), ("7zz", &[1, 8])];
+// errors reported by tools that exit with a generic code, e.g. chdman or dolphin-tool
+const TOOL_TRANSIENT_ERRORS: &[&str] = &[
+    "access is denied",
+    "being used by another process",
+    "permission denied",
+    "resource temporarily unavailable",
+    "sharing violation",
+];
+// settings overriding a tool binary per system
+const SYSTEM_TOOL_PATHS: &[(&str, &str)] =
+    &[("chdman", "CHDMAN_PATHS"), ("maxcso", "MAXCSO_PATHS")];
+
+pub const MAX_TOOL_PROCESSES_RANGE: [usize; 2] = [0, 256];
+pub const PARALLELISM_RANGE: [usize; 2] = [0, 256];
+pub const TOOL_NICENESS_RANGE: [usize; 2] = [0, 19];
+pub const TOOL_RETRIES_RANGE: [usize; 2] = [0, 10];
+// used by --nice when TOOL_NICENESS is 0
+pub const DEFAULT_NICENESS: usize = 10;
+
+// process exit codes, 2 is left to clap for invalid arguments
+#[derive(Clone, Copy, Debug, FromPrimitive, Eq, PartialEq)]
+#[repr(u8)]
+pub enum Outcome {
+    Success = 0,
+    Error = 1,
+    VerificationFailures = 3,
+    ToolMissing = 4,
+    NothingMatched = 5,
+}
+
+impl From<Outcome> for ExitCode {
+    fn from(outcome: Outcome) -> Self {
+        ExitCode::from(outcome as u8)
+    }
+}
+
+// dump status derived from GoodTools flags, worst first
+#[derive(Clone, Copy, Debug, Display, Eq, Ord, PartialEq, PartialOrd)]
+#[strum(serialize_all = "lowercase")]
+pub enum GoodToolsStatus {
+    Bad,
+    Overdump,
+    Hack,
+    Good,
+}
+
+// the first outcome reported wins
+static OUTCOME: AtomicU8 = AtomicU8::new(Outcome::Success as u8);
+static TOOL_NICENESS: AtomicUsize = AtomicUsize::new(0);
+static TOOL_RETRIES: AtomicUsize = AtomicUsize::new(0);
+// unlimited when unset
+static TOOL_PROCESSES: RwLock<Option<Arc<Semaphore>>> = RwLock::new(None);
+
+// rom and tmp directories, files deleted in the former are staged when set
+static SAFE_MODE: RwLock<Option<(PathBuf, PathBuf)>> = RwLock::new(None);
+
+tokio::task_local! {
+    // tool binaries overridden for the system being processed, scoped per future so concurrent systems don't clash
+    static TOOL_PATHS: HashMap<&'static str, PathBuf>;
+}
+
+pub const CHECKSUMS_FILE_NAME: &str = "checksums.sfv";
+pub const STAGING_DIRECTORY_NAME: &str = ".staged";
+
 pub async fn get_canonicalized_path<P: AsRef<Path>>(path: &P) -> SimpleResult<PathBuf> {
     let canonicalized_path = try_with!(
         path.as_ref().canonicalize(),
@@ -57,6 +176,39 @@ pub fn get_reader_sync<P: AsRef<Path>>(
     Ok(std::io::BufReader::new(f))
 }
 
+// directories inside the archive are kept, e.g. PS3 JB folders
+pub fn extract_tar_zst<P: AsRef<Path>, Q: AsRef<Path>>(
+    progress_bar: &ProgressBar,
+    path: &P,
+    destination_directory: &Q,
+) -> SimpleResult<()> {
+    progress_bar.println(format!(
+        "Extracting \"{}\"",
+        path.as_ref().file_name().unwrap().to_str().unwrap()
+    ));
+    progress_bar.reset();
+    progress_bar.set_style(get_bytes_progress_style());
+    progress_bar.set_length(
+        try_with!(
+            path.as_ref().metadata(),
+            "Failed to read \"{}\" metadata",
+            path.as_ref().as_os_str().to_str().unwrap()
+        )
+        .len(),
+    );
+    let decoder = try_with!(
+        zstd::Decoder::new(progress_bar.wrap_read(open_file_sync(path)?)),
+        "Failed to read zstd stream"
+    );
+    try_with!(
+        tar::Archive::new(decoder).unpack(destination_directory),
+        "Failed to extract \"{}\"",
+        path.as_ref().as_os_str().to_str().unwrap()
+    );
+    progress_bar.set_message("");
+    Ok(())
+}
+
 pub async fn create_file<P: AsRef<Path>>(
     progress_bar: &ProgressBar,
     path: &P,
@@ -103,6 +255,35 @@ pub async fn copy_file<P: AsRef<Path>, Q: AsRef<Path>>(
     Ok(())
 }
 
+pub async fn link_file<P: AsRef<Path>, Q: AsRef<Path>>(
+    progress_bar: &ProgressBar,
+    old_path: &P,
+    new_path: &Q,
+    quiet: bool,
+) -> SimpleResult<()> {
+    if old_path.as_ref() != new_path.as_ref() {
+        let new_directory = new_path.as_ref().parent().unwrap();
+        if !new_directory.is_dir() {
+            create_directory(progress_bar, &new_directory, quiet).await?;
+        }
+        // hardlinks can't replace existing files
+        if new_path.as_ref().is_file() {
+            remove_file(progress_bar, new_path, true).await?;
+        }
+        if !quiet {
+            progress_bar.println(format!(
+                "Linking to \"{}\"",
+                new_path.as_ref().as_os_str().to_str().unwrap()
+            ));
+        }
+        // hardlinks can't cross filesystems
+        if fs::hard_link(old_path, new_path).await.is_err() {
+            copy_file(progress_bar, old_path, new_path, quiet).await?;
+        }
+    }
+    Ok(())
+}
+
 pub async fn rename_file<P: AsRef<Path>, Q: AsRef<Path>>(
     progress_bar: &ProgressBar,
     old_path: &P,
@@ -130,11 +311,59 @@ pub async fn rename_file<P: AsRef<Path>, Q: AsRef<Path>>(
     Ok(())
 }
 
+pub fn set_safe_mode(directories: Option<(PathBuf, PathBuf)>) {
+    *SAFE_MODE.write().unwrap() = directories;
+}
+
+// only files and directories under the ROM directory are staged, anything else is deleted right away
+fn get_staged_path<P: AsRef<Path>>(path: &P) -> Option<PathBuf> {
+    let safe_mode = SAFE_MODE.read().unwrap();
+    let (rom_directory, tmp_directory) = safe_mode.as_ref()?;
+    let staging_directory = rom_directory.join(STAGING_DIRECTORY_NAME);
+    if path.as_ref().starts_with(tmp_directory) || path.as_ref().starts_with(&staging_directory) {
+        return None;
+    }
+    let relative_path = path.as_ref().strip_prefix(rom_directory).ok()?;
+    if relative_path.as_os_str().is_empty() {
+        return None;
+    }
+    let mut staged_path = staging_directory.join(relative_path);
+    // never overwrite a file staged earlier under the same name
+    let mut index = 1;
+    while staged_path.exists() {
+        staged_path = staging_directory.join(format!(
+            "{}.{}",
+            relative_path.as_os_str().to_str().unwrap(),
+            index
+        ));
+        index += 1;
+    }
+    Some(staged_path)
+}
+
 pub async fn remove_file<P: AsRef<Path>>(
     progress_bar: &ProgressBar,
     path: &P,
     quiet: bool,
 ) -> SimpleResult<()> {
+    if let Some(staged_path) = get_staged_path(path) {
+        if !quiet {
+            progress_bar.println(format!(
+                "Staging \"{}\" for deletion",
+                path.as_ref().as_os_str().to_str().unwrap()
+            ));
+        }
+        let staged_directory = staged_path.parent().unwrap();
+        if !staged_directory.is_dir() {
+            create_directory(progress_bar, &staged_directory, true).await?;
+        }
+        try_with!(
+            fs::rename(path, &staged_path).await,
+            "Failed to stage \"{}\"",
+            path.as_ref().as_os_str().to_str().unwrap()
+        );
+        return Ok(());
+    }
     if !quiet {
         progress_bar.println(format!(
             "Deleting \"{}\"",
@@ -183,6 +412,24 @@ pub async fn remove_directory<P: AsRef<Path>>(
     path: &P,
     quiet: bool,
 ) -> SimpleResult<()> {
+    if let Some(staged_path) = get_staged_path(path) {
+        if !quiet {
+            progress_bar.println(format!(
+                "Staging \"{}\" for deletion",
+                path.as_ref().as_os_str().to_str().unwrap()
+            ));
+        }
+        let staged_directory = staged_path.parent().unwrap();
+        if !staged_directory.is_dir() {
+            create_directory(progress_bar, &staged_directory, true).await?;
+        }
+        try_with!(
+            fs::rename(path, &staged_path).await,
+            "Failed to stage \"{}\"",
+            path.as_ref().as_os_str().to_str().unwrap()
+        );
+        return Ok(());
+    }
     if !quiet {
         progress_bar.println(format!(
             "Deleting \"{}\"",
@@ -197,6 +444,109 @@ pub async fn remove_directory<P: AsRef<Path>>(
     Ok(())
 }
 
+pub async fn remove_empty_directories(
+    connection: &mut SqliteConnection,
+    progress_bar: &ProgressBar,
+) -> SimpleResult<()> {
+    let rom_directory = get_rom_directory(connection).await;
+    let tmp_directory = get_tmp_directory(connection).await;
+    // children come first so that their parents can be emptied in the same pass
+    let directories: Vec<PathBuf> = WalkDir::new(rom_directory)
+        .min_depth(1)
+        .contents_first(true)
+        .into_iter()
+        .filter_entry(|entry| entry.path() != tmp_directory.as_path())
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_type().is_dir())
+        .map(|entry| entry.into_path())
+        .collect();
+    for directory in directories {
+        if directory
+            .read_dir()
+            .is_ok_and(|mut entries| entries.next().is_none())
+        {
+            remove_directory(progress_bar, &directory, false).await?;
+        }
+    }
+    Ok(())
+}
+
+pub async fn write_checksum_manifests(
+    connection: &mut SqliteConnection,
+    progress_bar: &ProgressBar,
+) -> SimpleResult<()> {
+    let rom_directory = get_rom_directory(connection).await;
+    let tmp_directory = get_tmp_directory(connection).await;
+    let mut romfiles_by_directory: HashMap<PathBuf, Vec<(String, i64)>> = HashMap::new();
+    for romfile in find_romfiles(connection).await {
+        let path = rom_directory.join(&romfile.path);
+        if !path.is_file() {
+            continue;
+        }
+        romfiles_by_directory
+            .entry(path.parent().unwrap().to_path_buf())
+            .or_default()
+            .push((
+                path.file_name().unwrap().to_str().unwrap().to_owned(),
+                romfile.updated_at,
+            ));
+    }
+
+    // drop manifests left in directories without ROM files
+    let manifest_paths: Vec<PathBuf> = WalkDir::new(rom_directory)
+        .into_iter()
+        .filter_entry(|entry| entry.path() != tmp_directory.as_path())
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_type().is_file() && entry.file_name() == CHECKSUMS_FILE_NAME)
+        .map(|entry| entry.into_path())
+        .collect();
+    for manifest_path in manifest_paths {
+        if !romfiles_by_directory.contains_key(manifest_path.parent().unwrap()) {
+            remove_file(progress_bar, &manifest_path, true).await?;
+        }
+    }
+
+    for (directory, mut romfiles) in romfiles_by_directory {
+        let manifest_path = directory.join(CHECKSUMS_FILE_NAME);
+        // ROM files untouched since the manifest was written keep their checksum
+        let written_at = manifest_path
+            .metadata()
+            .and_then(|metadata| metadata.modified())
+            .map(|modified| modified.duration_since(UNIX_EPOCH).unwrap().as_secs() as i64)
+            .unwrap_or(0);
+        let old_crcs: HashMap<String, String> = fs::read_to_string(&manifest_path)
+            .await
+            .unwrap_or_default()
+            .lines()
+            .filter(|line| !line.starts_with(';'))
+            .filter_map(|line| line.rsplit_once(' '))
+            .map(|(file_name, crc)| (file_name.to_owned(), crc.to_owned()))
+            .collect();
+
+        romfiles.sort();
+        let mut manifest = String::from("; Generated by oxyromon\n");
+        for (file_name, updated_at) in romfiles {
+            let crc = match old_crcs.get(&file_name) {
+                Some(crc) if updated_at < written_at => crc.to_owned(),
+                _ => CommonRomfile::from_path(&directory.join(&file_name))?
+                    .get_hash_and_size(connection, progress_bar, 1, 1, &HashAlgorithm::Crc)
+                    .await?
+                    .0
+                    .to_uppercase(),
+            };
+            manifest.push_str(&format!("{} {}\n", file_name, crc));
+        }
+
+        let mut file = create_file(progress_bar, &manifest_path, true).await?;
+        try_with!(
+            file.write_all(manifest.as_bytes()).await,
+            "Failed to write to \"{}\"",
+            manifest_path.as_os_str().to_str().unwrap()
+        );
+    }
+    Ok(())
+}
+
 pub async fn get_system_directory(
     connection: &mut SqliteConnection,
     system: &System,
@@ -265,6 +615,196 @@ pub fn get_executable_path(executables: &[&str]) -> SimpleResult<PathBuf> {
     }
 }
 
+pub fn get_romfile_format(path: &str) -> &str {
+    if path.ends_with(SEVENZIP_EXTENSION) {
+        "7Z"
+    } else if path.ends_with(CHD_EXTENSION) {
+        "CHD"
+    } else if path.ends_with(CSO_EXTENSION) {
+        "CSO"
+    } else if path.ends_with(NSZ_EXTENSION) {
+        "NSZ"
+    } else if path.ends_with(RVZ_EXTENSION) {
+        "RVZ"
+    } else if path.ends_with(ZIP_EXTENSION) {
+        "ZIP"
+    } else if path.ends_with(ZSO_EXTENSION) {
+        "ZSO"
+    } else {
+        "ORIGINAL"
+    }
+}
+
+pub fn set_tool_retries(tool_retries: usize) {
+    TOOL_RETRIES.store(tool_retries, AtomicOrdering::Relaxed);
+}
+
+fn is_retryable(program: &Path, result: &io::Result<Output>) -> bool {
+    match result {
+        // a missing or invalid executable won't fix itself
+        Err(error) => !matches!(
+            error.kind(),
+            io::ErrorKind::NotFound | io::ErrorKind::InvalidInput | io::ErrorKind::Unsupported
+        ),
+        Ok(output) if output.status.success() => false,
+        Ok(output) => match output.status.code() {
+            // processes killed by a signal didn't get to run to completion
+            None => true,
+            // other exit codes are deterministic, e.g. bad arguments or a corrupt file, unless the tool says otherwise
+            Some(code) => {
+                let tool = program
+                    .file_stem()
+                    .map(|file_stem| file_stem.to_string_lossy().to_lowercase())
+                    .unwrap_or_default();
+                let stderr = String::from_utf8_lossy(&output.stderr).to_lowercase();
+                TOOL_TRANSIENT_EXIT_CODES
+                    .iter()
+                    .any(|(transient_tool, codes)| *transient_tool == tool && codes.contains(&code))
+                    || TOOL_TRANSIENT_ERRORS
+                        .iter()
+                        .any(|transient_error| stderr.contains(transient_error))
+            }
+        },
+    }
+}
+
+pub fn set_outcome(outcome: Outcome) {
+    OUTCOME
+        .compare_exchange(
+            Outcome::Success as u8,
+            outcome as u8,
+            AtomicOrdering::Relaxed,
+            AtomicOrdering::Relaxed,
+        )
+        .ok();
+}
+
+pub fn get_outcome() -> Outcome {
+    Outcome::from_u8(OUTCOME.load(AtomicOrdering::Relaxed)).unwrap()
+}
+
+#[cfg(test)]
+pub fn reset_outcome() {
+    OUTCOME.store(Outcome::Success as u8, AtomicOrdering::Relaxed);
+}
+
+pub fn print_missing_tool(progress_bar: &ProgressBar, tool: &str) {
+    progress_bar.println(format!("Please install {}", tool));
+    set_outcome(Outcome::ToolMissing);
+}
+
+pub fn set_tool_niceness(tool_niceness: usize) {
+    TOOL_NICENESS.store(tool_niceness, AtomicOrdering::Relaxed);
+}
+
+pub fn get_tool_niceness() -> usize {
+    TOOL_NICENESS.load(AtomicOrdering::Relaxed)
+}
+
+// lowers the priority of the calling thread, best effort as it can't be raised back without privileges
+pub fn lower_thread_priority(niceness: usize) {
+    if niceness == 0 {
+        return;
+    }
+    cfg_if! {
+        if #[cfg(unix)] {
+            unsafe {
+                libc::nice(niceness as libc::c_int);
+            }
+        }
+    }
+}
+
+pub fn lower_tool_priority(command: &mut Command) {
+    let niceness = get_tool_niceness();
+    if niceness == 0 {
+        return;
+    }
+    cfg_if! {
+        if #[cfg(unix)] {
+            // only async-signal-safe calls are allowed between fork and exec
+            unsafe {
+                command.pre_exec(move || {
+                    libc::nice(niceness as libc::c_int);
+                    Ok(())
+                });
+            }
+        } else if #[cfg(windows)] {
+            const BELOW_NORMAL_PRIORITY_CLASS: u32 = 0x00004000;
+            command.creation_flags(BELOW_NORMAL_PRIORITY_CLASS);
+        }
+    }
+}
+
+pub fn set_max_tool_processes(max_tool_processes: usize) {
+    *TOOL_PROCESSES.write().unwrap() = if max_tool_processes > 0 {
+        Some(Arc::new(Semaphore::new(max_tool_processes)))
+    } else {
+        None
+    };
+}
+
+pub async fn acquire_tool_process() -> Option<OwnedSemaphorePermit> {
+    let semaphore = TOOL_PROCESSES.read().unwrap().clone();
+    match semaphore {
+        Some(semaphore) => semaphore.acquire_owned().await.ok(),
+        None => None,
+    }
+}
+
+pub async fn get_system_tool_paths(
+    connection: &mut SqliteConnection,
+    system_name: &str,
+) -> HashMap<&'static str, PathBuf> {
+    let mut tool_paths: HashMap<&'static str, PathBuf> = HashMap::new();
+    for (tool, key) in SYSTEM_TOOL_PATHS {
+        if let Some(path) = get_system_value(connection, key, system_name).await {
+            tool_paths.insert(*tool, PathBuf::from(path));
+        }
+    }
+    tool_paths
+}
+
+pub async fn with_tool_paths<F: Future>(
+    tool_paths: HashMap<&'static str, PathBuf>,
+    future: F,
+) -> F::Output {
+    TOOL_PATHS.scope(tool_paths, future).await
+}
+
+pub fn get_tool_path(tool: &str) -> PathBuf {
+    TOOL_PATHS
+        .try_with(|tool_paths| tool_paths.get(tool).cloned())
+        .ok()
+        .flatten()
+        .unwrap_or_else(|| PathBuf::from(tool))
+}
+
+pub trait OutputWithRetries {
+    async fn output_with_retries(&mut self) -> io::Result<Output>;
+}
+
+impl OutputWithRetries for Command {
+    async fn output_with_retries(&mut self) -> io::Result<Output> {
+        let tool_retries = TOOL_RETRIES.load(AtomicOrdering::Relaxed);
+        lower_tool_priority(self);
+        let mut attempt = 0;
+        loop {
+            let permit = acquire_tool_process().await;
+            let result = self.output().await;
+            drop(permit);
+            if attempt >= tool_retries
+                || !is_retryable(Path::new(self.as_std().get_program()), &result)
+            {
+                return result;
+            }
+            attempt += 1;
+            log::debug!("Retrying {:?} ({}/{})", self, attempt, tool_retries);
+            tokio::time::sleep(TOOL_RETRY_BACKOFF * 2u32.pow(attempt as u32 - 1)).await;
+        }
+    }
+}
+
 pub fn is_update(progress_bar: &ProgressBar, old_version: &str, new_version: &str) -> bool {
     match new_version.cmp(old_version) {
         Ordering::Less => {
@@ -319,6 +859,22 @@ pub async fn compute_system_completion(
     progress_bar.disable_steady_tick();
 }
 
+pub async fn is_arcade(connection: &mut SqliteConnection, system: &System) -> bool {
+    if get_list(connection, "ARCADE_SYSTEMS")
+        .await
+        .contains(&system.name)
+    {
+        true
+    } else if get_list(connection, "NON_ARCADE_SYSTEMS")
+        .await
+        .contains(&system.name)
+    {
+        false
+    } else {
+        system.arcade
+    }
+}
+
 pub async fn find_parent_chd_romfile_by_game(
     connection: &mut SqliteConnection,
     game: &Game,
@@ -351,6 +907,402 @@ pub async fn find_parent_chd_romfile_by_game(
     }
 }
 
+pub async fn read_header_title<P: AsRef<Path>>(path: &P) -> SimpleResult<Option<String>> {
+    let mut bytes: Vec<u8> = Vec::with_capacity(HEADER_TITLE_SIZE);
+    try_with!(
+        open_file(path)
+            .await?
+            .take(HEADER_TITLE_SIZE as u64)
+            .read_to_end(&mut bytes)
+            .await,
+        "Failed to read \"{}\"",
+        path.as_ref().as_os_str().to_str().unwrap()
+    );
+    Ok(get_header_title(&bytes))
+}
+
+pub fn get_header_title(bytes: &[u8]) -> Option<String> {
+    let title = if bytes.len() >= 0x150 && &bytes[0x104..0x108] == GB_LOGO {
+        // the last byte is the CGB flag on Game Boy Color cartridges
+        bytes[0x134..0x143].to_vec()
+    } else if bytes.len() >= 0xc0 && &bytes[0x04..0x08] == GBA_LOGO {
+        bytes[0xa0..0xac].to_vec()
+    } else if bytes.len() >= 0x180 && &bytes[0x100..0x104] == MEGA_DRIVE_MAGIC {
+        // overseas name
+        bytes[0x150..0x180].to_vec()
+    } else if bytes.len() >= 0x34 && &bytes[0x00..0x04] == N64_MAGIC_Z64 {
+        bytes[0x20..0x34].to_vec()
+    } else if bytes.len() >= 0x34 && &bytes[0x00..0x04] == N64_MAGIC_V64 {
+        bytes[0x20..0x34]
+            .chunks(2)
+            .flat_map(|chunk| chunk.iter().rev())
+            .copied()
+            .collect()
+    } else if bytes.len() >= 0x34 && &bytes[0x00..0x04] == N64_MAGIC_N64 {
+        bytes[0x20..0x34]
+            .chunks(4)
+            .flat_map(|chunk| chunk.iter().rev())
+            .copied()
+            .collect()
+    } else {
+        return None;
+    };
+    let title = title
+        .into_iter()
+        .take_while(|byte| *byte != 0)
+        .map(|byte| {
+            if byte.is_ascii_graphic() {
+                byte as char
+            } else {
+                ' '
+            }
+        })
+        .collect::<String>()
+        .split_whitespace()
+        .collect::<Vec<&str>>()
+        .join(" ");
+    if title.is_empty() {
+        None
+    } else {
+        Some(title)
+    }
+}
+
+pub struct WiiPartition {
+    pub index: usize,
+    pub partition_type: u32,
+    pub offset: u64,
+    pub valid: bool,
+}
+
+impl WiiPartition {
+    pub fn get_type_name(&self) -> String {
+        match self.partition_type {
+            0 => String::from("DATA"),
+            1 => String::from("UPDATE"),
+            2 => String::from("CHANNEL"),
+            partition_type => format!("{:08x}", partition_type),
+        }
+    }
+}
+
+async fn read_at(file: &mut File, offset: u64, buffer: &mut [u8]) -> bool {
+    file.seek(SeekFrom::Start(offset)).await.is_ok() && file.read_exact(buffer).await.is_ok()
+}
+
+fn read_u32(bytes: &[u8], offset: usize) -> u32 {
+    u32::from_be_bytes(bytes[offset..offset + 4].try_into().unwrap())
+}
+
+pub async fn read_wii_partitions<P: AsRef<Path>>(
+    path: &P,
+) -> SimpleResult<Option<Vec<WiiPartition>>> {
+    let mut file = open_file(path).await?;
+    let size = try_with!(
+        file.metadata().await,
+        "Failed to read \"{}\" metadata",
+        path.as_ref().as_os_str().to_str().unwrap()
+    )
+    .len();
+
+    let mut magic = [0u8; 4];
+    if !read_at(&mut file, WII_MAGIC_OFFSET, &mut magic).await || magic != WII_MAGIC {
+        return Ok(None);
+    }
+
+    // up to 4 groups of partitions, each with their own table
+    let mut partition_info = [0u8; 32];
+    if !read_at(&mut file, WII_PARTITION_INFO_OFFSET, &mut partition_info).await {
+        return Ok(Some(vec![]));
+    }
+    let mut partition_entries: Vec<(u64, u32)> = vec![];
+    for group in partition_info.chunks(8) {
+        let count = read_u32(group, 0) as usize;
+        let table_offset = (read_u32(group, 4) as u64) << 2;
+        if count == 0 || count > WII_MAX_PARTITION_COUNT {
+            continue;
+        }
+        let mut table = vec![0u8; count * 8];
+        if !read_at(&mut file, table_offset, &mut table).await {
+            continue;
+        }
+        for entry in table.chunks(8) {
+            partition_entries.push(((read_u32(entry, 0) as u64) << 2, read_u32(entry, 4)));
+        }
+    }
+
+    let mut partitions: Vec<WiiPartition> = vec![];
+    for (index, (offset, partition_type)) in partition_entries.into_iter().enumerate() {
+        let mut valid = false;
+        let mut header = [0u8; WII_PARTITION_HEADER_SIZE];
+        if read_at(&mut file, offset, &mut header).await && header[0..4] == WII_TICKET_MAGIC {
+            let tmd_size = read_u32(&header, 0x2a4) as u64;
+            let tmd_offset = (read_u32(&header, 0x2a8) as u64) << 2;
+            let h3_offset = (read_u32(&header, 0x2b4) as u64) << 2;
+            let data_offset = (read_u32(&header, 0x2b8) as u64) << 2;
+            let data_size = (read_u32(&header, 0x2bc) as u64) << 2;
+            // the first TMD content hash covers the H3 table, which in turn covers the whole partition data
+            let mut content_hash = [0u8; 20];
+            let mut h3_table = vec![0u8; WII_H3_TABLE_SIZE];
+            valid = tmd_size >= WII_TMD_CONTENT_HASH_OFFSET + 20
+                && offset + data_offset + data_size <= size
+                && read_at(
+                    &mut file,
+                    offset + tmd_offset + WII_TMD_CONTENT_HASH_OFFSET,
+                    &mut content_hash,
+                )
+                .await
+                && read_at(&mut file, offset + h3_offset, &mut h3_table).await
+                && Sha1::digest(&h3_table).as_slice() == content_hash;
+        }
+        partitions.push(WiiPartition {
+            index,
+            partition_type,
+            offset,
+            valid,
+        });
+    }
+
+    Ok(Some(partitions))
+}
+
+pub fn get_entropy_range(system_name: &str) -> Option<[f64; 2]> {
+    ENTROPY_RANGES
+        .iter()
+        .find(|(name, _)| system_name.starts_with(name))
+        .map(|(_, range)| *range)
+}
+
+pub fn compute_entropy(byte_counts: &[u64; 256]) -> f64 {
+    let total: u64 = byte_counts.iter().sum();
+    if total == 0 {
+        return 0.0;
+    }
+    byte_counts
+        .iter()
+        .filter(|&&count| count > 0)
+        .map(|&count| {
+            let probability = count as f64 / total as f64;
+            probability * (1.0 / probability).log2()
+        })
+        .sum()
+}
+
+pub async fn compute_file_entropy<P: AsRef<Path>>(path: &P) -> SimpleResult<f64> {
+    let mut file = open_file(path).await?;
+    let mut buffer = vec![0u8; ENTROPY_BUFFER_SIZE];
+    let mut byte_counts = [0u64; 256];
+    loop {
+        let count = try_with!(
+            file.read(&mut buffer).await,
+            "Failed to read \"{}\"",
+            path.as_ref().as_os_str().to_str().unwrap()
+        );
+        if count == 0 {
+            break;
+        }
+        buffer[..count]
+            .iter()
+            .for_each(|&byte| byte_counts[byte as usize] += 1);
+    }
+    Ok(compute_entropy(&byte_counts))
+}
+
+pub fn get_entropy_warning<P: AsRef<Path>>(
+    path: &P,
+    system_name: &str,
+    entropy: f64,
+) -> Option<String> {
+    let [min, max] = get_entropy_range(system_name)?;
+    if min <= entropy && entropy <= max {
+        return None;
+    }
+    Some(format!(
+        "\"{}\" has an entropy of {:.3} bits per byte, expected {}-{}, it is likely {}",
+        path.as_ref().file_name().unwrap().to_str().unwrap(),
+        entropy,
+        min,
+        max,
+        if entropy > max {
+            "encrypted or corrupt"
+        } else {
+            "blank or corrupt"
+        }
+    ))
+}
+
+pub fn get_sector_alignment_warning<P: AsRef<Path>>(path: &P) -> Option<String> {
+    let extension = path
+        .as_ref()
+        .extension()
+        .map(|extension| extension.to_str().unwrap().to_lowercase())
+        .unwrap_or_default();
+    if extension != ISO_EXTENSION && extension != BIN_EXTENSION {
+        return None;
+    }
+    let size = path.as_ref().metadata().ok()?.len();
+    if DISC_SECTOR_SIZES
+        .iter()
+        .any(|sector_size| size % sector_size == 0)
+    {
+        return None;
+    }
+    Some(format!(
+        "\"{}\" is not a multiple of {} bytes, it is likely truncated",
+        path.as_ref().file_name().unwrap().to_str().unwrap(),
+        DISC_SECTOR_SIZES
+            .iter()
+            .map(|sector_size| sector_size.to_string())
+            .collect::<Vec<String>>()
+            .join(" or ")
+    ))
+}
+
+// N64 dumps circulate in three byte orders but DATs only list the big-endian one (z64)
+pub async fn deswap_n64<P: AsRef<Path>, Q: AsRef<Path>>(
+    progress_bar: &ProgressBar,
+    path: &P,
+    destination_path: &Q,
+) -> SimpleResult<bool> {
+    let mut magic: Vec<u8> = Vec::with_capacity(N64_MAGIC_Z64.len());
+    try_with!(
+        open_file(path)
+            .await?
+            .take(N64_MAGIC_Z64.len() as u64)
+            .read_to_end(&mut magic)
+            .await,
+        "Failed to read \"{}\"",
+        path.as_ref().as_os_str().to_str().unwrap()
+    );
+    let word_size = if magic == N64_MAGIC_V64 {
+        2
+    } else if magic == N64_MAGIC_N64 {
+        4
+    } else {
+        return Ok(false);
+    };
+
+    let mut bytes: Vec<u8> = Vec::new();
+    try_with!(
+        open_file(path).await?.read_to_end(&mut bytes).await,
+        "Failed to read \"{}\"",
+        path.as_ref().as_os_str().to_str().unwrap()
+    );
+    if bytes.len() % word_size != 0 {
+        bail!(
+            "Size of \"{}\" is not a multiple of {}",
+            path.as_ref().as_os_str().to_str().unwrap(),
+            word_size
+        );
+    }
+    bytes
+        .chunks_exact_mut(word_size)
+        .for_each(|word| word.reverse());
+    if !bytes.starts_with(N64_MAGIC_Z64) {
+        bail!(
+            "Failed to deswap \"{}\"",
+            path.as_ref().as_os_str().to_str().unwrap()
+        );
+    }
+
+    progress_bar.println(format!(
+        "Deswapping \"{}\"",
+        path.as_ref().file_name().unwrap().to_str().unwrap()
+    ));
+    let mut file = create_file(progress_bar, destination_path, true).await?;
+    try_with!(
+        file.write_all(&bytes).await,
+        "Failed to write \"{}\"",
+        destination_path.as_ref().as_os_str().to_str().unwrap()
+    );
+    try_with!(
+        file.flush().await,
+        "Failed to write \"{}\"",
+        destination_path.as_ref().as_os_str().to_str().unwrap()
+    );
+    Ok(true)
+}
+
+pub fn is_fat_filesystem<P: AsRef<Path>>(path: &P) -> bool {
+    // the longest mount point containing the path is the one it lives on
+    match std::fs::read_to_string("/proc/mounts") {
+        Ok(mounts) => mounts
+            .lines()
+            .filter_map(|line| {
+                let mut fields = line.split_whitespace();
+                let mount_point = fields.nth(1)?.replace("\\040", " ");
+                let filesystem = fields.next()?;
+                if path.as_ref().starts_with(&mount_point) {
+                    Some((mount_point, filesystem.to_owned()))
+                } else {
+                    None
+                }
+            })
+            .max_by_key(|(mount_point, _)| mount_point.len())
+            .map(|(_, filesystem)| FAT_FILESYSTEMS.contains(&filesystem.as_str()))
+            .unwrap_or(false),
+        Err(_) => false,
+    }
+}
+
+pub fn get_fat_safe_name(name: &str) -> String {
+    let name: String = name
+        .chars()
+        .map(|c| {
+            if c.is_control() || FAT_INVALID_CHARACTERS.contains(&c) {
+                '_'
+            } else {
+                c
+            }
+        })
+        .collect();
+    // trailing dots and spaces are silently dropped by FAT drivers
+    name.trim_end_matches(['.', ' ']).to_owned()
+}
+
+pub fn get_cue_file_names(cue: &str) -> Vec<&str> {
+    cue.lines()
+        .filter_map(|line| line.trim_start().strip_prefix("FILE "))
+        .filter_map(|file| match file.strip_prefix('"') {
+            Some(file) => file.split_once('"').map(|(name, _)| name),
+            None => file.rsplit_once(' ').map(|(name, _)| name),
+        })
+        .collect()
+}
+
+pub fn get_m3u_file_names(m3u: &str) -> Vec<&str> {
+    m3u.lines()
+        .map(|line| line.trim())
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .collect()
+}
+
+pub fn get_goodtools_status(name: &str) -> Option<GoodToolsStatus> {
+    // alternate dumps ([a]) say nothing about the dump quality
+    GOODTOOLS_FLAG_REGEX
+        .captures_iter(name)
+        .filter_map(|captures| match captures[1].chars().next().unwrap() {
+            '!' => Some(GoodToolsStatus::Good),
+            'b' | 'x' => Some(GoodToolsStatus::Bad),
+            'o' => Some(GoodToolsStatus::Overdump),
+            'f' | 'h' | 'p' | 't' | 'T' => Some(GoodToolsStatus::Hack),
+            _ => None,
+        })
+        .min()
+}
+
+#[cfg(test)]
+mod test_entropy;
+#[cfg(test)]
+mod test_goodtools_status;
+#[cfg(test)]
+mod test_header_title_gb;
+#[cfg(test)]
+mod test_header_title_n64_byteswapped;
+#[cfg(all(test, unix))]
+mod test_max_tool_processes;
+#[cfg(test)]
+mod test_sector_alignment_misaligned_iso;
 #[cfg(test)]
 mod test_system_directory_no_group_subsystems;
 
@@ -361,3 +1313,13 @@ mod test_system_directory_group_subsystems;
 mod test_system_directory_custom_name;
 #[cfg(test)]
 mod test_system_directory_group_non_redump;
+#[cfg(all(test, unix))]
+mod test_tool_niceness;
+#[cfg(all(test, unix))]
+mod test_tool_retries_deterministic;
+#[cfg(all(test, unix))]
+mod test_tool_retries_flaky;
+#[cfg(all(test, unix))]
+mod test_tool_retries_flaky_exit_code;
+#[cfg(test)]
+mod test_wii_partitions;
@@ -0,0 +1,63 @@
+use super::*;
+use tempfile::TempDir;
+
+#[tokio::test]
+async fn test() {
+    // given
+    let test_directory = Path::new("tests");
+    let tmp_directory = TempDir::new_in(test_directory).unwrap();
+
+    // xorshift output is as random as an encrypted dump
+    let mut state: u64 = 0x9e3779b97f4a7c15;
+    let random_buffer: Vec<u8> = (0..1024 * 1024)
+        .map(|_| {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state as u8
+        })
+        .collect();
+    let random_path = tmp_directory.path().join("Random.gb");
+    fs::write(&random_path, &random_buffer).await.unwrap();
+
+    let blank_path = tmp_directory.path().join("Blank.gb");
+    fs::write(&blank_path, vec![0xffu8; 1024 * 1024])
+        .await
+        .unwrap();
+
+    let rom_path = test_directory.join("Test Game (USA, Europe).rom");
+
+    // when
+    let random_entropy = compute_file_entropy(&random_path).await.unwrap();
+    let blank_entropy = compute_file_entropy(&blank_path).await.unwrap();
+    let rom_entropy = compute_file_entropy(&rom_path).await.unwrap();
+
+    // then
+    assert!(random_entropy > 7.99);
+    assert_eq!(blank_entropy, 0.0);
+
+    assert_eq!(
+        get_entropy_warning(&random_path, "Nintendo - Game Boy", random_entropy).as_deref(),
+        Some(
+            format!(
+                "\"Random.gb\" has an entropy of {:.3} bits per byte, expected 0.5-7.95, it is likely encrypted or corrupt",
+                random_entropy
+            )
+            .as_str()
+        )
+    );
+    assert_eq!(
+        get_entropy_warning(&blank_path, "Nintendo - Game Boy", blank_entropy).as_deref(),
+        Some("\"Blank.gb\" has an entropy of 0.000 bits per byte, expected 0.5-7.95, it is likely blank or corrupt")
+    );
+    assert_eq!(
+        get_entropy_warning(&rom_path, "Nintendo - Game Boy", rom_entropy),
+        None
+    );
+
+    // systems without known expectations are never flagged
+    assert_eq!(
+        get_entropy_warning(&random_path, "Sony - PlayStation", random_entropy),
+        None
+    );
+}
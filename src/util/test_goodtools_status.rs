@@ -0,0 +1,37 @@
+use super::*;
+
+#[test]
+fn test() {
+    // given
+    let names = [
+        "Test Game (U) [!].nes",
+        "Test Game (U) [h1C].nes",
+        "Test Game (U) [b2].nes",
+        "Test Game (U) [o1].nes",
+        "Test Game (U) [T+Fre1.0].nes",
+        "Test Game (U) [a1][b1].nes",
+        "Test Game (U) [a1].nes",
+        "Test Game (USA).nes",
+    ];
+
+    // when
+    let statuses: Vec<Option<GoodToolsStatus>> = names
+        .iter()
+        .map(|name| get_goodtools_status(name))
+        .collect();
+
+    // then
+    assert_eq!(
+        statuses,
+        vec![
+            Some(GoodToolsStatus::Good),
+            Some(GoodToolsStatus::Hack),
+            Some(GoodToolsStatus::Bad),
+            Some(GoodToolsStatus::Overdump),
+            Some(GoodToolsStatus::Hack),
+            Some(GoodToolsStatus::Bad),
+            None,
+            None,
+        ]
+    );
+}
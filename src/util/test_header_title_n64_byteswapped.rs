@@ -0,0 +1,14 @@
+use super::*;
+
+#[tokio::test]
+async fn test() {
+    // given
+    let test_directory = Path::new("tests");
+    let romfile_path = test_directory.join("Header Title N64 (USA).v64");
+
+    // when
+    let header_title = read_header_title(&romfile_path).await.unwrap();
+
+    // then
+    assert_eq!(header_title.as_deref(), Some("HEADER TITLE N64"));
+}
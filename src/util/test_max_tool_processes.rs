@@ -0,0 +1,64 @@
+use super::*;
+use futures::future::join_all;
+use std::os::unix::fs::PermissionsExt;
+use tempfile::TempDir;
+
+#[tokio::test]
+async fn test() {
+    // given
+    let _guard = MUTEX.lock().await;
+
+    let test_directory = Path::new("tests");
+    let tmp_directory = TempDir::new_in(test_directory).unwrap();
+
+    // the fake tool logs when it starts and ends, giving away how many instances overlapped
+    let tool_path = tmp_directory.path().join("counting");
+    let log_path = tmp_directory.path().join("log");
+    fs::write(
+        &tool_path,
+        "#!/bin/sh\necho start >> \"$1\"\nsleep 0.2\necho end >> \"$1\"\n",
+    )
+    .await
+    .unwrap();
+    fs::set_permissions(&tool_path, std::fs::Permissions::from_mode(0o755))
+        .await
+        .unwrap();
+
+    set_max_tool_processes(2);
+
+    // when
+    let mut commands: Vec<Command> = (0..6)
+        .map(|_| {
+            let mut command = Command::new(&tool_path);
+            command.arg(&log_path);
+            command
+        })
+        .collect();
+    let outputs = join_all(
+        commands
+            .iter_mut()
+            .map(|command| command.output_with_retries()),
+    )
+    .await;
+
+    set_max_tool_processes(0);
+
+    // then
+    assert!(outputs
+        .iter()
+        .all(|output| output.as_ref().is_ok_and(|output| output.status.success())));
+
+    let log = fs::read_to_string(&log_path).await.unwrap();
+    let mut running = 0;
+    let mut max_running = 0;
+    for line in log.lines() {
+        match line {
+            "start" => running += 1,
+            "end" => running -= 1,
+            _ => panic!("Unexpected line \"{}\"", line),
+        }
+        max_running = max_running.max(running);
+    }
+    assert_eq!(log.lines().count(), 12);
+    assert_eq!(max_running, 2);
+}
@@ -0,0 +1,40 @@
+use super::*;
+use tempfile::TempDir;
+
+#[tokio::test]
+async fn test() {
+    // given
+    let test_directory = Path::new("tests");
+    let tmp_directory = TempDir::new_in(test_directory).unwrap();
+
+    let aligned_path = tmp_directory.path().join("Aligned.iso");
+    fs::copy(
+        test_directory.join("Test Game (USA, Europe).iso"),
+        &aligned_path,
+    )
+    .await
+    .unwrap();
+    let misaligned_path = tmp_directory.path().join("Misaligned.iso");
+    fs::copy(
+        test_directory.join("Test Game (USA, Europe).iso"),
+        &misaligned_path,
+    )
+    .await
+    .unwrap();
+    let file = std::fs::OpenOptions::new()
+        .write(true)
+        .open(&misaligned_path)
+        .unwrap();
+    file.set_len(file.metadata().unwrap().len() - 1).unwrap();
+
+    // when
+    let aligned_warning = get_sector_alignment_warning(&aligned_path);
+    let misaligned_warning = get_sector_alignment_warning(&misaligned_path);
+
+    // then
+    assert_eq!(aligned_warning, None);
+    assert_eq!(
+        misaligned_warning.as_deref(),
+        Some("\"Misaligned.iso\" is not a multiple of 2048 or 2352 bytes, it is likely truncated")
+    );
+}
@@ -0,0 +1,30 @@
+use super::*;
+
+async fn get_niceness() -> usize {
+    // without arguments, nice prints the niceness it runs with
+    let output = Command::new("nice").output_with_retries().await.unwrap();
+    String::from_utf8(output.stdout)
+        .unwrap()
+        .trim()
+        .parse()
+        .unwrap()
+}
+
+#[tokio::test]
+async fn test() {
+    // given
+    let _guard = MUTEX.lock().await;
+
+    let niceness = get_niceness().await;
+
+    set_tool_niceness(5);
+
+    // when
+    let tool_niceness = get_niceness().await;
+
+    set_tool_niceness(0);
+
+    // then
+    assert_eq!(tool_niceness, (niceness + 5).min(19));
+    assert_eq!(get_niceness().await, niceness);
+}
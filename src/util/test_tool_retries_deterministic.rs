@@ -0,0 +1,37 @@
+use super::*;
+use std::os::unix::fs::PermissionsExt;
+use tempfile::TempDir;
+
+#[tokio::test]
+async fn test() {
+    // given
+    let _guard = MUTEX.lock().await;
+
+    let test_directory = Path::new("tests");
+    let tmp_directory = TempDir::new_in(test_directory).unwrap();
+
+    // the fake tool always exits with an error code, like it would with bad arguments
+    let tool_path = tmp_directory.path().join("broken");
+    let marker_path = tmp_directory.path().join("marker");
+    fs::write(&tool_path, "#!/bin/sh\necho run >> \"$1\"\nexit 1\n")
+        .await
+        .unwrap();
+    fs::set_permissions(&tool_path, std::fs::Permissions::from_mode(0o755))
+        .await
+        .unwrap();
+
+    set_tool_retries(2);
+
+    // when
+    let output = Command::new(&tool_path)
+        .arg(&marker_path)
+        .output_with_retries()
+        .await
+        .unwrap();
+
+    set_tool_retries(0);
+
+    // then
+    assert!(!output.status.success());
+    assert_eq!(fs::read_to_string(&marker_path).await.unwrap(), "run\n");
+}
@@ -0,0 +1,40 @@
+use super::*;
+use std::os::unix::fs::PermissionsExt;
+use tempfile::TempDir;
+
+#[tokio::test]
+async fn test() {
+    // given
+    let _guard = MUTEX.lock().await;
+
+    let test_directory = Path::new("tests");
+    let tmp_directory = TempDir::new_in(test_directory).unwrap();
+
+    // the fake tool gets killed on its first run and succeeds afterwards
+    let tool_path = tmp_directory.path().join("flaky");
+    let marker_path = tmp_directory.path().join("marker");
+    fs::write(
+        &tool_path,
+        "#!/bin/sh\nif [ ! -f \"$1\" ]; then\n    touch \"$1\"\n    kill -9 $$\nfi\necho ok\n",
+    )
+    .await
+    .unwrap();
+    fs::set_permissions(&tool_path, std::fs::Permissions::from_mode(0o755))
+        .await
+        .unwrap();
+
+    set_tool_retries(1);
+
+    // when
+    let output = Command::new(&tool_path)
+        .arg(&marker_path)
+        .output_with_retries()
+        .await
+        .unwrap();
+
+    set_tool_retries(0);
+
+    // then
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "ok\n");
+}
@@ -0,0 +1,76 @@
+use super::*;
+use tempfile::TempDir;
+
+fn write_partition(iso: &mut [u8], offset: usize, h3_byte: u8, corrupt: bool) {
+    let tmd_offset = 0x2c0;
+    let h3_offset = 0x8000;
+    let data_offset = 0x20000;
+    let data_size = 0x8000;
+
+    iso[offset..offset + 4].copy_from_slice(&WII_TICKET_MAGIC);
+    iso[offset + 0x2a4..offset + 0x2a8].copy_from_slice(&0x208u32.to_be_bytes());
+    iso[offset + 0x2a8..offset + 0x2ac].copy_from_slice(&((tmd_offset >> 2) as u32).to_be_bytes());
+    iso[offset + 0x2b4..offset + 0x2b8].copy_from_slice(&((h3_offset >> 2) as u32).to_be_bytes());
+    iso[offset + 0x2b8..offset + 0x2bc].copy_from_slice(&((data_offset >> 2) as u32).to_be_bytes());
+    iso[offset + 0x2bc..offset + 0x2c0].copy_from_slice(&((data_size >> 2) as u32).to_be_bytes());
+
+    let h3_table = vec![h3_byte; WII_H3_TABLE_SIZE];
+    iso[offset + h3_offset..offset + h3_offset + WII_H3_TABLE_SIZE].copy_from_slice(&h3_table);
+
+    let mut content_hash = Sha1::digest(&h3_table).to_vec();
+    if corrupt {
+        content_hash[0] ^= 0xff;
+    }
+    let content_hash_offset = offset + tmd_offset + WII_TMD_CONTENT_HASH_OFFSET as usize;
+    iso[content_hash_offset..content_hash_offset + 20].copy_from_slice(&content_hash);
+}
+
+#[tokio::test]
+async fn test() {
+    // given
+    let test_directory = Path::new("tests");
+    let tmp_directory = TempDir::new_in(test_directory).unwrap();
+    let romfile_path = tmp_directory.path().join("Test Game (USA) (Wii).iso");
+
+    // a minimal Wii disc with a valid update partition and a corrupt data partition
+    let mut iso = vec![0u8; 0xa0000];
+    iso[WII_MAGIC_OFFSET as usize..WII_MAGIC_OFFSET as usize + 4].copy_from_slice(&WII_MAGIC);
+    let partition_info_offset = WII_PARTITION_INFO_OFFSET as usize;
+    iso[partition_info_offset..partition_info_offset + 4].copy_from_slice(&2u32.to_be_bytes());
+    iso[partition_info_offset + 4..partition_info_offset + 8]
+        .copy_from_slice(&(((partition_info_offset + 0x20) >> 2) as u32).to_be_bytes());
+    for (index, (offset, partition_type)) in [(0x50000usize, 1u32), (0x78000usize, 0u32)]
+        .into_iter()
+        .enumerate()
+    {
+        let entry_offset = partition_info_offset + 0x20 + index * 8;
+        iso[entry_offset..entry_offset + 4].copy_from_slice(&((offset >> 2) as u32).to_be_bytes());
+        iso[entry_offset + 4..entry_offset + 8].copy_from_slice(&partition_type.to_be_bytes());
+    }
+    write_partition(&mut iso, 0x50000, 0x11, false);
+    write_partition(&mut iso, 0x78000, 0x22, true);
+    fs::write(&romfile_path, &iso).await.unwrap();
+
+    // when
+    let partitions = read_wii_partitions(&romfile_path).await.unwrap().unwrap();
+
+    // then
+    assert_eq!(partitions.len(), 2);
+
+    let update_partition = partitions.first().unwrap();
+    assert_eq!(update_partition.get_type_name(), "UPDATE");
+    assert_eq!(update_partition.offset, 0x50000);
+    assert!(update_partition.valid);
+
+    let data_partition = partitions.get(1).unwrap();
+    assert_eq!(data_partition.get_type_name(), "DATA");
+    assert_eq!(data_partition.offset, 0x78000);
+    assert!(!data_partition.valid);
+
+    assert!(
+        read_wii_partitions(&test_directory.join("Test Game (USA).iso"))
+            .await
+            .unwrap()
+            .is_none()
+    );
+}
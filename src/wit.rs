@@ -1,6 +1,7 @@
 use super::common::*;
 use super::config::*;
 use super::progress::*;
+use super::util::*;
 use super::SimpleResult;
 use indicatif::ProgressBar;
 use lazy_static::lazy_static;
@@ -49,7 +50,7 @@ impl ToWbfs for IsoRomfile {
             .arg(&self.romfile.path)
             .arg("--dest")
             .arg(&path)
-            .output()
+            .output_with_retries()
             .await
             .expect("Failed to create wbfs");
 
@@ -68,7 +69,10 @@ impl ToWbfs for IsoRomfile {
 
 pub async fn get_version() -> SimpleResult<String> {
     let output = try_with!(
-        Command::new(WIT).arg("--version").output().await,
+        Command::new(WIT)
+            .arg("--version")
+            .output_with_retries()
+            .await,
         "Failed to spawn wit"
     );
 
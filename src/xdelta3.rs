@@ -1,6 +1,7 @@
 use super::common::*;
 use super::mimetype::*;
 use super::progress::*;
+use super::util::*;
 use super::SimpleResult;
 use lazy_static::lazy_static;
 use regex::Regex;
@@ -46,7 +47,7 @@ impl PatchFile for XdeltaRomfile {
             .arg(&romfile.path)
             .arg(&path)
             .arg(&self.romfile.path)
-            .output()
+            .output_with_retries()
             .await
             .unwrap_or_else(|_| {
                 panic!(
@@ -89,7 +90,7 @@ impl AsXdelta for CommonRomfile {
 
 pub async fn get_version() -> SimpleResult<String> {
     let output = try_with!(
-        Command::new(XDELTA3).arg("-V").output().await,
+        Command::new(XDELTA3).arg("-V").output_with_retries().await,
         "Failed to spawn xdelta3"
     );
 
@@ -0,0 +1,8 @@
+CATALOG 0000000000000
+FILE "Test Game: Part 1 (USA, Europe) (Track 01).bin" BINARY
+  TRACK 01 MODE1/2352
+    INDEX 00 00:00:00
+    INDEX 01 00:03:06
+FILE "Test Game: Part 1 (USA, Europe) (Track 02).bin" BINARY
+  TRACK 02 AUDIO
+    INDEX 01 00:00:00